@@ -3,6 +3,7 @@
 use std::{
     net::SocketAddr,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 /// Represents a single typed configuration item, specified as a
@@ -141,6 +142,136 @@ impl ConfigItem<SocketAddr> for GrpcBindAddr {
     }
 }
 
+pub(crate) struct MaxHttpRequestSize {}
+
+impl ConfigItem<usize> for MaxHttpRequestSize {
+    fn name(&self) -> &'static str {
+        "INFLUXDB_IOX_MAX_HTTP_REQUEST_SIZE"
+    }
+    fn short_description(&self) -> String {
+        "Maximum size of HTTP request bodies, in bytes".into()
+    }
+    fn default(&self) -> Option<String> {
+        Some("10485760".into())
+    }
+    fn long_description(&self) -> Option<String> {
+        Some(
+            "The maximum size of the body of an HTTP API request, such as a write. \
+             Compressed bodies are limited to this size both before and after they are \
+             decompressed."
+                .into(),
+        )
+    }
+    fn parse(&self, val: Option<&str>) -> std::result::Result<usize, String> {
+        let size: &str = val.ok_or_else(|| String::from("Empty value is not valid"))?;
+
+        size.parse::<usize>()
+            .map_err(|e| format!("Error parsing {} as a number of bytes: {}", size, e))
+    }
+    fn unparse(&self, val: &usize) -> String {
+        format!("{}", val)
+    }
+}
+
+pub(crate) struct HttpAuthToken {}
+
+impl ConfigItem<Option<String>> for HttpAuthToken {
+    fn name(&self) -> &'static str {
+        "INFLUXDB_IOX_HTTP_AUTH_TOKEN"
+    }
+    fn short_description(&self) -> String {
+        "The token HTTP API clients must provide, if any".into()
+    }
+    fn long_description(&self) -> Option<String> {
+        Some(
+            "If set, requests to the HTTP API must include an \
+             `Authorization: Token <value>` header with this value, as InfluxDB 2.x \
+             clients do, and are rejected otherwise. /ping and /health/ready don't \
+             require the token. If not set, the HTTP API is open to any client."
+                .into(),
+        )
+    }
+    fn parse(&self, val: Option<&str>) -> std::result::Result<Option<String>, String> {
+        Ok(val.map(|s| s.to_string()))
+    }
+    fn unparse(&self, val: &Option<String>) -> String {
+        // Don't display the token itself
+        if val.is_some() {
+            "<redacted>".into()
+        } else {
+            "".into()
+        }
+    }
+}
+
+pub(crate) struct HttpCorsAllowedOrigins {}
+
+impl ConfigItem<Vec<String>> for HttpCorsAllowedOrigins {
+    fn name(&self) -> &'static str {
+        "INFLUXDB_IOX_HTTP_CORS_ALLOWED_ORIGINS"
+    }
+    fn short_description(&self) -> String {
+        "Comma separated origins from which browsers may call the HTTP API".into()
+    }
+    fn example(&self) -> Option<String> {
+        Some("https://dashboard.example.com".into())
+    }
+    fn long_description(&self) -> Option<String> {
+        Some(
+            "Browser based applications served from these origins may call the HTTP \
+             API directly, as CORS headers allowing them are included in responses and \
+             OPTIONS preflight requests are answered. `*` allows any origin. If not set, \
+             CORS is disabled."
+                .into(),
+        )
+    }
+    fn parse(&self, val: Option<&str>) -> std::result::Result<Vec<String>, String> {
+        Ok(val
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+    fn unparse(&self, val: &Vec<String>) -> String {
+        val.join(",")
+    }
+}
+
+pub(crate) struct QueryTimeout {}
+
+impl ConfigItem<Duration> for QueryTimeout {
+    fn name(&self) -> &'static str {
+        "INFLUXDB_IOX_QUERY_TIMEOUT"
+    }
+    fn short_description(&self) -> String {
+        "Time HTTP API queries may take, in seconds".into()
+    }
+    fn default(&self) -> Option<String> {
+        Some("30".into())
+    }
+    fn long_description(&self) -> Option<String> {
+        Some(
+            "Queries made through the HTTP API that take longer than this, including \
+             formatting their results, are abandoned and fail with a 504 Gateway \
+             Timeout, so that a pathological query can't tie up a connection \
+             indefinitely."
+                .into(),
+        )
+    }
+    fn parse(&self, val: Option<&str>) -> std::result::Result<Duration, String> {
+        let secs: &str = val.ok_or_else(|| String::from("Empty value is not valid"))?;
+
+        secs.parse::<u64>()
+            .map(Duration::from_secs)
+            .map_err(|e| format!("Error parsing {} as a number of seconds: {}", secs, e))
+    }
+    fn unparse(&self, val: &Duration) -> String {
+        format!("{}", val.as_secs())
+    }
+}
+
 pub(crate) struct DBDir {}
 
 impl ConfigItem<PathBuf> for DBDir {
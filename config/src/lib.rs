@@ -29,6 +29,7 @@ use std::{
     fmt,
     net::SocketAddr,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use snafu::{ResultExt, Snafu};
@@ -99,6 +100,18 @@ pub struct Config {
     /// port to listen for gRPC API
     pub grpc_bind_address: SocketAddr,
 
+    /// Maximum size of HTTP request bodies, in bytes
+    pub max_http_request_size: usize,
+
+    /// Token HTTP API clients must provide, if any
+    pub http_auth_token: Option<String>,
+
+    /// Origins from which browsers may call the HTTP API
+    pub http_cors_allowed_origins: Vec<String>,
+
+    /// Time HTTP API queries may take
+    pub query_timeout: Duration,
+
     /// Directory to store local database files
     pub database_directory: PathBuf,
 
@@ -120,6 +133,13 @@ impl Config {
             writer_id: Self::parse_config(&name_values, &WriterID {})?,
             http_bind_address: Self::parse_config(&name_values, &HttpBindAddr {})?,
             grpc_bind_address: Self::parse_config(&name_values, &GrpcBindAddr {})?,
+            max_http_request_size: Self::parse_config(&name_values, &MaxHttpRequestSize {})?,
+            http_auth_token: Self::parse_config(&name_values, &HttpAuthToken {})?,
+            http_cors_allowed_origins: Self::parse_config(
+                &name_values,
+                &HttpCorsAllowedOrigins {},
+            )?,
+            query_timeout: Self::parse_config(&name_values, &QueryTimeout {})?,
             database_directory: Self::parse_config(&name_values, &DBDir {})?,
             gcp_bucket: Self::parse_config(&name_values, &GCPBucket {})?,
         })
@@ -135,6 +155,10 @@ impl Config {
         WriterID {}.display(f, &self.writer_id, verbose)?;
         HttpBindAddr {}.display(f, &self.http_bind_address, verbose)?;
         GrpcBindAddr {}.display(f, &self.grpc_bind_address, verbose)?;
+        MaxHttpRequestSize {}.display(f, &self.max_http_request_size, verbose)?;
+        HttpAuthToken {}.display(f, &self.http_auth_token, verbose)?;
+        HttpCorsAllowedOrigins {}.display(f, &self.http_cors_allowed_origins, verbose)?;
+        QueryTimeout {}.display(f, &self.query_timeout, verbose)?;
         DBDir {}.display(f, &self.database_directory, verbose)?;
         GCPBucket {}.display(f, &self.gcp_bucket, verbose)?;
         Ok(())
@@ -288,6 +312,13 @@ mod test {
             ),
             ("INFLUXDB_IOX_DB_DIR".into(), "/foo/bar".into()),
             ("INFLUXDB_IOX_ID".into(), "42".into()),
+            ("INFLUXDB_IOX_MAX_HTTP_REQUEST_SIZE".into(), "1024".into()),
+            ("INFLUXDB_IOX_HTTP_AUTH_TOKEN".into(), "my_token".into()),
+            (
+                "INFLUXDB_IOX_HTTP_CORS_ALLOWED_ORIGINS".into(),
+                "https://a.example.com, https://b.example.com".into(),
+            ),
+            ("INFLUXDB_IOX_QUERY_TIMEOUT".into(), "5".into()),
             ("INFLUXDB_IOX_GCP_BUCKET".into(), "my_bucket".into()),
             ("RUST_LOG".into(), "rust_log_level".into()),
             (
@@ -303,6 +334,13 @@ mod test {
         assert_eq!(config.writer_id, Some(42));
         assert_eq!(config.http_bind_address.to_string(), "127.0.0.1:1010");
         assert_eq!(config.grpc_bind_address.to_string(), "127.0.0.2:2020");
+        assert_eq!(config.max_http_request_size, 1024);
+        assert_eq!(config.http_auth_token, Some("my_token".into()));
+        assert_eq!(
+            config.http_cors_allowed_origins,
+            vec!["https://a.example.com", "https://b.example.com"]
+        );
+        assert_eq!(config.query_timeout, Duration::from_secs(5));
         assert_eq!(config.gcp_bucket, Some("my_bucket".into()));
     }
 
@@ -172,12 +172,68 @@ pub fn lines_to_replicated_write(
         lines,
     );
 
+    entries_to_replicated_write(writer, sequence, &entry_bytes)
+}
+
+/// Creates a replicated write that deletes the rows from `start` (inclusive)
+/// until `end` (exclusive), of `table_name` if specified, that have every
+/// `(tag, value)` pair in `tags`.
+pub fn delete_to_replicated_write(
+    writer: u32,
+    sequence: u64,
+    table_name: Option<&str>,
+    start: i64,
+    end: i64,
+    tags: &[(String, String)],
+) -> ReplicatedWrite {
+    let mut fbb = flatbuffers::FlatBufferBuilder::new_with_capacity(1024);
+
+    let table_name = table_name.map(|name| fbb.create_string(name));
+    let tags = tags
+        .iter()
+        .map(|(tag, value)| add_tag_value(&mut fbb, tag, value))
+        .collect::<Vec<_>>();
+    let tags = fbb.create_vector(&tags);
+
+    let delete = wb::WriteBufferDelete::create(
+        &mut fbb,
+        &wb::WriteBufferDeleteArgs {
+            table_name,
+            start_time: start,
+            end_time: end,
+            tags: Some(tags),
+            ..Default::default()
+        },
+    );
+    let entry = wb::WriteBufferEntry::create(
+        &mut fbb,
+        &wb::WriteBufferEntryArgs {
+            delete: Some(delete),
+            ..Default::default()
+        },
+    );
+    let entries = fbb.create_vector(&[entry]);
+    let batch = wb::WriteBufferBatch::create(
+        &mut fbb,
+        &wb::WriteBufferBatchArgs {
+            entries: Some(entries),
+        },
+    );
+
+    fbb.finish(batch, None);
+
+    let (mut data, idx) = fbb.collapse();
+    entries_to_replicated_write(writer, sequence, &data.split_off(idx))
+}
+
+// Wraps the bytes of a WriteBufferBatch in a replicated write
+fn entries_to_replicated_write(writer: u32, sequence: u64, entry_bytes: &[u8]) -> ReplicatedWrite {
     let mut hasher = Hasher::new();
-    hasher.update(&entry_bytes);
+    hasher.update(entry_bytes);
     let checksum = hasher.finalize();
 
     let mut fbb = flatbuffers::FlatBufferBuilder::new_with_capacity(1024);
-    let payload = fbb.create_vector_direct(&entry_bytes);
+    let payload = fbb.create_vector_direct(entry_bytes);
 
     let write = wb::ReplicatedWrite::create(
         &mut fbb,
@@ -4,7 +4,7 @@ use std::{borrow::Cow, ops::RangeInclusive};
 /// Length constraints for a database name.
 ///
 /// A `RangeInclusive` is a closed interval, covering [1, 64]
-const LENGTH_CONSTRAINT: RangeInclusive<usize> = 1..=64;
+pub const LENGTH_CONSTRAINT: RangeInclusive<usize> = 1..=64;
 
 /// Database name validation errors.
 #[derive(Debug, Snafu)]
@@ -1,3 +1,5 @@
+use std::{convert::TryFrom, time::Duration};
+
 use influxdb_line_protocol::ParsedLine;
 
 use chrono::{DateTime, TimeZone, Utc};
@@ -85,6 +87,15 @@ pub struct DatabaseRules {
     /// When set this will buffer WAL writes in memory based on the
     /// configuration.
     pub wal_buffer_config: Option<WalBufferConfig>,
+
+    /// Points with timestamps more than `max_future_skew` ahead of the
+    /// server's clock are assumed to come from clients with skewed clocks.
+    /// They're dropped, or written with their timestamp clamped to the
+    /// latest allowed time if `clamp_future_writes` is set. When `None`,
+    /// points may have any timestamp.
+    pub max_future_skew: Option<Duration>,
+    #[serde(default)]
+    pub clamp_future_writes: bool,
 }
 
 impl DatabaseRules {
@@ -95,6 +106,15 @@ impl DatabaseRules {
     ) -> Result<String> {
         self.partition_template.partition_key(line, default_time)
     }
+
+    /// The latest timestamp, in nanoseconds, that a point written at `now`
+    /// may have, if `max_future_skew` is set.
+    pub fn max_future_time(&self, now: i64) -> Option<i64> {
+        self.max_future_skew.map(|skew| {
+            let skew = i64::try_from(skew.as_nanos()).unwrap_or(i64::MAX);
+            now.saturating_add(skew)
+        })
+    }
 }
 
 /// WalBufferConfig defines the configuration for buffering data from the WAL in
@@ -22,8 +22,9 @@ fn main() -> Result<()> {
 
 /// Schema used with IOx specific gRPC requests
 ///
-/// Creates `influxdata.platform.storage.rs` and
-/// `com.github.influxdata.idpe.storage.read.rs`
+/// Creates `influxdata.platform.storage.rs`,
+/// `com.github.influxdata.idpe.storage.read.rs`, `arrow.flight.protocol.rs`
+/// and `arrow.flight.protocol.sql.rs`
 fn generate_grpc_types(root: &Path) -> Result<()> {
     let proto_files = vec![
         root.join("test.proto"),
@@ -32,6 +33,8 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
         root.join("storage_common_idpe.proto"),
         root.join("service.proto"),
         root.join("source.proto"),
+        root.join("flight.proto"),
+        root.join("flight_sql.proto"),
     ];
 
     // Tell cargo to recompile if any of these proto files are changed
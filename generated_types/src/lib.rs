@@ -15,6 +15,17 @@ include!(concat!(
 ));
 include!(concat!(env!("OUT_DIR"), "/wal_generated.rs"));
 
+/// The Arrow Flight protocol, in its own module as some of its messages,
+/// such as `Result`, have the names of common types
+pub mod arrow_flight {
+    include!(concat!(env!("OUT_DIR"), "/arrow.flight.protocol.rs"));
+
+    /// The Flight SQL commands, sent as the commands of `FlightDescriptor`s
+    pub mod sql {
+        include!(concat!(env!("OUT_DIR"), "/arrow.flight.protocol.sql.rs"));
+    }
+}
+
 // Can't implement `Default` because `prost::Message` implements `Default`
 impl TimestampRange {
     pub fn max() -> Self {
@@ -140,7 +140,7 @@ impl nom::error::ParseError<&str> for Error {
 ///
 /// assert_eq!(timestamp, Some(1590488773254420000));
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParsedLine<'a> {
     pub series: Series<'a>,
     pub field_set: FieldSet<'a>,
@@ -230,7 +230,7 @@ impl<'a> Display for ParsedLine<'a> {
 
 /// Represents the identifier of a series (measurement, tagset) for
 /// line protocol data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Series<'a> {
     raw_input: &'a str,
     pub measurement: EscapedStr<'a>,
@@ -470,36 +470,56 @@ impl PartialEq<String> for EscapedStr<'_> {
 }
 
 pub fn parse_lines(input: &str) -> impl Iterator<Item = Result<ParsedLine<'_>>> {
-    split_lines(input).filter_map(|line| {
-        let i = trim_leading(line);
+    split_lines(input).filter_map(parse_line_str)
+}
 
-        if i.is_empty() {
-            return None;
-        }
+/// Parses each line of `input` in the same way as `parse_lines`, along with
+/// the number of the line in `input` it was parsed from, starting at 1. This
+/// allows errors to be reported against the lines that caused them.
+pub fn parse_numbered_lines(input: &str) -> impl Iterator<Item = (usize, Result<ParsedLine<'_>>)> {
+    let mut line_number = 1;
+    let mut counted = 0;
+    split_lines(input).filter_map(move |line| {
+        // Lines are slices of `input`, so the newlines before each can be
+        // counted from the end of the previous one.
+        let offset = line.as_ptr() as usize - input.as_ptr() as usize;
+        line_number += input[counted..offset].matches('\n').count();
+        counted = offset;
+
+        parse_line_str(line).map(|res| (line_number, res))
+    })
+}
 
-        let res = match parse_line(i) {
-            Ok((remaining, line)) => {
-                // should have parsed the whole input line, if any
-                // data remains it is a parse error for this line
-                // corresponding Go logic:
-                // https://github.com/influxdata/influxdb/blob/217eddc87e14a79b01d0c22994fc139f530094a2/models/points_parser.go#L259-L266
-                if !remaining.is_empty() {
-                    Some(Err(Error::CannotParseEntireLine {
-                        trailing_content: String::from(remaining),
-                    }))
-                } else {
-                    Some(Ok(line))
-                }
+// Parses a single line, returning `None` if the line is empty.
+fn parse_line_str(line: &str) -> Option<Result<ParsedLine<'_>>> {
+    let i = trim_leading(line);
+
+    if i.is_empty() {
+        return None;
+    }
+
+    let res = match parse_line(i) {
+        Ok((remaining, line)) => {
+            // should have parsed the whole input line, if any
+            // data remains it is a parse error for this line
+            // corresponding Go logic:
+            // https://github.com/influxdata/influxdb/blob/217eddc87e14a79b01d0c22994fc139f530094a2/models/points_parser.go#L259-L266
+            if !remaining.is_empty() {
+                Some(Err(Error::CannotParseEntireLine {
+                    trailing_content: String::from(remaining),
+                }))
+            } else {
+                Some(Ok(line))
             }
-            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Some(Err(e)),
-            Err(nom::Err::Incomplete(_)) => unreachable!("Cannot have incomplete data"), // Only streaming parsers have this
-        };
-
-        if let Some(Err(r)) = &res {
-            debug!("Error parsing line: '{}'. Error was {:?}", line, r);
         }
-        res
-    })
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Some(Err(e)),
+        Err(nom::Err::Incomplete(_)) => unreachable!("Cannot have incomplete data"), /* Only streaming parsers have this */
+    };
+
+    if let Some(Err(r)) = &res {
+        debug!("Error parsing line: '{}'. Error was {:?}", line, r);
+    }
+    res
 }
 
 /// Split `input` into invidividual lines to be parsed, based on the
@@ -1178,6 +1198,23 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn parse_numbered_lines() -> Result {
+        let input = "foo value=1i 1\n\nfoo value=\"a\nb\" 2\nfoo\n  \nfoo value=3i 3";
+        let vals: Vec<_> = super::parse_numbered_lines(input).collect();
+        assert_eq!(vals.len(), 4);
+
+        // Empty lines are skipped, and newlines within quoted field values
+        // are counted.
+        let numbers: Vec<_> = vals.iter().map(|(number, _)| *number).collect();
+        assert_eq!(numbers, vec![1, 3, 5, 7]);
+        assert!(vals[0].1.is_ok());
+        assert!(vals[1].1.is_ok());
+        assert!(vals[2].1.is_err());
+        assert_eq!(vals[3].1.as_ref().unwrap().timestamp, Some(3));
+        Ok(())
+    }
+
     #[test]
     fn parse_no_fields() -> Result {
         let input = "foo 1234";
@@ -1,7 +1,7 @@
 //! Represents a Chunk of data (a collection of tables and their data within
 //! some chunk) in the mutable store.
 use arrow_deps::{
-    arrow::record_batch::RecordBatch,
+    arrow::{datatypes::Schema as ArrowSchema, record_batch::RecordBatch},
     datafusion::{
         logical_plan::Expr, logical_plan::Operator, optimizer::utils::expr_to_column_names,
         prelude::*,
@@ -11,7 +11,10 @@ use chrono::{DateTime, Utc};
 use generated_types::wal as wb;
 use std::collections::{BTreeSet, HashMap, HashSet};
 
-use data_types::{partition_metadata::Table as TableStats, TIME_COLUMN_NAME};
+use data_types::{
+    partition_metadata::{Column as ColumnStats, Table as TableStats},
+    TIME_COLUMN_NAME,
+};
 use query::{
     predicate::{Predicate, TimestampRange},
     util::{visit_expression, AndExprBuilder, ExpressionVisitor},
@@ -19,6 +22,7 @@ use query::{
 
 use crate::dictionary::{Dictionary, Error as DictionaryError};
 use crate::table::Table;
+use crate::tombstone::Tombstone;
 
 use snafu::{OptionExt, ResultExt, Snafu};
 
@@ -46,13 +50,32 @@ pub enum Error {
     #[snafu(display("Table {} not found in chunk {}", table, chunk))]
     TableNotFoundInChunk { table: u32, chunk: String },
 
+    #[snafu(display("Column ID {} not found in dictionary of chunk {}", column, chunk))]
+    ColumnIdNotFoundInDictionary {
+        column: u32,
+        chunk: String,
+        source: DictionaryError,
+    },
+
     #[snafu(display("Attempt to write table batch without a name"))]
     TableWriteWithoutName,
+
+    #[snafu(display(
+        "Error deleting rows from table {} of chunk {}: {}",
+        table,
+        chunk,
+        source
+    ))]
+    TableDelete {
+        table: u32,
+        chunk: String,
+        source: crate::table::Error,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Chunk {
     /// The id for this chunk
     pub id: u64,
@@ -84,6 +107,72 @@ pub struct Chunk {
     pub tables: HashMap<u32, Table>,
 }
 
+/// Describes the contents of a chunk, for debugging and introspection
+#[derive(Debug, PartialEq)]
+pub struct ChunkSummary {
+    /// partition key for all rows in the chunk
+    pub key: String,
+
+    /// The id of the chunk
+    pub id: u64,
+
+    pub time_of_first_write: Option<DateTime<Utc>>,
+    pub time_of_last_write: Option<DateTime<Utc>>,
+    pub time_closed: Option<DateTime<Utc>>,
+
+    /// The tables in the chunk, ordered by name
+    pub tables: Vec<TableSummary>,
+}
+
+impl ChunkSummary {
+    /// Returns the total number of rows in the chunk
+    pub fn row_count(&self) -> usize {
+        self.tables.iter().map(|t| t.row_count).sum()
+    }
+
+    /// Returns the approximate number of bytes used to store the chunk's data
+    pub fn size(&self) -> usize {
+        self.tables.iter().map(|t| t.size).sum()
+    }
+
+    /// Returns the minimum and maximum (inclusive) timestamps of the rows in
+    /// the chunk, if any of its tables have a time column
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.tables.iter().filter_map(|t| t.time_range).fold(
+            None,
+            |range, (min, max)| match range {
+                None => Some((min, max)),
+                Some((a, b)) => Some((a.min(min), b.max(max))),
+            },
+        )
+    }
+}
+
+/// Describes the schema and contents of a table within a chunk
+#[derive(Debug, PartialEq)]
+pub struct TableSummary {
+    pub name: String,
+    pub row_count: usize,
+
+    /// The approximate number of bytes used to store the table's data
+    pub size: usize,
+
+    /// The minimum and maximum (inclusive) timestamps in the table, if it has
+    /// a time column
+    pub time_range: Option<(i64, i64)>,
+
+    /// The columns in the table, ordered by name
+    pub columns: Vec<ColumnSummary>,
+}
+
+/// Describes the type and summary statistics of a column within a table
+#[derive(Debug, PartialEq)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub column_type: &'static str,
+    pub stats: ColumnStats,
+}
+
 /// Describes the result of translating a set of strings into
 /// chunk specific ids
 #[derive(Debug, PartialEq, Eq)]
@@ -332,6 +421,52 @@ impl Chunk {
         }
     }
 
+    /// Returns true if any rows in this chunk are deleted by `tombstone`
+    pub fn has_deleted_rows(&self, tombstone: &Tombstone) -> Result<bool> {
+        for table_id in self.deleted_table_ids(tombstone) {
+            if let Some(table) = self.tables.get(&table_id) {
+                let has_deleted_rows = table
+                    .has_deleted_rows(&self.dictionary, tombstone)
+                    .context(TableDelete {
+                        table: table_id,
+                        chunk: &self.key,
+                    })?;
+                if has_deleted_rows {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Removes the rows deleted by `tombstone` from this chunk, returning the
+    /// number of rows removed. Tables left without any rows are removed too.
+    pub fn delete(&mut self, tombstone: &Tombstone) -> Result<usize> {
+        let mut deleted = 0;
+        for table_id in self.deleted_table_ids(tombstone) {
+            if let Some(table) = self.tables.get_mut(&table_id) {
+                deleted += table
+                    .delete(&self.dictionary, tombstone)
+                    .context(TableDelete {
+                        table: table_id,
+                        chunk: &self.key,
+                    })?;
+                if table.row_count() == 0 {
+                    self.tables.remove(&table_id);
+                }
+            }
+        }
+        Ok(deleted)
+    }
+
+    // Returns the ids of the tables `tombstone` applies to
+    fn deleted_table_ids(&self, tombstone: &Tombstone) -> Vec<u32> {
+        match &tombstone.table_name {
+            Some(table_name) => self.dictionary.id(table_name).into_iter().collect(),
+            None => self.tables.keys().cloned().collect(),
+        }
+    }
+
     /// returns true if there is no data in this chunk
     pub fn is_empty(&self) -> bool {
         self.tables.is_empty()
@@ -360,6 +495,18 @@ impl Chunk {
         Ok(())
     }
 
+    /// Returns the arrow schema of the table specified in this chunk, with
+    /// all of its columns, or None if the chunk doesn't contain the table
+    pub fn table_schema(&self, table_name: &str) -> Result<Option<ArrowSchema>> {
+        match self.table(table_name)? {
+            Some(table) => table
+                .arrow_schema(&self)
+                .map(Some)
+                .context(NamedTableError { table_name }),
+            None => Ok(None),
+        }
+    }
+
     /// Returns a vec of the summary statistics of the tables in this chunk
     pub fn table_stats(&self) -> Result<Vec<TableStats>> {
         let mut stats = Vec::with_capacity(self.tables.len());
@@ -384,6 +531,66 @@ impl Chunk {
         Ok(stats)
     }
 
+    /// Returns a description of the schema, row counts, sizes and column
+    /// statistics of the tables in this chunk
+    pub fn summary(&self) -> Result<ChunkSummary> {
+        let mut tables = Vec::with_capacity(self.tables.len());
+
+        for (id, table) in &self.tables {
+            let name = self
+                .dictionary
+                .lookup_id(*id)
+                .context(TableIdNotFoundInDictionary {
+                    table: *id,
+                    chunk: &self.key,
+                })?;
+
+            let mut columns = Vec::with_capacity(table.columns.len());
+            let mut time_range = None;
+            for (column_id, &column_index) in &table.column_id_to_index {
+                let column_name = self.dictionary.lookup_id(*column_id).context(
+                    ColumnIdNotFoundInDictionary {
+                        column: *column_id,
+                        chunk: &self.key,
+                    },
+                )?;
+
+                let column = &table.columns[column_index];
+                let stats = column.stats();
+                if column_name == TIME_COLUMN_NAME {
+                    if let ColumnStats::I64(stats) = &stats {
+                        time_range = Some((stats.min, stats.max));
+                    }
+                }
+
+                columns.push(ColumnSummary {
+                    name: column_name.to_string(),
+                    column_type: column.type_description(),
+                    stats,
+                });
+            }
+            columns.sort_by(|a, b| a.name.cmp(&b.name));
+
+            tables.push(TableSummary {
+                name: name.to_string(),
+                row_count: table.row_count(),
+                size: table.size(),
+                time_range,
+                columns,
+            });
+        }
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(ChunkSummary {
+            key: self.key.clone(),
+            id: self.id,
+            time_of_first_write: self.time_of_first_write,
+            time_of_last_write: self.time_of_last_write,
+            time_closed: self.time_closed,
+            tables,
+        })
+    }
+
     /// Returns the named table, or None if no such table exists in this chunk
     fn table(&self, table_name: &str) -> Result<Option<&Table>> {
         let table_id = self.dictionary.lookup_value(table_name);
@@ -494,4 +701,33 @@ mod tests {
 
         assert_eq!(actual_string, expected_string);
     }
+
+    #[test]
+    fn test_summary_time_range() {
+        let table = |name: &str, time_range| TableSummary {
+            name: name.into(),
+            row_count: 1,
+            size: 10,
+            time_range,
+            columns: vec![],
+        };
+        let mut summary = ChunkSummary {
+            key: "key".into(),
+            id: 0,
+            time_of_first_write: None,
+            time_of_last_write: None,
+            time_closed: None,
+            tables: vec![],
+        };
+        assert_eq!(summary.time_range(), None);
+
+        summary.tables = vec![
+            table("cpu", Some((100, 200))),
+            table("disk", None),
+            table("mem", Some((50, 150))),
+        ];
+        assert_eq!(summary.time_range(), Some((50, 200)));
+        assert_eq!(summary.row_count(), 3);
+        assert_eq!(summary.size(), 30);
+    }
 }
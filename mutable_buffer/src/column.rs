@@ -1,8 +1,11 @@
 use generated_types::wal as wb;
-use snafu::Snafu;
+use snafu::{ResultExt, Snafu};
 
-use crate::dictionary::Dictionary;
-use data_types::{data::type_description, partition_metadata::Statistics};
+use crate::dictionary::{Dictionary, Error as DictionaryError};
+use data_types::{
+    data::type_description,
+    partition_metadata::{Column as ColumnStats, Statistics},
+};
 
 use arrow_deps::arrow::datatypes::DataType as ArrowDataType;
 
@@ -23,10 +26,13 @@ pub enum Error {
 
     #[snafu(display("InternalError: Applying i64 range on a column with non-i64 type"))]
     InternalTypeMismatchForTimePredicate,
+
+    #[snafu(display("Tag value ID {} not found in dictionary: {}", value, source))]
+    TagValueIdNotFound { value: u32, source: DictionaryError },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Stores the actual data for columns in a chunk along with summary
 /// statistics
 pub enum Column {
@@ -117,6 +123,31 @@ impl Column {
         self.len() == 0
     }
 
+    /// Returns the approximate number of bytes used to store the values in
+    /// this column
+    pub fn size(&self) -> usize {
+        match self {
+            Self::F64(v, _) => v.len() * std::mem::size_of::<Option<f64>>(),
+            Self::I64(v, _) => v.len() * std::mem::size_of::<Option<i64>>(),
+            Self::Bool(v, _) => v.len() * std::mem::size_of::<Option<bool>>(),
+            Self::Tag(v, _) => v.len() * std::mem::size_of::<Option<u32>>(),
+            Self::String(v, _) => v
+                .iter()
+                .map(|s| std::mem::size_of::<Option<String>>() + s.as_ref().map_or(0, |s| s.len()))
+                .sum(),
+        }
+    }
+
+    /// Returns the summary statistics for this column
+    pub fn stats(&self) -> ColumnStats {
+        match self {
+            Self::F64(_, stats) => ColumnStats::F64(stats.clone()),
+            Self::I64(_, stats) => ColumnStats::I64(stats.clone()),
+            Self::Bool(_, stats) => ColumnStats::Bool(stats.clone()),
+            Self::String(_, stats) | Self::Tag(_, stats) => ColumnStats::String(stats.clone()),
+        }
+    }
+
     pub fn type_description(&self) -> &'static str {
         match self {
             Self::F64(_, _) => "f64",
@@ -247,6 +278,24 @@ impl Column {
         }
     }
 
+    /// Keeps only the rows for which `keep` is true, recomputing the
+    /// statistics from the values that remain. Returns false, leaving the
+    /// statistics as they were, if no non-null values remain.
+    pub fn retain(&mut self, dictionary: &Dictionary, keep: &[bool]) -> Result<bool> {
+        Ok(match self {
+            Self::F64(v, stats) => retain_values(v, keep, stats, |v| Ok(*v))?,
+            Self::I64(v, stats) => retain_values(v, keep, stats, |v| Ok(*v))?,
+            Self::String(v, stats) => retain_values(v, keep, stats, |v| Ok(v.clone()))?,
+            Self::Bool(v, stats) => retain_values(v, keep, stats, |v| Ok(*v))?,
+            Self::Tag(v, stats) => retain_values(v, keep, stats, |&id| {
+                dictionary
+                    .lookup_id(id)
+                    .map(ToString::to_string)
+                    .context(TagValueIdNotFound { value: id })
+            })?,
+        })
+    }
+
     /// Return true of this column's type is a Tag
     pub fn is_tag(&self) -> bool {
         matches!(self, Self::Tag(..))
@@ -277,6 +326,39 @@ impl Column {
     }
 }
 
+// Keeps the values for which `keep` is true, and recomputes `stats` from the
+// `stat_value` of each that remains, if any do.
+fn retain_values<V, T, F>(
+    values: &mut Vec<Option<V>>,
+    keep: &[bool],
+    stats: &mut Statistics<T>,
+    stat_value: F,
+) -> Result<bool>
+where
+    T: PartialEq + PartialOrd + std::fmt::Debug + std::fmt::Display + Clone,
+    F: Fn(&V) -> Result<T>,
+{
+    let mut keep = keep.iter();
+    values.retain(|_| *keep.next().expect("a keep flag for every row"));
+
+    let mut new_stats: Option<Statistics<T>> = None;
+    for value in values.iter().flatten() {
+        let value = stat_value(value)?;
+        match new_stats.as_mut() {
+            Some(stats) => stats.update(value),
+            None => new_stats = Some(Statistics::new(value)),
+        }
+    }
+
+    Ok(match new_stats {
+        Some(new_stats) => {
+            *stats = new_stats;
+            true
+        }
+        None => false,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -345,4 +427,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_retain() -> Result {
+        let dictionary = Dictionary::new();
+        let mut stats = Statistics::new(1);
+        stats.update(5);
+        stats.update(3);
+        let mut col = Column::I64(vec![Some(1), Some(5), None, Some(3)], stats);
+
+        assert!(col.retain(&dictionary, &[false, true, true, true])?);
+        match &col {
+            Column::I64(vals, stats) => {
+                assert_eq!(vals, &[Some(5), None, Some(3)]);
+                assert_eq!((stats.min, stats.max, stats.count), (3, 5, 2));
+            }
+            _ => panic!("expected an i64 column"),
+        }
+
+        // No values remain
+        assert!(!col.retain(&dictionary, &[false, true, false])?);
+        assert_eq!(col.len(), 1);
+
+        Ok(())
+    }
 }
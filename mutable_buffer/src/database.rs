@@ -4,7 +4,8 @@ use query::group_by::GroupByAndAggregate;
 use query::group_by::WindowDuration;
 use query::{
     exec::{stringset::StringSet, FieldListPlan, SeriesSetPlan, SeriesSetPlans, StringSetPlan},
-    predicate::Predicate,
+    func::window::make_time_bucket_udf,
+    predicate::{Predicate, TimestampRange},
     SQLDatabase, TSDatabase,
 };
 use query::{group_by::Aggregate, predicate::PredicateBuilder};
@@ -12,8 +13,9 @@ use query::{group_by::Aggregate, predicate::PredicateBuilder};
 use crate::column::Column;
 use crate::table::Table;
 use crate::{
-    chunk::{Chunk, ChunkPredicate},
+    chunk::{Chunk, ChunkPredicate, ChunkSummary},
     partition::Partition,
+    tombstone::Tombstone,
 };
 
 use std::collections::{BTreeSet, HashMap, HashSet};
@@ -22,8 +24,12 @@ use std::sync::Arc;
 use arrow_deps::{
     arrow::{datatypes::Schema as ArrowSchema, record_batch::RecordBatch},
     datafusion::{
-        datasource::MemTable, error::DataFusionError, execution::context::ExecutionContext,
-        logical_plan::LogicalPlan, physical_plan::collect, prelude::ExecutionConfig,
+        datasource::MemTable,
+        error::DataFusionError,
+        execution::context::ExecutionContext,
+        logical_plan::LogicalPlan,
+        physical_plan::{collect, merge::MergeExec, ExecutionPlan, SendableRecordBatchStream},
+        prelude::ExecutionConfig,
     },
 };
 use data_types::data::{split_lines_into_write_entry_partitions, ReplicatedWrite};
@@ -32,7 +38,7 @@ use crate::dictionary::Error as DictionaryError;
 
 use async_trait::async_trait;
 use chrono::{offset::TimeZone, Utc};
-use snafu::{ResultExt, Snafu};
+use snafu::{OptionExt, ResultExt, Snafu};
 use sqlparser::{
     ast::{SetExpr, Statement, TableFactor},
     dialect::GenericDialect,
@@ -101,6 +107,9 @@ pub enum Error {
         source: DataFusionError,
     },
 
+    #[snafu(display("Table {} referenced by query {} not found", table, query))]
+    TableNotFound { query: String, table: String },
+
     #[snafu(display("Unsupported SQL statement in query {}: {}", query, statement))]
     UnsupportedStatement {
         query: String,
@@ -109,6 +118,9 @@ pub enum Error {
 
     #[snafu(display("replicated write from writer {} missing payload", writer))]
     MissingPayload { writer: u32 },
+
+    #[snafu(display("Delete in replicated write has a tag without a name or value"))]
+    InvalidDelete,
 }
 
 impl From<crate::table::Error> for Error {
@@ -140,6 +152,21 @@ impl From<crate::partition::Error> for Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// An estimate of the cost of reading a table within a time range, as
+/// returned by `MutableBufferDb::explain`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlanEstimate {
+    /// The number of chunks whose rows of the table overlap the time range
+    pub chunks_scanned: usize,
+
+    /// The number of rows of the table in those chunks. Rows outside the
+    /// time range are included, so this is an upper bound.
+    pub estimated_rows: usize,
+
+    /// The columns of the table in those chunks, ordered by name
+    pub columns: Vec<String>,
+}
+
 #[derive(Debug, Default)]
 /// This implements the mutable buffer. See the module doc comments
 /// for more details.
@@ -159,10 +186,17 @@ impl MutableBufferDb {
         }
     }
 
-    /// Directs the writes from batch into the appropriate partitions
+    /// Directs the writes from batch into the appropriate partitions, and
+    /// applies any deletes in it to every partition
     async fn write_entries_to_partitions(&self, batch: &wb::WriteBufferBatch<'_>) -> Result<()> {
         if let Some(entries) = batch.entries() {
             for entry in entries {
+                if let Some(delete) = entry.delete() {
+                    let tombstone = Tombstone::from_fb(&delete).context(InvalidDelete)?;
+                    self.delete(&tombstone).await?;
+                    continue;
+                }
+
                 let key = entry
                     .partition_key()
                     .expect("partition key should have been inserted");
@@ -186,6 +220,121 @@ impl MutableBufferDb {
         Ok(batches)
     }
 
+    /// Returns the schema of the record batch that `table_to_arrow` returns
+    /// first when all columns are requested, without converting any rows, or
+    /// None if no chunk contains the table
+    async fn table_schema(&self, table_name: &str) -> Result<Option<Arc<ArrowSchema>>> {
+        for partition in self.partition_snapshot().await.into_iter() {
+            let partition = partition.read().await;
+            if let Some(schema) = partition.table_schema(table_name)? {
+                return Ok(Some(Arc::new(schema)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns summaries of the chunks in the partition with the
+    /// specified key, in the order they were created, or None if there
+    /// is no such partition
+    pub async fn chunk_summaries(&self, partition_key: &str) -> Result<Option<Vec<ChunkSummary>>> {
+        let partition = match self.partitions.read().await.get(partition_key) {
+            Some(partition) => partition.clone(),
+            None => return Ok(None),
+        };
+        let partition = partition.read().await;
+
+        let summaries = partition
+            .iter()
+            .map(|chunk| chunk.summary())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(summaries))
+    }
+
+    /// Returns the minimum and maximum (inclusive) timestamps of the rows of
+    /// the named table across all partitions, or None if no chunk contains
+    /// timestamps for the table.
+    pub async fn time_bounds(&self, table_name: &str) -> Result<Option<(i64, i64)>> {
+        let mut bounds: Option<(i64, i64)> = None;
+        for partition in self.partition_snapshot().await {
+            let partition = partition.read().await;
+            for chunk in partition.iter() {
+                let summary = chunk.summary()?;
+                let range = summary
+                    .tables
+                    .iter()
+                    .find(|table| table.name == table_name)
+                    .and_then(|table| table.time_range);
+
+                if let Some((min, max)) = range {
+                    bounds = match bounds {
+                        None => Some((min, max)),
+                        Some((a, b)) => Some((a.min(min), b.max(max))),
+                    };
+                }
+            }
+        }
+        Ok(bounds)
+    }
+
+    /// Estimates the cost of reading the rows of the named table within
+    /// `range` from the chunk summaries alone, without reading any rows.
+    /// Chunks whose timestamps for the table lie outside the range aren't
+    /// scanned. Returns None if no chunk contains the table.
+    pub async fn explain(
+        &self,
+        table_name: &str,
+        range: TimestampRange,
+    ) -> Result<Option<QueryPlanEstimate>> {
+        let mut found = false;
+        let mut estimate = QueryPlanEstimate {
+            chunks_scanned: 0,
+            estimated_rows: 0,
+            columns: vec![],
+        };
+        let mut columns = BTreeSet::new();
+
+        for partition in self.partition_snapshot().await {
+            let partition = partition.read().await;
+            for chunk in partition.iter() {
+                let summary = chunk.summary()?;
+                let table = match summary.tables.iter().find(|table| table.name == table_name) {
+                    Some(table) => table,
+                    None => continue,
+                };
+                found = true;
+
+                let overlaps = table
+                    .time_range
+                    .map_or(false, |(min, max)| min < range.end && range.start <= max);
+                if overlaps {
+                    estimate.chunks_scanned += 1;
+                    estimate.estimated_rows += table.row_count;
+                    columns.extend(table.columns.iter().map(|column| column.name.clone()));
+                }
+            }
+        }
+
+        if !found {
+            return Ok(None);
+        }
+        estimate.columns = columns.into_iter().collect();
+        Ok(Some(estimate))
+    }
+
+    /// Removes the rows deleted by `tombstone` from every partition,
+    /// returning the number of rows removed. Deletes are recorded in the WAL
+    /// like writes, and applied as they are stored.
+    async fn delete(&self, tombstone: &Tombstone) -> Result<usize> {
+        let mut deleted = 0;
+        for partition in self.partition_snapshot().await {
+            let mut partition = partition.write().await;
+            deleted += partition.delete(tombstone)?;
+        }
+        Ok(deleted)
+    }
+
     /// Rolls over the active chunk in this partititon
     pub async fn rollover_partition(&self, partition_key: &str) -> Result<Arc<Chunk>> {
         let partition = self.get_partition(partition_key).await;
@@ -316,56 +465,27 @@ impl SQLDatabase for MutableBufferDb {
     type Error = Error;
 
     async fn query(&self, query: &str) -> Result<Vec<RecordBatch>, Self::Error> {
-        let mut tables = vec![];
-
-        let dialect = GenericDialect {};
-        let ast = Parser::parse_sql(&dialect, query).context(InvalidSqlQuery { query })?;
-
-        for statement in ast {
-            match statement {
-                Statement::Query(q) => {
-                    if let SetExpr::Select(q) = q.body {
-                        for item in q.from {
-                            if let TableFactor::Table { name, .. } = item.relation {
-                                let name = name.to_string();
-                                let data = self.table_to_arrow(&name, &[]).await?;
-                                tables.push(ArrowTable {
-                                    name,
-                                    schema: data[0].schema().clone(),
-                                    data,
-                                });
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    return UnsupportedStatement {
-                        query: query.to_string(),
-                        statement,
-                    }
-                    .fail()
-                }
-            }
-        }
+        let plan = self.physical_plan(query, false).await?;
+        collect(plan).await.context(QueryError { query })
+    }
 
-        let config = ExecutionConfig::new().with_batch_size(1024 * 1024);
-        let mut ctx = ExecutionContext::with_config(config);
+    async fn query_stream(&self, query: &str) -> Result<SendableRecordBatchStream, Self::Error> {
+        let plan = self.physical_plan(query, false).await?;
 
-        for table in tables {
-            let provider =
-                MemTable::try_new(table.schema, vec![table.data]).context(QueryError { query })?;
-            ctx.register_table(&table.name, Box::new(provider));
-        }
-
-        let plan = ctx
-            .create_logical_plan(&query)
-            .context(QueryError { query })?;
-        let plan = ctx.optimize(&plan).context(QueryError { query })?;
-        let plan = ctx
-            .create_physical_plan(&plan)
-            .context(QueryError { query })?;
+        // merge the output partitions into a single stream
+        let plan: Arc<dyn ExecutionPlan> = if plan.output_partitioning().partition_count() <= 1 {
+            plan
+        } else {
+            Arc::new(MergeExec::new(plan))
+        };
+        plan.execute(0).await.context(QueryError { query })
+    }
 
-        collect(plan).await.context(QueryError { query })
+    /// Plan the specified query without executing it, and return the
+    /// schema of its results
+    async fn query_schema(&self, query: &str) -> Result<Arc<ArrowSchema>, Self::Error> {
+        let plan = self.physical_plan(query, true).await?;
+        Ok(plan.schema())
     }
 
     /// Fetch the specified table names and columns as Arrow
@@ -378,6 +498,15 @@ impl SQLDatabase for MutableBufferDb {
         self.table_to_arrow(table_name, columns).await
     }
 
+    /// Return the schema of the specified table with all of its columns,
+    /// without converting any rows
+    async fn table_schema(
+        &self,
+        table_name: &str,
+    ) -> Result<Option<Arc<ArrowSchema>>, Self::Error> {
+        self.table_schema(table_name).await
+    }
+
     /// Return the partition keys for data in this DB
     async fn partition_keys(&self) -> Result<Vec<String>, Self::Error> {
         let partitions = self.partitions.read().await;
@@ -483,6 +612,69 @@ trait Visitor {
 }
 
 impl MutableBufferDb {
+    /// Plans the specified SQL query against the tables it reads from. If
+    /// `schema_only` is set the tables are planned from their schemas without
+    /// any of their rows, for when the plan won't be executed.
+    async fn physical_plan(
+        &self,
+        query: &str,
+        schema_only: bool,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut tables = vec![];
+
+        let dialect = GenericDialect {};
+        let ast = Parser::parse_sql(&dialect, query).context(InvalidSqlQuery { query })?;
+
+        for statement in ast {
+            match statement {
+                Statement::Query(q) => {
+                    if let SetExpr::Select(q) = q.body {
+                        for item in q.from {
+                            if let TableFactor::Table { name, .. } = item.relation {
+                                let name = name.to_string();
+                                let (schema, data) = if schema_only {
+                                    (self.table_schema(&name).await?, vec![])
+                                } else {
+                                    let data = self.table_to_arrow(&name, &[]).await?;
+                                    (data.first().map(|batch| batch.schema()), data)
+                                };
+                                let schema = schema.context(TableNotFound {
+                                    query,
+                                    table: &name,
+                                })?;
+                                tables.push(ArrowTable { name, schema, data });
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return UnsupportedStatement {
+                        query: query.to_string(),
+                        statement,
+                    }
+                    .fail()
+                }
+            }
+        }
+
+        let config = ExecutionConfig::new().with_batch_size(1024 * 1024);
+        let mut ctx = ExecutionContext::with_config(config);
+        ctx.register_udf(make_time_bucket_udf());
+
+        for table in tables {
+            let provider =
+                MemTable::try_new(table.schema, vec![table.data]).context(QueryError { query })?;
+            ctx.register_table(&table.name, Box::new(provider));
+        }
+
+        let plan = ctx
+            .create_logical_plan(&query)
+            .context(QueryError { query })?;
+        let plan = ctx.optimize(&plan).context(QueryError { query })?;
+        ctx.create_physical_plan(&plan)
+            .context(QueryError { query })
+    }
+
     /// returns the number of partitions in this database
     pub async fn len(&self) -> usize {
         self.partitions.read().await.len()
@@ -1080,7 +1272,7 @@ mod tests {
             seriesset::{Error as SeriesSetError, SeriesSet, SeriesSetItem},
             Executor,
         },
-        predicate::PredicateBuilder,
+        predicate::{PredicateBuilder, TimestampRange},
         TSDatabase,
     };
 
@@ -1250,6 +1442,108 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn delete_and_query() -> Result {
+        let db = MutableBufferDb::new("foo");
+
+        let lines: Vec<_> = parse_lines(
+            "cpu,region=west,host=A user=23.2 10\n\
+             cpu,region=west,host=B user=10.0 20\n\
+             cpu,region=west,host=A user=21.0 30\n\
+             cpu,region=east,host=A user=99.0 40\n\
+             mem,region=west,host=A free=1i 20",
+        )
+        .map(|l| l.unwrap())
+        .collect();
+        db.write_lines(&lines).await?;
+
+        delete(
+            &db,
+            Tombstone::new(TimestampRange::new(10, 35))
+                .with_table("cpu")
+                .with_tag("host", "A"),
+        )
+        .await?;
+
+        let results = db.query("select * from cpu order by time").await?;
+        let expected_cpu_table = &[
+            "+------+--------+------+------+",
+            "| host | region | time | user |",
+            "+------+--------+------+------+",
+            "| B    | west   | 20   | 10   |",
+            "| A    | east   | 40   | 99   |",
+            "+------+--------+------+------+",
+        ];
+        assert_table_eq!(expected_cpu_table, &results);
+
+        // Other tables are unaffected
+        let results = db.query("select * from mem").await?;
+        let expected_mem_table = &[
+            "+------+------+--------+------+",
+            "| free | host | region | time |",
+            "+------+------+--------+------+",
+            "| 1    | A    | west   | 20   |",
+            "+------+------+--------+------+",
+        ];
+        assert_table_eq!(expected_mem_table, &results);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_from_closed_chunks_and_non_sql_reads() -> Result {
+        let db = MutableBufferDb::new("foo");
+
+        let lines: Vec<_> = parse_lines(
+            "cpu,host=A user=23.2 10\n\
+             cpu,host=B user=10.0 20\n\
+             mem,host=A free=1i 20",
+        )
+        .map(|l| l.unwrap())
+        .collect();
+        db.write_lines(&lines).await?;
+        let partition_key = compute_partition_key(&lines[0]);
+        let closed_chunk = db.rollover_partition(&partition_key).await?;
+
+        let lines: Vec<_> = parse_lines("cpu,host=A user=21.0 30")
+            .map(|l| l.unwrap())
+            .collect();
+        db.write_lines(&lines).await?;
+
+        delete(
+            &db,
+            Tombstone::new(TimestampRange::new(0, 100)).with_tag("host", "A"),
+        )
+        .await?;
+
+        // The deleted rows are gone from plans, summaries and time bounds
+        assert_eq!(
+            table_names(&db, Predicate::default()).await?,
+            to_set(&["cpu"])
+        );
+        assert_eq!(db.time_bounds("cpu").await?, Some((20, 20)));
+        assert_eq!(db.time_bounds("mem").await?, None);
+        let summaries = db.chunk_summaries(&partition_key).await?.unwrap();
+        let row_counts: Vec<_> = summaries.iter().map(|s| s.row_count()).collect();
+        assert_eq!(row_counts, vec![1, 0]);
+
+        // A chunk taken before the delete, such as one being snapshotted, is
+        // unaffected
+        assert_eq!(closed_chunk.summary()?.row_count(), 3);
+
+        // Points written after the delete aren't deleted
+        let lines: Vec<_> = parse_lines("mem,host=A free=2i 40")
+            .map(|l| l.unwrap())
+            .collect();
+        db.write_lines(&lines).await?;
+        assert_eq!(
+            table_names(&db, Predicate::default()).await?,
+            to_set(&["cpu", "mem"])
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn db_partition_key() -> Result {
         let partition_keys: Vec<_> = parse_lines(
@@ -1935,6 +2229,14 @@ disk bytes=23432323i 1600136510000000000",
         Ok(())
     }
 
+    // Deletes as the server does, by storing a replicated write that records
+    // the delete
+    async fn delete(db: &MutableBufferDb, tombstone: Tombstone) -> Result {
+        db.store_replicated_write(&tombstone.to_replicated_write(1, 1))
+            .await?;
+        Ok(())
+    }
+
     /// Run the plan and gather the results in a order that can be compared
     async fn run_and_gather_results(
         plans: SeriesSetPlans,
@@ -16,7 +16,7 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Dictionary(
     StringInterner<DefaultSymbol, StringBackend<DefaultSymbol>, DefaultHashBuilder>,
 );
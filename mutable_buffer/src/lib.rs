@@ -63,8 +63,10 @@ mod dictionary;
 mod partition;
 mod store;
 mod table;
+mod tombstone;
 
 // Allow restore chunks to be used outside of this crate (for
 // benchmarking)
-pub use crate::database::MutableBufferDb;
+pub use crate::database::{Error as DatabaseError, MutableBufferDb, QueryPlanEstimate};
 pub use crate::store::MutableBufferDatabases;
+pub use crate::tombstone::Tombstone;
@@ -1,10 +1,13 @@
 //! Holds one or more Chunks.
 
-use arrow_deps::arrow::record_batch::RecordBatch;
+use arrow_deps::arrow::{datatypes::Schema, record_batch::RecordBatch};
 use generated_types::wal as wb;
 use std::{collections::BTreeMap, sync::Arc};
 
-use crate::chunk::{Chunk, Error as ChunkError};
+use crate::{
+    chunk::{Chunk, Error as ChunkError},
+    tombstone::Tombstone,
+};
 
 use snafu::{ResultExt, Snafu};
 
@@ -20,6 +23,18 @@ pub enum Error {
         source: ChunkError,
     },
 
+    #[snafu(display(
+        "Error deleting from chunk {} of partition with key '{}': {}",
+        chunk_id,
+        partition_key,
+        source
+    ))]
+    DeletingChunkData {
+        partition_key: String,
+        chunk_id: u64,
+        source: ChunkError,
+    },
+
     #[snafu(display(
         "Can not drop open chunk '{}' of partition with key '{}'",
         chunk_id,
@@ -99,6 +114,34 @@ impl Partition {
             })
     }
 
+    /// Removes the rows deleted by `tombstone` from the chunks of this
+    /// partition, returning the number of rows removed. A closed chunk with
+    /// deleted rows is replaced by a copy without them, so that anything
+    /// still reading the original, such as a snapshot, is unaffected. Closed
+    /// chunks left without any rows are dropped.
+    pub fn delete(&mut self, tombstone: &Tombstone) -> Result<usize> {
+        let mut deleted = self
+            .open_chunk
+            .delete(tombstone)
+            .context(DeletingChunkData {
+                partition_key: &self.key,
+                chunk_id: self.open_chunk.id(),
+            })?;
+
+        for (&chunk_id, chunk) in self.closed_chunks.iter_mut() {
+            let context = DeletingChunkData {
+                partition_key: &self.key,
+                chunk_id,
+            };
+            if chunk.has_deleted_rows(tombstone).context(context)? {
+                deleted += Arc::make_mut(chunk).delete(tombstone).context(context)?;
+            }
+        }
+        self.closed_chunks.retain(|_, chunk| !chunk.is_empty());
+
+        Ok(deleted)
+    }
+
     /// Convert the table specified in this chunk into some number of
     /// record batches, appended to dst
     pub fn table_to_arrow(
@@ -113,6 +156,17 @@ impl Partition {
         Ok(())
     }
 
+    /// Returns the schema of the table in the first chunk containing it, in
+    /// the order `table_to_arrow` visits the chunks
+    pub fn table_schema(&self, table_name: &str) -> crate::chunk::Result<Option<Schema>> {
+        for chunk in self.iter() {
+            if let Some(schema) = chunk.table_schema(table_name)? {
+                return Ok(Some(schema));
+            }
+        }
+        Ok(None)
+    }
+
     /// Return information about the chunks held in this partition
     #[allow(dead_code)]
     pub fn chunk_info(&self) -> PartitionChunkInfo {
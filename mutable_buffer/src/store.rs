@@ -43,6 +43,12 @@ impl DatabaseStore for MutableBufferDatabases {
         databases.get(name).cloned()
     }
 
+    async fn db_names_sorted(&self) -> Vec<String> {
+        let databases = self.databases.read().await;
+
+        databases.keys().cloned().collect()
+    }
+
     async fn db_or_create(&self, name: &str) -> Result<Arc<Self::Database>, Self::Error> {
         // get it through a read lock first if we can
         {
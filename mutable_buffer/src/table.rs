@@ -15,6 +15,7 @@ use crate::{
     column,
     column::Column,
     dictionary::{Dictionary, Error as DictionaryError},
+    tombstone::Tombstone,
 };
 use data_types::{partition_metadata::Column as ColumnStats, TIME_COLUMN_NAME};
 use snafu::{OptionExt, ResultExt, Snafu};
@@ -118,6 +119,12 @@ pub enum Error {
     #[snafu(display("Row insert to table {} missing column name", table))]
     ColumnNameNotInRow { table: u32 },
 
+    #[snafu(display("Error deleting rows from column {}: {}", column, source))]
+    DeletingRows {
+        column: u32,
+        source: crate::column::Error,
+    },
+
     #[snafu(display(
         "Group column '{}' not found in tag columns: {}",
         column_name,
@@ -136,7 +143,7 @@ pub enum Error {
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Table {
     /// Name of the table as a u32 in the chunk dictionary
     pub id: u32,
@@ -244,6 +251,83 @@ impl Table {
         Ok(())
     }
 
+    /// Returns true if any rows of this table are deleted by `tombstone`,
+    /// which must either apply to this table or to all tables.
+    pub fn has_deleted_rows(&self, dictionary: &Dictionary, tombstone: &Tombstone) -> Result<bool> {
+        Ok(self.deleted_rows(dictionary, tombstone)?.is_some())
+    }
+
+    /// Removes the rows deleted by `tombstone`, which must either apply to
+    /// this table or to all tables, returning the number of rows removed.
+    /// Columns left without any values are removed too.
+    pub fn delete(&mut self, dictionary: &Dictionary, tombstone: &Tombstone) -> Result<usize> {
+        let deleted = match self.deleted_rows(dictionary, tombstone)? {
+            Some(deleted) => deleted,
+            None => return Ok(0),
+        };
+        let keep: Vec<bool> = deleted.iter().map(|deleted| !deleted).collect();
+
+        let columns = std::mem::take(&mut self.columns);
+        let mut column_ids = vec![0; columns.len()];
+        for (&column_id, &column_index) in &self.column_id_to_index {
+            column_ids[column_index] = column_id;
+        }
+        self.column_id_to_index.clear();
+
+        for (column_id, mut column) in column_ids.into_iter().zip(columns) {
+            let has_values = column
+                .retain(dictionary, &keep)
+                .context(DeletingRows { column: column_id })?;
+            if has_values {
+                self.column_id_to_index
+                    .insert(column_id, self.columns.len());
+                self.columns.push(column);
+            }
+        }
+
+        Ok(deleted.iter().filter(|deleted| **deleted).count())
+    }
+
+    // Returns which rows of this table `tombstone` deletes, or None if it
+    // deletes none of them. A row without a time, or without one of the
+    // tombstone's tag values, is not deleted.
+    fn deleted_rows(
+        &self,
+        dictionary: &Dictionary,
+        tombstone: &Tombstone,
+    ) -> Result<Option<Vec<bool>>> {
+        let time_column_id = match dictionary.id(TIME_COLUMN_NAME) {
+            Some(id) if self.column_id_to_index.contains_key(&id) => id,
+            _ => return Ok(None),
+        };
+        let mut deleted: Vec<bool> = self
+            .column_i64(time_column_id)?
+            .iter()
+            .map(|&time| tombstone.range.contains_opt(time))
+            .collect();
+
+        for (tag, value) in &tombstone.tags {
+            let column = dictionary
+                .id(tag)
+                .and_then(|tag_id| self.column_id_to_index.get(&tag_id))
+                .map(|&column_index| &self.columns[column_index]);
+            let (values, value_id) = match (column, dictionary.id(value)) {
+                (Some(Column::Tag(values, _)), Some(value_id)) => (values, value_id),
+                _ => return Ok(None),
+            };
+
+            for (deleted, tag_value) in deleted.iter_mut().zip(values) {
+                *deleted = *deleted && *tag_value == Some(value_id);
+            }
+        }
+
+        Ok(if deleted.iter().any(|deleted| *deleted) {
+            Some(deleted)
+        } else {
+            None
+        })
+    }
+
     /// Creates and adds a datafuson filtering expression, if any out of the
     /// combination of predicate and timestamp. Returns the builder
     fn add_datafusion_predicate(
@@ -854,6 +938,35 @@ impl Table {
         self.to_arrow_impl(chunk, &requested_columns_with_index)
     }
 
+    /// Returns the arrow schema of this table with all of its columns, as
+    /// that of the record batch returned by `all_to_arrow`, without
+    /// converting any rows
+    pub fn arrow_schema(&self, chunk: &Chunk) -> Result<ArrowSchema> {
+        let mut fields = self
+            .column_id_to_index
+            .iter()
+            .map(|(&column_id, &column_index)| {
+                let column_name = chunk.dictionary.lookup_id(column_id).context(
+                    ColumnIdNotFoundInDictionary {
+                        column_id,
+                        chunk: &chunk.key,
+                    },
+                )?;
+                let data_type = match &self.columns[column_index] {
+                    Column::String(..) | Column::Tag(..) => ArrowDataType::Utf8,
+                    Column::F64(..) => ArrowDataType::Float64,
+                    Column::I64(..) => ArrowDataType::Int64,
+                    Column::Bool(..) => ArrowDataType::Boolean,
+                };
+                Ok(ArrowField::new(column_name, data_type, true))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        fields.sort_by(|a, b| a.name().cmp(b.name()));
+
+        Ok(ArrowSchema::new(fields))
+    }
+
     /// Converts this table to an arrow record batch,
     ///
     /// requested columns with index are tuples of column_name, column_index
@@ -1036,17 +1149,13 @@ impl Table {
     }
 
     pub fn stats(&self) -> Vec<ColumnStats> {
-        self.columns
-            .iter()
-            .map(|c| match c {
-                Column::F64(_, stats) => ColumnStats::F64(stats.clone()),
-                Column::I64(_, stats) => ColumnStats::I64(stats.clone()),
-                Column::Bool(_, stats) => ColumnStats::Bool(stats.clone()),
-                Column::String(_, stats) | Column::Tag(_, stats) => {
-                    ColumnStats::String(stats.clone())
-                }
-            })
-            .collect()
+        self.columns.iter().map(|c| c.stats()).collect()
+    }
+
+    /// Returns the approximate number of bytes used to store the data in
+    /// this table
+    pub fn size(&self) -> usize {
+        self.columns.iter().map(|c| c.size()).sum()
     }
 }
 
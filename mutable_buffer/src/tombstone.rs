@@ -0,0 +1,75 @@
+//! Deletes from the mutable buffer are described by tombstones, which are
+//! recorded in the WAL like writes. Applying a tombstone removes the rows it
+//! matches from the chunks that hold them, so that no later read, nor any
+//! snapshot of those chunks, sees them.
+
+use data_types::data::{delete_to_replicated_write, ReplicatedWrite};
+use generated_types::wal as wb;
+use query::predicate::TimestampRange;
+
+/// Describes the rows deleted by a single delete: those in the time range, of
+/// the table if one is specified, whose tags have all of the specified values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tombstone {
+    /// The table the rows are deleted from, or all tables if `None`
+    pub table_name: Option<String>,
+
+    /// The range of times of the deleted rows
+    pub range: TimestampRange,
+
+    /// `(tag, value)` pairs that the deleted rows must all have
+    pub tags: Vec<(String, String)>,
+}
+
+impl Tombstone {
+    pub fn new(range: TimestampRange) -> Self {
+        Self {
+            table_name: None,
+            range,
+            tags: vec![],
+        }
+    }
+
+    /// Restricts the delete to the rows of `table_name`
+    pub fn with_table(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = Some(table_name.into());
+        self
+    }
+
+    /// Restricts the delete to the rows with `value` for `tag`
+    pub fn with_tag(mut self, tag: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((tag.into(), value.into()));
+        self
+    }
+
+    /// Creates the replicated write that records this delete in the WAL
+    pub fn to_replicated_write(&self, writer: u32, sequence: u64) -> ReplicatedWrite {
+        delete_to_replicated_write(
+            writer,
+            sequence,
+            self.table_name.as_deref(),
+            self.range.start,
+            self.range.end,
+            &self.tags,
+        )
+    }
+
+    /// Decodes a delete recorded in the WAL. Returns None if one of its tags
+    /// has no name or isn't a tag value, as ignoring it would delete more
+    /// rows than were asked for.
+    pub fn from_fb(delete: &wb::WriteBufferDelete<'_>) -> Option<Self> {
+        let mut tombstone = Self::new(TimestampRange::new(delete.start_time(), delete.end_time()));
+        if let Some(table_name) = delete.table_name() {
+            tombstone = tombstone.with_table(table_name);
+        }
+
+        if let Some(tags) = delete.tags() {
+            for tag in tags {
+                let value = tag.value_as_tag_value()?.value()?;
+                tombstone = tombstone.with_tag(tag.column()?, value);
+            }
+        }
+
+        Some(tombstone)
+    }
+}
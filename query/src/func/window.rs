@@ -10,7 +10,9 @@ use arrow_deps::{
         datatypes::DataType,
     },
     datafusion::{
-        logical_plan::Expr, physical_plan::functions::ScalarFunctionImplementation, prelude::*,
+        logical_plan::Expr,
+        physical_plan::{functions::ScalarFunctionImplementation, udf::ScalarUDF},
+        prelude::*,
     },
 };
 
@@ -94,6 +96,57 @@ pub fn make_window_bound_expr(
     udf.call(vec![time_arg])
 }
 
+/// The name of the `time_bucket` SQL function
+pub const TIME_BUCKET_UDF_NAME: &str = "time_bucket";
+
+/// This is the implementation of the `time_bucket(time, every)` user defined
+/// function, which returns the start of the window of `every` nanoseconds that
+/// each time falls in. Windows are aligned to multiples of `every` since the
+/// epoch, so a time falls in the window starting at
+/// `floor(time / every) * every`.
+///
+/// The result is NULL if `every` isn't positive, or if the window would start
+/// before `i64::MIN`.
+fn time_bucket(args: &[ArrayRef]) -> Result<ArrayRef> {
+    // this is guaranteed by DataFusion based on the function's signature.
+    assert_eq!(args.len(), 2);
+
+    let time = &args[0]
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .expect("cast of time failed");
+    let every = &args[1]
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .expect("cast of every failed");
+
+    let mut builder = Int64Builder::new(time.len());
+    time.iter()
+        .zip(every.iter())
+        .try_for_each(|(ts, every)| match (ts, every) {
+            (Some(ts), Some(every)) if every > 0 => match ts.div_euclid(every).checked_mul(every) {
+                Some(start) => builder.append_value(start),
+                None => builder.append_null(),
+            },
+            _ => builder.append_null(),
+        })?;
+
+    Ok(Arc::new(builder.finish()))
+}
+
+/// Create the `time_bucket` user defined function, to be registered with the
+/// contexts that SQL queries are planned in
+pub fn make_time_bucket_udf() -> ScalarUDF {
+    let func_ptr: ScalarFunctionImplementation = Arc::new(time_bucket);
+
+    create_udf(
+        TIME_BUCKET_UDF_NAME,
+        vec![DataType::Int64, DataType::Int64], // argument types
+        Arc::new(DataType::Int64),              // return type
+        func_ptr,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +181,41 @@ mod tests {
             expected_array, bounds_array,
         );
     }
+
+    #[test]
+    fn test_time_bucket() {
+        let time: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(100),
+            None,
+            Some(-50),
+            Some(399),
+            Some(i64::MIN),
+            Some(100),
+        ]));
+        let every: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(200),
+            Some(200),
+            Some(200),
+            Some(200),
+            Some(200),
+            Some(0),
+        ]));
+
+        let buckets_array = time_bucket(&[time, every]).expect("time_bucket executed correctly");
+
+        let expected_array: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(0),
+            None,
+            Some(-200),
+            Some(200),
+            None,
+            None,
+        ]));
+
+        assert_eq!(
+            &expected_array, &buckets_array,
+            "Expected:\n{:?}\nActual:\n{:?}",
+            expected_array, buckets_array,
+        );
+    }
 }
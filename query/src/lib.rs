@@ -5,7 +5,10 @@
     clippy::use_self
 )]
 
-use arrow_deps::arrow::record_batch::RecordBatch;
+use arrow_deps::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    datafusion::physical_plan::SendableRecordBatchStream,
+};
 use async_trait::async_trait;
 use data_types::{data::ReplicatedWrite, partition_metadata::Table as TableStats};
 use exec::{FieldListPlan, SeriesSetPlans, StringSetPlan};
@@ -116,6 +119,15 @@ pub trait SQLDatabase: Debug + Send + Sync {
     /// result
     async fn query(&self, query: &str) -> Result<Vec<RecordBatch>, Self::Error>;
 
+    /// Execute the specified query and return a stream of the arrow
+    /// record batches with the result as they are produced, rather than
+    /// collecting them all in memory first
+    async fn query_stream(&self, query: &str) -> Result<SendableRecordBatchStream, Self::Error>;
+
+    /// Plan the specified query without executing it, and return the
+    /// schema of its results
+    async fn query_schema(&self, query: &str) -> Result<SchemaRef, Self::Error>;
+
     /// Fetch the specified table names and columns as Arrow
     /// RecordBatches. Columns are returned in the order specified.
     async fn table_to_arrow(
@@ -124,6 +136,11 @@ pub trait SQLDatabase: Debug + Send + Sync {
         columns: &[&str],
     ) -> Result<Vec<RecordBatch>, Self::Error>;
 
+    /// Return the schema of the specified table with all of its columns,
+    /// as returned by `table_to_arrow`, without converting any rows. None is
+    /// returned if the database has no data for the table.
+    async fn table_schema(&self, table_name: &str) -> Result<Option<SchemaRef>, Self::Error>;
+
     /// Return the partition keys for data in this DB
     async fn partition_keys(&self) -> Result<Vec<String>, Self::Error>;
 
@@ -170,6 +187,9 @@ pub trait DatabaseStore: Debug + Send + Sync {
     /// such database exists
     async fn db(&self, name: &str) -> Option<Arc<Self::Database>>;
 
+    /// The names of the databases in this store, in sorted order
+    async fn db_names_sorted(&self) -> Vec<String>;
+
     /// Retrieve the database specified by `name`, creating it if it
     /// doesn't exist.
     async fn db_or_create(&self, name: &str) -> Result<Arc<Self::Database>, Self::Error>;
@@ -1,7 +1,10 @@
 //! This module provides a reference implementaton of `query::DatabaseSource`
 //! and `query::Database` for use in testing.
 
-use arrow_deps::arrow::record_batch::RecordBatch;
+use arrow_deps::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    datafusion::physical_plan::SendableRecordBatchStream,
+};
 
 use crate::group_by::GroupByAndAggregate;
 use crate::{
@@ -416,6 +419,17 @@ impl SQLDatabase for TestDatabase {
         unimplemented!("query Not yet implemented");
     }
 
+    /// Execute the specified query and return a stream of arrow record
+    /// batches with the result
+    async fn query_stream(&self, _query: &str) -> Result<SendableRecordBatchStream, Self::Error> {
+        unimplemented!("query_stream Not yet implemented");
+    }
+
+    /// Plan the specified query and return the schema of its results
+    async fn query_schema(&self, _query: &str) -> Result<SchemaRef, Self::Error> {
+        unimplemented!("query_schema Not yet implemented");
+    }
+
     /// Return the partition keys for data in this DB
     async fn partition_keys(&self) -> Result<Vec<String>, Self::Error> {
         unimplemented!("partition_keys not yet implemented for test database");
@@ -438,6 +452,11 @@ impl SQLDatabase for TestDatabase {
     ) -> Result<Vec<RecordBatch>, Self::Error> {
         unimplemented!()
     }
+
+    /// Return the schema of the specified table
+    async fn table_schema(&self, _table_name: &str) -> Result<Option<SchemaRef>, Self::Error> {
+        unimplemented!()
+    }
 }
 
 #[derive(Debug)]
@@ -507,6 +526,12 @@ impl DatabaseStore for TestDatabaseStore {
         databases.get(name).cloned()
     }
 
+    async fn db_names_sorted(&self) -> Vec<String> {
+        let databases = self.databases.lock().await;
+
+        databases.keys().cloned().collect()
+    }
+
     /// Retrieve the database specified by name, creating it if it
     /// doesn't exist.
     async fn db_or_create(&self, name: &str) -> Result<Arc<Self::Database>, Self::Error> {
@@ -19,6 +19,7 @@ fn read_group(c: &mut Criterion) {
     let row_group = generate_row_group(500_000, &mut rng);
     read_group_predicate_all_time(c, &row_group, &mut rng);
     read_group_pre_computed_groups(c, &row_group, &mut rng);
+    read_group_multiple_aggregates(c, &row_group);
 }
 
 // These benchmarks track the performance of read_group using the general
@@ -126,6 +127,40 @@ fn read_group_pre_computed_groups(c: &mut Criterion, row_group: &RowGroup, rng:
     );
 }
 
+// These benchmarks track the performance of read_group when several aggregates
+// are calculated on the same column, which is read once for all of them. Both
+// the general and pre-computed group paths are measured.
+fn read_group_multiple_aggregates(c: &mut Criterion, row_group: &RowGroup) {
+    let mut group = c.benchmark_group("row_group_read_group_multiple_aggregates");
+    let time_pred = build_predicates_with_time(i64::MIN, i64::MAX, vec![]);
+    let aggregates = vec![
+        ("duration", AggregateType::Min),
+        ("duration", AggregateType::Max),
+        ("duration", AggregateType::Sum),
+        ("duration", AggregateType::Count),
+    ];
+
+    let cases: Vec<(&str, &[Predicate<'_>])> =
+        vec![("all_time", &time_pred), ("pre_computed", &[])];
+
+    for (name, predicates) in cases {
+        for num_aggregates in 1..=aggregates.len() {
+            group.throughput(Throughput::Elements(num_aggregates as u64));
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("aggregates_{:?}", num_aggregates)),
+                &aggregates[..num_aggregates],
+                |b, aggregates| {
+                    b.iter(|| {
+                        let result = row_group.read_group(predicates, &["cluster"], aggregates);
+                        assert_eq!(result.cardinality(), 200, "{}", &result);
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
 // This benchmarks the impact that the cardinality of group keys has on the
 // performance of read_group.
 fn benchmark_read_group_vary_cardinality(
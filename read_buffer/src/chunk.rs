@@ -1,8 +1,12 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use crate::column::AggregateType;
-use crate::row_group::{ColumnName, Predicate};
+use arrow_deps::arrow::record_batch::RecordBatch;
+use snafu::OptionExt;
+
+use crate::column::{AggregateType, LogicalDataType};
+use crate::row_group::{ColumnName, Predicate, PredicateExpr, ValuePredicate};
 use crate::table::{ReadFilterResults, ReadGroupResults, Table};
+use crate::{ColumnType, EmptyChunk, Result, TimeRange};
 
 type TableName = String;
 
@@ -31,19 +35,217 @@ impl Chunk {
         p
     }
 
+    /// Creates a chunk from a record batch for each of its tables. Each
+    /// table's rows are split into row groups of at most `max_row_group_rows`
+    /// rows, so that they can be pruned and scanned independently.
+    ///
+    /// An error is returned if `tables` is empty or any of the tables can't
+    /// be built. See `Table::from_record_batch`.
+    pub fn from_record_batches(
+        id: u32,
+        tables: BTreeMap<String, RecordBatch>,
+        max_row_group_rows: usize,
+    ) -> Result<Self> {
+        let mut tables = tables
+            .into_iter()
+            .map(|(name, rb)| Table::from_record_batch(name, &rb, max_row_group_rows));
+
+        let mut chunk = Self::new(id, tables.next().context(EmptyChunk { chunk_id: id })??);
+        for table in tables {
+            chunk.add_table(table?);
+        }
+        Ok(chunk)
+    }
+
+    /// Adds a table to the chunk. Panics if the chunk already contains a
+    /// table with the same name.
+    pub fn add_table(&mut self, table: Table) {
+        assert!(
+            !self.tables.contains_key(table.name()),
+            "chunk already contains table {}",
+            table.name()
+        );
+        self.meta.add_table(&table);
+        self.tables.insert(table.name().to_owned(), table);
+    }
+
+    /// Consumes the chunk, returning its tables in order of table name.
+    pub fn into_tables(self) -> impl Iterator<Item = Table> {
+        self.tables.into_iter().map(|(_, table)| table)
+    }
+
+    /// The unique identifier for this chunk.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The total size in bytes of the chunk.
+    pub fn size(&self) -> u64 {
+        self.meta.size
+    }
+
+    /// The total size in bytes of each column in the chunk, keyed by table
+    /// and column name. The sizes include the dictionaries of encoded
+    /// columns, and sum to the size of the chunk.
+    pub fn column_sizes(&self) -> BTreeMap<(String, String), u64> {
+        let mut sizes = BTreeMap::new();
+        for (table_name, table) in &self.tables {
+            for (column_name, size) in table.column_sizes() {
+                sizes.insert((table_name.clone(), column_name.to_owned()), size);
+            }
+        }
+        sizes
+    }
+
+    /// The total number of rows across all tables in the chunk.
+    pub fn rows(&self) -> u64 {
+        self.meta.rows
+    }
+
+    /// The inclusive range of timestamps across all tables in the chunk, or
+    /// `None` if none of the tables contain any timestamps.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.meta.time_range
+    }
+
+    /// Determines if the chunk could contain data within the time range.
+    pub fn overlaps_time_range(&self, time_range: TimeRange) -> bool {
+        match self.meta.time_range {
+            Some((min, max)) => time_range.overlaps(min, max),
+            None => false,
+        }
+    }
+
+    /// The inclusive range of timestamps in the specified table, or `None` if
+    /// the chunk doesn't contain the table or the table has no timestamps.
+    pub fn table_time_range(&self, table_name: &str) -> Option<(i64, i64)> {
+        self.tables.get(table_name)?.time_range()
+    }
+
+    /// Determines if the chunk contains the specified table.
+    pub fn contains_table(&self, table_name: &str) -> bool {
+        self.tables.contains_key(table_name)
+    }
+
+    /// Determines if the specified table could contain rows satisfying the
+    /// predicate expression, based only on the range of values in each of its
+    /// columns. This allows the chunk to be skipped without reading any rows.
+    pub fn could_satisfy_predicate_expr(&self, table_name: &str, expr: &PredicateExpr<'_>) -> bool {
+        self.tables
+            .get(table_name)
+            .map_or(false, |table| table.could_satisfy_predicate_expr(expr))
+    }
+
+    /// The logical data type of the column in the specified table, if both
+    /// exist in the chunk.
+    pub fn column_logical_datatype(
+        &self,
+        table_name: &str,
+        column_name: ColumnName<'_>,
+    ) -> Option<LogicalDataType> {
+        self.tables
+            .get(table_name)?
+            .column_logical_datatype(column_name)
+    }
+
+    /// The names of the tag columns in the specified table, if it exists in
+    /// the chunk.
+    pub fn tag_column_names(&self, table_name: &str) -> Option<Vec<ColumnName<'_>>> {
+        Some(self.tables.get(table_name)?.tag_column_names())
+    }
+
+    /// The distinct non-null values of the named tag column in each of the
+    /// specified table's segments. Empty if the chunk doesn't contain the
+    /// table or column.
+    pub fn tag_column_dictionaries(
+        &self,
+        table_name: &str,
+        column_name: ColumnName<'_>,
+    ) -> Vec<Vec<&String>> {
+        self.tables
+            .get(table_name)
+            .map_or_else(Vec::new, |table| table.tag_column_dictionaries(column_name))
+    }
+
+    /// The distinct non-null values of each of the named tag columns in the
+    /// rows of the specified table within the time range satisfying the
+    /// predicate expression. Empty if the chunk doesn't contain the table.
+    pub fn tag_column_values<'a>(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        tag_keys: &[ColumnName<'a>],
+    ) -> BTreeMap<ColumnName<'a>, BTreeSet<&String>> {
+        self.tables
+            .get(table_name)
+            .map_or_else(BTreeMap::new, |table| {
+                table.tag_column_values(time_range, expr, tag_keys)
+            })
+    }
+
+    /// The name and type of each column in the specified table, if it exists
+    /// in the chunk.
+    pub fn column_types(&self, table_name: &str) -> Option<Vec<(ColumnName<'_>, ColumnType)>> {
+        Some(self.tables.get(table_name)?.column_types())
+    }
+
     /// Returns data for the specified column selections on the specified table
-    /// name.
+    /// name, or `None` if the table does not exist in the chunk.
+    ///
+    /// Selected columns that do not exist in the table are not returned.
     ///
     /// Results may be filtered by conjunctive predicates. Time predicates
     /// should use as nanoseconds since the epoch.
-    pub fn select(
+    pub fn select<'input>(
         &self,
         table_name: &str,
         predicates: &[Predicate<'_>],
-        select_columns: &[ColumnName<'_>],
-    ) -> ReadFilterResults<'_, '_> {
+        select_columns: &[ColumnName<'input>],
+    ) -> Option<ReadFilterResults<'input, '_>> {
+        self.select_expr(table_name, &PredicateExpr::from(predicates), select_columns)
+    }
+
+    /// The same as `select`, but results are filtered by a predicate
+    /// expression.
+    pub fn select_expr<'input>(
+        &self,
+        table_name: &str,
+        expr: &PredicateExpr<'_>,
+        select_columns: &[ColumnName<'input>],
+    ) -> Option<ReadFilterResults<'input, '_>> {
         // Lookup table by name and dispatch execution.
-        todo!();
+        let table = self.tables.get(table_name)?;
+
+        let columns = select_columns
+            .iter()
+            .filter(|&&name| table.has_column(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        Some(table.select_expr(&columns, expr))
+    }
+
+    /// The number of rows of the specified table within the time range
+    /// satisfying the predicate expression, or zero if the table does not
+    /// exist in the chunk.
+    pub fn row_count(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+    ) -> u64 {
+        self.tables
+            .get(table_name)
+            .map_or(0, |table| table.row_count(time_range, expr))
+    }
+
+    /// An upper bound on the number of rows of the specified table within the
+    /// time range, or zero if the table does not exist in the chunk. See
+    /// `Table::estimated_rows`.
+    pub fn estimated_rows(&self, table_name: &str, time_range: TimeRange) -> u64 {
+        self.tables
+            .get(table_name)
+            .map_or(0, |table| table.estimated_rows(time_range))
     }
 
     /// Returns aggregates segmented by grouping keys for the specified
@@ -53,34 +255,116 @@ impl Chunk {
     /// predicates.
     ///
     /// Group keys are determined according to the provided group column names.
-    /// Currently only grouping by string (tag key) columns is supported.
+    /// String (tag key), integer, unsigned integer and boolean columns can be
+    /// grouped on.
     ///
     /// Required aggregates are specified via a tuple comprising a column name
     /// and the type of aggregation required. Multiple aggregations can be
-    /// applied to the same column.
-    pub fn aggregate(
+    /// applied to the same column. Each aggregate may have an optional value
+    /// predicate, which is applied to the aggregated value of each group.
+    ///
+    /// `None` is returned if the table does not exist in the chunk, or if it
+    /// does not contain all of the predicate columns. Missing group and
+    /// aggregate columns are handled as in `aggregate_expr`.
+    pub fn aggregate<'input>(
         &self,
         table_name: &str,
         predicates: &[Predicate<'_>],
-        group_columns: Vec<ColumnName<'_>>,
-        aggregates: Vec<(ColumnName<'_>, AggregateType)>,
-    ) -> ReadGroupResults<'_, '_> {
+        group_columns: &'input [ColumnName<'input>],
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+    ) -> Option<ReadGroupResults<'input, '_>> {
+        let table = self.tables.get(table_name)?;
+        let columns = predicates.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        if !table.has_all_columns(&columns) {
+            return None;
+        }
+
+        self.aggregate_expr(
+            table_name,
+            &PredicateExpr::from(predicates),
+            group_columns,
+            aggregates,
+            value_predicates,
+        )
+    }
+
+    /// The same as `aggregate`, but the data to be aggregated is filtered by
+    /// a predicate expression.
+    ///
+    /// `None` is returned if the table does not exist in the chunk.
+    /// Predicates on columns missing from the table match no rows. Group
+    /// columns missing from the table are NULL for all of its rows, and
+    /// aggregates of columns missing from the table are aggregates of no
+    /// values.
+    pub fn aggregate_expr<'input>(
+        &self,
+        table_name: &str,
+        expr: &PredicateExpr<'_>,
+        group_columns: &'input [ColumnName<'input>],
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+    ) -> Option<ReadGroupResults<'input, '_>> {
         // Lookup table by name and dispatch execution.
-        todo!()
+        let table = self.tables.get(table_name)?;
+        Some(table.aggregate_expr(expr, group_columns, aggregates, value_predicates))
+    }
+
+    /// The same as `aggregate_expr`, but rows are also grouped by the time
+    /// bucket of length `bucket` nanoseconds that they fall in. The start of
+    /// each row's bucket is the last value of its group key.
+    ///
+    /// `None` is returned if the table does not exist in the chunk.
+    pub fn aggregate_window_expr<'input>(
+        &self,
+        table_name: &str,
+        expr: &PredicateExpr<'_>,
+        group_columns: &'input [ColumnName<'input>],
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        bucket: i64,
+    ) -> Option<ReadGroupResults<'input, '_>> {
+        let table = self.tables.get(table_name)?;
+        Some(table.aggregate_window_expr(expr, group_columns, aggregates, &[], bucket))
     }
 
     //
     // ---- Schema API queries
     //
 
-    /// Returns the distinct set of table names that contain data that satisfies
-    /// the time range and predicates.
-    pub fn table_names(&self, predicates: &[Predicate<'_>]) -> BTreeSet<String> {
-        //
-        // TODO(edd): do we want to add the ability to apply a predicate to the
-        // table names? For example, a regex where you only want table names
-        // beginning with /cpu.+/ or something?
-        todo!()
+    /// Returns the distinct set of table names that contain at least one row
+    /// satisfying the predicate expression, which should include any time
+    /// range predicates.
+    ///
+    /// Tables in `skip_table_names` are not probed or returned, which allows
+    /// callers to avoid re-checking tables already found in other chunks.
+    pub fn table_names(
+        &self,
+        expr: &PredicateExpr<'_>,
+        skip_table_names: &BTreeSet<String>,
+    ) -> BTreeSet<String> {
+        self.tables
+            .iter()
+            .filter(|(name, _)| !skip_table_names.contains(name.as_str()))
+            .filter(|(_, table)| table.satisfies_predicate_expr(expr))
+            .map(|(name, _)| name.to_owned())
+            .collect()
+    }
+
+    /// Returns the distinct set of table names for tables containing all of
+    /// the provided columns, without checking any rows.
+    ///
+    /// Tables in `skip_table_names` are not returned.
+    pub fn table_names_with_columns(
+        &self,
+        columns: &[ColumnName<'_>],
+        skip_table_names: &BTreeSet<String>,
+    ) -> BTreeSet<String> {
+        self.tables
+            .iter()
+            .filter(|(name, _)| !skip_table_names.contains(name.as_str()))
+            .filter(|(_, table)| table.has_all_columns(columns))
+            .map(|(name, _)| name.to_owned())
+            .collect()
     }
 
     /// Returns the distinct set of tag keys (column names) matching the
@@ -144,8 +428,15 @@ impl MetaData {
     }
 
     pub fn add_table(&mut self, table: &Table) {
-        // Update size, rows, time_range
-        todo!()
+        self.size += table.size();
+        self.rows += table.rows();
+
+        if let Some((table_min, table_max)) = table.time_range() {
+            self.time_range = Some(match self.time_range {
+                Some((min, max)) => (min.min(table_min), max.max(table_max)),
+                None => (table_min, table_max),
+            });
+        }
     }
 
     // invalidate should be called when a table is removed. All meta data must
@@ -1,3 +1,4 @@
+pub mod boolean;
 pub mod cmp;
 pub mod dictionary;
 pub mod fixed;
@@ -11,6 +12,8 @@ use croaring::Bitmap;
 use arrow_deps::{arrow, arrow::array::Array};
 use either::Either;
 
+use crate::tdigest::TDigest;
+
 // Edd's totally made up magic constant. This determines whether we would use
 // a run-length encoded dictionary encoding or just a plain dictionary encoding.
 // I have ideas about how to build heuristics to do this in a much better way
@@ -43,8 +46,10 @@ pub enum Column {
     // type probably needs some thought.
     Unsigned(MetaData<u64>, IntegerEncoding), // TODO - 64-bit unsigned integers
 
+    // A column of nullable booleans.
+    Bool(MetaData<bool>, boolean::Bool),
+
     // These are TODO
-    Bool,                                         // TODO - booleans
     ByteArray(MetaData<Vec<u8>>, StringEncoding), // TODO - arbitrary bytes
 }
 
@@ -58,13 +63,33 @@ impl Column {
             Column::Float(meta, _) => meta.rows,
             Column::Integer(meta, _) => meta.rows,
             Column::Unsigned(meta, _) => meta.rows,
-            Column::Bool => todo!(),
+            Column::Bool(meta, _) => meta.rows,
             Column::ByteArray(meta, _) => meta.rows,
         }
     }
 
+    /// An estimation of the total size of the column in bytes.
     pub fn size(&self) -> u64 {
-        0
+        match &self {
+            Column::String(_, data) => data.size(),
+            Column::Float(_, data) => data.size(),
+            Column::Integer(_, data) => data.size(),
+            Column::Unsigned(_, data) => data.size(),
+            Column::Bool(_, data) => data.size(),
+            Column::ByteArray(_, data) => data.size(),
+        }
+    }
+
+    /// Determines if the column contains a NULL value.
+    pub fn contains_null(&self) -> bool {
+        match &self {
+            Column::String(_, data) => data.contains_null(),
+            Column::Float(_, data) => data.contains_null(),
+            Column::Integer(_, data) => data.contains_null(),
+            Column::Unsigned(_, data) => data.contains_null(),
+            Column::Bool(_, data) => data.contains_null(),
+            Column::ByteArray(_, data) => data.contains_null(),
+        }
     }
 
     /// Returns the (min, max)  values stored in this column
@@ -98,18 +123,33 @@ impl Column {
                 )),
                 None => None,
             },
-            Column::Bool => todo!(),
+            Column::Bool(meta, _) => match meta.range {
+                Some(range) => Some((OwnedValue::Boolean(range.0), OwnedValue::Boolean(range.1))),
+                None => None,
+            },
             Column::ByteArray(_, _) => todo!(),
         }
     }
 
+    /// The logical data type of the values in the column.
+    pub fn logical_datatype(&self) -> LogicalDataType {
+        match &self {
+            Column::String(_, _) => LogicalDataType::String,
+            Column::Float(_, _) => LogicalDataType::Float,
+            Column::Integer(_, _) => LogicalDataType::Integer,
+            Column::Unsigned(_, _) => LogicalDataType::Unsigned,
+            Column::Bool(_, _) => LogicalDataType::Boolean,
+            Column::ByteArray(_, _) => LogicalDataType::Binary,
+        }
+    }
+
     pub fn properties(&self) -> &ColumnProperties {
         match &self {
             Column::String(meta, _) => &meta.properties,
             Column::Float(meta, _) => &meta.properties,
             Column::Integer(meta, _) => &meta.properties,
             Column::Unsigned(meta, _) => &meta.properties,
-            Column::Bool => todo!(),
+            Column::Bool(meta, _) => &meta.properties,
             Column::ByteArray(meta, _) => &meta.properties,
         }
     }
@@ -142,7 +182,10 @@ impl Column {
             Column::Float(_, data) => data.value(row_id),
             Column::Integer(_, data) => data.value(row_id),
             Column::Unsigned(_, data) => data.value(row_id),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => match data.value(row_id) {
+                Some(v) => Value::Boolean(v),
+                None => Value::Null,
+            },
             Column::ByteArray(_, _) => todo!(),
         }
     }
@@ -163,7 +206,7 @@ impl Column {
             Column::Float(_, data) => data.values(row_ids),
             Column::Integer(_, data) => data.values(row_ids),
             Column::Unsigned(_, data) => data.values(row_ids),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => Values::Bool(data.values(row_ids, vec![])),
             Column::ByteArray(_, _) => todo!(),
         }
     }
@@ -175,7 +218,7 @@ impl Column {
             Column::Float(_, data) => data.all_values(),
             Column::Integer(_, data) => data.all_values(),
             Column::Unsigned(_, data) => data.all_values(),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => Values::Bool(data.all_values(vec![])),
             Column::ByteArray(_, _) => todo!(),
         }
     }
@@ -189,6 +232,15 @@ impl Column {
         }
     }
 
+    /// The distinct non-null values in a string column, in sorted order, or
+    /// `None` if the column doesn't contain strings.
+    pub fn dictionary(&self) -> Option<Vec<&String>> {
+        match &self {
+            Column::String(_, data) => Some(data.dictionary()),
+            _ => None,
+        }
+    }
+
     // The distinct set of values found at the logical row ids.
     pub fn distinct_values(&self, row_ids: &[u32]) -> ValueSet<'_> {
         assert!(
@@ -278,11 +330,12 @@ impl Column {
 
         // Check the column for all rows that satisfy the predicate.
         let row_ids = match &self {
+            _ if op.is_null_check() => self.row_ids_null_filter(op, dst),
             Column::String(_, data) => data.row_ids_filter(op, value.string(), dst),
             Column::Float(_, data) => data.row_ids_filter(op, value.scalar(), dst),
             Column::Integer(_, data) => data.row_ids_filter(op, value.scalar(), dst),
             Column::Unsigned(_, data) => data.row_ids_filter(op, value.scalar(), dst),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => data.row_ids_filter(value.boolean(), op, dst),
             Column::ByteArray(_, data) => todo!(),
         };
 
@@ -292,6 +345,24 @@ impl Column {
         RowIDsOption::Some(row_ids)
     }
 
+    // Determines the set of row ids whose values are NULL (`IS NULL`) or are
+    // not NULL (`IS NOT NULL`).
+    fn row_ids_null_filter(&self, op: &cmp::Operator, mut dst: RowIDs) -> RowIDs {
+        let is_null = matches!(op, cmp::Operator::IsNull);
+        if let Column::String(_, data) = &self {
+            return data.row_ids_null(is_null, dst);
+        }
+
+        dst.clear();
+        let values = self.all_values();
+        for row in 0..values.len() {
+            if values.value(row).is_null() == is_null {
+                dst.add(row as u32);
+            }
+        }
+        dst
+    }
+
     /// Determine the set of row ids that satisfy both of the predicates.
     ///
     /// Note: this method is a special case for common range-based predicates
@@ -330,18 +401,21 @@ impl Column {
         // TODO(edd): figure out pooling of these
         let dst = RowIDs::Bitmap(Bitmap::create());
 
-        let low_scalar = (&low.0, low.1.scalar());
-        let high_scalar = (&high.0, high.1.scalar());
-
         // Check the column for all rows that satisfy the predicate.
         let row_ids = match &self {
             Column::String(_, data) => unimplemented!("not supported on string columns yet"),
-            Column::Float(_, data) => data.row_ids_filter_range(low_scalar, high_scalar, dst),
-            Column::Integer(_, data) => data.row_ids_filter_range(low_scalar, high_scalar, dst),
+            Column::Float(_, data) => {
+                data.row_ids_filter_range((&low.0, low.1.scalar()), (&high.0, high.1.scalar()), dst)
+            }
+            Column::Integer(_, data) => {
+                data.row_ids_filter_range((&low.0, low.1.scalar()), (&high.0, high.1.scalar()), dst)
+            }
             Column::Unsigned(_, data) => {
                 data.row_ids_filter_range((&low.0, low.1.scalar()), (&high.0, high.1.scalar()), dst)
             }
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => {
+                data.row_ids_filter_range((low.1.boolean(), low.0), (high.1.boolean(), high.0), dst)
+            }
             Column::ByteArray(_, data) => todo!(),
         };
 
@@ -379,11 +453,30 @@ impl Column {
             // When the predicate is != and the metadata range indicates that the
             // column can't possibly contain `value` then the predicate must
             // match all rows on the column.
+            // NULL values never satisfy `!=`, so this only holds if the column
+            // doesn't contain any.
             cmp::Operator::NotEqual => {
-                if !self.might_contain_value(&value) {
+                if !self.might_contain_value(&value) && !self.contains_null() {
                     return PredicateMatch::All; // all rows are going to match.
                 }
             }
+
+            // The range of values in the column says nothing about whether they
+            // match a regular expression.
+            cmp::Operator::RegexMatch
+            | cmp::Operator::RegexNotMatch
+            | cmp::Operator::RegexMatchAny => {}
+
+            // A column without NULL values matches every row of `IS NOT NULL`
+            // and none of `IS NULL`. Otherwise the column has to be read, and
+            // the value of the predicate, which is NULL, is never compared.
+            cmp::Operator::IsNull | cmp::Operator::IsNotNull => {
+                return match (self.contains_null(), op) {
+                    (false, cmp::Operator::IsNull) => PredicateMatch::None,
+                    (false, _) => PredicateMatch::All,
+                    (true, _) => PredicateMatch::SomeMaybe,
+                };
+            }
         }
 
         if self.predicate_matches_no_values(&op, &value) {
@@ -424,7 +517,13 @@ impl Column {
                 .scalar()
                 .try_as_u64()
                 .map_or_else(|| false, |v| meta.might_contain_value(v)),
-            Column::Bool => todo!(),
+            Column::Bool(meta, _) => {
+                if let Value::Boolean(other) = value {
+                    meta.might_contain_value(*other)
+                } else {
+                    unreachable!("impossible value comparison");
+                }
+            }
             Column::ByteArray(meta, _) => todo!(),
         }
     }
@@ -481,7 +580,17 @@ impl Column {
                     .try_as_u64()
                     .map_or_else(|| false, |v| meta.might_match_all_values(op, v))
             }
-            Column::Bool => todo!(),
+            Column::Bool(meta, data) => {
+                if data.contains_null() {
+                    return false;
+                }
+
+                if let Value::Boolean(other) = value {
+                    meta.might_match_all_values(op, *other)
+                } else {
+                    unreachable!("impossible value comparison");
+                }
+            }
             Column::ByteArray(meta, _) => todo!(),
         }
     }
@@ -505,7 +614,13 @@ impl Column {
             Column::Float(meta, data) => meta.match_no_values(op, value.scalar().as_f64()),
             Column::Integer(meta, data) => meta.match_no_values(op, value.scalar().as_i64()),
             Column::Unsigned(meta, data) => meta.match_no_values(op, value.scalar().as_u64()),
-            Column::Bool => todo!(),
+            Column::Bool(meta, _) => {
+                if let Value::Boolean(other) = value {
+                    meta.match_no_values(op, *other)
+                } else {
+                    unreachable!("impossible value comparison");
+                }
+            }
             Column::ByteArray(meta, _) => todo!(),
         }
     }
@@ -523,7 +638,10 @@ impl Column {
             Column::Float(_, data) => data.min(row_ids),
             Column::Integer(_, data) => data.min(row_ids),
             Column::Unsigned(_, data) => data.min(row_ids),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => match data.min(row_ids) {
+                Some(v) => Value::Boolean(v),
+                None => Value::Null,
+            },
             Column::ByteArray(_, _) => todo!(),
         }
     }
@@ -537,7 +655,10 @@ impl Column {
             Column::Float(_, data) => data.max(row_ids),
             Column::Integer(_, data) => data.max(row_ids),
             Column::Unsigned(_, data) => data.max(row_ids),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => match data.max(row_ids) {
+                Some(v) => Value::Boolean(v),
+                None => Value::Null,
+            },
             Column::ByteArray(_, _) => todo!(),
         }
     }
@@ -567,7 +688,7 @@ impl Column {
             Column::Float(_, data) => data.count(row_ids),
             Column::Integer(_, data) => data.count(row_ids),
             Column::Unsigned(_, data) => data.count(row_ids),
-            Column::Bool => todo!(),
+            Column::Bool(_, data) => data.count(row_ids),
             Column::ByteArray(_, _) => todo!(),
         }
     }
@@ -646,6 +767,12 @@ impl<T: PartialOrd + std::fmt::Debug> MetaData<T> {
                 cmp::Operator::LT => range.1 < u,
                 // all values in column <= v
                 cmp::Operator::LTE => range.1 <= u,
+                // can't be determined from the range of values
+                cmp::Operator::RegexMatch
+                | cmp::Operator::RegexNotMatch
+                | cmp::Operator::RegexMatchAny
+                | cmp::Operator::IsNull
+                | cmp::Operator::IsNotNull => false,
             },
             None => false, // only null values in column.
         }
@@ -672,6 +799,12 @@ impl<T: PartialOrd + std::fmt::Debug> MetaData<T> {
                 cmp::Operator::LT => range.0 >= u,
                 // min value in column is `> v` so no values can be `<= v`
                 cmp::Operator::LTE => range.0 > u,
+                // can't be determined from the range of values
+                cmp::Operator::RegexMatch
+                | cmp::Operator::RegexNotMatch
+                | cmp::Operator::RegexMatchAny
+                | cmp::Operator::IsNull
+                | cmp::Operator::IsNotNull => false,
             },
             None => true, // only null values in column so no values satisfy `v`
         }
@@ -686,6 +819,14 @@ pub enum StringEncoding {
 /// This implementation is concerned with how to produce string columns with
 /// different encodings.
 impl StringEncoding {
+    /// An estimation of the size of the encoded column in bytes.
+    pub fn size(&self) -> u64 {
+        match &self {
+            Self::RLEDictionary(c) => c.size(),
+            Self::Dictionary(c) => c.size(),
+        }
+    }
+
     /// Determines if the column contains a NULL value.
     pub fn contains_null(&self) -> bool {
         match &self {
@@ -694,6 +835,14 @@ impl StringEncoding {
         }
     }
 
+    /// The distinct non-null values in the column, in sorted order.
+    pub fn dictionary(&self) -> Vec<&String> {
+        match &self {
+            Self::RLEDictionary(c) => c.dictionary(),
+            Self::Dictionary(c) => c.dictionary(),
+        }
+    }
+
     /// Returns the logical value found at the provided row id.
     pub fn value(&self, row_id: u32) -> Value<'_> {
         match &self {
@@ -760,6 +909,17 @@ impl StringEncoding {
         }
     }
 
+    /// Returns the row ids whose values are NULL if `is_null` is true, or
+    /// are not NULL otherwise.
+    pub fn row_ids_null(&self, is_null: bool, dst: RowIDs) -> RowIDs {
+        match (&self, is_null) {
+            (Self::RLEDictionary(c), true) => c.row_ids_null(dst),
+            (Self::RLEDictionary(c), false) => c.row_ids_not_null(dst),
+            (Self::Dictionary(c), true) => c.row_ids_null(dst),
+            (Self::Dictionary(c), false) => c.row_ids_not_null(dst),
+        }
+    }
+
     /// The lexicographic minimum non-null value at the rows specified, or the
     /// NULL value if the column only contains NULL values at the provided row
     /// ids.
@@ -1054,15 +1214,36 @@ pub enum IntegerEncoding {
 
     // Nullable encodings - TODO
     I64I64N(fixed_null::FixedNull<arrow::datatypes::Int64Type>),
+    U64U64N(fixed_null::FixedNull<arrow::datatypes::UInt64Type>),
 }
 
 impl IntegerEncoding {
+    /// An estimation of the size of the encoded column in bytes.
+    pub fn size(&self) -> u64 {
+        match &self {
+            Self::I64I64(c) => c.size(),
+            Self::I64I32(c) => c.size(),
+            Self::I64U32(c) => c.size(),
+            Self::I64I16(c) => c.size(),
+            Self::I64U16(c) => c.size(),
+            Self::I64I8(c) => c.size(),
+            Self::I64U8(c) => c.size(),
+            Self::U64U64(c) => c.size(),
+            Self::U64U32(c) => c.size(),
+            Self::U64U16(c) => c.size(),
+            Self::U64U8(c) => c.size(),
+            Self::I64I64N(c) => c.size(),
+            Self::U64U64N(c) => c.size(),
+        }
+    }
+
     /// Determines if the column contains a NULL value.
     pub fn contains_null(&self) -> bool {
-        if let Self::I64I64N(c) = &self {
-            return c.contains_null();
+        match &self {
+            Self::I64I64N(c) => c.contains_null(),
+            Self::U64U64N(c) => c.contains_null(),
+            _ => false,
         }
-        false
     }
 
     /// Returns the logical value found at the provided row id.
@@ -1090,6 +1271,10 @@ impl IntegerEncoding {
                 Some(v) => Value::Scalar(Scalar::I64(v)),
                 None => Value::Null,
             },
+            Self::U64U64N(c) => match c.value(row_id) {
+                Some(v) => Value::Scalar(Scalar::U64(v)),
+                None => Value::Null,
+            },
         }
     }
 
@@ -1115,6 +1300,7 @@ impl IntegerEncoding {
             Self::U64U8(c) => Values::U64(c.values::<u64>(row_ids, vec![])),
 
             Self::I64I64N(c) => Values::I64N(c.values(row_ids, vec![])),
+            Self::U64U64N(c) => Values::U64N(c.values(row_ids, vec![])),
         }
     }
 
@@ -1140,6 +1326,7 @@ impl IntegerEncoding {
             Self::U64U8(c) => Values::U64(c.all_values::<u64>(vec![])),
 
             Self::I64I64N(c) => Values::I64N(c.all_values(vec![])),
+            Self::U64U64N(c) => Values::U64N(c.all_values(vec![])),
         }
     }
 
@@ -1206,6 +1393,7 @@ impl IntegerEncoding {
             Self::U64U8(c) => c.row_ids_filter(value.as_u8(), op, dst),
 
             Self::I64I64N(c) => c.row_ids_filter(value.as_i64(), op, dst),
+            Self::U64U64N(c) => c.row_ids_filter(value.as_u64(), op, dst),
         }
     }
 
@@ -1256,7 +1444,12 @@ impl IntegerEncoding {
                 c.row_ids_filter_range((low.1.as_u8(), low.0), (high.1.as_u8(), high.0), dst)
             }
 
-            Self::I64I64N(c) => todo!(),
+            Self::I64I64N(c) => {
+                c.row_ids_filter_range((low.1.as_i64(), *low.0), (high.1.as_i64(), *high.0), dst)
+            }
+            Self::U64U64N(c) => {
+                c.row_ids_filter_range((low.1.as_u64(), *low.0), (high.1.as_u64(), *high.0), dst)
+            }
         }
     }
 
@@ -1277,6 +1470,10 @@ impl IntegerEncoding {
                 Some(v) => Value::Scalar(Scalar::I64(v)),
                 None => Value::Null,
             },
+            IntegerEncoding::U64U64N(c) => match c.min(row_ids) {
+                Some(v) => Value::Scalar(Scalar::U64(v)),
+                None => Value::Null,
+            },
         }
     }
 
@@ -1297,6 +1494,10 @@ impl IntegerEncoding {
                 Some(v) => Value::Scalar(Scalar::I64(v)),
                 None => Value::Null,
             },
+            IntegerEncoding::U64U64N(c) => match c.max(row_ids) {
+                Some(v) => Value::Scalar(Scalar::U64(v)),
+                None => Value::Null,
+            },
         }
     }
 
@@ -1317,6 +1518,10 @@ impl IntegerEncoding {
                 Some(v) => Scalar::I64(v),
                 None => Scalar::Null,
             },
+            IntegerEncoding::U64U64N(c) => match c.sum(row_ids) {
+                Some(v) => Scalar::U64(v),
+                None => Scalar::Null,
+            },
         }
     }
 
@@ -1334,26 +1539,43 @@ impl IntegerEncoding {
             IntegerEncoding::U64U16(c) => c.count(row_ids),
             IntegerEncoding::U64U8(c) => c.count(row_ids),
             IntegerEncoding::I64I64N(c) => c.count(row_ids),
+            IntegerEncoding::U64U64N(c) => c.count(row_ids),
         }
     }
 }
 
 pub enum FloatEncoding {
-    Fixed64(fixed::Fixed<f64>), // TODO(edd): encodings for nullable columns
+    Fixed64(fixed::Fixed<f64>),
+
+    // Nullable encodings
+    Fixed64N(fixed_null::FixedNull<arrow::datatypes::Float64Type>),
 }
 
 impl FloatEncoding {
+    /// An estimation of the size of the encoded column in bytes.
+    pub fn size(&self) -> u64 {
+        match &self {
+            Self::Fixed64(c) => c.size(),
+            Self::Fixed64N(c) => c.size(),
+        }
+    }
+
     /// Determines if the column contains a NULL value.
     pub fn contains_null(&self) -> bool {
-        // TODO(edd): when adding the nullable columns then ask the nullable
-        // encoding if it has any null values.
-        false
+        match &self {
+            Self::Fixed64(_) => false,
+            Self::Fixed64N(c) => c.contains_null(),
+        }
     }
 
     /// Returns the logical value found at the provided row id.
     pub fn value(&self, row_id: u32) -> Value<'_> {
         match &self {
             Self::Fixed64(c) => Value::Scalar(Scalar::F64(c.value(row_id))),
+            Self::Fixed64N(c) => match c.value(row_id) {
+                Some(v) => Value::Scalar(Scalar::F64(v)),
+                None => Value::Null,
+            },
         }
     }
 
@@ -1363,6 +1585,7 @@ impl FloatEncoding {
     pub fn values(&self, row_ids: &[u32]) -> Values<'_> {
         match &self {
             Self::Fixed64(c) => Values::F64(c.values::<f64>(row_ids, vec![])),
+            Self::Fixed64N(c) => Values::F64N(c.values(row_ids, vec![])),
         }
     }
 
@@ -1372,6 +1595,7 @@ impl FloatEncoding {
     pub fn all_values(&self) -> Values<'_> {
         match &self {
             Self::Fixed64(c) => Values::F64(c.all_values::<f64>(vec![])),
+            Self::Fixed64N(c) => Values::F64N(c.all_values(vec![])),
         }
     }
 
@@ -1383,6 +1607,7 @@ impl FloatEncoding {
     pub fn row_ids_filter(&self, op: &cmp::Operator, value: &Scalar, dst: RowIDs) -> RowIDs {
         match &self {
             FloatEncoding::Fixed64(c) => c.row_ids_filter(value.as_f64(), op, dst),
+            FloatEncoding::Fixed64N(c) => c.row_ids_filter(value.as_f64(), op, dst),
         }
     }
 
@@ -1401,30 +1626,46 @@ impl FloatEncoding {
             FloatEncoding::Fixed64(c) => {
                 c.row_ids_filter_range((low.1.as_f64(), &low.0), (high.1.as_f64(), &high.0), dst)
             }
+            FloatEncoding::Fixed64N(c) => {
+                c.row_ids_filter_range((low.1.as_f64(), *low.0), (high.1.as_f64(), *high.0), dst)
+            }
         }
     }
 
     pub fn min(&self, row_ids: &[u32]) -> Value<'_> {
         match &self {
             FloatEncoding::Fixed64(c) => Value::Scalar(Scalar::F64(c.min(row_ids))),
+            FloatEncoding::Fixed64N(c) => match c.min(row_ids) {
+                Some(v) => Value::Scalar(Scalar::F64(v)),
+                None => Value::Null,
+            },
         }
     }
 
     pub fn max(&self, row_ids: &[u32]) -> Value<'_> {
         match &self {
             FloatEncoding::Fixed64(c) => Value::Scalar(Scalar::F64(c.max(row_ids))),
+            FloatEncoding::Fixed64N(c) => match c.max(row_ids) {
+                Some(v) => Value::Scalar(Scalar::F64(v)),
+                None => Value::Null,
+            },
         }
     }
 
     pub fn sum(&self, row_ids: &[u32]) -> Scalar {
         match &self {
             FloatEncoding::Fixed64(c) => Scalar::F64(c.sum(row_ids)),
+            FloatEncoding::Fixed64N(c) => match c.sum(row_ids) {
+                Some(v) => Scalar::F64(v),
+                None => Scalar::Null,
+            },
         }
     }
 
     pub fn count(&self, row_ids: &[u32]) -> u32 {
         match &self {
             FloatEncoding::Fixed64(c) => c.count(row_ids),
+            FloatEncoding::Fixed64N(c) => c.count(row_ids),
         }
     }
 }
@@ -1949,6 +2190,30 @@ impl From<arrow::array::Int64Array> for Column {
     }
 }
 
+/// Converts an Arrow `UInt64Array`, which may contain NULL values, into a
+/// nullable fixed-width column encoding.
+impl From<arrow::array::UInt64Array> for Column {
+    fn from(arr: arrow::array::UInt64Array) -> Self {
+        // determine min and max of the non-null values.
+        let range = (0..arr.len())
+            .filter(|&i| !arr.is_null(i))
+            .map(|i| arr.value(i))
+            .fold(None, |range, v| match range {
+                Some((min, max)) => Some((v.min(min), v.max(max))),
+                None => Some((v, v)),
+            });
+
+        let data = fixed_null::FixedNull::<arrow::datatypes::UInt64Type>::from(arr);
+        let meta = MetaData {
+            size: data.size(),
+            rows: data.num_rows(),
+            range,
+            ..MetaData::default()
+        };
+        Column::Unsigned(meta, IntegerEncoding::U64U64N(data))
+    }
+}
+
 /// Converts a slice of `f64` values into a fixed-width column encoding.
 impl From<&[f64]> for Column {
     fn from(arr: &[f64]) -> Self {
@@ -1972,9 +2237,80 @@ impl From<&[f64]> for Column {
     }
 }
 
+/// Converts an Arrow `Float64Array`, which may contain NULL values, into a
+/// nullable fixed-width column encoding.
+impl From<arrow::array::Float64Array> for Column {
+    fn from(arr: arrow::array::Float64Array) -> Self {
+        // determine min and max of the non-null values.
+        let range = (0..arr.len())
+            .filter(|&i| !arr.is_null(i))
+            .map(|i| arr.value(i))
+            .fold(None, |range, v| match range {
+                Some((min, max)) => Some((v.min(min), v.max(max))),
+                None => Some((v, v)),
+            });
+
+        let data = fixed_null::FixedNull::<arrow::datatypes::Float64Type>::from(arr);
+        let meta = MetaData {
+            size: data.size(),
+            rows: data.num_rows(),
+            range,
+            ..MetaData::default()
+        };
+        Column::Float(meta, FloatEncoding::Fixed64N(data))
+    }
+}
+
+/// Converts an Arrow `BooleanArray`, which may contain NULL values, into a
+/// boolean column.
+impl From<arrow::array::BooleanArray> for Column {
+    fn from(arr: arrow::array::BooleanArray) -> Self {
+        // determine min and max of the non-null values.
+        let range = (0..arr.len())
+            .filter(|&i| !arr.is_null(i))
+            .map(|i| arr.value(i))
+            .fold(None, |range, v| match range {
+                Some((min, max)) => Some((v.min(min), v.max(max))),
+                None => Some((v, v)),
+            });
+
+        let data = boolean::Bool::from(arr);
+        let meta = MetaData {
+            size: data.size(),
+            rows: data.num_rows(),
+            range,
+            ..MetaData::default()
+        };
+        Column::Bool(meta, data)
+    }
+}
+
+impl From<&[Option<bool>]> for Column {
+    fn from(arr: &[Option<bool>]) -> Self {
+        Self::from(arrow::array::BooleanArray::from(arr.to_vec()))
+    }
+}
+
+impl From<&[bool]> for Column {
+    fn from(arr: &[bool]) -> Self {
+        Self::from(arrow::array::BooleanArray::from(arr.to_vec()))
+    }
+}
+
+/// The logical data types that column values can have.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LogicalDataType {
+    Integer,  // signed 64-bit integers
+    Unsigned, // unsigned 64-bit integers
+    Float,    // 64-bit floating point values
+    String,   // UTF-8 valid strings
+    Binary,   // arbitrary collections of bytes
+    Boolean,  // booleans
+}
+
 /// These variants describe supported aggregates that can applied to columnar
 /// data.
-#[derive(Copy, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AggregateType {
     Count,
     First,
@@ -1982,32 +2318,96 @@ pub enum AggregateType {
     Min,
     Max,
     Sum,
+
+    // The timestamp of the row with the largest value, or with the smallest
+    // value for `ArgMin`. Ties are resolved by taking the earliest timestamp.
+    ArgMax,
+    ArgMin,
+
+    // The exact percentile of the values, given as a percentage in
+    // `[0, 100]`, using the nearest-rank method. All values being aggregated
+    // are retained, so prefer `ApproxQuantile` for large columns.
+    Percentile(f64),
+
+    // An approximation of the percentile of the values, given as a percentage
+    // in `[0, 100]`. Values are summarised in a t-digest so memory use is
+    // bounded regardless of the number of rows.
+    ApproxQuantile(f64),
+
+    // The arithmetic mean of the values, which is a float. The mean of no
+    // values is NULL.
+    Mean,
+
+    // The sample standard deviation of the values.
+    StdDev,
+
+    // The sample variance of the values.
+    Variance,
+
+    // The number of values falling in each of the buckets delimited by the
+    // provided upper bounds, which must be in ascending order. A value falls
+    // in the first bucket whose bound is greater than or equal to it, and an
+    // extra bucket counts the values greater than every bound.
+    Histogram(Vec<f64>),
+
+    // The number of distinct values. NULL is counted as a distinct value only
+    // if `count_null` is set.
+    CountDistinct { count_null: bool },
+
+    // The number of true, or false, values in a boolean column. NULL values
+    // are excluded from both counts.
+    CountTrue,
+    CountFalse,
     /* TODO - support:
      * Distinct - (edd): not sure this counts as an aggregations. Seems more like a special
-     * filter. CountDistinct
-     * Percentile */
+     * filter. */
+}
+
+impl AggregateType {
+    /// Determines if the aggregate selects a value based on the timestamps of
+    /// the rows being aggregated.
+    pub fn is_selector(&self) -> bool {
+        matches!(self, Self::First | Self::Last | Self::ArgMax | Self::ArgMin)
+    }
+
+    /// The name of the aggregate, without any arguments.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateType::Count => "count",
+            AggregateType::First => "first",
+            AggregateType::Last => "last",
+            AggregateType::Min => "min",
+            AggregateType::Max => "max",
+            AggregateType::Sum => "sum",
+            AggregateType::ArgMax => "argmax",
+            AggregateType::ArgMin => "argmin",
+            AggregateType::Percentile(_) => "percentile",
+            AggregateType::ApproxQuantile(_) => "approx_quantile",
+            AggregateType::Mean => "mean",
+            AggregateType::StdDev => "stddev",
+            AggregateType::Variance => "variance",
+            AggregateType::Histogram(_) => "histogram",
+            AggregateType::CountDistinct { .. } => "count_distinct",
+            AggregateType::CountTrue => "count_true",
+            AggregateType::CountFalse => "count_false",
+        }
+    }
 }
 
 impl std::fmt::Display for AggregateType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                AggregateType::Count => "count",
-                AggregateType::First => "first",
-                AggregateType::Last => "last",
-                AggregateType::Min => "min",
-                AggregateType::Max => "max",
-                AggregateType::Sum => "sum",
+        match self {
+            AggregateType::Percentile(p) | AggregateType::ApproxQuantile(p) => {
+                write!(f, "{}_{}", self.name(), p)
             }
-        )
+            _ => write!(f, "{}", self.name()),
+        }
     }
 }
 
 /// These variants hold aggregates, which are the results of applying aggregates
 /// to column data.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub enum AggregateResult<'a> {
     // Any type of column can have rows counted. NULL values do not contribute
     // to the count. If all rows are NULL then count will be `0`.
@@ -2032,11 +2432,125 @@ pub enum AggregateResult<'a> {
 
     // The last value in the column data and the corresponding timestamp.
     Last(Option<(i64, Value<'a>)>),
+
+    // The timestamp at which the column data reached its maximum and the
+    // maximum value itself.
+    ArgMax(Option<(i64, Value<'a>)>),
+
+    // The timestamp at which the column data reached its minimum and the
+    // minimum value itself.
+    ArgMin(Option<(i64, Value<'a>)>),
+
+    // The requested percentile and all of the non-NULL values seen, from
+    // which the exact percentile is calculated.
+    Percentile(f64, Vec<Scalar>),
+
+    // The requested percentile and a digest summarising the values seen.
+    ApproxQuantile(f64, TDigest),
+
+    // The running state needed to calculate the mean. The mean is updated
+    // incrementally as a float, so large integer values can't overflow it.
+    Mean(VarianceState),
+
+    // The running state needed to calculate the sample standard deviation.
+    StdDev(VarianceState),
+
+    // The running state needed to calculate the sample variance.
+    Variance(VarianceState),
+
+    // The upper bounds of the histogram buckets and the number of values
+    // counted in each bucket, including the final unbounded bucket.
+    Histogram(Vec<f64>, Vec<u64>),
+
+    // Whether NULL is counted, and the distinct values seen. Values borrow
+    // from the column dictionaries, so strings aren't copied.
+    CountDistinct(bool, BTreeSet<Option<&'a str>>),
+
+    // The number of true values in boolean column data.
+    CountTrue(u64),
+
+    // The number of false values in boolean column data.
+    CountFalse(u64),
+}
+
+/// The running state of a variance calculation, updated in a single pass over
+/// the values using Welford's algorithm, which is numerically stable unlike
+/// the textbook sum of squares approach.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct VarianceState {
+    count: u64,
+    mean: f64,
+    // The sum of squared differences from the current mean.
+    m2: f64,
+}
+
+impl VarianceState {
+    pub fn new(count: u64, mean: f64, m2: f64) -> Self {
+        Self { count, mean, m2 }
+    }
+
+    pub fn update(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Combines the state of another set of values into this one, using
+    /// Chan et al.'s parallel form of the algorithm.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * other.count as f64 / count as f64;
+        self.count = count;
+    }
+
+    /// The sample variance, or `None` if there are fewer than two values.
+    pub fn variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            return None;
+        }
+        Some(self.m2 / (self.count - 1) as f64)
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn m2(&self) -> f64 {
+        self.m2
+    }
 }
 
 #[allow(unused_assignments)]
 impl<'a> AggregateResult<'a> {
     pub fn update(&mut self, other: Value<'a>) {
+        // NULL may be counted as a distinct value, so it's handled before
+        // NULL values are skipped for the other aggregates.
+        if let Self::CountDistinct(count_null, values) = self {
+            match other {
+                Value::String(v) => {
+                    values.insert(Some(v));
+                }
+                Value::Null => {
+                    if *count_null {
+                        values.insert(None);
+                    }
+                }
+                _ => unreachable!("not a possible variant combination"),
+            }
+            return;
+        }
+
         if other.is_null() {
             // a NULL value has no effect on aggregates
             return;
@@ -2126,11 +2640,305 @@ impl<'a> AggregateResult<'a> {
                 (_, Value::Scalar(b)) => *v += b,
                 (_, _) => unreachable!("not a possible variant combination"),
             },
-            _ => unimplemented!("First and Last aggregates not implemented yet"),
+            Self::Percentile(_, values) => match other {
+                Value::Scalar(Scalar::Null) => {}
+                Value::Scalar(v) => values.push(v),
+                _ => unreachable!("not a possible variant combination"),
+            },
+            Self::ApproxQuantile(_, digest) => match other {
+                Value::Scalar(Scalar::Null) => {}
+                Value::Scalar(v) => digest.insert(v.as_f64()),
+                _ => unreachable!("not a possible variant combination"),
+            },
+            Self::Mean(state) | Self::StdDev(state) | Self::Variance(state) => match other {
+                Value::Scalar(Scalar::Null) => {}
+                Value::Scalar(v) => state.update(v.as_f64()),
+                _ => unreachable!("not a possible variant combination"),
+            },
+            Self::Histogram(bounds, counts) => match other {
+                Value::Scalar(Scalar::Null) => {}
+                Value::Scalar(v) => {
+                    let v = v.as_f64();
+                    if !v.is_nan() {
+                        let bucket = bounds
+                            .iter()
+                            .position(|&bound| v <= bound)
+                            .unwrap_or_else(|| bounds.len());
+                        counts[bucket] += 1;
+                    }
+                }
+                _ => unreachable!("not a possible variant combination"),
+            },
+            Self::CountTrue(v) => match other {
+                Value::Boolean(true) => *v += 1,
+                Value::Boolean(false) => {}
+                _ => unreachable!("not a possible variant combination"),
+            },
+            Self::CountFalse(v) => match other {
+                Value::Boolean(false) => *v += 1,
+                Value::Boolean(true) => {}
+                _ => unreachable!("not a possible variant combination"),
+            },
+            Self::First(_) | Self::Last(_) | Self::ArgMax(_) | Self::ArgMin(_) => {
+                unreachable!("selector aggregates must be updated with a timestamp")
+            }
+            Self::CountDistinct(..) => unreachable!("count distinct is updated above"),
+        }
+    }
+
+    /// Updates the aggregate with a value from a row with the timestamp
+    /// `time`. The `First` and `Last` selectors keep the value with the
+    /// smallest and largest timestamp respectively, and ties are resolved by
+    /// keeping the value seen first. The `ArgMax` and `ArgMin` selectors keep
+    /// the timestamp of the largest and smallest value respectively, and ties
+    /// are resolved by keeping the earliest timestamp. Other aggregates ignore
+    /// the timestamp.
+    pub fn update_with_time(&mut self, other: Value<'a>, time: i64) {
+        match self {
+            // a NULL value has no effect on selectors
+            Self::First(_) | Self::Last(_) | Self::ArgMax(_) | Self::ArgMin(_)
+                if other.is_null() => {}
+            Self::First(v) => match v {
+                Some((ts, _)) if *ts <= time => {}
+                _ => *v = Some((time, other)),
+            },
+            Self::Last(v) => match v {
+                Some((ts, _)) if *ts >= time => {}
+                _ => *v = Some((time, other)),
+            },
+            Self::ArgMax(v) => {
+                if replaces_extreme(v, &other, time, std::cmp::Ordering::Greater) {
+                    *v = Some((time, other));
+                }
+            }
+            Self::ArgMin(v) => {
+                if replaces_extreme(v, &other, time, std::cmp::Ordering::Less) {
+                    *v = Some((time, other));
+                }
+            }
+            _ => self.update(other),
+        }
+    }
+}
+
+// Determines if `value` at `time` should replace the `current` extreme value
+// of an `ArgMax` or `ArgMin` aggregate, where `wanted` is the ordering a new
+// extreme has relative to the current one. Equal values are replaced only by
+// an earlier timestamp.
+fn replaces_extreme(
+    current: &Option<(i64, Value<'_>)>,
+    value: &Value<'_>,
+    time: i64,
+    wanted: std::cmp::Ordering,
+) -> bool {
+    let (current_time, current) = match current {
+        Some((ts, v)) => (*ts, v),
+        None => return true,
+    };
+
+    let ordering = match (value, current) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::ByteArray(b)) => Some(a.as_bytes().cmp(b)),
+        (Value::ByteArray(a), Value::String(b)) => Some(a.cmp(&b.as_bytes())),
+        (Value::ByteArray(a), Value::ByteArray(b)) => Some(a.cmp(b)),
+        (Value::Scalar(a), Value::Scalar(b)) => a.partial_cmp(b),
+        (_, _) => unreachable!("not a possible variant combination"),
+    };
+
+    match ordering {
+        Some(std::cmp::Ordering::Equal) => time < current_time,
+        Some(ordering) => ordering == wanted,
+        None => false,
+    }
+}
+
+impl<'a> AggregateResult<'a> {
+    /// Merges another aggregate result of the same type into this one. This
+    /// allows partial aggregates, for example from different chunks, to be
+    /// combined.
+    pub fn merge(&mut self, other: &AggregateResult<'a>) {
+        match other {
+            Self::Count(other) => match self {
+                Self::Count(v) => *v += *other,
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Sum(other) => match self {
+                Self::Sum(v) => {
+                    if v.is_null() {
+                        // NULL + something == something
+                        *v = *other;
+                    } else {
+                        *v += other;
+                    }
+                }
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Min(other) | Self::Max(other) => self.update(*other),
+            Self::First(other) => match self {
+                Self::First(v) => {
+                    if let Some((other_ts, _)) = other {
+                        // ties are resolved by keeping the existing value.
+                        match *v {
+                            Some((ts, _)) if ts <= *other_ts => {}
+                            _ => *v = *other,
+                        }
+                    }
+                }
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Last(other) => match self {
+                Self::Last(v) => {
+                    if let Some((other_ts, _)) = other {
+                        // ties are resolved by keeping the existing value.
+                        match *v {
+                            Some((ts, _)) if ts >= *other_ts => {}
+                            _ => *v = *other,
+                        }
+                    }
+                }
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::ArgMax(other) => match self {
+                Self::ArgMax(v) => {
+                    if let Some((other_ts, other_value)) = other {
+                        if replaces_extreme(v, other_value, *other_ts, std::cmp::Ordering::Greater)
+                        {
+                            *v = *other;
+                        }
+                    }
+                }
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::ArgMin(other) => match self {
+                Self::ArgMin(v) => {
+                    if let Some((other_ts, other_value)) = other {
+                        if replaces_extreme(v, other_value, *other_ts, std::cmp::Ordering::Less) {
+                            *v = *other;
+                        }
+                    }
+                }
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Percentile(_, other) => match self {
+                Self::Percentile(_, values) => values.extend_from_slice(other),
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::ApproxQuantile(_, other) => match self {
+                Self::ApproxQuantile(_, digest) => digest.merge(other),
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Mean(other) => match self {
+                Self::Mean(state) => state.merge(other),
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::StdDev(other) => match self {
+                Self::StdDev(state) => state.merge(other),
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Variance(other) => match self {
+                Self::Variance(state) => state.merge(other),
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::Histogram(_, other) => match self {
+                Self::Histogram(_, counts) => {
+                    for (count, other) in counts.iter_mut().zip(other) {
+                        *count += other;
+                    }
+                }
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::CountDistinct(_, other) => match self {
+                Self::CountDistinct(_, values) => values.extend(other),
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::CountTrue(other) => match self {
+                Self::CountTrue(v) => *v += *other,
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+            Self::CountFalse(other) => match self {
+                Self::CountFalse(v) => *v += *other,
+                _ => unreachable!("cannot merge different aggregate types"),
+            },
+        }
+    }
+
+    /// The aggregate as a logical value. Counts, including distinct counts
+    /// and counts of boolean values, are unsigned integers, and `ArgMax` and
+    /// `ArgMin` are the timestamps of the selected rows.
+    /// Exact percentiles have the type of the aggregated column, and
+    /// approximate ones are always floats, as are means, standard deviations
+    /// and variances. A histogram's value is the total number of values
+    /// counted; use `values` for the count of each bucket.
+    pub fn value(&self) -> Value<'a> {
+        match self {
+            Self::Count(v) | Self::CountTrue(v) | Self::CountFalse(v) => {
+                Value::Scalar(Scalar::U64(*v))
+            }
+            Self::Sum(v) => match v {
+                Scalar::Null => Value::Null,
+                _ => Value::Scalar(*v),
+            },
+            Self::Min(v) | Self::Max(v) => *v,
+            Self::First(v) | Self::Last(v) => match v {
+                Some((_, v)) => *v,
+                None => Value::Null,
+            },
+            Self::ArgMax(v) | Self::ArgMin(v) => match v {
+                Some((ts, _)) => Value::Scalar(Scalar::I64(*ts)),
+                None => Value::Null,
+            },
+            Self::Percentile(p, values) => match percentile(*p, values) {
+                Some(v) => Value::Scalar(v),
+                None => Value::Null,
+            },
+            Self::ApproxQuantile(p, digest) => match digest.quantile(p / 100.0) {
+                Some(v) => Value::Scalar(Scalar::F64(v)),
+                None => Value::Null,
+            },
+            Self::Mean(state) => match state.count() {
+                0 => Value::Null,
+                _ => Value::Scalar(Scalar::F64(state.mean())),
+            },
+            Self::StdDev(state) => match state.variance() {
+                Some(v) => Value::Scalar(Scalar::F64(v.sqrt())),
+                None => Value::Null,
+            },
+            Self::Variance(state) => match state.variance() {
+                Some(v) => Value::Scalar(Scalar::F64(v)),
+                None => Value::Null,
+            },
+            Self::Histogram(_, counts) => Value::Scalar(Scalar::U64(counts.iter().sum())),
+            Self::CountDistinct(_, values) => Value::Scalar(Scalar::U64(values.len() as u64)),
+        }
+    }
+
+    /// The aggregate as one logical value per output column. Histograms have
+    /// an unsigned count for each bucket, and other aggregates have the
+    /// single value returned by `value`.
+    pub fn values(&self) -> Vec<Value<'a>> {
+        match self {
+            Self::Histogram(_, counts) => counts
+                .iter()
+                .map(|&count| Value::Scalar(Scalar::U64(count)))
+                .collect(),
+            _ => vec![self.value()],
         }
     }
 }
 
+// The nearest-rank percentile of `values`: the smallest value that at least
+// `p` percent of the values are less than or equal to.
+fn percentile(p: f64, values: &[Scalar]) -> Option<Scalar> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut values = values.to_vec();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+    Some(values[rank.max(1).min(values.len()) - 1])
+}
+
 impl From<&AggregateType> for AggregateResult<'_> {
     fn from(typ: &AggregateType) -> Self {
         match typ {
@@ -2140,6 +2948,21 @@ impl From<&AggregateType> for AggregateResult<'_> {
             AggregateType::Min => Self::Min(Value::Null),
             AggregateType::Max => Self::Max(Value::Null),
             AggregateType::Sum => Self::Sum(Scalar::Null),
+            AggregateType::ArgMax => Self::ArgMax(None),
+            AggregateType::ArgMin => Self::ArgMin(None),
+            AggregateType::Percentile(p) => Self::Percentile(*p, vec![]),
+            AggregateType::ApproxQuantile(p) => Self::ApproxQuantile(*p, TDigest::default()),
+            AggregateType::Mean => Self::Mean(VarianceState::default()),
+            AggregateType::StdDev => Self::StdDev(VarianceState::default()),
+            AggregateType::Variance => Self::Variance(VarianceState::default()),
+            AggregateType::Histogram(bounds) => {
+                Self::Histogram(bounds.clone(), vec![0; bounds.len() + 1])
+            }
+            AggregateType::CountDistinct { count_null } => {
+                Self::CountDistinct(*count_null, BTreeSet::new())
+            }
+            AggregateType::CountTrue => Self::CountTrue(0),
+            AggregateType::CountFalse => Self::CountFalse(0),
         }
     }
 }
@@ -2147,7 +2970,9 @@ impl From<&AggregateType> for AggregateResult<'_> {
 impl std::fmt::Display for AggregateResult<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            AggregateResult::Count(v) => write!(f, "{}", v),
+            AggregateResult::Count(v)
+            | AggregateResult::CountTrue(v)
+            | AggregateResult::CountFalse(v) => write!(f, "{}", v),
             AggregateResult::First(v) => match v {
                 Some((_, v)) => write!(f, "{}", v),
                 None => write!(f, "NULL"),
@@ -2159,6 +2984,15 @@ impl std::fmt::Display for AggregateResult<'_> {
             AggregateResult::Min(v) => write!(f, "{}", v),
             AggregateResult::Max(v) => write!(f, "{}", v),
             AggregateResult::Sum(v) => write!(f, "{}", v),
+            AggregateResult::ArgMax(_)
+            | AggregateResult::ArgMin(_)
+            | AggregateResult::Percentile(..)
+            | AggregateResult::ApproxQuantile(..)
+            | AggregateResult::Mean(_)
+            | AggregateResult::StdDev(_)
+            | AggregateResult::Variance(_)
+            | AggregateResult::CountDistinct(..) => write!(f, "{}", self.value()),
+            AggregateResult::Histogram(_, counts) => write!(f, "{:?}", counts),
         }
     }
 }
@@ -2201,6 +3035,25 @@ impl Scalar {
         matches!(self, Self::Null)
     }
 
+    /// Compares two scalars by their numerical values, so that a predicate
+    /// such as `temp > 50` can be applied to a float column. Unlike
+    /// `partial_cmp`, scalars of different types can be ordered, and `None`
+    /// is returned if either scalar is NULL or NaN.
+    pub fn compare(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => None,
+            (Self::I64(a), Self::I64(b)) => a.partial_cmp(b),
+            (Self::U64(a), Self::U64(b)) => a.partial_cmp(b),
+            (Self::F64(a), Self::F64(b)) => a.partial_cmp(b),
+            (Self::I64(a), Self::U64(b)) => i128::from(*a).partial_cmp(&i128::from(*b)),
+            (Self::U64(a), Self::I64(b)) => i128::from(*a).partial_cmp(&i128::from(*b)),
+            (Self::I64(a), Self::F64(b)) => (*a as f64).partial_cmp(b),
+            (Self::U64(a), Self::F64(b)) => (*a as f64).partial_cmp(b),
+            (Self::F64(a), Self::I64(b)) => a.partial_cmp(&(*b as f64)),
+            (Self::F64(a), Self::U64(b)) => a.partial_cmp(&(*b as f64)),
+        }
+    }
+
     // Implementations of all the accessors for the variants of `Scalar`.
     typed_scalar_converters! {
         (as_i64, try_as_i64, i64),
@@ -2213,17 +3066,23 @@ impl Scalar {
         (as_u8, try_as_u8, u8),
     }
 
+    // Integer scalars are converted to the nearest f64, so that integer
+    // predicate values can be applied to float columns.
     fn as_f64(&self) -> f64 {
         match &self {
             Scalar::F64(v) => *v,
-            _ => unimplemented!("converting integer Scalar to f64 unsupported"),
+            Scalar::I64(v) => *v as f64,
+            Scalar::U64(v) => *v as f64,
+            Scalar::Null => panic!("cannot convert Scalar::Null"),
         }
     }
 
     fn try_as_f64(&self) -> Option<f64> {
         match &self {
             Scalar::F64(v) => Some(*v),
-            _ => unimplemented!("converting integer Scalar to f64 unsupported"),
+            Scalar::I64(v) => Some(*v as f64),
+            Scalar::U64(v) => Some(*v as f64),
+            Scalar::Null => panic!("cannot convert Scalar::Null"),
         }
     }
 }
@@ -2324,6 +3183,7 @@ impl PartialEq<Value<'_>> for OwnedValue {
     fn eq(&self, other: &Value<'_>) -> bool {
         match (&self, other) {
             (OwnedValue::String(a), Value::String(b)) => a == b,
+            (OwnedValue::Boolean(a), Value::Boolean(b)) => a == b,
             (OwnedValue::Scalar(a), Value::Scalar(b)) => a == b,
             _ => false,
         }
@@ -2334,6 +3194,7 @@ impl PartialOrd<Value<'_>> for OwnedValue {
     fn partial_cmp(&self, other: &Value<'_>) -> Option<std::cmp::Ordering> {
         match (&self, other) {
             (OwnedValue::String(a), Value::String(b)) => Some(a.as_str().cmp(b)),
+            (OwnedValue::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
             (OwnedValue::Scalar(a), Value::Scalar(b)) => a.partial_cmp(b),
             _ => None,
         }
@@ -2377,6 +3238,13 @@ impl Value<'_> {
         }
         panic!("cannot unwrap Value to String");
     }
+
+    pub fn boolean(&self) -> bool {
+        if let Self::Boolean(b) = self {
+            return *b;
+        }
+        panic!("cannot unwrap Value to Boolean");
+    }
 }
 
 impl std::fmt::Display for Value<'_> {
@@ -3535,6 +4403,47 @@ mod test {
         assert!(matches!(row_ids, RowIDsOption::All(_)));
     }
 
+    #[test]
+    fn row_ids_filter_bool() {
+        let col = Column::from(&[Some(true), None, Some(true)][..]);
+        assert_eq!(
+            col.column_range(),
+            Some((OwnedValue::Boolean(true), OwnedValue::Boolean(true)))
+        );
+
+        let mut row_ids = col.row_ids_filter(
+            &cmp::Operator::Equal,
+            &Value::Boolean(true),
+            RowIDs::new_bitmap(),
+        );
+        assert_eq!(row_ids.unwrap().to_vec(), vec![0, 2]);
+
+        // the column's range shows no rows are false.
+        row_ids = col.row_ids_filter(
+            &cmp::Operator::Equal,
+            &Value::Boolean(false),
+            RowIDs::new_bitmap(),
+        );
+        assert!(matches!(row_ids, RowIDsOption::None(_)));
+
+        let col = Column::from(&[false, true, false][..]);
+        row_ids = col.row_ids_filter(
+            &cmp::Operator::GTE,
+            &Value::Boolean(false),
+            RowIDs::new_bitmap(),
+        );
+        assert!(matches!(row_ids, RowIDsOption::All(_)));
+
+        row_ids = col.row_ids_filter(
+            &cmp::Operator::NotEqual,
+            &Value::Boolean(true),
+            RowIDs::new_bitmap(),
+        );
+        assert_eq!(row_ids.unwrap().to_vec(), vec![0, 2]);
+        assert_eq!(col.min(&[0, 1, 2]), Value::Boolean(false));
+        assert_eq!(col.max(&[0, 2]), Value::Boolean(false));
+    }
+
     #[test]
     fn row_ids_range() {
         let input = &[100, 200, 300, 2, 200, 22, 30];
@@ -3659,6 +4568,46 @@ mod test {
         }
     }
 
+    #[test]
+    fn dictionary() {
+        let col = Column::from(&["west", "east", "west"][..]);
+        assert_eq!(
+            col.dictionary(),
+            Some(vec![&"east".to_string(), &"west".to_string()])
+        );
+
+        // only string columns have a dictionary.
+        assert!(Column::from(&[1_i64, 2][..]).dictionary().is_none());
+        assert!(Column::from(&[1.0, 2.0][..]).dictionary().is_none());
+    }
+
+    #[test]
+    fn scalar_compare() {
+        use std::cmp::Ordering;
+
+        // scalars of different types are ordered by their numerical values.
+        let cases = vec![
+            (Scalar::I64(50), Scalar::F64(50.5), Some(Ordering::Less)),
+            (Scalar::F64(50.0), Scalar::I64(50), Some(Ordering::Equal)),
+            (
+                Scalar::U64(u64::MAX),
+                Scalar::I64(-1),
+                Some(Ordering::Greater),
+            ),
+            (Scalar::I64(-1), Scalar::U64(0), Some(Ordering::Less)),
+            (Scalar::F64(f64::NAN), Scalar::I64(1), None),
+            (Scalar::Null, Scalar::I64(1), None),
+            (Scalar::I64(1), Scalar::Null, None),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(a.compare(&b), expected, "{:?} compared to {:?}", a, b);
+        }
+
+        // but scalars of different types are never equal to each other.
+        assert_ne!(Scalar::F64(50.0), Scalar::I64(50));
+        assert_ne!(Scalar::U64(1), Scalar::I64(1));
+    }
+
     #[test]
     fn predicate_matches_all_values() {
         let input = &[100i64, 200, 300, 2, 200, 22, 30];
@@ -3894,4 +4843,169 @@ mod test {
         res.update(Value::Scalar(Scalar::Null));
         assert!(matches!(res, AggregateResult::Sum(Scalar::I64(15))));
     }
+
+    #[test]
+    fn aggregate_result_percentile() {
+        let mut res = AggregateResult::from(&AggregateType::Percentile(50.0));
+        assert_eq!(res.value(), Value::Null);
+
+        for v in &[15_i64, 20, 35, 40, 50] {
+            res.update(Value::Scalar(Scalar::I64(*v)));
+        }
+        res.update(Value::Null);
+        res.update(Value::Scalar(Scalar::Null));
+
+        let cases = vec![
+            (0.0, 15),
+            (5.0, 15),
+            (30.0, 20),
+            (40.0, 20),
+            (50.0, 35),
+            (99.0, 50),
+            (100.0, 50),
+        ];
+        for (p, expected) in cases {
+            if let AggregateResult::Percentile(percentile, _) = &mut res {
+                *percentile = p;
+            }
+            assert_eq!(res.value(), Value::Scalar(Scalar::I64(expected)), "p{}", p);
+        }
+
+        // partial results can be merged.
+        let mut other = AggregateResult::from(&AggregateType::Percentile(50.0));
+        other.update(Value::Scalar(Scalar::I64(1)));
+        other.update(Value::Scalar(Scalar::I64(2)));
+        other.merge(&res);
+        assert_eq!(other.value(), Value::Scalar(Scalar::I64(20)));
+
+        let mut res = AggregateResult::from(&AggregateType::ApproxQuantile(50.0));
+        assert_eq!(res.value(), Value::Null);
+        for v in &[1.0, 2.0, 3.0] {
+            res.update(Value::Scalar(Scalar::F64(*v)));
+        }
+        assert_eq!(res.value(), Value::Scalar(Scalar::F64(2.0)));
+    }
+
+    #[test]
+    fn aggregate_result_count_booleans() {
+        let values = Values::Bool(vec![
+            Some(true),
+            None,
+            Some(false),
+            Some(true),
+            None,
+            Some(true),
+        ]);
+
+        let mut count = AggregateResult::from(&AggregateType::Count);
+        let mut count_true = AggregateResult::from(&AggregateType::CountTrue);
+        let mut count_false = AggregateResult::from(&AggregateType::CountFalse);
+        for row in 0..values.len() {
+            count.update(values.value(row));
+            count_true.update(values.value(row));
+            count_false.update(values.value(row));
+        }
+        assert_eq!(count_true.value(), Value::Scalar(Scalar::U64(3)));
+        assert_eq!(count_false.value(), Value::Scalar(Scalar::U64(1)));
+
+        // NULL values are in neither count.
+        assert_eq!(count.value(), Value::Scalar(Scalar::U64(4)));
+
+        // merging partial results adds the counts.
+        let mut other = AggregateResult::from(&AggregateType::CountFalse);
+        other.update(Value::Boolean(false));
+        other.update(Value::Null);
+        count_false.merge(&other);
+        assert_eq!(count_false.value(), Value::Scalar(Scalar::U64(2)));
+        assert_eq!(count_false.to_string(), "2");
+    }
+
+    #[test]
+    fn aggregate_result_mean() {
+        let mut mean = AggregateResult::from(&AggregateType::Mean);
+        assert_eq!(mean.value(), Value::Null);
+        mean.update(Value::Null);
+        assert_eq!(mean.value(), Value::Null);
+
+        for v in &[1_i64, 2, 4] {
+            mean.update(Value::Scalar(Scalar::I64(*v)));
+        }
+        mean.update(Value::Null);
+        match mean.value() {
+            Value::Scalar(Scalar::F64(v)) => assert!((v - 7.0 / 3.0).abs() < 1e-12),
+            v => panic!("unexpected value {:?}", v),
+        }
+
+        // values whose sum overflows an i64.
+        let mut mean = AggregateResult::from(&AggregateType::Mean);
+        for _ in 0..4 {
+            mean.update(Value::Scalar(Scalar::I64(i64::MAX)));
+        }
+        assert_eq!(mean.value(), Value::Scalar(Scalar::F64(i64::MAX as f64)));
+
+        // merging partial results is equivalent to a single pass.
+        let mut merged = AggregateResult::from(&AggregateType::Mean);
+        for chunk in [2_u64, 4, 6, 8, 10].chunks(2) {
+            let mut partial = AggregateResult::from(&AggregateType::Mean);
+            for v in chunk {
+                partial.update(Value::Scalar(Scalar::U64(*v)));
+            }
+            merged.merge(&partial);
+        }
+        merged.merge(&AggregateResult::from(&AggregateType::Mean));
+        assert_eq!(merged.value(), Value::Scalar(Scalar::F64(6.0)));
+    }
+
+    #[test]
+    fn aggregate_result_variance() {
+        fn f64_value(value: Value<'_>) -> f64 {
+            match value {
+                Value::Scalar(Scalar::F64(v)) => v,
+                v => panic!("unexpected value {:?}", v),
+            }
+        }
+
+        // mean is 5 and the sum of squared differences is 32.
+        let input = &[2.0_f64, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let mut variance = AggregateResult::from(&AggregateType::Variance);
+        let mut stddev = AggregateResult::from(&AggregateType::StdDev);
+        for v in input {
+            variance.update(Value::Scalar(Scalar::F64(*v)));
+            stddev.update(Value::Scalar(Scalar::F64(*v)));
+        }
+        assert!((f64_value(variance.value()) - 32.0 / 7.0).abs() < 1e-12);
+        assert!((f64_value(stddev.value()) - (32.0_f64 / 7.0).sqrt()).abs() < 1e-12);
+
+        // merging partial results is equivalent to a single pass.
+        let mut merged = AggregateResult::from(&AggregateType::Variance);
+        for chunk in input.chunks(3) {
+            let mut partial = AggregateResult::from(&AggregateType::Variance);
+            for v in chunk {
+                partial.update(Value::Scalar(Scalar::F64(*v)));
+            }
+            merged.merge(&partial);
+        }
+        assert!((f64_value(merged.value()) - 32.0 / 7.0).abs() < 1e-12);
+
+        // NULL values don't contribute.
+        let input = vec![Some(1), None, Some(3), None];
+        let col = Column::from(Int64Array::from(input));
+        let values = col.values(&[0, 1, 2, 3]);
+        let mut variance = AggregateResult::from(&AggregateType::Variance);
+        let mut stddev = AggregateResult::from(&AggregateType::StdDev);
+        for row in 0..values.len() {
+            variance.update(values.value(row));
+            stddev.update(values.value(row));
+        }
+        assert!((f64_value(variance.value()) - 2.0).abs() < 1e-12);
+        assert!((f64_value(stddev.value()) - 2.0_f64.sqrt()).abs() < 1e-12);
+
+        // fewer than two values have no variance.
+        let mut variance = AggregateResult::from(&AggregateType::Variance);
+        assert_eq!(variance.value(), Value::Null);
+        variance.update(Value::Scalar(Scalar::I64(10)));
+        assert_eq!(variance.value(), Value::Null);
+        variance.update(Value::Null);
+        assert_eq!(variance.value(), Value::Null);
+    }
 }
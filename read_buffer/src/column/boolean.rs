@@ -0,0 +1,255 @@
+//! An encoding for nullable boolean values backed by an Arrow array.
+//!
+//! Each value is stored as a single bit, alongside a validity bitmap when the
+//! column contains NULL values.
+use std::mem::size_of;
+
+use arrow_deps::arrow;
+use arrow_deps::arrow::array::{Array, BooleanArray};
+
+use crate::column::{cmp, RowIDs};
+
+#[derive(Debug)]
+pub struct Bool {
+    // backing data
+    arr: BooleanArray,
+}
+
+impl std::fmt::Display for Bool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[Bool] rows: {:?}, nulls: {:?}, size: {}",
+            self.arr.len(),
+            self.arr.null_count(),
+            self.size()
+        )
+    }
+}
+
+impl Bool {
+    pub fn num_rows(&self) -> u32 {
+        self.arr.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arr.is_empty()
+    }
+
+    pub fn contains_null(&self) -> bool {
+        self.arr.null_count() > 0
+    }
+
+    /// Returns an estimation of the total size in bytes used by this column
+    /// encoding.
+    pub fn size(&self) -> u64 {
+        let bitmap_size = (self.arr.len() + 7) / 8;
+        let null_bitmap_size = if self.contains_null() { bitmap_size } else { 0 };
+        (size_of::<BooleanArray>() + bitmap_size + null_bitmap_size) as u64
+    }
+
+    //
+    //
+    // ---- Methods for getting decoded (materialised) values.
+    //
+    //
+
+    /// Return the logical (decoded) value at the provided row ID. A NULL value
+    /// is represented by None.
+    pub fn value(&self, row_id: u32) -> Option<bool> {
+        if self.arr.is_null(row_id as usize) {
+            return None;
+        }
+        Some(self.arr.value(row_id as usize))
+    }
+
+    /// Returns the logical (decoded) values for the provided row IDs.
+    ///
+    /// NULL values are represented by None.
+    pub fn values(&self, row_ids: &[u32], mut dst: Vec<Option<bool>>) -> Vec<Option<bool>> {
+        dst.clear();
+        dst.reserve(row_ids.len());
+
+        for &row_id in row_ids {
+            dst.push(self.value(row_id));
+        }
+        dst
+    }
+
+    /// Returns the logical (decoded) values for all the rows in the column.
+    ///
+    /// NULL values are represented by None.
+    pub fn all_values(&self, mut dst: Vec<Option<bool>>) -> Vec<Option<bool>> {
+        dst.clear();
+        dst.reserve(self.arr.len());
+
+        for i in 0..self.num_rows() {
+            dst.push(self.value(i));
+        }
+        dst
+    }
+
+    //
+    //
+    // ---- Methods for aggregation.
+    //
+    //
+
+    /// Returns the count of the non-null values for the provided row IDs.
+    pub fn count(&self, row_ids: &[u32]) -> u32 {
+        if !self.contains_null() {
+            return row_ids.len() as u32;
+        }
+
+        row_ids
+            .iter()
+            .filter(|&&i| !self.arr.is_null(i as usize))
+            .count() as u32
+    }
+
+    /// Returns the minimum non-null value for the provided row IDs, where
+    /// `false` is less than `true`.
+    pub fn min(&self, row_ids: &[u32]) -> Option<bool> {
+        row_ids.iter().filter_map(|&i| self.value(i)).min()
+    }
+
+    /// Returns the maximum non-null value for the provided row IDs, where
+    /// `false` is less than `true`.
+    pub fn max(&self, row_ids: &[u32]) -> Option<bool> {
+        row_ids.iter().filter_map(|&i| self.value(i)).max()
+    }
+
+    //
+    //
+    // ---- Methods for filtering via operators.
+    //
+    //
+
+    /// Returns the set of row ids that satisfy a binary operator on a logical
+    /// value, where `false` is less than `true`. NULL values never satisfy
+    /// the operator.
+    ///
+    /// Essentially, this supports `value {=, !=, >, >=, <, <=} x`.
+    pub fn row_ids_filter(&self, value: bool, op: &cmp::Operator, dst: RowIDs) -> RowIDs {
+        self.row_ids_filter_by(dst, |v| Self::compare(v, value, op))
+    }
+
+    /// Returns the set of row ids that satisfy both of the binary operators on
+    /// logical values.
+    pub fn row_ids_filter_range(
+        &self,
+        left: (bool, cmp::Operator),
+        right: (bool, cmp::Operator),
+        dst: RowIDs,
+    ) -> RowIDs {
+        self.row_ids_filter_by(dst, |v| {
+            Self::compare(v, left.0, &left.1) && Self::compare(v, right.0, &right.1)
+        })
+    }
+
+    // Determines the row ids of the non-null values satisfying `predicate`.
+    fn row_ids_filter_by(&self, mut dst: RowIDs, predicate: impl Fn(bool) -> bool) -> RowIDs {
+        dst.clear();
+
+        for i in 0..self.num_rows() {
+            if let Some(v) = self.value(i) {
+                if predicate(v) {
+                    dst.add(i);
+                }
+            }
+        }
+        dst
+    }
+
+    fn compare(v: bool, value: bool, op: &cmp::Operator) -> bool {
+        match op {
+            cmp::Operator::Equal => v == value,
+            cmp::Operator::NotEqual => v != value,
+            cmp::Operator::GT => v & !value,
+            cmp::Operator::GTE => v >= value,
+            cmp::Operator::LT => !v & value,
+            cmp::Operator::LTE => v <= value,
+            op => panic!("cannot apply {:?} to boolean values", op),
+        }
+    }
+}
+
+impl From<&[bool]> for Bool {
+    fn from(v: &[bool]) -> Self {
+        Self {
+            arr: BooleanArray::from(v.to_vec()),
+        }
+    }
+}
+
+impl From<&[Option<bool>]> for Bool {
+    fn from(v: &[Option<bool>]) -> Self {
+        Self {
+            arr: BooleanArray::from(v.to_vec()),
+        }
+    }
+}
+
+impl From<arrow::array::BooleanArray> for Bool {
+    fn from(arr: arrow::array::BooleanArray) -> Self {
+        Self { arr }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::cmp::Operator;
+    use super::*;
+
+    #[test]
+    fn values() {
+        let v = Bool::from(vec![Some(true), None, Some(false), Some(true)].as_slice());
+
+        assert_eq!(v.num_rows(), 4);
+        assert!(v.contains_null());
+        assert_eq!(v.value(1), None);
+        assert_eq!(v.values(&[0, 2], vec![]), vec![Some(true), Some(false)]);
+        assert_eq!(
+            v.all_values(vec![]),
+            vec![Some(true), None, Some(false), Some(true)]
+        );
+    }
+
+    #[test]
+    fn aggregates() {
+        let v = Bool::from(vec![None, Some(true), Some(true), None].as_slice());
+
+        assert_eq!(v.count(&[0, 1, 2, 3]), 2);
+        assert_eq!(v.min(&[0, 1, 2, 3]), Some(true));
+        assert_eq!(v.max(&[0, 1, 2, 3]), Some(true));
+        assert_eq!(v.min(&[0, 3]), None);
+
+        let v = Bool::from(vec![true, false, true].as_slice());
+        assert_eq!(v.count(&[0, 1]), 2);
+        assert_eq!(v.min(&[0, 1, 2]), Some(false));
+        assert_eq!(v.max(&[1]), Some(false));
+    }
+
+    #[test]
+    fn row_ids_filter() {
+        let v = Bool::from(vec![Some(true), None, Some(false), Some(true)].as_slice());
+
+        let dst = RowIDs::new_vector();
+        let row_ids = v.row_ids_filter(true, &Operator::Equal, dst);
+        assert_eq!(row_ids.to_vec(), vec![0, 3]);
+
+        // NULL values don't satisfy `!=`.
+        let row_ids = v.row_ids_filter(true, &Operator::NotEqual, row_ids);
+        assert_eq!(row_ids.to_vec(), vec![2]);
+
+        let row_ids = v.row_ids_filter(false, &Operator::GT, row_ids);
+        assert_eq!(row_ids.to_vec(), vec![0, 3]);
+
+        let row_ids = v.row_ids_filter(true, &Operator::LT, row_ids);
+        assert_eq!(row_ids.to_vec(), vec![2]);
+
+        let row_ids =
+            v.row_ids_filter_range((false, Operator::GTE), (true, Operator::LTE), row_ids);
+        assert_eq!(row_ids.to_vec(), vec![0, 2, 3]);
+    }
+}
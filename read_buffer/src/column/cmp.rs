@@ -1,3 +1,9 @@
+use regex::RegexSet;
+
+/// The separator between the patterns in the value of a `RegexMatchAny`
+/// predicate.
+pub const REGEX_PATTERN_SEPARATOR: &str = "\n";
+
 /// Possible comparison operators
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Operator {
@@ -7,4 +13,80 @@ pub enum Operator {
     GTE,
     LT,
     LTE,
+
+    // The value is a regular expression pattern that string values must
+    // (or must not) match. NULL values never match either operator.
+    RegexMatch,
+    RegexNotMatch,
+
+    // The value is a list of regular expression patterns separated by
+    // `REGEX_PATTERN_SEPARATOR`, and string values must match at least one of
+    // them. The patterns are compiled together so each value is only scanned
+    // once, which is cheaper than OR-ing separate `RegexMatch` predicates.
+    RegexMatchAny,
+
+    // Values must (or must not) be NULL. These operators take no value, so
+    // the value of the predicate should be `Value::Null`.
+    IsNull,
+    IsNotNull,
+}
+
+impl Operator {
+    /// Determines if the operator matches values against a regular
+    /// expression.
+    pub fn is_regex(&self) -> bool {
+        matches!(
+            self,
+            Self::RegexMatch | Self::RegexNotMatch | Self::RegexMatchAny
+        )
+    }
+
+    /// Determines if the operator checks whether values are NULL, rather than
+    /// comparing them to the value of the predicate.
+    pub fn is_null_check(&self) -> bool {
+        matches!(self, Self::IsNull | Self::IsNotNull)
+    }
+
+    /// Compiles the pattern(s) in the value of a regular expression predicate
+    /// using this operator into a set, which matches a value if any of the
+    /// patterns match it.
+    pub fn compile_regex(&self, pattern: &str) -> Result<RegexSet, regex::Error> {
+        match self {
+            Self::RegexMatchAny => RegexSet::new(pattern.split(REGEX_PATTERN_SEPARATOR)),
+            _ => RegexSet::new(&[pattern]),
+        }
+    }
+
+    /// Determines whether a value matching the compiled pattern(s) of a
+    /// regular expression predicate satisfies it.
+    pub fn regex_includes_matches(&self) -> bool {
+        !matches!(self, Self::RegexNotMatch)
+    }
+}
+
+/// Joins patterns into the value of a `RegexMatchAny` predicate.
+pub fn regex_match_any_pattern(patterns: &[&str]) -> String {
+    patterns.join(REGEX_PATTERN_SEPARATOR)
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Equal => "=",
+                Self::NotEqual => "!=",
+                Self::GT => ">",
+                Self::GTE => ">=",
+                Self::LT => "<",
+                Self::LTE => "<=",
+                Self::RegexMatch => "=~",
+                Self::RegexNotMatch => "!~",
+                Self::RegexMatchAny => "=~ any",
+                Self::IsNull => "IS NULL",
+                Self::IsNotNull => "IS NOT NULL",
+            }
+        )
+    }
 }
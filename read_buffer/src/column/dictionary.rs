@@ -473,6 +473,61 @@ mod test {
         assert_eq!(ids, RowIDs::Vector(vec![0, 1, 2]), "{}", name);
     }
 
+    #[test]
+    fn row_ids_filter_regex() {
+        let encodings = vec![
+            Encoding::RLE(RLE::default()),
+            Encoding::Plain(Plain::default()),
+        ];
+
+        for enc in encodings {
+            _row_ids_filter_regex(enc);
+        }
+    }
+
+    fn _row_ids_filter_regex(mut enc: Encoding) {
+        let name = enc.debug_name();
+        enc.push_additional(Some("prod-east".to_string()), 2); // 0, 1
+        enc.push_additional(Some("dev-east".to_string()), 1); // 2
+        enc.push_none(); // 3
+        enc.push_additional(Some("prod-west".to_string()), 1); // 4
+        enc.push_additional(Some("staging-prod".to_string()), 1); // 5
+
+        let ids = enc.row_ids_filter(
+            &"^prod-",
+            &cmp::Operator::RegexMatch,
+            RowIDs::Vector(vec![]),
+        );
+        assert_eq!(ids, RowIDs::Vector(vec![0, 1, 4]), "{}", name);
+
+        // NULL values match neither operator.
+        let ids = enc.row_ids_filter(
+            &"^prod-",
+            &cmp::Operator::RegexNotMatch,
+            RowIDs::Vector(vec![]),
+        );
+        assert_eq!(ids, RowIDs::Vector(vec![2, 5]), "{}", name);
+
+        let ids = enc.row_ids_filter(&"^qa-", &cmp::Operator::RegexMatch, RowIDs::Vector(vec![]));
+        assert!(ids.is_empty(), "{}", name);
+
+        // Rows matching any of the patterns are included.
+        let ids = enc.row_ids_filter(
+            &cmp::regex_match_any_pattern(&["^prod-", "^dev-"]),
+            &cmp::Operator::RegexMatchAny,
+            RowIDs::Vector(vec![]),
+        );
+        assert_eq!(ids, RowIDs::Vector(vec![0, 1, 2, 4]), "{}", name);
+
+        // Invalid patterns match no rows.
+        let ids = enc.row_ids_filter(
+            &"prod-(",
+            &cmp::Operator::RegexMatch,
+            RowIDs::Vector(vec![]),
+        );
+        assert!(ids.is_empty(), "{}", name);
+    }
+
     #[test]
     fn row_ids_filter_cmp() {
         let encodings = vec![
@@ -968,7 +1023,22 @@ mod test {
 
     #[test]
     fn distinct_values() {
-        let mut enc = Encoding::RLE(RLE::default());
+        let encodings = vec![
+            Encoding::RLE(RLE::default()),
+            Encoding::Plain(Plain::default()),
+        ];
+
+        for enc in encodings {
+            _distinct_values(enc);
+        }
+    }
+
+    fn _distinct_values(mut enc: Encoding) {
+        let name = enc.debug_name();
+        let empty = match &enc {
+            Encoding::RLE(_) => Encoding::RLE(RLE::default()),
+            Encoding::Plain(_) => Encoding::Plain(Plain::default()),
+        };
         enc.push_additional(Some("east".to_string()), 100);
 
         let values = enc.distinct_values((0..100).collect::<Vec<_>>().as_slice(), BTreeSet::new());
@@ -976,10 +1046,12 @@ mod test {
             values,
             vec![Some(&"east".to_string())]
                 .into_iter()
-                .collect::<BTreeSet<_>>()
+                .collect::<BTreeSet<_>>(),
+            "{}",
+            name
         );
 
-        enc = Encoding::RLE(RLE::default());
+        enc = empty;
         enc.push_additional(Some("east".to_string()), 3); // 0, 1, 2
         enc.push_additional(Some("north".to_string()), 1); // 3
         enc.push_additional(Some("east".to_string()), 5); // 4, 5, 6, 7, 8
@@ -996,7 +1068,9 @@ mod test {
                 Some(&"south".to_string()),
             ]
             .into_iter()
-            .collect::<BTreeSet<_>>()
+            .collect::<BTreeSet<_>>(),
+            "{}",
+            name
         );
 
         let values = enc.distinct_values((0..4).collect::<Vec<_>>().as_slice(), BTreeSet::new());
@@ -1004,7 +1078,9 @@ mod test {
             values,
             vec![Some(&"east".to_string()), Some(&"north".to_string()),]
                 .into_iter()
-                .collect::<BTreeSet<_>>()
+                .collect::<BTreeSet<_>>(),
+            "{}",
+            name
         );
 
         let values = enc.distinct_values(&[3, 10], BTreeSet::new());
@@ -1012,11 +1088,13 @@ mod test {
             values,
             vec![Some(&"north".to_string()), Some(&"south".to_string()),]
                 .into_iter()
-                .collect::<BTreeSet<_>>()
+                .collect::<BTreeSet<_>>(),
+            "{}",
+            name
         );
 
         let values = enc.distinct_values(&[100], BTreeSet::new());
-        assert!(values.is_empty());
+        assert!(values.is_empty(), "{}", name);
     }
 
     #[test]
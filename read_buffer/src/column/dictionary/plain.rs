@@ -175,9 +175,46 @@ impl Plain {
             cmp::Operator::LT | cmp::Operator::LTE | cmp::Operator::GT | cmp::Operator::GTE => {
                 self.row_ids_cmp(value, op, dst)
             }
+            cmp::Operator::RegexMatch
+            | cmp::Operator::RegexNotMatch
+            | cmp::Operator::RegexMatchAny => self.row_ids_regex(value, op, dst),
+            cmp::Operator::IsNull => self.row_ids_null(dst),
+            cmp::Operator::IsNotNull => self.row_ids_not_null(dst),
         }
     }
 
+    // Finds row ids based on a regular expression operator, where `pattern`
+    // holds the regular expression(s). The pattern is only evaluated once for each
+    // distinct value in the column. An invalid pattern matches no rows.
+    fn row_ids_regex(&self, pattern: &str, op: &cmp::Operator, mut dst: RowIDs) -> RowIDs {
+        dst.clear();
+
+        let regex = match op.compile_regex(pattern) {
+            Ok(regex) => regex,
+            Err(_) => return dst,
+        };
+        let include = op.regex_includes_matches();
+
+        // Determine which encoded ids match the pattern. NULL values never
+        // match.
+        let matching_ids = self
+            .entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(false, |entry| regex.is_match(entry) == include)
+            })
+            .collect::<Vec<_>>();
+
+        for (i, next) in self.encoded_data.iter().enumerate() {
+            if matching_ids[*next as usize] {
+                dst.add(i as u32);
+            }
+        }
+        dst
+    }
+
     // Finds row ids based on = or != operator.
     fn row_ids_equal(&self, value: &str, op: &cmp::Operator, mut dst: RowIDs) -> RowIDs {
         dst.clear();
@@ -607,7 +644,22 @@ impl Plain {
         // totally ordered.
         dst.clear();
 
-        todo!()
+        // Used to mark off when a decoded value has been added to the result
+        // set, so that only the distinct encoded ids referenced by the rows
+        // are decoded.
+        let mut encoded_values = vec![false; self.entries.len()];
+        for row_id in row_ids {
+            let encoded_id = match self.encoded_data.get(*row_id as usize) {
+                Some(&encoded_id) => encoded_id as usize,
+                None => return dst, // rows beyond the column size
+            };
+
+            if !encoded_values[encoded_id] {
+                dst.insert(self.entries[encoded_id].as_ref());
+                encoded_values[encoded_id] = true;
+            }
+        }
+        dst
     }
 
     //
@@ -243,9 +243,34 @@ impl RLE {
             cmp::Operator::LT | cmp::Operator::LTE | cmp::Operator::GT | cmp::Operator::GTE => {
                 self.row_ids_cmp(value, op, dst)
             }
+            cmp::Operator::RegexMatch
+            | cmp::Operator::RegexNotMatch
+            | cmp::Operator::RegexMatchAny => self.row_ids_regex(value, op, dst),
+            cmp::Operator::IsNull => self.row_ids_null(dst),
+            cmp::Operator::IsNotNull => self.row_ids_not_null(dst),
         }
     }
 
+    // Finds row ids based on a regular expression operator, where `pattern`
+    // holds the regular expression(s). The pattern is only evaluated once for each
+    // distinct value in the column. An invalid pattern matches no rows.
+    fn row_ids_regex(&self, pattern: &str, op: &cmp::Operator, mut dst: RowIDs) -> RowIDs {
+        dst.clear();
+
+        let regex = match op.compile_regex(pattern) {
+            Ok(regex) => regex,
+            Err(_) => return dst,
+        };
+        let include = op.regex_includes_matches();
+
+        for (entry, encoded_id) in &self.entry_index {
+            if regex.is_match(entry) == include {
+                dst.union(self.index_row_ids.get(encoded_id).unwrap());
+            }
+        }
+        dst
+    }
+
     // Finds row ids based on = or != operator.
     fn row_ids_equal(&self, value: &str, op: &cmp::Operator, mut dst: RowIDs) -> RowIDs {
         dst.clear();
@@ -14,6 +14,7 @@
 //! consumer of these encodings.
 use std::cmp::Ordering;
 use std::fmt::Debug;
+use std::mem::size_of;
 
 use arrow_deps::arrow;
 use arrow_deps::arrow::array::{Array, PrimitiveArray};
@@ -70,7 +71,16 @@ where
     /// is really an "accurate" estimation. It doesn't include for example the
     /// size of the `Plain` struct receiver.
     pub fn size(&self) -> u64 {
-        0
+        let values_size = size_of::<T::Native>() * self.arr.len();
+
+        // a validity bitmap is only needed when there are NULL values.
+        let null_bitmap_size = if self.contains_null() {
+            (self.arr.len() + 7) / 8
+        } else {
+            0
+        };
+
+        (size_of::<PrimitiveArray<T>>() + values_size + null_bitmap_size) as u64
     }
 
     //
@@ -241,7 +251,9 @@ where
                 continue;
             }
 
-            if self.value(v) < min {
+            // `None` orders before any value, so a leading NULL is replaced
+            // by the first non-null value.
+            if min.is_none() || self.value(v) < min {
                 min = self.value(v);
             }
         }
@@ -568,6 +580,8 @@ macro_rules! fixed_from_arrow_impls {
 // Need to look at possibility of initialising smaller datatypes...
 fixed_from_arrow_impls! {
     (arrow::array::Int64Array, arrow_deps::arrow::datatypes::Int64Type),
+    (arrow::array::UInt64Array, arrow_deps::arrow::datatypes::UInt64Type),
+    (arrow::array::Float64Array, arrow_deps::arrow::datatypes::Float64Type),
     // TODO(edd): add more datatypes
 }
 
@@ -581,6 +595,18 @@ mod test {
         v.iter().map(|x| Some(*x)).collect()
     }
 
+    #[test]
+    fn size() {
+        let base = size_of::<PrimitiveArray<Int64Type>>() as u64;
+
+        let v = super::FixedNull::<Int64Type>::from(vec![22, 33, 18].as_slice());
+        assert_eq!(v.size(), base + 24);
+
+        // 24 bytes of values and a byte of validity bitmap.
+        let v = super::FixedNull::<Int64Type>::from(vec![Some(22), None, Some(18)].as_slice());
+        assert_eq!(v.size(), base + 24 + 1);
+    }
+
     #[test]
     fn first_row_id_eq_value() {
         let v = super::FixedNull::<Int64Type>::from(vec![22, 33, 18].as_slice());
@@ -670,6 +696,14 @@ mod test {
         assert_eq!(v.min(&[0, 1, 2, 3, 4]), Some(1));
     }
 
+    #[test]
+    fn min_leading_null() {
+        let v = super::FixedNull::<Int64Type>::from(vec![None, Some(110), Some(20)].as_slice());
+
+        assert_eq!(v.min(&[0, 1, 2]), Some(20));
+        assert_eq!(v.min(&[0]), None);
+    }
+
     #[test]
     fn max() {
         let v = super::FixedNull::<Int16Type>::from(vec![100, 110, 20, 1, 109].as_slice());
@@ -0,0 +1,144 @@
+//! A HyperLogLog estimates the number of distinct values in a set using a
+//! small, fixed amount of memory. Sketches can be merged to estimate the
+//! number of distinct values in the union of their sets.
+//!
+//! This is the estimator described in Flajolet et al's "HyperLogLog: the
+//! analysis of a near-optimal cardinality estimation algorithm", including its
+//! small range correction. A 64-bit hash is used so the large range correction
+//! isn't needed.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The default precision, which uses `2^14` registers and gives a standard
+/// error of roughly 0.8%.
+pub const DEFAULT_PRECISION: u8 = 14;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    precision: u8,
+
+    // The largest rank seen for the hashes assigned to each register.
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRECISION)
+    }
+}
+
+impl HyperLogLog {
+    /// Creates a sketch with `2^precision` registers. Panics if `precision`
+    /// is not in the range `[4, 16]`.
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (4..=16).contains(&precision),
+            "precision {} must be between 4 and 16",
+            precision
+        );
+
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Adds a value to the sketch.
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        // The leading bits of the hash select the register, and the rank is
+        // the position of the first set bit in the remaining bits. The guard
+        // bit bounds the rank when all of the remaining bits are zero.
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank =
+            ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Merges another sketch into this one. Panics if the sketches have
+    /// different precisions.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(
+            self.precision, other.precision,
+            "cannot merge sketches with different precisions"
+        );
+
+        for (register, &other) in self.registers.iter_mut().zip(&other.registers) {
+            if other > *register {
+                *register = other;
+            }
+        }
+    }
+
+    /// Estimates the number of distinct values added to the sketch.
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum = self
+            .registers
+            .iter()
+            .map(|&rank| 2_f64.powi(-i32::from(rank)))
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+
+        // Linear counting is more accurate while many registers are empty.
+        let zeros = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn estimate_small() {
+        let mut hll = HyperLogLog::default();
+        assert_eq!(hll.estimate(), 0);
+
+        for v in &["west", "east", "west", "north", "east"] {
+            hll.insert(*v);
+        }
+        assert_eq!(hll.estimate(), 3);
+    }
+
+    #[test]
+    fn estimate_large() {
+        let mut hll = HyperLogLog::default();
+        for v in 0..100_000 {
+            hll.insert(&format!("host-{}", v));
+            hll.insert(&format!("host-{}", v / 2));
+        }
+
+        let got = hll.estimate() as f64;
+        assert!((got - 100_000.0).abs() / 100_000.0 < 0.03, "got {}", got);
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = HyperLogLog::default();
+        let mut b = HyperLogLog::default();
+        for v in 0..10_000 {
+            a.insert(&v);
+            b.insert(&(v + 5_000));
+        }
+
+        a.merge(&b);
+        let got = a.estimate() as f64;
+        assert!((got - 15_000.0).abs() / 15_000.0 < 0.03, "got {}", got);
+    }
+}
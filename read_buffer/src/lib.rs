@@ -4,21 +4,446 @@
 #![allow(unused_variables)]
 pub(crate) mod chunk;
 pub mod column;
+pub mod hll;
 pub mod row_group;
+pub mod spill;
 pub(crate) mod table;
+pub mod tdigest;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::sync::Arc;
 
-use arrow_deps::arrow::record_batch::RecordBatch;
+use arrow_deps::arrow::{
+    array::{
+        ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array,
+    },
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+
+use rayon::prelude::*;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 
 use chunk::Chunk;
-use column::AggregateType;
-use row_group::{ColumnName, Predicate};
+use column::{AggregateResult, AggregateType, LogicalDataType, Scalar, Value};
+use hll::HyperLogLog;
+use row_group::{ColumnName, GroupKey, Predicate, PredicateExpr, ValuePredicate};
+use spill::SpillConfig;
+use table::Table;
+
+/// The name of the column holding table names in the results of
+/// `table_names`.
+pub const TABLE_NAMES_COLUMN_NAME: &str = "table";
+
+/// The name of the column holding tag keys in the results of `tag_keys` and
+/// `tag_values`.
+pub const TAG_KEYS_COLUMN_NAME: &str = "tag_key";
+
+/// The name of the column holding tag values in the results of `tag_values`.
+pub const TAG_VALUES_COLUMN_NAME: &str = "tag_value";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("table {} not found", table_name))]
+    TableNotFound { table_name: String },
+
+    #[snafu(display("column {} not found in table {}", column_name, table_name))]
+    ColumnNotFound {
+        table_name: String,
+        column_name: String,
+    },
+
+    #[snafu(display(
+        "unsupported predicate on {:?} column {}: {}",
+        data_type,
+        column_name,
+        value
+    ))]
+    UnsupportedPredicate {
+        column_name: String,
+        data_type: LogicalDataType,
+        value: String,
+    },
+
+    #[snafu(display(
+        "unsupported predicate {} {} {} between {:?} and {:?} columns",
+        left_column,
+        op,
+        right_column,
+        left_type,
+        right_type
+    ))]
+    UnsupportedColumnPredicate {
+        left_column: String,
+        op: String,
+        right_column: String,
+        left_type: LogicalDataType,
+        right_type: LogicalDataType,
+    },
+
+    #[snafu(display(
+        "unsupported aggregate {} on {:?} column {}",
+        agg_type,
+        data_type,
+        column_name
+    ))]
+    UnsupportedAggregate {
+        column_name: String,
+        data_type: LogicalDataType,
+        agg_type: AggregateType,
+    },
+
+    #[snafu(display("invalid aggregate {} on column {}: {}", agg_type, column_name, reason))]
+    InvalidAggregate {
+        column_name: String,
+        agg_type: AggregateType,
+        reason: String,
+    },
+
+    #[snafu(display("unsupported grouping on {:?} column {}", data_type, column_name))]
+    UnsupportedGroupColumn {
+        column_name: String,
+        data_type: LogicalDataType,
+    },
+
+    #[snafu(display("unable to spill aggregate state at {}: {}", location, source))]
+    Spill {
+        location: String,
+        source: spill::StoreError,
+    },
+
+    #[snafu(display("spilled aggregate state at {} is corrupt", location))]
+    CorruptSpill { location: String },
+
+    #[snafu(display("unsupported delta on {:?} column {}", data_type, column_name))]
+    UnsupportedDelta {
+        column_name: String,
+        data_type: LogicalDataType,
+    },
+
+    #[snafu(display(
+        "column {} in table {} is {:?} in one chunk and {:?} in another",
+        column_name,
+        table_name,
+        first,
+        second
+    ))]
+    SchemaConflict {
+        table_name: String,
+        column_name: String,
+        first: ColumnType,
+        second: ColumnType,
+    },
+
+    #[snafu(display(
+        "adding chunk of {} bytes would exceed memory limit of {} bytes ({} bytes used)",
+        chunk_size,
+        limit,
+        used
+    ))]
+    MemoryLimitExceeded {
+        chunk_size: u64,
+        used: u64,
+        limit: u64,
+    },
+
+    #[snafu(display("chunk {} not found", chunk_key))]
+    ChunkNotFound { chunk_key: String },
+
+    #[snafu(display("chunk {} has no tables", chunk_id))]
+    EmptyChunk { chunk_id: u32 },
+
+    #[snafu(display("table {} has no rows", table_name))]
+    EmptyTable { table_name: String },
+
+    #[snafu(display("unsupported {:?} column {}", data_type, column_name))]
+    UnsupportedColumnType {
+        column_name: String,
+        data_type: DataType,
+    },
+
+    #[snafu(display("invalid time column: {}", reason))]
+    InvalidTimeColumn { reason: String },
+
+    #[snafu(display("invalid column type metadata for column {}: {}", column_name, reason))]
+    InvalidColumnTypeMetadata { column_name: String, reason: String },
+
+    #[snafu(display("max_row_group_rows must be greater than zero"))]
+    InvalidMaxRowGroupRows,
+
+    #[snafu(display("invalid window {} with step {}: {}", window, step, reason))]
+    InvalidWindow {
+        window: i64,
+        step: i64,
+        reason: String,
+    },
+
+    #[snafu(display("no chunks to compact"))]
+    NoChunksToCompact,
+
+    #[snafu(display("no chunk ids are available after chunk {}", last_id))]
+    ChunkIdsExhausted { last_id: u32 },
+
+    #[snafu(display(
+        "table {} has a different schema in some of the chunks being compacted",
+        table_name
+    ))]
+    IncompatibleSchemas { table_name: String },
+
+    #[snafu(display("invalid time range: start {} is after end {}", start, end))]
+    InvalidTimeRange { start: i64, end: i64 },
+
+    #[snafu(display("invalid regex {}: {}", pattern, source))]
+    InvalidRegex {
+        pattern: String,
+        source: regex::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A range of time `[start, end)`, in nanoseconds since the epoch. A missing
+/// bound leaves that side of the range unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+impl TimeRange {
+    /// Creates the time range `[start, end)`. An error is returned if `start`
+    /// is after `end`.
+    pub fn new(start: i64, end: i64) -> Result<Self> {
+        ensure!(start <= end, InvalidTimeRange { start, end });
+        Ok(Self {
+            start: Some(start),
+            end: Some(end),
+        })
+    }
+
+    /// A time range covering all times.
+    pub fn all() -> Self {
+        Self {
+            start: None,
+            end: None,
+        }
+    }
+
+    /// A time range covering all times at or after `start`.
+    pub fn since(start: i64) -> Self {
+        Self {
+            start: Some(start),
+            end: None,
+        }
+    }
+
+    /// A time range covering all times before `end`.
+    pub fn before(end: i64) -> Self {
+        Self {
+            start: None,
+            end: Some(end),
+        }
+    }
+
+    /// A time range covering no times.
+    pub fn empty() -> Self {
+        Self {
+            start: Some(0),
+            end: Some(0),
+        }
+    }
+
+    /// The inclusive start of the time range, or `None` if it's unbounded.
+    pub fn start(&self) -> Option<i64> {
+        self.start
+    }
+
+    /// The exclusive end of the time range, or `None` if it's unbounded.
+    pub fn end(&self) -> Option<i64> {
+        self.end
+    }
+
+    /// Determines if the time range covers no times.
+    pub fn is_empty(&self) -> bool {
+        matches!((self.start, self.end), (Some(start), Some(end)) if start >= end)
+    }
+
+    /// Determines if the inclusive range `[min, max]` overlaps the time range.
+    /// Nothing overlaps an empty time range.
+    pub fn overlaps(&self, min: i64, max: i64) -> bool {
+        !self.is_empty()
+            && self.end.map_or(true, |end| min < end)
+            && self.start.map_or(true, |start| max >= start)
+    }
+
+    /// Determines if the inclusive range `[min, max]` is entirely within the
+    /// time range.
+    pub fn contains(&self, min: i64, max: i64) -> bool {
+        self.start.map_or(true, |start| min >= start) && self.end.map_or(true, |end| max < end)
+    }
+}
+
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.start {
+            Some(start) => write!(f, "[{}, ", start)?,
+            None => write!(f, "(-inf, ")?,
+        }
+        match self.end {
+            Some(end) => write!(f, "{})", end),
+            None => write!(f, "+inf)"),
+        }
+    }
+}
+
+/// The type of a column in a table's schema: whether it's a tag, field or
+/// time column, along with the logical type of its values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Tag(LogicalDataType),
+    Field(LogicalDataType),
+    Time(LogicalDataType),
+}
+
+/// A description of how a selection is executed, as returned by
+/// `explain_select`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectExplanation {
+    /// The time range `[min, max)` used to prune chunks and row groups, after
+    /// any predicates bounding the time column have been folded into it.
+    pub time_range: TimeRange,
+
+    /// The predicates applied to the rows of each chunk that isn't pruned.
+    pub row_filters: Vec<String>,
+
+    /// The ids of the chunks that are scanned, in the order they're scanned.
+    pub chunk_ids: Vec<u32>,
+}
+
+impl std::fmt::Display for SelectExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut bounds = vec![];
+        if let Some(start) = self.time_range.start() {
+            bounds.push(format!("{} >= {}", row_group::TIME_COLUMN_NAME, start));
+        }
+        if let Some(end) = self.time_range.end() {
+            bounds.push(format!("{} < {}", row_group::TIME_COLUMN_NAME, end));
+        }
+
+        if bounds.is_empty() {
+            writeln!(f, "chunk pruning: none")?;
+        } else {
+            writeln!(f, "chunk pruning: {}", bounds.join(" AND "))?;
+        }
+
+        if self.row_filters.is_empty() {
+            writeln!(f, "row filters: none")?;
+        } else {
+            writeln!(f, "row filters: {}", self.row_filters.join(" AND "))?;
+        }
+
+        let chunk_ids = self
+            .chunk_ids
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        writeln!(f, "chunks: [{}]", chunk_ids.join(", "))
+    }
+}
+
+/// An estimate of the cost of a selection, as returned by `explain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlanEstimate {
+    /// The number of chunks that would be scanned after pruning.
+    pub chunks_scanned: usize,
+
+    /// The number of rows in the row groups of the scanned chunks whose
+    /// timestamps overlap the time range. Rows excluded by other predicates
+    /// are included, so this is an upper bound on the rows returned.
+    pub estimated_rows: u64,
+
+    /// The names of the columns read to evaluate the time range and
+    /// predicates, in sorted order.
+    pub columns: Vec<String>,
+}
+
+/// How the cardinality of a tag column is estimated across chunks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardinalityEstimate {
+    /// The sum of the dictionary sizes of each segment containing the column.
+    /// This is cheap to compute, but values present in several segments are
+    /// counted more than once, so it's an upper bound.
+    UpperBound,
+
+    /// A HyperLogLog sketch of the dictionary values of every segment. This is
+    /// a tighter estimate but requires hashing every dictionary value.
+    HyperLogLog,
+}
+
+impl Default for CardinalityEstimate {
+    fn default() -> Self {
+        Self::UpperBound
+    }
+}
+
+/// Where the time column is positioned amongst the columns returned by a
+/// selection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeColumnPosition {
+    /// The time column is the first column.
+    First,
+
+    /// The time column is the last column.
+    Last,
+
+    /// The time column is positioned where it was requested.
+    AsListed,
+}
+
+impl Default for TimeColumnPosition {
+    fn default() -> Self {
+        Self::AsListed
+    }
+}
+
+/// The default maximum number of rows in each row group of a chunk added to
+/// the `Store`.
+pub const DEFAULT_MAX_ROW_GROUP_ROWS: usize = 100_000;
+
+/// The maximum number of sliding windows that a single row may fall in, which
+/// bounds the work done per row when the `step` between windows is much
+/// smaller than the `window` itself.
+pub const MAX_WINDOWS_PER_ROW: i64 = 1_000;
+
+/// Configuration for a `Store`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreConfig {
+    /// The maximum total size of the store, in bytes. Chunks can't be added
+    /// once they would take the store over this size. There is no limit if
+    /// `None`.
+    pub max_bytes: Option<u64>,
+
+    /// The maximum number of rows in each row group of a chunk added via
+    /// `Store::add_chunk`.
+    pub max_row_group_rows: usize,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            max_row_group_rows: DEFAULT_MAX_ROW_GROUP_ROWS,
+        }
+    }
+}
 
 /// The `Store` is responsible for providing an execution engine for reading
 /// `Chunk` data.
 #[derive(Default)]
 pub struct Store {
+    config: StoreConfig,
+
     // A mapping from database name (tenant id, bucket id etc) to a database.
     databases: BTreeMap<String, Database>,
 
@@ -27,80 +452,392 @@ pub struct Store {
 }
 
 impl Store {
-    // TODO(edd): accept a configuration of some sort.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Creates a store with the provided configuration. An error is returned
+    /// if `max_row_group_rows` is zero.
+    pub fn with_config(config: StoreConfig) -> Result<Self> {
+        ensure!(config.max_row_group_rows > 0, InvalidMaxRowGroupRows);
+        Ok(Self {
+            config,
+            ..Self::default()
+        })
+    }
+
     /// Add a new database to the store
     pub fn add_database(&mut self, id: String, database: Database) {
         self.size += database.size();
         self.databases.insert(id, database);
     }
 
-    /// Remove an entire database from the store.
-    pub fn remove_database(&mut self, id: String) {
-        todo!()
+    /// Remove an entire database from the store, returning it if it existed.
+    /// The store's size is reduced by the size of the removed database.
+    pub fn remove_database(&mut self, id: String) -> Option<Database> {
+        let database = self.databases.remove(&id)?;
+        self.size -= database.size();
+        Some(database)
+    }
+
+    /// Removes a chunk from a database in the store, returning it if both
+    /// existed. The store's size is reduced by the size of the removed chunk.
+    pub fn remove_chunk(&mut self, database_id: &str, chunk_key: &str) -> Option<Chunk> {
+        let chunk = self
+            .databases
+            .get_mut(database_id)?
+            .remove_chunk(chunk_key)?;
+        self.size -= chunk.size();
+        Some(chunk)
+    }
+
+    /// The current total size of the store, in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The names of the databases in the store, in sorted order.
+    pub fn database_names(&self) -> Vec<String> {
+        self.databases.keys().cloned().collect()
+    }
+
+    /// The current total size of the store and the configured limit on it,
+    /// in bytes.
+    pub fn memory_status(&self) -> (u64, Option<u64>) {
+        (self.size, self.config.max_bytes)
     }
 
     /// This method adds a `Chunk` to the Read Buffer. It is probably what
     /// the `MutableBuffer` will call.
     ///
-    /// The chunk should comprise a single record batch for each table it
-    /// contains.
-    pub fn add_chunk(&mut self, database_id: String, chunk: BTreeMap<String, RecordBatch>) {
-        todo!()
+    /// The chunk should comprise a single, non-empty record batch for each
+    /// table it contains. The database is created if it doesn't exist, and
+    /// any chunk in it with the same id is replaced. An error is returned, and
+    /// the chunk isn't added, if the chunk can't be built from the record
+    /// batches (see `Chunk::from_record_batches`) or would take the store over
+    /// its configured memory limit.
+    pub fn add_chunk(
+        &mut self,
+        database_id: String,
+        chunk_id: u32,
+        chunk: BTreeMap<String, RecordBatch>,
+    ) -> Result<()> {
+        let chunk = Chunk::from_record_batches(chunk_id, chunk, self.config.max_row_group_rows)?;
+
+        // The space of a chunk that's replaced is freed up for its
+        // replacement.
+        let replaced_size = self
+            .databases
+            .get(&database_id)
+            .and_then(|db| db.chunks.get(&chunk_id.to_string()))
+            .map_or(0, Chunk::size);
+        let used = self.size - replaced_size;
+
+        let chunk_size = chunk.size();
+        if let Some(limit) = self.config.max_bytes {
+            ensure!(
+                used.saturating_add(chunk_size) <= limit,
+                MemoryLimitExceeded {
+                    chunk_size,
+                    used,
+                    limit,
+                }
+            );
+        }
+
+        self.databases
+            .entry(database_id)
+            .or_default()
+            .add_chunk(chunk);
+        self.size = used + chunk_size;
+        Ok(())
+    }
+
+    /// Adds several chunks to a database in the Read Buffer, in the same way
+    /// as `add_chunk`, returning the keys of the new chunks in order.
+    ///
+    /// Each chunk is assigned an id following the highest id of the chunks
+    /// already in the database. Either all of the chunks are added or, if
+    /// any of them can't be built or they would take the store over its
+    /// configured memory limit, none of them are and an error is returned.
+    pub fn add_chunks(
+        &mut self,
+        database_id: String,
+        chunks: Vec<BTreeMap<String, RecordBatch>>,
+    ) -> Result<Vec<String>> {
+        let last_id = self
+            .databases
+            .get(&database_id)
+            .and_then(|db| db.chunks.values().map(Chunk::id).max())
+            .unwrap_or(0);
+        let ids = next_chunk_ids(last_id, chunks.len())?;
+
+        // The chunks are only added once they're all known to fit, so a
+        // failure leaves the store as it was.
+        let mut used = self.size;
+        let mut new_chunks = Vec::with_capacity(chunks.len());
+        for (id, chunk) in ids.zip(chunks) {
+            let chunk = Chunk::from_record_batches(id, chunk, self.config.max_row_group_rows)?;
+
+            let chunk_size = chunk.size();
+            if let Some(limit) = self.config.max_bytes {
+                ensure!(
+                    used.saturating_add(chunk_size) <= limit,
+                    MemoryLimitExceeded {
+                        chunk_size,
+                        used,
+                        limit,
+                    }
+                );
+            }
+            used += chunk_size;
+            new_chunks.push(chunk);
+        }
+
+        let db = self.databases.entry(database_id).or_default();
+        let chunk_keys = new_chunks
+            .into_iter()
+            .map(|chunk| {
+                let chunk_key = chunk.id().to_string();
+                db.add_chunk(chunk);
+                chunk_key
+            })
+            .collect();
+        self.size = used;
+        Ok(chunk_keys)
     }
 
     /// Executes selections against matching chunks, returning a single
     /// record batch with all chunk results appended.
     ///
-    /// Results may be filtered by (currently only) equality predicates, but can
-    /// be ranged by time, which should be represented as nanoseconds since the
-    /// epoch. Results are included if they satisfy the predicate and fall
-    /// with the [min, max) time range domain.
+    /// Results may be filtered by conjunctive equality (`=`, `!=`) and
+    /// comparison (`<`, `<=`, `>`, `>=`) predicates, and can be ranged by
+    /// time, which should be represented as nanoseconds since the epoch.
+    /// Results are included if they satisfy the predicates and fall with the
+    /// [min, max) time range domain.
+    ///
+    /// Columns are returned in the order they appear in `select_columns`, or
+    /// all columns are returned if it is empty.
+    ///
+    /// The first `offset` rows are skipped and at most `limit` rows are
+    /// returned. If `dedup` is set only the most recently written row for
+    /// each series and timestamp is returned. See `Database::select`.
     pub fn select(
         &self,
         database_name: &str,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
         select_columns: Vec<String>,
-    ) -> Option<RecordBatch> {
+        limit: Option<usize>,
+        offset: usize,
+        dedup: bool,
+    ) -> Result<Option<RecordBatch>> {
         // Execute against matching database.
-        //
-        // TODO(edd): error handling on everything...................
-        //
         if let Some(db) = self.databases.get(database_name) {
-            return db.select(table_name, time_range, predicates, select_columns);
+            return db.select(
+                table_name,
+                time_range,
+                predicates,
+                select_columns,
+                limit,
+                offset,
+                dedup,
+            );
         }
-        None
+        Ok(None)
+    }
+
+    /// Executes the same selection against each of the tables, returning the
+    /// results keyed by table name. See `Database::select_tables`.
+    pub fn select_tables(
+        &self,
+        database_name: &str,
+        table_names: &[String],
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        select_columns: Vec<String>,
+    ) -> Result<BTreeMap<String, RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.select_tables(table_names, time_range, predicates, select_columns);
+        }
+        Ok(BTreeMap::new())
+    }
+
+    /// Executes the same selection against every database in the store,
+    /// returning the results keyed by database name.
+    ///
+    /// Databases that don't contain the table, or have no rows satisfying the
+    /// time range and predicates, are omitted from the results. An error is
+    /// returned if `select` would return one for any database containing the
+    /// table.
+    pub fn select_all_databases(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        select_columns: Vec<String>,
+    ) -> Result<BTreeMap<String, RecordBatch>> {
+        let mut results = BTreeMap::new();
+        for (database_name, db) in &self.databases {
+            if !db.has_table(table_name) {
+                continue;
+            }
+
+            let rb = db.select(
+                table_name,
+                time_range,
+                predicates,
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )?;
+
+            if let Some(rb) = rb.filter(|rb| rb.num_rows() > 0) {
+                results.insert(database_name.to_owned(), rb);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Counts the rows that `select` would return for the same table, time
+    /// range and predicates. See `Database::row_count`.
+    pub fn row_count(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<u64> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.row_count(table_name, time_range, predicates);
+        }
+        Ok(0)
+    }
+
+    /// Executes selections against matching chunks in the same way as
+    /// `select`, but returns each distinct row of the selected columns once.
+    /// See `Database::select_distinct`.
+    pub fn select_distinct(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        select_columns: Vec<String>,
+    ) -> Result<Option<RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.select_distinct(table_name, time_range, predicates, select_columns);
+        }
+        Ok(None)
+    }
+
+    /// Describes how `select` would execute for the time range and predicates,
+    /// without executing it. See `Database::explain_select`.
+    pub fn explain_select(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<Option<SelectExplanation>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db
+                .explain_select(table_name, time_range, predicates)
+                .map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Estimates the cost of `select` for the time range and predicates,
+    /// without executing it. See `Database::explain`.
+    pub fn explain(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<Option<QueryPlanEstimate>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.explain(table_name, time_range, predicates).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Executes selections against matching chunks, in the same way as
+    /// `select`, but with the time column, if selected, positioned according
+    /// to `time_position`. The other columns appear in the order requested.
+    pub fn select_with_time_position(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        select_columns: Vec<String>,
+        time_position: TimeColumnPosition,
+    ) -> Result<Option<RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.select_with_time_position(
+                table_name,
+                time_range,
+                predicates,
+                select_columns,
+                time_position,
+            );
+        }
+        Ok(None)
+    }
+
+    /// Executes selections against matching chunks, in the same way as
+    /// `select`, but results are filtered by a predicate expression, which
+    /// can combine predicates with both AND and OR.
+    pub fn select_expr(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        select_columns: Vec<String>,
+    ) -> Result<Option<RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.select_expr(table_name, time_range, expr, select_columns);
+        }
+        Ok(None)
     }
 
     /// Returns aggregates segmented by grouping keys for the specified
-    /// measurement as record batches, with one record batch per matching
-    /// chunk.
+    /// measurement as a single record batch, sorted by group key.
     ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
+    /// The set of data to be aggregated may be filtered by conjunctive equality
+    /// (`=`, `!=`) and comparison (`<`, `<=`, `>`, `>=`) predicates, and can be
+    /// ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Results are included if they satisfy the predicates and fall
+    /// with the [min, max) time range domain.
     ///
     /// Group keys are determined according to the provided group column names.
-    /// Currently only grouping by string (tag key) columns is supported.
+    /// String (tag key), integer, unsigned integer and boolean columns can be
+    /// grouped on.
     ///
-    /// Required aggregates are specified via a tuple comprising a column name
-    /// and the type of aggregation required. Multiple aggregations can be
-    /// applied to the same column.
+    /// Required aggregates are specified via a tuple comprising a column name,
+    /// the type of aggregation required and an optional predicate on the
+    /// column's values. Multiple aggregations can be applied to the same
+    /// column.
+    ///
+    /// Value predicates are distinct from `predicates`: rather than filtering
+    /// the rows being aggregated, only the values of the aggregated column
+    /// satisfying the predicate contribute to that aggregate. For example,
+    /// `("temp", AggregateType::Sum, Some((Operator::GT, Value::from(0_i64))))`
+    /// sums only the positive temperatures.
     pub fn aggregate(
         &self,
         database_name: &str,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
         group_columns: Vec<String>,
-        aggregates: Vec<(ColumnName<'_>, AggregateType)>,
-    ) -> Option<RecordBatch> {
+        aggregates: Vec<(ColumnName<'_>, AggregateType, Option<ValuePredicate<'_>>)>,
+    ) -> Result<Option<RecordBatch>> {
         if let Some(db) = self.databases.get(database_name) {
             return db.aggregate(
                 table_name,
@@ -110,19 +847,38 @@ impl Store {
                 aggregates,
             );
         }
-        None
+        Ok(None)
+    }
+
+    /// Returns aggregates segmented by grouping keys, in the same way as
+    /// `aggregate`, but the data to be aggregated is filtered by a predicate
+    /// expression, which can combine predicates with both AND and OR.
+    pub fn aggregate_expr(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType, Option<ValuePredicate<'_>>)>,
+    ) -> Result<Option<RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.aggregate_expr(table_name, time_range, expr, group_columns, aggregates);
+        }
+        Ok(None)
     }
 
     /// Returns aggregates segmented by grouping keys and windowed by time.
     ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
+    /// The set of data to be aggregated may be filtered by conjunctive equality
+    /// (`=`, `!=`) and comparison (`<`, `<=`, `>`, `>=`) predicates, and can be
+    /// ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Results are included if they satisfy the predicates and fall
+    /// with the [min, max) time range domain.
     ///
     /// Group keys are determined according to the provided group column names
-    /// (`group_columns`). Currently only grouping by string (tag key) columns
-    /// is supported.
+    /// (`group_columns`). String (tag key), integer, unsigned integer and
+    /// boolean columns can be grouped on.
     ///
     /// Required aggregates are specified via a tuple comprising a column name
     /// and the type of aggregation required. Multiple aggregations can be
@@ -130,17 +886,17 @@ impl Store {
     ///
     /// Results are grouped and windowed according to the `window` parameter,
     /// which represents an interval in nanoseconds. For example, to window
-    /// results by one minute, window should be set to 600_000_000_000.
+    /// results by one minute, window should be set to 60_000_000_000.
     pub fn aggregate_window(
         &self,
         database_name: &str,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
         group_columns: Vec<String>,
         aggregates: Vec<(ColumnName<'_>, AggregateType)>,
         window: i64,
-    ) -> Option<RecordBatch> {
+    ) -> Result<Option<RecordBatch>> {
         if let Some(db) = self.databases.get(database_name) {
             return db.aggregate_window(
                 table_name,
@@ -151,45 +907,164 @@ impl Store {
                 window,
             );
         }
-        None
+        Ok(None)
     }
 
-    //
-    // ---- Schema API queries
-    //
-
-    /// Returns the distinct set of table names that contain data that satisfies
-    /// the time range and predicates.
-    pub fn table_names(
+    /// Returns aggregates segmented by grouping keys and windowed by time,
+    /// where consecutive windows may overlap.
+    ///
+    /// Unlike `aggregate_window`, which uses tumbling (non-overlapping)
+    /// windows, a window of length `window` is emitted every `step`
+    /// nanoseconds. Window starts are aligned to multiples of `step`, and each
+    /// row contributes to every window containing it. For example, a `window`
+    /// of one minute and a `step` of 30 seconds smooths results over a minute
+    /// while emitting a result every 30 seconds.
+    ///
+    /// The remaining parameters behave as they do for `aggregate_window`.
+    pub fn aggregate_sliding_window(
         &self,
         database_name: &str,
-        time_range: (i64, i64),
+        table_name: &str,
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
-    ) -> Option<RecordBatch> {
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType)>,
+        window: i64,
+        step: i64,
+    ) -> Result<Option<RecordBatch>> {
         if let Some(db) = self.databases.get(database_name) {
-            return db.table_names(database_name, time_range, predicates);
-        }
-        None
-    }
-
-    /// Returns the distinct set of tag keys (column names) matching the
-    /// provided optional predicates and time range.
-    pub fn tag_keys(
+            return db.aggregate_sliding_window(
+                table_name,
+                time_range,
+                predicates,
+                group_columns,
+                aggregates,
+                window,
+                step,
+            );
+        }
+        Ok(None)
+    }
+
+    /// Returns the difference between each value of the `field` column and
+    /// its predecessor, for each group of rows, in time order.
+    ///
+    /// Groups are determined according to the provided group column names.
+    /// The first row of each group, rows with a NULL value, and integer
+    /// deltas that don't fit in an i64, have a NULL delta. Rows are ordered by
+    /// time across chunks, so the last value of a group in one chunk is the
+    /// predecessor of the first value in the next.
+    pub fn select_delta(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        field: &str,
+        group_columns: Vec<String>,
+    ) -> Result<Option<RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.select_delta(table_name, time_range, predicates, field, group_columns);
+        }
+        Ok(None)
+    }
+
+    //
+    // ---- Schema API queries
+    //
+
+    /// Returns the distinct set of table names that contain at least one row
+    /// satisfying the time range and predicates, as a single string column
+    /// sorted by name. `None` is returned if no tables match.
+    pub fn table_names(
+        &self,
+        database_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<Option<RecordBatch>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.table_names(database_name, time_range, predicates);
+        }
+        Ok(None)
+    }
+
+    /// Returns the name and type of each column in the table, for the chunks
+    /// overlapping the time range. See `Database::table_schema`.
+    pub fn table_schema(
         &self,
         database_name: &str,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
+    ) -> Result<Option<Vec<(String, ColumnType)>>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.table_schema(table_name, time_range);
+        }
+        Ok(None)
+    }
+
+    /// Returns the distinct set of table names that could contain rows
+    /// satisfying the time range and predicates, using only chunk time ranges
+    /// and table schemas. See `Database::table_names_fast`.
+    pub fn table_names_fast(
+        &self,
+        database_name: &str,
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
     ) -> Option<RecordBatch> {
+        self.databases
+            .get(database_name)?
+            .table_names_fast(time_range, predicates)
+    }
+
+    /// Returns each tag key matching the predicates and time range with its
+    /// number of distinct values. See `Database::tag_keys_with_counts`.
+    pub fn tag_keys_with_counts(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<BTreeMap<String, u64>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.tag_keys_with_counts(table_name, time_range, predicates);
+        }
+        Ok(BTreeMap::new())
+    }
+
+    /// Returns the distinct values of each of the tag keys matching the
+    /// predicates and time range. See `Database::tag_values_by_key`.
+    pub fn tag_values_by_key(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        tag_keys: &[String],
+    ) -> Result<BTreeMap<String, Vec<String>>> {
+        if let Some(db) = self.databases.get(database_name) {
+            return db.tag_values_by_key(table_name, time_range, predicates, tag_keys);
+        }
+        Ok(BTreeMap::new())
+    }
+
+    /// Returns the distinct set of tag keys (column names) matching the
+    /// provided optional predicates and time range.
+    pub fn tag_keys(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<Option<RecordBatch>> {
         if let Some(db) = self.databases.get(database_name) {
             return db.tag_keys(table_name, time_range, predicates);
         }
-        None
+        Ok(None)
     }
 
     /// Returns the distinct set of tag values (column values) for each provided
     /// tag key, where each returned value lives in a row matching the provided
-    /// optional predicates and time range.
+    /// optional predicates and time range. See `Database::tag_values`.
     ///
     /// As a special case, if `tag_keys` is empty then all distinct values for
     /// all columns (tag keys) are returned for the chunks.
@@ -197,41 +1072,464 @@ impl Store {
         &self,
         database_name: &str,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
         tag_keys: &[String],
-    ) -> Option<RecordBatch> {
+    ) -> Result<Option<RecordBatch>> {
         if let Some(db) = self.databases.get(database_name) {
             return db.tag_values(table_name, time_range, predicates, tag_keys);
         }
+        Ok(None)
+    }
+}
+
+// Executes the selection against the table in the chunk, returning the values
+// of each selected column, or `None` if the chunk doesn't contain the table.
+// Columns the chunk doesn't have are padded with NULLs.
+fn select_chunk_values<'a>(
+    chunk: &'a Chunk,
+    table_name: &str,
+    chunk_expr: &PredicateExpr<'_>,
+    columns: &[ColumnName<'_>],
+) -> Option<Vec<Vec<Value<'a>>>> {
+    if !chunk.contains_table(table_name) {
+        return None;
+    }
+
+    // There's no need to read any rows if the column ranges rule out the
+    // predicates.
+    if !chunk.could_satisfy_predicate_expr(table_name, chunk_expr) {
+        return Some(vec![vec![]; columns.len()]);
+    }
+
+    let results = chunk.select_expr(table_name, chunk_expr, columns)?;
+
+    let mut output_values = vec![vec![]; columns.len()];
+    for segment_result in &results.values {
+        if segment_result.is_empty() {
+            continue;
+        }
+        let rows = segment_result.rows();
+
+        for (i, name) in columns.iter().enumerate() {
+            match segment_result.column(name) {
+                Some(values) => output_values[i].extend((0..rows).map(|row| values.value(row))),
+                None => output_values[i].extend(std::iter::repeat(Value::Null).take(rows)),
+            }
+        }
+    }
+    Some(output_values)
+}
+
+// Executes the selection against the table in the chunk in the same way as
+// `select_chunk_values`, but returns each distinct row once, or `None` if the
+// chunk doesn't contain the table. Rows are deduplicated as each row group's
+// results are read, so the chunk's rows are never all held at once.
+fn distinct_chunk_rows<'a>(
+    chunk: &'a Chunk,
+    table_name: &str,
+    chunk_expr: &PredicateExpr<'_>,
+    columns: &[ColumnName<'_>],
+) -> Option<BTreeSet<GroupKey<'a>>> {
+    if !chunk.contains_table(table_name) {
+        return None;
+    }
+
+    let mut rows = BTreeSet::new();
+    if !chunk.could_satisfy_predicate_expr(table_name, chunk_expr) {
+        return Some(rows);
+    }
+
+    let results = chunk.select_expr(table_name, chunk_expr, columns)?;
+    for segment_result in &results.values {
+        if segment_result.is_empty() {
+            continue;
+        }
+
+        let values = columns
+            .iter()
+            .map(|name| segment_result.column(name))
+            .collect::<Vec<_>>();
+        for row in 0..segment_result.rows() {
+            let key = values
+                .iter()
+                .map(|values| values.map_or(Value::Null, |values| values.value(row)))
+                .collect::<Vec<_>>();
+            rows.insert(GroupKey::from(key));
+        }
+    }
+    Some(rows)
+}
+
+// Merges the distinct rows of two sets of chunks, either of which may not
+// contain the table.
+fn merge_distinct_rows<'a>(
+    a: Option<BTreeSet<GroupKey<'a>>>,
+    b: Option<BTreeSet<GroupKey<'a>>>,
+) -> Option<BTreeSet<GroupKey<'a>>> {
+    match (a, b) {
+        (Some(mut a), Some(b)) => {
+            a.extend(b);
+            Some(a)
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+// Appends the values yielded for each chunk into a single set of columns,
+// with `columns` columns. If `rows_needed` is provided no further chunks are
+// read once at least that many rows have been appended. `None` is returned if
+// no chunks yielded values.
+fn append_chunk_values<'a>(
+    chunk_values: impl Iterator<Item = Vec<Vec<Value<'a>>>>,
+    columns: usize,
+    rows_needed: Option<usize>,
+) -> Option<Vec<Vec<Value<'a>>>> {
+    let mut found = false;
+    let mut output_values = vec![vec![]; columns];
+    for values in chunk_values {
+        found = true;
+        for (output, values) in output_values.iter_mut().zip(values) {
+            output.extend(values);
+        }
+
+        let rows_found = output_values.first().map_or(0, Vec::len);
+        if rows_needed.map_or(false, |rows_needed| rows_found >= rows_needed) {
+            break;
+        }
+    }
+
+    if found {
+        Some(output_values)
+    } else {
         None
     }
 }
 
-/// Generate a predicate for the time range [from, to).
-pub fn time_range_predicate<'a>(from: i64, to: i64) -> Vec<row_group::Predicate<'a>> {
-    vec![
-        (
+// Folds the predicates bounding the time column into the time range,
+// returning the narrowed range along with the predicates that must still be
+// applied to each row. Duplicate predicates are removed. An empty range is
+// returned if the bounds rule out every time, e.g., `time > 10 AND time < 5`,
+// in which case no chunks overlap it.
+fn plan_predicates<'a>(
+    time_range: TimeRange,
+    predicates: &[Predicate<'a>],
+) -> (TimeRange, Vec<Predicate<'a>>) {
+    use column::cmp::Operator;
+
+    let TimeRange {
+        start: mut min,
+        end: mut max,
+    } = time_range;
+
+    // Narrowing an unbounded side of the range always takes the new bound.
+    // The exclusive end after `i64::MAX` can't be represented, so bounds
+    // including `i64::MAX` leave the end unbounded, and nothing is after it.
+    let narrow_min = |min: Option<i64>, v: i64| Some(min.map_or(v, |min| min.max(v)));
+    let narrow_max = |max: Option<i64>, v: Option<i64>| match (max, v) {
+        (Some(max), Some(v)) => Some(max.min(v)),
+        (max, v) => max.or(v),
+    };
+    let mut empty = false;
+    let mut row_filters: Vec<Predicate<'a>> = vec![];
+    for predicate in predicates {
+        let time_bound = match predicate {
+            (row_group::TIME_COLUMN_NAME, (op, Value::Scalar(Scalar::I64(v)))) => Some((*op, *v)),
+            _ => None,
+        };
+
+        match time_bound {
+            Some((Operator::GT, v)) => match v.checked_add(1) {
+                Some(v) => min = narrow_min(min, v),
+                None => empty = true,
+            },
+            Some((Operator::GTE, v)) => min = narrow_min(min, v),
+            Some((Operator::LT, v)) => max = narrow_max(max, Some(v)),
+            Some((Operator::LTE, v)) => max = narrow_max(max, v.checked_add(1)),
+            Some((Operator::Equal, v)) => {
+                min = narrow_min(min, v);
+                max = narrow_max(max, v.checked_add(1));
+            }
+            _ => {
+                if !row_filters.contains(predicate) {
+                    row_filters.push(*predicate);
+                }
+            }
+        }
+    }
+
+    let time_range = TimeRange {
+        start: min,
+        end: max,
+    };
+    if empty || time_range.is_empty() {
+        return (TimeRange::empty(), row_filters);
+    }
+    (time_range, row_filters)
+}
+
+// Moves the time column, if present, to the position specified.
+fn position_time_column(mut columns: Vec<String>, position: TimeColumnPosition) -> Vec<String> {
+    let i = match columns
+        .iter()
+        .position(|name| name == row_group::TIME_COLUMN_NAME)
+    {
+        Some(i) => i,
+        None => return columns,
+    };
+
+    match position {
+        TimeColumnPosition::First => {
+            let time = columns.remove(i);
+            columns.insert(0, time);
+        }
+        TimeColumnPosition::Last => {
+            let time = columns.remove(i);
+            columns.push(time);
+        }
+        TimeColumnPosition::AsListed => {}
+    }
+    columns
+}
+
+// Merges the aggregates for a group key into the aggregates already held for
+// the group key, if any.
+fn merge_group<'a>(
+    groups: &mut BTreeMap<GroupKey<'a>, Vec<AggregateResult<'a>>>,
+    group_key: &GroupKey<'a>,
+    aggregates: &[AggregateResult<'a>],
+) {
+    match groups.get_mut(group_key) {
+        Some(existing) => {
+            for (existing, other) in existing.iter_mut().zip(aggregates) {
+                existing.merge(other);
+            }
+        }
+        None => {
+            groups.insert(group_key.clone(), aggregates.to_vec());
+        }
+    }
+}
+
+// The runs spilled by a query. Runs are removed from the spill store when
+// this is dropped, so that they're cleaned up however the query finishes.
+struct SpilledRuns<'a, 'b> {
+    spill: &'a SpillConfig<'b>,
+    locations: Vec<String>,
+}
+
+impl<'a, 'b> SpilledRuns<'a, 'b> {
+    fn new(spill: &'a SpillConfig<'b>) -> Self {
+        Self {
+            spill,
+            locations: vec![],
+        }
+    }
+
+    // Writes the groups to the spill store as a new run.
+    fn spill(&mut self, groups: &BTreeMap<GroupKey<'_>, Vec<AggregateResult<'_>>>) -> Result<()> {
+        let location = self.spill.run_location(self.locations.len());
+        // The run is tracked before it's written so that anything partially
+        // written is removed too.
+        self.locations.push(location.clone());
+        self.spill
+            .store
+            .put(&location, spill::encode_run(groups.iter()))
+            .context(Spill { location })
+    }
+
+    // Reads the runs back one at a time, merging each into the runs read
+    // before it. Only the merged groups and a single run are held in memory
+    // at once, and the runs are sorted by group key, so each is merged in a
+    // single pass. The merged groups are returned as an encoded run.
+    fn merge(&self, key_len: usize, agg_types: &[AggregateType]) -> Result<Vec<u8>> {
+        let mut merged = vec![];
+        for location in &self.locations {
+            let data = self.spill.store.get(location).context(Spill { location })?;
+            let next = {
+                let run_rows = spill::decode_run(&data, key_len, agg_types)
+                    .context(CorruptSpill { location })?;
+                let merged_rows = spill::decode_run(&merged, key_len, agg_types)
+                    .context(CorruptSpill { location })?;
+
+                let rows = spill::merge_runs(merged_rows, run_rows);
+                spill::encode_run(
+                    rows.iter()
+                        .map(|(group_key, aggregates)| (group_key, aggregates)),
+                )
+            };
+            merged = next;
+        }
+        Ok(merged)
+    }
+
+    // Removes the runs from the spill store, returning the first error.
+    fn remove(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for location in self.locations.drain(..) {
+            let removed = self.spill.store.delete(&location);
+            if result.is_ok() {
+                result = removed.context(Spill { location });
+            }
+        }
+        result
+    }
+}
+
+impl Drop for SpilledRuns<'_, '_> {
+    fn drop(&mut self) {
+        // Errors can't be returned from here, and at worst leave unused runs
+        // in the spill store.
+        let _ = self.remove();
+    }
+}
+
+// Builds a record batch with a row for each group key, sorted by group key.
+fn groups_record_batch(
+    columns: &[(String, LogicalDataType)],
+    groups: &BTreeMap<GroupKey<'_>, Vec<AggregateResult<'_>>>,
+) -> Option<RecordBatch> {
+    let mut values = vec![Vec::with_capacity(groups.len()); columns.len()];
+    for (group_key, aggregates) in groups {
+        let row = group_key
+            .values()
+            .iter()
+            .cloned()
+            .chain(aggregates.iter().flat_map(AggregateResult::values));
+        for (column, value) in values.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+
+    let columns = columns
+        .iter()
+        .map(|(name, data_type)| (name.as_str(), *data_type))
+        .collect::<Vec<_>>();
+    record_batch_from_values(&columns, &values)
+}
+
+// Returns the difference between two numeric values, or NULL if either value is
+// NULL. Unsigned values produce a signed difference. Integer differences that
+// don't fit in an i64 are NULL.
+fn value_delta<'a>(prev: Value<'a>, curr: Value<'a>) -> Value<'a> {
+    match (prev, curr) {
+        (Value::Scalar(Scalar::I64(prev)), Value::Scalar(Scalar::I64(curr))) => {
+            curr.checked_sub(prev).map_or(Value::Null, Value::from)
+        }
+        (Value::Scalar(Scalar::U64(prev)), Value::Scalar(Scalar::U64(curr))) => {
+            i64::try_from(i128::from(curr) - i128::from(prev)).map_or(Value::Null, Value::from)
+        }
+        (Value::Scalar(Scalar::F64(prev)), Value::Scalar(Scalar::F64(curr))) => {
+            Value::from(curr - prev)
+        }
+        _ => Value::Null,
+    }
+}
+
+// The ids of `count` chunks added after the chunk with id `last_id`. An error
+// is returned if there aren't enough ids left.
+fn next_chunk_ids(last_id: u32, count: usize) -> Result<impl Iterator<Item = u32>> {
+    let available = (u32::MAX - last_id) as usize;
+    ensure!(count <= available, ChunkIdsExhausted { last_id });
+    Ok((1..=count as u32).map(move |i| last_id + i))
+}
+
+// The greatest common divisor of two positive integers.
+fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+// Returns the start of each window of length `window` containing `time`, where
+// window starts are aligned to multiples of `step`. Windows starting before
+// `i64::MIN` can't be represented and are omitted.
+fn window_starts(time: i64, window: i64, step: i64) -> impl Iterator<Item = i64> {
+    let last = time.div_euclid(step).checked_mul(step);
+    std::iter::successors(last, move |start| start.checked_sub(step)).take_while(move |start| {
+        // `time - start` only overflows when the window is far too early.
+        time.checked_sub(*start)
+            .map_or(false, |offset| offset < window)
+    })
+}
+
+/// The aggregates that can be applied to columns in `aggregate` and related
+/// queries. Aggregates that take an argument are listed with an example
+/// argument.
+pub fn supported_aggregates() -> &'static [AggregateType] {
+    // A constant is needed because the histogram bounds prevent the slice
+    // from being promoted to a static.
+    const AGGREGATES: &[AggregateType] = &[
+        AggregateType::Count,
+        AggregateType::First,
+        AggregateType::Last,
+        AggregateType::Min,
+        AggregateType::Max,
+        AggregateType::Sum,
+        AggregateType::ArgMax,
+        AggregateType::ArgMin,
+        AggregateType::Percentile(50.0),
+        AggregateType::ApproxQuantile(50.0),
+        AggregateType::Mean,
+        AggregateType::StdDev,
+        AggregateType::Variance,
+        AggregateType::Histogram(Vec::new()),
+        AggregateType::CountDistinct { count_null: false },
+        AggregateType::CountTrue,
+        AggregateType::CountFalse,
+    ];
+    AGGREGATES
+}
+
+/// The operators that can be used in predicates.
+pub fn supported_operators() -> &'static [column::cmp::Operator] {
+    use column::cmp::Operator;
+    &[
+        Operator::Equal,
+        Operator::NotEqual,
+        Operator::GT,
+        Operator::GTE,
+        Operator::LT,
+        Operator::LTE,
+        Operator::RegexMatch,
+        Operator::RegexNotMatch,
+        Operator::RegexMatchAny,
+        Operator::IsNull,
+        Operator::IsNotNull,
+    ]
+}
+
+/// Generate a predicate for the time range. No predicate is generated for an
+/// unbounded side of the range.
+pub fn time_range_predicate<'a>(time_range: TimeRange) -> Vec<row_group::Predicate<'a>> {
+    let mut predicates = vec![];
+    if let Some(start) = time_range.start() {
+        predicates.push((
             row_group::TIME_COLUMN_NAME,
             (
                 column::cmp::Operator::GTE,
-                column::Value::Scalar(column::Scalar::I64(from)),
+                column::Value::Scalar(column::Scalar::I64(start)),
             ),
-        ),
-        (
+        ));
+    }
+    if let Some(end) = time_range.end() {
+        predicates.push((
             row_group::TIME_COLUMN_NAME,
             (
                 column::cmp::Operator::LT,
-                column::Value::Scalar(column::Scalar::I64(to)),
+                column::Value::Scalar(column::Scalar::I64(end)),
             ),
-        ),
-    ]
+        ));
+    }
+    predicates
 }
 
 // A database is scoped to a single tenant. Within a database there exists
 // tables for measurements. There is a 1:1 mapping between a table and a
 // measurement name.
-#[derive(Default)]
 pub struct Database {
     // The collection of chunks in the database. Each chunk is uniquely
     // identified by a chunk key.
@@ -239,6 +1537,106 @@ pub struct Database {
 
     // The current total size of the database.
     size: u64,
+
+    // Whether chunks are executed against in parallel.
+    parallel: bool,
+
+    // An index of the chunks containing timestamps, keyed by the start of
+    // each chunk's time range and its chunk key, to the end of the range.
+    // Queries only consider the chunks starting before the end of their time
+    // range.
+    time_index: BTreeMap<(i64, String), i64>,
+
+    // The schema of the tables in the chunks, which answers schema queries
+    // spanning all time without visiting each chunk.
+    schema_cache: SchemaCache,
+
+    // The insertion sequence number of each chunk, keyed by chunk key. Rows in
+    // chunks with higher sequence numbers were written more recently, which
+    // determines the rows kept when deduplicating.
+    sequences: BTreeMap<String, u64>,
+
+    // The sequence number of the next chunk to be added.
+    next_sequence: u64,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self {
+            chunks: BTreeMap::new(),
+            size: 0,
+            parallel: true,
+            time_index: BTreeMap::new(),
+            schema_cache: SchemaCache::default(),
+            sequences: BTreeMap::new(),
+            next_sequence: 0,
+        }
+    }
+}
+
+// The schema of the tables in the chunks containing timestamps, which are
+// those visited by queries spanning all time. It's extended as chunks are
+// added, and rebuilt from the remaining chunks when one is removed.
+#[derive(Default)]
+struct SchemaCache {
+    // The tables with at least one row.
+    table_names: BTreeSet<String>,
+
+    tables: BTreeMap<String, TableSchemaCache>,
+}
+
+#[derive(Default)]
+struct TableSchemaCache {
+    columns: BTreeMap<String, ColumnType>,
+
+    // The tag columns with at least one non-null value.
+    tag_keys: BTreeSet<String>,
+
+    // Whether chunks disagree on the type of a column, in which case the
+    // schema isn't served from the cache so that the conflict is reported.
+    conflicting: bool,
+}
+
+impl SchemaCache {
+    fn new<'a>(chunks: impl IntoIterator<Item = &'a Chunk>) -> Self {
+        let mut cache = Self::default();
+        for chunk in chunks {
+            cache.add_chunk(chunk);
+        }
+        cache
+    }
+
+    fn add_chunk(&mut self, chunk: &Chunk) {
+        if chunk.time_range().is_none() {
+            return;
+        }
+
+        let no_predicates: &[Predicate<'_>] = &[];
+        let table_names = chunk.table_names(&PredicateExpr::from(no_predicates), &self.table_names);
+        self.table_names.extend(table_names);
+
+        for table_name in chunk.table_names_with_columns(&[], &BTreeSet::new()) {
+            let table = self.tables.entry(table_name.clone()).or_default();
+            for (column_name, column_type) in chunk.column_types(&table_name).unwrap_or_default() {
+                match table.columns.get(column_name) {
+                    Some(&first) => table.conflicting |= first != column_type,
+                    None => {
+                        table.columns.insert(column_name.to_owned(), column_type);
+                    }
+                }
+            }
+
+            for tag_key in chunk.tag_column_names(&table_name).unwrap_or_default() {
+                let has_values = chunk
+                    .tag_column_dictionaries(&table_name, tag_key)
+                    .iter()
+                    .any(|dictionary| !dictionary.is_empty());
+                if has_values {
+                    table.tag_keys.insert(tag_key.to_owned());
+                }
+            }
+        }
+    }
 }
 
 impl Database {
@@ -246,88 +1644,1021 @@ impl Database {
         Self::default()
     }
 
+    /// Sets whether queries execute against each chunk in parallel, which is
+    /// the default. Results are the same either way.
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel = parallel;
+    }
+
+    /// Adds a chunk to the database. Chunks are keyed by their id, and any
+    /// chunk with the same id is replaced.
+    ///
+    /// The rows of each added chunk are considered to have been written more
+    /// recently than those of the chunks added before it, regardless of chunk
+    /// ids. See `select`.
     pub fn add_chunk(&mut self, chunk: Chunk) {
-        todo!()
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.insert_chunk(chunk, sequence);
+    }
+
+    // Adds a chunk with the provided insertion sequence number, replacing any
+    // existing chunk with the same key.
+    fn insert_chunk(&mut self, chunk: Chunk, sequence: u64) {
+        let chunk_key = chunk.id().to_string();
+        self.remove_chunk(&chunk_key);
+
+        self.sequences.insert(chunk_key.clone(), sequence);
+        self.size += chunk.size();
+        if let Some((min, max)) = chunk.time_range() {
+            self.time_index.insert((min, chunk_key.clone()), max);
+        }
+        self.schema_cache.add_chunk(&chunk);
+        self.chunks.insert(chunk_key, chunk);
     }
 
-    pub fn remove_chunk(&mut self, chunk: Chunk) {
-        todo!()
+    /// Removes the chunk with the provided key from the database, returning
+    /// it if it existed. The database's size is reduced by the size of the
+    /// removed chunk.
+    pub fn remove_chunk(&mut self, chunk_key: &str) -> Option<Chunk> {
+        let chunk = self.chunks.remove(chunk_key)?;
+        self.sequences.remove(chunk_key);
+        self.size -= chunk.size();
+        if let Some((min, _)) = chunk.time_range() {
+            self.time_index.remove(&(min, chunk_key.to_owned()));
+        }
+        self.schema_cache = SchemaCache::new(self.overlapping_chunks(TimeRange::all()));
+        Some(chunk)
     }
 
     pub fn size(&self) -> u64 {
         self.size
     }
 
+    /// Determines if any chunk in the database contains the specified table,
+    /// without running a query.
+    pub fn has_table(&self, table_name: &str) -> bool {
+        self.chunks
+            .values()
+            .any(|chunk| chunk.contains_table(table_name))
+    }
+
+    /// Merges the chunks with the provided keys into a single new chunk,
+    /// which replaces them in the database. The new chunk's key is returned.
+    ///
+    /// The segments of each table are moved into the new chunk as they are,
+    /// in the order the chunks were added, so every row is preserved along
+    /// with the order in which rows were written. The new chunk takes the
+    /// place of the most recently added of the merged chunks when
+    /// deduplicating. A table must have the same schema in each of the chunks
+    /// that contain it. Nothing is changed if an error is returned.
+    pub fn compact_chunks(&mut self, chunk_keys: &[String]) -> Result<String> {
+        let chunk_keys = chunk_keys.iter().collect::<BTreeSet<_>>();
+        ensure!(!chunk_keys.is_empty(), NoChunksToCompact);
+
+        let mut chunks = chunk_keys
+            .iter()
+            .map(|&key| {
+                self.chunks
+                    .get(key)
+                    .context(ChunkNotFound { chunk_key: key })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        chunks.sort_by_key(|chunk| self.sequence(chunk));
+        let sequence = chunks.last().map(|chunk| self.sequence(chunk)).unwrap_or(0);
+
+        let mut schemas = BTreeMap::new();
+        for chunk in &chunks {
+            for table_name in chunk.table_names_with_columns(&[], &BTreeSet::new()) {
+                let schema = chunk.column_types(&table_name);
+                match schemas.get(&table_name) {
+                    Some(existing) => {
+                        ensure!(existing == &schema, IncompatibleSchemas { table_name })
+                    }
+                    None => {
+                        schemas.insert(table_name, schema);
+                    }
+                }
+            }
+        }
+
+        let chunk_ids = chunks.iter().map(|chunk| chunk.id()).collect::<Vec<_>>();
+        let last_id = self.chunks.values().map(Chunk::id).max().unwrap_or(0);
+        let id = last_id
+            .checked_add(1)
+            .context(ChunkIdsExhausted { last_id })?;
+
+        // All of the chunks were found above, so each of them is removed.
+        let mut tables: BTreeMap<String, Table> = BTreeMap::new();
+        let removed = chunk_ids
+            .iter()
+            .filter_map(|chunk_id| self.remove_chunk(&chunk_id.to_string()))
+            .collect::<Vec<_>>();
+        for chunk in removed {
+            for table in chunk.into_tables() {
+                match tables.get_mut(table.name()) {
+                    Some(existing) => {
+                        for segment in table.into_segments() {
+                            existing.add_segment(segment);
+                        }
+                    }
+                    None => {
+                        tables.insert(table.name().to_owned(), table);
+                    }
+                }
+            }
+        }
+
+        // Chunks always have at least one table, so the compacted chunk does.
+        let mut chunk: Option<Chunk> = None;
+        for (_, table) in tables {
+            match &mut chunk {
+                Some(chunk) => chunk.add_table(table),
+                None => chunk = Some(Chunk::new(id, table)),
+            }
+        }
+        if let Some(chunk) = chunk {
+            self.insert_chunk(chunk, sequence);
+        }
+        Ok(id.to_string())
+    }
+
+    // The insertion sequence number of a chunk in the database.
+    fn sequence(&self, chunk: &Chunk) -> u64 {
+        self.sequences[&chunk.id().to_string()]
+    }
+
+    /// The keys of the chunks in the database, in sorted order.
+    pub fn chunk_keys(&self) -> Vec<String> {
+        self.chunks.keys().cloned().collect()
+    }
+
+    /// The inclusive range of timestamps of the table across all chunks, or
+    /// `None` if no chunk contains timestamps for the table.
+    pub fn time_bounds(&self, table_name: &str) -> Option<(i64, i64)> {
+        self.chunks
+            .values()
+            .filter_map(|chunk| chunk.table_time_range(table_name))
+            .fold(None, |bounds, (min, max)| match bounds {
+                None => Some((min, max)),
+                Some((a, b)) => Some((a.min(min), b.max(max))),
+            })
+    }
+
     /// Executes selections against matching chunks, returning a single
     /// record batch with all chunk results appended.
     ///
-    /// Results may be filtered by (currently only) equality predicates, but can
-    /// be ranged by time, which should be represented as nanoseconds since the
-    /// epoch. Results are included if they satisfy the predicate and fall
-    /// with the [min, max) time range domain.
+    /// Results may be filtered by conjunctive equality (`=`, `!=`) and
+    /// comparison (`<`, `<=`, `>`, `>=`) predicates, and can be ranged by
+    /// time, which should be represented as nanoseconds since the epoch.
+    /// Results are included if they satisfy the predicates and fall with the
+    /// [min, max) time range domain.
+    ///
+    /// Columns are returned in the order they appear in `select_columns`.
+    /// Selected columns that are missing from some of the matching chunks are
+    /// NULL for the rows from those chunks. An empty `select_columns` selects
+    /// all columns, i.e., `select *`: the union of the table's columns in the
+    /// chunks overlapping the time range, ordered by name, so that a table
+    /// whose schema has evolved across chunks can be read in full.
+    ///
+    /// An error is returned if no chunk in the database contains the table, if
+    /// no chunk contains a selected column, or if a predicate isn't supported
+    /// for the column it's applied to. Otherwise `None` is returned if none of
+    /// the chunks overlapping the time range contain the table.
+    ///
+    /// Chunks are read in order of their ids. The first `offset` rows of the
+    /// appended results are skipped and at most `limit` rows are returned.
+    /// Once enough rows have been found to satisfy the limit no further chunks
+    /// are scanned.
+    ///
+    /// Predicates bounding the time column are folded into the time range
+    /// before chunks are pruned. See `explain_select`.
+    ///
+    /// Chunks can overlap, for example when data is backfilled, so the same
+    /// series may have several rows with the same timestamp. If `dedup` is set
+    /// only the most recently written of those rows is returned, where the
+    /// series is identified by the values of every tag column. Rows in chunks
+    /// added to the database later are more recent than rows in chunks added
+    /// earlier, and later rows within a chunk more recent than earlier ones.
+    /// Deduplicated rows are returned ordered by series and then timestamp,
+    /// and `offset` and `limit` are applied after deduplication.
     pub fn select(
         &self,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
         select_columns: Vec<String>,
-    ) -> Option<RecordBatch> {
-        // Find all matching chunks using:
-        //   - time range
-        //   - measurement name.
-        //
-        // Execute against each chunk and append each result set into a
-        // single record batch.
-        todo!();
+        limit: Option<usize>,
+        offset: usize,
+        dedup: bool,
+    ) -> Result<Option<RecordBatch>> {
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        if dedup {
+            return self.select_deduplicated(
+                table_name,
+                time_range,
+                &row_filters,
+                select_columns,
+                limit,
+                offset,
+            );
+        }
+
+        self.select_expr_with_limit(
+            table_name,
+            time_range,
+            &PredicateExpr::from(row_filters.as_slice()),
+            select_columns,
+            limit,
+            offset,
+        )
     }
 
-    /// Returns aggregates segmented by grouping keys for the specified
-    /// measurement as record batches, with one record batch per matching
-    /// chunk.
-    ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
-    ///
-    /// Group keys are determined according to the provided group column names.
-    /// Currently only grouping by string (tag key) columns is supported.
+    /// Executes the same selection, in the same way as `select`, against each
+    /// of the tables, returning the results keyed by table name.
     ///
-    /// Required aggregates are specified via a tuple comprising a column name
-    /// and the type of aggregation required. Multiple aggregations can be
-    /// applied to the same column.
-    pub fn aggregate(
+    /// Tables with no rows satisfying the time range and predicates are
+    /// omitted from the results. An error is returned if `select` would
+    /// return one for any of the tables.
+    pub fn select_tables(
         &self,
-        table_name: &str,
-        time_range: (i64, i64),
+        table_names: &[String],
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
-        group_columns: Vec<String>,
-        aggregates: Vec<(ColumnName<'_>, AggregateType)>,
-    ) -> Option<RecordBatch> {
-        // Find all matching chunks using:
-        //   - time range
-        //   - measurement name.
-        //
-        // Execute query against each matching chunk and get result set.
-        // For each result set it may be possible for there to be duplicate
-        // group keys, e.g., due to back-filling. So chunk results may need
-        // to be merged together with the aggregates from identical group keys
-        // being resolved.
+        select_columns: Vec<String>,
+    ) -> Result<BTreeMap<String, RecordBatch>> {
+        let mut results = BTreeMap::new();
+        for table_name in table_names {
+            let rb = self.select(
+                table_name,
+                time_range,
+                predicates,
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )?;
+
+            if let Some(rb) = rb.filter(|rb| rb.num_rows() > 0) {
+                results.insert(table_name.to_owned(), rb);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Counts the rows that `select` would return for the same table, time
+    /// range and predicates, without materialising any column values.
+    ///
+    /// Row groups lying entirely within the time range are counted from
+    /// their metadata when there are no predicates other than those bounding
+    /// the time column. An error is returned if no chunk contains the table,
+    /// or if a predicate isn't supported for the column it's applied to.
+    pub fn row_count(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<u64> {
+        ensure!(self.has_table(table_name), TableNotFound { table_name });
+        self.validate_predicates(table_name, predicates)?;
+
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        let expr = PredicateExpr::from(row_filters.as_slice());
+        let chunks = self.overlapping_chunks(time_range);
+        let count = |chunk: &&Chunk| chunk.row_count(table_name, time_range, &expr);
+
+        if self.parallel {
+            Ok(chunks.par_iter().map(count).sum())
+        } else {
+            Ok(chunks.iter().map(count).sum())
+        }
+    }
+
+    /// Executes selections against matching chunks in the same way as
+    /// `select`, but each distinct row of the selected columns is returned
+    /// once, e.g., the distinct `(region, host)` pairs. Rows are deduplicated
+    /// across chunks and returned ordered by their values. An empty selection
+    /// selects every column, as with `select`.
+    ///
+    /// The rows of each chunk are deduplicated as the chunk is scanned, and
+    /// only its distinct rows are merged into the result, so memory use is
+    /// proportional to the cardinality of the selected columns rather than to
+    /// the number of rows scanned.
+    pub fn select_distinct(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        select_columns: Vec<String>,
+    ) -> Result<Option<RecordBatch>> {
+        self.validate_predicates(table_name, predicates)?;
+
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        let select_columns = self.resolve_select_columns(table_name, time_range, select_columns)?;
+        let mut chunk_predicates = time_range_predicate(time_range);
+        chunk_predicates.extend_from_slice(&row_filters);
+        let chunk_expr = PredicateExpr::from(chunk_predicates.as_slice());
+
+        let columns = select_columns
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let output_columns = columns
+            .iter()
+            .map(|&name| Ok((name, self.column_type(table_name, name)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Columns missing from a chunk are NULL for the rows from that chunk.
+        let chunks = self.overlapping_chunks(time_range);
+        let rows = if self.parallel {
+            chunks
+                .par_iter()
+                .map(|chunk| distinct_chunk_rows(chunk, table_name, &chunk_expr, &columns))
+                .reduce(|| None, merge_distinct_rows)
+        } else {
+            chunks
+                .iter()
+                .map(|chunk| distinct_chunk_rows(chunk, table_name, &chunk_expr, &columns))
+                .fold(None, merge_distinct_rows)
+        };
+
+        let rows = match rows {
+            Some(rows) => rows,
+            None => return Ok(None),
+        };
+
+        let mut output_values = vec![vec![]; output_columns.len()];
+        for row in &rows {
+            for (i, &value) in row.values().iter().enumerate() {
+                output_values[i].push(value);
+            }
+        }
+
+        Ok(record_batch_from_values(&output_columns, &output_values))
+    }
+
+    /// Describes how `select` would execute for the time range and predicates,
+    /// without reading any rows.
+    ///
+    /// Predicates on the time column with `=`, `<`, `<=`, `>` or `>=` and an
+    /// integer value are folded into the time range used to prune chunks and
+    /// row groups. The remaining predicates, with any duplicates removed, are
+    /// applied to the rows of each scanned chunk. Chunks whose column ranges
+    /// rule out the predicates aren't scanned. An error is returned if no
+    /// chunk contains the table, or if a predicate isn't supported for the
+    /// column it's applied to.
+    pub fn explain_select(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<SelectExplanation> {
+        ensure!(self.has_table(table_name), TableNotFound { table_name });
+        self.validate_predicates(table_name, predicates)?;
+
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        let chunk_ids = self
+            .scanned_chunks(table_name, time_range, &row_filters)
+            .into_iter()
+            .map(|chunk| chunk.id())
+            .collect::<Vec<_>>();
+
+        Ok(SelectExplanation {
+            time_range,
+            row_filters: row_filters
+                .iter()
+                .map(|(column_name, (op, value))| format!("{} {} {}", column_name, op, value))
+                .collect(),
+            chunk_ids,
+        })
+    }
+
+    /// Estimates the cost of `select` for the time range and predicates
+    /// without reading any rows: the number of chunks that would be scanned,
+    /// an upper bound on the rows read from them and the columns those rows
+    /// are filtered on.
+    ///
+    /// Chunks are pruned in the same way as `explain_select`, and rows are
+    /// estimated from row group metadata. An error is returned if no chunk
+    /// contains the table, or if a predicate isn't supported for the column
+    /// it's applied to.
+    pub fn explain(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<QueryPlanEstimate> {
+        ensure!(self.has_table(table_name), TableNotFound { table_name });
+        self.validate_predicates(table_name, predicates)?;
+
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        let chunks = self.scanned_chunks(table_name, time_range, &row_filters);
+
+        let mut columns = row_filters
+            .iter()
+            .map(|(column_name, _)| column_name.to_string())
+            .collect::<BTreeSet<_>>();
+        if time_range.start().is_some() || time_range.end().is_some() {
+            columns.insert(row_group::TIME_COLUMN_NAME.to_string());
+        }
+
+        Ok(QueryPlanEstimate {
+            chunks_scanned: chunks.len(),
+            estimated_rows: chunks
+                .iter()
+                .map(|chunk| chunk.estimated_rows(table_name, time_range))
+                .sum(),
+            columns: columns.into_iter().collect(),
+        })
+    }
+
+    // The chunks containing the table that could have rows within the time
+    // range satisfying the row filters, based on their metadata, in order of
+    // chunk id.
+    fn scanned_chunks(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        row_filters: &[Predicate<'_>],
+    ) -> Vec<&Chunk> {
+        let mut chunk_predicates = time_range_predicate(time_range);
+        chunk_predicates.extend_from_slice(row_filters);
+        let chunk_expr = PredicateExpr::from(chunk_predicates.as_slice());
+
+        self.overlapping_chunks(time_range)
+            .into_iter()
+            .filter(|chunk| chunk.could_satisfy_predicate_expr(table_name, &chunk_expr))
+            .collect()
+    }
+
+    // Executes `select` with deduplication, for the row filters remaining once
+    // time predicates have been folded into the time range.
+    fn select_deduplicated(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        row_filters: &[Predicate<'_>],
+        select_columns: Vec<String>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Result<Option<RecordBatch>> {
+        self.validate_predicates(table_name, row_filters)?;
+        let select_columns = self.resolve_select_columns(table_name, time_range, select_columns)?;
+
+        let mut chunk_predicates = time_range_predicate(time_range);
+        chunk_predicates.extend_from_slice(row_filters);
+        let chunk_expr = PredicateExpr::from(chunk_predicates.as_slice());
+
+        let columns = select_columns
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let output_columns = columns
+            .iter()
+            .map(|&name| Ok((name, self.column_type(table_name, name)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Chunks are visited in the order they were added rather than by id.
+        let mut chunks = self.overlapping_chunks(time_range);
+        chunks.sort_by_key(|chunk| self.sequence(chunk));
+
+        // The series key is made up of the tag columns from all chunks. Chunks
+        // missing some of them have NULL values for those columns.
+        let mut key_columns = chunks
+            .iter()
+            .filter_map(|chunk| chunk.tag_column_names(table_name))
+            .flatten()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        key_columns.push(row_group::TIME_COLUMN_NAME);
+
+        let mut read_columns = key_columns.clone();
+        read_columns.extend(columns.iter().filter(|name| !key_columns.contains(*name)));
+
+        // Rows are visited in write order, so later rows replace earlier rows
+        // with the same key.
+        let mut found = false;
+        let mut rows = BTreeMap::new();
+        for chunk in chunks {
+            let results = match chunk.select_expr(table_name, &chunk_expr, &read_columns) {
+                Some(results) => results,
+                None => continue,
+            };
+            found = true;
+
+            for segment_result in &results.values {
+                if segment_result.is_empty() {
+                    continue;
+                }
+                let row_count = segment_result.0[0].1.len();
+
+                let column_values = |name: &str| {
+                    segment_result
+                        .0
+                        .iter()
+                        .find(|(column, _)| *column == name)
+                        .map(|(_, values)| values)
+                };
+                let key_values = key_columns
+                    .iter()
+                    .map(|&name| column_values(name))
+                    .collect::<Vec<_>>();
+                let output_values = output_columns
+                    .iter()
+                    .map(|&(name, _)| column_values(name))
+                    .collect::<Vec<_>>();
+
+                for row in 0..row_count {
+                    let key = key_values
+                        .iter()
+                        .map(|values| values.map_or(Value::Null, |values| values.value(row)))
+                        .collect::<Vec<_>>();
+                    let values = output_values
+                        .iter()
+                        .map(|values| values.map_or(Value::Null, |values| values.value(row)))
+                        .collect::<Vec<_>>();
+                    rows.insert(GroupKey::from(key), values);
+                }
+            }
+        }
+
+        if !found {
+            return Ok(None);
+        }
+
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut output_values = vec![vec![]; output_columns.len()];
+        for (_, row) in rows.into_iter().skip(offset).take(limit) {
+            for (i, value) in row.into_iter().enumerate() {
+                output_values[i].push(value);
+            }
+        }
+
+        Ok(record_batch_from_values(&output_columns, &output_values))
+    }
+
+    /// Executes selections against matching chunks, in the same way as
+    /// `select`, but with the time column, if selected, positioned according
+    /// to `time_position`. The other columns appear in the order requested.
+    pub fn select_with_time_position(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        select_columns: Vec<String>,
+        time_position: TimeColumnPosition,
+    ) -> Result<Option<RecordBatch>> {
+        self.select(
+            table_name,
+            time_range,
+            predicates,
+            position_time_column(select_columns, time_position),
+            None,
+            0,
+            false,
+        )
+    }
+
+    /// Executes selections against matching chunks, in the same way as
+    /// `select`, but results are filtered by a predicate expression, which
+    /// can combine predicates with both AND and OR.
+    pub fn select_expr(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        select_columns: Vec<String>,
+    ) -> Result<Option<RecordBatch>> {
+        self.select_expr_with_limit(table_name, time_range, expr, select_columns, None, 0)
+    }
+
+    // Executes `select_expr`, skipping the first `offset` rows and returning
+    // at most `limit` rows.
+    fn select_expr_with_limit(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        select_columns: Vec<String>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Result<Option<RecordBatch>> {
+        self.validate_expr(table_name, expr)?;
+        let select_columns = self.resolve_select_columns(table_name, time_range, select_columns)?;
+
+        let time_predicates = time_range_predicate(time_range);
+        let chunk_expr = PredicateExpr::from(time_predicates.as_slice()).and(expr.clone());
+
+        let columns = select_columns
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let output_columns = columns
+            .iter()
+            .map(|&name| Ok((name, self.column_type(table_name, name)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let rows_needed = limit.map(|limit| offset.saturating_add(limit));
+        // Chunks are only scanned lazily when a limit may allow some of them
+        // to be skipped.
+        let chunk_values: Box<dyn Iterator<Item = _>> = match rows_needed {
+            Some(_) => Box::new(self.chunk_values(
+                table_name,
+                time_range,
+                chunk_expr,
+                select_columns.clone(),
+            )),
+            None => Box::new(
+                self.all_chunk_values(table_name, time_range, &chunk_expr, &select_columns)
+                    .into_iter(),
+            ),
+        };
+        let mut output_values =
+            match append_chunk_values(chunk_values, output_columns.len(), rows_needed) {
+                Some(output_values) => output_values,
+                None => return Ok(None),
+            };
+
+        if offset > 0 || limit.is_some() {
+            let limit = limit.unwrap_or(usize::MAX);
+            for values in &mut output_values {
+                *values = values.drain(..).skip(offset).take(limit).collect();
+            }
+        }
+
+        Ok(record_batch_from_values(&output_columns, &output_values))
+    }
+
+    /// Executes selections against matching chunks in the same way as
+    /// `select`, but rather than appending all of the results into a single
+    /// record batch, a record batch is lazily produced for each chunk that
+    /// contains the table, in order of chunk id. This allows large results to
+    /// be streamed without materialising them all at once.
+    ///
+    /// Every batch has the same schema, with columns missing from a chunk
+    /// being NULL in its batch. Errors are returned before any chunk is
+    /// scanned.
+    pub fn select_stream<'a>(
+        &'a self,
+        table_name: &'a str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'a>],
+        select_columns: Vec<String>,
+    ) -> Result<Box<dyn Iterator<Item = RecordBatch> + 'a>> {
+        self.validate_predicates(table_name, predicates)?;
+
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        let select_columns = self.resolve_select_columns(table_name, time_range, select_columns)?;
+        let output_columns = select_columns
+            .iter()
+            .map(|name| Ok((name.to_owned(), self.column_type(table_name, name)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut chunk_predicates = time_range_predicate(time_range);
+        chunk_predicates.extend_from_slice(&row_filters);
+        let chunk_expr = PredicateExpr::from(chunk_predicates.as_slice());
+
+        let batches = self
+            .chunk_values(table_name, time_range, chunk_expr, select_columns)
+            .filter_map(move |values| {
+                let output_columns = output_columns
+                    .iter()
+                    .map(|(name, data_type)| (name.as_str(), *data_type))
+                    .collect::<Vec<_>>();
+                record_batch_from_values(&output_columns, &values)
+            });
+        Ok(Box::new(batches))
+    }
+
+    // Lazily executes the selection against each chunk containing the table
+    // and overlapping the time range, in order of chunk id, yielding the
+    // values of each selected column for the chunk. Columns the chunk doesn't
+    // have are padded with NULLs.
+    fn chunk_values<'a>(
+        &'a self,
+        table_name: &'a str,
+        time_range: TimeRange,
+        chunk_expr: PredicateExpr<'a>,
+        select_columns: Vec<String>,
+    ) -> impl Iterator<Item = Vec<Vec<Value<'a>>>> + 'a {
+        self.overlapping_chunks(time_range)
+            .into_iter()
+            .filter_map(move |chunk| {
+                let columns = select_columns
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                select_chunk_values(chunk, table_name, &chunk_expr, &columns)
+            })
+    }
+
+    // The same as `chunk_values`, but every chunk is executed against,
+    // in parallel if enabled. The values are returned in order of chunk id
+    // regardless of the order in which chunks complete.
+    fn all_chunk_values<'a>(
+        &'a self,
+        table_name: &str,
+        time_range: TimeRange,
+        chunk_expr: &PredicateExpr<'a>,
+        select_columns: &[String],
+    ) -> Vec<Vec<Vec<Value<'a>>>> {
+        let chunks = self.overlapping_chunks(time_range);
+        let columns = select_columns
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let select =
+            |chunk: &&'a Chunk| select_chunk_values(*chunk, table_name, chunk_expr, &columns);
+
+        if self.parallel {
+            chunks.par_iter().filter_map(select).collect()
+        } else {
+            chunks.iter().filter_map(select).collect()
+        }
+    }
+
+    // Resolves the columns selected from the table. An empty selection, i.e.,
+    // `select *`, selects the union of the table's columns in each of the
+    // chunks overlapping the time range, ordered by name.
+    fn resolve_select_columns(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        select_columns: Vec<String>,
+    ) -> Result<Vec<String>> {
+        if !select_columns.is_empty() {
+            return Ok(select_columns);
+        }
+
+        ensure!(self.has_table(table_name), TableNotFound { table_name });
+        let columns = self
+            .overlapping_chunks(time_range)
+            .into_iter()
+            .filter_map(|chunk| chunk.column_types(table_name))
+            .flat_map(|column_types| column_types.into_iter().map(|(name, _)| name.to_owned()))
+            .collect::<BTreeSet<_>>();
+        Ok(columns.into_iter().collect())
+    }
+
+    // The chunks overlapping the time range, in order of chunk id. Only the
+    // chunks starting before the end of the time range are visited.
+    fn overlapping_chunks(&self, time_range: TimeRange) -> Vec<&Chunk> {
+        let candidates: Box<dyn Iterator<Item = (&(i64, String), &i64)>> = match time_range.end() {
+            Some(end) => Box::new(self.time_index.range(..(end, String::new()))),
+            None => Box::new(self.time_index.iter()),
+        };
+
+        let mut chunks = candidates
+            .filter(|&((min, _), &max)| time_range.overlaps(*min, max))
+            .map(|((_, chunk_key), _)| &self.chunks[chunk_key])
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk| chunk.id());
+        chunks
+    }
+
+    /// Returns aggregates segmented by grouping keys for the specified
+    /// measurement as a single record batch, sorted by group key.
+    ///
+    /// The set of data to be aggregated may be filtered by conjunctive equality
+    /// (`=`, `!=`) and comparison (`<`, `<=`, `>`, `>=`) predicates, and can be
+    /// ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Results are included if they satisfy the predicates and fall
+    /// with the [min, max) time range domain.
+    ///
+    /// Group keys are determined according to the provided group column names.
+    /// String (tag key), integer, unsigned integer and boolean columns can be
+    /// grouped on.
+    ///
+    /// Required aggregates are specified via a tuple comprising a column name,
+    /// the type of aggregation required and an optional predicate on the
+    /// column's values. Multiple aggregations can be applied to the same
+    /// column. Aggregate columns are named `{column}_{aggregate}` in the
+    /// result, e.g., `temp_sum`.
+    ///
+    /// Different chunks may produce the same group key, e.g., due to
+    /// back-filling. The aggregates for such group keys are merged, so each
+    /// group key appears once in the result. `None` is returned if no chunks
+    /// contain the table and columns within the time range. An error is
+    /// returned if no chunk contains the table or a requested column, or if a
+    /// predicate or aggregate isn't supported for the column it's applied to.
+    pub fn aggregate(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType, Option<ValuePredicate<'_>>)>,
+    ) -> Result<Option<RecordBatch>> {
+        self.aggregate_expr(
+            table_name,
+            time_range,
+            &PredicateExpr::from(predicates),
+            group_columns,
+            aggregates,
+        )
+    }
+
+    /// Returns aggregates segmented by grouping keys, in the same way as
+    /// `aggregate`, but the data to be aggregated is filtered by a predicate
+    /// expression, which can combine predicates with both AND and OR.
+    pub fn aggregate_expr(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType, Option<ValuePredicate<'_>>)>,
+    ) -> Result<Option<RecordBatch>> {
+        self.aggregate_with_optional_spilling(
+            table_name,
+            time_range,
+            expr,
+            group_columns,
+            aggregates,
+            None,
+        )
+    }
+
+    /// Returns aggregates segmented by grouping keys, in the same way as
+    /// `aggregate`, while bounding the number of groups held in memory.
+    ///
+    /// Whenever more than `spill.max_groups` groups are held in memory, their
+    /// partial aggregates are spilled to `spill.store` as a run. Once all
+    /// chunks have been processed the runs are read back and merged, one run
+    /// at a time. The runs are removed from the store however the query
+    /// finishes, including when an error is returned.
+    pub fn aggregate_with_spilling(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType, Option<ValuePredicate<'_>>)>,
+        spill: &SpillConfig<'_>,
+    ) -> Result<Option<RecordBatch>> {
+        self.aggregate_with_optional_spilling(
+            table_name,
+            time_range,
+            &PredicateExpr::from(predicates),
+            group_columns,
+            aggregates,
+            Some(spill),
+        )
+    }
+
+    fn aggregate_with_optional_spilling<'a>(
+        &'a self,
+        table_name: &str,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType, Option<ValuePredicate<'_>>)>,
+        spill: Option<&SpillConfig<'_>>,
+    ) -> Result<Option<RecordBatch>> {
+        let time_predicates = time_range_predicate(time_range);
+        let chunk_expr = PredicateExpr::from(time_predicates.as_slice()).and(expr.clone());
+
+        let group_columns = group_columns.iter().map(String::as_str).collect::<Vec<_>>();
+        let (aggregate_columns, value_predicates): (Vec<_>, Vec<_>) = aggregates
+            .iter()
+            .map(|(name, agg_type, value_predicate)| ((*name, agg_type.clone()), *value_predicate))
+            .unzip();
+
+        self.validate_expr(table_name, expr)?;
+        let value_predicates_by_column = aggregates
+            .iter()
+            .filter_map(|&(name, _, value_predicate)| value_predicate.map(|pred| (name, pred)))
+            .collect::<Vec<_>>();
+        self.validate_predicates(table_name, &value_predicates_by_column)?;
+        let output_columns =
+            self.aggregate_output_columns(table_name, &group_columns, false, &aggregate_columns)?;
+
+        // Find all matching chunks using:
+        //   - time range
+        //   - measurement name.
         //
-        // Finally a record batch is returned.
-        todo!()
+        // and execute against each of them. Each chunk may produce group keys
+        // that other chunks have also produced, so the aggregates from
+        // identical group keys are merged together. The ordered map also
+        // ensures the result is sorted by group key.
+        let chunks = self
+            .overlapping_chunks(time_range)
+            .into_iter()
+            .filter(|chunk| {
+                // Chunks missing the columns needed to satisfy the predicates
+                // can be skipped entirely.
+                expr.could_be_satisfied(&|(name, _)| {
+                    chunk.column_logical_datatype(table_name, name).is_some()
+                })
+            })
+            .collect::<Vec<_>>();
+        // Chunks whose column ranges rule out the predicates still match, but
+        // there's no need to aggregate any of their rows.
+        let aggregate = |chunk: &&'a Chunk| {
+            if !chunk.contains_table(table_name) {
+                return None;
+            } else if !chunk.could_satisfy_predicate_expr(table_name, &chunk_expr) {
+                return Some(None);
+            }
+            (*chunk)
+                .aggregate_expr(
+                    table_name,
+                    &chunk_expr,
+                    &group_columns,
+                    &aggregate_columns,
+                    &value_predicates,
+                )
+                .map(Some)
+        };
+
+        // Chunks are executed against in parallel unless partial aggregates
+        // are being spilled, since that requires the results of only a few
+        // chunks to be held at a time. Either way, results are merged in the
+        // same order.
+        let chunk_results: Box<dyn Iterator<Item = _>> = if self.parallel && spill.is_none() {
+            Box::new(
+                chunks
+                    .par_iter()
+                    .filter_map(aggregate)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )
+        } else {
+            Box::new(chunks.iter().filter_map(aggregate))
+        };
+
+        let mut matched = false;
+        let mut groups: BTreeMap<GroupKey<'_>, Vec<AggregateResult<'_>>> = BTreeMap::new();
+        let mut runs = spill.map(SpilledRuns::new);
+        for results in chunk_results {
+            matched = true;
+            let results = match results {
+                Some(results) => results,
+                None => continue,
+            };
+
+            for segment_result in results.values() {
+                let rows = segment_result
+                    .group_keys()
+                    .iter()
+                    .zip(segment_result.aggregates());
+                for (group_key, aggregates) in rows {
+                    merge_group(&mut groups, group_key, aggregates);
+                }
+
+                if let Some(runs) = &mut runs {
+                    if groups.len() > runs.spill.max_groups {
+                        runs.spill(&groups)?;
+                        groups.clear();
+                    }
+                }
+            }
+        }
+
+        if !matched {
+            return Ok(None);
+        }
+
+        let mut runs = match runs {
+            Some(runs) if !runs.locations.is_empty() => runs,
+            _ => return Ok(groups_record_batch(&output_columns, &groups)),
+        };
+
+        // Spill the remaining groups so that all partial aggregates can be
+        // merged from the runs.
+        if !groups.is_empty() {
+            runs.spill(&groups)?;
+            groups.clear();
+        }
+
+        let agg_types = aggregate_columns
+            .iter()
+            .map(|(_, agg_type)| agg_type.clone())
+            .collect::<Vec<_>>();
+        let merged_run = runs.merge(group_columns.len(), &agg_types)?;
+        runs.remove()?;
+
+        let merged = spill::decode_run(&merged_run, group_columns.len(), &agg_types)
+            .context(CorruptSpill {
+                location: &runs.spill.prefix,
+            })?
+            .into_iter()
+            .collect();
+        Ok(groups_record_batch(&output_columns, &merged))
     }
 
     /// Returns aggregates segmented by grouping keys and windowed by time.
     ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
+    /// The set of data to be aggregated may be filtered by conjunctive equality
+    /// (`=`, `!=`) and comparison (`<`, `<=`, `>`, `>=`) predicates, and can be
+    /// ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Results are included if they satisfy the predicates and fall
+    /// with the [min, max) time range domain.
     ///
     /// Group keys are determined according to the provided group column names
-    /// (`group_columns`). Currently only grouping by string (tag key) columns
-    /// is supported.
+    /// (`group_columns`). String (tag key), integer, unsigned integer and
+    /// boolean columns can be grouped on.
     ///
     /// Required aggregates are specified via a tuple comprising a column name
     /// and the type of aggregation required. Multiple aggregations can be
@@ -335,84 +2666,6092 @@ impl Database {
     ///
     /// Results are grouped and windowed according to the `window` parameter,
     /// which represents an interval in nanoseconds. For example, to window
-    /// results by one minute, window should be set to 600_000_000_000.
+    /// results by one minute, window should be set to 60_000_000_000. A row
+    /// falls in the window starting at `floor(time / window) * window`, so
+    /// rows exactly on a boundary start a new window, and the first and last
+    /// windows may extend beyond the time range if `window` doesn't evenly
+    /// divide it.
+    ///
+    /// The result contains the group columns, followed by a `time` column
+    /// holding the start of each window, followed by the aggregate columns.
+    /// Rows from different chunks falling in the same group key and window
+    /// have their aggregates merged.
     pub fn aggregate_window(
         &self,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
         group_columns: Vec<String>,
         aggregates: Vec<(ColumnName<'_>, AggregateType)>,
         window: i64,
-    ) -> Option<RecordBatch> {
-        // Find all matching chunks using:
-        //   - time range
-        //   - measurement name.
-        //
-        // Execute query against each matching chunk and get result set.
-        // For each result set it may be possible for there to be duplicate
-        // group keys, e.g., due to back-filling. So chunk results may need
-        // to be merged together with the aggregates from identical group keys
-        // being resolved.
-        //
-        // Finally a record batch is returned.
-        todo!()
-    }
-
-    //
-    // ---- Schema API queries
-    //
-
-    /// Returns the distinct set of table names that contain data that satisfies
-    /// the time range and predicates.
-    pub fn table_names(
-        &self,
-        database_name: &str,
-        time_range: (i64, i64),
-        predicates: &[Predicate<'_>],
-    ) -> Option<RecordBatch> {
-        //
-        // TODO(edd): do we want to add the ability to apply a predicate to the
-        // table names? For example, a regex where you only want table names
-        // beginning with /cpu.+/ or something?
-        todo!()
+    ) -> Result<Option<RecordBatch>> {
+        // Tumbling windows are sliding windows that don't overlap.
+        self.aggregate_sliding_window(
+            table_name,
+            time_range,
+            predicates,
+            group_columns,
+            aggregates,
+            window,
+            window,
+        )
     }
 
-    /// Returns the distinct set of tag keys (column names) matching the
-    /// provided optional predicates and time range.
-    pub fn tag_keys(
-        &self,
+    /// Returns aggregates segmented by grouping keys and windowed by time,
+    /// where consecutive windows may overlap.
+    ///
+    /// A window of length `window` is emitted every `step` nanoseconds. Window
+    /// starts are aligned to multiples of `step`, and each row contributes to
+    /// every window containing it, so a window may start before the queried
+    /// time range.
+    ///
+    /// The result contains the group columns, followed by a `time` column
+    /// holding the start of each window, followed by the aggregate columns,
+    /// which are named `{column}_{aggregate}`. Rows are sorted by group key
+    /// and then window start. Rows from different chunks falling in the same
+    /// group key and window have their aggregates merged. `None` is returned
+    /// if no chunks contain the table within the time range. An error is
+    /// returned if no chunk contains the table or a requested column, or if a
+    /// predicate or aggregate isn't supported for the column it's applied to.
+    /// An error is also returned if `window` or `step` aren't positive, or if a
+    /// row would fall in more than `MAX_WINDOWS_PER_ROW` windows.
+    pub fn aggregate_sliding_window<'a>(
+        &'a self,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
-    ) -> Option<RecordBatch> {
-        // Find all matching chunks using:
-        //   - time range
-        //   - measurement name.
-        //
-        // Execute query against matching chunks. The `tag_keys` method for
-        // a chunk allows the caller to provide already found tag keys
-        // (column names). This allows the execution to skip entire chunks,
-        // tables or segments if there are no new columns to be found there...
-        todo!();
+        group_columns: Vec<String>,
+        aggregates: Vec<(ColumnName<'_>, AggregateType)>,
+        window: i64,
+        step: i64,
+    ) -> Result<Option<RecordBatch>> {
+        ensure!(
+            window > 0,
+            InvalidWindow {
+                window,
+                step,
+                reason: "window must be positive",
+            }
+        );
+        ensure!(
+            step > 0,
+            InvalidWindow {
+                window,
+                step,
+                reason: "step must be positive",
+            }
+        );
+        // The number of windows each row falls in, rounded up.
+        ensure!(
+            (window - 1) / step < MAX_WINDOWS_PER_ROW,
+            InvalidWindow {
+                window,
+                step,
+                reason: format!(
+                    "a row can't fall in more than {} windows",
+                    MAX_WINDOWS_PER_ROW
+                ),
+            }
+        );
+
+        let mut chunk_predicates = time_range_predicate(time_range);
+        chunk_predicates.extend_from_slice(predicates);
+        let chunk_expr = PredicateExpr::from(chunk_predicates.as_slice());
+
+        let group_columns = group_columns.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.validate_predicates(table_name, predicates)?;
+        let output_columns =
+            self.aggregate_output_columns(table_name, &group_columns, true, &aggregates)?;
+
+        // Each row group aggregates its rows by group key and the time bucket
+        // they fall in. Buckets divide both `window` and `step`, so all of the
+        // rows in a bucket fall in the same windows, and the aggregates of each
+        // bucket are merged into every window containing it.
+        let bucket = gcd(window, step);
+        let chunks = self
+            .overlapping_chunks(time_range)
+            .into_iter()
+            .filter(|chunk| {
+                // Chunks missing the columns needed to satisfy the predicates
+                // can be skipped entirely.
+                chunk_expr.could_be_satisfied(&|(name, _)| {
+                    chunk.column_logical_datatype(table_name, name).is_some()
+                })
+            })
+            .collect::<Vec<_>>();
+        // Chunks whose column ranges rule out the predicates still match, but
+        // there's no need to aggregate any of their rows.
+        let aggregate = |chunk: &&'a Chunk| {
+            if !chunk.contains_table(table_name) {
+                return None;
+            } else if !chunk.could_satisfy_predicate_expr(table_name, &chunk_expr) {
+                return Some(None);
+            }
+            (*chunk)
+                .aggregate_window_expr(table_name, &chunk_expr, &group_columns, &aggregates, bucket)
+                .map(Some)
+        };
+        let chunk_results = if self.parallel {
+            chunks.par_iter().filter_map(aggregate).collect::<Vec<_>>()
+        } else {
+            chunks.iter().filter_map(aggregate).collect::<Vec<_>>()
+        };
+
+        if chunk_results.is_empty() {
+            return Ok(None);
+        }
+
+        // Each window is identified by a group key comprising the group column
+        // values and the window start. Windows from different chunks with the
+        // same group key are merged, and the ordered map ensures the result is
+        // sorted by group key.
+        let mut windows: BTreeMap<GroupKey<'_>, Vec<AggregateResult<'_>>> = BTreeMap::new();
+        for results in chunk_results.iter().flatten() {
+            for segment_result in results.values() {
+                let rows = segment_result
+                    .group_keys()
+                    .iter()
+                    .zip(segment_result.aggregates());
+                for (group_key, bucket_aggregates) in rows {
+                    let (bucket_start, group_values) = group_key
+                        .values()
+                        .split_last()
+                        .expect("bucketed group keys end with the bucket start");
+                    // Buckets that would start before `i64::MIN` have a NULL
+                    // start, and aren't contained by any window that can be
+                    // represented.
+                    let bucket_start = match bucket_start {
+                        Value::Scalar(Scalar::I64(start)) => *start,
+                        _ => continue,
+                    };
+
+                    for start in window_starts(bucket_start, window, step) {
+                        let mut key = group_values.to_vec();
+                        key.push(Value::from(start));
+                        merge_group(&mut windows, &GroupKey::from(key), bucket_aggregates);
+                    }
+                }
+            }
+        }
+
+        let mut output_values = vec![Vec::with_capacity(windows.len()); output_columns.len()];
+        for (group_key, aggregates) in &windows {
+            let row = group_key
+                .values()
+                .iter()
+                .cloned()
+                .chain(aggregates.iter().flat_map(AggregateResult::values));
+            for (column, value) in output_values.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+
+        let output_columns = output_columns
+            .iter()
+            .map(|(name, data_type)| (name.as_str(), *data_type))
+            .collect::<Vec<_>>();
+        Ok(record_batch_from_values(&output_columns, &output_values))
     }
 
-    /// Returns the distinct set of tag values (column values) for each provided
-    /// tag key, where each returned value lives in a row matching the provided
-    /// optional predicates and time range.
+    /// Returns the difference between each value of the `field` column and
+    /// its predecessor, for each group of rows, in time order.
     ///
-    /// As a special case, if `tag_keys` is empty then all distinct values for
-    /// all columns (tag keys) are returned for the chunk.
-    pub fn tag_values(
+    /// Results may be filtered by equality and comparison predicates, and can
+    /// be ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Groups are determined according to the provided group column
+    /// names.
+    ///
+    /// The result contains the group columns, followed by the `time` column,
+    /// followed by a `{field}_delta` column, and is sorted by group key and
+    /// then time. The first row of each group, and rows with a NULL value,
+    /// have a NULL delta. Rows are ordered by time across chunks, so the last
+    /// value of a group in one chunk is the predecessor of the first value in
+    /// the next. Deltas of integer and unsigned columns are integers, and are
+    /// NULL when they don't fit in an i64.
+    pub fn select_delta(
         &self,
         table_name: &str,
-        time_range: (i64, i64),
+        time_range: TimeRange,
         predicates: &[Predicate<'_>],
-        tag_keys: &[String],
-    ) -> Option<RecordBatch> {
-        // Find the measurement name on the chunk and dispatch query to the
-        // table for that measurement if the chunk's time range overlaps the
-        // requested time range.
-        todo!();
+        field: &str,
+        group_columns: Vec<String>,
+    ) -> Result<Option<RecordBatch>> {
+        self.validate_predicates(table_name, predicates)?;
+
+        let group_columns = group_columns.iter().map(String::as_str).collect::<Vec<_>>();
+        let mut output_columns = group_columns
+            .iter()
+            .map(|&name| Ok((name.to_string(), self.column_type(table_name, name)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let delta_type = match self.column_type(table_name, field)? {
+            LogicalDataType::Integer | LogicalDataType::Unsigned => LogicalDataType::Integer,
+            LogicalDataType::Float => LogicalDataType::Float,
+            data_type => {
+                return UnsupportedDelta {
+                    column_name: field,
+                    data_type,
+                }
+                .fail()
+            }
+        };
+        output_columns.push((
+            row_group::TIME_COLUMN_NAME.to_string(),
+            LogicalDataType::Integer,
+        ));
+        output_columns.push((format!("{}_delta", field), delta_type));
+
+        let mut chunk_predicates = time_range_predicate(time_range);
+        chunk_predicates.extend_from_slice(predicates);
+
+        let mut columns = vec![row_group::TIME_COLUMN_NAME];
+        for &name in group_columns.iter().chain(std::iter::once(&field)) {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+
+        // Collect the time and value of each row in each group across all
+        // matching chunks.
+        let mut matched = false;
+        let mut groups: BTreeMap<GroupKey<'_>, Vec<(i64, Value<'_>)>> = BTreeMap::new();
+        for chunk in self.overlapping_chunks(time_range) {
+            let results = match chunk.select(table_name, &chunk_predicates, &columns) {
+                Some(results) => results,
+                None => continue,
+            };
+            matched = true;
+
+            for segment_result in &results.values {
+                let times = match segment_result.column(row_group::TIME_COLUMN_NAME) {
+                    Some(times) => times,
+                    None => continue,
+                };
+                let group_values = group_columns
+                    .iter()
+                    .map(|&name| segment_result.column(name))
+                    .collect::<Vec<_>>();
+                let field_values = segment_result.column(field);
+
+                for row in 0..times.len() {
+                    let time = match times.value(row) {
+                        Value::Scalar(Scalar::I64(time)) => time,
+                        _ => continue,
+                    };
+
+                    // Columns missing from the chunk are NULL.
+                    let key = group_values
+                        .iter()
+                        .map(|values| values.map_or(Value::Null, |values| values.value(row)))
+                        .collect::<Vec<_>>();
+                    let value = field_values.map_or(Value::Null, |values| values.value(row));
+                    groups
+                        .entry(GroupKey::from(key))
+                        .or_default()
+                        .push((time, value));
+                }
+            }
+        }
+
+        if !matched {
+            return Ok(None);
+        }
+
+        let mut output_values = vec![vec![]; output_columns.len()];
+        for (group_key, rows) in &mut groups {
+            // Rows from different chunks may be interleaved in time.
+            rows.sort_by_key(|(time, _)| *time);
+
+            let mut prev = None;
+            for &(time, value) in rows.iter() {
+                let delta = match prev {
+                    Some(prev) => value_delta(prev, value),
+                    None => Value::Null,
+                };
+                if !value.is_null() {
+                    prev = Some(value);
+                }
+
+                let row = group_key
+                    .values()
+                    .iter()
+                    .cloned()
+                    .chain(vec![Value::from(time), delta]);
+                for (column, value) in output_values.iter_mut().zip(row) {
+                    column.push(value);
+                }
+            }
+        }
+
+        let output_columns = output_columns
+            .iter()
+            .map(|(name, data_type)| (name.as_str(), *data_type))
+            .collect::<Vec<_>>();
+        Ok(record_batch_from_values(&output_columns, &output_values))
+    }
+
+    // Determines the logical data type of a column in a table. Returns an
+    // error if no chunk contains the table or column.
+    fn column_type(&self, table_name: &str, column_name: &str) -> Result<LogicalDataType> {
+        ensure!(self.has_table(table_name), TableNotFound { table_name });
+
+        self.chunks
+            .values()
+            .find_map(|chunk| chunk.column_logical_datatype(table_name, column_name))
+            .context(ColumnNotFound {
+                table_name,
+                column_name,
+            })
+    }
+
+    // Checks the predicates of an expression as `validate_predicates` does,
+    // and that the columns compared by any column-to-column predicates have
+    // comparable types.
+    fn validate_expr(&self, table_name: &str, expr: &PredicateExpr<'_>) -> Result<()> {
+        self.validate_predicates(table_name, &expr.predicates())?;
+
+        for (left_column, op, right_column) in expr.column_predicates() {
+            let left_type = self.column_type(table_name, left_column)?;
+            let right_type = self.column_type(table_name, right_column)?;
+            ensure!(
+                row_group::columns_comparable(left_type, &op, right_type),
+                UnsupportedColumnPredicate {
+                    left_column,
+                    op: op.to_string(),
+                    right_column,
+                    left_type,
+                    right_type,
+                }
+            );
+        }
+        Ok(())
+    }
+
+    // Validates that each predicate's value can be compared to the values in
+    // the column it's applied to.
+    fn validate_predicates(&self, table_name: &str, predicates: &[Predicate<'_>]) -> Result<()> {
+        for (column_name, (op, value)) in predicates {
+            let data_type = self.column_type(table_name, column_name)?;
+            let supported = match value {
+                // Any column can be checked for NULL values, which the value
+                // of the predicate must be.
+                _ if op.is_null_check() => matches!(value, Value::Null),
+                // Regular expressions can only be matched against strings.
+                _ if op.is_regex() => {
+                    matches!(value, Value::String(_)) && data_type == LogicalDataType::String
+                }
+                Value::String(_) => data_type == LogicalDataType::String,
+                // Float values can't be compared to integer columns without
+                // losing precision.
+                Value::Scalar(Scalar::F64(_)) => data_type == LogicalDataType::Float,
+                Value::Scalar(_) => matches!(
+                    data_type,
+                    LogicalDataType::Integer | LogicalDataType::Unsigned | LogicalDataType::Float
+                ),
+                Value::Null | Value::ByteArray(_) | Value::Boolean(_) => false,
+            };
+
+            ensure!(
+                supported,
+                UnsupportedPredicate {
+                    column_name: *column_name,
+                    data_type,
+                    value: value.to_string(),
+                }
+            );
+
+            if let (true, Value::String(pattern)) = (op.is_regex(), value) {
+                op.compile_regex(pattern)
+                    .context(InvalidRegex { pattern: *pattern })?;
+            }
+        }
+        Ok(())
+    }
+
+    // Determines the name and type of each column in an aggregate result: the
+    // group columns, an optional `time` column holding window starts, then the
+    // aggregate columns named `{column}_{aggregate}`. Returns an error if an
+    // aggregate isn't supported for the column it's applied to.
+    fn aggregate_output_columns(
+        &self,
+        table_name: &str,
+        group_columns: &[ColumnName<'_>],
+        windowed: bool,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> Result<Vec<(String, LogicalDataType)>> {
+        let mut columns = Vec::with_capacity(group_columns.len() + aggregates.len() + 1);
+        for &name in group_columns {
+            let data_type = self.column_type(table_name, name)?;
+            ensure!(
+                matches!(
+                    data_type,
+                    LogicalDataType::String
+                        | LogicalDataType::Integer
+                        | LogicalDataType::Unsigned
+                        | LogicalDataType::Boolean
+                ),
+                UnsupportedGroupColumn {
+                    column_name: name,
+                    data_type,
+                }
+            );
+            columns.push((name.to_string(), data_type));
+        }
+
+        if windowed {
+            columns.push((
+                row_group::TIME_COLUMN_NAME.to_string(),
+                LogicalDataType::Integer,
+            ));
+        }
+
+        for (name, agg_type) in aggregates {
+            let name = *name;
+            match agg_type {
+                AggregateType::Percentile(p) | AggregateType::ApproxQuantile(p) => ensure!(
+                    (0.0..=100.0).contains(p),
+                    InvalidAggregate {
+                        column_name: name,
+                        agg_type: agg_type.clone(),
+                        reason: "percentile must be between 0 and 100",
+                    }
+                ),
+                AggregateType::Histogram(bounds) => ensure!(
+                    bounds.iter().all(|bound| !bound.is_nan())
+                        && bounds.windows(2).all(|pair| pair[0] < pair[1]),
+                    InvalidAggregate {
+                        column_name: name,
+                        agg_type: agg_type.clone(),
+                        reason: "bucket bounds must be in ascending order",
+                    }
+                ),
+                _ => {}
+            }
+
+            let data_type = self.column_type(table_name, name)?;
+            let supported = match agg_type {
+                // Selectors return values of any type.
+                AggregateType::Count | AggregateType::First | AggregateType::Last => true,
+                AggregateType::Min
+                | AggregateType::Max
+                | AggregateType::ArgMax
+                | AggregateType::ArgMin => matches!(
+                    data_type,
+                    LogicalDataType::String
+                        | LogicalDataType::Integer
+                        | LogicalDataType::Unsigned
+                        | LogicalDataType::Float
+                ),
+                AggregateType::Sum
+                | AggregateType::Percentile(_)
+                | AggregateType::ApproxQuantile(_)
+                | AggregateType::Mean
+                | AggregateType::StdDev
+                | AggregateType::Variance
+                | AggregateType::Histogram(_) => matches!(
+                    data_type,
+                    LogicalDataType::Integer | LogicalDataType::Unsigned | LogicalDataType::Float
+                ),
+                // Distinct values are counted using the column dictionary.
+                AggregateType::CountDistinct { .. } => data_type == LogicalDataType::String,
+                AggregateType::CountTrue | AggregateType::CountFalse => {
+                    data_type == LogicalDataType::Boolean
+                }
+            };
+
+            ensure!(
+                supported,
+                UnsupportedAggregate {
+                    column_name: name,
+                    data_type,
+                    agg_type: agg_type.clone(),
+                }
+            );
+
+            // Histograms have an unsigned count column for each bucket, named
+            // after the bucket's upper bound.
+            if let AggregateType::Histogram(bounds) = agg_type {
+                for bound in bounds {
+                    columns.push((
+                        format!("{}_{}_le_{}", name, agg_type, bound),
+                        LogicalDataType::Unsigned,
+                    ));
+                }
+                columns.push((
+                    format!("{}_{}_le_inf", name, agg_type),
+                    LogicalDataType::Unsigned,
+                ));
+                continue;
+            }
+
+            let data_type = match agg_type {
+                AggregateType::Count
+                | AggregateType::CountDistinct { .. }
+                | AggregateType::CountTrue
+                | AggregateType::CountFalse => LogicalDataType::Unsigned,
+                // The timestamp of the selected row.
+                AggregateType::ArgMax | AggregateType::ArgMin => LogicalDataType::Integer,
+                AggregateType::ApproxQuantile(_)
+                | AggregateType::Mean
+                | AggregateType::StdDev
+                | AggregateType::Variance => LogicalDataType::Float,
+                _ => data_type,
+            };
+            columns.push((format!("{}_{}", name, agg_type), data_type));
+        }
+
+        Ok(columns)
+    }
+
+    //
+    // ---- Schema API queries
+    //
+
+    /// Returns the distinct set of table names that contain at least one row
+    /// satisfying the time range and predicates, as a single string column
+    /// sorted by name. `None` is returned if no tables match.
+    pub fn table_names(
+        &self,
+        database_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<Option<RecordBatch>> {
+        if time_range == TimeRange::all() && predicates.is_empty() {
+            return Ok(table_names_record_batch(&self.schema_cache.table_names));
+        }
+
+        let time_predicates = time_range_predicate(time_range);
+        let expr =
+            PredicateExpr::from(time_predicates.as_slice()).and(PredicateExpr::from(predicates));
+
+        let mut names = BTreeSet::new();
+        for chunk in self.overlapping_chunks(time_range) {
+            let chunk_names = chunk.table_names(&expr, &names);
+            names.extend(chunk_names);
+        }
+
+        Ok(table_names_record_batch(&names))
+    }
+
+    /// Returns the distinct set of table names that could contain rows
+    /// satisfying the time range and predicates, as a single string column
+    /// sorted by name. `None` is returned if no tables match.
+    ///
+    /// Unlike `table_names` no rows are scanned: a table is returned if it is
+    /// in a chunk overlapping the time range and it has every column referenced
+    /// by the predicates. The result is therefore a superset of the tables
+    /// returned by `table_names`, which may include tables with no rows that
+    /// satisfy the predicates.
+    pub fn table_names_fast(
+        &self,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Option<RecordBatch> {
+        let columns = predicates.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+
+        let mut names = BTreeSet::new();
+        for chunk in self.overlapping_chunks(time_range) {
+            let chunk_names = chunk.table_names_with_columns(&columns, &names);
+            names.extend(chunk_names);
+        }
+
+        table_names_record_batch(&names)
+    }
+
+    /// Returns the name and type of each column in the table, ordered by
+    /// name, across the chunks overlapping the time range. No rows are
+    /// scanned. `None` is returned if no such chunk contains the table.
+    ///
+    /// Columns need not appear in every chunk, but an error is returned if two
+    /// chunks disagree on the type of a column.
+    pub fn table_schema(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+    ) -> Result<Option<Vec<(String, ColumnType)>>> {
+        if time_range == TimeRange::all() {
+            match self.schema_cache.tables.get(table_name) {
+                Some(table) if !table.conflicting => {
+                    return Ok(Some(
+                        table
+                            .columns
+                            .iter()
+                            .map(|(name, column_type)| (name.clone(), *column_type))
+                            .collect(),
+                    ))
+                }
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+
+        let mut schema: Option<BTreeMap<String, ColumnType>> = None;
+        for chunk in self.overlapping_chunks(time_range) {
+            let column_types = match chunk.column_types(table_name) {
+                Some(column_types) => column_types,
+                None => continue,
+            };
+
+            let schema = schema.get_or_insert_with(BTreeMap::new);
+            for (column_name, column_type) in column_types {
+                match schema.get(column_name) {
+                    Some(&first) => ensure!(
+                        first == column_type,
+                        SchemaConflict {
+                            table_name,
+                            column_name,
+                            first,
+                            second: column_type,
+                        }
+                    ),
+                    None => {
+                        schema.insert(column_name.to_owned(), column_type);
+                    }
+                }
+            }
+        }
+
+        Ok(schema.map(|schema| schema.into_iter().collect()))
+    }
+
+    /// Returns an estimate of the number of distinct values for each of the
+    /// tag keys, across the chunks overlapping the time range. No rows are
+    /// scanned.
+    ///
+    /// By default the estimate is an upper bound: the sum of the dictionary
+    /// sizes of each segment containing the tag column. A tighter estimate
+    /// using a HyperLogLog sketch can be requested with
+    /// `CardinalityEstimate::HyperLogLog`. Tag keys not found in the table
+    /// have an estimate of zero.
+    pub fn tag_cardinality(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        tag_keys: &[String],
+        estimate: CardinalityEstimate,
+    ) -> BTreeMap<String, u64> {
+        let chunks = self.overlapping_chunks(time_range);
+
+        tag_keys
+            .iter()
+            .map(|tag_key| {
+                let dictionaries = chunks
+                    .iter()
+                    .flat_map(|chunk| chunk.tag_column_dictionaries(table_name, tag_key));
+
+                let cardinality = match estimate {
+                    CardinalityEstimate::UpperBound => {
+                        dictionaries.map(|dictionary| dictionary.len() as u64).sum()
+                    }
+                    CardinalityEstimate::HyperLogLog => {
+                        let mut hll = HyperLogLog::default();
+                        for value in dictionaries.flatten() {
+                            hll.insert(value.as_str());
+                        }
+                        hll.estimate()
+                    }
+                };
+                (tag_key.to_owned(), cardinality)
+            })
+            .collect()
+    }
+
+    /// Returns each tag key with at least one value in a row matching the
+    /// predicates and time range, along with its number of distinct values.
+    ///
+    /// Values are deduplicated across chunks. Row groups lying entirely within
+    /// the time range have their values taken from the column dictionaries
+    /// when there are no predicates other than those bounding the time column.
+    /// An error is returned if no chunk contains the table, or if a predicate
+    /// isn't supported for the column it's applied to.
+    pub fn tag_keys_with_counts(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<BTreeMap<String, u64>> {
+        Ok(self
+            .distinct_tag_values(table_name, time_range, predicates, &[])?
+            .into_iter()
+            .map(|(tag_key, values)| (tag_key, values.len() as u64))
+            .collect())
+    }
+
+    /// Returns the distinct values of each of the tag keys, ordered by value,
+    /// where each value lives in a row matching the predicates and time range.
+    /// Tag keys that aren't tag columns, or that have no matching values, are
+    /// omitted.
+    ///
+    /// As a special case, if `tag_keys` is empty then the values of all of the
+    /// table's tag keys are returned. An error is returned if no chunk contains
+    /// the table, or if a predicate isn't supported for the column it's applied
+    /// to.
+    pub fn tag_values_by_key(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        tag_keys: &[String],
+    ) -> Result<BTreeMap<String, Vec<String>>> {
+        Ok(self
+            .distinct_tag_values(table_name, time_range, predicates, tag_keys)?
+            .into_iter()
+            .map(|(tag_key, values)| (tag_key, values.into_iter().cloned().collect()))
+            .collect())
+    }
+
+    // The distinct non-null values of each of the tag keys in rows matching the
+    // predicates and time range, deduplicated across chunks. All of the
+    // table's tag keys are used if `tag_keys` is empty.
+    fn distinct_tag_values(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        tag_keys: &[String],
+    ) -> Result<BTreeMap<String, BTreeSet<&String>>> {
+        ensure!(self.has_table(table_name), TableNotFound { table_name });
+        self.validate_predicates(table_name, predicates)?;
+
+        let (time_range, row_filters) = plan_predicates(time_range, predicates);
+        let expr = PredicateExpr::from(row_filters.as_slice());
+        let chunks = self.overlapping_chunks(time_range);
+
+        let tag_keys = if tag_keys.is_empty() {
+            chunks
+                .iter()
+                .flat_map(|chunk| chunk.tag_column_names(table_name).unwrap_or_default())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>()
+        } else {
+            tag_keys.iter().map(String::as_str).collect()
+        };
+
+        let mut values: BTreeMap<String, BTreeSet<_>> = BTreeMap::new();
+        for chunk in &chunks {
+            for (tag_key, chunk_values) in
+                chunk.tag_column_values(table_name, time_range, &expr, &tag_keys)
+            {
+                values
+                    .entry(tag_key.to_owned())
+                    .or_default()
+                    .extend(chunk_values);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Returns the distinct set of tag keys (column names) with at least one
+    /// value in a row matching the provided optional predicates and time
+    /// range, as a single string column sorted by name. `None` is returned if
+    /// no tag keys match.
+    ///
+    /// An error is returned if no chunk contains the table, or if a predicate
+    /// isn't supported for the column it's applied to.
+    pub fn tag_keys(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+    ) -> Result<Option<RecordBatch>> {
+        if time_range == TimeRange::all() && predicates.is_empty() {
+            if let Some(table) = self.schema_cache.tables.get(table_name) {
+                return Ok(names_record_batch(TAG_KEYS_COLUMN_NAME, &table.tag_keys));
+            }
+        }
+
+        let tag_keys = self
+            .distinct_tag_values(table_name, time_range, predicates, &[])?
+            .into_iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(tag_key, _)| tag_key)
+            .collect::<BTreeSet<_>>();
+        Ok(names_record_batch(TAG_KEYS_COLUMN_NAME, &tag_keys))
+    }
+
+    /// Returns the distinct set of tag values (column values) for each provided
+    /// tag key, where each returned value lives in a row matching the provided
+    /// optional predicates and time range.
+    ///
+    /// The result has a `tag_key` and a `tag_value` string column, with a row
+    /// for each distinct tag value, sorted by tag key and then value. `None` is
+    /// returned if no tag values match. Tag keys are chosen as they are for
+    /// `tag_values_by_key`, so if `tag_keys` is empty then the values of all of
+    /// the table's tag keys are returned.
+    ///
+    /// An error is returned if no chunk contains the table, or if a predicate
+    /// isn't supported for the column it's applied to.
+    pub fn tag_values(
+        &self,
+        table_name: &str,
+        time_range: TimeRange,
+        predicates: &[Predicate<'_>],
+        tag_keys: &[String],
+    ) -> Result<Option<RecordBatch>> {
+        let values = self.distinct_tag_values(table_name, time_range, predicates, tag_keys)?;
+
+        let mut keys_column = vec![];
+        let mut values_column = vec![];
+        for (tag_key, tag_values) in &values {
+            for tag_value in tag_values {
+                keys_column.push(Value::String(tag_key.as_str()));
+                values_column.push(Value::String(tag_value.as_str()));
+            }
+        }
+
+        if keys_column.is_empty() {
+            return Ok(None);
+        }
+        Ok(record_batch_from_values(
+            &[
+                (TAG_KEYS_COLUMN_NAME, LogicalDataType::String),
+                (TAG_VALUES_COLUMN_NAME, LogicalDataType::String),
+            ],
+            &[keys_column, values_column],
+        ))
+    }
+}
+
+// Converts a logical data type into the arrow data type used to represent it.
+fn arrow_data_type(data_type: LogicalDataType) -> DataType {
+    match data_type {
+        LogicalDataType::Integer => DataType::Int64,
+        LogicalDataType::Unsigned => DataType::UInt64,
+        LogicalDataType::Float => DataType::Float64,
+        LogicalDataType::String => DataType::Utf8,
+        LogicalDataType::Binary => DataType::Binary,
+        LogicalDataType::Boolean => DataType::Boolean,
+    }
+}
+
+// Builds an arrow array of the provided logical type from materialised values.
+// Values that don't have the expected type are treated as NULL.
+fn arrow_array(data_type: LogicalDataType, values: &[Value<'_>]) -> ArrayRef {
+    match data_type {
+        LogicalDataType::Integer => Arc::new(Int64Array::from(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::Scalar(Scalar::I64(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        LogicalDataType::Unsigned => Arc::new(UInt64Array::from(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::Scalar(Scalar::U64(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        LogicalDataType::Float => Arc::new(Float64Array::from(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::Scalar(Scalar::F64(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        LogicalDataType::String => Arc::new(StringArray::from(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::String(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        LogicalDataType::Binary => Arc::new(BinaryArray::from(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::ByteArray(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        LogicalDataType::Boolean => Arc::new(BooleanArray::from(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::Boolean(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+    }
+}
+
+// Builds the single column record batch of table names returned by the
+// `table_names` methods. `None` is returned if there are no names.
+fn table_names_record_batch(names: &BTreeSet<String>) -> Option<RecordBatch> {
+    names_record_batch(TABLE_NAMES_COLUMN_NAME, names)
+}
+
+// Builds a record batch with a single string column of names. `None` is
+// returned if there are no names.
+fn names_record_batch(column_name: &str, names: &BTreeSet<String>) -> Option<RecordBatch> {
+    if names.is_empty() {
+        return None;
+    }
+
+    let values = names
+        .iter()
+        .map(|name| Value::String(name.as_str()))
+        .collect::<Vec<_>>();
+    record_batch_from_values(&[(column_name, LogicalDataType::String)], &[values])
+}
+
+// Builds a record batch from column-wise materialised values. `None` is
+// returned if there are no columns.
+fn record_batch_from_values(
+    columns: &[(&str, LogicalDataType)],
+    values: &[Vec<Value<'_>>],
+) -> Option<RecordBatch> {
+    if columns.is_empty() {
+        return None;
+    }
+
+    let schema = Schema::new(
+        columns
+            .iter()
+            .map(|(name, data_type)| Field::new(name, arrow_data_type(*data_type), true))
+            .collect(),
+    );
+
+    let arrays = columns
+        .iter()
+        .zip(values.iter())
+        .map(|((_, data_type), values)| arrow_array(*data_type, values))
+        .collect();
+
+    Some(
+        RecordBatch::try_new(Arc::new(schema), arrays)
+            .expect("columns should have the same number of rows"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_deps::arrow::array::Array;
+    use column::{cmp::Operator, Column};
+    use row_group::{ColumnType, RowGroup};
+    use table::Table;
+
+    // Builds a chunk containing a single "cpu" table with a single row group.
+    fn build_chunk(id: u32, rows: u32, columns: Vec<(&str, ColumnType)>) -> Chunk {
+        let columns = columns
+            .into_iter()
+            .map(|(name, column)| (name.to_string(), column))
+            .collect::<BTreeMap<_, _>>();
+        let row_group = RowGroup::new(rows, columns);
+        Chunk::new(id, Table::new("cpu".to_string(), row_group))
+    }
+
+    fn time_range(start: i64, end: i64) -> TimeRange {
+        TimeRange::new(start, end).unwrap()
+    }
+
+    fn i64_values(array: &ArrayRef) -> Vec<Option<i64>> {
+        let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    None
+                } else {
+                    Some(array.value(i))
+                }
+            })
+            .collect()
+    }
+
+    fn u64_values(array: &ArrayRef) -> Vec<Option<u64>> {
+        let array = array.as_any().downcast_ref::<UInt64Array>().unwrap();
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    None
+                } else {
+                    Some(array.value(i))
+                }
+            })
+            .collect()
+    }
+
+    fn f64_values(array: &ArrayRef) -> Vec<Option<f64>> {
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    None
+                } else {
+                    Some(array.value(i))
+                }
+            })
+            .collect()
+    }
+
+    fn string_values(array: &ArrayRef) -> Vec<Option<&str>> {
+        let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    None
+                } else {
+                    Some(array.value(i))
+                }
+            })
+            .collect()
+    }
+
+    fn bool_values(array: &ArrayRef) -> Vec<Option<bool>> {
+        let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    None
+                } else {
+                    Some(array.value(i))
+                }
+            })
+            .collect()
+    }
+
+    fn column_names(rb: &RecordBatch) -> Vec<String> {
+        rb.schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect()
+    }
+
+    fn database_with_size(size: u64) -> Database {
+        Database {
+            size,
+            ..Database::new()
+        }
+    }
+
+    #[test]
+    fn time_range_new() {
+        let range = TimeRange::new(10, 20).unwrap();
+        assert_eq!((range.start(), range.end()), (Some(10), Some(20)));
+        assert!(!range.is_empty());
+        assert_eq!(range.to_string(), "[10, 20)");
+
+        // An empty range is valid, but nothing overlaps it.
+        let range = TimeRange::new(10, 10).unwrap();
+        assert!(range.is_empty());
+        assert!(!range.overlaps(0, 20));
+        assert!(TimeRange::empty().is_empty());
+
+        assert!(matches!(
+            TimeRange::new(20, 10),
+            Err(Error::InvalidTimeRange { start: 20, end: 10 })
+        ));
+
+        let range = TimeRange::all();
+        assert_eq!((range.start(), range.end()), (None, None));
+        assert!(!range.is_empty());
+        assert_eq!(TimeRange::since(10).to_string(), "[10, +inf)");
+        assert_eq!(TimeRange::before(10).to_string(), "(-inf, 10)");
+    }
+
+    #[test]
+    fn time_range_negative_bounds() {
+        let range = time_range(-1000, 1000);
+        assert!(range.overlaps(-5000, -1000));
+        assert!(range.overlaps(-500, -500));
+        assert!(!range.overlaps(-5000, -1001));
+        assert!(range.contains(-1000, 999));
+        assert!(!range.contains(-1001, 0));
+        assert_eq!(range.to_string(), "[-1000, 1000)");
+
+        assert_eq!(
+            time_range_predicate(range),
+            vec![
+                ("time", (Operator::GTE, Value::from(-1000_i64))),
+                ("time", (Operator::LT, Value::from(1000_i64))),
+            ]
+        );
+    }
+
+    #[test]
+    fn remove_database() {
+        let mut store = Store::new();
+        store.add_database("db1".to_string(), database_with_size(100));
+        store.add_database("db2".to_string(), database_with_size(250));
+        assert_eq!(store.size(), 350);
+
+        let removed = store.remove_database("db1".to_string()).unwrap();
+        assert_eq!(removed.size(), 100);
+        assert_eq!(store.size(), 250);
+
+        // removing an unknown database is a no-op.
+        assert!(store.remove_database("db1".to_string()).is_none());
+        assert!(store.remove_database("unknown".to_string()).is_none());
+        assert_eq!(store.size(), 250);
+
+        store.remove_database("db2".to_string()).unwrap();
+        assert_eq!(store.size(), 0);
+    }
+
+    #[test]
+    fn store_memory_limit() {
+        let chunk = |times: Vec<i64>| {
+            let schema = Schema::new(vec![Field::new("time", DataType::Int64, false)]);
+            let rb =
+                RecordBatch::try_new(Arc::new(schema), vec![Arc::new(Int64Array::from(times))])
+                    .unwrap();
+
+            let mut tables = BTreeMap::new();
+            tables.insert("cpu".to_string(), rb);
+            tables
+        };
+        let chunk_size = Chunk::from_record_batches(1, chunk(vec![1, 2, 3]), 100)
+            .unwrap()
+            .size();
+        assert!(chunk_size > 0);
+
+        let mut store = Store::with_config(StoreConfig {
+            max_bytes: Some(chunk_size * 2),
+            max_row_group_rows: 100,
+        })
+        .unwrap();
+        assert_eq!(store.memory_status(), (0, Some(chunk_size * 2)));
+
+        store
+            .add_chunk("db1".to_string(), 1, chunk(vec![1, 2, 3]))
+            .unwrap();
+        assert_eq!(store.memory_status(), (chunk_size, Some(chunk_size * 2)));
+        store
+            .add_chunk("db2".to_string(), 1, chunk(vec![4, 5, 6]))
+            .unwrap();
+        assert_eq!(
+            store.memory_status(),
+            (chunk_size * 2, Some(chunk_size * 2))
+        );
+
+        // The store is full so the chunk isn't added.
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 2, chunk(vec![7, 8, 9])),
+            Err(Error::MemoryLimitExceeded { .. })
+        ));
+        assert_eq!(store.size(), chunk_size * 2);
+        let rb = store
+            .select(
+                "db1",
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(1), Some(2), Some(3)]);
+
+        // Removing a database frees up space for the chunk.
+        store.remove_database("db2".to_string()).unwrap();
+        assert_eq!(store.memory_status(), (chunk_size, Some(chunk_size * 2)));
+        store
+            .add_chunk("db1".to_string(), 2, chunk(vec![7, 8, 9]))
+            .unwrap();
+        assert_eq!(store.size(), chunk_size * 2);
+
+        // There's no limit by default.
+        let store = Store::new();
+        assert_eq!(store.memory_status(), (0, None));
+    }
+
+    #[test]
+    fn store_add_chunk_replaces_chunk() {
+        let chunk = |times: Vec<i64>| {
+            let schema = Schema::new(vec![Field::new("time", DataType::Int64, false)]);
+            let rb =
+                RecordBatch::try_new(Arc::new(schema), vec![Arc::new(Int64Array::from(times))])
+                    .unwrap();
+
+            let mut tables = BTreeMap::new();
+            tables.insert("cpu".to_string(), rb);
+            tables
+        };
+        let chunk_size = Chunk::from_record_batches(1, chunk(vec![1, 2, 3]), 100)
+            .unwrap()
+            .size();
+        let big_chunk_size = Chunk::from_record_batches(1, chunk((1..=100).collect()), 100)
+            .unwrap()
+            .size();
+        assert!(big_chunk_size > chunk_size);
+        let select = |store: &Store| {
+            store
+                .select(
+                    "db1",
+                    "cpu",
+                    time_range(0, 1000),
+                    &[],
+                    vec!["time".to_string()],
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .map_or(vec![], |rb| {
+                    // Rows are returned in chunk id order
+                    let mut values = i64_values(rb.column(0));
+                    values.sort();
+                    values
+                })
+        };
+
+        let mut store = Store::with_config(StoreConfig {
+            max_bytes: Some(chunk_size * 2),
+            max_row_group_rows: 100,
+        })
+        .unwrap();
+        store
+            .add_chunk("db1".to_string(), 1, chunk(vec![1, 2, 3]))
+            .unwrap();
+        store
+            .add_chunk("db1".to_string(), 2, chunk(vec![4, 5, 6]))
+            .unwrap();
+        assert_eq!(store.size(), chunk_size * 2);
+
+        // The store is full, but the space of the replaced chunk is reused.
+        store
+            .add_chunk("db1".to_string(), 1, chunk(vec![7, 8, 9]))
+            .unwrap();
+        assert_eq!(store.size(), chunk_size * 2);
+        assert_eq!(store.databases["db1"].size(), chunk_size * 2);
+        assert_eq!(
+            select(&store),
+            vec![Some(4), Some(5), Some(6), Some(7), Some(8), Some(9)]
+        );
+
+        // A bigger replacement that doesn't fit isn't added, and the chunk it
+        // would have replaced is kept.
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 1, chunk((1..=100).collect())),
+            Err(Error::MemoryLimitExceeded { used, .. }) if used == chunk_size
+        ));
+        assert_eq!(store.size(), chunk_size * 2);
+        assert_eq!(
+            select(&store),
+            vec![Some(4), Some(5), Some(6), Some(7), Some(8), Some(9)]
+        );
+
+        // Once it fits, the replacement's size is accounted for in place of
+        // the replaced chunk's.
+        store.config.max_bytes = None;
+        store
+            .add_chunk("db1".to_string(), 1, chunk((1..=100).collect()))
+            .unwrap();
+        assert_eq!(store.size(), big_chunk_size);
+        assert_eq!(store.databases["db1"].size(), big_chunk_size);
+        assert_eq!(select(&store).len(), 100);
+    }
+
+    #[test]
+    fn store_add_chunks() {
+        let chunk = |times: Vec<i64>| {
+            let schema = Schema::new(vec![Field::new("time", DataType::Int64, false)]);
+            let rb =
+                RecordBatch::try_new(Arc::new(schema), vec![Arc::new(Int64Array::from(times))])
+                    .unwrap();
+
+            let mut tables = BTreeMap::new();
+            tables.insert("cpu".to_string(), rb);
+            tables
+        };
+        let chunk_size = Chunk::from_record_batches(1, chunk(vec![1, 2, 3]), 100)
+            .unwrap()
+            .size();
+        let select = |store: &Store| {
+            store
+                .select(
+                    "db1",
+                    "cpu",
+                    time_range(0, 100),
+                    &[],
+                    vec!["time".to_string()],
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .map_or(vec![], |rb| i64_values(rb.column(0)))
+        };
+
+        let mut store = Store::with_config(StoreConfig {
+            max_bytes: Some(chunk_size * 3),
+            max_row_group_rows: 100,
+        })
+        .unwrap();
+        let chunk_keys = store
+            .add_chunks(
+                "db1".to_string(),
+                vec![chunk(vec![1, 2, 3]), chunk(vec![4, 5, 6])],
+            )
+            .unwrap();
+        assert_eq!(chunk_keys, vec!["1", "2"]);
+        assert_eq!(store.size(), chunk_size * 2);
+        assert_eq!(
+            select(&store),
+            vec![Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)]
+        );
+
+        // The second chunk would exceed the limit, so neither is added.
+        assert!(matches!(
+            store.add_chunks(
+                "db1".to_string(),
+                vec![chunk(vec![7, 8, 9]), chunk(vec![10, 11, 12])],
+            ),
+            Err(Error::MemoryLimitExceeded { .. })
+        ));
+        assert_eq!(store.size(), chunk_size * 2);
+        assert_eq!(store.databases["db1"].chunk_keys(), vec!["1", "2"]);
+        assert_eq!(
+            select(&store),
+            vec![Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)]
+        );
+
+        // Nor is a database created when its chunks can't be added.
+        assert!(store
+            .add_chunks(
+                "db2".to_string(),
+                vec![chunk(vec![1, 2, 3]), chunk(vec![4, 5, 6])],
+            )
+            .is_err());
+        assert_eq!(store.database_names(), vec!["db1"]);
+
+        // New chunks follow the existing ones.
+        let chunk_keys = store
+            .add_chunks("db1".to_string(), vec![chunk(vec![7, 8, 9])])
+            .unwrap();
+        assert_eq!(chunk_keys, vec!["3"]);
+        assert_eq!(store.size(), chunk_size * 3);
+
+        // Nothing is added if the chunks would need ids beyond `u32::MAX`.
+        let mut store = Store::new();
+        store
+            .add_chunk("db1".to_string(), u32::MAX - 1, chunk(vec![1, 2, 3]))
+            .unwrap();
+        assert!(matches!(
+            store.add_chunks(
+                "db1".to_string(),
+                vec![chunk(vec![4, 5, 6]), chunk(vec![7, 8, 9])],
+            ),
+            Err(Error::ChunkIdsExhausted { last_id }) if last_id == u32::MAX - 1
+        ));
+        assert_eq!(
+            store.databases["db1"].chunk_keys(),
+            vec![(u32::MAX - 1).to_string()]
+        );
+        let chunk_keys = store
+            .add_chunks("db1".to_string(), vec![chunk(vec![4, 5, 6])])
+            .unwrap();
+        assert_eq!(chunk_keys, vec![u32::MAX.to_string()]);
+    }
+
+    #[test]
+    fn store_database_names() {
+        let mut store = Store::new();
+        assert!(store.database_names().is_empty());
+
+        store.add_database("db2".to_string(), Database::new());
+        store.add_database("db1".to_string(), Database::new());
+        store.add_database("db3".to_string(), Database::new());
+        assert_eq!(store.database_names(), vec!["db1", "db2", "db3"]);
+
+        store.remove_database("db2".to_string()).unwrap();
+        assert_eq!(store.database_names(), vec!["db1", "db3"]);
+    }
+
+    #[test]
+    fn database_chunk_keys() {
+        let mut db = Database::new();
+        assert!(db.chunk_keys().is_empty());
+
+        for &id in &[3, 1, 2] {
+            db.add_chunk(build_chunk(
+                id,
+                1,
+                vec![("time", ColumnType::Time(Column::from(&[1_i64][..])))],
+            ));
+        }
+        assert_eq!(db.chunk_keys(), vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn database_remove_chunk() {
+        let chunk = |id| {
+            build_chunk(
+                id,
+                3,
+                vec![("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..])))],
+            )
+        };
+        let chunk_size = chunk(1).size();
+        assert!(chunk_size > 0);
+
+        let mut db = Database::new();
+        db.add_chunk(chunk(1));
+        db.add_chunk(chunk(2));
+        assert_eq!(db.size(), chunk_size * 2);
+
+        let removed = db.remove_chunk("1").unwrap();
+        assert_eq!(removed.id(), 1);
+        assert_eq!(db.size(), chunk_size);
+        assert_eq!(db.chunk_keys(), vec!["2"]);
+
+        // removing an unknown chunk is a no-op.
+        assert!(db.remove_chunk("1").is_none());
+        assert!(db.remove_chunk("unknown").is_none());
+        assert_eq!(db.size(), chunk_size);
+
+        // the store's size is kept in step with the database's.
+        let mut store = Store::new();
+        store.add_database("db1".to_string(), db);
+        assert!(store.remove_chunk("db2", "2").is_none());
+        assert_eq!(store.size(), chunk_size);
+        assert_eq!(store.remove_chunk("db1", "2").unwrap().id(), 2);
+        assert_eq!(store.size(), 0);
+    }
+
+    #[test]
+    fn database_compact_chunks() {
+        let cpu_chunk = |id, times: &[i64], regions: &[&str]| {
+            build_chunk(
+                id,
+                times.len() as u32,
+                vec![
+                    ("time", ColumnType::Time(Column::from(times))),
+                    ("region", ColumnType::Tag(Column::from(regions))),
+                ],
+            )
+        };
+
+        let mut db = Database::new();
+        db.add_chunk(cpu_chunk(1, &[1, 2, 3], &["west", "east", "west"]));
+        let mut chunk = cpu_chunk(2, &[20, 2], &["north", "west"]);
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(&[30_i64][..])),
+        );
+        chunk.add_table(Table::new("mem".to_string(), RowGroup::new(1, columns)));
+        db.add_chunk(chunk);
+        db.add_chunk(cpu_chunk(3, &[100], &["east"]));
+
+        let columns = vec!["time".to_string(), "region".to_string()];
+        // Excludes the chunk that isn't compacted.
+        let select = |db: &Database, table_name| {
+            let rb = db
+                .select(
+                    table_name,
+                    time_range(0, 50),
+                    &[],
+                    columns.clone(),
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .unwrap();
+            (i64_values(rb.column(0)), string_values(rb.column(1)))
+        };
+        let cpu_before = select(&db, "cpu");
+        let size = db.size();
+
+        // Nothing changes if any of the chunks are missing.
+        assert!(matches!(
+            db.compact_chunks(&["1".to_string(), "9".to_string()]),
+            Err(Error::ChunkNotFound { .. })
+        ));
+        assert!(matches!(
+            db.compact_chunks(&[]),
+            Err(Error::NoChunksToCompact)
+        ));
+        assert_eq!(db.chunk_keys(), vec!["1", "2", "3"]);
+
+        // Nor if the compacted chunk would need an id beyond `u32::MAX`.
+        let mut full_db = Database::new();
+        full_db.add_chunk(cpu_chunk(1, &[1], &["west"]));
+        full_db.add_chunk(cpu_chunk(u32::MAX, &[2], &["east"]));
+        assert!(matches!(
+            full_db.compact_chunks(&["1".to_string()]),
+            Err(Error::ChunkIdsExhausted { last_id }) if last_id == u32::MAX
+        ));
+        assert_eq!(full_db.chunk_keys().len(), 2);
+
+        let key = db
+            .compact_chunks(&["2".to_string(), "1".to_string()])
+            .unwrap();
+        assert_eq!(key, "4");
+        assert_eq!(db.chunk_keys(), vec!["3", "4"]);
+        assert_eq!(db.size(), size);
+
+        let chunk = &db.chunks["4"];
+        assert_eq!(chunk.rows(), 6);
+        assert!(chunk.overlaps_time_range(time_range(1, 2)));
+        assert!(chunk.overlaps_time_range(time_range(30, 31)));
+        assert!(!chunk.overlaps_time_range(time_range(31, 100)));
+
+        // Every row is preserved, in the same order.
+        assert_eq!(select(&db, "cpu"), cpu_before);
+        let rb = db
+            .select(
+                "mem",
+                time_range(0, 1000),
+                &[],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(30)]);
+
+        // The chunks' tables must have the same schema.
+        db.add_chunk(build_chunk(
+            5,
+            1,
+            vec![("time", ColumnType::Time(Column::from(&[7_i64][..])))],
+        ));
+        assert!(matches!(
+            db.compact_chunks(&["4".to_string(), "5".to_string()]),
+            Err(Error::IncompatibleSchemas { .. })
+        ));
+        assert_eq!(db.chunk_keys(), vec!["3", "4", "5"]);
+    }
+
+    #[test]
+    fn chunk_column_sizes() {
+        let regions = std::iter::repeat("west")
+            .take(500)
+            .chain(std::iter::repeat("east").take(500))
+            .collect::<Vec<_>>();
+        let times = (0..1000).collect::<Vec<i64>>();
+        let chunk = build_chunk(
+            1,
+            1000,
+            vec![
+                ("time", ColumnType::Time(Column::from(times.as_slice()))),
+                ("region", ColumnType::Tag(Column::from(regions.as_slice()))),
+            ],
+        );
+
+        let sizes = chunk.column_sizes();
+        assert_eq!(
+            sizes.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                ("cpu".to_string(), "region".to_string()),
+                ("cpu".to_string(), "time".to_string()),
+            ]
+        );
+        assert_eq!(sizes.values().sum::<u64>(), chunk.size());
+
+        // The repeated values are only stored once in the dictionary, so the
+        // column is far smaller than the same values held as strings.
+        let strings_size = std::mem::size_of::<Vec<String>>()
+            + regions
+                .iter()
+                .map(|v| std::mem::size_of::<String>() + v.len())
+                .sum::<usize>();
+        let region_size = sizes[&("cpu".to_string(), "region".to_string())];
+        assert!(region_size > 0);
+        assert!(region_size < strings_size as u64);
+    }
+
+    #[test]
+    fn database_select() {
+        let mut db = Database::new();
+
+        // The two chunks have slightly different schemas.
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west"][..])),
+                ),
+                (
+                    "humidity",
+                    ColumnType::Field(Column::from(&[10_u64, 20][..])),
+                ),
+            ],
+        ));
+
+        let select_columns = vec![
+            "time".to_string(),
+            "region".to_string(),
+            "temp".to_string(),
+            "humidity".to_string(),
+        ];
+
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[("region", (Operator::Equal, Value::String("west")))],
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(column_names(&rb), select_columns);
+        assert_eq!(i64_values(rb.column(0)), vec![Some(1), Some(3), Some(5)]);
+        assert_eq!(
+            string_values(rb.column(1)),
+            vec![Some("west"), Some("west"), Some("west")]
+        );
+        assert_eq!(f64_values(rb.column(2)), vec![Some(1.0), Some(3.0), None]);
+        assert_eq!(u64_values(rb.column(3)), vec![None, None, Some(20)]);
+
+        // Only the second chunk overlaps the time range.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(4, 10),
+                &[],
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(4), Some(5)]);
+        assert_eq!(f64_values(rb.column(2)), vec![None, None]);
+        assert_eq!(u64_values(rb.column(3)), vec![Some(10), Some(20)]);
+
+        // No chunks overlap the time range.
+        assert!(db
+            .select(
+                "cpu",
+                time_range(100, 200),
+                &[],
+                select_columns.clone(),
+                None,
+                0,
+                false
+            )
+            .unwrap()
+            .is_none());
+
+        // No chunks contain the table.
+        assert!(matches!(
+            db.select(
+                "mem",
+                time_range(0, 10),
+                &[],
+                select_columns,
+                None,
+                0,
+                false
+            ),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_all_columns() {
+        let mut db = Database::new();
+
+        // The "cpu" table gained the "c" column and lost the "b" column
+        // between the two chunks.
+        db.add_chunk(build_chunk(
+            1,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2][..]))),
+                ("a", ColumnType::Tag(Column::from(&["x", "y"][..]))),
+                ("b", ColumnType::Field(Column::from(&[1.0_f64, 2.0][..]))),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[3_i64, 4][..]))),
+                ("a", ColumnType::Tag(Column::from(&["y", "z"][..]))),
+                ("c", ColumnType::Field(Column::from(&[30_u64, 40][..]))),
+            ],
+        ));
+
+        let rb = db
+            .select("cpu", TimeRange::all(), &[], vec![], None, 0, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["a", "b", "c", "time"]);
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("x"), Some("y"), Some("y"), Some("z")]
+        );
+        assert_eq!(
+            f64_values(rb.column(1)),
+            vec![Some(1.0), Some(2.0), None, None]
+        );
+        assert_eq!(
+            u64_values(rb.column(2)),
+            vec![None, None, Some(30), Some(40)]
+        );
+        assert_eq!(
+            i64_values(rb.column(3)),
+            vec![Some(1), Some(2), Some(3), Some(4)]
+        );
+
+        // Only the columns of the chunks overlapping the time range are
+        // selected.
+        let rb = db
+            .select("cpu", time_range(3, 10), &[], vec![], None, 0, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["a", "c", "time"]);
+
+        // Streamed batches share the merged schema.
+        let batches = db
+            .select_stream("cpu", TimeRange::all(), &[], vec![])
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(batches.len(), 2);
+        for rb in &batches {
+            assert_eq!(column_names(rb), vec!["a", "b", "c", "time"]);
+        }
+        assert_eq!(u64_values(batches[0].column(2)), vec![None, None]);
+        assert_eq!(f64_values(batches[1].column(1)), vec![None, None]);
+
+        assert!(matches!(
+            db.select("mem", TimeRange::all(), &[], vec![], None, 0, false),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_projection() {
+        let mut db = Database::new();
+        // Columns are materialised on the calling thread.
+        db.set_parallel(false);
+        for id in 1..=2 {
+            let base = id as i64 * 10;
+            db.add_chunk(build_chunk(
+                id,
+                3,
+                vec![
+                    (
+                        "time",
+                        ColumnType::Time(Column::from(&[base, base + 1, base + 2][..])),
+                    ),
+                    (
+                        "region",
+                        ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                    ),
+                    ("host", ColumnType::Tag(Column::from(&["a", "b", "c"][..]))),
+                    (
+                        "temp",
+                        ColumnType::Field(Column::from(
+                            &[base as f64, base as f64 + 1.0, base as f64 + 2.0][..],
+                        )),
+                    ),
+                ],
+            ));
+        }
+        row_group::materialised_columns::take();
+
+        // Only the selected column is materialised in each chunk, not the
+        // columns the predicates and time range refer to.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 21),
+                &[("region", (Operator::Equal, Value::String("west")))],
+                vec!["temp".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["temp"]);
+        assert_eq!(
+            f64_values(rb.column(0)),
+            vec![Some(10.0), Some(12.0), Some(20.0)]
+        );
+        assert_eq!(
+            row_group::materialised_columns::take(),
+            vec!["temp", "temp"]
+        );
+
+        // Selecting every column materialises every column.
+        db.select("cpu", TimeRange::all(), &[], vec![], None, 0, false)
+            .unwrap()
+            .unwrap();
+        let materialised = row_group::materialised_columns::take()
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            materialised.into_iter().collect::<Vec<_>>(),
+            vec!["host", "region", "temp", "time"]
+        );
+    }
+
+    #[test]
+    fn database_select_limit_offset() {
+        let mut db = Database::new();
+        for id in 0..2 {
+            let times = (id * 10..id * 10 + 10).collect::<Vec<i64>>();
+            let temps = times.iter().map(|&t| t as f64).collect::<Vec<_>>();
+            db.add_chunk(build_chunk(
+                id as u32,
+                10,
+                vec![
+                    ("time", ColumnType::Time(Column::from(times.as_slice()))),
+                    ("temp", ColumnType::Field(Column::from(temps.as_slice()))),
+                ],
+            ));
+        }
+
+        let select = |limit, offset| {
+            let rb = db
+                .select(
+                    "cpu",
+                    time_range(0, 100),
+                    &[],
+                    vec!["time".to_string()],
+                    limit,
+                    offset,
+                    false,
+                )
+                .unwrap()
+                .unwrap();
+            i64_values(rb.column(0))
+                .into_iter()
+                .map(Option::unwrap)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(select(Some(5), 0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(select(Some(5), 8), vec![8, 9, 10, 11, 12]);
+        assert_eq!(select(None, 15), vec![15, 16, 17, 18, 19]);
+        assert_eq!(select(Some(100), 18), vec![18, 19]);
+        assert!(select(Some(5), 20).is_empty());
+        assert!(select(Some(0), 0).is_empty());
+        assert_eq!(select(None, 0).len(), 20);
+
+        // The second chunk isn't scanned once the first provides enough rows.
+        let time_predicates = time_range_predicate(time_range(0, 100));
+        let chunks_scanned = |rows_needed| {
+            let mut scanned = 0;
+            let chunk_values = db
+                .chunk_values(
+                    "cpu",
+                    time_range(0, 100),
+                    PredicateExpr::from(time_predicates.as_slice()),
+                    vec!["time".to_string()],
+                )
+                .inspect(|_| scanned += 1);
+            append_chunk_values(chunk_values, 1, rows_needed).unwrap();
+            scanned
+        };
+        assert_eq!(chunks_scanned(Some(5)), 1);
+        assert_eq!(chunks_scanned(Some(15)), 2);
+        assert_eq!(chunks_scanned(None), 2);
+    }
+
+    #[test]
+    fn database_time_bounds() {
+        let mut db = Database::new();
+        assert_eq!(db.time_bounds("cpu"), None);
+
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[10_i64, 20, 30][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        assert_eq!(db.time_bounds("cpu"), Some((10, 30)));
+
+        // The second chunk's range overlaps the first's, and it also contains
+        // a table with an earlier timestamp.
+        let mut chunk = build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[25_i64, 45][..]))),
+                ("temp", ColumnType::Field(Column::from(&[4.0_f64, 5.0][..]))),
+            ],
+        );
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(&[1_i64][..])),
+        );
+        chunk.add_table(Table::new("mem".to_string(), RowGroup::new(1, columns)));
+        db.add_chunk(chunk);
+
+        assert_eq!(db.time_bounds("cpu"), Some((10, 45)));
+        assert_eq!(db.time_bounds("mem"), Some((1, 1)));
+        assert_eq!(db.time_bounds("disk"), None);
+    }
+
+    #[test]
+    fn database_row_count() {
+        let row_group = |rows, columns: Vec<(&str, ColumnType)>| {
+            let columns = columns
+                .into_iter()
+                .map(|(name, column)| (name.to_string(), column))
+                .collect::<BTreeMap<_, _>>();
+            RowGroup::new(rows, columns)
+        };
+
+        // The first chunk's table has two row groups.
+        let mut table = Table::new(
+            "cpu".to_string(),
+            row_group(
+                3,
+                vec![
+                    ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                    (
+                        "region",
+                        ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                    ),
+                    (
+                        "temp",
+                        ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                    ),
+                ],
+            ),
+        );
+        table.add_segment(row_group(
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "east"][..])),
+                ),
+                ("temp", ColumnType::Field(Column::from(&[4.0_f64, 5.0][..]))),
+            ],
+        ));
+
+        let mut db = Database::new();
+        db.add_chunk(Chunk::new(1, table));
+        db.add_chunk(build_chunk(
+            2,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[6_i64, 7, 8][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "north", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[6.0_f64, 7.0, 8.0][..])),
+                ),
+            ],
+        ));
+
+        let selected_rows = |time_range: TimeRange, predicates: &[Predicate<'_>]| {
+            db.select(
+                "cpu",
+                time_range,
+                predicates,
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .map_or(0, |rb| rb.num_rows() as u64)
+        };
+
+        let cases: Vec<(TimeRange, Vec<Predicate<'_>>)> = vec![
+            (TimeRange::all(), vec![]),
+            // The first row group is entirely within the range, the second
+            // partially.
+            (time_range(1, 5), vec![]),
+            (time_range(2, 7), vec![]),
+            (time_range(100, 200), vec![]),
+            (
+                TimeRange::all(),
+                vec![("time", (Operator::GT, Value::Scalar(Scalar::I64(4))))],
+            ),
+            (
+                time_range(0, 100),
+                vec![("region", (Operator::Equal, Value::String("west")))],
+            ),
+            (
+                time_range(3, 7),
+                vec![("temp", (Operator::GTE, Value::from(4.0)))],
+            ),
+            (
+                TimeRange::all(),
+                vec![("region", (Operator::Equal, Value::String("south")))],
+            ),
+        ];
+        for (time_range, predicates) in &cases {
+            assert_eq!(
+                db.row_count("cpu", *time_range, predicates).unwrap(),
+                selected_rows(*time_range, predicates),
+                "{} {:?}",
+                time_range,
+                predicates
+            );
+        }
+
+        assert_eq!(db.row_count("cpu", TimeRange::all(), &[]).unwrap(), 8);
+        assert_eq!(db.row_count("cpu", time_range(2, 7), &[]).unwrap(), 5);
+
+        db.set_parallel(false);
+        assert_eq!(
+            db.row_count(
+                "cpu",
+                TimeRange::all(),
+                &[("region", (Operator::Equal, Value::String("west")))]
+            )
+            .unwrap(),
+            4
+        );
+
+        assert!(matches!(
+            db.row_count("mem", TimeRange::all(), &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_stream() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        // The second chunk doesn't have a "temp" column.
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "north"][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            3,
+            1,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[100_i64][..]))),
+                ("region", ColumnType::Tag(Column::from(&["west"][..]))),
+            ],
+        ));
+
+        let columns = vec!["time".to_string(), "temp".to_string()];
+        let west = [("region", (Operator::Equal, Value::String("west")))];
+        let batches = db
+            .select_stream("cpu", time_range(0, 10), &west, columns.clone())
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        // One batch for each chunk overlapping the time range.
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].schema(), batches[1].schema());
+        assert_eq!(column_names(&batches[0]), vec!["time", "temp"]);
+        assert_eq!(i64_values(batches[0].column(0)), vec![Some(1), Some(3)]);
+        assert_eq!(f64_values(batches[0].column(1)), vec![Some(1.0), Some(3.0)]);
+        assert_eq!(i64_values(batches[1].column(0)), vec![Some(4)]);
+        assert_eq!(f64_values(batches[1].column(1)), vec![None]);
+
+        // The same rows as `select`, which appends the chunk results.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &west,
+                columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(1), Some(3), Some(4)]);
+
+        assert_eq!(
+            db.select_stream("cpu", time_range(200, 300), &west, columns.clone())
+                .unwrap()
+                .count(),
+            0
+        );
+        assert!(matches!(
+            db.select_stream("cpu", time_range(0, 10), &west, vec!["nope".to_string()]),
+            Err(Error::ColumnNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_chunk_from_record_batches() {
+        let batch = |times: Vec<i64>, regions: Vec<&str>| {
+            let schema = Schema::new(vec![
+                Field::new("region", DataType::Utf8, false),
+                Field::new("time", DataType::Int64, false),
+            ]);
+            RecordBatch::try_new(
+                Arc::new(schema),
+                vec![
+                    Arc::new(StringArray::from(regions)),
+                    Arc::new(Int64Array::from(times)),
+                ],
+            )
+            .unwrap()
+        };
+
+        let mut tables = BTreeMap::new();
+        tables.insert(
+            "cpu".to_string(),
+            batch(
+                vec![1, 2, 3, 4, 5],
+                vec!["west", "east", "west", "north", "south"],
+            ),
+        );
+        tables.insert("mem".to_string(), batch(vec![20, 30], vec!["east", "west"]));
+        let chunk = Chunk::from_record_batches(1, tables, 2).unwrap();
+        assert_eq!(chunk.rows(), 7);
+        assert!(chunk.overlaps_time_range(time_range(25, 40)));
+
+        let mut db = Database::new();
+        db.add_chunk(chunk);
+
+        let columns = vec!["time".to_string(), "region".to_string()];
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            i64_values(rb.column(0)),
+            vec![Some(1), Some(2), Some(3), Some(4), Some(5)]
+        );
+        assert_eq!(
+            string_values(rb.column(1)),
+            vec![
+                Some("west"),
+                Some("east"),
+                Some("west"),
+                Some("north"),
+                Some("south")
+            ]
+        );
+
+        let rb = db
+            .select("mem", time_range(0, 100), &[], columns, None, 0, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(20), Some(30)]);
+    }
+
+    #[test]
+    fn store_add_chunk_null_fields() {
+        let schema = Schema::new(vec![
+            Field::new("region", DataType::Utf8, true),
+            Field::new("count", DataType::Int64, true),
+            Field::new("reads", DataType::UInt64, true),
+            Field::new("temp", DataType::Float64, true),
+            Field::new("time", DataType::Int64, false),
+        ]);
+        let rb = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec![Some("west"), None, Some("east")])),
+                Arc::new(Int64Array::from(vec![None, Some(2), Some(3)])),
+                Arc::new(UInt64Array::from(vec![Some(10), Some(20), None])),
+                Arc::new(Float64Array::from(vec![None, Some(1.5), None])),
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+
+        let mut tables = BTreeMap::new();
+        tables.insert("cpu".to_string(), rb);
+
+        // The second row group only contains NULL temperatures.
+        let mut store = Store::with_config(StoreConfig {
+            max_bytes: None,
+            max_row_group_rows: 2,
+        })
+        .unwrap();
+        store.add_chunk("db1".to_string(), 1, tables).unwrap();
+
+        let columns = ["region", "count", "reads", "temp"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let rb = store
+            .select(
+                "db1",
+                "cpu",
+                time_range(0, 10),
+                &[],
+                columns,
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("west"), None, Some("east")]
+        );
+        assert_eq!(i64_values(rb.column(1)), vec![None, Some(2), Some(3)]);
+        assert_eq!(u64_values(rb.column(2)), vec![Some(10), Some(20), None]);
+        assert_eq!(f64_values(rb.column(3)), vec![None, Some(1.5), None]);
+
+        // The all-NULL row group can't contain rows with a temperature.
+        let rb = store
+            .select(
+                "db1",
+                "cpu",
+                time_range(0, 10),
+                &[("temp", (Operator::GTE, Value::Scalar(Scalar::F64(0.0))))],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(2)]);
+    }
+
+    #[test]
+    fn store_add_chunk_boolean_field() {
+        let schema = Schema::new(vec![
+            Field::new("region", DataType::Utf8, true),
+            Field::new("active", DataType::Boolean, true),
+            Field::new("time", DataType::Int64, false),
+        ]);
+        let rb = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec!["west", "west", "east", "east"])),
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    None,
+                    Some(false),
+                    Some(true),
+                ])),
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            ],
+        )
+        .unwrap();
+
+        let mut tables = BTreeMap::new();
+        tables.insert("cpu".to_string(), rb);
+
+        let mut store = Store::new();
+        store.add_chunk("db1".to_string(), 1, tables).unwrap();
+
+        let rb = store
+            .select(
+                "db1",
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["active".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            bool_values(rb.column(0)),
+            vec![Some(true), None, Some(false), Some(true)]
+        );
+    }
+
+    #[test]
+    fn store_add_chunk_invalid() {
+        let mut store = Store::new();
+
+        // A chunk without any tables.
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 1, BTreeMap::new()),
+            Err(Error::EmptyChunk { chunk_id: 1 })
+        ));
+
+        let batch = |fields: Vec<Field>, columns: Vec<ArrayRef>| {
+            let rb = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+            let mut tables = BTreeMap::new();
+            tables.insert("cpu".to_string(), rb);
+            tables
+        };
+
+        // A table without any rows.
+        let tables = batch(
+            vec![Field::new("time", DataType::Int64, false)],
+            vec![Arc::new(Int64Array::from(Vec::<i64>::new()))],
+        );
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 1, tables),
+            Err(Error::EmptyTable { .. })
+        ));
+
+        // A table without a time column.
+        let tables = batch(
+            vec![Field::new("region", DataType::Utf8, false)],
+            vec![Arc::new(StringArray::from(vec!["west"]))],
+        );
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 1, tables),
+            Err(Error::InvalidTimeColumn { .. })
+        ));
+
+        // A time column with NULL values.
+        let tables = batch(
+            vec![Field::new("time", DataType::Int64, true)],
+            vec![Arc::new(Int64Array::from(vec![Some(1), None]))],
+        );
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 1, tables),
+            Err(Error::InvalidTimeColumn { .. })
+        ));
+
+        // A column with an unsupported type.
+        let tables = batch(
+            vec![
+                Field::new("data", DataType::Binary, false),
+                Field::new("time", DataType::Int64, false),
+            ],
+            vec![
+                Arc::new(BinaryArray::from(vec![&b"a"[..]])),
+                Arc::new(Int64Array::from(vec![1])),
+            ],
+        );
+        assert!(matches!(
+            store.add_chunk("db1".to_string(), 1, tables),
+            Err(Error::UnsupportedColumnType { .. })
+        ));
+
+        // None of the chunks were added.
+        assert_eq!(store.size(), 0);
+        assert!(store.database_names().is_empty());
+
+        assert!(matches!(
+            Store::with_config(StoreConfig {
+                max_bytes: None,
+                max_row_group_rows: 0,
+            }),
+            Err(Error::InvalidMaxRowGroupRows)
+        ));
+    }
+
+    #[test]
+    fn database_explain_select() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[20_i64, 21][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "north"][..])),
+                ),
+            ],
+        ));
+
+        // The time predicate narrows the range used to prune chunks, and the
+        // duplicated tag predicate is applied to rows once.
+        let predicates = [
+            ("time", (Operator::GTE, Value::from(10_i64))),
+            ("region", (Operator::Equal, Value::String("west"))),
+            ("region", (Operator::Equal, Value::String("west"))),
+        ];
+        let explanation = db
+            .explain_select("cpu", time_range(0, 100), &predicates)
+            .unwrap();
+        assert_eq!(
+            explanation,
+            SelectExplanation {
+                time_range: time_range(10, 100),
+                row_filters: vec!["region = west".to_string()],
+                chunk_ids: vec![2],
+            }
+        );
+        assert_eq!(
+            explanation.to_string(),
+            "chunk pruning: time >= 10 AND time < 100
+row filters: region = west
+chunks: [2]
+"
+        );
+
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 100),
+                &predicates,
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(20)]);
+
+        let explanation = db
+            .explain_select(
+                "cpu",
+                time_range(0, 100),
+                &[("time", (Operator::Equal, Value::from(2_i64)))],
+            )
+            .unwrap();
+        assert_eq!(explanation.time_range, time_range(2, 3));
+        assert!(explanation.row_filters.is_empty());
+        assert_eq!(explanation.chunk_ids, vec![1]);
+
+        // Predicates that can't bound the time range are kept as row filters.
+        let explanation = db
+            .explain_select(
+                "cpu",
+                time_range(0, 100),
+                &[("time", (Operator::NotEqual, Value::from(2_i64)))],
+            )
+            .unwrap();
+        assert_eq!(explanation.time_range, time_range(0, 100));
+        assert_eq!(explanation.row_filters, vec!["time != 2".to_string()]);
+        assert_eq!(explanation.chunk_ids, vec![1, 2]);
+
+        assert!(matches!(
+            db.explain_select("mem", time_range(0, 100), &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+
+        let mut store = Store::new();
+        store.add_database("db1".to_string(), db);
+        assert!(store
+            .explain_select("db1", "cpu", time_range(0, 100), &[])
+            .unwrap()
+            .is_some());
+        assert!(store
+            .explain_select("db2", "cpu", time_range(0, 100), &[])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn database_explain() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west", "west"][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[10_i64, 11][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["north", "west"][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            3,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[20_i64, 21, 22, 23][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "east", "east", "east"][..])),
+                ),
+            ],
+        ));
+
+        let estimate = db.explain("cpu", TimeRange::all(), &[]).unwrap();
+        assert_eq!(
+            estimate,
+            QueryPlanEstimate {
+                chunks_scanned: 3,
+                estimated_rows: 9,
+                columns: vec![],
+            }
+        );
+
+        // A more selective time range prunes chunks.
+        let estimate = db.explain("cpu", time_range(0, 15), &[]).unwrap();
+        assert_eq!(estimate.chunks_scanned, 2);
+        assert_eq!(estimate.estimated_rows, 5);
+        assert_eq!(estimate.columns, vec!["time"]);
+
+        let estimate = db.explain("cpu", time_range(0, 5), &[]).unwrap();
+        assert_eq!(estimate.chunks_scanned, 1);
+        assert_eq!(estimate.estimated_rows, 3);
+
+        // Predicates prune chunks whose column ranges rule them out, but
+        // don't reduce the estimated rows of the chunks that are scanned.
+        let estimate = db
+            .explain(
+                "cpu",
+                time_range(0, 100),
+                &[("region", (Operator::Equal, Value::String("west")))],
+            )
+            .unwrap();
+        assert_eq!(estimate.chunks_scanned, 2);
+        assert_eq!(estimate.estimated_rows, 5);
+        assert_eq!(estimate.columns, vec!["region", "time"]);
+
+        assert!(matches!(
+            db.explain("mem", time_range(0, 100), &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_negative_timestamps() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[-5000_i64, -4000, -3000][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[-2000_i64, -500, 0, 500][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "east", "west"][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            3,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[999_i64, 2000][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west"][..])),
+                ),
+            ],
+        ));
+
+        let select = |time_range: TimeRange, predicates: &[Predicate<'_>]| {
+            db.select(
+                "cpu",
+                time_range,
+                predicates,
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .map_or(vec![], |rb| i64_values(rb.column(0)))
+        };
+
+        // The range spans the epoch boundary.
+        assert_eq!(
+            select(time_range(-1000, 1000), &[]),
+            vec![Some(-500), Some(0), Some(500), Some(999)]
+        );
+        assert_eq!(
+            db.explain_select("cpu", time_range(-1000, 1000), &[])
+                .unwrap()
+                .chunk_ids,
+            vec![2, 3]
+        );
+        assert_eq!(
+            db.row_count("cpu", time_range(-1000, 1000), &[]).unwrap(),
+            4
+        );
+
+        // The range lies entirely before the epoch.
+        assert_eq!(
+            select(time_range(-4500, -1000), &[]),
+            vec![Some(-4000), Some(-3000), Some(-2000)]
+        );
+        assert_eq!(
+            select(
+                time_range(-1000, 1000),
+                &[("region", (Operator::Equal, Value::String("west")))]
+            ),
+            vec![Some(-500), Some(500)]
+        );
+
+        // Negative bounds on the time column are folded into the time range.
+        assert_eq!(
+            select(
+                TimeRange::all(),
+                &[
+                    ("time", (Operator::GT, Value::from(-2000_i64))),
+                    ("time", (Operator::LTE, Value::from(-500_i64))),
+                ]
+            ),
+            vec![Some(-500)]
+        );
+        assert_eq!(
+            select(TimeRange::before(-3500), &[]),
+            vec![Some(-5000), Some(-4000)]
+        );
+    }
+
+    #[test]
+    fn database_chunk_pruning() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["north", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[50.0_f64, 60.0][..])),
+                ),
+            ],
+        ));
+
+        let chunk_ids = |predicates: &[Predicate<'_>]| {
+            db.explain_select("cpu", time_range(0, 100), predicates)
+                .unwrap()
+                .chunk_ids
+        };
+        let select = |predicates: &[Predicate<'_>]| {
+            let rb = db
+                .select(
+                    "cpu",
+                    time_range(0, 100),
+                    predicates,
+                    vec!["time".to_string()],
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .unwrap();
+            i64_values(rb.column(0))
+        };
+
+        // The first chunk's temperatures are all below the bound.
+        let hot = [("temp", (Operator::GT, Value::from(40.0)))];
+        assert_eq!(chunk_ids(&hot), vec![2]);
+        assert_eq!(select(&hot), vec![Some(4), Some(5)]);
+
+        // "north" is outside of the first chunk's range of regions, and "east"
+        // outside of the second's.
+        let north = [("region", (Operator::Equal, Value::String("north")))];
+        assert_eq!(chunk_ids(&north), vec![2]);
+        assert_eq!(select(&north), vec![Some(4)]);
+        let east = [("region", (Operator::Equal, Value::String("east")))];
+        assert_eq!(chunk_ids(&east), vec![1]);
+        assert_eq!(select(&east), vec![Some(1)]);
+
+        // Ranges can't rule out "west" from either chunk.
+        let west = [("region", (Operator::Equal, Value::String("west")))];
+        assert_eq!(chunk_ids(&west), vec![1, 2]);
+        assert_eq!(select(&west), vec![Some(2), Some(3), Some(5)]);
+
+        // Pruned chunks still contain the table, so an empty result is
+        // returned rather than none at all.
+        let hotter = [("temp", (Operator::GTE, Value::from(100.0)))];
+        assert!(chunk_ids(&hotter).is_empty());
+        assert!(select(&hotter).is_empty());
+
+        let aggregate = |predicates: &[Predicate<'_>]| {
+            let rb = db
+                .aggregate(
+                    "cpu",
+                    time_range(0, 100),
+                    predicates,
+                    vec!["region".to_string()],
+                    vec![("temp", AggregateType::Count, None)],
+                )
+                .unwrap()
+                .unwrap();
+            (string_values(rb.column(0)).len(), u64_values(rb.column(1)))
+        };
+        assert_eq!(aggregate(&hot), (2, vec![Some(1), Some(1)]));
+        assert_eq!(aggregate(&hotter), (0, vec![]));
+    }
+
+    #[test]
+    fn database_time_index() {
+        let mut chunk = build_chunk(
+            1,
+            3,
+            vec![("time", ColumnType::Time(Column::from(&[10_i64, 2, 30][..])))],
+        );
+        assert_eq!(chunk.time_range(), Some((2, 30)));
+
+        // The range covers the timestamps of every table.
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(&[45_i64][..])),
+        );
+        chunk.add_table(Table::new("mem".to_string(), RowGroup::new(1, columns)));
+        assert_eq!(chunk.time_range(), Some((2, 45)));
+
+        let mut db = Database::new();
+        db.add_chunk(chunk);
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![("time", ColumnType::Time(Column::from(&[100_i64, 200][..])))],
+        ));
+        db.add_chunk(build_chunk(
+            3,
+            1,
+            vec![("time", ColumnType::Time(Column::from(&[40_i64][..])))],
+        ));
+
+        let chunk_ids = |db: &Database, time_range: TimeRange| {
+            db.overlapping_chunks(time_range)
+                .iter()
+                .map(|chunk| chunk.id())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(chunk_ids(&db, time_range(0, 1000)), vec![1, 2, 3]);
+        assert_eq!(chunk_ids(&db, time_range(40, 41)), vec![1, 3]);
+        assert_eq!(chunk_ids(&db, time_range(45, 100)), vec![1]);
+        assert_eq!(chunk_ids(&db, time_range(200, 201)), vec![2]);
+
+        // No chunks are touched by a query outside of all of their ranges.
+        assert!(chunk_ids(&db, time_range(46, 100)).is_empty());
+        assert!(chunk_ids(&db, time_range(201, 1000)).is_empty());
+        let rb = db
+            .select(
+                "cpu",
+                time_range(46, 100),
+                &[],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap();
+        assert!(rb.is_none());
+
+        // The index tracks removed and replaced chunks.
+        db.remove_chunk("3");
+        assert_eq!(chunk_ids(&db, time_range(40, 41)), vec![1]);
+        db.add_chunk(build_chunk(
+            2,
+            1,
+            vec![("time", ColumnType::Time(Column::from(&[50_i64][..])))],
+        ));
+        assert_eq!(chunk_ids(&db, time_range(50, 51)), vec![2]);
+        assert!(chunk_ids(&db, time_range(100, 1000)).is_empty());
+    }
+
+    #[test]
+    fn database_unbounded_time_range() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![("time", ColumnType::Time(Column::from(&[-20_i64, 2, 3][..])))],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![(
+                "time",
+                ColumnType::Time(Column::from(&[40_i64, i64::MAX][..])),
+            )],
+        ));
+
+        let chunk_ids =
+            |time_range: TimeRange| db.explain_select("cpu", time_range, &[]).unwrap().chunk_ids;
+        let select = |time_range: TimeRange| {
+            let rb = db
+                .select(
+                    "cpu",
+                    time_range,
+                    &[],
+                    vec!["time".to_string()],
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .unwrap();
+            i64_values(rb.column(0))
+        };
+
+        // start-only range
+        assert_eq!(chunk_ids(TimeRange::since(3)), vec![1, 2]);
+        assert_eq!(
+            select(TimeRange::since(3)),
+            vec![Some(3), Some(40), Some(i64::MAX)]
+        );
+        assert_eq!(chunk_ids(TimeRange::since(4)), vec![2]);
+
+        // end-only range
+        assert_eq!(chunk_ids(TimeRange::before(40)), vec![1]);
+        assert_eq!(
+            select(TimeRange::before(40)),
+            vec![Some(-20), Some(2), Some(3)]
+        );
+        assert!(chunk_ids(TimeRange::before(-20)).is_empty());
+
+        assert_eq!(
+            select(TimeRange::all()),
+            vec![Some(-20), Some(2), Some(3), Some(40), Some(i64::MAX)]
+        );
+
+        // Predicates on the time column narrow an unbounded side of the range.
+        let explanation = db
+            .explain_select(
+                "cpu",
+                TimeRange::since(0),
+                &[("time", (Operator::LTE, Value::Scalar(Scalar::I64(2))))],
+            )
+            .unwrap();
+        assert_eq!(explanation.time_range, time_range(0, 3));
+        assert_eq!(explanation.chunk_ids, vec![1]);
+
+        // Bounds including `i64::MAX` leave the end of the range unbounded.
+        let predicates = |op| [("time", (op, Value::Scalar(Scalar::I64(i64::MAX))))];
+        let explanation = db
+            .explain_select("cpu", TimeRange::since(0), &predicates(Operator::LTE))
+            .unwrap();
+        assert_eq!(explanation.time_range, TimeRange::since(0));
+        assert_eq!(explanation.chunk_ids, vec![1, 2]);
+
+        let explanation = db
+            .explain_select("cpu", TimeRange::all(), &predicates(Operator::Equal))
+            .unwrap();
+        assert_eq!(explanation.time_range, TimeRange::since(i64::MAX));
+        assert_eq!(explanation.chunk_ids, vec![2]);
+        let rb = db
+            .select(
+                "cpu",
+                TimeRange::all(),
+                &predicates(Operator::Equal),
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(i64::MAX)]);
+
+        // Nothing is after `i64::MAX`, and inverted bounds rule out every time.
+        let explanation = db
+            .explain_select("cpu", TimeRange::all(), &predicates(Operator::GT))
+            .unwrap();
+        assert!(explanation.time_range.is_empty());
+        assert!(explanation.chunk_ids.is_empty());
+
+        let explanation = db
+            .explain_select(
+                "cpu",
+                TimeRange::all(),
+                &[
+                    ("time", (Operator::GT, Value::Scalar(Scalar::I64(10)))),
+                    ("time", (Operator::LT, Value::Scalar(Scalar::I64(5)))),
+                ],
+            )
+            .unwrap();
+        assert!(explanation.time_range.is_empty());
+        assert!(explanation.chunk_ids.is_empty());
+    }
+
+    #[test]
+    fn database_select_tables() {
+        let build = |id, table_name: &str, times: &[i64], regions: &[&str]| {
+            let mut columns = BTreeMap::new();
+            columns.insert("time".to_string(), ColumnType::Time(Column::from(times)));
+            columns.insert("region".to_string(), ColumnType::Tag(Column::from(regions)));
+            let row_group = RowGroup::new(times.len() as u32, columns);
+            Chunk::new(id, Table::new(table_name.to_string(), row_group))
+        };
+
+        let mut db = Database::new();
+        db.add_chunk(build(1, "cpu", &[1, 2, 3], &["west", "east", "west"]));
+        db.add_chunk(build(2, "mem", &[1, 2], &["east", "west"]));
+        db.add_chunk(build(3, "disk", &[1, 2], &["east", "north"]));
+
+        let table_names = vec!["cpu".to_string(), "mem".to_string(), "disk".to_string()];
+        let west = [("region", (Operator::Equal, Value::String("west")))];
+        let results = db
+            .select_tables(
+                &table_names,
+                time_range(0, 10),
+                &west,
+                vec!["time".to_string()],
+            )
+            .unwrap();
+
+        // "disk" has no rows in the west region.
+        assert_eq!(results.keys().collect::<Vec<_>>(), vec!["cpu", "mem"]);
+        assert_eq!(i64_values(results["cpu"].column(0)), vec![Some(1), Some(3)]);
+        assert_eq!(i64_values(results["mem"].column(0)), vec![Some(2)]);
+
+        assert!(db
+            .select_tables(
+                &table_names,
+                time_range(100, 200),
+                &west,
+                vec!["time".to_string()]
+            )
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            db.select_tables(
+                &["cpu".to_string(), "nope".to_string()],
+                time_range(0, 10),
+                &[],
+                vec!["time".to_string()]
+            ),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn store_select_all_databases() {
+        let build = |id, table_name: &str, times: &[i64], regions: &[&str]| {
+            let mut columns = BTreeMap::new();
+            columns.insert("time".to_string(), ColumnType::Time(Column::from(times)));
+            columns.insert("region".to_string(), ColumnType::Tag(Column::from(regions)));
+            let row_group = RowGroup::new(times.len() as u32, columns);
+            Chunk::new(id, Table::new(table_name.to_string(), row_group))
+        };
+
+        let mut store = Store::new();
+        let mut db = Database::new();
+        db.add_chunk(build(1, "cpu", &[1, 2, 3], &["west", "east", "west"]));
+        store.add_database("db1".to_string(), db);
+
+        let mut db = Database::new();
+        db.add_chunk(build(1, "cpu", &[4, 5], &["east", "west"]));
+        store.add_database("db2".to_string(), db);
+
+        let mut db = Database::new();
+        db.add_chunk(build(1, "cpu", &[1, 2], &["east", "north"]));
+        store.add_database("db3".to_string(), db);
+
+        let mut db = Database::new();
+        db.add_chunk(build(1, "mem", &[1, 2], &["west", "west"]));
+        store.add_database("db4".to_string(), db);
+
+        let west = [("region", (Operator::Equal, Value::String("west")))];
+        let results = store
+            .select_all_databases("cpu", time_range(0, 10), &west, vec!["time".to_string()])
+            .unwrap();
+
+        // "db3" has no rows in the west region and "db4" has no "cpu" table.
+        assert_eq!(results.keys().collect::<Vec<_>>(), vec!["db1", "db2"]);
+        assert_eq!(i64_values(results["db1"].column(0)), vec![Some(1), Some(3)]);
+        assert_eq!(i64_values(results["db2"].column(0)), vec![Some(5)]);
+
+        assert!(store
+            .select_all_databases("cpu", time_range(100, 200), &west, vec!["time".to_string()])
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            store.select_all_databases("cpu", time_range(0, 10), &[], vec!["nope".to_string()]),
+            Err(Error::ColumnNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_distinct() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                ("host", ColumnType::Tag(Column::from(&["a", "b", "a"][..]))),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[10_i64, 11][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "north"][..])),
+                ),
+                ("host", ColumnType::Tag(Column::from(&["a", "c"][..]))),
+                ("temp", ColumnType::Field(Column::from(&[4.0_f64, 5.0][..]))),
+            ],
+        ));
+
+        let columns = vec!["region".to_string(), "host".to_string()];
+        let distinct = |time_range, predicates: &[Predicate<'_>]| {
+            let rb = db
+                .select_distinct("cpu", time_range, predicates, columns.clone())
+                .unwrap()
+                .unwrap();
+            assert_eq!(column_names(&rb), vec!["region", "host"]);
+            string_values(rb.column(0))
+                .into_iter()
+                .zip(string_values(rb.column(1)))
+                .map(|(region, host)| (region.unwrap().to_string(), host.unwrap().to_string()))
+                .collect::<Vec<_>>()
+        };
+        let pair = |region: &str, host: &str| (region.to_string(), host.to_string());
+
+        // (west, a) appears in both chunks but is returned once.
+        assert_eq!(
+            distinct(time_range(0, 100), &[]),
+            vec![pair("east", "b"), pair("north", "c"), pair("west", "a")]
+        );
+        assert_eq!(
+            distinct(time_range(5, 100), &[]),
+            vec![pair("north", "c"), pair("west", "a")]
+        );
+        assert_eq!(
+            distinct(
+                time_range(0, 100),
+                &[("temp", (Operator::GT, Value::from(1.5_f64)))]
+            ),
+            vec![pair("east", "b"), pair("north", "c"), pair("west", "a")]
+        );
+        assert_eq!(
+            distinct(
+                time_range(0, 100),
+                &[("region", (Operator::NotEqual, Value::String("west")))]
+            ),
+            vec![pair("east", "b"), pair("north", "c")]
+        );
+
+        // An empty selection selects every column.
+        let rb = db
+            .select_distinct("cpu", time_range(0, 100), &[], vec![])
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["host", "region", "temp", "time"]);
+        assert_eq!(rb.num_rows(), 5);
+
+        assert!(db
+            .select_distinct("cpu", time_range(100, 200), &[], columns.clone())
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            db.select_distinct("cpu", time_range(0, 100), &[], vec!["nope".to_string()]),
+            Err(Error::ColumnNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_with_range_predicates() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[10.0_f64, 60.0, 55.5, 80.0][..])),
+                ),
+            ],
+        ));
+
+        let select_columns = vec!["time".to_string(), "temp".to_string()];
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[
+                    ("region", (Operator::Equal, Value::String("west"))),
+                    ("temp", (Operator::GT, Value::from(50.0_f64))),
+                    ("temp", (Operator::LTE, Value::from(80_i64))),
+                ],
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(3), Some(4)]);
+        assert_eq!(f64_values(rb.column(1)), vec![Some(55.5), Some(80.0)]);
+
+        // Disjoint ranges match no rows.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[
+                    ("temp", (Operator::LT, Value::from(20_i64))),
+                    ("temp", (Operator::GT, Value::from(70_i64))),
+                ],
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap();
+        assert!(rb.map_or(true, |rb| rb.num_rows() == 0));
+
+        // Float literals can't be compared against integer columns.
+        assert!(matches!(
+            db.select(
+                "cpu",
+                time_range(0, 10),
+                &[("time", (Operator::GT, Value::from(1.5_f64)))],
+                select_columns,
+                None,
+                0,
+                false,
+            ),
+            Err(Error::UnsupportedPredicate { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_with_regex_predicates() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "host",
+                    ColumnType::Tag(Column::from(
+                        &["prod-a", "dev-b", "prod-c", "staging-prod"][..],
+                    )),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0][..])),
+                ),
+            ],
+        ));
+
+        let select = |op, pattern: &str| {
+            db.select(
+                "cpu",
+                time_range(0, 10),
+                &[("host", (op, Value::String(pattern)))],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            )
+        };
+
+        let rb = select(Operator::RegexMatch, "^prod-").unwrap().unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(1), Some(3)]);
+
+        let rb = select(Operator::RegexNotMatch, "^prod-").unwrap().unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(2), Some(4)]);
+
+        // Rows matching any of the patterns are selected.
+        let patterns = column::cmp::regex_match_any_pattern(&["^prod-", "^dev-"]);
+        let rb = select(Operator::RegexMatchAny, &patterns).unwrap().unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(1), Some(2), Some(3)]);
+
+        let patterns = column::cmp::regex_match_any_pattern(&["^prod-", "^qa-"]);
+        let rb = select(Operator::RegexMatchAny, &patterns).unwrap().unwrap();
+        assert_eq!(i64_values(rb.column(0)), vec![Some(1), Some(3)]);
+
+        // Any invalid pattern is an error.
+        let patterns = column::cmp::regex_match_any_pattern(&["^prod-", "^dev-("]);
+        assert!(matches!(
+            select(Operator::RegexMatchAny, &patterns),
+            Err(Error::InvalidRegex { .. })
+        ));
+
+        // Invalid patterns are an error.
+        assert!(matches!(
+            select(Operator::RegexMatch, "^prod-("),
+            Err(Error::InvalidRegex { .. })
+        ));
+
+        // Regular expressions can only be matched against string columns.
+        assert!(matches!(
+            db.select(
+                "cpu",
+                time_range(0, 10),
+                &[("temp", (Operator::RegexMatch, Value::String("^1")))],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            ),
+            Err(Error::UnsupportedPredicate { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_with_null_predicates() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            5,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(
+                        &[Some("west"), None, Some("east"), None, Some("west")][..],
+                    )),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0, 5.0][..])),
+                ),
+            ],
+        ));
+
+        let select = |predicates: &[Predicate<'_>]| {
+            let rb = db
+                .select(
+                    "cpu",
+                    TimeRange::all(),
+                    predicates,
+                    vec!["time".to_string()],
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .unwrap();
+            i64_values(rb.column(0))
+        };
+
+        assert_eq!(
+            select(&[("region", (Operator::IsNull, Value::Null))]),
+            vec![Some(2), Some(4)]
+        );
+        assert_eq!(
+            select(&[("region", (Operator::IsNotNull, Value::Null))]),
+            vec![Some(1), Some(3), Some(5)]
+        );
+
+        // NULL values never satisfy a comparison.
+        assert_eq!(
+            select(&[("region", (Operator::Equal, Value::String("west")))]),
+            vec![Some(1), Some(5)]
+        );
+        assert_eq!(
+            select(&[("region", (Operator::NotEqual, Value::String("west")))]),
+            vec![Some(3)]
+        );
+        // Even when the value is outside of the column's range.
+        assert_eq!(
+            select(&[("region", (Operator::NotEqual, Value::String("north")))]),
+            vec![Some(1), Some(3), Some(5)]
+        );
+        assert_eq!(
+            select(&[
+                ("region", (Operator::IsNull, Value::Null)),
+                ("region", (Operator::Equal, Value::String("west"))),
+            ]),
+            vec![]
+        );
+
+        // Columns without NULL values are answered from their metadata.
+        assert_eq!(
+            select(&[("temp", (Operator::IsNotNull, Value::Null))]),
+            vec![Some(1), Some(2), Some(3), Some(4), Some(5)]
+        );
+        assert!(select(&[("temp", (Operator::IsNull, Value::Null))]).is_empty());
+
+        // NULL checks take no value.
+        assert!(matches!(
+            db.select(
+                "cpu",
+                TimeRange::all(),
+                &[("region", (Operator::IsNull, Value::String("west")))],
+                vec!["time".to_string()],
+                None,
+                0,
+                false,
+            ),
+            Err(Error::UnsupportedPredicate { .. })
+        ));
+    }
+
+    #[test]
+    fn database_select_column_order() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east"][..])),
+                ),
+                ("temp", ColumnType::Field(Column::from(&[1.0_f64, 2.0][..]))),
+            ],
+        ));
+
+        // Columns are returned in the order requested, rather than schema
+        // order.
+        let select_columns = vec!["temp".to_string(), "time".to_string(), "region".to_string()];
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                select_columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["temp", "time", "region"]);
+        assert_eq!(f64_values(rb.column(0)), vec![Some(1.0), Some(2.0)]);
+        assert_eq!(i64_values(rb.column(1)), vec![Some(1), Some(2)]);
+        assert_eq!(
+            string_values(rb.column(2)),
+            vec![Some("west"), Some("east")]
+        );
+
+        let cases = vec![
+            (TimeColumnPosition::First, vec!["time", "temp", "region"]),
+            (TimeColumnPosition::Last, vec!["temp", "region", "time"]),
+            (TimeColumnPosition::AsListed, vec!["temp", "time", "region"]),
+        ];
+        for (time_position, expected) in cases {
+            let rb = db
+                .select_with_time_position(
+                    "cpu",
+                    time_range(0, 10),
+                    &[],
+                    select_columns.clone(),
+                    time_position,
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(column_names(&rb), expected);
+        }
+
+        // The time column isn't added if it's not selected.
+        let rb = db
+            .select_with_time_position(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                TimeColumnPosition::First,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["region"]);
+    }
+
+    #[test]
+    fn database_predicate_expr() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[10_u64, 20, 30][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5, 6][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west", "north"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[5_u64, 1, 100][..])),
+                ),
+            ],
+        ));
+
+        let east = ("region", (Operator::Equal, Value::String("east")));
+        let north = ("region", (Operator::Equal, Value::String("north")));
+        let expr = PredicateExpr::Or(vec![PredicateExpr::Leaf(east), PredicateExpr::Leaf(north)]);
+        let select_columns = vec!["time".to_string(), "counter".to_string()];
+
+        // The disjunction selects the union of the rows selected by each
+        // equality predicate.
+        let selected_times = |predicates: &PredicateExpr<'_>| {
+            let rb = db
+                .select_expr("cpu", time_range(0, 10), predicates, select_columns.clone())
+                .unwrap()
+                .unwrap();
+            let mut times = i64_values(rb.column(0));
+            times.sort();
+            times
+        };
+        let mut union = selected_times(&PredicateExpr::Leaf(east));
+        union.extend(selected_times(&PredicateExpr::Leaf(north)));
+        union.sort();
+        assert_eq!(selected_times(&expr), union);
+        assert_eq!(union, vec![Some(2), Some(4), Some(6)]);
+
+        // Likewise for aggregates, where each group is only in one side of the
+        // disjunction.
+        let aggregates = vec![("counter", AggregateType::Sum, None)];
+        let aggregate = |predicates: &PredicateExpr<'_>| {
+            let rb = db
+                .aggregate_expr(
+                    "cpu",
+                    time_range(0, 10),
+                    predicates,
+                    vec!["region".to_string()],
+                    aggregates.clone(),
+                )
+                .unwrap()
+                .unwrap();
+            string_values(rb.column(0))
+                .into_iter()
+                .map(|region| region.map(str::to_string))
+                .zip(u64_values(rb.column(1)))
+                .collect::<Vec<_>>()
+        };
+        let mut union = aggregate(&PredicateExpr::Leaf(east));
+        union.extend(aggregate(&PredicateExpr::Leaf(north)));
+        union.sort();
+        assert_eq!(aggregate(&expr), union);
+        assert_eq!(
+            union,
+            vec![
+                (Some("east".to_string()), Some(25)),
+                (Some("north".to_string()), Some(100))
+            ]
+        );
+
+        // Disjunctions can be nested within conjunctions.
+        let expr = PredicateExpr::And(vec![
+            PredicateExpr::Leaf(("counter", (Operator::GT, Value::from(10_u64)))),
+            PredicateExpr::Or(vec![
+                PredicateExpr::Leaf(east),
+                PredicateExpr::Leaf(("region", (Operator::Equal, Value::String("west")))),
+            ]),
+        ]);
+        assert_eq!(selected_times(&expr), vec![Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn database_table_names() {
+        let build = |id, table_name: &str, times: &[i64], states: &[&str]| {
+            let mut columns = BTreeMap::new();
+            columns.insert("time".to_string(), ColumnType::Time(Column::from(times)));
+            columns.insert("state".to_string(), ColumnType::Tag(Column::from(states)));
+            let row_group = RowGroup::new(times.len() as u32, columns);
+            Chunk::new(id, Table::new(table_name.to_string(), row_group))
+        };
+
+        let mut db = Database::new();
+        db.add_chunk(build(1, "cpu", &[1, 2, 3], &["CA", "MA", "CA"]));
+        db.add_chunk(build(2, "mem", &[1, 20], &["MA", "CA"]));
+        db.add_chunk(build(3, "disk", &[100], &["CA"]));
+
+        let table_names = |time_range, predicates: &[Predicate<'_>]| {
+            db.table_names("db", time_range, predicates)
+                .unwrap()
+                .map(|rb| {
+                    assert_eq!(column_names(&rb), vec![TABLE_NAMES_COLUMN_NAME]);
+                    string_values(rb.column(0))
+                        .into_iter()
+                        .map(|name| name.unwrap().to_string())
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        assert_eq!(
+            table_names(time_range(0, 10), &[]),
+            Some(vec!["cpu".to_string(), "mem".to_string()])
+        );
+
+        // "mem" only has a CA row outside of the requested time range.
+        let ca = [("state", (Operator::Equal, Value::String("CA")))];
+        assert_eq!(
+            table_names(time_range(0, 10), &ca),
+            Some(vec!["cpu".to_string()])
+        );
+        assert_eq!(
+            table_names(time_range(0, 1000), &ca),
+            Some(vec![
+                "cpu".to_string(),
+                "disk".to_string(),
+                "mem".to_string()
+            ])
+        );
+
+        // no tables contain rows satisfying the predicates.
+        let ny = [("state", (Operator::Equal, Value::String("NY")))];
+        assert_eq!(table_names(time_range(0, 1000), &ny), None);
+
+        // predicates on columns that don't exist match no rows.
+        let region = [("region", (Operator::Equal, Value::String("west")))];
+        assert_eq!(table_names(time_range(0, 1000), &region), None);
+
+        // The fast variant only considers chunk time ranges and table schemas,
+        // so it returns a superset of the exact table names.
+        let table_names_fast = |time_range, predicates: &[Predicate<'_>]| {
+            db.table_names_fast(time_range, predicates).map(|rb| {
+                string_values(rb.column(0))
+                    .into_iter()
+                    .map(|name| name.unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            table_names_fast(time_range(0, 10), &[]),
+            table_names(time_range(0, 10), &[])
+        );
+        assert_eq!(
+            table_names_fast(time_range(0, 10), &ca),
+            Some(vec!["cpu".to_string(), "mem".to_string()])
+        );
+        assert_eq!(
+            table_names_fast(time_range(0, 1000), &ny),
+            Some(vec![
+                "cpu".to_string(),
+                "disk".to_string(),
+                "mem".to_string()
+            ])
+        );
+        assert_eq!(table_names_fast(time_range(0, 1000), &region), None);
+        assert_eq!(table_names_fast(time_range(200, 1000), &ca), None);
+    }
+
+    #[test]
+    fn database_has_table() {
+        let mut db = Database::new();
+        assert!(!db.has_table("cpu"));
+
+        db.add_chunk(build_chunk(
+            1,
+            2,
+            vec![("time", ColumnType::Time(Column::from(&[1_i64, 2][..])))],
+        ));
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(&[30_i64][..])),
+        );
+        db.add_chunk(Chunk::new(
+            2,
+            Table::new("mem".to_string(), RowGroup::new(1, columns)),
+        ));
+
+        assert!(db.chunks["1"].contains_table("cpu"));
+        assert!(!db.chunks["1"].contains_table("mem"));
+        assert!(db.chunks["2"].contains_table("mem"));
+        assert!(!db.chunks["2"].contains_table("cpu"));
+
+        // A table is present if any chunk contains it.
+        assert!(db.has_table("cpu"));
+        assert!(db.has_table("mem"));
+        assert!(!db.has_table("disk"));
+
+        db.remove_chunk("1").unwrap();
+        assert!(!db.has_table("cpu"));
+        assert!(db.has_table("mem"));
+    }
+
+    #[test]
+    fn database_schema_cache() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "env",
+                    ColumnType::Tag(Column::from(&["prod", "stag", "prod"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+
+        let tag_keys = |db: &Database, time_range| {
+            db.tag_keys("cpu", time_range, &[]).unwrap().map(|rb| {
+                assert_eq!(rb.schema().field(0).name(), TAG_KEYS_COLUMN_NAME);
+                string_values(rb.column(0))
+                    .into_iter()
+                    .map(|name| name.unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+        };
+        let column_names = |db: &Database| {
+            db.table_schema("cpu", TimeRange::all())
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+
+        // Queries spanning all time are served from the cache, and agree with
+        // those visiting each chunk.
+        let bounded = time_range(i64::MIN, i64::MAX);
+        assert_eq!(
+            tag_keys(&db, TimeRange::all()),
+            Some(vec!["env".to_string()])
+        );
+        assert_eq!(tag_keys(&db, TimeRange::all()), tag_keys(&db, bounded));
+        assert_eq!(column_names(&db), vec!["env", "temp", "time"]);
+        assert_eq!(
+            db.table_schema("cpu", TimeRange::all()).unwrap(),
+            db.table_schema("cpu", bounded).unwrap()
+        );
+
+        // A chunk with a new tag column invalidates the cached schema.
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                ("env", ColumnType::Tag(Column::from(&["prod", "prod"][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east"][..])),
+                ),
+                ("temp", ColumnType::Field(Column::from(&[4.0_f64, 5.0][..]))),
+            ],
+        ));
+        assert_eq!(
+            tag_keys(&db, TimeRange::all()),
+            Some(vec!["env".to_string(), "region".to_string()])
+        );
+        assert_eq!(tag_keys(&db, TimeRange::all()), tag_keys(&db, bounded));
+        assert_eq!(column_names(&db), vec!["env", "region", "temp", "time"]);
+
+        let table_names = db
+            .table_names("db", TimeRange::all(), &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(string_values(table_names.column(0)), vec![Some("cpu")]);
+
+        // Removing the chunk removes its column from the cached schema.
+        db.remove_chunk("2").unwrap();
+        assert_eq!(
+            tag_keys(&db, TimeRange::all()),
+            Some(vec!["env".to_string()])
+        );
+        assert_eq!(column_names(&db), vec!["env", "temp", "time"]);
+
+        db.remove_chunk("1").unwrap();
+        assert!(db
+            .table_names("db", TimeRange::all(), &[])
+            .unwrap()
+            .is_none());
+        assert_eq!(db.table_schema("cpu", TimeRange::all()).unwrap(), None);
+        assert!(matches!(
+            db.tag_keys("cpu", TimeRange::all(), &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_tag_keys() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "env",
+                    ColumnType::Tag(Column::from(&[None, Some("prod"), None][..])),
+                ),
+            ],
+        ));
+
+        let tag_keys = |time_range, predicates: &[Predicate<'_>]| {
+            db.tag_keys("cpu", time_range, predicates)
+                .unwrap()
+                .map(|rb| {
+                    string_values(rb.column(0))
+                        .into_iter()
+                        .map(Option::unwrap)
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        assert_eq!(
+            tag_keys(TimeRange::all(), &[]),
+            Some(vec!["env".to_string(), "region".to_string()])
+        );
+
+        // Only tag keys with a value in a matching row are returned.
+        assert_eq!(
+            tag_keys(
+                TimeRange::all(),
+                &[("region", (Operator::Equal, Value::String("west")))]
+            ),
+            Some(vec!["region".to_string()])
+        );
+        assert_eq!(
+            tag_keys(time_range(3, 10), &[]),
+            Some(vec!["region".to_string()])
+        );
+        assert_eq!(tag_keys(time_range(10, 20), &[]), None);
+        assert!(matches!(
+            db.tag_keys("mem", TimeRange::all(), &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_table_schema() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+
+        // The second chunk has an identical schema.
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west"][..])),
+                ),
+                ("temp", ColumnType::Field(Column::from(&[4.0_f64, 5.0][..]))),
+            ],
+        ));
+
+        let expected = vec![
+            (
+                "region".to_string(),
+                crate::ColumnType::Tag(LogicalDataType::String),
+            ),
+            (
+                "temp".to_string(),
+                crate::ColumnType::Field(LogicalDataType::Float),
+            ),
+            (
+                "time".to_string(),
+                crate::ColumnType::Time(LogicalDataType::Integer),
+            ),
+        ];
+        assert_eq!(
+            db.table_schema("cpu", time_range(0, 10)).unwrap(),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            db.table_schema("cpu", time_range(4, 10)).unwrap(),
+            Some(expected)
+        );
+
+        // No chunks with the table overlap the time range.
+        assert_eq!(db.table_schema("cpu", time_range(100, 200)).unwrap(), None);
+        assert_eq!(db.table_schema("mem", time_range(0, 10)).unwrap(), None);
+
+        // The third chunk disagrees on the type of the "temp" column.
+        db.add_chunk(build_chunk(
+            3,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[100_i64, 101][..]))),
+                ("temp", ColumnType::Field(Column::from(&[10_u64, 20][..]))),
+            ],
+        ));
+
+        assert!(db.table_schema("cpu", time_range(0, 10)).unwrap().is_some());
+        assert!(matches!(
+            db.table_schema("cpu", time_range(0, 200)),
+            Err(Error::SchemaConflict { column_name, .. }) if column_name == "temp"
+        ));
+
+        let mut store = Store::new();
+        store.add_database("db1".to_string(), db);
+        assert!(store
+            .table_schema("db1", "cpu", time_range(100, 200))
+            .is_ok());
+        assert!(store
+            .table_schema("db1", "cpu", time_range(0, 200))
+            .is_err());
+        assert_eq!(
+            store
+                .table_schema("db2", "cpu", time_range(0, 200))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn database_tag_cardinality() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west", "north"][..])),
+                ),
+                (
+                    "host",
+                    ColumnType::Tag(Column::from(&["a", "b", "c", "d"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0][..])),
+                ),
+            ],
+        ));
+
+        let tag_keys = vec![
+            "region".to_string(),
+            "host".to_string(),
+            "temp".to_string(),
+            "unknown".to_string(),
+        ];
+        let cardinality = |time_range, estimate| {
+            db.tag_cardinality("cpu", time_range, &tag_keys, estimate)
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        // For a single chunk the upper bound is the number of distinct values.
+        // Only tag columns have a cardinality.
+        let expected = vec![
+            ("host".to_string(), 4),
+            ("region".to_string(), 3),
+            ("temp".to_string(), 0),
+            ("unknown".to_string(), 0),
+        ];
+        assert_eq!(
+            cardinality(time_range(0, 10), CardinalityEstimate::UpperBound),
+            expected
+        );
+        assert_eq!(
+            cardinality(time_range(0, 10), CardinalityEstimate::HyperLogLog),
+            expected
+        );
+
+        // Values repeated across chunks are counted once per chunk by the upper
+        // bound, but not by the HyperLogLog estimate.
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 6][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "south"][..])),
+                ),
+                ("host", ColumnType::Tag(Column::from(&["a", "e"][..]))),
+            ],
+        ));
+
+        assert_eq!(
+            cardinality(time_range(0, 10), CardinalityEstimate::UpperBound)[..2],
+            [("host".to_string(), 6), ("region".to_string(), 5)]
+        );
+        assert_eq!(
+            cardinality(time_range(0, 10), CardinalityEstimate::HyperLogLog)[..2],
+            [("host".to_string(), 5), ("region".to_string(), 4)]
+        );
+
+        // Only chunks overlapping the time range are considered.
+        assert_eq!(
+            cardinality(time_range(5, 10), CardinalityEstimate::UpperBound)[..2],
+            [("host".to_string(), 2), ("region".to_string(), 2)]
+        );
+        assert!(db
+            .tag_cardinality(
+                "cpu",
+                time_range(100, 200),
+                &tag_keys,
+                CardinalityEstimate::default()
+            )
+            .values()
+            .all(|&cardinality| cardinality == 0));
+    }
+
+    #[test]
+    fn database_tag_keys_with_counts() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            5,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west", "north", "west"][..])),
+                ),
+                (
+                    "env",
+                    ColumnType::Tag(Column::from(
+                        &[Some("prod"), None, Some("prod"), Some("stag"), None][..],
+                    )),
+                ),
+                (
+                    "host",
+                    ColumnType::Tag(Column::from(&["a", "b", "c", "d", "e"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0, 5.0][..])),
+                ),
+            ],
+        ));
+
+        let counts = |time_range, predicates: &[Predicate<'_>]| {
+            db.tag_keys_with_counts("cpu", time_range, predicates)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        // Without predicates the counts are the number of distinct non-null
+        // values of each tag column in the chunk.
+        let tag_keys = vec!["env".to_string(), "host".to_string(), "region".to_string()];
+        let expected = db
+            .tag_cardinality(
+                "cpu",
+                time_range(0, 10),
+                &tag_keys,
+                CardinalityEstimate::UpperBound,
+            )
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(counts(time_range(0, 10), &[]), expected);
+        assert_eq!(
+            counts(time_range(0, 10), &[]),
+            vec![
+                ("env".to_string(), 2),
+                ("host".to_string(), 5),
+                ("region".to_string(), 3),
+            ]
+        );
+
+        // Only values in rows matching the predicates are counted.
+        assert_eq!(
+            counts(
+                time_range(0, 10),
+                &[("region", (Operator::Equal, Value::String("west")))]
+            ),
+            vec![
+                ("env".to_string(), 1),
+                ("host".to_string(), 3),
+                ("region".to_string(), 1),
+            ]
+        );
+
+        // As are only values in rows within the time range.
+        assert_eq!(
+            counts(time_range(2, 4), &[]),
+            vec![
+                ("env".to_string(), 1),
+                ("host".to_string(), 2),
+                ("region".to_string(), 2),
+            ]
+        );
+
+        // Tag keys without any matching values are omitted.
+        assert!(counts(
+            time_range(0, 10),
+            &[("region", (Operator::Equal, Value::String("south")))]
+        )
+        .is_empty());
+
+        assert!(matches!(
+            db.tag_keys_with_counts("mem", time_range(0, 10), &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_tag_values_by_key() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "env",
+                    ColumnType::Tag(Column::from(&[Some("prod"), None, Some("stag")][..])),
+                ),
+                ("host", ColumnType::Tag(Column::from(&["a", "b", "c"][..]))),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["north", "west"][..])),
+                ),
+                ("env", ColumnType::Tag(Column::from(&["prod", "dev"][..]))),
+                ("host", ColumnType::Tag(Column::from(&["a", "d"][..]))),
+                ("temp", ColumnType::Field(Column::from(&[4.0_f64, 5.0][..]))),
+            ],
+        ));
+
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        // Each tag key's values are deduplicated across chunks.
+        let values = db
+            .tag_values_by_key(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                &["region".to_string(), "env".to_string()],
+            )
+            .unwrap();
+        assert_eq!(
+            values.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("env".to_string(), strings(&["dev", "prod", "stag"])),
+                ("region".to_string(), strings(&["east", "north", "west"])),
+            ]
+        );
+
+        // Values are only taken from rows matching the predicates.
+        let values = db
+            .tag_values_by_key(
+                "cpu",
+                time_range(0, 10),
+                &[("region", (Operator::Equal, Value::String("west")))],
+                &["host".to_string(), "env".to_string(), "temp".to_string()],
+            )
+            .unwrap();
+        assert_eq!(
+            values.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("env".to_string(), strings(&["dev", "prod", "stag"])),
+                ("host".to_string(), strings(&["a", "c", "d"])),
+            ]
+        );
+
+        // No tag keys returns the values of every tag key, which agree with
+        // the counts of distinct values.
+        let values = db
+            .tag_values_by_key("cpu", time_range(0, 10), &[], &[])
+            .unwrap();
+        assert_eq!(
+            values.keys().collect::<Vec<_>>(),
+            vec!["env", "host", "region"]
+        );
+        let counts = db
+            .tag_keys_with_counts("cpu", time_range(0, 10), &[])
+            .unwrap();
+        for (tag_key, count) in counts {
+            assert_eq!(values[&tag_key].len() as u64, count, "{}", tag_key);
+        }
+
+        assert!(matches!(
+            db.tag_values_by_key("mem", time_range(0, 10), &[], &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+
+        // The same values as a record batch of tag key and value pairs.
+        let rb = db
+            .tag_values(
+                "cpu",
+                time_range(0, 10),
+                &[("region", (Operator::Equal, Value::String("west")))],
+                &["host".to_string(), "env".to_string()],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["tag_key", "tag_value"]);
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![
+                Some("env"),
+                Some("env"),
+                Some("env"),
+                Some("host"),
+                Some("host"),
+                Some("host")
+            ]
+        );
+        assert_eq!(
+            string_values(rb.column(1)),
+            vec![
+                Some("dev"),
+                Some("prod"),
+                Some("stag"),
+                Some("a"),
+                Some("c"),
+                Some("d")
+            ]
+        );
+
+        // No tag keys returns the values of every tag key.
+        let rb = db
+            .tag_values("cpu", time_range(0, 10), &[], &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(rb.num_rows(), values.values().map(Vec::len).sum::<usize>());
+
+        // No rows match the predicates.
+        assert!(db
+            .tag_values(
+                "cpu",
+                time_range(0, 10),
+                &[("region", (Operator::Equal, Value::String("south")))],
+                &[],
+            )
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            db.tag_values("mem", time_range(0, 10), &[], &[]),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_aggregate() {
+        let mut db = Database::new();
+
+        // Both chunks contain the "west" and "east" group keys, e.g., because
+        // data has been back-filled.
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[10_u64, 20, 30][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[4_i64, 5, 6][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "west", "north"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[5_u64, 1, 100][..])),
+                ),
+            ],
+        ));
+
+        let aggregates = vec![
+            ("counter", AggregateType::Sum, None),
+            ("counter", AggregateType::Count, None),
+            ("counter", AggregateType::Min, None),
+            ("counter", AggregateType::Max, None),
+        ];
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            column_names(&rb),
+            vec![
+                "region",
+                "counter_sum",
+                "counter_count",
+                "counter_min",
+                "counter_max"
+            ]
+        );
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("north"), Some("west")]
+        );
+        assert_eq!(
+            u64_values(rb.column(1)),
+            vec![Some(25), Some(100), Some(41)]
+        );
+        assert_eq!(u64_values(rb.column(2)), vec![Some(2), Some(1), Some(3)]);
+        assert_eq!(u64_values(rb.column(3)), vec![Some(5), Some(100), Some(1)]);
+        assert_eq!(
+            u64_values(rb.column(4)),
+            vec![Some(20), Some(100), Some(30)]
+        );
+
+        // Only the first chunk overlaps the time range.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 4),
+                &[],
+                vec!["region".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west")]
+        );
+        assert_eq!(u64_values(rb.column(1)), vec![Some(20), Some(40)]);
+        assert_eq!(u64_values(rb.column(2)), vec![Some(1), Some(2)]);
+
+        // No chunks overlap the time range.
+        assert!(db
+            .aggregate(
+                "cpu",
+                time_range(100, 200),
+                &[],
+                vec!["region".to_string()],
+                aggregates.clone()
+            )
+            .unwrap()
+            .is_none());
+
+        // No chunks contain the table.
+        assert!(matches!(
+            db.aggregate(
+                "mem",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                aggregates
+            ),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_parallel_execution() {
+        let mut db = Database::new();
+        for id in 1..=8 {
+            let times = (0..4).map(|i| i64::from(id) * 10 + i).collect::<Vec<_>>();
+            let temps = (0..4).map(|i| f64::from(id) + i as f64).collect::<Vec<_>>();
+            db.add_chunk(build_chunk(
+                id,
+                4,
+                vec![
+                    ("time", ColumnType::Time(Column::from(times.as_slice()))),
+                    (
+                        "region",
+                        ColumnType::Tag(Column::from(&["west", "east", "west", "north"][..])),
+                    ),
+                    ("temp", ColumnType::Field(Column::from(temps.as_slice()))),
+                ],
+            ));
+        }
+
+        let run = |db: &Database| {
+            let rb = db
+                .select(
+                    "cpu",
+                    time_range(0, 1000),
+                    &[],
+                    vec!["time".to_string(), "temp".to_string()],
+                    None,
+                    0,
+                    false,
+                )
+                .unwrap()
+                .unwrap();
+            let selected = (i64_values(rb.column(0)), f64_values(rb.column(1)));
+
+            let rb = db
+                .select_distinct("cpu", time_range(0, 1000), &[], vec!["region".to_string()])
+                .unwrap()
+                .unwrap();
+            let distinct = string_values(rb.column(0))
+                .into_iter()
+                .map(|v| v.map(str::to_owned))
+                .collect::<Vec<_>>();
+
+            let aggregates = vec![
+                ("temp", AggregateType::Sum, None),
+                ("time", AggregateType::Count, None),
+            ];
+            let rb = db
+                .aggregate(
+                    "cpu",
+                    time_range(0, 1000),
+                    &[],
+                    vec!["region".to_string()],
+                    aggregates,
+                )
+                .unwrap()
+                .unwrap();
+            let aggregated = (f64_values(rb.column(1)), u64_values(rb.column(2)));
+
+            (selected, distinct, aggregated)
+        };
+
+        let parallel = run(&db);
+        db.set_parallel(false);
+        let serial = run(&db);
+        assert_eq!(parallel, serial);
+
+        // Results are in order of chunk id regardless of which chunks
+        // complete first.
+        let ((times, _), distinct, (_, counts)) = parallel;
+        assert_eq!(times.len(), 32);
+        assert_eq!(
+            times[..5],
+            [Some(10), Some(11), Some(12), Some(13), Some(20)]
+        );
+        assert_eq!(
+            distinct,
+            vec![
+                Some("east".to_string()),
+                Some("north".to_string()),
+                Some("west".to_string())
+            ]
+        );
+        assert_eq!(counts, vec![Some(8), Some(8), Some(16)]);
+    }
+
+    #[derive(Default)]
+    struct MemorySpillStore {
+        data: std::cell::RefCell<BTreeMap<String, Vec<u8>>>,
+        puts: std::cell::Cell<usize>,
+        fail_gets: std::cell::Cell<bool>,
+    }
+
+    impl spill::SpillStore for MemorySpillStore {
+        fn put(&self, location: &str, data: Vec<u8>) -> Result<(), spill::StoreError> {
+            self.puts.set(self.puts.get() + 1);
+            self.data.borrow_mut().insert(location.to_string(), data);
+            Ok(())
+        }
+
+        fn get(&self, location: &str) -> Result<Vec<u8>, spill::StoreError> {
+            if self.fail_gets.get() {
+                return Err(format!("unable to read {}", location).into());
+            }
+            self.data
+                .borrow()
+                .get(location)
+                .cloned()
+                .ok_or_else(|| format!("{} not found", location).into())
+        }
+
+        fn delete(&self, location: &str) -> Result<(), spill::StoreError> {
+            self.data.borrow_mut().remove(location);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn database_aggregate_group_by_integer() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west", "west"][..])),
+                ),
+                (
+                    "status_code",
+                    ColumnType::Field(Column::from(&[200_i64, 500, 200, 404][..])),
+                ),
+                (
+                    "latency",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 6][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "east"][..])),
+                ),
+                (
+                    "status_code",
+                    ColumnType::Field(Column::from(&[500_i64, 200][..])),
+                ),
+                (
+                    "latency",
+                    ColumnType::Field(Column::from(&[5.0_f64, 6.0][..])),
+                ),
+            ],
+        ));
+
+        let aggregates = vec![("latency", AggregateType::Count, None)];
+
+        // One row per distinct status code.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["status_code".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["status_code", "latency_count"]);
+        assert_eq!(
+            i64_values(rb.column(0)),
+            vec![Some(200), Some(404), Some(500)]
+        );
+        assert_eq!(u64_values(rb.column(1)), vec![Some(3), Some(1), Some(2)]);
+
+        // Integer columns can be grouped on alongside string columns.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[("time", (Operator::LT, Value::from(6_i64)))],
+                vec!["region".to_string(), "status_code".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west"), Some("west")]
+        );
+        assert_eq!(
+            i64_values(rb.column(1)),
+            vec![Some(500), Some(200), Some(404)]
+        );
+        assert_eq!(u64_values(rb.column(2)), vec![Some(2), Some(2), Some(1)]);
+
+        // Float columns can't be grouped on.
+        assert!(matches!(
+            db.aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["latency".to_string()],
+                aggregates,
+            ),
+            Err(Error::UnsupportedGroupColumn { .. })
+        ));
+    }
+
+    #[test]
+    fn database_aggregate_group_by_bool() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west", "west"][..])),
+                ),
+                (
+                    "active",
+                    ColumnType::Field(Column::from(
+                        &[Some(true), Some(false), None, Some(true)][..],
+                    )),
+                ),
+                (
+                    "latency",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 6][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["east", "east"][..])),
+                ),
+                (
+                    "active",
+                    ColumnType::Field(Column::from(&[false, true][..])),
+                ),
+                (
+                    "latency",
+                    ColumnType::Field(Column::from(&[5.0_f64, 6.0][..])),
+                ),
+            ],
+        ));
+
+        let aggregates = vec![("latency", AggregateType::Sum, None)];
+
+        // One row per distinct boolean value, with NULL values forming their
+        // own group.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["active".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["active", "latency_sum"]);
+        assert_eq!(
+            bool_values(rb.column(0)),
+            vec![None, Some(false), Some(true)]
+        );
+        assert_eq!(
+            f64_values(rb.column(1)),
+            vec![Some(3.0), Some(7.0), Some(11.0)]
+        );
+
+        // Boolean columns can be grouped on alongside string columns.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[("time", (Operator::GT, Value::from(1_i64)))],
+                vec!["region".to_string(), "active".to_string()],
+                aggregates,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("east"), Some("west"), Some("west")]
+        );
+        assert_eq!(
+            bool_values(rb.column(1)),
+            vec![Some(false), Some(true), None, Some(true)]
+        );
+        assert_eq!(
+            f64_values(rb.column(2)),
+            vec![Some(7.0), Some(6.0), Some(3.0), Some(4.0)]
+        );
+    }
+
+    #[test]
+    fn database_aggregate_count_booleans() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            5,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "west", "east", "east"][..])),
+                ),
+                (
+                    "active",
+                    ColumnType::Field(Column::from(
+                        &[Some(true), None, Some(true), Some(false), None][..],
+                    )),
+                ),
+                (
+                    "latency",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0, 5.0][..])),
+                ),
+            ],
+        ));
+
+        let aggregates = vec![
+            ("active", AggregateType::CountTrue, None),
+            ("active", AggregateType::CountFalse, None),
+            ("active", AggregateType::Count, None),
+        ];
+
+        // NULL values are neither true nor false, so the counts of true and
+        // false values sum to the count of non-null values.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec![
+                "region",
+                "active_count_true",
+                "active_count_false",
+                "active_count"
+            ]
+        );
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west")]
+        );
+        assert_eq!(u64_values(rb.column(1)), vec![Some(0), Some(2)]);
+        assert_eq!(u64_values(rb.column(2)), vec![Some(1), Some(0)]);
+        assert_eq!(u64_values(rb.column(3)), vec![Some(1), Some(2)]);
+
+        // Without any group columns.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[("time", (Operator::GT, Value::from(1_i64)))],
+                vec![],
+                aggregates,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(u64_values(rb.column(0)), vec![Some(1)]);
+        assert_eq!(u64_values(rb.column(1)), vec![Some(1)]);
+        assert_eq!(u64_values(rb.column(2)), vec![Some(2)]);
+
+        // Only boolean columns can have their true and false values counted.
+        assert!(matches!(
+            db.aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec![],
+                vec![("latency", AggregateType::CountTrue, None)],
+            ),
+            Err(Error::UnsupportedAggregate { .. })
+        ));
+    }
+
+    #[test]
+    fn database_aggregate_first_last() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 1, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "east"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[50_i64, 10, 30][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[8_i64, 0, 2, 3][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "east", "east"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[80_i64, 5, 20, 33][..])),
+                ),
+            ],
+        ));
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("temp", AggregateType::First, None),
+                    ("temp", AggregateType::Last, None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["region", "temp_first", "temp_last"]);
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west")]
+        );
+
+        // The earliest east point is in the second chunk and the latest in the
+        // first, where it's tied with a point in the second chunk. Ties keep
+        // the first value encountered. The earliest west point is in the first
+        // chunk and the latest in the second.
+        assert_eq!(i64_values(rb.column(1)), vec![Some(5), Some(10)]);
+        assert_eq!(i64_values(rb.column(2)), vec![Some(30), Some(80)]);
+
+        // Selectors also apply within windows.
+        let rb = db
+            .aggregate_window(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("temp", AggregateType::First),
+                    ("temp", AggregateType::Last),
+                ],
+                5,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec!["region", "time", "temp_first", "temp_last"]
+        );
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west"), Some("west")]
+        );
+        assert_eq!(i64_values(rb.column(1)), vec![Some(0), Some(0), Some(5)]);
+        assert_eq!(i64_values(rb.column(2)), vec![Some(5), Some(10), Some(50)]);
+        assert_eq!(i64_values(rb.column(3)), vec![Some(30), Some(10), Some(80)]);
+    }
+
+    #[test]
+    fn database_aggregate_arg_max_min() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 1, 3][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "east"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[50_i64, 10, 30][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[8_i64, 0, 2, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "east", "east"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[80_i64, 5, 30, 5][..])),
+                ),
+            ],
+        ));
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("temp", AggregateType::ArgMax, None),
+                    ("temp", AggregateType::ArgMin, None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec!["region", "temp_argmax", "temp_argmin"]
+        );
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west")]
+        );
+
+        // The maximum west value is in the second chunk. The maximum east
+        // value appears in both chunks and the minimum twice in the second,
+        // and ties resolve to the earliest time.
+        assert_eq!(i64_values(rb.column(1)), vec![Some(2), Some(8)]);
+        assert_eq!(i64_values(rb.column(2)), vec![Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn database_aggregate_mean() {
+        let mut db = Database::new();
+        for (id, temps) in vec![
+            (1, vec![Some(i64::MAX), None, Some(i64::MAX - 1)]),
+            (2, vec![Some(i64::MAX - 3), None, Some(i64::MAX)]),
+        ] {
+            db.add_chunk(build_chunk(
+                id,
+                3,
+                vec![
+                    ("time", ColumnType::Time(Column::from(&[1_i64, 2, 3][..]))),
+                    (
+                        "region",
+                        ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                    ),
+                    (
+                        "temp",
+                        ColumnType::Field(Column::from(Int64Array::from(temps))),
+                    ),
+                ],
+            ));
+        }
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("temp", AggregateType::Mean, None),
+                    ("temp", AggregateType::Count, None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["region", "temp_mean", "temp_count"]);
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west")]
+        );
+
+        // Every east value is NULL, so it has no mean. The sum of the west
+        // values overflows an i64, but their mean doesn't.
+        let means = f64_values(rb.column(1));
+        assert_eq!(means[0], None);
+        let expected =
+            (i64::MAX as f64 * 2.0 + (i64::MAX - 1) as f64 + (i64::MAX - 3) as f64) / 4.0;
+        assert!((means[1].unwrap() - expected).abs() / expected < 1e-12);
+        assert_eq!(u64_values(rb.column(2)), vec![Some(0), Some(4)]);
+    }
+
+    #[test]
+    fn database_aggregate_missing_columns() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west"][..])),
+                ),
+                ("host", ColumnType::Tag(Column::from(&["a", "b"][..]))),
+                ("temp", ColumnType::Field(Column::from(&[1.0_f64, 2.0][..]))),
+            ],
+        ));
+        // The second chunk has neither the "host" nor the "temp" column.
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[3_i64, 4][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east"][..])),
+                ),
+                (
+                    "load",
+                    ColumnType::Field(Column::from(&[10.0_f64, 20.0][..])),
+                ),
+            ],
+        ));
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string(), "host".to_string()],
+                vec![
+                    ("temp", AggregateType::Count, None),
+                    ("temp", AggregateType::Sum, None),
+                    ("load", AggregateType::Count, None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec!["region", "host", "temp_count", "temp_sum", "load_count"]
+        );
+
+        // The rows of the second chunk are aggregated with a NULL host, and
+        // none of their temps.
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west"), Some("west"), Some("west")]
+        );
+        assert_eq!(
+            string_values(rb.column(1)),
+            vec![None, None, Some("a"), Some("b")]
+        );
+        assert_eq!(
+            u64_values(rb.column(2)),
+            vec![Some(0), Some(0), Some(1), Some(1)]
+        );
+        assert_eq!(
+            f64_values(rb.column(3)),
+            vec![None, None, Some(1.0), Some(2.0)]
+        );
+        assert_eq!(
+            u64_values(rb.column(4)),
+            vec![Some(1), Some(1), Some(0), Some(0)]
+        );
+    }
+
+    #[test]
+    fn database_aggregate_stddev_variance() {
+        let mut db = Database::new();
+        for (id, temps) in vec![
+            (1, vec![2.0_f64, 4.0, 4.0]),
+            (2, vec![4.0, 5.0, 5.0, 7.0, 9.0]),
+        ] {
+            let times = (0..temps.len() as i64).collect::<Vec<_>>();
+            let regions = vec!["west"; temps.len()];
+            db.add_chunk(build_chunk(
+                id,
+                temps.len() as u32,
+                vec![
+                    ("time", ColumnType::Time(Column::from(times.as_slice()))),
+                    ("region", ColumnType::Tag(Column::from(regions.as_slice()))),
+                    ("temp", ColumnType::Field(Column::from(temps.as_slice()))),
+                ],
+            ));
+        }
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("temp", AggregateType::Variance, None),
+                    ("temp", AggregateType::StdDev, None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec!["region", "temp_variance", "temp_stddev"]
+        );
+
+        // the partial results from each chunk are combined.
+        let variance = f64_values(rb.column(1))[0].unwrap();
+        assert!((variance - 32.0 / 7.0).abs() < 1e-12, "got {}", variance);
+        let stddev = f64_values(rb.column(2))[0].unwrap();
+        assert!(
+            (stddev - (32.0_f64 / 7.0).sqrt()).abs() < 1e-12,
+            "got {}",
+            stddev
+        );
+
+        // a single value has no variance.
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(4, 5),
+                &[],
+                vec!["region".to_string()],
+                vec![("temp", AggregateType::Variance, None)],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(f64_values(rb.column(1)), vec![None]);
+    }
+
+    #[test]
+    fn database_aggregate_percentiles() {
+        let mut db = Database::new();
+        for (id, rows) in vec![(1, 1..=500_i64), (2, 501..=1000)] {
+            let values = rows.collect::<Vec<_>>();
+            let regions = vec!["west"; values.len()];
+            db.add_chunk(build_chunk(
+                id,
+                values.len() as u32,
+                vec![
+                    ("time", ColumnType::Time(Column::from(values.as_slice()))),
+                    ("region", ColumnType::Tag(Column::from(regions.as_slice()))),
+                    ("value", ColumnType::Field(Column::from(values.as_slice()))),
+                ],
+            ));
+        }
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 1001),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("value", AggregateType::Percentile(50.0), None),
+                    ("value", AggregateType::Percentile(99.0), None),
+                    ("value", AggregateType::ApproxQuantile(50.0), None),
+                    ("value", AggregateType::ApproxQuantile(99.0), None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec![
+                "region",
+                "value_percentile_50",
+                "value_percentile_99",
+                "value_approx_quantile_50",
+                "value_approx_quantile_99",
+            ]
+        );
+
+        // exact percentiles are taken across all chunks.
+        assert_eq!(i64_values(rb.column(1)), vec![Some(500)]);
+        assert_eq!(i64_values(rb.column(2)), vec![Some(990)]);
+
+        let p50 = f64_values(rb.column(3))[0].unwrap();
+        assert!((p50 - 500.0).abs() < 10.0, "got {}", p50);
+        let p99 = f64_values(rb.column(4))[0].unwrap();
+        assert!((p99 - 990.0).abs() < 10.0, "got {}", p99);
+
+        // only percentiles between 0 and 100 are valid.
+        for agg_type in vec![
+            AggregateType::Percentile(100.1),
+            AggregateType::Percentile(-1.0),
+            AggregateType::ApproxQuantile(f64::NAN),
+        ] {
+            assert!(matches!(
+                db.aggregate(
+                    "cpu",
+                    time_range(0, 1001),
+                    &[],
+                    vec!["region".to_string()],
+                    vec![("value", agg_type, None)],
+                ),
+                Err(Error::InvalidAggregate { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn database_aggregate_histogram() {
+        let mut db = Database::new();
+        for (id, latencies) in vec![
+            (1, vec![5_i64, 10, 50, 200]),
+            (2, vec![1, 100, 101, 500, 20, 30]),
+        ] {
+            let times = (0..latencies.len() as i64).collect::<Vec<_>>();
+            let regions = vec!["west"; latencies.len()];
+            db.add_chunk(build_chunk(
+                id,
+                latencies.len() as u32,
+                vec![
+                    ("time", ColumnType::Time(Column::from(times.as_slice()))),
+                    ("region", ColumnType::Tag(Column::from(regions.as_slice()))),
+                    (
+                        "latency",
+                        ColumnType::Field(Column::from(latencies.as_slice())),
+                    ),
+                ],
+            ));
+        }
+
+        let rb = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![("latency", AggregateType::Histogram(vec![10.0, 100.0]), None)],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            column_names(&rb),
+            vec![
+                "region",
+                "latency_histogram_le_10",
+                "latency_histogram_le_100",
+                "latency_histogram_le_inf",
+            ]
+        );
+
+        // the bucket counts from each chunk are combined.
+        assert_eq!(u64_values(rb.column(1)), vec![Some(3)]);
+        assert_eq!(u64_values(rb.column(2)), vec![Some(4)]);
+        assert_eq!(u64_values(rb.column(3)), vec![Some(3)]);
+
+        // bucket bounds must be ascending.
+        for bounds in vec![vec![100.0, 10.0], vec![10.0, 10.0], vec![f64::NAN]] {
+            assert!(matches!(
+                db.aggregate(
+                    "cpu",
+                    time_range(0, 10),
+                    &[],
+                    vec!["region".to_string()],
+                    vec![("latency", AggregateType::Histogram(bounds), None)],
+                ),
+                Err(Error::InvalidAggregate { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn database_aggregate_with_spilling() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "north", "south"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[10_u64, 20, 30, 40][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 6, 7][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["south", "east", "west"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[1_u64, 2, 3][..])),
+                ),
+            ],
+        ));
+
+        let aggregates = vec![
+            ("counter", AggregateType::Sum, None),
+            ("counter", AggregateType::Count, None),
+            ("counter", AggregateType::Max, None),
+        ];
+
+        let store = MemorySpillStore::default();
+        let spill = SpillConfig {
+            max_groups: 2,
+            store: &store,
+            prefix: "query-1".to_string(),
+        };
+        let rb = db
+            .aggregate_with_spilling(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                aggregates.clone(),
+                &spill,
+            )
+            .unwrap()
+            .unwrap();
+
+        // Both chunks exceed the group budget, and the remaining groups are
+        // spilled before merging.
+        assert!(store.puts.get() >= 2);
+        assert!(store.data.borrow().is_empty());
+
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("north"), Some("south"), Some("west")]
+        );
+        assert_eq!(
+            u64_values(rb.column(1)),
+            vec![Some(22), Some(30), Some(41), Some(13)]
+        );
+        assert_eq!(
+            u64_values(rb.column(2)),
+            vec![Some(2), Some(1), Some(2), Some(2)]
+        );
+        assert_eq!(
+            u64_values(rb.column(3)),
+            vec![Some(20), Some(30), Some(40), Some(10)]
+        );
+
+        // The result is the same as without spilling.
+        let expected = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                aggregates.clone(),
+            )
+            .unwrap()
+            .unwrap();
+        for i in 0..expected.num_columns() {
+            assert_eq!(
+                format!("{:?}", rb.column(i)),
+                format!("{:?}", expected.column(i))
+            );
+        }
+
+        // The runs are removed when they can't be merged.
+        store.fail_gets.set(true);
+        let err = db
+            .aggregate_with_spilling(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                aggregates,
+                &spill,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::Spill { .. }));
+        assert!(store.data.borrow().is_empty());
+    }
+
+    #[test]
+    fn database_aggregate_sliding_window() {
+        const SECOND: i64 = 1_000_000_000;
+        let mut db = Database::new();
+
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[10 * SECOND, 40 * SECOND, 70 * SECOND][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "east"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[1_u64, 2, 3][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            2,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[50 * SECOND, 80 * SECOND][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[10_u64, 20][..])),
+                ),
+            ],
+        ));
+
+        // one minute windows emitted every 30 seconds.
+        let rb = db
+            .aggregate_sliding_window(
+                "cpu",
+                time_range(0, 120 * SECOND),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("counter", AggregateType::Sum),
+                    ("counter", AggregateType::Count),
+                ],
+                60 * SECOND,
+                30 * SECOND,
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            column_names(&rb),
+            vec!["region", "time", "counter_sum", "counter_count"]
+        );
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![
+                Some("east"),
+                Some("east"),
+                Some("west"),
+                Some("west"),
+                Some("west")
+            ]
+        );
+        assert_eq!(
+            i64_values(rb.column(1)),
+            vec![
+                Some(30 * SECOND),
+                Some(60 * SECOND),
+                Some(-30 * SECOND),
+                Some(0),
+                Some(30 * SECOND)
+            ]
+        );
+        // The overlapping windows contain rows from both chunks.
+        assert_eq!(
+            u64_values(rb.column(2)),
+            vec![Some(23), Some(23), Some(1), Some(13), Some(12)]
+        );
+        assert_eq!(
+            u64_values(rb.column(3)),
+            vec![Some(2), Some(2), Some(1), Some(3), Some(2)]
+        );
+
+        let sliding_window = |window, step| {
+            db.aggregate_sliding_window(
+                "cpu",
+                time_range(0, 120 * SECOND),
+                &[],
+                vec!["region".to_string()],
+                vec![("counter", AggregateType::Sum)],
+                window,
+                step,
+            )
+        };
+
+        // The window and step must be positive.
+        assert!(matches!(
+            sliding_window(0, 30 * SECOND),
+            Err(Error::InvalidWindow { window: 0, .. })
+        ));
+        assert!(matches!(
+            sliding_window(-60 * SECOND, 30 * SECOND),
+            Err(Error::InvalidWindow { .. })
+        ));
+        assert!(matches!(
+            sliding_window(60 * SECOND, 0),
+            Err(Error::InvalidWindow { step: 0, .. })
+        ));
+        assert!(matches!(
+            sliding_window(60 * SECOND, -1),
+            Err(Error::InvalidWindow { step: -1, .. })
+        ));
+
+        // Each row may fall in at most `MAX_WINDOWS_PER_ROW` windows.
+        assert!(sliding_window(MAX_WINDOWS_PER_ROW, 1).is_ok());
+        assert!(matches!(
+            sliding_window(MAX_WINDOWS_PER_ROW + 1, 1),
+            Err(Error::InvalidWindow { .. })
+        ));
+        assert!(matches!(
+            sliding_window(i64::MAX, 1),
+            Err(Error::InvalidWindow { .. })
+        ));
+
+        // A window wider than any representable time doesn't overflow.
+        let rb = sliding_window(i64::MAX, i64::MAX).unwrap().unwrap();
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west")]
+        );
+        assert_eq!(i64_values(rb.column(1)), vec![Some(0), Some(0)]);
+    }
+
+    #[test]
+    fn window_starts() {
+        let starts =
+            |time, window, step| super::window_starts(time, window, step).collect::<Vec<_>>();
+
+        assert_eq!(starts(10, 60, 30), vec![0, -30]);
+        assert_eq!(starts(30, 60, 30), vec![30, 0]);
+        assert_eq!(starts(59, 60, 60), vec![0]);
+        assert_eq!(starts(60, 60, 60), vec![60]);
+        assert_eq!(starts(-1, 60, 60), vec![-60]);
+        assert_eq!(starts(10, 60, 20), vec![0, -20, -40]);
+
+        // Window starts that can't be represented are omitted.
+        assert_eq!(starts(i64::MIN, 64, 64), vec![i64::MIN]);
+        assert_eq!(starts(i64::MIN, 60, 7), Vec::<i64>::new());
+        assert_eq!(starts(1 << 62, i64::MAX, (1 << 62) + 1), vec![0]);
+    }
+
+    #[test]
+    fn value_delta() {
+        let i64_delta =
+            |prev: i64, curr: i64| super::value_delta(Value::from(prev), Value::from(curr));
+        let u64_delta =
+            |prev: u64, curr: u64| super::value_delta(Value::from(prev), Value::from(curr));
+
+        assert_eq!(i64_delta(10, 4), Value::from(-6_i64));
+        assert_eq!(i64_delta(i64::MIN, -1), Value::from(i64::MAX));
+        assert_eq!(i64_delta(-1, i64::MIN), Value::from(i64::MIN + 1));
+        assert_eq!(i64_delta(0, i64::MIN), Value::from(i64::MIN));
+        assert_eq!(i64_delta(i64::MIN, i64::MAX), Value::Null);
+        assert_eq!(i64_delta(i64::MAX, i64::MIN), Value::Null);
+        assert_eq!(i64_delta(-1, i64::MAX), Value::Null);
+
+        assert_eq!(u64_delta(10, 4), Value::from(-6_i64));
+        assert_eq!(u64_delta(0, i64::MAX as u64), Value::from(i64::MAX));
+        assert_eq!(u64_delta(i64::MAX as u64 + 1, 0), Value::from(i64::MIN));
+        assert_eq!(u64_delta(u64::MAX, 1 << 63), Value::from(-(i64::MAX)));
+        assert_eq!(u64_delta(0, u64::MAX), Value::Null);
+        assert_eq!(u64_delta(u64::MAX, 0), Value::Null);
+        assert_eq!(u64_delta(0, 1 << 63), Value::Null);
+
+        assert_eq!(
+            super::value_delta(Value::Null, Value::from(1_i64)),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn database_aggregate_window() {
+        const SECOND: i64 = 1_000_000_000;
+        let mut db = Database::new();
+
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(
+                        &[5 * SECOND, 59 * SECOND, 60 * SECOND, 130 * SECOND][..],
+                    )),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "west", "east"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0, 4.0][..])),
+                ),
+            ],
+        ));
+        // Back-filled data falling in the same windows as the first chunk.
+        db.add_chunk(build_chunk(
+            2,
+            3,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[30 * SECOND, 90 * SECOND, 150 * SECOND][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "west", "east"][..])),
+                ),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[10.0_f64, 20.0, 30.0][..])),
+                ),
+            ],
+        ));
+
+        // The one minute window doesn't evenly divide the time range.
+        let rb = db
+            .aggregate_window(
+                "cpu",
+                time_range(0, 170 * SECOND),
+                &[],
+                vec!["region".to_string()],
+                vec![
+                    ("temp", AggregateType::Sum),
+                    ("temp", AggregateType::Count),
+                    ("temp", AggregateType::Max),
+                ],
+                60 * SECOND,
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            column_names(&rb),
+            vec!["region", "time", "temp_sum", "temp_count", "temp_max"]
+        );
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("east"), Some("west"), Some("west")]
+        );
+        // The row at 60s is on a boundary so it starts the second window.
+        assert_eq!(
+            i64_values(rb.column(1)),
+            vec![Some(120 * SECOND), Some(0), Some(60 * SECOND)]
+        );
+        assert_eq!(
+            f64_values(rb.column(2)),
+            vec![Some(34.0), Some(13.0), Some(23.0)]
+        );
+        assert_eq!(u64_values(rb.column(3)), vec![Some(2), Some(3), Some(2)]);
+        assert_eq!(
+            f64_values(rb.column(4)),
+            vec![Some(30.0), Some(10.0), Some(20.0)]
+        );
+
+        // No chunks overlap the time range.
+        assert!(db
+            .aggregate_window(
+                "cpu",
+                time_range(200 * SECOND, 300 * SECOND),
+                &[],
+                vec!["region".to_string()],
+                vec![("temp", AggregateType::Sum)],
+                60 * SECOND,
+            )
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn database_select_deduplicated() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 1, 2][..]))),
+                ("host", ColumnType::Tag(Column::from(&["a", "b", "a"][..]))),
+                (
+                    "temp",
+                    ColumnType::Field(Column::from(&[1.0_f64, 2.0, 3.0][..])),
+                ),
+            ],
+        ));
+        // overwrites the value for host "a" at time 1.
+        db.add_chunk(build_chunk(
+            2,
+            1,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64][..]))),
+                ("host", ColumnType::Tag(Column::from(&["a"][..]))),
+                ("temp", ColumnType::Field(Column::from(&[10.0_f64][..]))),
+            ],
+        ));
+
+        let columns = vec!["host".to_string(), "temp".to_string()];
+
+        // Without deduplication both rows are returned.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                columns.clone(),
+                None,
+                0,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(rb.num_rows(), 4);
+
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                columns.clone(),
+                None,
+                0,
+                true,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["host", "temp"]);
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("a"), Some("a"), Some("b")]
+        );
+        assert_eq!(
+            f64_values(rb.column(1)),
+            vec![Some(10.0), Some(3.0), Some(2.0)]
+        );
+
+        // Only rows satisfying the predicates are deduplicated.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[("temp", (Operator::LT, Value::Scalar(Scalar::F64(5.0))))],
+                columns,
+                None,
+                0,
+                true,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            f64_values(rb.column(1)),
+            vec![Some(1.0), Some(3.0), Some(2.0)]
+        );
+
+        // A backfilled chunk overwrites the value for host "b" at time 1. It
+        // was added most recently, so its rows win despite its lower id.
+        db.add_chunk(build_chunk(
+            0,
+            1,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64][..]))),
+                ("host", ColumnType::Tag(Column::from(&["b"][..]))),
+                ("temp", ColumnType::Field(Column::from(&[20.0_f64][..]))),
+            ],
+        ));
+
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["host".to_string(), "time".to_string(), "temp".to_string()],
+                None,
+                0,
+                true,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![Some("a"), Some("a"), Some("b")]
+        );
+        assert_eq!(i64_values(rb.column(1)), vec![Some(1), Some(2), Some(1)]);
+        assert_eq!(
+            f64_values(rb.column(2)),
+            vec![Some(10.0), Some(3.0), Some(20.0)]
+        );
+
+        // The offset and limit apply to the deduplicated rows.
+        let rb = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["temp".to_string()],
+                Some(1),
+                1,
+                true,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(f64_values(rb.column(0)), vec![Some(3.0)]);
+
+        // Time predicates are folded into the time range before
+        // deduplicating.
+        let rb = db
+            .select(
+                "cpu",
+                TimeRange::all(),
+                &[("time", (Operator::LT, Value::from(2_i64)))],
+                vec!["temp".to_string()],
+                None,
+                0,
+                true,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(f64_values(rb.column(0)), vec![Some(10.0), Some(20.0)]);
+    }
+
+    #[test]
+    fn database_select_delta() {
+        let mut db = Database::new();
+
+        // The monotonically increasing series for each region continues from
+        // the first chunk into the second.
+        db.add_chunk(build_chunk(
+            1,
+            4,
+            vec![
+                (
+                    "time",
+                    ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..])),
+                ),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west", "east"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[10_i64, 100, 20, 110][..])),
+                ),
+            ],
+        ));
+        db.add_chunk(build_chunk(
+            2,
+            3,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[5_i64, 6, 7][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east", "west"][..])),
+                ),
+                (
+                    "counter",
+                    ColumnType::Field(Column::from(&[30_i64, 120, 40][..])),
+                ),
+            ],
+        ));
+
+        let rb = db
+            .select_delta(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                "counter",
+                vec!["region".to_string()],
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(column_names(&rb), vec!["region", "time", "counter_delta"]);
+        assert_eq!(
+            string_values(rb.column(0)),
+            vec![
+                Some("east"),
+                Some("east"),
+                Some("east"),
+                Some("west"),
+                Some("west"),
+                Some("west"),
+                Some("west")
+            ]
+        );
+        assert_eq!(
+            i64_values(rb.column(1)),
+            vec![
+                Some(2),
+                Some(4),
+                Some(6),
+                Some(1),
+                Some(3),
+                Some(5),
+                Some(7)
+            ]
+        );
+        assert_eq!(
+            i64_values(rb.column(2)),
+            vec![None, Some(10), Some(10), None, Some(10), Some(10), Some(10)]
+        );
+
+        // Without group columns the whole table is a single series.
+        let rb = db
+            .select_delta("cpu", time_range(4, 10), &[], "counter", vec![])
+            .unwrap()
+            .unwrap();
+        assert_eq!(column_names(&rb), vec!["time", "counter_delta"]);
+        assert_eq!(
+            i64_values(rb.column(0)),
+            vec![Some(4), Some(5), Some(6), Some(7)]
+        );
+        assert_eq!(
+            i64_values(rb.column(1)),
+            vec![None, Some(-80), Some(90), Some(-80)]
+        );
+
+        let err = db
+            .select_delta("cpu", time_range(0, 10), &[], "region", vec![])
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedDelta { .. }));
+    }
+
+    #[test]
+    fn database_errors() {
+        let mut db = Database::new();
+        db.add_chunk(build_chunk(
+            1,
+            2,
+            vec![
+                ("time", ColumnType::Time(Column::from(&[1_i64, 2][..]))),
+                (
+                    "region",
+                    ColumnType::Tag(Column::from(&["west", "east"][..])),
+                ),
+                ("temp", ColumnType::Field(Column::from(&[1.0_f64, 2.0][..]))),
+            ],
+        ));
+
+        let err = db
+            .select(
+                "mem",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "table mem not found");
+
+        let err = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["host".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "column host not found in table cpu");
+
+        let err = db
+            .select(
+                "cpu",
+                time_range(0, 10),
+                &[("temp", (Operator::Equal, Value::String("hot")))],
+                vec!["region".to_string()],
+                None,
+                0,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported predicate on Float column temp: hot"
+        );
+
+        let err = db
+            .aggregate(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                vec![("region", AggregateType::Sum, None)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported aggregate sum on String column region"
+        );
+
+        let err = db
+            .aggregate_window(
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["host".to_string()],
+                vec![("temp", AggregateType::Sum)],
+                5,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::ColumnNotFound { .. }));
+
+        // An empty store has no databases.
+        let store = Store::new();
+        assert!(store
+            .select(
+                "db",
+                "cpu",
+                time_range(0, 10),
+                &[],
+                vec!["region".to_string()],
+                None,
+                0,
+                false
+            )
+            .unwrap()
+            .is_none());
     }
 }
@@ -1,16 +1,35 @@
-use std::{borrow::Cow, collections::BTreeMap};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
 
+use arrow_deps::arrow::{
+    array::{Array, ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array},
+    datatypes::DataType,
+    record_batch::RecordBatch,
+};
 use hashbrown::{hash_map, HashMap};
 use itertools::Itertools;
+use regex::RegexSet;
+use snafu::{ensure, OptionExt};
 
 use crate::column::{
-    cmp::Operator, AggregateResult, AggregateType, Column, EncodedValues, OwnedValue, RowIDs,
-    RowIDsOption, Scalar, Value, Values, ValuesIterator,
+    cmp::Operator, AggregateResult, AggregateType, Column, EncodedValues, LogicalDataType,
+    OwnedValue, RowIDs, RowIDsOption, Scalar, Value, ValueSet, Values, ValuesIterator,
 };
+use crate::{InvalidColumnTypeMetadata, InvalidTimeColumn, Result, UnsupportedColumnType};
 
 /// The name used for a timestamp column.
 pub const TIME_COLUMN_NAME: &str = data_types::TIME_COLUMN_NAME;
 
+/// The prefix of the schema metadata keys describing the type of each column
+/// in a record batch. The value of the `iox::column_type::<column name>`
+/// entry is `tag`, `field` or, for the column named `TIME_COLUMN_NAME`,
+/// `time`. See `RowGroup::from_record_batch_rows`.
+pub const COLUMN_TYPE_METADATA_KEY_PREFIX: &str = "iox::column_type::";
+
 /// A `RowGroup` is an immutable horizontal chunk of a single `Table`. By
 /// definition it has the same schema as all the other read groups in the table.
 /// All the columns within the `RowGroup` must have the same number of logical
@@ -44,8 +63,7 @@ impl RowGroup {
                 ColumnType::Tag(c) => {
                     assert_eq!(c.num_rows(), rows);
 
-                    meta.column_ranges
-                        .insert(name.clone(), c.column_range().unwrap());
+                    meta.column_ranges.insert(name.clone(), c.column_range());
                     all_columns_by_name.insert(name.clone(), all_columns.len());
                     tag_columns_by_name.insert(name, all_columns.len());
                     all_columns.push(c);
@@ -53,8 +71,7 @@ impl RowGroup {
                 ColumnType::Field(c) => {
                     assert_eq!(c.num_rows(), rows);
 
-                    meta.column_ranges
-                        .insert(name.clone(), c.column_range().unwrap());
+                    meta.column_ranges.insert(name.clone(), c.column_range());
                     all_columns_by_name.insert(name.clone(), all_columns.len());
                     field_columns_by_name.insert(name, all_columns.len());
                     all_columns.push(c);
@@ -71,8 +88,7 @@ impl RowGroup {
                         Some((_, _)) => unreachable!("unexpected types for time range"),
                     };
 
-                    meta.column_ranges
-                        .insert(name.clone(), c.column_range().unwrap());
+                    meta.column_ranges.insert(name.clone(), c.column_range());
 
                     all_columns_by_name.insert(name.clone(), all_columns.len());
                     time_column = Some(all_columns.len());
@@ -91,6 +107,99 @@ impl RowGroup {
         }
     }
 
+    /// Builds a `RowGroup` from a range of rows in a record batch.
+    ///
+    /// The column named `TIME_COLUMN_NAME` becomes the time column. Other
+    /// columns are tag or field columns as described by the schema metadata
+    /// (see `COLUMN_TYPE_METADATA_KEY_PREFIX`). A column without a metadata
+    /// entry is a tag column if it's a string column and a field column
+    /// otherwise.
+    ///
+    /// An error is returned if there is no time column, the time column isn't
+    /// made up of non-null `Int64` values, a column's type isn't supported or
+    /// its metadata entry is invalid. Panics if the range is empty.
+    pub fn from_record_batch_rows(rb: &RecordBatch, rows: Range<usize>) -> Result<Self> {
+        let schema = rb.schema();
+        let time_field = schema
+            .column_with_name(TIME_COLUMN_NAME)
+            .context(InvalidTimeColumn {
+                reason: "column not found",
+            })?;
+        ensure!(
+            time_field.1.data_type() == &DataType::Int64,
+            InvalidTimeColumn {
+                reason: format!("expected Int64 values, got {:?}", time_field.1.data_type()),
+            }
+        );
+        let time_array = rb.column(time_field.0);
+        ensure!(
+            !rows.clone().any(|i| time_array.is_null(i)),
+            InvalidTimeColumn {
+                reason: "column contains NULL values",
+            }
+        );
+
+        let mut columns = BTreeMap::new();
+        for (field, array) in schema.fields().iter().zip(rb.columns()) {
+            let name = field.name();
+            let key = format!("{}{}", COLUMN_TYPE_METADATA_KEY_PREFIX, name);
+            let is_tag = match schema.metadata().get(&key).map(String::as_str) {
+                Some("time") if name == TIME_COLUMN_NAME => false,
+                Some("time") => {
+                    return InvalidColumnTypeMetadata {
+                        column_name: name,
+                        reason: format!(
+                            "only the {} column can be a time column",
+                            TIME_COLUMN_NAME
+                        ),
+                    }
+                    .fail()
+                }
+                Some(_) if name == TIME_COLUMN_NAME => {
+                    return InvalidColumnTypeMetadata {
+                        column_name: name,
+                        reason: "the time column must have the time type",
+                    }
+                    .fail()
+                }
+                Some("tag") => {
+                    ensure!(
+                        field.data_type() == &DataType::Utf8,
+                        InvalidColumnTypeMetadata {
+                            column_name: name,
+                            reason: format!(
+                                "tag columns must be Utf8, got {:?}",
+                                field.data_type()
+                            ),
+                        }
+                    );
+                    true
+                }
+                Some("field") => false,
+                Some(other) => {
+                    return InvalidColumnTypeMetadata {
+                        column_name: name,
+                        reason: format!("unknown column type {}", other),
+                    }
+                    .fail()
+                }
+                None => field.data_type() == &DataType::Utf8,
+            };
+
+            let column = column_from_array(name, array, rows.clone())?;
+            let column_type = if name == TIME_COLUMN_NAME {
+                ColumnType::Time(column)
+            } else if is_tag {
+                ColumnType::Tag(column)
+            } else {
+                ColumnType::Field(column)
+            };
+            columns.insert(name.to_owned(), column_type);
+        }
+
+        Ok(Self::new(rows.len() as u32, columns))
+    }
+
     /// The total size in bytes of the read group
     pub fn size(&self) -> u64 {
         self.meta.size
@@ -102,8 +211,9 @@ impl RowGroup {
         self.meta.rows
     }
 
-    /// The ranges on each column in the `RowGroup`.
-    pub fn column_ranges(&self) -> &BTreeMap<String, (OwnedValue, OwnedValue)> {
+    /// The ranges on each column in the `RowGroup`. The range of a column
+    /// containing only NULL values is `None`.
+    pub fn column_ranges(&self) -> &BTreeMap<String, Option<(OwnedValue, OwnedValue)>> {
         &self.meta.column_ranges
     }
 
@@ -124,6 +234,14 @@ impl RowGroup {
         (column_name, &self.columns[*column_index])
     }
 
+    /// The logical data type of the named column, if it exists in the
+    /// `RowGroup`.
+    pub fn column_logical_datatype(&self, name: ColumnName<'_>) -> Option<LogicalDataType> {
+        self.all_columns_by_name
+            .get(name)
+            .map(|&i| self.columns[i].logical_datatype())
+    }
+
     // Returns a reference to the timestamp column.
     fn time_column(&self) -> &Column {
         &self.columns[self.time_column]
@@ -134,6 +252,75 @@ impl RowGroup {
         self.meta.time_range
     }
 
+    /// The names of the tag columns in the row group.
+    pub fn tag_column_names(&self) -> Vec<ColumnName<'_>> {
+        self.tag_columns_by_name
+            .keys()
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The distinct non-null values of the named tag column, or `None` if the
+    /// `RowGroup` has no such tag column or it doesn't contain strings.
+    pub fn tag_column_dictionary(&self, name: ColumnName<'_>) -> Option<Vec<&String>> {
+        self.tag_columns_by_name
+            .get(name)
+            .and_then(|&i| self.columns[i].dictionary())
+    }
+
+    /// The distinct non-null values of the named tag column in the rows
+    /// satisfying the predicate expression, or `None` if the `RowGroup` has no
+    /// such tag column or it doesn't contain strings. The column dictionary is
+    /// used when no rows need to be filtered.
+    pub fn tag_column_values(
+        &self,
+        name: ColumnName<'_>,
+        expr: &PredicateExpr<'_>,
+    ) -> Option<BTreeSet<&String>> {
+        let column = &self.columns[*self.tag_columns_by_name.get(name)?];
+        let dictionary = column.dictionary()?;
+        if expr.is_empty() {
+            return Some(dictionary.into_iter().collect());
+        }
+
+        let row_ids = match self.row_ids_from_expr(expr) {
+            RowIDsOption::None(_) => return Some(BTreeSet::new()),
+            RowIDsOption::Some(row_ids) => row_ids.to_vec(),
+            RowIDsOption::All(_) => return Some(dictionary.into_iter().collect()),
+        };
+        match column.distinct_values(&row_ids) {
+            ValueSet::String(values) => Some(values.into_iter().flatten().collect()),
+            ValueSet::ByteArray(_) => unreachable!("tag columns are strings"),
+        }
+    }
+
+    /// The name and size in bytes of each column in the `RowGroup`, ordered
+    /// by name.
+    pub fn column_sizes(&self) -> Vec<(ColumnName<'_>, u64)> {
+        self.all_columns_by_name
+            .iter()
+            .map(|(name, &i)| (name.as_str(), self.columns[i].size()))
+            .collect()
+    }
+
+    /// The name and type of each column in the `RowGroup`, ordered by name.
+    pub fn column_types(&self) -> Vec<(ColumnName<'_>, crate::ColumnType)> {
+        self.all_columns_by_name
+            .iter()
+            .map(|(name, &i)| {
+                let data_type = self.columns[i].logical_datatype();
+                let column_type = if self.tag_columns_by_name.contains_key(name) {
+                    crate::ColumnType::Tag(data_type)
+                } else if i == self.time_column {
+                    crate::ColumnType::Time(data_type)
+                } else {
+                    crate::ColumnType::Field(data_type)
+                };
+                (name.as_str(), column_type)
+            })
+            .collect()
+    }
+
     /// Efficiently determine if the provided predicate might be satisfied by
     /// the provided column.
     pub fn column_could_satisfy_predicate(
@@ -158,10 +345,36 @@ impl RowGroup {
         columns: &[ColumnName<'_>],
         predicates: &[Predicate<'_>],
     ) -> ReadFilterResult<'_> {
-        let row_ids = self.row_ids_from_predicates(predicates);
+        self.read_filter_expr(columns, &PredicateExpr::from(predicates))
+    }
+
+    /// Returns a set of materialised column values that satisfy a predicate
+    /// expression.
+    pub fn read_filter_expr(
+        &self,
+        columns: &[ColumnName<'_>],
+        expr: &PredicateExpr<'_>,
+    ) -> ReadFilterResult<'_> {
+        let row_ids = self.row_ids_from_expr(expr);
         ReadFilterResult(self.materialise_rows(columns, row_ids))
     }
 
+    /// Determines if at least one row in the row group satisfies the
+    /// predicate expression.
+    pub fn satisfies_predicate_expr(&self, expr: &PredicateExpr<'_>) -> bool {
+        !matches!(self.row_ids_from_expr(expr), RowIDsOption::None(_))
+    }
+
+    /// The number of rows satisfying the predicate expression. No column
+    /// values are materialised.
+    pub fn row_count(&self, expr: &PredicateExpr<'_>) -> u64 {
+        match self.row_ids_from_expr(expr) {
+            RowIDsOption::None(_) => 0,
+            RowIDsOption::Some(row_ids) => row_ids.len() as u64,
+            RowIDsOption::All(_) => self.rows() as u64,
+        }
+    }
+
     fn materialise_rows(
         &self,
         names: &[ColumnName<'_>],
@@ -175,8 +388,7 @@ impl RowGroup {
                 // pooled buffer to the croaring Bitmap API.
                 let row_ids = row_ids.to_vec();
                 for &name in names {
-                    let (col_name, col) = self.column_name_and_column(name);
-                    results.push((col_name, col.values(row_ids.as_slice())));
+                    results.push(self.materialise_column(name, row_ids.as_slice()));
                 }
                 results
             }
@@ -188,14 +400,122 @@ impl RowGroup {
                 let row_ids = (0..self.rows()).collect::<Vec<_>>();
 
                 for &name in names {
-                    let (col_name, col) = self.column_name_and_column(name);
-                    results.push((col_name, col.values(row_ids.as_slice())));
+                    results.push(self.materialise_column(name, row_ids.as_slice()));
                 }
                 results
             }
         }
     }
 
+    // Materialises the values of the named column at the row ids. Only the
+    // columns being returned are materialised: predicates are evaluated
+    // against the encoded columns, which for dictionary encoded columns avoids
+    // looking up the value of every row.
+    fn materialise_column(
+        &self,
+        name: ColumnName<'_>,
+        row_ids: &[u32],
+    ) -> (ColumnName<'_>, Values<'_>) {
+        let (col_name, col) = self.column_name_and_column(name);
+        #[cfg(test)]
+        materialised_columns::record(col_name);
+        (col_name, col.values(row_ids))
+    }
+
+    // Determines the set of row ids that satisfy the provided predicate
+    // expression.
+    //
+    // The predicates directly beneath an `And` are evaluated together, so that
+    // a time range can be special-cased, before any nested expressions. An
+    // `And` stops evaluating as soon as no rows match, and an `Or` stops as
+    // soon as all rows match. Predicates on columns that don't exist in the
+    // `RowGroup` match no rows.
+    fn row_ids_from_expr(&self, expr: &PredicateExpr<'_>) -> RowIDsOption {
+        match expr {
+            PredicateExpr::Leaf(predicate) => {
+                self.row_ids_from_conjunction(std::slice::from_ref(predicate))
+            }
+            PredicateExpr::Columns(predicate) => self.row_ids_from_column_predicate(predicate),
+            PredicateExpr::And(exprs) => {
+                let predicates = exprs
+                    .iter()
+                    .filter_map(|expr| match expr {
+                        PredicateExpr::Leaf(predicate) => Some(*predicate),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut row_ids = self.row_ids_from_conjunction(&predicates);
+                for expr in exprs {
+                    if matches!(expr, PredicateExpr::Leaf(_)) {
+                        continue;
+                    }
+                    if let RowIDsOption::None(_) = row_ids {
+                        return row_ids;
+                    }
+                    row_ids = intersect_row_ids(row_ids, self.row_ids_from_expr(expr));
+                }
+                row_ids
+            }
+            PredicateExpr::Or(exprs) => {
+                let mut row_ids = RowIDsOption::None(RowIDs::new_bitmap());
+                for expr in exprs {
+                    if let RowIDsOption::All(_) = row_ids {
+                        return row_ids;
+                    }
+                    row_ids = union_row_ids(row_ids, self.row_ids_from_expr(expr));
+                }
+                row_ids
+            }
+        }
+    }
+
+    // Determines the set of row ids where the values of two columns satisfy
+    // the comparison `left op right`. Rows where either value is NULL don't
+    // match, and no rows match if either column is missing from the
+    // `RowGroup` or the types of the columns can't be compared.
+    fn row_ids_from_column_predicate(&self, predicate: &ColumnPredicate<'_>) -> RowIDsOption {
+        let &(left, op, right) = predicate;
+        if !self.all_columns_by_name.contains_key(left)
+            || !self.all_columns_by_name.contains_key(right)
+        {
+            return RowIDsOption::None(RowIDs::new_bitmap());
+        }
+
+        let (left, right) = (self.column_by_name(left), self.column_by_name(right));
+        if !columns_comparable(left.logical_datatype(), &op, right.logical_datatype()) {
+            return RowIDsOption::None(RowIDs::new_bitmap());
+        }
+
+        let (left, right) = (left.all_values(), right.all_values());
+        let mut row_ids = RowIDs::new_bitmap();
+        for row in 0..left.len() {
+            let rhs = right.value(row);
+            if !rhs.is_null() && value_satisfies_predicate(&left.value(row), &op, &rhs, None) {
+                row_ids.add(row as u32);
+            }
+        }
+
+        match row_ids.len() {
+            0 => RowIDsOption::None(row_ids),
+            n if n == self.rows() as usize => RowIDsOption::All(row_ids),
+            _ => RowIDsOption::Some(row_ids),
+        }
+    }
+
+    // Determines the set of row ids that satisfy all of the provided
+    // predicates, none of which may be on a column missing from the
+    // `RowGroup`.
+    fn row_ids_from_conjunction(&self, predicates: &[Predicate<'_>]) -> RowIDsOption {
+        if predicates
+            .iter()
+            .any(|(name, _)| !self.all_columns_by_name.contains_key(*name))
+        {
+            return RowIDsOption::None(RowIDs::new_bitmap());
+        }
+        self.row_ids_from_predicates(predicates)
+    }
+
     // Determines the set of row ids that satisfy the provided predicates. If
     // `predicates` contains two predicates on the time column they are
     // special-cased.
@@ -271,6 +591,11 @@ impl RowGroup {
                         result_row_ids.union(&row_ids)
                     }
                     result_row_ids.intersect(&row_ids);
+
+                    // No rows satisfy all of the predicates applied so far.
+                    if result_row_ids.is_empty() {
+                        return RowIDsOption::None(row_ids);
+                    }
                     dst = row_ids; // hand buffer back
                 }
 
@@ -325,6 +650,80 @@ impl RowGroup {
         predicates: &[Predicate<'_>],
         group_columns: &[ColumnName<'_>],
         aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> ReadGroupResult<'_> {
+        self.read_group_with_value_predicates(predicates, group_columns, aggregates, &[])
+    }
+
+    /// The same as `read_group`, but each aggregate can have an optional
+    /// predicate applied to the values of the column being aggregated.
+    ///
+    /// Unlike `predicates`, which determine the rows that are read, a value
+    /// predicate only determines which of the values in the aggregate column
+    /// contribute to that aggregate. For example, the sum of only positive
+    /// values can be calculated alongside the count of all values.
+    ///
+    /// `value_predicates` should either be empty or contain an entry for each
+    /// aggregate.
+    pub fn read_group_with_value_predicates(
+        &self,
+        predicates: &[Predicate<'_>],
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+    ) -> ReadGroupResult<'_> {
+        self.read_group_expr(
+            &PredicateExpr::from(predicates),
+            group_columns,
+            aggregates,
+            value_predicates,
+        )
+    }
+
+    /// The same as `read_group_with_value_predicates`, but the rows that are
+    /// read are determined by a predicate expression.
+    pub fn read_group_expr(
+        &self,
+        expr: &PredicateExpr<'_>,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+    ) -> ReadGroupResult<'_> {
+        self.read_group_bucketed(expr, group_columns, aggregates, value_predicates, None)
+    }
+
+    /// The same as `read_group_expr`, but rows are also grouped by the
+    /// time bucket of length `bucket` nanoseconds that they fall in. Buckets
+    /// are aligned to multiples of `bucket` since the epoch, and the start of
+    /// each row's bucket is the last value of its group key, under the time
+    /// column's name. The bucket start is NULL for rows whose bucket would
+    /// start before `i64::MIN`.
+    pub fn read_group_window_expr(
+        &self,
+        expr: &PredicateExpr<'_>,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+        bucket: i64,
+    ) -> ReadGroupResult<'_> {
+        assert!(bucket > 0, "time buckets must have a positive length");
+        self.read_group_bucketed(
+            expr,
+            group_columns,
+            aggregates,
+            value_predicates,
+            Some(bucket),
+        )
+    }
+
+    // Executes a read-group-aggregate operation, grouping rows by their time
+    // bucket after the group columns if `bucket` is provided.
+    fn read_group_bucketed(
+        &self,
+        expr: &PredicateExpr<'_>,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+        bucket: Option<i64>,
     ) -> ReadGroupResult<'_> {
         // `ReadGroupResult`s should have the same lifetime as self.
         // Alternatively ReadGroupResult could not store references to input
@@ -342,7 +741,7 @@ impl RowGroup {
                 .iter()
                 .map(|(name, typ)| {
                     let (column_name, col) = self.column_name_and_column(name);
-                    (column_name, *typ)
+                    (column_name, typ.clone())
                 })
                 .collect::<Vec<_>>(),
             ..ReadGroupResult::default()
@@ -356,14 +755,19 @@ impl RowGroup {
                 .properties()
                 .has_pre_computed_row_ids
         });
-        if predicates.is_empty() && all_group_cols_pre_computed {
+        let has_value_predicates = value_predicates.iter().any(Option::is_some);
+        if expr.is_empty()
+            && all_group_cols_pre_computed
+            && !has_value_predicates
+            && bucket.is_none()
+        {
             self.read_group_all_rows_all_rle(&mut result);
             return result;
         }
 
         // There are predicates. The next stage is apply them and determine the
         // intermediate set of row ids.
-        let row_ids = self.row_ids_from_predicates(predicates);
+        let row_ids = self.row_ids_from_expr(expr);
         let filter_row_ids = match row_ids {
             RowIDsOption::None(_) => {
                 return result;
@@ -372,17 +776,28 @@ impl RowGroup {
             RowIDsOption::All(row_ids) => None,
         };
 
-        let group_cols_num = result.group_columns.len();
         let agg_cols_num = result.aggregate_columns.len();
 
         // materialise all *encoded* values for each column we are grouping on.
         // These will not be the logical (typically string) values, but will be
         // vectors of integers representing the physical values.
-        let groupby_encoded_ids: Vec<_> = result
+        let mut groupby_encoded_ids: Vec<_> = result
             .group_columns
             .iter()
             .map(|name| {
                 let col = self.column_by_name(name);
+
+                // Only string columns are dictionary encoded. The values of
+                // other columns are encoded against a dictionary of their
+                // distinct values.
+                if col.logical_datatype() != LogicalDataType::String {
+                    let values = match &filter_row_ids {
+                        Some(row_ids) => col.values(row_ids),
+                        None => col.all_values(),
+                    };
+                    return GroupColumnIds::from_values(col, &values);
+                }
+
                 let mut encoded_values_buf =
                     EncodedValues::with_capacity_u32(col.num_rows() as usize);
 
@@ -398,18 +813,49 @@ impl RowGroup {
                         encoded_values_buf = col.all_encoded_values(encoded_values_buf);
                     }
                 }
-                encoded_values_buf.take_u32()
+                GroupColumnIds {
+                    column: col,
+                    ids: encoded_values_buf.take_u32(),
+                    dictionary: None,
+                }
             })
             .collect();
 
-        // Materialise values in aggregate columns.
-        let mut aggregate_columns_data = Vec::with_capacity(agg_cols_num);
-        for (name, agg_type) in &result.aggregate_columns {
-            let col = self.column_by_name(name);
+        // The time bucket of each row is grouped on after the group columns.
+        if let Some(bucket) = bucket {
+            let col = self.time_column();
+            let times = time_values(&match &filter_row_ids {
+                Some(row_ids) => col.values(row_ids),
+                None => col.all_values(),
+            });
+            let starts = Values::I64N(
+                times
+                    .iter()
+                    .map(|time| time.div_euclid(bucket).checked_mul(bucket))
+                    .collect(),
+            );
+            groupby_encoded_ids.push(GroupColumnIds::from_values(col, &starts));
+            result.group_columns.push(TIME_COLUMN_NAME);
+        }
+
+        // Materialise values in aggregate columns. Aggregates on the same
+        // column with the same value predicate share the materialised values,
+        // so that the column is only read once.
+        let mut aggregate_columns_data: Vec<AggregateColumnData<'_>> =
+            Vec::with_capacity(agg_cols_num);
+        let mut materialised = Vec::with_capacity(agg_cols_num);
+        for (i, (name, agg_type)) in result.aggregate_columns.iter().enumerate() {
+            let value_predicate = value_predicates.get(i).and_then(Option::as_ref);
+            if let Some(pos) = materialised
+                .iter()
+                .position(|&key| key == (*name, value_predicate))
+            {
+                aggregate_columns_data[pos].aggregates.push(i);
+                continue;
+            }
+            materialised.push((*name, value_predicate));
 
-            // TODO(edd): this materialises a column per aggregate. If there are
-            // multiple aggregates for the same column then this will
-            // over-allocate
+            let col = self.column_by_name(name);
 
             // Do we want some rows for the column or all of them?
             let column_values = match &filter_row_ids {
@@ -421,22 +867,51 @@ impl RowGroup {
                     col.all_values()
                 }
             };
-            aggregate_columns_data.push(column_values);
+
+            // Values not satisfying a value predicate are nulled out so that
+            // they don't contribute to the aggregate.
+            let column_values = match value_predicate {
+                Some((op, value)) => apply_value_predicate(column_values, op, value),
+                None => column_values,
+            };
+            aggregate_columns_data.push(AggregateColumnData {
+                values: column_values,
+                aggregates: vec![i],
+            });
         }
 
+        // Selector aggregates choose values using the timestamps of the rows,
+        // so they're only materialised when needed.
+        let timestamps = if aggregates.iter().any(|(_, typ)| typ.is_selector()) {
+            let col = self.column_by_name(TIME_COLUMN_NAME);
+            Some(time_values(&match &filter_row_ids {
+                Some(row_ids) => col.values(row_ids),
+                None => col.all_values(),
+            }))
+        } else {
+            None
+        };
+        let timestamps = timestamps.as_deref();
+
         // If there is a single group column then we can use an optimised
         // approach for building group keys
-        if group_columns.len() == 1 {
+        if groupby_encoded_ids.len() == 1 {
             self.read_group_single_group_column(
                 &mut result,
                 &groupby_encoded_ids[0],
                 aggregate_columns_data,
+                timestamps,
             );
             return result;
         }
 
         // Perform the group by using a hashmap
-        self.read_group_with_hashing(&mut result, &groupby_encoded_ids, aggregate_columns_data);
+        self.read_group_with_hashing(
+            &mut result,
+            &groupby_encoded_ids,
+            aggregate_columns_data,
+            timestamps,
+        );
         result
     }
 
@@ -448,18 +923,29 @@ impl RowGroup {
     fn read_group_with_hashing<'a>(
         &'a self,
         dst: &mut ReadGroupResult<'a>,
-        groupby_encoded_ids: &[Vec<u32>],
-        aggregate_columns_data: Vec<Values<'a>>,
+        groupby_encoded_ids: &[GroupColumnIds<'a>],
+        aggregate_columns_data: Vec<AggregateColumnData<'a>>,
+        timestamps: Option<&[i64]>,
     ) {
         // An optimised approach to building the hashmap of group keys using a
         // single 128-bit integer as the group key. If grouping is on more than
         // four columns then a fallback to using an vector as a key will happen.
         if dst.group_columns.len() <= 4 {
-            self.read_group_hash_with_u128_key(dst, &groupby_encoded_ids, &aggregate_columns_data);
+            self.read_group_hash_with_u128_key(
+                dst,
+                &groupby_encoded_ids,
+                &aggregate_columns_data,
+                timestamps,
+            );
             return;
         }
 
-        self.read_group_hash_with_vec_key(dst, &groupby_encoded_ids, &aggregate_columns_data);
+        self.read_group_hash_with_vec_key(
+            dst,
+            &groupby_encoded_ids,
+            &aggregate_columns_data,
+            timestamps,
+        );
     }
 
     // This function is used with `read_group_hash` when the number of columns
@@ -468,13 +954,16 @@ impl RowGroup {
     fn read_group_hash_with_vec_key<'a>(
         &'a self,
         dst: &mut ReadGroupResult<'a>,
-        groupby_encoded_ids: &[Vec<u32>],
-        aggregate_columns_data: &[Values<'a>],
+        groupby_encoded_ids: &[GroupColumnIds<'a>],
+        aggregate_columns_data: &[AggregateColumnData<'a>],
+        timestamps: Option<&[i64]>,
     ) {
         // Now begin building the group keys.
         let mut groups: HashMap<Vec<u32>, Vec<AggregateResult<'_>>> = HashMap::default();
-        let total_rows = groupby_encoded_ids[0].len();
-        assert!(groupby_encoded_ids.iter().all(|x| x.len() == total_rows));
+        let total_rows = groupby_encoded_ids[0].ids.len();
+        assert!(groupby_encoded_ids
+            .iter()
+            .all(|x| x.ids.len() == total_rows));
 
         // key_buf will be used as a temporary buffer for group keys, which are
         // themselves integers.
@@ -483,16 +972,14 @@ impl RowGroup {
         for row in 0..total_rows {
             // update the group key buffer with the group key for this row
             for (j, col_ids) in groupby_encoded_ids.iter().enumerate() {
-                key_buf[j] = col_ids[row];
+                key_buf[j] = col_ids.ids[row];
             }
 
             match groups.raw_entry_mut().from_key(&key_buf) {
                 // aggregates for this group key are already present. Update
                 // them
                 hash_map::RawEntryMut::Occupied(mut entry) => {
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        entry.get_mut()[i].update(values.value(row));
-                    }
+                    update_aggregates(entry.get_mut(), aggregate_columns_data, timestamps, row);
                 }
                 // group key does not exist, so create it.
                 hash_map::RawEntryMut::Vacant(entry) => {
@@ -501,9 +988,7 @@ impl RowGroup {
                         group_key_aggs.push(AggregateResult::from(agg_type));
                     }
 
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(&mut group_key_aggs, aggregate_columns_data, timestamps, row);
 
                     entry.insert(key_buf.clone(), group_key_aggs);
                 }
@@ -512,11 +997,6 @@ impl RowGroup {
 
         // Finally, build results set. Each encoded group key needs to be
         // materialised into a logical group key
-        let columns = dst
-            .group_columns
-            .iter()
-            .map(|name| self.column_by_name(name))
-            .collect::<Vec<_>>();
         let mut group_key_vec: Vec<GroupKey<'_>> = Vec::with_capacity(groups.len());
         let mut aggregate_vec = Vec::with_capacity(groups.len());
 
@@ -524,7 +1004,7 @@ impl RowGroup {
             let mut logical_key = Vec::with_capacity(group_key.len());
             for (col_idx, &encoded_id) in group_key.iter().enumerate() {
                 // TODO(edd): address the cast to u32
-                logical_key.push(columns[col_idx].decode_id(encoded_id as u32));
+                logical_key.push(groupby_encoded_ids[col_idx].decode(encoded_id as u32));
             }
 
             group_key_vec.push(GroupKey(logical_key));
@@ -546,31 +1026,32 @@ impl RowGroup {
     fn read_group_hash_with_u128_key<'a>(
         &'a self,
         dst: &mut ReadGroupResult<'a>,
-        groupby_encoded_ids: &[Vec<u32>],
-        aggregate_columns_data: &[Values<'a>],
+        groupby_encoded_ids: &[GroupColumnIds<'a>],
+        aggregate_columns_data: &[AggregateColumnData<'a>],
+        timestamps: Option<&[i64]>,
     ) {
-        let total_rows = groupby_encoded_ids[0].len();
-        assert!(groupby_encoded_ids.iter().all(|x| x.len() == total_rows));
+        let total_rows = groupby_encoded_ids[0].ids.len();
+        assert!(groupby_encoded_ids
+            .iter()
+            .all(|x| x.ids.len() == total_rows));
         assert!(dst.group_columns.len() <= 4);
 
         // Now begin building the group keys.
         let mut groups: HashMap<u128, Vec<AggregateResult<'_>>> = HashMap::default();
 
-        for row in 0..groupby_encoded_ids[0].len() {
+        for row in 0..total_rows {
             // pack each column's encoded value for the row into a packed group
             // key.
             let mut group_key_packed = 0_u128;
             for (i, col_ids) in groupby_encoded_ids.iter().enumerate() {
-                group_key_packed = pack_u32_in_u128(group_key_packed, col_ids[row], i);
+                group_key_packed = pack_u32_in_u128(group_key_packed, col_ids.ids[row], i);
             }
 
             match groups.raw_entry_mut().from_key(&group_key_packed) {
                 // aggregates for this group key are already present. Update
                 // them
                 hash_map::RawEntryMut::Occupied(mut entry) => {
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        entry.get_mut()[i].update(values.value(row));
-                    }
+                    update_aggregates(entry.get_mut(), aggregate_columns_data, timestamps, row);
                 }
                 // group key does not exist, so create it.
                 hash_map::RawEntryMut::Vacant(entry) => {
@@ -579,9 +1060,7 @@ impl RowGroup {
                         group_key_aggs.push(AggregateResult::from(agg_type));
                     }
 
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(&mut group_key_aggs, aggregate_columns_data, timestamps, row);
 
                     entry.insert(group_key_packed, group_key_aggs);
                 }
@@ -590,23 +1069,18 @@ impl RowGroup {
 
         // Finally, build results set. Each encoded group key needs to be
         // materialised into a logical group key
-        let columns = dst
-            .group_columns
-            .iter()
-            .map(|name| self.column_by_name(name))
-            .collect::<Vec<_>>();
         let mut group_key_vec: Vec<GroupKey<'_>> = Vec::with_capacity(groups.len());
         let mut aggregate_vec = Vec::with_capacity(groups.len());
 
         for (group_key_packed, aggs) in groups.into_iter() {
-            let mut logical_key = Vec::with_capacity(columns.len());
+            let mut logical_key = Vec::with_capacity(groupby_encoded_ids.len());
 
             // Unpack the appropriate encoded id for each column from the packed
             // group key, then materialise the logical value for that id and add
             // it to the materialised group key (`logical_key`).
-            for (col_idx, column) in columns.iter().enumerate() {
+            for (col_idx, column) in groupby_encoded_ids.iter().enumerate() {
                 let encoded_id = (group_key_packed >> (col_idx * 32)) as u32;
-                logical_key.push(column.decode_id(encoded_id));
+                logical_key.push(column.decode(encoded_id));
             }
 
             group_key_vec.push(GroupKey(logical_key));
@@ -629,11 +1103,23 @@ impl RowGroup {
             .map(|name| self.column_by_name(name))
             .collect::<Vec<_>>();
 
-        let aggregate_columns_typ = dst
-            .aggregate_columns
-            .iter()
-            .map(|(name, typ)| (self.column_by_name(name), *typ))
+        // Aggregates on the same column are calculated together, so that the
+        // column's values are only read once for each group key.
+        let mut aggregate_columns: Vec<(ColumnName<'_>, Vec<(usize, AggregateType)>)> = vec![];
+        for (i, (name, typ)) in dst.aggregate_columns.iter().enumerate() {
+            match aggregate_columns
+                .iter_mut()
+                .find(|(col_name, _)| col_name == name)
+            {
+                Some((_, aggregates)) => aggregates.push((i, typ.clone())),
+                None => aggregate_columns.push((*name, vec![(i, typ.clone())])),
+            }
+        }
+        let aggregate_columns = aggregate_columns
+            .into_iter()
+            .map(|(name, aggregates)| (self.column_by_name(name), aggregates))
             .collect::<Vec<_>>();
+        let time_column = self.column_by_name(TIME_COLUMN_NAME);
 
         let encoded_groups = dst
             .group_columns
@@ -705,26 +1191,41 @@ impl RowGroup {
             }
             dst.group_keys.push(GroupKey(material_key));
 
-            let mut aggregates = Vec::with_capacity(aggregate_columns_typ.len());
-            for (agg_col, typ) in &aggregate_columns_typ {
-                aggregates.push(match typ {
-                    AggregateType::Count => {
-                        AggregateResult::Count(agg_col.count(&aggregate_row_ids.to_vec()) as u64)
-                    }
-                    AggregateType::First => todo!(),
-                    AggregateType::Last => todo!(),
-                    AggregateType::Min => {
-                        AggregateResult::Min(agg_col.min(&aggregate_row_ids.to_vec()))
-                    }
-                    AggregateType::Max => {
-                        AggregateResult::Max(agg_col.max(&aggregate_row_ids.to_vec()))
-                    }
-                    AggregateType::Sum => {
-                        AggregateResult::Sum(agg_col.sum(&aggregate_row_ids.to_vec()))
+            let row_ids = aggregate_row_ids.to_vec();
+            let mut aggregates = vec![None; dst.aggregate_columns.len()];
+            for (agg_col, col_aggregates) in &aggregate_columns {
+                if let [(i, typ)] = col_aggregates.as_slice() {
+                    aggregates[*i] = Some(aggregate_rows(agg_col, typ, &row_ids, time_column));
+                    continue;
+                }
+
+                // Several aggregates on the column are calculated in a single
+                // pass over its values.
+                let values = agg_col.values(&row_ids);
+                let timestamps = if col_aggregates.iter().any(|(_, typ)| typ.is_selector()) {
+                    Some(time_values(&time_column.values(&row_ids)))
+                } else {
+                    None
+                };
+                let mut results = col_aggregates
+                    .iter()
+                    .map(|(_, typ)| AggregateResult::from(typ))
+                    .collect::<Vec<_>>();
+                for row in 0..values.len() {
+                    let value = values.value(row);
+                    for result in &mut results {
+                        match &timestamps {
+                            Some(timestamps) => result.update_with_time(value, timestamps[row]),
+                            None => result.update(value),
+                        }
                     }
-                });
+                }
+                for ((i, _), result) in col_aggregates.iter().zip(results) {
+                    aggregates[*i] = Some(result);
+                }
             }
-            dst.aggregates.push(aggregates);
+            dst.aggregates
+                .push(aggregates.into_iter().map(Option::unwrap).collect());
         }
     }
 
@@ -736,11 +1237,18 @@ impl RowGroup {
     fn read_group_single_group_column<'a>(
         &'a self,
         dst: &mut ReadGroupResult<'a>,
-        groupby_encoded_ids: &[u32],
-        aggregate_columns_data: Vec<Values<'a>>,
+        group_column: &GroupColumnIds<'a>,
+        aggregate_columns_data: Vec<AggregateColumnData<'a>>,
+        timestamps: Option<&[i64]>,
     ) {
-        let column = self.column_by_name(dst.group_columns[0]);
-        assert_eq!(dst.group_columns.len(), aggregate_columns_data.len());
+        let groupby_encoded_ids = &group_column.ids;
+        assert_eq!(
+            dst.aggregate_columns.len(),
+            aggregate_columns_data
+                .iter()
+                .map(|data| data.aggregates.len())
+                .sum::<usize>()
+        );
         let total_rows = groupby_encoded_ids.len();
 
         // Allocate a vector to hold aggregates that can be updated as rows are
@@ -755,9 +1263,7 @@ impl RowGroup {
             match &mut groups[idx] {
                 Some(group_key_aggs) => {
                     // Update all aggregates for the group key
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(group_key_aggs, &aggregate_columns_data, timestamps, row);
                 }
                 None => {
                     let mut group_key_aggs = dst
@@ -766,9 +1272,12 @@ impl RowGroup {
                         .map(|(_, agg_type)| AggregateResult::from(agg_type))
                         .collect::<Vec<_>>();
 
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(
+                        &mut group_key_aggs,
+                        &aggregate_columns_data,
+                        timestamps,
+                        row,
+                    );
 
                     groups[idx] = Some(group_key_aggs);
                 }
@@ -782,7 +1291,7 @@ impl RowGroup {
 
         for (group_key, aggs) in groups.into_iter().enumerate() {
             if let Some(aggs) = aggs {
-                group_key_vec.push(GroupKey(vec![column.decode_id(group_key as u32)]));
+                group_key_vec.push(GroupKey(vec![group_column.decode(group_key as u32)]));
                 aggregate_vec.push(aggs);
             }
         }
@@ -806,6 +1315,71 @@ impl RowGroup {
     }
 }
 
+// The encoded values of a column being grouped on, and the means to decode
+// them back into logical values.
+struct GroupColumnIds<'a> {
+    column: &'a Column,
+
+    // The encoded value for each row being grouped.
+    ids: Vec<u32>,
+
+    // The distinct values of a column that isn't dictionary encoded, indexed
+    // by encoded value. `None` if the column's own encoding is used.
+    dictionary: Option<Vec<Value<'a>>>,
+}
+
+// The key identifying a distinct value when encoding the values of a column
+// that isn't dictionary encoded. All values in a column have the same type, so
+// keys of different value types never need to be distinguished.
+#[derive(PartialEq, Eq, Hash)]
+enum GroupValueKey<'a> {
+    Null,
+    Bits(u64),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> GroupColumnIds<'a> {
+    // Encodes the values of a column that isn't dictionary encoded, such as an
+    // integer or boolean column, by assigning each distinct value an id.
+    fn from_values(column: &'a Column, values: &Values<'a>) -> Self {
+        let mut ids = Vec::with_capacity(values.len());
+        let mut dictionary = vec![];
+        let mut encoded: HashMap<GroupValueKey<'a>, u32> = HashMap::default();
+
+        for row in 0..values.len() {
+            let value = values.value(row);
+            let key = match value {
+                Value::Null | Value::Scalar(Scalar::Null) => GroupValueKey::Null,
+                Value::Scalar(Scalar::I64(v)) => GroupValueKey::Bits(v as u64),
+                Value::Scalar(Scalar::U64(v)) => GroupValueKey::Bits(v),
+                Value::Scalar(Scalar::F64(v)) => GroupValueKey::Bits(v.to_bits()),
+                Value::Boolean(v) => GroupValueKey::Bits(v as u64),
+                Value::String(v) => GroupValueKey::Bytes(v.as_bytes()),
+                Value::ByteArray(v) => GroupValueKey::Bytes(v),
+            };
+
+            let id = *encoded.entry(key).or_insert_with(|| {
+                dictionary.push(value);
+                dictionary.len() as u32 - 1
+            });
+            ids.push(id);
+        }
+
+        Self {
+            column,
+            ids,
+            dictionary: Some(dictionary),
+        }
+    }
+
+    fn decode(&self, encoded_id: u32) -> Value<'a> {
+        match &self.dictionary {
+            Some(dictionary) => dictionary[encoded_id as usize],
+            None => self.column.decode_id(encoded_id),
+        }
+    }
+}
+
 // Packs an encoded values into a `u128` at `pos`, which must be `[0,4)`.
 #[inline(always)]
 fn pack_u32_in_u128(packed_value: u128, encoded_id: u32, pos: usize) -> u128 {
@@ -825,71 +1399,493 @@ fn unpack_u128_group_key(group_key_packed: u128, n: usize, mut dst: Vec<u32>) ->
     dst
 }
 
-pub type Predicate<'a> = (ColumnName<'a>, (Operator, Value<'a>));
-
-// A GroupKey is an ordered collection of row values. The order determines which
-// columns the values originated from.
-#[derive(PartialEq, PartialOrd, Clone)]
-pub struct GroupKey<'row_group>(Vec<Value<'row_group>>);
-
-impl Eq for GroupKey<'_> {}
-
-// Implementing the `Ord` trait on `GroupKey` means that collections of group
-// keys become sortable. This is typically useful for test because depending on
-// the implementation, group keys are not always emitted in sorted order.
-//
-// To be compared, group keys *must* have the same length, or `cmp` will panic.
-// They will be ordered as follows:
-//
-//    [foo, zoo, zoo], [foo, bar, zoo], [bar, bar, bar], [bar, bar, zoo],
-//
-//    becomes:
-//
-//    [bar, bar, bar], [bar, bar, zoo], [foo, bar, zoo], [foo, zoo, zoo],
-//
-// Be careful sorting group keys in result sets, because other columns
-// associated with the group keys won't be sorted unless the correct `sort`
-// methods are used on the result set implementations.
-impl Ord for GroupKey<'_> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // two group keys must have same length
-        assert_eq!(self.0.len(), other.0.len());
-
-        let cols = self.0.len();
-        for i in 0..cols {
-            match self.0[i].partial_cmp(&other.0[i]) {
-                Some(ord) => return ord,
-                None => continue,
+// Intersects two sets of row ids.
+fn intersect_row_ids(a: RowIDsOption, b: RowIDsOption) -> RowIDsOption {
+    match (a, b) {
+        (RowIDsOption::None(ids), _) | (_, RowIDsOption::None(ids)) => RowIDsOption::None(ids),
+        (RowIDsOption::All(_), other) | (other, RowIDsOption::All(_)) => other,
+        (RowIDsOption::Some(mut a), RowIDsOption::Some(b)) => {
+            a.intersect(&b);
+            if a.is_empty() {
+                return RowIDsOption::None(a);
             }
+            RowIDsOption::Some(a)
         }
+    }
+}
 
-        std::cmp::Ordering::Equal
+// Unions two sets of row ids.
+fn union_row_ids(a: RowIDsOption, b: RowIDsOption) -> RowIDsOption {
+    match (a, b) {
+        (RowIDsOption::All(ids), _) | (_, RowIDsOption::All(ids)) => RowIDsOption::All(ids),
+        (RowIDsOption::None(_), other) | (other, RowIDsOption::None(_)) => other,
+        (RowIDsOption::Some(mut a), RowIDsOption::Some(b)) => {
+            a.union(&b);
+            RowIDsOption::Some(a)
+        }
     }
 }
 
-// A representation of a column name.
-pub type ColumnName<'a> = &'a str;
+pub type Predicate<'a> = (ColumnName<'a>, (Operator, Value<'a>));
 
-/// The logical type that a column could have.
-pub enum ColumnType {
-    Tag(Column),
-    Field(Column),
-    Time(Column),
+/// A comparison between the values of two columns in the same row, e.g.,
+/// `("surface_degrees", Operator::GT, "bottom_degrees")`.
+pub type ColumnPredicate<'a> = (ColumnName<'a>, Operator, ColumnName<'a>);
+
+/// Determines if the values of columns with the provided types can be
+/// compared with `op`. Numeric columns can be compared with each other, and
+/// string and boolean columns with columns of the same type. Regular
+/// expressions and NULL checks can't be applied between columns.
+pub fn columns_comparable(left: LogicalDataType, op: &Operator, right: LogicalDataType) -> bool {
+    let numeric = |data_type| {
+        matches!(
+            data_type,
+            LogicalDataType::Integer | LogicalDataType::Unsigned | LogicalDataType::Float
+        )
+    };
+
+    !op.is_regex()
+        && !op.is_null_check()
+        && ((numeric(left) && numeric(right))
+            || (left == right
+                && matches!(left, LogicalDataType::String | LogicalDataType::Boolean)))
 }
 
-impl ColumnType {
-    // The total size in bytes of the column
-    pub fn size(&self) -> u64 {
-        match &self {
-            ColumnType::Tag(c) => c.size(),
-            ColumnType::Field(c) => c.size(),
-            ColumnType::Time(c) => c.size(),
+/// A tree of predicates combined with logical AND and OR.
+///
+/// A slice of predicates, which is evaluated as their conjunction, can be
+/// converted into an `And` of `Leaf` expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PredicateExpr<'a> {
+    /// A single comparison on a column.
+    Leaf(Predicate<'a>),
+
+    /// A comparison between two columns.
+    Columns(ColumnPredicate<'a>),
+
+    /// Satisfied when all of the expressions are. An empty `And` is satisfied
+    /// by every row.
+    And(Vec<PredicateExpr<'a>>),
+
+    /// Satisfied when any of the expressions are. An empty `Or` is satisfied
+    /// by no rows.
+    Or(Vec<PredicateExpr<'a>>),
+}
+
+impl<'a> PredicateExpr<'a> {
+    /// Returns the conjunction of `self` and `other`, flattening any nested
+    /// `And` expressions.
+    pub fn and(self, other: PredicateExpr<'a>) -> Self {
+        let mut exprs = match self {
+            Self::And(exprs) => exprs,
+            expr => vec![expr],
+        };
+        match other {
+            Self::And(other) => exprs.extend(other),
+            expr => exprs.push(expr),
         }
+        Self::And(exprs)
     }
-}
 
-#[derive(Default, Debug)]
-struct MetaData {
+    /// Determines if the expression is satisfied by every row, i.e., it
+    /// contains no predicates.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Leaf(_) | Self::Columns(_) => false,
+            Self::And(exprs) => exprs.iter().all(Self::is_empty),
+            Self::Or(_) => false,
+        }
+    }
+
+    /// All of the predicates on a single column in the expression, in
+    /// depth-first order.
+    pub fn predicates(&self) -> Vec<Predicate<'a>> {
+        let mut predicates = vec![];
+        self.collect_predicates(&mut predicates, &mut vec![]);
+        predicates
+    }
+
+    /// All of the comparisons between two columns in the expression, in
+    /// depth-first order.
+    pub fn column_predicates(&self) -> Vec<ColumnPredicate<'a>> {
+        let mut predicates = vec![];
+        self.collect_predicates(&mut vec![], &mut predicates);
+        predicates
+    }
+
+    fn collect_predicates(
+        &self,
+        dst: &mut Vec<Predicate<'a>>,
+        columns_dst: &mut Vec<ColumnPredicate<'a>>,
+    ) {
+        match self {
+            Self::Leaf(predicate) => dst.push(*predicate),
+            Self::Columns(predicate) => columns_dst.push(*predicate),
+            Self::And(exprs) | Self::Or(exprs) => {
+                for expr in exprs {
+                    expr.collect_predicates(dst, columns_dst);
+                }
+            }
+        }
+    }
+
+    /// Determines if the expression could be satisfied, given a function that
+    /// determines if an individual predicate could be satisfied. Comparisons
+    /// between two columns are assumed to be satisfiable.
+    pub fn could_be_satisfied(&self, could_satisfy: &dyn Fn(&Predicate<'a>) -> bool) -> bool {
+        match self {
+            Self::Leaf(predicate) => could_satisfy(predicate),
+            Self::Columns(_) => true,
+            Self::And(exprs) => exprs
+                .iter()
+                .all(|expr| expr.could_be_satisfied(could_satisfy)),
+            Self::Or(exprs) => exprs
+                .iter()
+                .any(|expr| expr.could_be_satisfied(could_satisfy)),
+        }
+    }
+}
+
+impl<'a> From<&[Predicate<'a>]> for PredicateExpr<'a> {
+    fn from(predicates: &[Predicate<'a>]) -> Self {
+        Self::And(predicates.iter().copied().map(Self::Leaf).collect())
+    }
+}
+
+// The materialised values of a column being aggregated, and the positions of
+// the aggregates calculated from them.
+struct AggregateColumnData<'a> {
+    values: Values<'a>,
+    aggregates: Vec<usize>,
+}
+
+// Updates each aggregate with its column's value in `row`. Each column's value
+// is read once and used to update all of the aggregates on that column.
+// `timestamps` holds the timestamp of each row, and must be provided if any of
+// the aggregates are selectors.
+fn update_aggregates<'a>(
+    aggregates: &mut [AggregateResult<'a>],
+    aggregate_columns_data: &[AggregateColumnData<'a>],
+    timestamps: Option<&[i64]>,
+    row: usize,
+) {
+    for data in aggregate_columns_data {
+        let value = data.values.value(row);
+        for &i in &data.aggregates {
+            match timestamps {
+                Some(timestamps) => aggregates[i].update_with_time(value, timestamps[row]),
+                None => aggregates[i].update(value),
+            }
+        }
+    }
+}
+
+// Calculates an aggregate of the values of `column` in the rows `row_ids`.
+fn aggregate_rows<'a>(
+    column: &'a Column,
+    typ: &AggregateType,
+    row_ids: &[u32],
+    time_column: &'a Column,
+) -> AggregateResult<'a> {
+    match typ {
+        AggregateType::Count => AggregateResult::Count(column.count(row_ids) as u64),
+        AggregateType::First
+        | AggregateType::Last
+        | AggregateType::ArgMax
+        | AggregateType::ArgMin => {
+            let values = column.values(row_ids);
+            let timestamps = time_values(&time_column.values(row_ids));
+            let mut result = AggregateResult::from(typ);
+            for (row, &time) in timestamps.iter().enumerate() {
+                result.update_with_time(values.value(row), time);
+            }
+            result
+        }
+        AggregateType::Min => AggregateResult::Min(column.min(row_ids)),
+        AggregateType::Max => AggregateResult::Max(column.max(row_ids)),
+        AggregateType::Sum => AggregateResult::Sum(column.sum(row_ids)),
+        AggregateType::Percentile(_)
+        | AggregateType::ApproxQuantile(_)
+        | AggregateType::Mean
+        | AggregateType::StdDev
+        | AggregateType::Variance
+        | AggregateType::Histogram(_)
+        | AggregateType::CountTrue
+        | AggregateType::CountFalse => {
+            let values = column.values(row_ids);
+            let mut result = AggregateResult::from(typ);
+            for row in 0..values.len() {
+                result.update(values.value(row));
+            }
+            result
+        }
+        AggregateType::CountDistinct { .. } => {
+            // Only the distinct dictionary entries referenced by the rows are
+            // decoded, rather than every row's value.
+            let mut result = AggregateResult::from(typ);
+            match column.distinct_values(row_ids) {
+                ValueSet::String(values) => {
+                    for value in values {
+                        result.update(value.map_or(Value::Null, |v| Value::String(v.as_str())));
+                    }
+                }
+                ValueSet::ByteArray(_) => unreachable!("unsupported column type"),
+            }
+            result
+        }
+    }
+}
+
+// Converts the materialised values of the time column into timestamps.
+fn time_values(values: &Values<'_>) -> Vec<i64> {
+    (0..values.len())
+        .map(|row| match values.value(row) {
+            Value::Scalar(Scalar::I64(time)) => time,
+            v => unreachable!("invalid timestamp {:?}", v),
+        })
+        .collect()
+}
+
+/// A predicate applied to the values of a column being aggregated, rather
+/// than to the rows of the `RowGroup`.
+pub type ValuePredicate<'a> = (Operator, Value<'a>);
+
+// Determines if a value satisfies the comparison `value op rhs`. As in SQL,
+// comparing a NULL value is unknown, so NULL values only satisfy `IS NULL`.
+// For regular expression operators `regex` is the compiled `rhs` pattern(s),
+// and only string values can match.
+fn value_satisfies_predicate(
+    value: &Value<'_>,
+    op: &Operator,
+    rhs: &Value<'_>,
+    regex: Option<&RegexSet>,
+) -> bool {
+    if value.is_null() {
+        return matches!(op, Operator::IsNull);
+    }
+
+    // Scalars of different types, such as an integer predicate value and a
+    // float column, are compared by their numerical values.
+    let ordering = match (value, rhs) {
+        (Value::Scalar(a), Value::Scalar(b)) => a.compare(b),
+        _ => value.partial_cmp(rhs),
+    };
+    match op {
+        Operator::Equal => ordering == Some(Ordering::Equal),
+        Operator::NotEqual => ordering != Some(Ordering::Equal),
+        Operator::GT => ordering == Some(Ordering::Greater),
+        Operator::GTE => matches!(ordering, Some(Ordering::Greater) | Some(Ordering::Equal)),
+        Operator::LT => ordering == Some(Ordering::Less),
+        Operator::LTE => matches!(ordering, Some(Ordering::Less) | Some(Ordering::Equal)),
+        Operator::RegexMatch | Operator::RegexNotMatch | Operator::RegexMatchAny => {
+            match (value, regex) {
+                (Value::String(v), Some(regex)) => regex.is_match(v) == op.regex_includes_matches(),
+                _ => false,
+            }
+        }
+        Operator::IsNull => false,
+        Operator::IsNotNull => true,
+    }
+}
+
+// Replaces any values that do not satisfy the value predicate `op rhs` with
+// NULL. Non-nullable variants are converted into their nullable equivalents.
+fn apply_value_predicate<'a>(values: Values<'a>, op: &Operator, rhs: &Value<'_>) -> Values<'a> {
+    // Compile any regular expression once, rather than for each value.
+    let regex = match rhs {
+        Value::String(pattern) if op.is_regex() => op.compile_regex(pattern).ok(),
+        _ => None,
+    };
+    let keep = |v: Value<'_>| value_satisfies_predicate(&v, op, rhs, regex.as_ref());
+
+    match values {
+        Values::String(arr) => Values::String(
+            arr.into_iter()
+                .map(|v| v.filter(|&v| keep(Value::String(v))))
+                .collect(),
+        ),
+        Values::I64(arr) => Values::I64N(
+            arr.into_iter()
+                .map(|v| Some(v).filter(|&v| keep(Value::from(v))))
+                .collect(),
+        ),
+        Values::U64(arr) => Values::U64N(
+            arr.into_iter()
+                .map(|v| Some(v).filter(|&v| keep(Value::from(v))))
+                .collect(),
+        ),
+        Values::F64(arr) => Values::F64N(
+            arr.into_iter()
+                .map(|v| Some(v).filter(|&v| keep(Value::from(v))))
+                .collect(),
+        ),
+        Values::I64N(arr) => Values::I64N(
+            arr.into_iter()
+                .map(|v| v.filter(|&v| keep(Value::from(v))))
+                .collect(),
+        ),
+        Values::U64N(arr) => Values::U64N(
+            arr.into_iter()
+                .map(|v| v.filter(|&v| keep(Value::from(v))))
+                .collect(),
+        ),
+        Values::F64N(arr) => Values::F64N(
+            arr.into_iter()
+                .map(|v| v.filter(|&v| keep(Value::from(v))))
+                .collect(),
+        ),
+        Values::Bool(arr) => Values::Bool(
+            arr.into_iter()
+                .map(|v| v.filter(|&v| keep(Value::Boolean(v))))
+                .collect(),
+        ),
+        Values::ByteArray(arr) => Values::ByteArray(
+            arr.into_iter()
+                .map(|v| v.filter(|&v| keep(Value::ByteArray(v))))
+                .collect(),
+        ),
+    }
+}
+
+// A GroupKey is an ordered collection of row values. The order determines which
+// columns the values originated from.
+#[derive(PartialEq, PartialOrd, Clone)]
+pub struct GroupKey<'row_group>(Vec<Value<'row_group>>);
+
+impl<'row_group> GroupKey<'row_group> {
+    /// The column-wise values making up the group key.
+    pub fn values(&self) -> &[Value<'row_group>] {
+        &self.0
+    }
+}
+
+impl<'row_group> From<Vec<Value<'row_group>>> for GroupKey<'row_group> {
+    fn from(values: Vec<Value<'row_group>>) -> Self {
+        Self(values)
+    }
+}
+
+impl Eq for GroupKey<'_> {}
+
+// Implementing the `Ord` trait on `GroupKey` means that collections of group
+// keys become sortable. This is typically useful for test because depending on
+// the implementation, group keys are not always emitted in sorted order.
+//
+// To be compared, group keys *must* have the same length, or `cmp` will panic.
+// They will be ordered as follows:
+//
+//    [foo, zoo, zoo], [foo, bar, zoo], [bar, bar, bar], [bar, bar, zoo],
+//
+//    becomes:
+//
+//    [bar, bar, bar], [bar, bar, zoo], [foo, bar, zoo], [foo, zoo, zoo],
+//
+// Be careful sorting group keys in result sets, because other columns
+// associated with the group keys won't be sorted unless the correct `sort`
+// methods are used on the result set implementations.
+impl Ord for GroupKey<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // two group keys must have same length
+        assert_eq!(self.0.len(), other.0.len());
+
+        let cols = self.0.len();
+        for i in 0..cols {
+            match self.0[i].partial_cmp(&other.0[i]) {
+                Some(std::cmp::Ordering::Equal) | None => continue,
+                Some(ord) => return ord,
+            }
+        }
+
+        std::cmp::Ordering::Equal
+    }
+}
+
+// A representation of a column name.
+pub type ColumnName<'a> = &'a str;
+
+// Builds a column from a range of values in an arrow array. Numeric columns
+// containing NULL values in the range use a nullable encoding.
+fn column_from_array(name: &str, array: &ArrayRef, rows: Range<usize>) -> Result<Column> {
+    let unsupported = || UnsupportedColumnType {
+        column_name: name,
+        data_type: array.data_type().clone(),
+    };
+
+    // Collects the values in the range, with NULL values represented by
+    // `None`.
+    macro_rules! nullable_values {
+        ($arr:expr) => {
+            rows.clone()
+                .map(|i| {
+                    if $arr.is_null(i) {
+                        None
+                    } else {
+                        Some($arr.value(i))
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+    }
+
+    // Builds a nullable column if there are NULL values in the range, and
+    // otherwise the most compact non-null encoding of the values.
+    macro_rules! numeric_column {
+        ($array_type:ty) => {{
+            let arr = array
+                .as_any()
+                .downcast_ref::<$array_type>()
+                .with_context(unsupported)?;
+            if rows.clone().any(|i| arr.is_null(i)) {
+                Column::from(<$array_type>::from(nullable_values!(arr)))
+            } else {
+                let values = rows.clone().map(|i| arr.value(i)).collect::<Vec<_>>();
+                Column::from(values.as_slice())
+            }
+        }};
+    }
+
+    Ok(match array.data_type() {
+        DataType::Utf8 => {
+            let arr = array
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .with_context(unsupported)?;
+            Column::from(nullable_values!(arr).as_slice())
+        }
+        DataType::Int64 => numeric_column!(Int64Array),
+        DataType::UInt64 => numeric_column!(UInt64Array),
+        DataType::Float64 => numeric_column!(Float64Array),
+        DataType::Boolean => {
+            let arr = array
+                .as_any()
+                .downcast_ref::<BooleanArray>()
+                .with_context(unsupported)?;
+            Column::from(nullable_values!(arr).as_slice())
+        }
+        _ => return unsupported().fail(),
+    })
+}
+
+/// The logical type that a column could have.
+pub enum ColumnType {
+    Tag(Column),
+    Field(Column),
+    Time(Column),
+}
+
+impl ColumnType {
+    // The total size in bytes of the column
+    pub fn size(&self) -> u64 {
+        match &self {
+            ColumnType::Tag(c) => c.size(),
+            ColumnType::Field(c) => c.size(),
+            ColumnType::Time(c) => c.size(),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+struct MetaData {
     // The total size of the table in bytes.
     size: u64,
 
@@ -902,7 +1898,7 @@ struct MetaData {
     //
     // This can be used to skip the table entirely if a logical predicate can't
     // possibly match based on the range of values a column has.
-    column_ranges: BTreeMap<String, (OwnedValue, OwnedValue)>,
+    column_ranges: BTreeMap<String, Option<(OwnedValue, OwnedValue)>>,
 
     // The total time range of this table spanning all of the `RowGroup`s within
     // the table.
@@ -922,37 +1918,83 @@ impl MetaData {
         column_name: ColumnName<'_>,
         predicate: &(Operator, Value<'_>),
     ) -> bool {
-        let (column_min, column_max) = match self.column_ranges.get(column_name) {
-            Some(range) => range,
-            None => return false, // column doesn't exist.
-        };
+        match self.column_ranges.get(column_name) {
+            Some(range) => range_could_satisfy_predicate(range.as_ref(), predicate),
+            None => false, // column doesn't exist.
+        }
+    }
+}
 
-        let (op, value) = predicate;
-        match op {
-            // If the column range covers the value then it could contain that
-            // value.
-            Operator::Equal => column_min <= value && column_max >= value,
+/// Determines if a column with the provided range of non-null values could
+/// contain a value satisfying the predicate. If this function returns `false`
+/// then no rows in the column can satisfy the predicate. A column without a
+/// range only contains NULL values.
+pub fn range_could_satisfy_predicate(
+    range: Option<&(OwnedValue, OwnedValue)>,
+    predicate: &(Operator, Value<'_>),
+) -> bool {
+    let (op, value) = predicate;
+
+    // the range says nothing about how a NULL value compares.
+    if matches!(value, Value::Null | Value::Scalar(Scalar::Null)) {
+        return true;
+    }
 
-            // If every value in the column is equal to "value" then this will
-            // be false, otherwise it must be satisfied
-            Operator::NotEqual => (column_min != column_max) || column_max != value,
+    // no non-null value can satisfy the predicate.
+    let (column_min, column_max) = match range {
+        Some(range) => range,
+        None => return false,
+    };
+
+    // Scalars of different types, such as an integer predicate value and a
+    // float column, are compared by their numerical values.
+    let compare = |column_value: &OwnedValue| match (column_value, value) {
+        (OwnedValue::Scalar(a), Value::Scalar(b)) => a.compare(b),
+        _ => column_value.partial_cmp(value),
+    };
+    let (min_ordering, max_ordering) = (compare(column_min), compare(column_max));
+
+    match op {
+        // If the column range covers the value then it could contain that
+        // value.
+        Operator::Equal => {
+            matches!(min_ordering, Some(Ordering::Less) | Some(Ordering::Equal))
+                && matches!(
+                    max_ordering,
+                    Some(Ordering::Greater) | Some(Ordering::Equal)
+                )
+        }
 
-            // if the column max is larger than value then the column could
-            // contain the value.
-            Operator::GT => column_max > value,
+        // If every value in the column is equal to "value" then this will
+        // be false, otherwise it must be satisfied
+        Operator::NotEqual => (column_min != column_max) || max_ordering != Some(Ordering::Equal),
 
-            // if the column max is at least as large as `value` then the column
-            // could contain the value.
-            Operator::GTE => column_max >= value,
+        // if the column max is larger than value then the column could
+        // contain the value.
+        Operator::GT => max_ordering == Some(Ordering::Greater),
 
-            // if the column min is smaller than value then the column could
-            // contain the value.
-            Operator::LT => column_min < value,
+        // if the column max is at least as large as `value` then the column
+        // could contain the value.
+        Operator::GTE => matches!(
+            max_ordering,
+            Some(Ordering::Greater) | Some(Ordering::Equal)
+        ),
 
-            // if the column min is at least as small as value then the column
-            // could contain the value.
-            Operator::LTE => column_min <= value,
-        }
+        // if the column min is smaller than value then the column could
+        // contain the value.
+        Operator::LT => min_ordering == Some(Ordering::Less),
+
+        // if the column min is at least as small as value then the column
+        // could contain the value.
+        Operator::LTE => matches!(min_ordering, Some(Ordering::Less) | Some(Ordering::Equal)),
+
+        // the column range can't rule out values matching a regular
+        // expression.
+        Operator::RegexMatch | Operator::RegexNotMatch | Operator::RegexMatchAny => true,
+
+        // the range only describes the non-null values in the column, so it
+        // can't rule out either.
+        Operator::IsNull | Operator::IsNotNull => true,
     }
 }
 
@@ -960,10 +2002,23 @@ impl MetaData {
 /// easier to work with and display.
 pub struct ReadFilterResult<'row_group>(pub Vec<(ColumnName<'row_group>, Values<'row_group>)>);
 
-impl ReadFilterResult<'_> {
+impl<'row_group> ReadFilterResult<'row_group> {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// The number of rows in the result.
+    pub fn rows(&self) -> usize {
+        self.0.first().map_or(0, |(_, values)| values.len())
+    }
+
+    /// The values for the named column, if it was selected.
+    pub fn column(&self, name: ColumnName<'_>) -> Option<&Values<'row_group>> {
+        self.0
+            .iter()
+            .find(|(column, _)| *column == name)
+            .map(|(_, values)| values)
+    }
 }
 
 impl std::fmt::Debug for &ReadFilterResult<'_> {
@@ -1035,7 +2090,7 @@ pub struct ReadGroupResult<'row_group> {
     aggregates: Vec<Vec<AggregateResult<'row_group>>>,
 }
 
-impl ReadGroupResult<'_> {
+impl<'row_group> ReadGroupResult<'row_group> {
     pub fn is_empty(&self) -> bool {
         self.group_keys.is_empty()
     }
@@ -1045,6 +2100,40 @@ impl ReadGroupResult<'_> {
         self.group_keys.len()
     }
 
+    /// The row-wise group keys in the result.
+    pub fn group_keys(&self) -> &[GroupKey<'row_group>] {
+        &self.group_keys
+    }
+
+    /// The row-wise aggregates in the result. Each row contains an aggregate
+    /// for each of the aggregate columns.
+    pub fn aggregates(&self) -> &[Vec<AggregateResult<'row_group>>] {
+        &self.aggregates
+    }
+
+    /// Inserts a NULL value into each group key at each of `null_groups`, and
+    /// an aggregate of no values of each type in `empty_aggregates` into each
+    /// row of aggregates at its position. This is used to add columns that
+    /// are missing from the row group to the result. Positions are in the
+    /// padded result, and must be in ascending order.
+    pub fn pad(&mut self, null_groups: &[usize], empty_aggregates: &[(usize, AggregateType)]) {
+        if null_groups.is_empty() && empty_aggregates.is_empty() {
+            return;
+        }
+
+        for key in &mut self.group_keys {
+            for &i in null_groups {
+                key.0.insert(i, Value::Null);
+            }
+        }
+
+        for aggregates in &mut self.aggregates {
+            for (i, typ) in empty_aggregates {
+                aggregates.insert(*i, AggregateResult::from(typ));
+            }
+        }
+    }
+
     /// Executes a mutable sort of the rows in the result set based on the
     /// lexicographic order of each group key column. This is useful for testing
     /// because it allows you to compare `read_group` results.
@@ -1132,6 +2221,27 @@ pub fn build_predicates_with_time(
     arr
 }
 
+// Records the columns materialised by reads on each thread, so that tests can
+// check that reads only materialise the columns they return.
+#[cfg(test)]
+pub(crate) mod materialised_columns {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static COLUMNS: RefCell<Vec<String>> = RefCell::new(vec![]);
+    }
+
+    pub fn record(column_name: &str) {
+        COLUMNS.with(|columns| columns.borrow_mut().push(column_name.to_owned()));
+    }
+
+    /// Returns the names of the columns materialised on this thread since the
+    /// last call, in the order they were materialised.
+    pub fn take() -> Vec<String> {
+        COLUMNS.with(|columns| columns.borrow_mut().drain(..).collect())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1204,6 +2314,240 @@ mod test {
         assert!(matches!(row_ids, RowIDsOption::All(_)));
     }
 
+    #[test]
+    fn row_ids_from_expr() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[100_i64, 200, 500, 600, 300, 300][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "south", "north"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+        let row_group = RowGroup::new(6, columns);
+
+        let region = |name| PredicateExpr::Leaf(("region", (Operator::Equal, Value::String(name))));
+
+        // Disjunction of two equality predicates
+        let expr = PredicateExpr::Or(vec![region("east"), region("south")]);
+        let row_ids = row_group.row_ids_from_expr(&expr);
+        assert_eq!(row_ids.unwrap().to_vec(), vec![2, 4]);
+
+        // Disjunction within a time range
+        let expr = PredicateExpr::from(&build_predicates_with_time(200, 600, vec![])[..])
+            .and(PredicateExpr::Or(vec![region("west"), region("north")]));
+        let row_ids = row_group.row_ids_from_expr(&expr);
+        assert_eq!(row_ids.unwrap().to_vec(), vec![1, 5]);
+
+        // Disjunction matching all rows
+        let expr = PredicateExpr::Or(vec![
+            region("west"),
+            PredicateExpr::Leaf(("region", (Operator::NotEqual, Value::String("west")))),
+        ]);
+        assert!(matches!(
+            row_group.row_ids_from_expr(&expr),
+            RowIDsOption::All(_)
+        ));
+
+        // Disjunction where no predicates match
+        let expr = PredicateExpr::Or(vec![region("nope"), region("also nope")]);
+        assert!(matches!(
+            row_group.row_ids_from_expr(&expr),
+            RowIDsOption::None(_)
+        ));
+
+        // Predicates on missing columns match no rows, which doesn't prevent
+        // the other side of a disjunction matching.
+        let expr = PredicateExpr::Or(vec![
+            region("east"),
+            PredicateExpr::Leaf(("host", (Operator::Equal, Value::String("a")))),
+        ]);
+        let row_ids = row_group.row_ids_from_expr(&expr);
+        assert_eq!(row_ids.unwrap().to_vec(), vec![2]);
+
+        // Nested conjunction within a disjunction
+        let expr = PredicateExpr::Or(vec![
+            region("east"),
+            PredicateExpr::And(vec![
+                region("west"),
+                PredicateExpr::Leaf((
+                    TIME_COLUMN_NAME,
+                    (Operator::GT, Value::Scalar(Scalar::I64(100))),
+                )),
+            ]),
+        ]);
+        let row_ids = row_group.row_ids_from_expr(&expr);
+        assert_eq!(row_ids.unwrap().to_vec(), vec![1, 2, 3]);
+
+        // Empty conjunctions match all rows and empty disjunctions match none.
+        assert!(matches!(
+            row_group.row_ids_from_expr(&PredicateExpr::And(vec![])),
+            RowIDsOption::All(_)
+        ));
+        assert!(matches!(
+            row_group.row_ids_from_expr(&PredicateExpr::Or(vec![])),
+            RowIDsOption::None(_)
+        ));
+    }
+
+    #[test]
+    fn read_filter_column_predicate() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "north", "south"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+        let sc = ColumnType::Field(Column::from(&[20.5, 18.0, 22.1, 15.0, 19.5][..]));
+        columns.insert("surface_degrees".to_string(), sc);
+        let bc = ColumnType::Field(Column::from(&[19.0, 18.0, 23.4, 11.2, 19.0][..]));
+        columns.insert("bottom_degrees".to_string(), bc);
+        let cc = ColumnType::Field(Column::from(&[19_u64, 25, 20, 10, 21][..]));
+        columns.insert("count".to_string(), cc);
+        let row_group = RowGroup::new(5, columns);
+
+        let expr = PredicateExpr::Columns(("surface_degrees", Operator::GT, "bottom_degrees"));
+        let results = row_group.read_filter_expr(&["region", "time"], &expr);
+        assert_eq!(
+            format!("{:?}", &results),
+            "region,time
+west,1
+north,4
+south,5
+"
+        );
+
+        // Columns with different numeric types can be compared.
+        let expr = PredicateExpr::Columns(("count", Operator::LTE, "surface_degrees"));
+        let results = row_group.read_filter_expr(&["region"], &expr);
+        assert_eq!(
+            format!("{:?}", &results),
+            "region
+west
+east
+north
+"
+        );
+
+        // Combined with a literal predicate.
+        let expr = PredicateExpr::And(vec![
+            PredicateExpr::Columns(("surface_degrees", Operator::GT, "bottom_degrees")),
+            PredicateExpr::Leaf(("region", (Operator::Equal, Value::String("west")))),
+        ]);
+        let results = row_group.read_filter_expr(&["time"], &expr);
+        assert_eq!(
+            format!("{:?}", &results),
+            "time
+1
+"
+        );
+
+        // Incompatible and unknown columns match no rows.
+        let expr = PredicateExpr::Columns(("region", Operator::GT, "bottom_degrees"));
+        assert!(row_group.read_filter_expr(&["time"], &expr).is_empty());
+        let expr = PredicateExpr::Columns(("surface_degrees", Operator::GT, "nope"));
+        assert!(row_group.read_filter_expr(&["time"], &expr).is_empty());
+    }
+
+    #[test]
+    fn read_filter_materialises_selected_columns() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "south", "north"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+        let mc = ColumnType::Tag(Column::from(
+            &["GET", "POST", "POST", "POST", "PUT", "GET"][..],
+        ));
+        columns.insert("method".to_string(), mc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200, 203, 203, 10][..]));
+        columns.insert("count".to_string(), fc);
+        let row_group = RowGroup::new(6, columns);
+        materialised_columns::take();
+
+        // Columns referenced only by predicates aren't materialised.
+        let results = row_group.read_filter(
+            &["count"],
+            &build_predicates_with_time(
+                1,
+                6,
+                vec![
+                    ("region", (Operator::Equal, Value::String("west"))),
+                    ("method", (Operator::NotEqual, Value::String("GET"))),
+                ],
+            ),
+        );
+        assert_eq!(
+            format!("{:?}", &results),
+            "count
+101
+203
+"
+        );
+        assert_eq!(materialised_columns::take(), vec!["count"]);
+
+        let results = row_group.read_filter(&["method", "time"], &[]);
+        assert_eq!(results.rows(), 6);
+        assert_eq!(materialised_columns::take(), vec!["method", "time"]);
+
+        // Nothing is materialised if no rows match.
+        let results = row_group.read_filter(
+            &["count"],
+            &[("region", (Operator::Equal, Value::String("up")))],
+        );
+        assert!(results.is_empty());
+        assert!(materialised_columns::take().is_empty());
+    }
+
+    #[test]
+    fn read_filter_negative_timestamps() {
+        // Each time column spans the epoch and is encoded with a different
+        // physical type.
+        let cases: Vec<(Vec<i64>, i64, i64, &str)> = vec![
+            (vec![-100, -50, -1, 0, 50], -60, 1, "time\n-50\n-1\n0\n"),
+            (
+                vec![-3000, -500, 0, 500, 3000],
+                -1000,
+                1000,
+                "time\n-500\n0\n500\n",
+            ),
+            (
+                vec![-100_000, -500, 0, 70_000],
+                -100_000,
+                0,
+                "time\n-100000\n-500\n",
+            ),
+            (
+                vec![-1_000_000_000_000, -500, 1_000_000_000_000],
+                -1000,
+                1000,
+                "time\n-500\n",
+            ),
+        ];
+
+        for (times, from, to, expected) in cases {
+            let mut columns = BTreeMap::new();
+            let tc = ColumnType::Time(Column::from(times.as_slice()));
+            columns.insert("time".to_string(), tc);
+            let row_group = RowGroup::new(times.len() as u32, columns);
+            assert_eq!(row_group.time_range(), (times[0], times[times.len() - 1]));
+
+            let predicates = build_predicates_with_time(from, to, vec![]);
+            let results = row_group.read_filter(&["time"], &predicates);
+            assert_eq!(format!("{:?}", &results), expected, "{:?}", times);
+
+            // Each bound applied on its own.
+            let results = row_group.read_filter(&["time"], &predicates[..1]);
+            let expected = times.iter().filter(|&&t| t >= from).count();
+            assert_eq!(results.rows(), expected, "{:?}", times);
+            let results = row_group.read_filter(&["time"], &predicates[1..]);
+            let expected = times.iter().filter(|&&t| t < to).count();
+            assert_eq!(results.rows(), expected, "{:?}", times);
+        }
+    }
+
     #[test]
     fn read_filter() {
         let mut columns = BTreeMap::new();
@@ -1300,6 +2644,40 @@ west,4
         );
         let expected = "";
         assert!(results.is_empty());
+
+        // test mixing equality and comparison predicates
+        let results = row_group.read_filter(
+            &["count", "region", "time"],
+            &build_predicates_with_time(
+                0,
+                7,
+                vec![
+                    ("region", (Operator::Equal, Value::String("west"))),
+                    ("count", (Operator::GT, Value::Scalar(Scalar::U64(100)))),
+                ],
+            ),
+        );
+        assert_eq!(
+            format!("{:?}", &results),
+            "count,region,time
+101,west,2
+203,west,4
+"
+        );
+
+        // test predicates that each match rows, but not the same rows
+        let results = row_group.read_filter(
+            &["count", "region", "time"],
+            &build_predicates_with_time(
+                0,
+                7,
+                vec![
+                    ("region", (Operator::Equal, Value::String("east"))),
+                    ("count", (Operator::LT, Value::Scalar(Scalar::I64(150)))),
+                ],
+            ),
+        );
+        assert!(results.is_empty());
     }
 
     #[test]
@@ -1460,16 +2838,44 @@ west,POST,prod,Bravo,two,203
 
     // the read_group path where grouping is on a single column.
     fn read_group_single_groupby_column(row_group: &RowGroup) {
-        let cases = vec![(
-            build_predicates_with_time(0, 7, vec![]), // all time but with explicit pred
-            vec!["method"],
-            vec![("counter", AggregateType::Sum)],
-            "method,counter_sum
+        let cases = vec![
+            (
+                build_predicates_with_time(0, 7, vec![]), // all time but with explicit pred
+                vec!["method"],
+                vec![("counter", AggregateType::Sum)],
+                "method,counter_sum
 GET,110
 POST,504
 PUT,203
 ",
-        )];
+            ),
+            (
+                build_predicates_with_time(0, 7, vec![]),
+                vec!["method"],
+                vec![
+                    ("counter", AggregateType::First),
+                    ("counter", AggregateType::Last),
+                ],
+                "method,counter_first,counter_last
+GET,100,10
+POST,101,203
+PUT,203,203
+",
+            ),
+            (
+                build_predicates_with_time(0, 7, vec![]),
+                vec!["method"],
+                vec![
+                    ("env", AggregateType::CountDistinct { count_null: false }),
+                    ("env", AggregateType::CountDistinct { count_null: true }),
+                ],
+                "method,env_count_distinct,env_count_distinct
+GET,1,2
+POST,2,2
+PUT,0,1
+",
+            ),
+        ];
 
         for (predicate, group_cols, aggs, expected) in cases {
             let mut results = row_group.read_group(&predicate, &group_cols, &aggs);
@@ -1528,6 +2934,33 @@ north,GET,10,10,10
 south,PUT,203,203,203
 west,GET,100,100,100
 west,POST,304,101,203
+",
+            ),
+            (
+                vec![],
+                vec!["method"],
+                vec![
+                    ("counter", AggregateType::First),
+                    ("counter", AggregateType::Last),
+                ],
+                "method,counter_first,counter_last
+GET,100,10
+POST,101,203
+PUT,203,203
+",
+            ),
+            (
+                vec![],
+                vec!["method"],
+                vec![
+                    ("region", AggregateType::CountDistinct { count_null: false }),
+                    ("env", AggregateType::CountDistinct { count_null: false }),
+                    ("env", AggregateType::CountDistinct { count_null: true }),
+                ],
+                "method,region_count_distinct,env_count_distinct,env_count_distinct
+GET,2,1,2
+POST,2,2,2
+PUT,1,0,1
 ",
             ),
         ];
@@ -1538,6 +2971,206 @@ west,POST,304,101,203
         }
     }
 
+    #[test]
+    fn read_group_multiple_aggregates_on_column() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5, 6, 7][..]));
+        columns.insert("time".to_string(), tc);
+
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "east", "east", "north"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+
+        let mc = ColumnType::Tag(Column::from(
+            &["GET", "POST", "POST", "GET", "GET", "POST", "GET"][..],
+        ));
+        columns.insert("method".to_string(), mc);
+
+        let fc = ColumnType::Field(Column::from(
+            &[10.5_f64, 3.2, 0.0, 10.5, -1.0, 7.7, 2.0][..],
+        ));
+        columns.insert("temp".to_string(), fc);
+
+        let row_group = RowGroup::new(7, columns);
+
+        let aggregates = vec![
+            ("temp", AggregateType::Min),
+            ("temp", AggregateType::Max),
+            ("temp", AggregateType::Sum),
+            ("temp", AggregateType::Count),
+            ("region", AggregateType::Count),
+            ("temp", AggregateType::First),
+            ("temp", AggregateType::ArgMax),
+            ("temp", AggregateType::Variance),
+        ];
+
+        // The single pass over each column must produce the same aggregates
+        // as calculating each aggregate separately, on each read_group path.
+        let cases = vec![
+            (vec![], vec!["region"]),
+            (vec![], vec!["region", "method"]),
+            (build_predicates_with_time(0, 8, vec![]), vec!["region"]),
+            (
+                build_predicates_with_time(2, 7, vec![]),
+                vec!["region", "method"],
+            ),
+        ];
+
+        for (predicates, group_cols) in cases {
+            let mut results = row_group.read_group(&predicates, &group_cols, &aggregates);
+            results.sort();
+
+            for (i, aggregate) in aggregates.iter().enumerate() {
+                let mut expected =
+                    row_group.read_group(&predicates, &group_cols, &[aggregate.clone()]);
+                expected.sort();
+
+                assert!(results.group_keys() == expected.group_keys());
+                for (row, expected_aggregates) in expected.aggregates().iter().enumerate() {
+                    assert_eq!(
+                        results.aggregates()[row][i].to_string(),
+                        expected_aggregates[0].to_string(),
+                        "{:?} on {:?} grouped by {:?}",
+                        aggregate,
+                        predicates,
+                        group_cols
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn read_group_with_value_predicates() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "east", "east"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+
+        let temps = [-10_i64, 20, 30, -5, 15, 40];
+        let fc = ColumnType::Field(Column::from(&temps[..]));
+        columns.insert("temp".to_string(), fc);
+
+        let row_group = RowGroup::new(6, columns);
+
+        // the mean of only positive temperatures in each region
+        let aggregates = vec![("temp", AggregateType::Sum), ("temp", AggregateType::Count)];
+        let positive = Some((Operator::GT, Value::from(0_i64)));
+        let value_predicates = vec![positive, positive];
+
+        let mut results = row_group.read_group_with_value_predicates(
+            &build_predicates_with_time(0, 7, vec![]),
+            &["region"],
+            &aggregates,
+            &value_predicates,
+        );
+        results.sort();
+        assert_eq!(
+            format!("{:?}", &results),
+            "region,temp_sum,temp_count
+east,85,3
+west,20,1
+",
+        );
+
+        let regions = ["west", "west", "east", "west", "east", "east"];
+        for (key, aggs) in results.group_keys.iter().zip(results.aggregates.iter()) {
+            let region = key.0[0].string();
+            let expected = temps
+                .iter()
+                .zip(regions.iter())
+                .filter(|(temp, r)| **r == region && **temp > 0)
+                .map(|(temp, _)| *temp)
+                .collect::<Vec<_>>();
+            let expected_mean = expected.iter().sum::<i64>() as f64 / expected.len() as f64;
+
+            let mean = match (&aggs[0], &aggs[1]) {
+                (AggregateResult::Sum(Scalar::I64(sum)), AggregateResult::Count(count)) => {
+                    *sum as f64 / *count as f64
+                }
+                (sum, count) => panic!("unexpected aggregates {:?} {:?}", sum, count),
+            };
+            assert!((mean - expected_mean).abs() < f64::EPSILON);
+        }
+
+        // without value predicates all values are aggregated.
+        let mut results = row_group.read_group(
+            &build_predicates_with_time(0, 7, vec![]),
+            &["region"],
+            &aggregates,
+        );
+        results.sort();
+        assert_eq!(
+            format!("{:?}", &results),
+            "region,temp_sum,temp_count
+east,85,3
+west,5,3
+",
+        );
+    }
+
+    #[test]
+    fn read_group_window_expr() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[-3_i64, 1, 2, 4, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "east", "east"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+
+        let fc = ColumnType::Field(Column::from(&[-10_i64, 20, 30, -5, 15, 40][..]));
+        columns.insert("temp".to_string(), fc);
+
+        let row_group = RowGroup::new(6, columns);
+
+        // rows are grouped by the start of the 4ns bucket they fall in, which
+        // is aligned to the epoch even for negative timestamps.
+        let predicates = build_predicates_with_time(-10, 10, vec![]);
+        let mut results = row_group.read_group_window_expr(
+            &PredicateExpr::from(predicates.as_slice()),
+            &["region"],
+            &[("temp", AggregateType::Sum)],
+            &[],
+            4,
+        );
+        results.sort();
+        assert_eq!(
+            format!("{:?}", &results),
+            "region,time,temp_sum
+east,0,30
+east,4,55
+west,-4,-10
+west,0,20
+west,4,-5
+",
+        );
+
+        // the bucket is the only group column when none are requested.
+        let mut results = row_group.read_group_window_expr(
+            &PredicateExpr::from(predicates.as_slice()),
+            &[],
+            &[("temp", AggregateType::Count)],
+            &[],
+            4,
+        );
+        results.sort();
+        assert_eq!(
+            format!("{:?}", &results),
+            "time,temp_count
+-4,1
+0,2
+4,3
+",
+        );
+    }
+
     #[test]
     fn row_group_could_satisfy_predicate() {
         let mut columns = BTreeMap::new();
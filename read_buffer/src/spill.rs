@@ -0,0 +1,523 @@
+//! Support for spilling partial aggregate state out of memory.
+//!
+//! When a group-by query produces more groups than fit in its memory budget,
+//! the partial aggregates for the groups are encoded into "runs" and written
+//! to a `SpillStore`. The runs are later decoded and merged back together.
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::io;
+
+use bytes::{Bytes, BytesMut};
+use futures::{executor::block_on, stream, TryStreamExt};
+use object_store::ObjectStore;
+
+use crate::column::{AggregateResult, AggregateType, Scalar, Value, VarianceState};
+use crate::row_group::GroupKey;
+use crate::tdigest::TDigest;
+
+/// A generic error for spill store implementations.
+pub type StoreError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Storage for partial aggregate state that doesn't fit in memory, for example
+/// an object store.
+pub trait SpillStore {
+    /// Stores `data` at `location`, replacing anything already stored there.
+    fn put(&self, location: &str, data: Vec<u8>) -> Result<(), StoreError>;
+
+    /// Returns the data stored at `location`.
+    fn get(&self, location: &str) -> Result<Vec<u8>, StoreError>;
+
+    /// Removes the data stored at `location`.
+    fn delete(&self, location: &str) -> Result<(), StoreError>;
+}
+
+/// Spills to an object store. Each request is driven to completion on the
+/// calling thread, so queries that spill to an object store should run
+/// outside of async tasks, for example via `tokio::task::spawn_blocking`.
+impl SpillStore for ObjectStore {
+    fn put(&self, location: &str, data: Vec<u8>) -> Result<(), StoreError> {
+        let len = data.len();
+        let data = io::Result::Ok(Bytes::from(data));
+        block_on(ObjectStore::put(
+            self,
+            location,
+            stream::once(async move { data }),
+            len,
+        ))?;
+        Ok(())
+    }
+
+    fn get(&self, location: &str) -> Result<Vec<u8>, StoreError> {
+        let data = block_on(async {
+            ObjectStore::get(self, location)
+                .await?
+                .map_ok(|b| BytesMut::from(&b[..]))
+                .try_concat()
+                .await
+        })?;
+        Ok(data.to_vec())
+    }
+
+    fn delete(&self, location: &str) -> Result<(), StoreError> {
+        block_on(ObjectStore::delete(self, location))?;
+        Ok(())
+    }
+}
+
+/// Configures how a group-by query spills partial aggregate state.
+pub struct SpillConfig<'a> {
+    /// The maximum number of groups held in memory before they are spilled.
+    pub max_groups: usize,
+
+    /// Where spilled state is written.
+    pub store: &'a dyn SpillStore,
+
+    /// A prefix for the locations of spilled state, which should be unique to
+    /// the query.
+    pub prefix: String,
+}
+
+impl SpillConfig<'_> {
+    /// The location of the spilled run with the provided index.
+    pub fn run_location(&self, run: usize) -> String {
+        format!("{}/run-{}", self.prefix, run)
+    }
+}
+
+// Value tags in the encoded format.
+const NULL: u8 = 0;
+const STRING: u8 = 1;
+const BYTE_ARRAY: u8 = 2;
+const BOOLEAN: u8 = 3;
+const I64: u8 = 4;
+const U64: u8 = 5;
+const F64: u8 = 6;
+const SCALAR_NULL: u8 = 7;
+
+/// Encodes a run of group keys and their partial aggregates.
+pub fn encode_run<'r, 'a: 'r>(
+    rows: impl Iterator<Item = (&'r GroupKey<'a>, &'r Vec<AggregateResult<'a>>)>,
+) -> Vec<u8> {
+    let mut buf = vec![];
+    for (group_key, aggregates) in rows {
+        for value in group_key.values() {
+            encode_value(&mut buf, value);
+        }
+
+        for aggregate in aggregates {
+            match aggregate {
+                AggregateResult::Count(v)
+                | AggregateResult::CountTrue(v)
+                | AggregateResult::CountFalse(v) => buf.extend_from_slice(&v.to_le_bytes()),
+                AggregateResult::Sum(v) => encode_value(&mut buf, &Value::Scalar(*v)),
+                AggregateResult::Min(v) | AggregateResult::Max(v) => encode_value(&mut buf, v),
+                AggregateResult::First(v)
+                | AggregateResult::Last(v)
+                | AggregateResult::ArgMax(v)
+                | AggregateResult::ArgMin(v) => match v {
+                    Some((ts, v)) => {
+                        buf.push(1);
+                        buf.extend_from_slice(&ts.to_le_bytes());
+                        encode_value(&mut buf, v);
+                    }
+                    None => buf.push(0),
+                },
+                AggregateResult::Percentile(_, values) => {
+                    buf.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                    for v in values {
+                        encode_value(&mut buf, &Value::Scalar(*v));
+                    }
+                }
+                AggregateResult::ApproxQuantile(_, digest) => {
+                    let centroids = digest.centroids();
+                    buf.extend_from_slice(&digest.min().to_bits().to_le_bytes());
+                    buf.extend_from_slice(&digest.max().to_bits().to_le_bytes());
+                    buf.extend_from_slice(&(centroids.len() as u32).to_le_bytes());
+                    for (mean, weight) in centroids {
+                        buf.extend_from_slice(&mean.to_bits().to_le_bytes());
+                        buf.extend_from_slice(&weight.to_bits().to_le_bytes());
+                    }
+                }
+                AggregateResult::Mean(state)
+                | AggregateResult::StdDev(state)
+                | AggregateResult::Variance(state) => {
+                    buf.extend_from_slice(&state.count().to_le_bytes());
+                    buf.extend_from_slice(&state.mean().to_bits().to_le_bytes());
+                    buf.extend_from_slice(&state.m2().to_bits().to_le_bytes());
+                }
+                AggregateResult::Histogram(_, counts) => {
+                    // The bucket bounds are part of the aggregate type, so
+                    // only the counts are encoded.
+                    buf.extend_from_slice(&(counts.len() as u32).to_le_bytes());
+                    for count in counts {
+                        buf.extend_from_slice(&count.to_le_bytes());
+                    }
+                }
+                AggregateResult::CountDistinct(_, values) => {
+                    buf.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                    for &v in values {
+                        encode_value(&mut buf, &v.map_or(Value::Null, Value::String));
+                    }
+                }
+            }
+        }
+    }
+    buf
+}
+
+/// Merges two runs of group keys and their partial aggregates, each sorted by
+/// group key, into a single run sorted by group key. The partial aggregates
+/// of group keys found in both runs are merged.
+pub fn merge_runs<'a>(
+    left: Vec<(GroupKey<'a>, Vec<AggregateResult<'a>>)>,
+    right: Vec<(GroupKey<'a>, Vec<AggregateResult<'a>>)>,
+) -> Vec<(GroupKey<'a>, Vec<AggregateResult<'a>>)> {
+    let mut merged = Vec::with_capacity(left.len().max(right.len()));
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    loop {
+        let ordering = match (left.peek(), right.peek()) {
+            (Some((l, _)), Some((r, _))) => l.cmp(r),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return merged,
+        };
+
+        match ordering {
+            Ordering::Less => merged.extend(left.next()),
+            Ordering::Greater => merged.extend(right.next()),
+            Ordering::Equal => {
+                if let (Some((group_key, mut aggregates)), Some((_, other))) =
+                    (left.next(), right.next())
+                {
+                    for (aggregate, other) in aggregates.iter_mut().zip(&other) {
+                        aggregate.merge(other);
+                    }
+                    merged.push((group_key, aggregates));
+                }
+            }
+        }
+    }
+}
+
+fn encode_value(buf: &mut Vec<u8>, value: &Value<'_>) {
+    match value {
+        Value::Null => buf.push(NULL),
+        Value::String(v) => {
+            buf.push(STRING);
+            encode_bytes(buf, v.as_bytes());
+        }
+        Value::ByteArray(v) => {
+            buf.push(BYTE_ARRAY);
+            encode_bytes(buf, v);
+        }
+        Value::Boolean(v) => {
+            buf.push(BOOLEAN);
+            buf.push(*v as u8);
+        }
+        Value::Scalar(Scalar::I64(v)) => {
+            buf.push(I64);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Scalar(Scalar::U64(v)) => {
+            buf.push(U64);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Scalar(Scalar::F64(v)) => {
+            buf.push(F64);
+            buf.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        Value::Scalar(Scalar::Null) => buf.push(SCALAR_NULL),
+    }
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// Decodes a run of group keys, each with `key_len` values, and their partial
+/// aggregates. Decoded strings and byte arrays borrow from `data`.
+///
+/// `None` is returned if the run is not valid.
+pub fn decode_run<'a>(
+    mut data: &'a [u8],
+    key_len: usize,
+    aggregates: &[AggregateType],
+) -> Option<Vec<(GroupKey<'a>, Vec<AggregateResult<'a>>)>> {
+    let mut rows = vec![];
+    while !data.is_empty() {
+        let mut key = Vec::with_capacity(key_len);
+        for _ in 0..key_len {
+            key.push(decode_value(&mut data)?);
+        }
+
+        let mut row_aggregates = Vec::with_capacity(aggregates.len());
+        for agg_type in aggregates {
+            row_aggregates.push(match agg_type {
+                AggregateType::Count => {
+                    AggregateResult::Count(u64::from_le_bytes(take_array(&mut data)?))
+                }
+                AggregateType::CountTrue => {
+                    AggregateResult::CountTrue(u64::from_le_bytes(take_array(&mut data)?))
+                }
+                AggregateType::CountFalse => {
+                    AggregateResult::CountFalse(u64::from_le_bytes(take_array(&mut data)?))
+                }
+                AggregateType::Sum => match decode_value(&mut data)? {
+                    Value::Scalar(v) => AggregateResult::Sum(v),
+                    _ => return None,
+                },
+                AggregateType::Min => AggregateResult::Min(decode_value(&mut data)?),
+                AggregateType::Max => AggregateResult::Max(decode_value(&mut data)?),
+                AggregateType::First => AggregateResult::First(decode_timestamped(&mut data)?),
+                AggregateType::Last => AggregateResult::Last(decode_timestamped(&mut data)?),
+                AggregateType::ArgMax => AggregateResult::ArgMax(decode_timestamped(&mut data)?),
+                AggregateType::ArgMin => AggregateResult::ArgMin(decode_timestamped(&mut data)?),
+                AggregateType::Percentile(p) => {
+                    let len = decode_len(&mut data)?;
+                    let mut values = Vec::with_capacity(len);
+                    for _ in 0..len {
+                        match decode_value(&mut data)? {
+                            Value::Scalar(v) => values.push(v),
+                            _ => return None,
+                        }
+                    }
+                    AggregateResult::Percentile(*p, values)
+                }
+                AggregateType::ApproxQuantile(p) => {
+                    let min = decode_f64(&mut data)?;
+                    let max = decode_f64(&mut data)?;
+                    let len = decode_len(&mut data)?;
+                    let mut centroids = Vec::with_capacity(len);
+                    for _ in 0..len {
+                        centroids.push((decode_f64(&mut data)?, decode_f64(&mut data)?));
+                    }
+                    AggregateResult::ApproxQuantile(
+                        *p,
+                        TDigest::from_centroids(centroids, min, max),
+                    )
+                }
+                AggregateType::Mean => AggregateResult::Mean(decode_variance(&mut data)?),
+                AggregateType::StdDev => AggregateResult::StdDev(decode_variance(&mut data)?),
+                AggregateType::Variance => AggregateResult::Variance(decode_variance(&mut data)?),
+                AggregateType::Histogram(bounds) => {
+                    let len = decode_len(&mut data)?;
+                    if len != bounds.len() + 1 {
+                        return None;
+                    }
+                    let mut counts = Vec::with_capacity(len);
+                    for _ in 0..len {
+                        counts.push(u64::from_le_bytes(take_array(&mut data)?));
+                    }
+                    AggregateResult::Histogram(bounds.clone(), counts)
+                }
+                AggregateType::CountDistinct { count_null } => {
+                    let len = decode_len(&mut data)?;
+                    let mut values = BTreeSet::new();
+                    for _ in 0..len {
+                        match decode_value(&mut data)? {
+                            Value::String(v) => values.insert(Some(v)),
+                            Value::Null => values.insert(None),
+                            _ => return None,
+                        };
+                    }
+                    AggregateResult::CountDistinct(*count_null, values)
+                }
+            });
+        }
+
+        rows.push((GroupKey::from(key), row_aggregates));
+    }
+    Some(rows)
+}
+
+fn decode_timestamped<'a>(data: &mut &'a [u8]) -> Option<Option<(i64, Value<'a>)>> {
+    match take(data, 1)?[0] {
+        0 => Some(None),
+        _ => {
+            let ts = i64::from_le_bytes(take_array(data)?);
+            Some(Some((ts, decode_value(data)?)))
+        }
+    }
+}
+
+fn decode_variance(data: &mut &[u8]) -> Option<VarianceState> {
+    let count = u64::from_le_bytes(take_array(data)?);
+    Some(VarianceState::new(
+        count,
+        decode_f64(data)?,
+        decode_f64(data)?,
+    ))
+}
+
+fn decode_value<'a>(data: &mut &'a [u8]) -> Option<Value<'a>> {
+    Some(match take(data, 1)?[0] {
+        NULL => Value::Null,
+        STRING => Value::String(std::str::from_utf8(decode_bytes(data)?).ok()?),
+        BYTE_ARRAY => Value::ByteArray(decode_bytes(data)?),
+        BOOLEAN => Value::Boolean(take(data, 1)?[0] != 0),
+        I64 => Value::Scalar(Scalar::I64(i64::from_le_bytes(take_array(data)?))),
+        U64 => Value::Scalar(Scalar::U64(u64::from_le_bytes(take_array(data)?))),
+        F64 => Value::Scalar(Scalar::F64(f64::from_bits(u64::from_le_bytes(take_array(
+            data,
+        )?)))),
+        SCALAR_NULL => Value::Scalar(Scalar::Null),
+        _ => return None,
+    })
+}
+
+fn decode_bytes<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = decode_len(data)?;
+    take(data, len)
+}
+
+fn decode_len(data: &mut &[u8]) -> Option<usize> {
+    Some(u32::from_le_bytes(take(data, 4)?.try_into().ok()?) as usize)
+}
+
+fn decode_f64(data: &mut &[u8]) -> Option<f64> {
+    Some(f64::from_bits(u64::from_le_bytes(take_array(data)?)))
+}
+
+fn take_array(data: &mut &[u8]) -> Option<[u8; 8]> {
+    take(data, 8)?.try_into().ok()
+}
+
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if data.len() < n {
+        return None;
+    }
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Some(head)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_decode_run() {
+        let rows = vec![
+            (
+                GroupKey::from(vec![Value::String("east"), Value::Null]),
+                vec![
+                    AggregateResult::Count(3),
+                    AggregateResult::Sum(Scalar::F64(1.5)),
+                    AggregateResult::Min(Value::Scalar(Scalar::I64(-2))),
+                    AggregateResult::Max(Value::String("zoo")),
+                    AggregateResult::First(Some((10, Value::Scalar(Scalar::U64(4))))),
+                    AggregateResult::Last(None),
+                    AggregateResult::Percentile(50.0, vec![Scalar::I64(3), Scalar::I64(1)]),
+                    AggregateResult::Variance(VarianceState::new(3, 1.5, 0.25)),
+                    AggregateResult::Histogram(vec![1.0, 10.0], vec![2, 0, 1]),
+                    AggregateResult::CountDistinct(
+                        true,
+                        vec![None, Some("a"), Some("b")].into_iter().collect(),
+                    ),
+                ],
+            ),
+            (
+                GroupKey::from(vec![Value::String("west"), Value::Boolean(true)]),
+                vec![
+                    AggregateResult::Count(0),
+                    AggregateResult::Sum(Scalar::Null),
+                    AggregateResult::Min(Value::Null),
+                    AggregateResult::Max(Value::ByteArray(&[1, 2, 3])),
+                    AggregateResult::First(None),
+                    AggregateResult::Last(Some((-1, Value::String("a")))),
+                    AggregateResult::Percentile(50.0, vec![]),
+                    AggregateResult::Variance(VarianceState::default()),
+                    AggregateResult::Histogram(vec![1.0, 10.0], vec![0, 0, 0]),
+                    AggregateResult::CountDistinct(true, BTreeSet::new()),
+                ],
+            ),
+        ];
+        let aggregates = [
+            AggregateType::Count,
+            AggregateType::Sum,
+            AggregateType::Min,
+            AggregateType::Max,
+            AggregateType::First,
+            AggregateType::Last,
+            AggregateType::Percentile(50.0),
+            AggregateType::Variance,
+            AggregateType::Histogram(vec![1.0, 10.0]),
+            AggregateType::CountDistinct { count_null: true },
+        ];
+
+        let data = encode_run(rows.iter().map(|(key, aggs)| (key, aggs)));
+        let decoded = decode_run(&data, 2, &aggregates).unwrap();
+        assert_eq!(decoded.len(), rows.len());
+        for ((key, aggs), (exp_key, exp_aggs)) in decoded.iter().zip(&rows) {
+            assert_eq!(key.values(), exp_key.values());
+            assert_eq!(format!("{:?}", aggs), format!("{:?}", exp_aggs));
+        }
+
+        // truncated runs are invalid.
+        assert!(decode_run(&data[..data.len() - 1], 2, &aggregates).is_none());
+    }
+
+    #[test]
+    fn encode_decode_run_digest() {
+        let mut digest = TDigest::default();
+        for v in 1..=1000 {
+            digest.insert(v as f64);
+        }
+        let key = GroupKey::from(vec![Value::String("east")]);
+        let aggs = vec![AggregateResult::ApproxQuantile(99.0, digest)];
+
+        let data = encode_run(std::iter::once((&key, &aggs)));
+        let decoded = decode_run(&data, 1, &[AggregateType::ApproxQuantile(99.0)]).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].1[0].value(), aggs[0].value());
+    }
+
+    #[test]
+    fn merge_sorted_runs() {
+        let run = |rows: Vec<(&'static str, u64)>| {
+            rows.into_iter()
+                .map(|(key, count)| {
+                    (
+                        GroupKey::from(vec![Value::String(key)]),
+                        vec![AggregateResult::Count(count)],
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let merged = merge_runs(
+            run(vec![("east", 1), ("north", 2), ("west", 3)]),
+            run(vec![("east", 10), ("south", 20), ("west", 30)]),
+        );
+        let merged = merged
+            .iter()
+            .map(|(key, aggs)| (key.values().to_vec(), aggs[0].value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            vec![
+                (vec![Value::String("east")], Value::Scalar(Scalar::U64(11))),
+                (vec![Value::String("north")], Value::Scalar(Scalar::U64(2))),
+                (vec![Value::String("south")], Value::Scalar(Scalar::U64(20))),
+                (vec![Value::String("west")], Value::Scalar(Scalar::U64(33))),
+            ]
+        );
+
+        assert!(merge_runs(vec![], vec![]).is_empty());
+    }
+
+    #[test]
+    fn object_store_spill_store() {
+        let store = ObjectStore::new_in_memory(object_store::InMemory::new());
+        let store: &dyn SpillStore = &store;
+
+        store.put("query-1/run-0", vec![1, 2, 3]).unwrap();
+        assert_eq!(store.get("query-1/run-0").unwrap(), vec![1, 2, 3]);
+
+        store.delete("query-1/run-0").unwrap();
+        assert!(store.get("query-1/run-0").is_err());
+    }
+}
@@ -3,11 +3,15 @@ use std::fmt::Display;
 use std::slice::Iter;
 
 use arrow_deps::arrow::record_batch::RecordBatch;
+use snafu::{ensure, OptionExt};
 
-use crate::row_group::{ColumnName, GroupKey, Predicate, RowGroup};
+use crate::row_group::{
+    self, ColumnName, GroupKey, Predicate, PredicateExpr, RowGroup, ValuePredicate,
+};
 use crate::{
-    column::{AggregateResult, AggregateType, OwnedValue, Scalar, Value},
+    column::{AggregateResult, AggregateType, LogicalDataType, OwnedValue, Scalar, Value},
     row_group::{ReadFilterResult, ReadGroupResult},
+    ColumnNotFound, EmptyTable, InvalidMaxRowGroupRows, Result, TimeRange, UnsupportedAggregate,
 };
 
 /// A Table represents data for a single measurement.
@@ -46,11 +50,44 @@ impl Table {
         }
     }
 
+    /// Create a new table from a record batch, splitting its rows into
+    /// segments of at most `max_row_group_rows` rows. Each segment is built by
+    /// `RowGroup::from_record_batch_rows`.
+    ///
+    /// An error is returned if the record batch is empty, `max_row_group_rows`
+    /// is zero, or a segment can't be built from the batch.
+    pub fn from_record_batch(
+        name: String,
+        rb: &RecordBatch,
+        max_row_group_rows: usize,
+    ) -> Result<Self> {
+        ensure!(max_row_group_rows > 0, InvalidMaxRowGroupRows);
+        ensure!(rb.num_rows() > 0, EmptyTable { table_name: name });
+
+        let segment = |start: usize| {
+            let end = (start + max_row_group_rows).min(rb.num_rows());
+            RowGroup::from_record_batch_rows(rb, start..end)
+        };
+
+        let mut starts = (0..rb.num_rows()).step_by(max_row_group_rows);
+        let mut table = Self::new(name, segment(starts.next().unwrap())?);
+        for start in starts {
+            table.add_segment(segment(start)?);
+        }
+        Ok(table)
+    }
+
     /// Add a new segment to this table.
     pub fn add_segment(&mut self, segment: RowGroup) {
+        self.meta.add_segment(&segment);
         self.segments.push(segment);
     }
 
+    /// Consumes the table, returning its segments.
+    pub fn into_segments(self) -> Vec<RowGroup> {
+        self.segments
+    }
+
     /// Remove the segment at `position` from table.
     pub fn drop_segment(&mut self, position: usize) {
         todo!();
@@ -78,26 +115,112 @@ impl Table {
 
     /// The total size of the table in bytes.
     pub fn size(&self) -> u64 {
-        todo!()
+        self.meta.size
     }
 
     /// The number of rows in this table.
     pub fn rows(&self) -> u64 {
-        todo!()
+        self.meta.rows
     }
 
     /// The time range of all segments within this table.
     pub fn time_range(&self) -> Option<(i64, i64)> {
-        todo!()
+        self.meta.time_range
     }
 
-    /// The ranges on each column in the table (across all segments).
-    pub fn column_ranges(&self) -> BTreeMap<String, (OwnedValue, OwnedValue)> {
-        todo!()
+    /// The ranges on each column in the table (across all segments). The
+    /// range of a column containing only NULL values is `None`.
+    pub fn column_ranges(&self) -> BTreeMap<String, Option<(OwnedValue, OwnedValue)>> {
+        self.meta.column_ranges.clone()
+    }
+
+    /// Determines if the table contains the named column.
+    pub fn has_column(&self, name: ColumnName<'_>) -> bool {
+        self.meta.column_ranges.contains_key(name)
+    }
+
+    /// The logical data type of the named column, if it exists in the table.
+    pub fn column_logical_datatype(&self, name: ColumnName<'_>) -> Option<LogicalDataType> {
+        // all segments have the same schema.
+        self.segments.first()?.column_logical_datatype(name)
+    }
+
+    /// The names of the tag columns in the table.
+    pub fn tag_column_names(&self) -> Vec<ColumnName<'_>> {
+        // all segments have the same schema.
+        self.segments
+            .first()
+            .map_or_else(Vec::new, |segment| segment.tag_column_names())
+    }
+
+    /// The distinct non-null values of the named tag column in each segment.
+    /// Values may appear in the dictionaries of several segments.
+    pub fn tag_column_dictionaries(&self, name: ColumnName<'_>) -> Vec<Vec<&String>> {
+        self.segments
+            .iter()
+            .filter_map(|segment| segment.tag_column_dictionary(name))
+            .collect()
+    }
+
+    /// The distinct non-null values of each of the named tag columns in the
+    /// rows within the time range satisfying the predicate expression. Tag
+    /// columns without any such values are omitted.
+    ///
+    /// Segments lying entirely within the time range are only filtered by the
+    /// expression, so their column dictionaries are used if it's empty.
+    pub fn tag_column_values<'a>(
+        &self,
+        time_range: TimeRange,
+        expr: &PredicateExpr<'_>,
+        tag_keys: &[ColumnName<'a>],
+    ) -> BTreeMap<ColumnName<'a>, BTreeSet<&String>> {
+        let time_predicates = crate::time_range_predicate(time_range);
+        let segment_expr = PredicateExpr::from(time_predicates.as_slice()).and(expr.clone());
+
+        let mut values: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for segment in self.filter_segments(&segment_expr) {
+            let (min, max) = segment.time_range();
+            let expr = if time_range.contains(min, max) {
+                expr
+            } else {
+                &segment_expr
+            };
+
+            for &name in tag_keys {
+                match segment.tag_column_values(name, expr) {
+                    Some(segment_values) if !segment_values.is_empty() => {
+                        values.entry(name).or_default().extend(segment_values)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        values
+    }
+
+    /// The total size in bytes of each column across all segments in the
+    /// table.
+    pub fn column_sizes(&self) -> BTreeMap<ColumnName<'_>, u64> {
+        let mut sizes = BTreeMap::new();
+        for segment in &self.segments {
+            for (name, size) in segment.column_sizes() {
+                *sizes.entry(name).or_default() += size;
+            }
+        }
+        sizes
+    }
+
+    /// The name and type of each column in the table, ordered by name.
+    pub fn column_types(&self) -> Vec<(ColumnName<'_>, crate::ColumnType)> {
+        // all segments have the same schema.
+        self.segments
+            .first()
+            .map_or_else(Vec::new, |segment| segment.column_types())
     }
 
     // Determines if schema contains all the provided column names.
-    fn has_all_columns(&self, names: &[ColumnName<'_>]) -> bool {
+    /// Determines if the table contains all of the provided columns.
+    pub fn has_all_columns(&self, names: &[ColumnName<'_>]) -> bool {
         for &name in names {
             if !self.meta.column_ranges.contains_key(name) {
                 return false;
@@ -107,40 +230,63 @@ impl Table {
     }
 
     // Identify set of segments that may satisfy the predicates.
-    fn filter_segments(&self, predicates: &[Predicate<'_>]) -> Vec<&RowGroup> {
-        let mut segments = Vec::with_capacity(self.segments.len());
-
-        'seg: for segment in &self.segments {
-            // check all provided predicates
-            for (col_name, pred) in predicates {
-                if !segment.column_could_satisfy_predicate(col_name, pred) {
-                    continue 'seg;
-                }
-            }
+    fn filter_segments(&self, expr: &PredicateExpr<'_>) -> Vec<&RowGroup> {
+        self.segments
+            .iter()
+            .filter(|segment| {
+                expr.could_be_satisfied(&|(col_name, pred)| {
+                    segment.column_could_satisfy_predicate(col_name, pred)
+                })
+            })
+            .collect()
+    }
 
-            // segment could potentially satisfy all predicates
-            segments.push(segment);
-        }
+    /// Determines if the table could contain rows satisfying the predicate
+    /// expression, based only on the range of values in each of its columns.
+    /// Unlike `satisfies_predicate_expr` no rows are read.
+    pub fn could_satisfy_predicate_expr(&self, expr: &PredicateExpr<'_>) -> bool {
+        expr.could_be_satisfied(
+            &|(name, predicate)| match self.meta.column_ranges.get(*name) {
+                Some(range) => row_group::range_could_satisfy_predicate(range.as_ref(), predicate),
+                None => false,
+            },
+        )
+    }
 
-        segments
+    /// Determines if at least one row in the table satisfies the predicate
+    /// expression.
+    pub fn satisfies_predicate_expr(&self, expr: &PredicateExpr<'_>) -> bool {
+        self.filter_segments(expr)
+            .iter()
+            .any(|segment| segment.satisfies_predicate_expr(expr))
     }
 
     /// Returns vectors of columnar data for the specified column
     /// selections.
     ///
-    /// Results may be filtered by (currently only) conjunctive (AND)
-    /// predicates, but can be ranged by time, which should be represented
+    /// Results may be filtered by conjunctive (AND) equality and comparison
+    /// predicates, and can be ranged by time, which should be represented
     /// as nanoseconds since the epoch. Results are included if they satisfy
     /// the predicate and fall with the [min, max) time range domain.
     pub fn select<'input>(
         &self,
         columns: &[ColumnName<'input>],
         predicates: &[Predicate<'_>],
+    ) -> ReadFilterResults<'input, '_> {
+        self.select_expr(columns, &PredicateExpr::from(predicates))
+    }
+
+    /// Returns vectors of columnar data for the specified column selections,
+    /// for the rows satisfying a predicate expression.
+    pub fn select_expr<'input>(
+        &self,
+        columns: &[ColumnName<'input>],
+        expr: &PredicateExpr<'_>,
     ) -> ReadFilterResults<'input, '_> {
         // identify segments where time range and predicates match could match
         // using segment meta data, and then execute against those segments and
         // merge results.
-        let segments = self.filter_segments(predicates);
+        let segments = self.filter_segments(expr);
 
         let mut results = ReadFilterResults {
             names: columns.to_vec(),
@@ -152,53 +298,159 @@ impl Table {
         }
 
         for segment in segments {
-            results
-                .values
-                .push(segment.read_filter(columns, predicates));
+            results.values.push(segment.read_filter_expr(columns, expr));
         }
 
         results
     }
 
+    /// The number of rows within the time range satisfying the predicate
+    /// expression, without materialising any column values.
+    ///
+    /// When the expression has no predicates, segments lying entirely within
+    /// the time range are counted from their metadata alone.
+    pub fn row_count(&self, time_range: TimeRange, expr: &PredicateExpr<'_>) -> u64 {
+        let time_predicates = crate::time_range_predicate(time_range);
+        let segment_expr = PredicateExpr::from(time_predicates.as_slice()).and(expr.clone());
+
+        self.filter_segments(&segment_expr)
+            .iter()
+            .map(|segment| {
+                let (min, max) = segment.time_range();
+                if expr.is_empty() && time_range.contains(min, max) {
+                    segment.rows() as u64
+                } else {
+                    segment.row_count(&segment_expr)
+                }
+            })
+            .sum()
+    }
+
+    /// The number of rows in the segments whose timestamps overlap the time
+    /// range. This is determined from segment metadata alone, so it's an
+    /// upper bound on the number of rows within the time range.
+    pub fn estimated_rows(&self, time_range: TimeRange) -> u64 {
+        self.segments
+            .iter()
+            .filter(|segment| {
+                let (min, max) = segment.time_range();
+                time_range.overlaps(min, max)
+            })
+            .map(|segment| segment.rows() as u64)
+            .sum()
+    }
+
     /// Returns aggregates segmented by grouping keys.
     ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
+    /// The set of data to be aggregated may be filtered by conjunctive equality
+    /// (`=`, `!=`) and comparison (`<`, `<=`, `>`, `>=`) predicates, and can be
+    /// ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Results are included if they satisfy the predicates and fall
+    /// with the [min, max) time range domain.
     ///
     /// Group keys are determined according to the provided group column names.
-    /// Currently only grouping by string (tag key) columns is supported.
+    /// String (tag key), integer, unsigned integer and boolean columns can be
+    /// grouped on.
     ///
     /// Required aggregates are specified via a tuple comprising a column name
     /// and the type of aggregation required. Multiple aggregations can be
     /// applied to the same column.
+    ///
+    /// Each aggregate can optionally have a predicate applied to the values
+    /// being aggregated via `value_predicates`, which should either be empty
+    /// or contain an entry for each aggregate.
     pub fn aggregate<'input>(
         &self,
         predicates: &[Predicate<'_>],
         group_columns: &'input [ColumnName<'input>],
         aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
     ) -> ReadGroupResults<'input, '_> {
-        if !self.has_all_columns(&group_columns) {
-            todo!() //TODO(edd): return an error here "group key column x not
-                    //found"
-        }
+        self.aggregate_expr(
+            &PredicateExpr::from(predicates),
+            group_columns,
+            aggregates,
+            value_predicates,
+        )
+    }
 
-        if !self.has_all_columns(&aggregates.iter().map(|(name, _)| *name).collect::<Vec<_>>()) {
-            todo!() //TODO(edd): return an error here "aggregate column x not
-                    // found"
+    /// Returns aggregates segmented by grouping keys, for the rows satisfying
+    /// a predicate expression.
+    ///
+    /// Predicates on columns missing from the table match no rows. Group
+    /// columns missing from the table are NULL for all of its rows, and
+    /// aggregates of columns missing from the table are aggregates of no
+    /// values, i.e., NULL or a count of zero.
+    pub fn aggregate_expr<'input>(
+        &self,
+        expr: &PredicateExpr<'_>,
+        group_columns: &'input [ColumnName<'input>],
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+    ) -> ReadGroupResults<'input, '_> {
+        self.aggregate_bucketed(expr, group_columns, aggregates, value_predicates, None)
+    }
+
+    /// The same as `aggregate_expr`, but rows are also grouped by the time
+    /// bucket of length `bucket` nanoseconds that they fall in. The start of
+    /// each row's bucket is the last value of its group key. See
+    /// `RowGroup::read_group_window_expr`.
+    pub fn aggregate_window_expr<'input>(
+        &self,
+        expr: &PredicateExpr<'_>,
+        group_columns: &'input [ColumnName<'input>],
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+        bucket: i64,
+    ) -> ReadGroupResults<'input, '_> {
+        self.aggregate_bucketed(
+            expr,
+            group_columns,
+            aggregates,
+            value_predicates,
+            Some(bucket),
+        )
+    }
+
+    // Aggregates the segments matching the expression, grouping rows by their
+    // time bucket after the group columns if `bucket` is provided.
+    fn aggregate_bucketed<'input>(
+        &self,
+        expr: &PredicateExpr<'_>,
+        group_columns: &'input [ColumnName<'input>],
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        value_predicates: &[Option<ValuePredicate<'_>>],
+        bucket: Option<i64>,
+    ) -> ReadGroupResults<'input, '_> {
+        // Columns missing from the table are NULL for all of its rows, so are
+        // left out of the aggregation and added to each result afterwards.
+        let mut present_group_columns = Vec::with_capacity(group_columns.len());
+        let mut null_groups = vec![];
+        for (i, &name) in group_columns.iter().enumerate() {
+            if self.has_column(name) {
+                present_group_columns.push(name);
+            } else {
+                null_groups.push(i);
+            }
         }
 
-        if !self.has_all_columns(&predicates.iter().map(|(name, _)| *name).collect::<Vec<_>>()) {
-            todo!() //TODO(edd): return an error here "predicate column x not
-                    // found"
+        let mut present_aggregates = Vec::with_capacity(aggregates.len());
+        let mut present_value_predicates = Vec::with_capacity(aggregates.len());
+        let mut empty_aggregates = vec![];
+        for (i, (name, typ)) in aggregates.iter().enumerate() {
+            if self.has_column(name) {
+                present_aggregates.push((*name, typ.clone()));
+                present_value_predicates.push(value_predicates.get(i).cloned().flatten());
+            } else {
+                empty_aggregates.push((i, typ.clone()));
+            }
         }
 
         // identify segments where time range and predicates match could match
         // using segment meta data, and then execute against those segments and
         // merge results.
         let mut results = ReadGroupResults::default();
-        let segments = self.filter_segments(predicates);
+        let segments = self.filter_segments(expr);
         if segments.is_empty() {
             results.groupby_columns = group_columns;
             results.aggregate_columns = aggregates;
@@ -207,7 +459,22 @@ impl Table {
 
         results.values.reserve(segments.len());
         for segment in segments {
-            let segment_result = segment.read_group(predicates, &group_columns, &aggregates);
+            let mut segment_result = match bucket {
+                Some(bucket) => segment.read_group_window_expr(
+                    expr,
+                    &present_group_columns,
+                    &present_aggregates,
+                    &present_value_predicates,
+                    bucket,
+                ),
+                None => segment.read_group_expr(
+                    expr,
+                    &present_group_columns,
+                    &present_aggregates,
+                    &present_value_predicates,
+                ),
+            };
+            segment_result.pad(&null_groups, &empty_aggregates);
             results.values.push(segment_result);
         }
 
@@ -218,14 +485,15 @@ impl Table {
 
     /// Returns aggregates segmented by grouping keys and windowed by time.
     ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
+    /// The set of data to be aggregated may be filtered by conjunctive equality
+    /// (`=`, `!=`) and comparison (`<`, `<=`, `>`, `>=`) predicates, and can be
+    /// ranged by time, which should be represented as nanoseconds since the
+    /// epoch. Results are included if they satisfy the predicates and fall
+    /// with the [min, max) time range domain.
     ///
     /// Group keys are determined according to the provided group column names
-    /// (`group_columns`). Currently only grouping by string (tag key) columns
-    /// is supported.
+    /// (`group_columns`). String (tag key), integer, unsigned integer and
+    /// boolean columns can be grouped on.
     ///
     /// Required aggregates are specified via a tuple comprising a column name
     /// and the type of aggregation required. Multiple aggregations can be
@@ -250,12 +518,15 @@ impl Table {
 
     // Perform aggregates without any grouping. Filtering on optional predicates
     // and time range is still supported.
+    //
+    // Only aggregates that can be computed from column statistics are
+    // supported; an error is returned for any other aggregate.
     fn read_aggregate_no_group<'a>(
         &self,
         time_range: (i64, i64),
         predicates: &[(&str, &str)],
         aggregates: Vec<(ColumnName<'a>, AggregateType)>,
-    ) -> Vec<(ColumnName<'a>, AggregateResult<'_>)> {
+    ) -> Result<Vec<(ColumnName<'a>, AggregateResult<'_>)>> {
         // The fast path where there are no predicates or a time range to apply.
         // We just want the equivalent of column statistics.
         if predicates.is_empty() {
@@ -300,6 +571,30 @@ impl Table {
 
                         results.push((col_name, AggregateResult::Sum(res)));
                     }
+                    AggregateType::ArgMax
+                    | AggregateType::ArgMin
+                    | AggregateType::Percentile(_)
+                    | AggregateType::ApproxQuantile(_)
+                    | AggregateType::Mean
+                    | AggregateType::StdDev
+                    | AggregateType::Variance
+                    | AggregateType::Histogram(_)
+                    | AggregateType::CountDistinct { .. }
+                    | AggregateType::CountTrue
+                    | AggregateType::CountFalse => {
+                        let data_type =
+                            self.column_logical_datatype(col_name)
+                                .context(ColumnNotFound {
+                                    table_name: &self.name,
+                                    column_name: *col_name,
+                                })?;
+                        return UnsupportedAggregate {
+                            column_name: *col_name,
+                            data_type,
+                            agg_type: agg_type.clone(),
+                        }
+                        .fail();
+                    }
                 }
             }
         }
@@ -458,7 +753,7 @@ struct MetaData {
     //
     // This can be used to skip the table entirely if a logical predicate can't
     // possibly match based on the range of values a column has.
-    column_ranges: BTreeMap<String, (OwnedValue, OwnedValue)>,
+    column_ranges: BTreeMap<String, Option<(OwnedValue, OwnedValue)>>,
 
     // The total time range of this table spanning all of the segments within
     // the table.
@@ -476,7 +771,7 @@ impl MetaData {
             column_ranges: segment
                 .column_ranges()
                 .iter()
-                .map(|(k, v)| (k.to_string(), (v.0.clone(), v.1.clone())))
+                .map(|(k, v)| (k.to_string(), v.clone()))
                 .collect(),
             time_range: Some(segment.time_range()),
         }
@@ -487,14 +782,29 @@ impl MetaData {
         self.size += segment.size();
         self.rows += u64::from(segment.rows());
 
+        let (segment_min, segment_max) = segment.time_range();
+        self.time_range = Some(match self.time_range {
+            Some((min, max)) => (min.min(segment_min), max.max(segment_max)),
+            None => (segment_min, segment_max),
+        });
+
         assert_eq!(self.column_ranges.len(), segment.column_ranges().len());
-        for (segment_column_name, (segment_column_range_min, segment_column_range_max)) in
-            segment.column_ranges()
-        {
-            let mut curr_range = self
+        for (segment_column_name, segment_column_range) in segment.column_ranges() {
+            let (segment_column_range_min, segment_column_range_max) = match segment_column_range {
+                Some(range) => range,
+                None => continue, // only NULL values in the segment's column
+            };
+
+            let curr_range = self
                 .column_ranges
                 .get_mut(&segment_column_name.to_string())
-                .unwrap();
+                .unwrap()
+                .get_or_insert_with(|| {
+                    (
+                        segment_column_range_min.clone(),
+                        segment_column_range_max.clone(),
+                    )
+                });
             if segment_column_range_min < &curr_range.0 {
                 curr_range.0 = segment_column_range_min.clone();
             }
@@ -562,6 +872,13 @@ pub struct ReadGroupResults<'input, 'segment> {
     values: Vec<ReadGroupResult<'segment>>,
 }
 
+impl<'input, 'segment> ReadGroupResults<'input, 'segment> {
+    /// The segment-wise result sets containing grouped values and aggregates.
+    pub fn values(&self) -> &[ReadGroupResult<'segment>] {
+        &self.values
+    }
+}
+
 impl std::fmt::Display for ReadGroupResults<'_, '_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // header line - display group columns first
@@ -589,6 +906,13 @@ impl std::fmt::Display for ReadGroupResults<'_, '_> {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
+    use arrow_deps::arrow::{
+        array::{Float64Array, Int64Array, StringArray},
+        datatypes::{DataType, Field, Schema},
+    };
+
     use super::*;
     use crate::column::{cmp::Operator, Column};
     use crate::row_group::{ColumnType, TIME_COLUMN_NAME};
@@ -613,6 +937,170 @@ mod test {
         arr
     }
 
+    #[test]
+    fn from_record_batch() {
+        let schema = Schema::new(vec![
+            Field::new("region", DataType::Utf8, true),
+            Field::new("temp", DataType::Float64, false),
+            Field::new(TIME_COLUMN_NAME, DataType::Int64, false),
+        ]);
+        let regions = (0..25)
+            .map(|i| {
+                if i % 7 == 0 {
+                    None
+                } else if i < 12 {
+                    Some("east")
+                } else {
+                    Some("west")
+                }
+            })
+            .collect::<Vec<_>>();
+        let temps = (0..25).map(|i| i as f64 * 1.5).collect::<Vec<_>>();
+        let times = (100..125).collect::<Vec<i64>>();
+        let rb = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(regions)),
+                Arc::new(Float64Array::from(temps)),
+                Arc::new(Int64Array::from(times)),
+            ],
+        )
+        .unwrap();
+
+        let table = Table::from_record_batch("cpu".to_string(), &rb, 10).unwrap();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.rows(), 25);
+        assert_eq!(table.time_range(), Some((100, 124)));
+        assert_eq!(
+            table.column_types(),
+            vec![
+                ("region", crate::ColumnType::Tag(LogicalDataType::String)),
+                ("temp", crate::ColumnType::Field(LogicalDataType::Float)),
+                ("time", crate::ColumnType::Time(LogicalDataType::Integer)),
+            ]
+        );
+
+        let string = |v: &str| OwnedValue::String(v.to_string());
+        let float = |v| OwnedValue::Scalar(Scalar::F64(v));
+        let expected = vec![
+            (
+                10,
+                (100, 109),
+                (string("east"), string("east")),
+                (0.0, 13.5),
+            ),
+            (
+                10,
+                (110, 119),
+                (string("east"), string("west")),
+                (15.0, 28.5),
+            ),
+            (
+                5,
+                (120, 124),
+                (string("west"), string("west")),
+                (30.0, 36.0),
+            ),
+        ];
+        for (segment, (rows, time_range, region_range, temp_range)) in
+            table.segments.iter().zip(expected)
+        {
+            assert_eq!(segment.rows(), rows);
+            assert_eq!(segment.time_range(), time_range);
+            assert_eq!(segment.column_ranges()["region"], Some(region_range));
+            assert_eq!(
+                segment.column_ranges()["temp"],
+                Some((float(temp_range.0), float(temp_range.1)))
+            );
+        }
+
+        // A batch that fits within a single segment isn't split.
+        let table = Table::from_record_batch("cpu".to_string(), &rb, 25).unwrap();
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn from_record_batch_column_type_metadata() {
+        let batch = |metadata: Vec<(&str, &str)>| {
+            let metadata = metadata
+                .into_iter()
+                .map(|(column, column_type)| {
+                    (
+                        format!("{}{}", row_group::COLUMN_TYPE_METADATA_KEY_PREFIX, column),
+                        column_type.to_string(),
+                    )
+                })
+                .collect();
+            let schema = Schema::new_with_metadata(
+                vec![
+                    Field::new("region", DataType::Utf8, false),
+                    Field::new("status", DataType::Utf8, false),
+                    Field::new("temp", DataType::Float64, false),
+                    Field::new(TIME_COLUMN_NAME, DataType::Int64, false),
+                ],
+                metadata,
+            );
+            RecordBatch::try_new(
+                Arc::new(schema),
+                vec![
+                    Arc::new(StringArray::from(vec!["west", "east"])),
+                    Arc::new(StringArray::from(vec!["ok", "failed"])),
+                    Arc::new(Float64Array::from(vec![1.5, 2.5])),
+                    Arc::new(Int64Array::from(vec![100, 200])),
+                ],
+            )
+            .unwrap()
+        };
+
+        // A string column can be a field.
+        let rb = batch(vec![
+            ("region", "tag"),
+            ("status", "field"),
+            ("temp", "field"),
+            ("time", "time"),
+        ]);
+        let table = Table::from_record_batch("cpu".to_string(), &rb, 10).unwrap();
+        assert_eq!(
+            table.column_types(),
+            vec![
+                ("region", crate::ColumnType::Tag(LogicalDataType::String)),
+                ("status", crate::ColumnType::Field(LogicalDataType::String)),
+                ("temp", crate::ColumnType::Field(LogicalDataType::Float)),
+                ("time", crate::ColumnType::Time(LogicalDataType::Integer)),
+            ]
+        );
+
+        // Columns without metadata are typed by their data type.
+        let rb = batch(vec![("status", "field")]);
+        let table = Table::from_record_batch("cpu".to_string(), &rb, 10).unwrap();
+        assert_eq!(
+            table.column_types(),
+            vec![
+                ("region", crate::ColumnType::Tag(LogicalDataType::String)),
+                ("status", crate::ColumnType::Field(LogicalDataType::String)),
+                ("temp", crate::ColumnType::Field(LogicalDataType::Float)),
+                ("time", crate::ColumnType::Time(LogicalDataType::Integer)),
+            ]
+        );
+
+        for metadata in vec![
+            vec![("temp", "tag")],
+            vec![("status", "measurement")],
+            vec![("region", "time")],
+            vec![("time", "field")],
+        ] {
+            let rb = batch(metadata.clone());
+            assert!(
+                matches!(
+                    Table::from_record_batch("cpu".to_string(), &rb, 10),
+                    Err(crate::Error::InvalidColumnTypeMetadata { .. })
+                ),
+                "{:?}",
+                metadata
+            );
+        }
+    }
+
     #[test]
     fn select() {
         // Build first segment.
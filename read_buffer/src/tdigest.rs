@@ -0,0 +1,254 @@
+//! A t-digest summarises the distribution of a set of values in bounded space,
+//! so that quantiles can be estimated over large columns without sorting or
+//! retaining every value.
+//!
+//! This is the "merging" t-digest described in Dunning & Ertl's "Computing
+//! Extremely Accurate Quantiles Using t-Digests", using the `k1` scale
+//! function. Estimates are most accurate towards the tails of a distribution.
+use std::f64::consts::PI;
+
+/// The default compression factor, which bounds the number of centroids held
+/// by a digest to roughly `compression / 2`.
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+// The number of values buffered before they're merged into the centroids.
+const BUFFER_SIZE: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TDigest {
+    compression: f64,
+
+    // (mean, weight) pairs ordered by mean.
+    centroids: Vec<(f64, f64)>,
+
+    // values that have not yet been merged into the centroids.
+    buffer: Vec<f64>,
+
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION)
+    }
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression,
+            centroids: vec![],
+            buffer: vec![],
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Rebuilds a digest from the `(mean, weight)` centroids and the
+    /// extremes of a digest previously produced by `centroids`, `min` and
+    /// `max`.
+    pub fn from_centroids(centroids: Vec<(f64, f64)>, min: f64, max: f64) -> Self {
+        let mut digest = Self::default();
+        digest.centroids = centroids;
+        digest.min = min;
+        digest.max = max;
+        digest
+    }
+
+    /// Adds a value to the digest. NaN values are ignored.
+    pub fn insert(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(value);
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.compress();
+        }
+    }
+
+    /// Merges another digest into this one.
+    pub fn merge(&mut self, other: &Self) {
+        if other.is_empty() {
+            return;
+        }
+
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.centroids.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        self.compress();
+    }
+
+    /// The number of values summarised by the digest.
+    pub fn count(&self) -> f64 {
+        self.centroids.iter().map(|(_, weight)| weight).sum::<f64>() + self.buffer.len() as f64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.centroids.is_empty() && self.buffer.is_empty()
+    }
+
+    /// The smallest value added to the digest.
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+
+    /// The largest value added to the digest.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// The `(mean, weight)` centroids of the digest, including any values
+    /// that are yet to be merged.
+    pub fn centroids(&self) -> Vec<(f64, f64)> {
+        if self.buffer.is_empty() {
+            return self.centroids.clone();
+        }
+
+        let mut digest = self.clone();
+        digest.compress();
+        digest.centroids
+    }
+
+    /// Estimates the value at quantile `q`, which is clamped to `[0, 1]`.
+    /// `None` is returned if the digest is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let centroids = self.centroids();
+        let total = centroids.iter().map(|(_, weight)| weight).sum::<f64>();
+        let target = q.max(0.0).min(1.0) * total;
+
+        // Each centroid's mean is placed at the middle of the ranks it covers,
+        // and the extremes are placed at the ends. The estimate is linearly
+        // interpolated between the two points either side of the target rank.
+        let mut prev = (self.min, 0.0);
+        let mut cumulative = 0.0;
+        for &(mean, weight) in &centroids {
+            let centre = cumulative + weight / 2.0;
+            if target <= centre {
+                return Some(interpolate(prev, (mean, centre), target));
+            }
+            prev = (mean, centre);
+            cumulative += weight;
+        }
+        Some(interpolate(prev, (self.max, total), target))
+    }
+
+    // Merges the buffered values into the centroids, combining adjacent
+    // centroids while they remain within the size allowed by the scale
+    // function.
+    fn compress(&mut self) {
+        let mut points = std::mem::take(&mut self.centroids);
+        points.extend(self.buffer.drain(..).map(|value| (value, 1.0)));
+        if points.is_empty() {
+            return;
+        }
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let total = points.iter().map(|(_, weight)| weight).sum::<f64>();
+        let mut merged = Vec::with_capacity(self.compression as usize);
+        let mut weight_so_far = 0.0;
+        let mut limit = total * self.q_limit(0.0);
+        let mut current = points[0];
+        for &(mean, weight) in &points[1..] {
+            if weight_so_far + current.1 + weight <= limit {
+                current.1 += weight;
+                current.0 += (mean - current.0) * weight / current.1;
+            } else {
+                weight_so_far += current.1;
+                merged.push(current);
+                limit = total * self.q_limit(weight_so_far / total);
+                current = (mean, weight);
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    // The largest quantile that a centroid starting at quantile `q` may
+    // extend to, which is one unit further along the `k1` scale:
+    // `k(q) = compression / 2π * asin(2q - 1)`.
+    fn q_limit(&self, q: f64) -> f64 {
+        let k = self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin() + 1.0;
+        let angle = (k * 2.0 * PI / self.compression).min(PI / 2.0);
+        (angle.sin() + 1.0) / 2.0
+    }
+}
+
+// Linearly interpolates the value at `rank` between two `(value, rank)`
+// points.
+fn interpolate(from: (f64, f64), to: (f64, f64), rank: f64) -> f64 {
+    if to.1 <= from.1 {
+        return to.0;
+    }
+    from.0 + (to.0 - from.0) * (rank - from.1) / (to.1 - from.1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quantile_small() {
+        let mut digest = TDigest::default();
+        assert_eq!(digest.quantile(0.5), None);
+
+        for v in &[3.0, 1.0, 2.0] {
+            digest.insert(*v);
+        }
+        assert_eq!(digest.count(), 3.0);
+        assert_eq!(digest.quantile(0.0), Some(1.0));
+        assert_eq!(digest.quantile(0.5), Some(2.0));
+        assert_eq!(digest.quantile(1.0), Some(3.0));
+    }
+
+    #[test]
+    fn quantile_large() {
+        let mut digest = TDigest::default();
+        for v in 1..=100_000 {
+            digest.insert(v as f64);
+        }
+        assert!(digest.centroids().len() <= DEFAULT_COMPRESSION as usize);
+
+        for &(q, expected) in &[(0.5, 50_000.0), (0.99, 99_000.0), (0.999, 99_900.0)] {
+            let got = digest.quantile(q).unwrap();
+            assert!(
+                (got - expected).abs() / expected < 0.005,
+                "q={} got={} expected={}",
+                q,
+                got,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = TDigest::default();
+        let mut b = TDigest::default();
+        for v in 1..=10_000 {
+            if v % 2 == 0 {
+                a.insert(v as f64);
+            } else {
+                b.insert(v as f64);
+            }
+        }
+
+        a.merge(&b);
+        assert_eq!(a.count(), 10_000.0);
+        assert_eq!(a.min(), 1.0);
+        assert_eq!(a.max(), 10_000.0);
+        let median = a.quantile(0.5).unwrap();
+        assert!((median - 5_000.0).abs() < 50.0, "got {}", median);
+
+        let rebuilt = TDigest::from_centroids(a.centroids(), a.min(), a.max());
+        assert_eq!(rebuilt.quantile(0.5), a.quantile(0.5));
+    }
+}
@@ -1,14 +1,18 @@
 //! This module contains code for organizing the running server
 
 use std::{
+    borrow::Cow,
     collections::BTreeMap,
     sync::{
-        atomic::{AtomicU32, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         Arc,
     },
 };
 
-use crate::buffer::Buffer;
+use crate::{
+    buffer::Buffer,
+    snapshot::{Snapshot, SnapshotSummary},
+};
 use arrow_deps::arrow::record_batch::RecordBatch;
 use data_types::{
     data::{lines_to_replicated_write, ReplicatedWrite},
@@ -16,28 +20,43 @@ use data_types::{
     {DatabaseName, DatabaseNameError},
 };
 use influxdb_line_protocol::ParsedLine;
-use mutable_buffer::MutableBufferDb;
+use mutable_buffer::{MutableBufferDb, Tombstone};
 use object_store::ObjectStore;
 use query::{DatabaseStore, SQLDatabase, TSDatabase};
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::Utc;
 use futures::stream::TryStreamExt;
 use serde::{Deserialize, Serialize};
-use snafu::{OptionExt, ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
 type DatabaseError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 /// A server ID of 0 is reserved and indicates no ID has been configured.
 const SERVER_ID_NOT_SET: u32 = 0;
 
+/// The number of finished snapshots recorded for each database. Older ones
+/// are forgotten once there are more.
+const MAX_FINISHED_SNAPSHOTS: usize = 100;
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Server error: {}", source))]
     ServerError { source: std::io::Error },
     #[snafu(display("database not found: {}", db_name))]
     DatabaseNotFound { db_name: String },
+    #[snafu(display("database already exists: {}", db_name))]
+    DatabaseAlreadyExists { db_name: String },
+    #[snafu(display("unknown rule {} for database {}", rule, db_name))]
+    UnknownRule { db_name: String, rule: String },
+    #[snafu(display("invalid rules for database {}: {}", db_name, source))]
+    InvalidRules {
+        db_name: String,
+        source: serde_json::Error,
+    },
     #[snafu(display("invalid database: {}", source))]
     InvalidDatabaseName { source: DatabaseNameError },
     #[snafu(display("database error: {}", source))]
@@ -67,15 +86,55 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Summary of the points handled by a call to `write_lines`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteSummary {
+    /// The number of points written.
+    pub points_written: usize,
+    /// The number of points dropped because their timestamps were beyond the
+    /// database's `max_future_skew`.
+    pub future_points_rejected: usize,
+    /// The number of points written with their timestamps clamped to the
+    /// database's `max_future_skew`.
+    pub future_points_clamped: usize,
+}
+
 /// `Server` is the container struct for how servers store data internally, as
 /// well as how they communicate with other servers. Each server will have one
 /// of these structs, which keeps track of all replication and query rules.
 #[derive(Debug)]
 pub struct Server<M: ConnectionManager> {
     id: AtomicU32,
+    ready: AtomicBool,
     config: RwLock<Config>,
     connection_manager: Arc<M>,
     pub store: Arc<ObjectStore>,
+    // The snapshots started for each database, in the order they started
+    snapshots: RwLock<BTreeMap<String, Vec<RecordedSnapshot>>>,
+}
+
+// A snapshot recorded by the server. Once a snapshot has finished only its
+// summary is kept, so that the snapshot is dropped.
+#[derive(Debug)]
+enum RecordedSnapshot {
+    Running(Arc<Snapshot>),
+    Finished(SnapshotSummary),
+}
+
+impl RecordedSnapshot {
+    fn summary(&self) -> SnapshotSummary {
+        match self {
+            Self::Running(snapshot) => snapshot.summary(),
+            Self::Finished(summary) => summary.clone(),
+        }
+    }
+
+    fn id(&self) -> Uuid {
+        match self {
+            Self::Running(snapshot) => snapshot.id,
+            Self::Finished(summary) => summary.id,
+        }
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
@@ -88,9 +147,11 @@ impl<M: ConnectionManager> Server<M> {
     pub fn new(connection_manager: M, store: Arc<ObjectStore>) -> Self {
         Self {
             id: AtomicU32::new(SERVER_ID_NOT_SET),
+            ready: AtomicBool::new(false),
             config: RwLock::new(Config::default()),
             store,
             connection_manager: Arc::new(connection_manager),
+            snapshots: RwLock::new(BTreeMap::new()),
         }
     }
 
@@ -102,6 +163,23 @@ impl<M: ConnectionManager> Server<M> {
         self.id.store(id, Ordering::Release)
     }
 
+    /// Returns true once the server ID has been set.
+    pub fn has_id(&self) -> bool {
+        self.id.load(Ordering::Acquire) != SERVER_ID_NOT_SET
+    }
+
+    /// Marks the server as ready to serve reads, which should happen once
+    /// startup work such as WAL replay and initial chunk loading is complete.
+    /// Until then reads could return incomplete data.
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::Release)
+    }
+
+    /// Returns true once the server is ready to serve reads.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
     /// Returns the current server ID, or an error if not yet set.
     async fn require_id(&self) -> Result<u32> {
         match self.id.load(Ordering::Acquire) {
@@ -111,7 +189,8 @@ impl<M: ConnectionManager> Server<M> {
     }
 
     /// Tells the server the set of rules for a database. Currently, this is not
-    /// persisted and is for in-memory processing rules only.
+    /// persisted and is for in-memory processing rules only. An error is
+    /// returned if the database already exists.
     pub async fn create_database(
         &self,
         db_name: impl Into<String>,
@@ -137,11 +216,75 @@ impl<M: ConnectionManager> Server<M> {
         };
 
         let mut config = self.config.write().await;
+        ensure!(
+            !config.databases.contains_key(&db_name),
+            DatabaseAlreadyExists { db_name: &*db_name }
+        );
         config.databases.insert(db_name, db);
 
         Ok(())
     }
 
+    /// Removes a database from the server. Queries that already hold the
+    /// database's buffer are unaffected, but it can no longer be written to or
+    /// looked up.
+    pub async fn remove_database(&self, db_name: &str) -> Result<()> {
+        let db_name = DatabaseName::new(db_name.to_string()).context(InvalidDatabaseName)?;
+
+        let mut config = self.config.write().await;
+        config
+            .databases
+            .remove(&db_name)
+            .context(DatabaseNotFound { db_name: &*db_name })?;
+        self.snapshots.write().await.remove(&*db_name);
+
+        Ok(())
+    }
+
+    /// Updates the rules of an existing database. Each key in `update`
+    /// replaces the rule of the same name, and rules not in `update` are
+    /// left unchanged.
+    ///
+    /// The updated rules are validated before they're applied, and are
+    /// applied atomically, so each write observes either the old or the new
+    /// rules. Enabling `store_locally` creates a local write buffer if there
+    /// isn't one already. Disabling it stops writes being stored locally, but
+    /// keeps the existing buffer so that its data can still be queried.
+    pub async fn update_database_rules(
+        &self,
+        db_name: &str,
+        update: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<()> {
+        let db_name = DatabaseName::new(db_name.to_string()).context(InvalidDatabaseName)?;
+
+        let mut config = self.config.write().await;
+        let db = config
+            .databases
+            .get_mut(&db_name)
+            .context(DatabaseNotFound { db_name: &*db_name })?;
+
+        let mut rules = match serde_json::to_value(&db.rules).context(ErrorSerializing)? {
+            serde_json::Value::Object(rules) => rules,
+            _ => unreachable!("database rules are serialized as an object"),
+        };
+        for (name, value) in update {
+            let rule = rules.get_mut(&name).context(UnknownRule {
+                db_name: &*db_name,
+                rule: &name,
+            })?;
+            *rule = value;
+        }
+        let rules: DatabaseRules = serde_json::from_value(serde_json::Value::Object(rules))
+            .context(InvalidRules { db_name: &*db_name })?;
+
+        if rules.store_locally && db.local_store.is_none() {
+            db.local_store = Some(Arc::new(MutableBufferDb::new(db_name.to_string())));
+        }
+        db.rules = rules;
+
+        Ok(())
+    }
+
     /// Creates a host group with a set of connection strings to hosts. These
     /// host connection strings should be something that the connection
     /// manager can use to return a remote server to work with.
@@ -208,7 +351,14 @@ impl<M: ConnectionManager> Server<M> {
     /// `ReplicatedWrite`, which is then replicated to other servers based
     /// on the configuration of the `db`. This is step #1 from the crate
     /// level documentation.
-    pub async fn write_lines(&self, db_name: &str, lines: &[ParsedLine<'_>]) -> Result<()> {
+    ///
+    /// Points too far in the future for the database's `max_future_skew` are
+    /// dropped or clamped, which is reported in the returned summary.
+    pub async fn write_lines(
+        &self,
+        db_name: &str,
+        lines: &[ParsedLine<'_>],
+    ) -> Result<WriteSummary> {
         let id = self.require_id().await?;
 
         let db_name = DatabaseName::new(db_name).context(InvalidDatabaseName)?;
@@ -221,12 +371,47 @@ impl<M: ConnectionManager> Server<M> {
             .get(&db_name)
             .context(DatabaseNotFound { db_name: &*db_name })?;
 
+        let mut summary = WriteSummary::default();
+        let lines = match db.rules.max_future_time(Utc::now().timestamp_nanos()) {
+            Some(max_time) => Cow::Owned(guard_future_points(
+                lines,
+                max_time,
+                db.rules.clamp_future_writes,
+                &mut summary,
+            )),
+            None => Cow::Borrowed(lines),
+        };
+        summary.points_written = lines.len();
+
+        // There's nothing to write if every point was too far in the future.
+        if lines.is_empty() && summary.future_points_rejected > 0 {
+            return Ok(summary);
+        }
+
         let sequence = db.next_sequence();
-        let write = lines_to_replicated_write(id, sequence, lines, &db.rules);
+        let write = lines_to_replicated_write(id, sequence, &lines, &db.rules);
 
         self.handle_replicated_write(&db_name, db, write).await?;
 
-        Ok(())
+        Ok(summary)
+    }
+
+    /// Deletes the rows described by `tombstone` from the named database.
+    /// Like a write, the delete is stored in the local write buffer and
+    /// replicated according to the database's rules, so that it is recorded
+    /// in the WAL and applied again when the WAL is replayed.
+    pub async fn delete(&self, db_name: &str, tombstone: &Tombstone) -> Result<()> {
+        let id = self.require_id().await?;
+
+        let db_name = DatabaseName::new(db_name).context(InvalidDatabaseName)?;
+        let config = self.config.read().await;
+        let db = config
+            .databases
+            .get(&db_name)
+            .context(DatabaseNotFound { db_name: &*db_name })?;
+
+        let write = tombstone.to_replicated_write(id, db.next_sequence());
+        self.handle_replicated_write(&db_name, db, write).await
     }
 
     /// Executes a query against the local write buffer database, if one exists.
@@ -255,7 +440,9 @@ impl<M: ConnectionManager> Server<M> {
         db: &Db,
         write: ReplicatedWrite,
     ) -> Result<()> {
-        if let Some(buf) = &db.local_store {
+        // A database may have a local buffer from before `store_locally` was
+        // disabled, which is kept for queries but no longer written to.
+        if let Some(buf) = db.local_store.as_ref().filter(|_| db.rules.store_locally) {
             buf.store_replicated_write(&write)
                 .await
                 .map_err(|e| Box::new(e) as DatabaseError)
@@ -326,6 +513,66 @@ impl<M: ConnectionManager> Server<M> {
             .get(&name)
             .and_then(|d| d.local_store.clone())
     }
+
+    /// Records a snapshot of a chunk of the named database, so that its
+    /// progress can be looked up while it runs and after it has finished.
+    /// Only the most recent `MAX_FINISHED_SNAPSHOTS` finished snapshots of
+    /// each database are kept.
+    pub async fn add_snapshot(&self, db_name: &DatabaseName<'_>, snapshot: Arc<Snapshot>) {
+        let mut snapshots = self.snapshots.write().await;
+        let recorded = snapshots.entry(db_name.to_string()).or_default();
+
+        for entry in recorded.iter_mut() {
+            if let RecordedSnapshot::Running(running) = entry {
+                let summary = running.summary();
+                if summary.finished() {
+                    *entry = RecordedSnapshot::Finished(summary);
+                }
+            }
+        }
+
+        let finished = recorded
+            .iter()
+            .filter(|entry| matches!(entry, RecordedSnapshot::Finished(_)))
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_SNAPSHOTS);
+        recorded.retain(|entry| match entry {
+            RecordedSnapshot::Finished(_) if excess > 0 => {
+                excess -= 1;
+                false
+            }
+            _ => true,
+        });
+
+        recorded.push(RecordedSnapshot::Running(snapshot));
+    }
+
+    /// Returns a summary of the recorded snapshot with the specified id, if
+    /// any.
+    pub async fn snapshot(&self, id: Uuid) -> Option<SnapshotSummary> {
+        let snapshots = self.snapshots.read().await;
+        snapshots
+            .values()
+            .flatten()
+            .find(|snapshot| snapshot.id() == id)
+            .map(RecordedSnapshot::summary)
+    }
+
+    /// Returns summaries of the recorded snapshots of the named database, in
+    /// the order they were started.
+    pub async fn snapshots(&self, db_name: &DatabaseName<'_>) -> Vec<SnapshotSummary> {
+        let snapshots = self.snapshots.read().await;
+        snapshots
+            .get(&**db_name)
+            .map(|recorded| recorded.iter().map(RecordedSnapshot::summary).collect())
+            .unwrap_or_default()
+    }
+
+    /// The names of the databases the server knows about, in sorted order.
+    pub async fn db_names(&self) -> Vec<String> {
+        let config = self.config.read().await;
+        config.databases.keys().map(ToString::to_string).collect()
+    }
 }
 
 #[async_trait]
@@ -341,6 +588,10 @@ impl DatabaseStore for Server<ConnectionManagerImpl> {
         None
     }
 
+    async fn db_names_sorted(&self) -> Vec<String> {
+        self.db_names().await
+    }
+
     // TODO: refactor usages of this to use the Server rather than this trait and to
     //       explicitly create a database.
     async fn db_or_create(&self, name: &str) -> Result<Arc<Self::Database>, Self::Error> {
@@ -354,7 +605,11 @@ impl DatabaseStore for Server<ConnectionManagerImpl> {
                     ..Default::default()
                 };
 
-                self.create_database(name, rules).await?;
+                // Another caller may have created the database in the meantime.
+                match self.create_database(name, rules).await {
+                    Ok(()) | Err(Error::DatabaseAlreadyExists { .. }) => {}
+                    Err(e) => return Err(e),
+                }
                 self.db(&db_name).await.expect("db not inserted")
             }
         };
@@ -450,6 +705,33 @@ impl RemoteServer for RemoteServerImpl {
     }
 }
 
+// Drops the points with timestamps later than `max_time`, or if `clamp` is set
+// sets their timestamps to `max_time`, counting them in `summary`.
+fn guard_future_points<'a>(
+    lines: &[ParsedLine<'a>],
+    max_time: i64,
+    clamp: bool,
+    summary: &mut WriteSummary,
+) -> Vec<ParsedLine<'a>> {
+    lines
+        .iter()
+        .filter_map(|line| match line.timestamp {
+            Some(time) if time > max_time => {
+                if clamp {
+                    summary.future_points_clamped += 1;
+                    let mut line = line.clone();
+                    line.timestamp = Some(max_time);
+                    Some(line)
+                } else {
+                    summary.future_points_rejected += 1;
+                    None
+                }
+            }
+            _ => Some(line.clone()),
+        })
+        .collect()
+}
+
 // location in the store for the configuration file
 fn config_location(id: u32) -> String {
     format!("{}/config.json", id)
@@ -464,6 +746,7 @@ mod tests {
     use futures::TryStreamExt;
     use influxdb_line_protocol::parse_lines;
     use object_store::{InMemory, ObjectStoreIntegration};
+    use query::predicate::TimestampRange;
     use snafu::Snafu;
     use std::sync::Mutex;
 
@@ -522,6 +805,102 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn create_existing_database() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1).await;
+
+        server
+            .create_database("foo", DatabaseRules::default())
+            .await?;
+        let got = server
+            .create_database("foo", DatabaseRules::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(got, Error::DatabaseAlreadyExists { .. }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remove_database() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        server.create_database("foo", rules).await?;
+        let lines: Vec<_> = parse_lines("cpu bar=1 10").map(|l| l.unwrap()).collect();
+        server.write_lines("foo", &lines).await?;
+        let db = server.db(&DatabaseName::new("foo").unwrap()).await.unwrap();
+
+        server.remove_database("foo").await?;
+        assert!(server
+            .db(&DatabaseName::new("foo").unwrap())
+            .await
+            .is_none());
+        assert!(server.db_names().await.is_empty());
+
+        // The removed database can still be queried by those holding it.
+        let results = db.query("select * from cpu").await.unwrap();
+        assert_eq!(results[0].num_rows(), 1);
+
+        let got = server.remove_database("foo").await.unwrap_err();
+        assert!(matches!(got, Error::DatabaseNotFound { .. }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn snapshots() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        server.create_database("foo", rules).await?;
+        let db_name = DatabaseName::new("foo").unwrap();
+        let db = server.db(&db_name).await.unwrap();
+
+        let chunk = db.rollover_partition("key").await.unwrap();
+        let snapshot =
+            crate::snapshot::snapshot_chunk("meta", "data", server.store.clone(), chunk, None)
+                .unwrap();
+        server.add_snapshot(&db_name, snapshot.clone()).await;
+
+        let found = server.snapshot(snapshot.id).await.unwrap();
+        assert_eq!(found.id, snapshot.id);
+        assert_eq!(found.chunk_meta.key, "key");
+        assert!(server.snapshot(Uuid::new_v4()).await.is_none());
+
+        let ids: Vec<_> = server
+            .snapshots(&db_name)
+            .await
+            .iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        assert_eq!(ids, vec![snapshot.id]);
+        assert!(server
+            .snapshots(&DatabaseName::new("bar").unwrap())
+            .await
+            .is_empty());
+
+        // The snapshots of a database are forgotten when it's removed
+        server.remove_database("foo").await?;
+        assert!(server.snapshot(snapshot.id).await.is_none());
+        assert!(server.snapshots(&db_name).await.is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn writes_local() -> Result {
         let manager = TestConnectionManager::new();
@@ -555,6 +934,73 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn writes_beyond_max_future_skew() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1).await;
+
+        let now = Utc::now().timestamp_nanos();
+        let hour = 3_600_000_000_000;
+        let lp = format!(
+            "cpu bar=1 {}\ncpu bar=2 {}\ncpu bar=3 {}",
+            now - hour,
+            now + hour / 2,
+            now + 24 * hour
+        );
+        let lines = parsed_lines(&lp);
+
+        // points beyond the skew are rejected.
+        let rules = DatabaseRules {
+            store_locally: true,
+            max_future_skew: Some(std::time::Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        server.create_database("foo", rules).await?;
+
+        let summary = server.write_lines("foo", &lines).await.unwrap();
+        assert_eq!(
+            summary,
+            WriteSummary {
+                points_written: 2,
+                future_points_rejected: 1,
+                future_points_clamped: 0,
+            }
+        );
+
+        let results = server
+            .query_local("foo", "select bar from cpu")
+            .await
+            .unwrap();
+        assert_eq!(csv_string(results), "bar\n1.0\n2.0\n");
+
+        // or clamped to the latest allowed time.
+        let rules = DatabaseRules {
+            store_locally: true,
+            max_future_skew: Some(std::time::Duration::from_secs(3600)),
+            clamp_future_writes: true,
+            ..Default::default()
+        };
+        server.create_database("bar", rules).await?;
+
+        let summary = server.write_lines("bar", &lines).await.unwrap();
+        assert_eq!(
+            summary,
+            WriteSummary {
+                points_written: 3,
+                future_points_rejected: 0,
+                future_points_clamped: 1,
+            }
+        );
+
+        let query = format!("select bar from cpu where time <= {}", now + 2 * hour);
+        let results = server.query_local("bar", &query).await.unwrap();
+        assert_eq!(csv_string(results), "bar\n1.0\n2.0\n3.0\n");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn replicate_to_single_group() -> Result {
         let mut manager = TestConnectionManager::new();
@@ -614,6 +1060,70 @@ partition_key:
         Ok(())
     }
 
+    #[tokio::test]
+    async fn deletes_are_replicated_and_replayed() -> Result {
+        let mut manager = TestConnectionManager::new();
+        let remote = Arc::new(TestRemoteServer::default());
+        let remote_id = "serverA";
+        manager
+            .remotes
+            .insert(remote_id.to_string(), remote.clone());
+
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        let mut server = Server::new(manager, store);
+        server.set_id(1).await;
+        let host_group_id = "az1".to_string();
+        let rules = DatabaseRules {
+            store_locally: true,
+            replication: vec![host_group_id.clone()],
+            replication_count: 1,
+            ..Default::default()
+        };
+        server
+            .create_host_group(host_group_id.clone(), vec![remote_id.to_string()])
+            .await
+            .unwrap();
+        let db_name = "foo";
+        server.create_database(db_name, rules).await.unwrap();
+
+        let lines = parsed_lines("cpu,host=A bar=1 10\ncpu,host=B bar=2 20");
+        server.write_lines(db_name, &lines).await.unwrap();
+        let tombstone = Tombstone::new(TimestampRange::new(0, 100)).with_tag("host", "A");
+        server.delete(db_name, &tombstone).await.unwrap();
+
+        let expected = "bar,host,time\n2.0,B,20\n";
+        let results = server
+            .query_local(db_name, "select * from cpu")
+            .await
+            .unwrap();
+        assert_eq!(to_csv(results), expected);
+
+        // Replaying the replicated writes, delete included, gives the same
+        // results
+        let writes = remote.writes.lock().unwrap().get(db_name).unwrap().clone();
+        assert_eq!(writes.len(), 2);
+        let replayed = MutableBufferDb::new(db_name);
+        for write in &writes {
+            replayed.store_replicated_write(write).await.unwrap();
+        }
+        let results = replayed.query("select * from cpu").await.unwrap();
+        assert_eq!(to_csv(results), expected);
+
+        Ok(())
+    }
+
+    fn to_csv(batches: Vec<RecordBatch>) -> String {
+        let mut sw = StringWriter::new();
+        {
+            let mut writer = csv::Writer::new(&mut sw);
+            for batch in batches {
+                writer.write(&batch).unwrap();
+            }
+        }
+        sw.to_string()
+    }
+
     #[tokio::test]
     async fn sends_all_to_subscriber() -> Result {
         let mut manager = TestConnectionManager::new();
@@ -713,7 +1223,7 @@ partition_key:
             .await
             .unwrap();
 
-        let config = r#"{"databases":{"foo":{"partition_template":{"parts":[]},"store_locally":false,"replication":["az1"],"replication_count":1,"replication_queue_max_size":0,"subscriptions":[],"query_local":false,"primary_query_group":null,"secondary_query_groups":[],"read_only_partitions":[],"wal_buffer_config":null}},"host_groups":{"az1":{"id":"az1","hosts":["serverA"]}}}"#;
+        let config = r#"{"databases":{"foo":{"partition_template":{"parts":[]},"store_locally":false,"replication":["az1"],"replication_count":1,"replication_queue_max_size":0,"subscriptions":[],"query_local":false,"primary_query_group":null,"secondary_query_groups":[],"read_only_partitions":[],"wal_buffer_config":null,"max_future_skew":null,"clamp_future_writes":false}},"host_groups":{"az1":{"id":"az1","hosts":["serverA"]}}}"#;
         let read_data = std::str::from_utf8(&*read_data).unwrap();
         println!("\n\n{}\n", read_data);
         assert_eq!(read_data, config);
@@ -793,4 +1303,15 @@ partition_key:
     fn parsed_lines(lp: &str) -> Vec<ParsedLine<'_>> {
         parse_lines(lp).map(|l| l.unwrap()).collect()
     }
+
+    fn csv_string(results: Vec<RecordBatch>) -> String {
+        let mut sw = StringWriter::new();
+        {
+            let mut writer = csv::Writer::new(&mut sw);
+            for r in results {
+                writer.write(&r).unwrap();
+            }
+        }
+        sw.to_string()
+    }
 }
@@ -1,10 +1,18 @@
 //! This module contains code for snapshotting a database chunk to Parquet
 //! files in object storage.
 use arrow_deps::{
-    arrow::record_batch::RecordBatch,
+    arrow::{
+        array::{Array, Int64Array},
+        compute,
+        datatypes::{DataType, TimeUnit},
+        record_batch::RecordBatch,
+    },
     parquet::{self, arrow::ArrowWriter, file::writer::TryClone},
 };
-use data_types::partition_metadata::{Partition as PartitionMeta, Table};
+use data_types::{
+    partition_metadata::{Partition as PartitionMeta, Table},
+    TIME_COLUMN_NAME,
+};
 use object_store::ObjectStore;
 use query::PartitionChunk;
 
@@ -12,7 +20,9 @@ use std::io::{Cursor, Seek, SeekFrom, Write};
 use std::sync::{Arc, Mutex};
 
 use bytes::Bytes;
-use snafu::{ResultExt, Snafu};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
 use tokio::sync::oneshot;
 use tracing::{error, info};
 use uuid::Uuid;
@@ -48,36 +58,165 @@ pub enum Error {
     #[snafu(display("Error writing to object store: {}", source))]
     WritingToObjectStore { source: object_store::Error },
 
+    #[snafu(display(
+        "Column {} in table {} has unsupported type {:?}",
+        column_name,
+        table_name,
+        data_type
+    ))]
+    UnsupportedColumnType {
+        table_name: String,
+        column_name: String,
+        data_type: DataType,
+    },
+
     #[snafu(display("Stopped early"))]
     StoppedEarly,
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Metadata describing the chunk captured by a snapshot. It is written
+/// alongside the snapshot's data so that a loader can reconstruct catalog
+/// entries without having to scan the data files.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ChunkMeta {
+    /// The partition key of the chunk
+    pub key: String,
+    /// The id of the chunk within its partition
+    pub id: u64,
+    /// The [min, max] timestamps (inclusive) of all rows in the chunk, or
+    /// `None` if the chunk contains no timestamps.
+    pub time_range: Option<(i64, i64)>,
+    /// The total number of rows across all tables in the chunk
+    pub row_count: usize,
+    /// A stable fingerprint of the table names, column names and column
+    /// types in the chunk.
+    pub schema_fingerprint: u64,
+    /// The time the snapshot was created, as nanoseconds since the epoch
+    pub created_at: i64,
+}
+
+impl ChunkMeta {
+    fn new(key: String, id: u64) -> Self {
+        Self {
+            key,
+            id,
+            created_at: Utc::now().timestamp_nanos(),
+            ..Default::default()
+        }
+    }
+
+    // Updates the metadata with the contents of a table's record batches.
+    fn add_table(&mut self, table_name: &str, batches: &[RecordBatch]) -> Result<()> {
+        if let Some(batch) = batches.first() {
+            let mut fingerprint = Fingerprint(self.schema_fingerprint);
+            fingerprint.write(table_name.as_bytes());
+            for field in batch.schema().fields() {
+                let type_name =
+                    data_type_name(field.data_type()).context(UnsupportedColumnType {
+                        table_name,
+                        column_name: field.name(),
+                        data_type: field.data_type().clone(),
+                    })?;
+                fingerprint.write(field.name().as_bytes());
+                fingerprint.write(type_name.as_bytes());
+            }
+            self.schema_fingerprint = fingerprint.0;
+        }
+
+        for batch in batches {
+            self.row_count += batch.num_rows();
+
+            let times = batch
+                .schema()
+                .index_of(TIME_COLUMN_NAME)
+                .ok()
+                .and_then(|i| batch.column(i).as_any().downcast_ref::<Int64Array>());
+
+            if let Some(times) = times {
+                if let (Some(min), Some(max)) = (compute::min(times), compute::max(times)) {
+                    self.time_range = match self.time_range {
+                        Some((lo, hi)) => Some((lo.min(min), hi.max(max))),
+                        None => Some((min, max)),
+                    };
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The name of a column type included in the schema fingerprint. Unlike the
+// `Debug` output of `DataType`, these names are fixed, so fingerprints don't
+// change when arrow does. `None` if the type can't be fingerprinted.
+fn data_type_name(data_type: &DataType) -> Option<&'static str> {
+    let name = match data_type {
+        DataType::Boolean => "boolean",
+        DataType::Int8 => "int8",
+        DataType::Int16 => "int16",
+        DataType::Int32 => "int32",
+        DataType::Int64 => "int64",
+        DataType::UInt8 => "uint8",
+        DataType::UInt16 => "uint16",
+        DataType::UInt32 => "uint32",
+        DataType::UInt64 => "uint64",
+        DataType::Float32 => "float32",
+        DataType::Float64 => "float64",
+        DataType::Utf8 => "utf8",
+        DataType::LargeUtf8 => "large_utf8",
+        DataType::Binary => "binary",
+        DataType::LargeBinary => "large_binary",
+        DataType::Timestamp(TimeUnit::Second, None) => "timestamp_s",
+        DataType::Timestamp(TimeUnit::Millisecond, None) => "timestamp_ms",
+        DataType::Timestamp(TimeUnit::Microsecond, None) => "timestamp_us",
+        DataType::Timestamp(TimeUnit::Nanosecond, None) => "timestamp_ns",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// An FNV-1a hasher, used because its output, unlike that of the std library
+// `DefaultHasher`, is stable across Rust releases.
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn write(&mut self, bytes: &[u8]) {
+        if self.0 == 0 {
+            self.0 = Self::OFFSET;
+        }
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+/// A snapshot of a chunk, written by `snapshot_chunk`. The snapshot doesn't
+/// keep a reference to the chunk, which is released once the snapshot has
+/// finished.
 #[derive(Debug)]
-pub struct Snapshot<T>
-where
-    T: Send + Sync + 'static + PartitionChunk,
-{
+pub struct Snapshot {
     pub id: Uuid,
     pub partition_meta: PartitionMeta,
+    pub chunk_meta: Mutex<ChunkMeta>,
     pub metadata_path: String,
     pub data_path: String,
     store: Arc<ObjectStore>,
-    partition: Arc<T>,
     status: Mutex<Status>,
 }
 
-impl<T> Snapshot<T>
-where
-    T: Send + Sync + 'static + PartitionChunk,
-{
+impl Snapshot {
     fn new(
         partition_key: String,
+        chunk_id: u64,
         metadata_path: String,
         data_path: String,
         store: Arc<ObjectStore>,
-        partition: Arc<T>,
         tables: Vec<Table>,
     ) -> Self {
         let table_states = vec![TableState::NotStarted; tables.len()];
@@ -87,16 +226,18 @@ where
             ..Default::default()
         };
 
+        let chunk_meta = ChunkMeta::new(partition_key.clone(), chunk_id);
+
         Self {
             id: Uuid::new_v4(),
             partition_meta: PartitionMeta {
                 key: partition_key,
                 tables,
             },
+            chunk_meta: Mutex::new(chunk_meta),
             metadata_path,
             data_path,
             store,
-            partition,
             status: Mutex::new(status),
         }
     }
@@ -128,6 +269,42 @@ where
         status.meta_written = true;
     }
 
+    /// Returns the metadata of the chunk being snapshot. The time range, row
+    /// count and schema fingerprint are only complete once the snapshot has
+    /// finished.
+    pub fn chunk_meta(&self) -> ChunkMeta {
+        self.chunk_meta.lock().expect("mutex poisoned").clone()
+    }
+
+    /// Returns how far the snapshot has got.
+    pub fn state(&self) -> SnapshotState {
+        let status = self.status.lock().expect("mutex poisoned");
+
+        if status.error.is_some() {
+            SnapshotState::Errored
+        } else if status.meta_written {
+            SnapshotState::Complete
+        } else {
+            SnapshotState::Running
+        }
+    }
+
+    /// Returns a description of the error that stopped the snapshot, if any.
+    pub fn error(&self) -> Option<String> {
+        let status = self.status.lock().expect("mutex poisoned");
+        status.error.as_ref().map(ToString::to_string)
+    }
+
+    /// Returns a summary of the snapshot's progress so far.
+    pub fn summary(&self) -> SnapshotSummary {
+        SnapshotSummary {
+            id: self.id,
+            state: self.state(),
+            error: self.error(),
+            chunk_meta: self.chunk_meta(),
+        }
+    }
+
     pub fn finished(&self) -> bool {
         let status = self.status.lock().expect("mutex poisoned");
 
@@ -142,14 +319,22 @@ where
         status.stop_on_next_update
     }
 
-    async fn run(&self, notify: Option<oneshot::Sender<()>>) -> Result<()> {
+    async fn run<T>(&self, partition: &T) -> Result<()>
+    where
+        T: PartitionChunk,
+    {
         while let Some((pos, table_name)) = self.next_table() {
             let mut batches = Vec::new();
-            self.partition
+            partition
                 .table_to_arrow(&mut batches, table_name, &[])
                 .map_err(|e| Box::new(e) as _)
                 .context(PartitionError)?;
 
+            self.chunk_meta
+                .lock()
+                .expect("mutex poisoned")
+                .add_table(table_name, &batches)?;
+
             let file_name = format!("{}/{}.parquet", &self.data_path, table_name);
             self.write_batches(batches, &file_name).await?;
             self.mark_table_finished(pos);
@@ -162,27 +347,34 @@ where
         let partition_meta_path =
             format!("{}/{}.json", &self.metadata_path, &self.partition_meta.key);
         let json_data = serde_json::to_vec(&self.partition_meta).context(JsonGenerationError)?;
+        self.write_json(json_data, &partition_meta_path).await?;
+
+        // Each chunk in the partition has its own metadata file.
+        let chunk_meta = self.chunk_meta();
+        let chunk_meta_path = format!(
+            "{}/{}.{}.chunk.json",
+            &self.metadata_path, &chunk_meta.key, chunk_meta.id
+        );
+        let json_data = serde_json::to_vec(&chunk_meta).context(JsonGenerationError)?;
+        self.write_json(json_data, &chunk_meta_path).await?;
+
+        self.mark_meta_written();
+
+        Ok(())
+    }
+
+    async fn write_json(&self, json_data: Vec<u8>, file_name: &str) -> Result<()> {
         let data = Bytes::from(json_data);
         let len = data.len();
         let stream_data = std::io::Result::Ok(data);
         self.store
             .put(
-                &partition_meta_path,
+                &file_name,
                 futures::stream::once(async move { stream_data }),
                 len,
             )
             .await
-            .context(WritingToObjectStore)?;
-
-        self.mark_meta_written();
-
-        if let Some(notify) = notify {
-            if let Err(e) = notify.send(()) {
-                error!("error sending notify: {:?}", e);
-            }
-        }
-
-        Ok(())
+            .context(WritingToObjectStore)
     }
 
     async fn write_batches(&self, batches: Vec<RecordBatch>, file_name: &str) -> Result<()> {
@@ -220,6 +412,35 @@ where
     }
 }
 
+/// A summary of the progress of a snapshot, which, unlike the `Snapshot`
+/// itself, can be kept once the snapshot has finished.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotSummary {
+    pub id: Uuid,
+    pub state: SnapshotState,
+    pub error: Option<String>,
+    pub chunk_meta: ChunkMeta,
+}
+
+impl SnapshotSummary {
+    /// Returns true if the snapshot has completed or stopped with an error.
+    pub fn finished(&self) -> bool {
+        self.state != SnapshotState::Running
+    }
+}
+
+/// The progress of a snapshot as a whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotState {
+    /// Tables or metadata are still being written.
+    Running,
+    /// All of the tables and metadata have been written.
+    Complete,
+    /// The snapshot was stopped by an error.
+    Errored,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TableState {
     NotStarted,
@@ -235,13 +456,16 @@ pub struct Status {
     error: Option<Error>,
 }
 
+/// Starts a snapshot of `partition`, which is written in the background. The
+/// partition is released once the snapshot has finished, and `notify` is sent
+/// to if the snapshot completes.
 pub fn snapshot_chunk<T>(
     metadata_path: impl Into<String>,
     data_path: impl Into<String>,
     store: Arc<ObjectStore>,
     partition: Arc<T>,
     notify: Option<oneshot::Sender<()>>,
-) -> Result<Arc<Snapshot<T>>>
+) -> Result<Arc<Snapshot>>
 where
     T: Send + Sync + 'static + PartitionChunk,
 {
@@ -252,10 +476,10 @@ where
 
     let snapshot = Snapshot::new(
         partition.key().to_string(),
+        partition.id(),
         metadata_path.into(),
         data_path.into(),
         store,
-        partition,
         table_stats,
     );
     let snapshot = Arc::new(snapshot);
@@ -267,9 +491,22 @@ where
             "starting snapshot of {} to {}",
             &snapshot.partition_meta.key, &snapshot.data_path
         );
-        if let Err(e) = snapshot.run(notify).await {
-            error!("error running snapshot: {:?}", e);
-            snapshot.set_error(e);
+        let result = snapshot.run(partition.as_ref()).await;
+        // The chunk is only needed while its tables are written
+        drop(partition);
+
+        match result {
+            Ok(()) => {
+                if let Some(notify) = notify {
+                    if let Err(e) = notify.send(()) {
+                        error!("error sending notify: {:?}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("error running snapshot: {:?}", e);
+                snapshot.set_error(e);
+            }
         }
     });
 
@@ -362,6 +599,10 @@ mem,host=A,region=west used=45 1
         .unwrap();
 
         rx.await.unwrap();
+        assert_eq!(snapshot.state(), SnapshotState::Complete);
+        assert_eq!(snapshot.error(), None);
+        // The chunk is released once the snapshot has finished
+        assert_eq!(Arc::strong_count(&chunk), 1);
 
         let summary = store
             .get("/meta/testaroo.json")
@@ -374,6 +615,24 @@ mem,host=A,region=west used=45 1
 
         let meta: PartitionMeta = serde_json::from_slice(&*summary).unwrap();
         assert_eq!(meta, snapshot.partition_meta);
+
+        let chunk_summary = store
+            .get("/meta/testaroo.11.chunk.json")
+            .await
+            .unwrap()
+            .map_ok(|b| bytes::BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .unwrap();
+
+        let chunk_meta: ChunkMeta = serde_json::from_slice(&*chunk_summary).unwrap();
+        assert_eq!(chunk_meta, snapshot.chunk_meta());
+        assert_eq!(chunk_meta.key, "testaroo");
+        assert_eq!(chunk_meta.id, 11);
+        assert_eq!(chunk_meta.time_range, Some((1, 10)));
+        assert_eq!(chunk_meta.row_count, 4);
+        assert_ne!(chunk_meta.schema_fingerprint, 0);
+        assert!(chunk_meta.created_at > 0);
     }
 
     #[test]
@@ -394,19 +653,20 @@ mem,host=A,region=west used=45 1
         ];
 
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
-        let chunk = Arc::new(ChunkWB::new("testaroo", 11));
         let metadata_path = "/meta".to_string();
         let data_path = "/data".to_string();
 
         let snapshot = Snapshot::new(
-            chunk.key.clone(),
+            "testaroo".to_string(),
+            11,
             metadata_path,
             data_path,
             store,
-            chunk,
             tables,
         );
 
+        assert_eq!(snapshot.state(), SnapshotState::Running);
+
         let (pos, name) = snapshot.next_table().unwrap();
         assert_eq!(0, pos);
         assert_eq!("foo", name);
@@ -428,5 +688,13 @@ mem,host=A,region=west used=45 1
         snapshot.mark_table_finished(0);
         snapshot.mark_table_finished(2);
         assert!(snapshot.finished());
+        assert_eq!(snapshot.state(), SnapshotState::Running);
+
+        snapshot.mark_meta_written();
+        assert_eq!(snapshot.state(), SnapshotState::Complete);
+
+        snapshot.set_error(Error::StoppedEarly);
+        assert_eq!(snapshot.state(), SnapshotState::Errored);
+        assert_eq!(snapshot.error(), Some("Stopped early".to_string()));
     }
 }
@@ -7,9 +7,9 @@ use std::sync::Arc;
 
 use crate::server::http_routes;
 use crate::server::rpc::service;
+use crate::server::IdsAsNames;
 use server::server::{ConnectionManagerImpl as ConnectionManager, Server as AppServer};
 
-use hyper::Server;
 use object_store::{self, GoogleCloudStorage, ObjectStore};
 use query::exec::Executor as QueryExecutor;
 
@@ -128,21 +128,58 @@ pub async fn main(logging_level: LoggingLevel, ignore_config_file: bool) -> Resu
 
     // Construct and start up HTTP server
 
-    let router_service = http_routes::router_service(app_server.clone());
+    let router_service = http_routes::router_service(
+        app_server.clone(),
+        config.max_http_request_size,
+        config.http_auth_token.clone(),
+        config.http_cors_allowed_origins.clone(),
+        config.query_timeout,
+        Arc::new(IdsAsNames),
+    );
 
     let bind_addr = config.http_bind_address;
-    let http_server = Server::try_bind(&bind_addr)
-        .context(StartListeningHttp { bind_addr })?
-        .serve(router_service);
+    let (_, http_server) = http_routes::serve(&bind_addr, router_service, shutdown_signal())
+        .context(StartListeningHttp { bind_addr })?;
     info!(bind_address=?bind_addr, "HTTP server listening");
 
+    // Any startup work that must finish before reads return complete data,
+    // such as WAL replay, has to happen before the server is marked ready.
+    app_server.set_ready();
     println!("InfluxDB IOx server ready");
 
-    // Wait for both the servers to complete
-    let (grpc_server, server) = futures::future::join(grpc_server, http_server).await;
-
-    grpc_server.context(ServingRPC)?;
-    server.context(ServingHttp)?;
+    // Serve until the HTTP server has shut down and completed the requests in
+    // flight. The gRPC server, which has no graceful shutdown, stops with it.
+    tokio::select! {
+        result = grpc_server => result.context(ServingRPC)?,
+        result = http_server => result.context(ServingHttp)?,
+    }
+    info!("InfluxDB IOx server shut down");
 
     Ok(())
 }
+
+/// Completes when the process is asked to terminate, by SIGTERM or ctrl-c.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!(error = ?e, "Unable to listen for SIGTERM");
+                futures::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = futures::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate => {},
+    }
+    info!("Shutting down once requests in flight have completed");
+}
@@ -1,4 +1,6 @@
 pub mod http_routes;
+pub mod influxql;
+pub mod metrics;
 pub mod rpc;
 
 use data_types::{DatabaseName, DatabaseNameError};
@@ -13,6 +15,9 @@ pub enum OrgBucketMappingError {
     ))]
     InvalidBucketOrgName { org: String, bucket_name: String },
 
+    #[snafu(display("Invalid org {}, bucket {}: neither may be empty", org, bucket_name,))]
+    EmptyBucketOrgName { org: String, bucket_name: String },
+
     #[snafu(display("Invalid database name: {}", source))]
     InvalidDatabaseName { source: DatabaseNameError },
 }
@@ -28,6 +33,14 @@ pub(crate) fn org_and_bucket_to_database<'a, O: AsRef<str>, B: AsRef<str>>(
 ) -> Result<DatabaseName<'a>, OrgBucketMappingError> {
     const SEPARATOR: char = '_';
 
+    if org.as_ref().is_empty() || bucket.as_ref().is_empty() {
+        return EmptyBucketOrgName {
+            bucket_name: bucket.as_ref(),
+            org: org.as_ref(),
+        }
+        .fail();
+    }
+
     // Ensure neither the org, nor the bucket contain the separator character.
     if org.as_ref().chars().any(|c| c == SEPARATOR)
         || bucket.as_ref().chars().any(|c| c == SEPARATOR)
@@ -44,6 +57,35 @@ pub(crate) fn org_and_bucket_to_database<'a, O: AsRef<str>, B: AsRef<str>>(
     DatabaseName::new(db_name).context(InvalidDatabaseName)
 }
 
+/// Maps the ids of an InfluxDB 2.X org & bucket into an IOx DatabaseName, for
+/// requests that address them by id rather than by name.
+///
+/// The influx cloud maps ids to names in other services, so an implementation
+/// of this can let IOx sit behind that mapping layer.
+pub trait OrgBucketResolver: std::fmt::Debug + Send + Sync {
+    /// Returns the name of the database for the org & bucket, or None if
+    /// either of the ids is unknown.
+    fn database_name(
+        &self,
+        org_id: &str,
+        bucket_id: &str,
+    ) -> Result<Option<DatabaseName<'static>>, OrgBucketMappingError>;
+}
+
+/// The default `OrgBucketResolver`, which maps ids in the same way as names.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IdsAsNames;
+
+impl OrgBucketResolver for IdsAsNames {
+    fn database_name(
+        &self,
+        org_id: &str,
+        bucket_id: &str,
+    ) -> Result<Option<DatabaseName<'static>>, OrgBucketMappingError> {
+        org_and_bucket_to_database(org_id, bucket_id).map(Some)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,6 +106,15 @@ mod tests {
         assert!(matches!(err, OrgBucketMappingError::InvalidBucketOrgName {..}));
     }
 
+    #[test]
+    fn test_org_bucket_map_db_empty() {
+        let err = org_and_bucket_to_database("", "bucket").unwrap_err();
+        assert!(matches!(err, OrgBucketMappingError::EmptyBucketOrgName {..}));
+
+        let err = org_and_bucket_to_database("org", "").unwrap_err();
+        assert!(matches!(err, OrgBucketMappingError::EmptyBucketOrgName {..}));
+    }
+
     #[test]
     fn test_bad_database_name() {
         let err = org_and_bucket_to_database("org!", "bucket?").unwrap_err();
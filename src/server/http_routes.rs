@@ -9,26 +9,54 @@
 //! Long term, we expect to create IOx specific api in terms of
 //! database names and may remove this quasi /v2 API.
 
-use http::header::CONTENT_ENCODING;
-use tracing::{debug, error, info};
+use http::header::{
+    HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION, CONTENT_ENCODING,
+    CONTENT_TYPE, ORIGIN, VARY,
+};
+use tracing::{debug, error, info, warn};
 
-use arrow_deps::arrow;
-use influxdb_line_protocol::parse_lines;
-use query::SQLDatabase;
-use server::server::{ConnectionManager, Server as AppServer};
+use arrow_deps::{
+    arrow::{
+        self,
+        datatypes::{DataType, Schema},
+        record_batch::RecordBatch,
+    },
+    datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream},
+};
+use influxdb_line_protocol::parse_numbered_lines;
+use mutable_buffer::{DatabaseError, Tombstone};
+use query::{
+    exec::Executor,
+    func::window::TIME_BUCKET_UDF_NAME,
+    predicate::{PredicateBuilder, TimestampRange},
+    SQLDatabase, TSDatabase,
+};
+use server::{
+    server::{ConnectionManager, Error as ServerError, Server as AppServer},
+    snapshot::SnapshotSummary,
+};
 
-use super::{org_and_bucket_to_database, OrgBucketMappingError};
+use super::{
+    influxql, metrics::Metrics, org_and_bucket_to_database, OrgBucketMappingError,
+    OrgBucketResolver,
+};
 use bytes::{Bytes, BytesMut};
-use data_types::database_rules::DatabaseRules;
+use data_types::{
+    database_rules::DatabaseRules, DatabaseName, DatabaseNameError, LENGTH_CONSTRAINT,
+};
 use futures::{self, StreamExt};
 use hyper::{Body, Method, Request, Response, StatusCode};
 use routerify::prelude::*;
 use routerify::{Middleware, RequestInfo, Router, RouterService};
-use serde::Deserialize;
-use snafu::{OptionExt, ResultExt, Snafu};
+use serde::{de::DeserializeOwned, Deserialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::fmt::Debug;
+use std::future::Future;
+use std::net::SocketAddr;
 use std::str;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Snafu)]
 pub enum ApplicationError {
@@ -44,8 +72,13 @@ pub enum ApplicationError {
         bucket_name: String,
         source: Box<dyn std::error::Error + Send + Sync>,
     },
-    #[snafu(display("Internal error mapping org & bucket: {}", source))]
-    BucketMappingError { source: OrgBucketMappingError },
+    #[snafu(display("Invalid database name '{}': {}", name, reason))]
+    InvalidDatabaseName { name: String, reason: String },
+
+    #[snafu(display(
+        "Expected either the org and bucket or the orgID and bucketID query parameters"
+    ))]
+    ExpectedOrgAndBucket {},
 
     #[snafu(display(
         "Internal error writing points into org {}, bucket {}:  {}",
@@ -73,6 +106,9 @@ pub enum ApplicationError {
     #[snafu(display("Bucket {} not found in org {}", bucket, org))]
     BucketNotFound { org: String, bucket: String },
 
+    #[snafu(display("Table {} not found", table))]
+    TableNotFound { table: String },
+
     #[snafu(display("Body exceeds limit of {} bytes", max_body_size))]
     RequestSizeExceeded { max_body_size: usize },
 
@@ -90,15 +126,114 @@ pub enum ApplicationError {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
-    #[snafu(display("Invalid request body '{}': {}", request_body, source))]
+    #[snafu(display("Expected either measurement or sql_query in read request"))]
+    ExpectedMeasurement {},
+
+    #[snafu(display("Invalid name '{}': only letters, digits and '_' are supported", name))]
+    InvalidName { name: String },
+
+    #[snafu(display(
+        "Invalid predicate '{}': expected <tag>=<value> or <tag>!=<value>",
+        predicate
+    ))]
+    InvalidPredicate { predicate: String },
+
+    #[snafu(display(
+        "Invalid timestamp '{}': expected an RFC3339 time or nanoseconds since the epoch",
+        timestamp
+    ))]
+    InvalidTimestamp { timestamp: String },
+
+    #[snafu(display(
+        "Invalid window aggregate: invalid duration '{}': expected a positive number of seconds, minutes or hours, such as 30s, 1m or 2h",
+        value
+    ))]
+    InvalidWindowDuration { value: String },
+
+    #[snafu(display(
+        "Invalid window aggregate: invalid aggregate '{}': expected <field>:<function>, where the function is one of mean, sum, count, min or max",
+        spec
+    ))]
+    InvalidWindowAggregate { spec: String },
+
+    #[snafu(display("Invalid window aggregate: expected an aggregate parameter for each window"))]
+    ExpectedWindowAggregate {},
+
+    #[snafu(display("Invalid window aggregate: expected an every parameter with the aggregates"))]
+    ExpectedWindowEvery {},
+
+    #[snafu(display("Invalid window aggregate: windows can't be applied to a sql_query"))]
+    WindowWithSqlQuery {},
+
+    #[snafu(display(
+        "Invalid window aggregate: column '{}' not found in measurement '{}'",
+        column,
+        measurement
+    ))]
+    WindowColumnNotFound { column: String, measurement: String },
+
+    #[snafu(display(
+        "Invalid window aggregate: {} can't be applied to column '{}' of type {:?}",
+        function,
+        column,
+        data_type
+    ))]
+    UnsupportedWindowAggregate {
+        function: &'static str,
+        column: String,
+        data_type: DataType,
+    },
+
+    #[snafu(display(
+        "Internal error deleting points from org {}, bucket {}:  {}",
+        org,
+        bucket_name,
+        source
+    ))]
+    DeletingPoints {
+        org: String,
+        bucket_name: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display(
+        "Invalid delete predicate '{}': expected <tag>=\"<value>\" conditions joined by AND",
+        predicate
+    ))]
+    InvalidDeletePredicate { predicate: String },
+
+    #[snafu(display("Query did not complete within {:?}", timeout))]
+    QueryTimeout { timeout: Duration },
+
+    #[snafu(display("Error parsing InfluxQL query '{}': {}", query, source))]
+    ParsingInfluxQL {
+        query: String,
+        source: influxql::Error,
+    },
+
+    #[snafu(display(
+        "Invalid request body '{}' at line {}, column {}: {}",
+        request_body,
+        line,
+        column,
+        source
+    ))]
     InvalidRequestBody {
         request_body: String,
+        line: usize,
+        column: usize,
         source: serde_json::error::Error,
     },
 
     #[snafu(display("Invalid content encoding: {}", content_encoding))]
     InvalidContentEncoding { content_encoding: String },
 
+    #[snafu(display(
+        "Invalid content type '{}': expected text/plain line protocol",
+        content_type
+    ))]
+    InvalidContentType { content_type: String },
+
     #[snafu(display("Error reading request header '{}' as Utf8: {}", header_name, source))]
     ReadingHeaderAsUtf8 {
         header_name: String,
@@ -119,6 +254,15 @@ pub enum ApplicationError {
     #[snafu(display("Error decompressing body as gzip: {}", source))]
     ReadingBodyAsGzip { source: std::io::Error },
 
+    #[snafu(display("Error decompressing body as zstd: {}", source))]
+    ReadingBodyAsZstd { source: std::io::Error },
+
+    #[snafu(display("Invalid precision '{}': expected one of ns, us, ms or s", precision))]
+    InvalidPrecision { precision: String },
+
+    #[snafu(display("Timestamp {} with precision {} is out of range", timestamp, precision))]
+    TimestampOutOfRange { timestamp: i64, precision: String },
+
     #[snafu(display("No handler for {:?} {}", method, path))]
     RouteNotFound { method: Method, path: String },
 
@@ -130,30 +274,131 @@ pub enum ApplicationError {
 
     #[snafu(display("Error generating json response: {}", source))]
     JsonGenerationError { source: serde_json::Error },
+
+    #[snafu(display("Error formatting query results: {}", source))]
+    FormattingResults { source: arrow::error::ArrowError },
+
+    #[snafu(display("Error compressing response as gzip: {}", source))]
+    CompressingResponse { source: std::io::Error },
+
+    #[snafu(display("Error sending response body: {}", source))]
+    SendingResults { source: hyper::error::Error },
+
+    #[snafu(display("Error encoding metrics: {}", source))]
+    EncodingMetrics { source: prometheus::Error },
+
+    #[snafu(display("Server is not yet ready to serve reads"))]
+    NotReady {},
+
+    #[snafu(display(
+        "Unauthorized: a valid token must be provided with an 'Authorization: Token <token>' header"
+    ))]
+    Unauthorized {},
+
+    #[snafu(display("Partition {} not found in org {}, bucket {}", key, org, bucket))]
+    PartitionNotFound {
+        org: String,
+        bucket: String,
+        key: String,
+    },
+
+    #[snafu(display("Database {} not found", name))]
+    DatabaseNotFound { name: String },
+
+    #[snafu(display(
+        "Measurement {} not found in org {}, bucket {}",
+        measurement,
+        org,
+        bucket
+    ))]
+    MeasurementNotFound {
+        org: String,
+        bucket: String,
+        measurement: String,
+    },
+
+    #[snafu(display(
+        "Error snapshotting partition {} of database {}: {}",
+        key,
+        database,
+        source
+    ))]
+    SnapshotError {
+        database: String,
+        key: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Invalid snapshot id '{}': {}", id, source))]
+    InvalidSnapshotId { id: String, source: uuid::Error },
+
+    #[snafu(display("Snapshot {} not found", id))]
+    SnapshotNotFound { id: uuid::Uuid },
+
+    #[snafu(display("Database {} already exists", name))]
+    DatabaseAlreadyExists { name: String },
+
+    #[snafu(display("Invalid rules for database {}: {}", name, source))]
+    InvalidDatabaseRules { name: String, source: ServerError },
 }
 
 impl ApplicationError {
     pub fn response(&self) -> Result<Response<Body>, Self> {
         Ok(match self {
             Self::BucketByName { .. } => self.internal_error(),
-            Self::BucketMappingError { .. } => self.internal_error(),
+            Self::InvalidDatabaseName { .. } => self.bad_request(),
+            Self::ExpectedOrgAndBucket { .. } => self.bad_request(),
             Self::WritingPoints { .. } => self.internal_error(),
             Self::Query { .. } => self.internal_error(),
             Self::QueryError { .. } => self.bad_request(),
+            Self::ParsingInfluxQL { .. } => self.bad_request(),
+            Self::QueryTimeout { .. } => self.gateway_timeout(),
+            Self::InvalidDeletePredicate { .. } => self.bad_request(),
+            Self::DeletingPoints { .. } => self.internal_error(),
+            Self::ExpectedMeasurement { .. } => self.bad_request(),
+            Self::InvalidName { .. } => self.bad_request(),
+            Self::InvalidPredicate { .. } => self.bad_request(),
+            Self::InvalidTimestamp { .. } => self.bad_request(),
+            Self::InvalidWindowDuration { .. } => self.bad_request(),
+            Self::InvalidWindowAggregate { .. } => self.bad_request(),
+            Self::ExpectedWindowAggregate { .. } => self.bad_request(),
+            Self::ExpectedWindowEvery { .. } => self.bad_request(),
+            Self::WindowWithSqlQuery { .. } => self.bad_request(),
+            Self::WindowColumnNotFound { .. } => self.bad_request(),
+            Self::UnsupportedWindowAggregate { .. } => self.bad_request(),
             Self::BucketNotFound { .. } => self.not_found(),
+            Self::TableNotFound { .. } => self.not_found_with_reason(),
             Self::RequestSizeExceeded { .. } => self.bad_request(),
             Self::ExpectedQueryString { .. } => self.bad_request(),
             Self::InvalidQueryString { .. } => self.bad_request(),
             Self::InvalidRequestBody { .. } => self.bad_request(),
             Self::InvalidContentEncoding { .. } => self.bad_request(),
+            Self::InvalidContentType { .. } => self.bad_request(),
             Self::ReadingHeaderAsUtf8 { .. } => self.bad_request(),
             Self::ReadingBody { .. } => self.bad_request(),
             Self::ReadingBodyAsUtf8 { .. } => self.bad_request(),
             Self::ParsingLineProtocol { .. } => self.bad_request(),
             Self::ReadingBodyAsGzip { .. } => self.bad_request(),
+            Self::ReadingBodyAsZstd { .. } => self.bad_request(),
+            Self::InvalidPrecision { .. } => self.bad_request(),
+            Self::TimestampOutOfRange { .. } => self.bad_request(),
             Self::RouteNotFound { .. } => self.not_found(),
             Self::DatabaseError { .. } => self.internal_error(),
             Self::JsonGenerationError { .. } => self.internal_error(),
+            Self::FormattingResults { .. } => self.internal_error(),
+            Self::CompressingResponse { .. } => self.internal_error(),
+            Self::SendingResults { .. } => self.internal_error(),
+            Self::EncodingMetrics { .. } => self.internal_error(),
+            Self::NotReady { .. } => self.service_unavailable(),
+            Self::Unauthorized { .. } => self.unauthorized(),
+            Self::PartitionNotFound { .. } => self.not_found(),
+            Self::DatabaseNotFound { .. } => self.not_found(),
+            Self::MeasurementNotFound { .. } => self.not_found(),
+            Self::SnapshotError { .. } => self.internal_error(),
+            Self::InvalidSnapshotId { .. } => self.bad_request(),
+            Self::SnapshotNotFound { .. } => self.not_found(),
+            Self::DatabaseAlreadyExists { .. } => self.conflict(),
+            Self::InvalidDatabaseRules { .. } => self.bad_request(),
         })
     }
 
@@ -171,6 +416,34 @@ impl ApplicationError {
             .unwrap()
     }
 
+    fn unauthorized(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn service_unavailable(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn gateway_timeout(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::GATEWAY_TIMEOUT)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn conflict(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(self.body())
+            .unwrap()
+    }
+
     fn not_found(&self) -> Response<Body> {
         Response::builder()
             .status(StatusCode::NOT_FOUND)
@@ -178,45 +451,294 @@ impl ApplicationError {
             .unwrap()
     }
 
+    fn not_found_with_reason(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(self.body())
+            .unwrap()
+    }
+
     fn body(&self) -> Body {
         let json = serde_json::json!({"error": self.to_string()}).to_string();
         Body::from(json)
     }
 }
 
-const MAX_SIZE: usize = 10_485_760; // max write request size of 10MB
+/// The default maximum size of a request body, and of it once decompressed:
+/// 10MB.
+pub const DEFAULT_MAX_REQUEST_SIZE: usize = 10_485_760;
+
+// Response bodies smaller than this aren't compressed, as there's little to
+// gain from it.
+const MIN_COMPRESSED_RESPONSE_SIZE: usize = 1024;
+
+// The maximum size of request bodies, held in the router's data.
+#[derive(Debug, Clone, Copy)]
+struct MaxRequestSize(usize);
+
+/// The default time a query may take, including formatting its results,
+/// before it's abandoned: 30s.
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The time queries may take, held in the router's data.
+#[derive(Debug, Clone, Copy)]
+struct QueryTimeout(Duration);
+
+// The time by which a query must have finished.
+#[derive(Debug, Clone, Copy)]
+struct QueryDeadline {
+    timeout: Duration,
+    deadline: tokio::time::Instant,
+}
+
+impl QueryDeadline {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            deadline: tokio::time::Instant::now() + timeout,
+        }
+    }
+
+    // Runs part of a query, failing with `QueryTimeout` if the deadline
+    // passes first.
+    async fn run<T>(
+        &self,
+        query: impl Future<Output = Result<T, ApplicationError>>,
+    ) -> Result<T, ApplicationError> {
+        let timeout = self.timeout;
+        tokio::time::timeout_at(self.deadline, query)
+            .await
+            .map_err(|_| ApplicationError::QueryTimeout { timeout })?
+    }
+}
+
+// The maximum number of characters of a request body that are echoed back to
+// the client in an error message.
+const MAX_ECHOED_BODY_CHARS: usize = 256;
 
-fn router<M>(server: Arc<AppServer<M>>) -> Router<Body, ApplicationError>
+// Routes that can be used without the token, so that probes needn't be
+// configured with it.
+const UNAUTHENTICATED_PATHS: &[&str] = &["/ping", "/health/ready"];
+
+fn router<M>(
+    server: Arc<AppServer<M>>,
+    max_request_size: usize,
+    auth_token: Option<String>,
+    cors_allowed_origins: Vec<String>,
+    query_timeout: Duration,
+    resolver: Arc<dyn OrgBucketResolver>,
+) -> Router<Body, ApplicationError>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
+    let auth_token: Option<Arc<str>> = auth_token.map(Into::into);
+    let error_auth_token = auth_token.clone();
+    let cors = Arc::new(Cors {
+        allowed_origins: cors_allowed_origins,
+    });
+
     // Create a router and specify the the handlers.
-    Router::builder()
+    let builder = Router::builder()
         .data(server)
+        .data(MaxRequestSize(max_request_size))
+        .data(QueryTimeout(query_timeout))
+        .data(resolver)
+        .data(Arc::new(Metrics::new()))
         .middleware(Middleware::pre(|req| async move {
             info!(request = ?req, "Processing request");
             Ok(req)
         }))
+        .middleware(Middleware::pre(move |req| {
+            let auth_token = auth_token.clone();
+            async move {
+                ensure!(
+                    is_authorized(
+                        req.method(),
+                        req.uri().path(),
+                        req.headers(),
+                        auth_token.as_deref()
+                    ),
+                    Unauthorized
+                );
+                Ok(req)
+            }
+        }))
         .middleware(Middleware::post(|res| async move {
             info!(response = ?res, "Successfully processed request");
             Ok(res)
-        })) // this endpoint is for API backward compatibility with InfluxDB 2.x
+        }));
+
+    // Browsers only send CORS preflight requests, and only read CORS headers,
+    // if allowed origins are configured.
+    let builder = if cors.is_enabled() {
+        builder
+            .middleware(Middleware::post_with_info(move |res, req| {
+                let cors = cors.clone();
+                async move { Ok(cors.add_headers(res, req.headers())) }
+            }))
+            .options("/*", cors_preflight)
+    } else {
+        builder
+    };
+
+    builder // this endpoint is for API backward compatibility with InfluxDB 2.x
         .post("/api/v2/write", write_handler::<M>)
+        .post("/api/v2/delete", delete_handler::<M>)
         .get("/ping", ping)
+        .get("/health/ready", health_ready::<M>)
+        .get("/metrics", metrics::<M>)
+        .get("/api/v2/capabilities", capabilities)
         .get("/api/v2/read", read_handler::<M>)
+        .post("/api/v2/read", read_handler::<M>)
+        .get("/query", influxql_query_handler::<M>)
+        .post("/query", influxql_query_handler::<M>)
+        .get("/api/v1/databases", list_databases_handler::<M>)
         .get("/api/v1/partitions", list_partitions_handler::<M>)
+        .get("/api/v1/chunks", list_chunks_handler::<M>)
+        .get("/api/v1/chunks/:key", chunk_summaries_handler::<M>)
+        .get("/api/v1/time_range", time_range_handler::<M>)
+        .get("/api/v1/explain", explain_handler::<M>)
         .post("/api/v1/snapshot", snapshot_partition_handler::<M>)
+        .get("/api/v1/snapshot/:id", snapshot_status_handler::<M>)
+        .get("/api/v1/snapshots", list_snapshots_handler::<M>)
+        .put("/api/v1/databases/:name", create_database_handler::<M>)
+        .delete("/api/v1/databases/:name", delete_database_handler::<M>)
+        .patch(
+            "/api/v2/databases/:name/rules",
+            update_database_rules_handler::<M>,
+        )
         // Specify the error handler to handle any errors caused by
         // a route or any middleware.
-        .err_handler_with_info(error_handler)
+        .err_handler_with_info(move |err, req| error_handler(err, req, error_auth_token.clone()))
         .build()
         .unwrap()
 }
 
+// Determines if a request may be served: either no token is configured, the
+// route doesn't require it, or the request has an `Authorization: Token
+// <token>` header with the configured token, as InfluxDB 2.x clients send.
+// Browsers don't send credentials with CORS preflight requests, so OPTIONS
+// requests don't require the token either.
+fn is_authorized(
+    method: &Method,
+    path: &str,
+    headers: &HeaderMap,
+    auth_token: Option<&str>,
+) -> bool {
+    let auth_token = match auth_token {
+        Some(auth_token) if method != Method::OPTIONS && !UNAUTHENTICATED_PATHS.contains(&path) => {
+            auth_token
+        }
+        _ => return true,
+    };
+
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Token "))
+        .map_or(false, |token| constant_time_eq(token.trim(), auth_token))
+}
+
+// Compares strings in a time that depends only on their lengths, so that a
+// token can't be guessed a character at a time from response times.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+// The methods and request headers browsers are told cross-origin requests may
+// use.
+const CORS_ALLOWED_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
+const CORS_ALLOWED_HEADERS: &str =
+    "Accept, Accept-Encoding, Authorization, Content-Encoding, Content-Type";
+
+/// The origins from which browsers may call the API, for dashboards served
+/// from elsewhere. `*` allows any origin, and if there are none, CORS is
+/// disabled.
+#[derive(Debug)]
+struct Cors {
+    allowed_origins: Vec<String>,
+}
+
+impl Cors {
+    fn is_enabled(&self) -> bool {
+        !self.allowed_origins.is_empty()
+    }
+
+    // The value of the `Access-Control-Allow-Origin` header for a request
+    // from `origin`, if it's allowed.
+    fn allow_origin(&self, origin: &str) -> Option<&str> {
+        self.allowed_origins.iter().find_map(|allowed| {
+            if allowed == "*" {
+                Some("*")
+            } else if allowed == origin {
+                Some(origin)
+            } else {
+                None
+            }
+        })
+    }
+
+    // Adds the CORS headers to the response to a request with the given
+    // headers, if it comes from an allowed origin.
+    fn add_headers(&self, mut res: Response<Body>, req_headers: &HeaderMap) -> Response<Body> {
+        let allow_origin = req_headers
+            .get(ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .and_then(|origin| self.allow_origin(origin))
+            .and_then(|origin| HeaderValue::from_str(origin).ok());
+
+        let headers = res.headers_mut();
+        // The response depends on the origin unless every origin is allowed.
+        if !self.allowed_origins.iter().any(|allowed| allowed == "*") {
+            headers.append(VARY, HeaderValue::from_static("Origin"));
+        }
+        if let Some(allow_origin) = allow_origin {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+            headers.insert(
+                ACCESS_CONTROL_ALLOW_METHODS,
+                HeaderValue::from_static(CORS_ALLOWED_METHODS),
+            );
+            headers.insert(
+                ACCESS_CONTROL_ALLOW_HEADERS,
+                HeaderValue::from_static(CORS_ALLOWED_HEADERS),
+            );
+        }
+        res
+    }
+}
+
+// Answers CORS preflight requests. The CORS headers themselves are added by
+// the router's middleware.
+async fn cors_preflight(_: Request<Body>) -> Result<Response<Body>, ApplicationError> {
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
 // the Routerify error handler. This should be the handler of last resort.
 // Errors should be handled with responses built in the individual handlers for
 // specific ApplicationError(s)
-async fn error_handler(err: routerify::Error, req: RequestInfo) -> Response<Body> {
+async fn error_handler(
+    err: routerify::Error,
+    req: RequestInfo,
+    auth_token: Option<Arc<str>>,
+) -> Response<Body> {
+    // Routerify only passes on the message of errors, so requests rejected by
+    // the authorization middleware are identified by checking them again.
+    if !is_authorized(
+        req.method(),
+        req.uri().path(),
+        req.headers(),
+        auth_token.as_deref(),
+    ) {
+        return ApplicationError::Unauthorized {}.unauthorized();
+    }
+
     let method = req.method().clone();
     let uri = req.uri().clone();
     error!(error = ?err, error_message = ?err.to_string(), method = ?method, uri = ?uri, "Error while handling request");
@@ -230,25 +752,145 @@ async fn error_handler(err: routerify::Error, req: RequestInfo) -> Response<Body
 
 #[derive(Debug, Deserialize)]
 /// Body of the request to the /write endpoint
+///
+/// The org and bucket are given either by name or by id, as for InfluxDB 2.X.
 struct WriteInfo {
-    org: String,
-    bucket: String,
+    org: Option<String>,
+    bucket: Option<String>,
+    #[serde(rename = "orgID")]
+    org_id: Option<String>,
+    #[serde(rename = "bucketID")]
+    bucket_id: Option<String>,
+    // The precision of the timestamps being written: ns (the default), us, ms
+    // or s.
+    precision: Option<String>,
+    // Whether to respond with a summary of the points written, rather than
+    // with no content. The summary is always returned if any points were
+    // rejected or clamped for being too far in the future.
+    #[serde(default)]
+    verbose: bool,
+    // Whether to write the lines that can be parsed when others can't, rather
+    // than rejecting the whole request.
+    #[serde(default)]
+    partial: bool,
+}
+
+/// Describes why an org & bucket can't be mapped into a database name, so
+/// that clients can correct them.
+fn invalid_database_name(source: OrgBucketMappingError) -> ApplicationError {
+    let (name, reason) = match source {
+        OrgBucketMappingError::InvalidBucketOrgName { org, bucket_name } => (
+            format!("{}_{}", org, bucket_name),
+            "the '_' character is reserved in org and bucket names".to_string(),
+        ),
+        OrgBucketMappingError::EmptyBucketOrgName { org, bucket_name } => (
+            format!("{}_{}", org, bucket_name),
+            "the org and bucket names must not be empty".to_string(),
+        ),
+        OrgBucketMappingError::InvalidDatabaseName {
+            source: DatabaseNameError::LengthConstraint { name },
+        } => (
+            name,
+            format!(
+                "the org and bucket names, joined by '_', must be at most {} characters long",
+                LENGTH_CONSTRAINT.end()
+            ),
+        ),
+        OrgBucketMappingError::InvalidDatabaseName {
+            source: DatabaseNameError::BadChars { name },
+        } => (
+            name,
+            "the org and bucket names may only contain alphanumeric characters and '-'".to_string(),
+        ),
+    };
+    ApplicationError::InvalidDatabaseName { name, reason }
+}
+
+impl WriteInfo {
+    /// The org and bucket, as they're named or identified in the request,
+    /// along with the name of the database they map into. Ids are mapped by
+    /// `resolver`.
+    fn database_name(
+        &self,
+        resolver: &dyn OrgBucketResolver,
+    ) -> Result<(&str, &str, DatabaseName<'static>), ApplicationError> {
+        match (&self.org, &self.bucket, &self.org_id, &self.bucket_id) {
+            (Some(org), Some(bucket), None, None) => {
+                let db_name =
+                    org_and_bucket_to_database(org, bucket).map_err(invalid_database_name)?;
+                Ok((org, bucket, db_name))
+            }
+            (None, None, Some(org_id), Some(bucket_id)) => {
+                let db_name = resolver
+                    .database_name(org_id, bucket_id)
+                    .map_err(invalid_database_name)?
+                    .context(BucketNotFound {
+                        org: org_id,
+                        bucket: bucket_id,
+                    })?;
+                Ok((org_id, bucket_id, db_name))
+            }
+            _ => ExpectedOrgAndBucket.fail(),
+        }
+    }
+}
+
+// The multiplier that converts timestamps of the precision to nanoseconds.
+fn precision_multiplier(precision: &str) -> Result<i64, ApplicationError> {
+    match precision {
+        "ns" => Ok(1),
+        "us" => Ok(1_000),
+        "ms" => Ok(1_000_000),
+        "s" => Ok(1_000_000_000),
+        _ => InvalidPrecision { precision }.fail(),
+    }
+}
+
+// The content encodings request bodies can be decompressed from.
+enum ContentEncoding {
+    Gzip,
+    Zstd,
+}
+
+/// Checks that the request's body, if its content type is specified, is
+/// line protocol, which is sent as `text/plain`. Any parameters of the content
+/// type, such as its charset, are ignored.
+fn check_line_protocol_content_type(req: &Request<Body>) -> Result<(), ApplicationError> {
+    // clippy says the const needs to be assigned to a local variable:
+    // error: a `const` item with interior mutability should not be borrowed
+    let header_name = CONTENT_TYPE;
+    if let Some(content_type) = req.headers().get(&header_name) {
+        let content_type = content_type.to_str().context(ReadingHeaderAsUtf8 {
+            header_name: header_name.as_str(),
+        })?;
+        let media_type = content_type.split(';').next().unwrap_or("").trim();
+        ensure!(
+            media_type.eq_ignore_ascii_case("text/plain"),
+            InvalidContentType { content_type }
+        );
+    }
+    Ok(())
 }
 
 /// Parse the request's body into raw bytes, applying size limits and
 /// content encoding as needed.
 async fn parse_body(req: hyper::Request<Body>) -> Result<Bytes, ApplicationError> {
+    let max_size = req
+        .data::<MaxRequestSize>()
+        .map_or(DEFAULT_MAX_REQUEST_SIZE, |size| size.0);
+
     // clippy says the const needs to be assigned to a local variable:
     // error: a `const` item with interior mutability should not be borrowed
     let header_name = CONTENT_ENCODING;
-    let ungzip = match req.headers().get(&header_name) {
-        None => false,
+    let encoding = match req.headers().get(&header_name) {
+        None => None,
         Some(content_encoding) => {
             let content_encoding = content_encoding.to_str().context(ReadingHeaderAsUtf8 {
                 header_name: header_name.as_str(),
             })?;
             match content_encoding {
-                "gzip" => true,
+                "gzip" => Some(ContentEncoding::Gzip),
+                "zstd" => Some(ContentEncoding::Zstd),
                 _ => InvalidContentEncoding { content_encoding }.fail()?,
             }
         }
@@ -258,33 +900,69 @@ async fn parse_body(req: hyper::Request<Body>) -> Result<Bytes, ApplicationError
 
     let mut body = BytesMut::new();
     while let Some(chunk) = payload.next().await {
-        let chunk = chunk.expect("Should have been able to read the next chunk");
+        // e.g. the client disconnected part way through sending the body
+        let chunk = chunk.context(ReadingBody)?;
         // limit max size of in-memory payload
-        if (body.len() + chunk.len()) > MAX_SIZE {
+        if (body.len() + chunk.len()) > max_size {
             return Err(ApplicationError::RequestSizeExceeded {
-                max_body_size: MAX_SIZE,
+                max_body_size: max_size,
             });
         }
         body.extend_from_slice(&chunk);
     }
     let body = body.freeze();
 
-    // apply any content encoding needed
-    if ungzip {
-        use std::io::Read;
-        let decoder = flate2::read::GzDecoder::new(&body[..]);
-
-        // Read at most MAX_SIZE bytes to prevent a decompression bomb based
-        // DoS.
-        let mut decoder = decoder.take(MAX_SIZE as u64);
-        let mut decoded_data = Vec::new();
-        decoder
-            .read_to_end(&mut decoded_data)
-            .context(ReadingBodyAsGzip)?;
-        Ok(decoded_data.into())
-    } else {
-        Ok(body)
+    // apply any content encoding needed, reading at most one byte more than
+    // max_size to prevent a decompression bomb based DoS, while still
+    // detecting a body that decompresses to more than max_size.
+    use std::io::Read;
+    let mut decoded_data = Vec::new();
+    match encoding {
+        None => return Ok(body),
+        Some(ContentEncoding::Gzip) => {
+            let decoder = flate2::read::GzDecoder::new(&body[..]);
+            decoder
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decoded_data)
+                .context(ReadingBodyAsGzip)?;
+        }
+        Some(ContentEncoding::Zstd) => {
+            let decoder = zstd::stream::read::Decoder::new(&body[..]).context(ReadingBodyAsZstd)?;
+            decoder
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decoded_data)
+                .context(ReadingBodyAsZstd)?;
+        }
+    }
+    ensure!(
+        decoded_data.len() <= max_size,
+        RequestSizeExceeded {
+            max_body_size: max_size
+        }
+    );
+    Ok(decoded_data.into())
+}
+
+/// Truncates a request body so that it can be included in an error message
+/// without echoing a large payload back to the client.
+fn truncate_body(body: &str) -> String {
+    if body.chars().count() <= MAX_ECHOED_BODY_CHARS {
+        return body.to_string();
     }
+
+    let mut truncated: String = body.chars().take(MAX_ECHOED_BODY_CHARS).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+/// Deserializes a JSON request body, reporting where any error occurred.
+fn parse_json_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, ApplicationError> {
+    serde_json::from_slice(body).map_err(|source| ApplicationError::InvalidRequestBody {
+        request_body: truncate_body(&String::from_utf8_lossy(body)),
+        line: source.line(),
+        column: source.column(),
+        source,
+    })
 }
 
 #[tracing::instrument(level = "debug")]
@@ -310,6 +988,8 @@ where
         .data::<Arc<AppServer<M>>>()
         .expect("server state")
         .clone();
+    let metrics = req.data::<Arc<Metrics>>().expect("metrics").clone();
+    metrics.write_requests.inc();
 
     let query = req.uri().query().context(ExpectedQueryString)?;
 
@@ -317,51 +997,105 @@ where
         query_string: String::from(query),
     })?;
 
-    let db_name = org_and_bucket_to_database(&write_info.org, &write_info.bucket)
-        .context(BucketMappingError)?;
+    let resolver = req
+        .data::<Arc<dyn OrgBucketResolver>>()
+        .expect("org & bucket resolver")
+        .clone();
+    let (org, bucket, db_name) = write_info.database_name(resolver.as_ref())?;
+
+    let precision = write_info.precision.as_deref().unwrap_or("ns");
+    let multiplier = precision_multiplier(precision)?;
 
+    check_line_protocol_content_type(&req)?;
     let body = parse_body(req).await?;
+    metrics.bytes_written.inc_by(body.len() as u64);
 
     let body = str::from_utf8(&body).context(ReadingBodyAsUtf8)?;
 
-    let lines = parse_lines(body)
-        .collect::<Result<Vec<_>, influxdb_line_protocol::Error>>()
-        .context(ParsingLineProtocol)?;
+    let mut lines = vec![];
+    let mut line_errors = vec![];
+    for (line_number, line) in parse_numbered_lines(body) {
+        match line {
+            Ok(line) => lines.push(line),
+            Err(e) if write_info.partial => line_errors.push(serde_json::json!({
+                "line": line_number,
+                "reason": e.to_string(),
+            })),
+            Err(source) => return Err(ApplicationError::ParsingLineProtocol { source }),
+        }
+    }
+
+    // Timestamps are stored in nanoseconds.
+    if multiplier != 1 {
+        for line in &mut lines {
+            if let Some(timestamp) = line.timestamp {
+                let scaled = timestamp
+                    .checked_mul(multiplier)
+                    .context(TimestampOutOfRange {
+                        timestamp,
+                        precision,
+                    })?;
+                line.timestamp = Some(scaled);
+            }
+        }
+    }
 
     debug!(
         "Inserting {} lines into database {} (org {} bucket {})",
         lines.len(),
         db_name,
-        write_info.org,
-        write_info.bucket
+        org,
+        bucket
     );
 
-    // TODO: remove this once the API is in to create a database
-    if server.db(&db_name).await.is_none() {
-        let rules = DatabaseRules {
-            store_locally: true,
-            ..Default::default()
-        };
-
-        server
-            .create_database(db_name.to_string(), rules)
-            .await
-            .map_err(|e| Box::new(e) as _)
-            .context(WritingPoints {
-                org: write_info.org.clone(),
-                bucket_name: write_info.bucket.clone(),
-            })?;
-    }
-
-    server
+    // Databases must be created with `PUT /api/v1/databases/:name` before
+    // they can be written to.
+    let summary = server
         .write_lines(&db_name, &lines)
         .await
-        .map_err(|e| Box::new(e) as _)
-        .context(WritingPoints {
-            org: write_info.org.clone(),
-            bucket_name: write_info.bucket.clone(),
+        .map_err(|e| match e {
+            ServerError::DatabaseNotFound { .. } => ApplicationError::BucketNotFound {
+                org: org.to_string(),
+                bucket: bucket.to_string(),
+            },
+            e => ApplicationError::WritingPoints {
+                org: org.to_string(),
+                bucket_name: bucket.to_string(),
+                source: Box::new(e),
+            },
         })?;
 
+    // The lines that could be parsed have been written, but the request still
+    // fails so that clients notice the lines that couldn't be.
+    if !line_errors.is_empty() {
+        let result = serde_json::json!({
+            "error": format!("{} lines could not be parsed", line_errors.len()),
+            "points_written": summary.points_written,
+            "future_points_rejected": summary.future_points_rejected,
+            "future_points_clamped": summary.future_points_clamped,
+            "line_errors": line_errors,
+        })
+        .to_string();
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from(result))
+            .unwrap());
+    }
+
+    // Clients are always told about points dropped or altered because their
+    // timestamps were too far in the future, even without the verbose flag.
+    let future_points = summary.future_points_rejected + summary.future_points_clamped;
+    if write_info.verbose || future_points > 0 {
+        let result = serde_json::json!({
+            "points_written": summary.points_written,
+            "lines_parsed": lines.len(),
+            "future_points_rejected": summary.future_points_rejected,
+            "future_points_clamped": summary.future_points_clamped,
+        })
+        .to_string();
+        return Ok(Response::new(Body::from(result)));
+    }
+
     Ok(Response::builder()
         .status(StatusCode::NO_CONTENT)
         .body(Body::empty())
@@ -369,21 +1103,23 @@ where
 }
 
 #[derive(Deserialize, Debug)]
-/// Body of the request to the /read endpoint
-struct ReadInfo {
-    org: String,
-    bucket: String,
-    // TODO This is currently a "SQL" request -- should be updated to conform
-    // to the V2 API for reading (using timestamps, etc).
-    sql_query: String,
+/// Body of the request to the /delete endpoint, as for InfluxDB 2.x. The
+/// points from `start` until `stop`, inclusive, that match `predicate` are
+/// deleted.
+struct DeleteInfo {
+    start: String,
+    stop: String,
+    // Conditions of the form `<tag>="<value>"` joined by `AND`, where the
+    // `_measurement` tag restricts the delete to a measurement.
+    predicate: Option<String>,
 }
 
 #[tracing::instrument(level = "debug")]
-async fn read_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+async fn delete_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    match read::<M>(req).await {
+    match delete::<M>(req).await {
         Err(e) => {
             error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
 
@@ -393,10 +1129,10 @@ where
     }
 }
 
-// TODO: figure out how to stream read results out rather than rendering the
-// whole thing in mem
+/// Deletes points from a bucket. The delete is recorded in the WAL like a
+/// write, and removes the points from the chunks that hold them.
 #[tracing::instrument(level = "debug")]
-async fn read<M: ConnectionManager + Send + Sync + Debug + 'static>(
+async fn delete<M: ConnectionManager + Send + Sync + Debug + 'static>(
     req: Request<Body>,
 ) -> Result<Response<Body>, ApplicationError> {
     let server = req
@@ -405,48 +1141,490 @@ async fn read<M: ConnectionManager + Send + Sync + Debug + 'static>(
         .clone();
     let query = req.uri().query().context(ExpectedQueryString {})?;
 
-    let read_info: ReadInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+    let info: DatabaseInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
         query_string: query,
     })?;
 
-    let db_name = org_and_bucket_to_database(&read_info.org, &read_info.bucket)
-        .context(BucketMappingError)?;
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
 
-    let db = server.db(&db_name).await.context(BucketNotFound {
-        org: read_info.org.clone(),
-        bucket: read_info.bucket.clone(),
-    })?;
+    let body = parse_body(req).await?;
+    let delete_info: DeleteInfo = parse_json_body(&body)?;
 
-    let results = db
-        .query(&read_info.sql_query)
+    let start = parse_timestamp(&delete_info.start)?;
+    let stop = parse_timestamp(&delete_info.stop)?;
+    let range = TimestampRange::new(start, stop.saturating_add(1));
+    let tombstone = match &delete_info.predicate {
+        Some(predicate) => parse_delete_predicate(predicate, Tombstone::new(range))?,
+        None => Tombstone::new(range),
+    };
+
+    server
+        .delete(&db_name, &tombstone)
         .await
-        .map_err(|e| Box::new(e) as _)
-        .context(QueryError {})?;
-    let results = arrow::util::pretty::pretty_format_batches(&results).unwrap();
+        .map_err(|e| match e {
+            ServerError::DatabaseNotFound { .. } => ApplicationError::BucketNotFound {
+                org: info.org.clone(),
+                bucket: info.bucket.clone(),
+            },
+            e => ApplicationError::DeletingPoints {
+                org: info.org.clone(),
+                bucket_name: info.bucket.clone(),
+                source: Box::new(e),
+            },
+        })?;
 
-    Ok(Response::new(Body::from(results.into_bytes())))
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
 }
 
-// Route to test that the server is alive
-#[tracing::instrument(level = "debug")]
-async fn ping(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
-    let response_body = "PONG";
-    Ok(Response::new(Body::from(response_body.to_string())))
-}
+// Restricts `tombstone` to the points matching an InfluxDB 2.x delete
+// predicate, such as `_measurement="cpu" AND host="server01"`.
+fn parse_delete_predicate(
+    predicate: &str,
+    mut tombstone: Tombstone,
+) -> Result<Tombstone, ApplicationError> {
+    const MEASUREMENT: &str = "_measurement";
 
-#[derive(Deserialize, Debug)]
-/// Arguments in the query string of the request to /partitions
-struct DatabaseInfo {
+    let conditions =
+        split_delete_conditions(predicate).context(InvalidDeletePredicate { predicate })?;
+    for condition in conditions {
+        let mut parts = condition.splitn(2, '=');
+        let (tag, value) = match (parts.next(), parts.next()) {
+            (Some(tag), Some(value)) => (tag.trim(), value.trim()),
+            _ => return InvalidDeletePredicate { predicate }.fail(),
+        };
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        ensure!(
+            !tag.is_empty() && !tag.ends_with('!'),
+            InvalidDeletePredicate { predicate }
+        );
+
+        tombstone = if tag == MEASUREMENT {
+            ensure!(
+                tombstone.table_name.is_none(),
+                InvalidDeletePredicate { predicate }
+            );
+            tombstone.with_table(value)
+        } else {
+            tombstone.with_tag(tag, value)
+        };
+    }
+    Ok(tombstone)
+}
+
+// Splits a delete predicate into its conditions at each ` AND ` or ` and `
+// outside of a quoted value, so that values may contain them. Returns None if
+// a quoted value isn't closed.
+fn split_delete_conditions(predicate: &str) -> Option<Vec<&str>> {
+    let mut conditions = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in predicate.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && i >= start {
+            let rest = &predicate[i..];
+            if rest.starts_with(" AND ") || rest.starts_with(" and ") {
+                conditions.push(&predicate[start..i]);
+                start = i + " AND ".len();
+            }
+        }
+    }
+
+    if quoted {
+        return None;
+    }
+    conditions.push(&predicate[start..]);
+    Some(conditions)
+}
+
+#[derive(Deserialize, Debug)]
+/// Body of the request to the /read endpoint. These can either be provided in
+/// the query string of a GET request or as the JSON body of a POST request.
+///
+/// The rows of `measurement` from `start` (inclusive) until `stop`
+/// (exclusive) that match every `predicate` are read. Times are RFC3339
+/// strings or nanoseconds since the epoch, and predicates are of the form
+/// `<tag>=<value>` or `<tag>!=<value>`.
+///
+/// If `every` is given, the rows are instead aggregated into windows of that
+/// duration (such as `30s`, `1m` or `2h`), using each `aggregate`, of the form
+/// `<field>:<function>`. Each series, identified by the measurement's tag
+/// columns, is windowed separately.
+struct ReadInfo {
     org: String,
     bucket: String,
+    measurement: Option<String>,
+    start: Option<String>,
+    stop: Option<String>,
+    // Repeated in the query string of a GET request.
+    #[serde(default)]
+    predicate: Vec<String>,
+    every: Option<String>,
+    // Repeated in the query string of a GET request.
+    #[serde(default)]
+    aggregate: Vec<String>,
+    /// Deprecated: a SQL query to run instead of the structured parameters
+    /// above.
+    sql_query: Option<String>,
+}
+
+impl ReadInfo {
+    // Parses the query string of a GET request, in which each predicate and
+    // aggregate is a separate `predicate` or `aggregate` parameter.
+    fn from_query(query: &str) -> Result<Self, ApplicationError> {
+        let pairs: Vec<(String, String)> =
+            serde_urlencoded::from_str(query).context(InvalidQueryString {
+                query_string: query,
+            })?;
+        let (predicates, rest): (Vec<_>, Vec<_>) =
+            pairs.into_iter().partition(|(key, _)| key == "predicate");
+        let (aggregates, rest): (Vec<_>, Vec<_>) =
+            rest.into_iter().partition(|(key, _)| key == "aggregate");
+
+        let rest = serde_urlencoded::to_string(rest).expect("encoding string pairs");
+        let mut read_info: Self =
+            serde_urlencoded::from_str(&rest).context(InvalidQueryString {
+                query_string: query,
+            })?;
+        read_info.predicate = predicates.into_iter().map(|(_, value)| value).collect();
+        read_info.aggregate = aggregates.into_iter().map(|(_, value)| value).collect();
+        Ok(read_info)
+    }
+
+    /// The windows the results are aggregated into, if any. Each of `every`
+    /// and `aggregate` needs the other.
+    fn window(&self) -> Result<Option<Window>, ApplicationError> {
+        let every = match &self.every {
+            Some(every) => parse_duration(every)?,
+            None => {
+                ensure!(self.aggregate.is_empty(), ExpectedWindowEvery);
+                return Ok(None);
+            }
+        };
+        ensure!(!self.aggregate.is_empty(), ExpectedWindowAggregate);
+
+        let aggregates = self
+            .aggregate
+            .iter()
+            .map(|spec| WindowAggregate::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Window {
+            every,
+            aggregates,
+            series: vec![],
+        }))
+    }
+
+    /// The SQL query the request is run as. When the results are aggregated
+    /// into `window`, the query groups the rows by series and window.
+    fn sql(&self, window: Option<&Window>) -> Result<String, ApplicationError> {
+        if let Some(sql_query) = &self.sql_query {
+            ensure!(window.is_none(), WindowWithSqlQuery);
+            warn!("sql_query is deprecated: use measurement, start, stop and predicate instead");
+            return Ok(sql_query.clone());
+        }
+
+        let measurement = self.measurement.as_ref().context(ExpectedMeasurement)?;
+        ensure_plain_name(measurement)?;
+
+        let mut conditions = vec![];
+        let mut start = self
+            .start
+            .as_deref()
+            .map(parse_timestamp)
+            .transpose()?
+            .unwrap_or(i64::MIN);
+        // Windows that would start before `i64::MIN` can't be represented, so
+        // the rows that would fall in them are omitted.
+        if let Some(window) = window {
+            start = start.max(window.first_start());
+        }
+        let range = TimestampRange::new(
+            start,
+            self.stop
+                .as_deref()
+                .map(parse_timestamp)
+                .transpose()?
+                .unwrap_or(i64::MAX),
+        );
+        if range.start != i64::MIN {
+            conditions.push(format!("time >= {}", range.start));
+        }
+        if range.end != i64::MAX {
+            conditions.push(format!("time < {}", range.end));
+        }
+
+        for predicate in &self.predicate {
+            let (tag, operator, value) = match predicate.find('=') {
+                Some(i) if predicate[..i].ends_with('!') => {
+                    (&predicate[..i - 1], "!=", &predicate[i + 1..])
+                }
+                Some(i) => (&predicate[..i], "=", &predicate[i + 1..]),
+                None => return InvalidPredicate { predicate }.fail(),
+            };
+            ensure!(!tag.is_empty(), InvalidPredicate { predicate });
+            ensure_plain_name(tag)?;
+            conditions.push(format!(
+                "{} {} '{}'",
+                tag,
+                operator,
+                value.replace('\'', "''")
+            ));
+        }
+
+        let (columns, clauses) = match window {
+            Some(window) => window.sql()?,
+            None => ("*".to_string(), " ORDER BY time".to_string()),
+        };
+
+        let mut sql = format!("SELECT {} FROM {}", columns, measurement);
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        sql.push_str(&clauses);
+        Ok(sql)
+    }
+}
+
+/// The aggregate functions that can be applied to a field in each window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WindowFunction {
+    Mean,
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+impl WindowFunction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Sum => "sum",
+            Self::Count => "count",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+
+    // The SQL aggregate function the function is run as.
+    fn sql_name(&self) -> &'static str {
+        match self {
+            Self::Mean => "AVG",
+            Self::Sum => "SUM",
+            Self::Count => "COUNT",
+            Self::Min => "MIN",
+            Self::Max => "MAX",
+        }
+    }
+
+    // Only `count` can be applied to non-numeric columns.
+    fn can_apply_to(&self, data_type: &DataType) -> bool {
+        *self == Self::Count
+            || matches!(
+                data_type,
+                DataType::Float64 | DataType::Int64 | DataType::UInt64
+            )
+    }
+}
+
+/// An aggregate of a field, computed for each window and named
+/// `<field>_<function>` in the results.
+#[derive(Debug, Clone, PartialEq)]
+struct WindowAggregate {
+    field: String,
+    function: WindowFunction,
+}
+
+impl WindowAggregate {
+    /// Parses an aggregate given as `<field>:<function>`, such as
+    /// `usage:mean`.
+    fn parse(spec: &str) -> Result<Self, ApplicationError> {
+        let mut parts = spec.splitn(2, ':');
+        let field = parts.next().unwrap_or_default();
+        let function = match parts.next() {
+            Some("mean") => WindowFunction::Mean,
+            Some("sum") => WindowFunction::Sum,
+            Some("count") => WindowFunction::Count,
+            Some("min") => WindowFunction::Min,
+            Some("max") => WindowFunction::Max,
+            _ => return InvalidWindowAggregate { spec }.fail(),
+        };
+        ensure!(!field.is_empty(), InvalidWindowAggregate { spec });
+
+        Ok(Self {
+            field: field.to_string(),
+            function,
+        })
+    }
+
+    fn column_name(&self) -> String {
+        format!("{}_{}", self.field, self.function.name())
+    }
+}
+
+/// The windows requested by the `every` and `aggregate` parameters of a read
+/// request. Windows are `every` long and aligned to multiples of `every` since
+/// the epoch, and those without any rows are omitted.
+#[derive(Debug, Clone, PartialEq)]
+struct Window {
+    /// The length of each window, in nanoseconds
+    every: i64,
+
+    /// The aggregates computed for each window
+    aggregates: Vec<WindowAggregate>,
+
+    /// The tag columns identifying each series, which is windowed separately
+    series: Vec<String>,
+}
+
+impl Window {
+    // The start of the earliest window that can be represented.
+    fn first_start(&self) -> i64 {
+        // Integer division rounds towards zero, so this can't overflow.
+        (i64::MIN / self.every) * self.every
+    }
+
+    // The columns a query for the windows selects, and its GROUP BY and ORDER
+    // BY clauses. Rows are grouped by their series and the start of their
+    // window, which is returned as the time of each group.
+    fn sql(&self) -> Result<(String, String), ApplicationError> {
+        let bucket = format!("{}(time, {})", TIME_BUCKET_UDF_NAME, self.every);
+        let mut group_by = vec![];
+        for column in &self.series {
+            ensure_plain_name(column)?;
+            group_by.push(column.clone());
+        }
+
+        let mut columns = group_by.clone();
+        columns.push(format!("{} AS time", bucket));
+        for aggregate in &self.aggregates {
+            ensure_plain_name(&aggregate.field)?;
+            columns.push(format!(
+                "{}({}) AS {}",
+                aggregate.function.sql_name(),
+                aggregate.field,
+                aggregate.column_name()
+            ));
+        }
+
+        let mut order_by = group_by.clone();
+        order_by.push("time".to_string());
+        group_by.push(bucket);
+
+        let clauses = format!(
+            " GROUP BY {} ORDER BY {}",
+            group_by.join(", "),
+            order_by.join(", ")
+        );
+        Ok((columns.join(", "), clauses))
+    }
+
+    /// Checks that each aggregate can be applied to its field in `schema`,
+    /// the schema of `measurement`.
+    fn check_schema(&self, measurement: &str, schema: &Schema) -> Result<(), ApplicationError> {
+        for aggregate in &self.aggregates {
+            let field =
+                schema
+                    .field_with_name(&aggregate.field)
+                    .ok()
+                    .context(WindowColumnNotFound {
+                        column: &aggregate.field,
+                        measurement,
+                    })?;
+            ensure!(
+                aggregate.function.can_apply_to(field.data_type()),
+                UnsupportedWindowAggregate {
+                    function: aggregate.function.name(),
+                    column: &aggregate.field,
+                    data_type: field.data_type().clone(),
+                }
+            );
+        }
+        Ok(())
+    }
+}
+
+// Converts a duration such as `30s`, `1m` or `2h` to nanoseconds.
+fn parse_duration(value: &str) -> Result<i64, ApplicationError> {
+    let (number, multiplier): (_, i64) = if let Some(number) = value.strip_suffix('s') {
+        (number, 1_000_000_000)
+    } else if let Some(number) = value.strip_suffix('m') {
+        (number, 60 * 1_000_000_000)
+    } else if let Some(number) = value.strip_suffix('h') {
+        (number, 60 * 60 * 1_000_000_000)
+    } else {
+        return InvalidWindowDuration { value }.fail();
+    };
+
+    number
+        .parse::<i64>()
+        .ok()
+        .filter(|number| *number > 0)
+        .and_then(|number| number.checked_mul(multiplier))
+        .context(InvalidWindowDuration { value })
+}
+
+// The names of the tag columns of a measurement.
+async fn tag_columns<D: TSDatabase>(
+    db: &D,
+    measurement: &str,
+) -> Result<Vec<String>, ApplicationError> {
+    let predicate = PredicateBuilder::default().table(measurement).build();
+    let plan = db
+        .tag_column_names(predicate)
+        .await
+        .map_err(|e| ApplicationError::QueryError {
+            source: Box::new(e),
+        })?;
+    let tags =
+        Executor::new()
+            .to_string_set(plan)
+            .await
+            .map_err(|e| ApplicationError::QueryError {
+                source: Box::new(e),
+            })?;
+    Ok(tags.iter().cloned().collect())
+}
+
+// Names are interpolated into SQL unquoted, so are limited to those that don't
+// need quoting.
+fn ensure_plain_name(name: &str) -> Result<(), ApplicationError> {
+    ensure!(
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        InvalidName { name }
+    );
+    Ok(())
+}
+
+// Parses an RFC3339 time or an integer number of nanoseconds since the epoch.
+fn parse_timestamp(timestamp: &str) -> Result<i64, ApplicationError> {
+    timestamp
+        .parse::<i64>()
+        .ok()
+        .or_else(|| {
+            chrono::DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|time| time.timestamp_nanos())
+        })
+        .context(InvalidTimestamp { timestamp })
 }
 
 #[tracing::instrument(level = "debug")]
-async fn list_partitions_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+async fn read_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    match list_partitions::<M>(req).await {
+    match read::<M>(req).await {
         Err(e) => {
             error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
 
@@ -457,149 +1635,3984 @@ where
 }
 
 #[tracing::instrument(level = "debug")]
-async fn list_partitions<M: ConnectionManager + Send + Sync + Debug + 'static>(
+async fn read<M: ConnectionManager + Send + Sync + Debug + 'static>(
     req: Request<Body>,
 ) -> Result<Response<Body>, ApplicationError> {
     let server = req
         .data::<Arc<AppServer<M>>>()
         .expect("server state")
         .clone();
-    let query = req.uri().query().context(ExpectedQueryString {})?;
+    let metrics = req.data::<Arc<Metrics>>().expect("metrics").clone();
+    metrics.read_requests.inc();
+    let query_timeout = req
+        .data::<QueryTimeout>()
+        .map_or(DEFAULT_QUERY_TIMEOUT, |timeout| timeout.0);
+    let gzip = accepts_gzip(req.headers());
+    let format = OutputFormat::from_headers(req.headers());
 
-    let info: DatabaseInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
-        query_string: query,
-    })?;
+    let read_info: ReadInfo = if req.method() == Method::POST {
+        let body = parse_body(req).await?;
+        parse_json_body(&body)?
+    } else {
+        let query = req.uri().query().context(ExpectedQueryString {})?;
+        ReadInfo::from_query(query)?
+    };
+    let window = read_info.window()?;
+    let mut sql = read_info.sql(window.as_ref())?;
 
-    let db_name =
-        org_and_bucket_to_database(&info.org, &info.bucket).context(BucketMappingError)?;
+    let db_name = org_and_bucket_to_database(&read_info.org, &read_info.bucket)
+        .map_err(invalid_database_name)?;
+
+    // Data may be incomplete until startup (e.g. WAL replay) has finished.
+    ensure!(server.is_ready(), NotReady);
 
     let db = server.db(&db_name).await.context(BucketNotFound {
-        org: &info.org,
-        bucket: &info.bucket,
+        org: read_info.org.clone(),
+        bucket: read_info.bucket.clone(),
     })?;
 
-    let partition_keys = db
-        .partition_keys()
-        .await
-        .map_err(|e| Box::new(e) as _)
-        .context(BucketByName {
-            org: &info.org,
-            bucket_name: &info.bucket,
-        })?;
+    // Each series of the measurement, identified by its tag columns, is
+    // windowed separately.
+    if let Some(mut window) = window {
+        let measurement = read_info
+            .measurement
+            .as_ref()
+            .context(ExpectedMeasurement)?;
+        let schema = db
+            .table_schema(measurement)
+            .await
+            .map_err(|e| ApplicationError::QueryError {
+                source: Box::new(e),
+            })?
+            .context(TableNotFound {
+                table: measurement.as_str(),
+            })?;
+        window.check_schema(measurement, &schema)?;
+        window.series = tag_columns(db.as_ref(), measurement).await?;
+        sql = read_info.sql(Some(&window))?;
+    }
 
-    let result = serde_json::to_string(&partition_keys).context(JsonGenerationError)?;
+    // Observes the duration when dropped, once all the results are formatted.
+    let timer = metrics.query_duration.start_timer();
+    let deadline = QueryDeadline::new(query_timeout);
+    let stream = deadline
+        .run(async {
+            db.query_stream(&sql).await.map_err(|e| match e {
+                DatabaseError::TableNotFound { table, .. } => {
+                    ApplicationError::TableNotFound { table }
+                }
+                e => ApplicationError::QueryError {
+                    source: Box::new(e),
+                },
+            })
+        })
+        .await?;
 
-    Ok(Response::new(Body::from(result)))
+    let mut results = FormattedResults::try_new(stream, format)?;
+
+    let response = Response::builder().header(CONTENT_TYPE, format.content_type());
+
+    // Whether the response is compressed is decided by its size, so enough of
+    // the results to make that decision are formatted up front. Errors found
+    // here can still be reported with an error status.
+    let mut head = Vec::new();
+    while head.len() < MIN_COMPRESSED_RESPONSE_SIZE {
+        match deadline.run(results.next()).await? {
+            Some(output) => head.extend(output),
+            None => return Ok(response.body(Body::from(head)).unwrap()),
+        }
+    }
+
+    let response = if gzip {
+        response.header(CONTENT_ENCODING, "gzip")
+    } else {
+        response
+    };
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        if let Err(e) = send_results(&mut sender, head, results, gzip, deadline).await {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while streaming read results");
+            sender.abort();
+        }
+        drop(timer);
+    });
+
+    Ok(response.body(body).unwrap())
 }
 
-#[derive(Deserialize, Debug)]
-/// Arguments in the query string of the request to /snapshot
-struct SnapshotInfo {
-    org: String,
-    bucket: String,
-    chunk: String,
+// Sends the formatted results to the response body as each batch is
+// produced, compressing them first if requested.
+async fn send_results(
+    sender: &mut hyper::body::Sender,
+    head: Vec<u8>,
+    mut results: FormattedResults,
+    gzip: bool,
+    deadline: QueryDeadline,
+) -> Result<(), ApplicationError> {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let mut encoder = if gzip {
+        Some(GzEncoder::new(Vec::new(), Compression::default()))
+    } else {
+        None
+    };
+
+    let mut output = Some(head);
+    while let Some(mut chunk) = output {
+        if let Some(encoder) = encoder.as_mut() {
+            encoder.write_all(&chunk).context(CompressingResponse)?;
+            chunk = std::mem::take(encoder.get_mut());
+        }
+        if !chunk.is_empty() {
+            sender
+                .send_data(chunk.into())
+                .await
+                .context(SendingResults)?;
+        }
+        output = deadline.run(results.next()).await?;
+    }
+
+    if let Some(encoder) = encoder {
+        let chunk = encoder.finish().context(CompressingResponse)?;
+        sender
+            .send_data(chunk.into())
+            .await
+            .context(SendingResults)?;
+    }
+
+    Ok(())
+}
+
+/// The formats the results of /api/v2/read can be returned in, chosen by the
+/// Accept header of the request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    /// Human readable tables, the same as printed by the CLI.
+    Pretty,
+    /// A JSON array containing an object for each row.
+    Json,
+    /// RFC 4180 CSV with a header row of column names.
+    Csv,
+    /// The Arrow IPC streaming format.
+    Arrow,
+}
+
+impl OutputFormat {
+    // The first media type in the Accept header that names a supported format
+    // is used. Anything else, including `text/plain` and `*/*`, gets the pretty
+    // format.
+    fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .find_map(|media_type| {
+                let media_type = media_type.split(';').next().unwrap_or_default().trim();
+                match media_type.to_ascii_lowercase().as_str() {
+                    "text/plain" => Some(Self::Pretty),
+                    "application/json" => Some(Self::Json),
+                    "text/csv" => Some(Self::Csv),
+                    "application/vnd.apache.arrow.stream" => Some(Self::Arrow),
+                    _ => None,
+                }
+            })
+            .unwrap_or(Self::Pretty)
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::Pretty => "text/plain; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+}
+
+/// Formats the record batches of a query result as they are produced.
+struct FormattedResults {
+    stream: SendableRecordBatchStream,
+    // None once the end of the results has been written
+    writer: Option<ResultsWriter>,
+}
+
+impl FormattedResults {
+    fn try_new(
+        stream: SendableRecordBatchStream,
+        format: OutputFormat,
+    ) -> Result<Self, ApplicationError> {
+        let writer = ResultsWriter::try_new(format, &stream.schema())?;
+        Ok(Self {
+            stream,
+            writer: Some(writer),
+        })
+    }
+
+    /// Returns the output for the next batch of results, or for the end of
+    /// the results, and then `None` once all the results have been returned.
+    async fn next(&mut self) -> Result<Option<Vec<u8>>, ApplicationError> {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(None),
+        };
+
+        match self.stream.next().await {
+            Some(batch) => {
+                let batch = batch.map_err(|e| Box::new(e) as _).context(QueryError {})?;
+                writer.write(&batch).map(Some)
+            }
+            None => {
+                let writer = self.writer.take().expect("writer not finished");
+                writer.finish().map(Some)
+            }
+        }
+    }
+}
+
+// The state needed to write each format a batch at a time.
+enum ResultsWriter {
+    // Column widths depend on every row, so the batches are kept until all of
+    // them have been produced.
+    Pretty(Vec<RecordBatch>),
+    Json {
+        rows_written: bool,
+    },
+    Csv {
+        header_written: bool,
+    },
+    Arrow {
+        writer: arrow::ipc::writer::StreamWriter<SharedBuffer>,
+        output: SharedBuffer,
+    },
+}
+
+impl ResultsWriter {
+    fn try_new(format: OutputFormat, schema: &Schema) -> Result<Self, ApplicationError> {
+        Ok(match format {
+            OutputFormat::Pretty => Self::Pretty(vec![]),
+            OutputFormat::Json => Self::Json {
+                rows_written: false,
+            },
+            OutputFormat::Csv => Self::Csv {
+                header_written: false,
+            },
+            OutputFormat::Arrow => {
+                let output = SharedBuffer::default();
+                let writer = arrow::ipc::writer::StreamWriter::try_new(output.clone(), schema)
+                    .context(FormattingResults)?;
+                Self::Arrow { writer, output }
+            }
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<Vec<u8>, ApplicationError> {
+        let mut output = Vec::new();
+        match self {
+            Self::Pretty(batches) => batches.push(batch.clone()),
+            Self::Json { rows_written } => {
+                let rows =
+                    arrow::json::writer::record_batches_to_json_rows(std::slice::from_ref(batch));
+                for row in rows {
+                    output.push(if *rows_written { b',' } else { b'[' });
+                    serde_json::to_writer(&mut output, &row).context(JsonGenerationError)?;
+                    *rows_written = true;
+                }
+            }
+            Self::Csv { header_written } => {
+                // Nulls are written as empty fields.
+                let mut writer = arrow::csv::WriterBuilder::new()
+                    .has_headers(!*header_written)
+                    .build(&mut output);
+                writer.write(batch).context(FormattingResults)?;
+                *header_written = true;
+            }
+            Self::Arrow { writer, output } => {
+                writer.write(batch).context(FormattingResults)?;
+                return Ok(output.take());
+            }
+        }
+        Ok(output)
+    }
+
+    fn finish(self) -> Result<Vec<u8>, ApplicationError> {
+        match self {
+            Self::Pretty(batches) => {
+                let results = arrow::util::pretty::pretty_format_batches(&batches)
+                    .context(FormattingResults)?;
+                Ok(results.into_bytes())
+            }
+            Self::Json { rows_written } => Ok(if rows_written {
+                b"]".to_vec()
+            } else {
+                b"[]".to_vec()
+            }),
+            Self::Csv { .. } => Ok(vec![]),
+            Self::Arrow { mut writer, output } => {
+                writer.finish().context(FormattingResults)?;
+                // flush anything still buffered by the writer
+                drop(writer);
+                Ok(output.take())
+            }
+        }
+    }
+}
+
+// A buffer that the output of a writer which takes ownership of it can still
+// be taken from.
+#[derive(Debug, Clone, Default)]
+struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().expect("mutex poisoned"))
+    }
+}
+
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .expect("mutex poisoned")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Determines if the client accepts gzip encoded responses, i.e. gzip is listed
+// in the Accept-Encoding header without a quality of 0.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|encoding| {
+            let mut parts = encoding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let rejected = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f64>().ok())
+                    .map_or(false, |q| q == 0.0)
+            });
+            name.eq_ignore_ascii_case("gzip") && !rejected
+        })
 }
 
+// Route to test that the server is alive
 #[tracing::instrument(level = "debug")]
-async fn snapshot_partition_handler<M>(
-    req: Request<Body>,
-) -> Result<Response<Body>, ApplicationError>
+async fn ping(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
+    let response_body = "PONG";
+    Ok(Response::new(Body::from(response_body.to_string())))
+}
+
+// The formats results of /api/v2/read can be returned in.
+const OUTPUT_FORMATS: &[&str] = &["pretty", "json", "csv", "arrow"];
+
+// Route reporting the query features the server supports, so clients can
+// tailor the queries they build.
+#[tracing::instrument(level = "debug")]
+async fn capabilities(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
+    let aggregates: Vec<_> = read_buffer::supported_aggregates()
+        .iter()
+        .map(|agg| agg.name())
+        .collect();
+    let operators: Vec<_> = read_buffer::supported_operators()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let result = serde_json::json!({
+        "aggregates": aggregates,
+        "operators": operators,
+        "output_formats": OUTPUT_FORMATS,
+    })
+    .to_string();
+
+    Ok(Response::new(Body::from(result)))
+}
+
+// Route exposing metrics in the Prometheus text format.
+#[tracing::instrument(level = "debug")]
+async fn metrics<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    match snapshot_partition::<M>(req).await {
-        Err(e) => {
-            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let metrics = req.data::<Arc<Metrics>>().expect("metrics").clone();
 
-            e.response()
-        }
-        res => res,
-    }
+    let databases = server.db_names().await.len();
+    let output = metrics.render(databases).context(EncodingMetrics)?;
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, metrics.content_type())
+        .body(Body::from(output))
+        .unwrap())
 }
 
+// Readiness probe for deployments, which fails until the server ID is set and
+// startup work such as WAL replay has completed, and while the object store
+// can't be reached. The body reports the result of each check.
 #[tracing::instrument(level = "debug")]
-async fn snapshot_partition<M: ConnectionManager + Send + Sync + Debug + 'static>(
-    req: Request<Body>,
-) -> Result<Response<Body>, ApplicationError> {
+async fn health_ready<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
     let server = req
         .data::<Arc<AppServer<M>>>()
         .expect("server state")
         .clone();
-    let query = req.uri().query().context(ExpectedQueryString {})?;
 
-    let snapshot: SnapshotInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
-        query_string: query,
-    })?;
+    let mut checks = serde_json::Map::new();
+    let mut ready = true;
+
+    let server_id = if server.has_id() {
+        "ok".to_string()
+    } else {
+        ready = false;
+        "server ID not set".to_string()
+    };
+    checks.insert("server_id".to_string(), server_id.into());
+
+    // Reads could return incomplete data until the WAL has been replayed.
+    let replay = if server.is_ready() {
+        "ok".to_string()
+    } else {
+        ready = false;
+        "WAL replay in progress".to_string()
+    };
+    checks.insert("replay".to_string(), replay.into());
+
+    // Listing (the first page of) objects shows the store can be reached.
+    let object_store = match server.store.list(None).await {
+        Ok(mut objects) => match objects.next().await {
+            Some(Err(e)) => Err(e),
+            _ => Ok(()),
+        },
+        Err(e) => Err(e),
+    };
+    let object_store = match object_store {
+        Ok(()) => "ok".to_string(),
+        Err(e) => {
+            ready = false;
+            format!("object store unreachable: {}", e)
+        }
+    };
+    checks.insert("object_store".to_string(), object_store.into());
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = serde_json::json!({ "ready": ready, "checks": checks }).to_string();
+
+    Ok(Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+#[tracing::instrument(level = "debug")]
+async fn list_databases_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match list_databases::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Returns the names of the databases the server knows about as a JSON array.
+#[tracing::instrument(level = "debug")]
+async fn list_databases<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+
+    let names = server.db_names().await;
+    let result = serde_json::to_string(&names).context(JsonGenerationError)?;
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[derive(Deserialize, Debug)]
+/// Arguments to the InfluxDB 1.x compatible /query endpoint. These can be
+/// provided in the query string, or for a POST request, in a form encoded
+/// body.
+struct InfluxQLQueryInfo {
+    db: String,
+    q: String,
+    // The units of the timestamps in the results: ns, us, ms or s. RFC3339
+    // strings are returned if not provided.
+    epoch: Option<String>,
+}
+
+#[tracing::instrument(level = "debug")]
+async fn influxql_query_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match influxql_query::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Runs a query written in the supported subset of InfluxQL, so that tools
+/// built for InfluxDB 1.x can read from IOx. `db` names the IOx database to
+/// query.
+#[tracing::instrument(level = "debug")]
+async fn influxql_query<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query_timeout = req
+        .data::<QueryTimeout>()
+        .map_or(DEFAULT_QUERY_TIMEOUT, |timeout| timeout.0);
+
+    // Clients commonly send the database in the query string and the
+    // statement in the body, so the two are combined.
+    let mut params = req.uri().query().unwrap_or_default().to_string();
+    if req.method() == Method::POST {
+        let body = parse_body(req).await?;
+        let body = str::from_utf8(&body).context(ReadingBodyAsUtf8)?;
+        if !body.is_empty() {
+            params = format!("{}&{}", params, body);
+        }
+    }
+    let info: InfluxQLQueryInfo =
+        serde_urlencoded::from_str(&params).context(InvalidQueryString {
+            query_string: &params,
+        })?;
+    let epoch = info
+        .epoch
+        .as_deref()
+        .map(precision_multiplier)
+        .transpose()?;
+
+    let select = influxql::parse(&info.q, chrono::Utc::now().timestamp_nanos())
+        .context(ParsingInfluxQL { query: &info.q })?;
+
+    ensure!(server.is_ready(), NotReady);
+
+    let db = match DatabaseName::new(info.db.as_str()) {
+        Ok(name) => server.db(&name).await,
+        Err(_) => None,
+    }
+    .context(DatabaseNotFound { name: &info.db })?;
+
+    let batches = QueryDeadline::new(query_timeout)
+        .run(async {
+            db.query(&select.to_sql())
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(QueryError {})
+        })
+        .await?;
+
+    let result = serde_json::to_string(&select.results_json(&batches, epoch))
+        .context(JsonGenerationError)?;
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(result))
+        .unwrap())
+}
+
+#[derive(Deserialize, Debug)]
+/// Arguments in the query string of the request to /partitions
+struct DatabaseInfo {
+    org: String,
+    bucket: String,
+}
+
+#[tracing::instrument(level = "debug")]
+async fn list_partitions_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match list_partitions::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+#[tracing::instrument(level = "debug")]
+async fn list_partitions<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let info: DatabaseInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
+
+    let db = server.db(&db_name).await.context(BucketNotFound {
+        org: &info.org,
+        bucket: &info.bucket,
+    })?;
+
+    let partition_keys = db
+        .partition_keys()
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(BucketByName {
+            org: &info.org,
+            bucket_name: &info.bucket,
+        })?;
+
+    let result = serde_json::to_string(&partition_keys).context(JsonGenerationError)?;
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[tracing::instrument(level = "debug")]
+async fn chunk_summaries_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match chunk_summaries::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Returns the schema, time range, row count, size and column statistics of
+/// each chunk in a partition as JSON, for debugging. Like the other routes,
+/// it can be used by any client with the API token, when one is configured.
+#[tracing::instrument(level = "debug")]
+async fn chunk_summaries<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let key = req.param("key").expect("partition key in route").clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let info: DatabaseInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
+
+    let db = server.db(&db_name).await.context(BucketNotFound {
+        org: &info.org,
+        bucket: &info.bucket,
+    })?;
+
+    let summaries = db
+        .chunk_summaries(&key)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(BucketByName {
+            org: &info.org,
+            bucket_name: &info.bucket,
+        })?
+        .context(PartitionNotFound {
+            org: &info.org,
+            bucket: &info.bucket,
+            key: &key,
+        })?;
+
+    let chunks: Vec<_> = summaries
+        .iter()
+        .map(|chunk| {
+            let tables: Vec<_> = chunk
+                .tables
+                .iter()
+                .map(|table| {
+                    let columns: Vec<_> = table
+                        .columns
+                        .iter()
+                        .map(|column| {
+                            serde_json::json!({
+                                "name": column.name,
+                                "type": column.column_type,
+                                "stats": column.stats,
+                            })
+                        })
+                        .collect();
+
+                    serde_json::json!({
+                        "name": table.name,
+                        "row_count": table.row_count,
+                        "size": table.size,
+                        "time_range": table.time_range.map(|(min, max)| {
+                            serde_json::json!({"min": min, "max": max})
+                        }),
+                        "columns": columns,
+                    })
+                })
+                .collect();
+
+            serde_json::json!({
+                "key": chunk.key,
+                "id": chunk.id,
+                "time_of_first_write": chunk.time_of_first_write.map(|t| t.to_rfc3339()),
+                "time_of_last_write": chunk.time_of_last_write.map(|t| t.to_rfc3339()),
+                "time_closed": chunk.time_closed.map(|t| t.to_rfc3339()),
+                "row_count": chunk.row_count(),
+                "size": chunk.size(),
+                "tables": tables,
+            })
+        })
+        .collect();
+
+    let result = serde_json::to_string(&chunks).context(JsonGenerationError)?;
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[derive(Deserialize, Debug)]
+/// Arguments in the query string of the request to /chunks
+struct ChunksInfo {
+    org: String,
+    bucket: String,
+    partition: String,
+}
+
+#[tracing::instrument(level = "debug")]
+async fn list_chunks_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match list_chunks::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Lists the chunks in a partition, in the order they were created, with
+/// their row counts, time ranges and approximate sizes in bytes. Use
+/// /chunks/:key for the full details of each chunk's tables.
+#[tracing::instrument(level = "debug")]
+async fn list_chunks<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let info: ChunksInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
+
+    let db = server.db(&db_name).await.context(BucketNotFound {
+        org: &info.org,
+        bucket: &info.bucket,
+    })?;
+
+    let summaries = db
+        .chunk_summaries(&info.partition)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(BucketByName {
+            org: &info.org,
+            bucket_name: &info.bucket,
+        })?
+        .context(PartitionNotFound {
+            org: &info.org,
+            bucket: &info.bucket,
+            key: &info.partition,
+        })?;
+
+    let chunks: Vec<_> = summaries
+        .iter()
+        .map(|chunk| {
+            serde_json::json!({
+                "id": chunk.id,
+                "row_count": chunk.row_count(),
+                "time_range": chunk.time_range().map(|(min, max)| {
+                    serde_json::json!({"min": min, "max": max})
+                }),
+                "size": chunk.size(),
+            })
+        })
+        .collect();
+
+    let result = serde_json::to_string(&chunks).context(JsonGenerationError)?;
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[derive(Deserialize, Debug)]
+/// Arguments in the query string of the request to /time_range
+struct TimeRangeInfo {
+    org: String,
+    bucket: String,
+    measurement: String,
+}
+
+#[tracing::instrument(level = "debug")]
+async fn time_range_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match time_range::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Returns the minimum and maximum (inclusive) timestamps of a measurement,
+/// e.g. for choosing the default time window of a dashboard.
+#[tracing::instrument(level = "debug")]
+async fn time_range<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let info: TimeRangeInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
+
+    let db = server.db(&db_name).await.context(BucketNotFound {
+        org: &info.org,
+        bucket: &info.bucket,
+    })?;
+
+    let (min, max) = db
+        .time_bounds(&info.measurement)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(BucketByName {
+            org: &info.org,
+            bucket_name: &info.bucket,
+        })?
+        .context(MeasurementNotFound {
+            org: &info.org,
+            bucket: &info.bucket,
+            measurement: &info.measurement,
+        })?;
+
+    let result = serde_json::json!({"min": min, "max": max}).to_string();
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[derive(Deserialize, Debug)]
+/// Arguments in the query string of the request to /explain. Times are
+/// RFC3339 strings or nanoseconds since the epoch, as for /read.
+struct ExplainInfo {
+    org: String,
+    bucket: String,
+    measurement: String,
+    start: Option<String>,
+    stop: Option<String>,
+}
+
+#[tracing::instrument(level = "debug")]
+async fn explain_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match explain::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Returns an estimate of the cost of reading a measurement from `start`
+/// (inclusive) until `stop` (exclusive), without reading it, to help
+/// understand why a query is slow.
+#[tracing::instrument(level = "debug")]
+async fn explain<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let info: ExplainInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let range = TimestampRange::new(
+        info.start
+            .as_deref()
+            .map(parse_timestamp)
+            .transpose()?
+            .unwrap_or(i64::MIN),
+        info.stop
+            .as_deref()
+            .map(parse_timestamp)
+            .transpose()?
+            .unwrap_or(i64::MAX),
+    );
+
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
+
+    let db = server.db(&db_name).await.context(BucketNotFound {
+        org: &info.org,
+        bucket: &info.bucket,
+    })?;
+
+    let estimate = db
+        .explain(&info.measurement, range)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(BucketByName {
+            org: &info.org,
+            bucket_name: &info.bucket,
+        })?
+        .context(MeasurementNotFound {
+            org: &info.org,
+            bucket: &info.bucket,
+            measurement: &info.measurement,
+        })?;
+
+    let result = serde_json::json!({
+        "chunks_scanned": estimate.chunks_scanned,
+        "estimated_rows": estimate.estimated_rows,
+        "columns": estimate.columns,
+    })
+    .to_string();
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[derive(Deserialize, Debug)]
+/// Arguments in the query string of the request to /snapshot
+struct SnapshotInfo {
+    org: String,
+    bucket: String,
+    chunk: String,
+}
+
+#[tracing::instrument(level = "debug")]
+async fn snapshot_partition_handler<M>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match snapshot_partition::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+#[tracing::instrument(level = "debug")]
+async fn snapshot_partition<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let snapshot: SnapshotInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let db_name = org_and_bucket_to_database(&snapshot.org, &snapshot.bucket)
+        .map_err(invalid_database_name)?;
+
+    // TODO: refactor the rest of this out of the http route and into the server
+    // crate.
+    let db = server.db(&db_name).await.context(BucketNotFound {
+        org: &snapshot.org,
+        bucket: &snapshot.bucket,
+    })?;
+
+    // Rolling over a partition that doesn't exist would create it.
+    let partition_keys = db
+        .partition_keys()
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(SnapshotError {
+            database: db_name.to_string(),
+            key: &snapshot.chunk,
+        })?;
+    ensure!(
+        partition_keys.contains(&snapshot.chunk),
+        PartitionNotFound {
+            org: &snapshot.org,
+            bucket: &snapshot.bucket,
+            key: &snapshot.chunk,
+        }
+    );
+
+    let metadata_path = format!("{}/meta", &db_name);
+    let data_path = format!("{}/data/{}", &db_name, &snapshot.chunk);
+    let partition = db
+        .rollover_partition(&snapshot.chunk)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(SnapshotError {
+            database: db_name.to_string(),
+            key: &snapshot.chunk,
+        })?;
+    let snapshot = server::snapshot::snapshot_chunk(
+        metadata_path,
+        data_path,
+        server.store.clone(),
+        partition,
+        None,
+    )
+    .map_err(|e| Box::new(e) as _)
+    .context(SnapshotError {
+        database: db_name.to_string(),
+        key: &snapshot.chunk,
+    })?;
+    server.add_snapshot(&db_name, snapshot.clone()).await;
+
+    let ret = format!("{}", snapshot.id);
+    Ok(Response::new(Body::from(ret)))
+}
+
+// The description of a snapshot returned by the snapshot routes.
+fn snapshot_json(snapshot: &SnapshotSummary) -> serde_json::Value {
+    serde_json::json!({
+        "id": snapshot.id.to_string(),
+        "partition_key": snapshot.chunk_meta.key,
+        "chunk_id": snapshot.chunk_meta.id,
+        "state": snapshot.state,
+        "error": snapshot.error,
+    })
+}
+
+#[tracing::instrument(level = "debug")]
+async fn snapshot_status_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match snapshot_status::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+#[tracing::instrument(level = "debug")]
+async fn snapshot_status<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let id = req.param("id").expect("snapshot id in route");
+    let id = uuid::Uuid::parse_str(id).context(InvalidSnapshotId { id })?;
+
+    let snapshot = server.snapshot(id).await.context(SnapshotNotFound { id })?;
+
+    Ok(Response::new(Body::from(
+        snapshot_json(&snapshot).to_string(),
+    )))
+}
+
+#[tracing::instrument(level = "debug")]
+async fn list_snapshots_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match list_snapshots::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+#[tracing::instrument(level = "debug")]
+async fn list_snapshots<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let query = req.uri().query().context(ExpectedQueryString {})?;
+
+    let info: DatabaseInfo = serde_urlencoded::from_str(query).context(InvalidQueryString {
+        query_string: query,
+    })?;
+
+    let db_name =
+        org_and_bucket_to_database(&info.org, &info.bucket).map_err(invalid_database_name)?;
+
+    server.db(&db_name).await.context(BucketNotFound {
+        org: &info.org,
+        bucket: &info.bucket,
+    })?;
+
+    let snapshots: Vec<_> = server
+        .snapshots(&db_name)
+        .await
+        .iter()
+        .map(snapshot_json)
+        .collect();
+
+    let result = serde_json::to_string(&snapshots).context(JsonGenerationError)?;
+
+    Ok(Response::new(Body::from(result)))
+}
+
+#[tracing::instrument(level = "debug")]
+async fn create_database_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match create_database::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Creates a database with the rules in the JSON body of the request.
+#[tracing::instrument(level = "debug")]
+async fn create_database<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let name = req.param("name").expect("database name in route").clone();
+
+    let body = parse_body(req).await?;
+    let rules: DatabaseRules = parse_json_body(&body)?;
+
+    server
+        .create_database(&name, rules)
+        .await
+        .map_err(|e| match e {
+            ServerError::DatabaseAlreadyExists { .. } => {
+                ApplicationError::DatabaseAlreadyExists { name: name.clone() }
+            }
+            e => ApplicationError::DatabaseError {
+                database: name.clone(),
+                source: Box::new(e),
+            },
+        })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[tracing::instrument(level = "debug")]
+async fn delete_database_handler<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match delete_database::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Removes a database. Queries already running against it complete, but
+/// subsequent reads and writes are rejected as it no longer exists.
+#[tracing::instrument(level = "debug")]
+async fn delete_database<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let name = req.param("name").expect("database name in route").clone();
+
+    server.remove_database(&name).await.map_err(|e| match e {
+        ServerError::DatabaseNotFound { .. } | ServerError::InvalidDatabaseName { .. } => {
+            ApplicationError::DatabaseNotFound { name: name.clone() }
+        }
+        e => ApplicationError::DatabaseError {
+            database: name.clone(),
+            source: Box::new(e),
+        },
+    })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[tracing::instrument(level = "debug")]
+async fn update_database_rules_handler<M>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    match update_database_rules::<M>(req).await {
+        Err(e) => {
+            error!(error = ?e, error_message = ?e.to_string(), "Error while handling request");
+
+            e.response()
+        }
+        res => res,
+    }
+}
+
+/// Updates some of the rules of a database from a JSON object of the rules to
+/// change, e.g. `{"store_locally": false}`. Subsequent writes observe the new
+/// rules.
+#[tracing::instrument(level = "debug")]
+async fn update_database_rules<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = req
+        .data::<Arc<AppServer<M>>>()
+        .expect("server state")
+        .clone();
+    let name = req.param("name").expect("database name in route").clone();
+
+    let body = parse_body(req).await?;
+    let update: serde_json::Map<String, serde_json::Value> = parse_json_body(&body)?;
+
+    server
+        .update_database_rules(&name, update)
+        .await
+        .map_err(|e| match e {
+            ServerError::DatabaseNotFound { .. } | ServerError::InvalidDatabaseName { .. } => {
+                ApplicationError::DatabaseNotFound { name: name.clone() }
+            }
+            ServerError::UnknownRule { .. } | ServerError::InvalidRules { .. } => {
+                ApplicationError::InvalidDatabaseRules {
+                    name: name.clone(),
+                    source: e,
+                }
+            }
+            e => ApplicationError::DatabaseError {
+                database: name.clone(),
+                source: Box::new(e),
+            },
+        })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+/// Creates the service routing HTTP API requests to the server. Request bodies
+/// larger than `max_request_size` bytes, either before or after they're
+/// decompressed, are rejected.
+pub fn router_service<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    server: Arc<AppServer<M>>,
+    max_request_size: usize,
+    auth_token: Option<String>,
+    cors_allowed_origins: Vec<String>,
+    query_timeout: Duration,
+    resolver: Arc<dyn OrgBucketResolver>,
+) -> RouterService<Body, ApplicationError> {
+    let router = router(
+        server,
+        max_request_size,
+        auth_token,
+        cors_allowed_origins,
+        query_timeout,
+        resolver,
+    );
+    RouterService::new(router).unwrap()
+}
+
+/// Serves the HTTP API on `bind_addr` until `shutdown` completes, after which
+/// no new connections are accepted but requests in flight are completed.
+/// Returns the address bound to, which has the port the OS picked if
+/// `bind_addr`'s port is 0, and the future that runs the server.
+pub fn serve<F>(
+    bind_addr: &SocketAddr,
+    router_service: RouterService<Body, ApplicationError>,
+    shutdown: F,
+) -> Result<(SocketAddr, impl Future<Output = Result<(), hyper::Error>>), hyper::Error>
+where
+    F: Future<Output = ()>,
+{
+    let server = hyper::Server::try_bind(bind_addr)?.serve(router_service);
+    let local_addr = server.local_addr();
+    Ok((local_addr, server.with_graceful_shutdown(shutdown)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    use http::header;
+    use reqwest::{Client, Response};
+
+    use tokio::task::JoinHandle;
+
+    use data_types::database_rules::{DatabaseRules, PartitionTemplate, TemplatePart};
+    use data_types::DatabaseName;
+    use object_store::{InMemory, ObjectStore};
+    use server::server::ConnectionManagerImpl;
+
+    use crate::server::IdsAsNames;
+
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+    type Result<T, E = Error> = std::result::Result<T, E>;
+
+    #[tokio::test]
+    async fn test_ping() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+        let response = client.get(&format!("{}/ping", server_url)).send().await;
+
+        // Print the response so if the test fails, we have a log of what went wrong
+        check_response("ping", response, StatusCode::OK, "PONG").await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let (shutdown, shutdown_signal) = tokio::sync::oneshot::channel::<()>();
+        let (server_url, server) = test_server_with_shutdown(test_storage.clone(), async {
+            shutdown_signal.await.ok();
+        });
+        let client = hyper::Client::new();
+
+        // Start a write, sending its body in two parts so that it's in flight
+        // when the server is shut down.
+        let (mut body, request_body) = Body::channel();
+        let request = Request::post(format!(
+            "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+            server_url
+        ))
+        .body(request_body)?;
+        let response = tokio::spawn(client.request(request));
+        body.send_data("h2o_temperature,location=santa_monica ".into())
+            .await?;
+        tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
+
+        shutdown.send(()).unwrap();
+        body.send_data("surface_degrees=65.2 1568756160".into())
+            .await?;
+        drop(body);
+
+        let response = response.await??;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        server.await??;
+
+        // The write completed, and the server no longer accepts requests.
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let batches = test_db.query("select * from h2o_temperature").await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+        assert!(Client::new()
+            .get(&format!("{}/ping", server_url))
+            .send()
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cors() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        let server_url = test_server_with_cors(test_storage.clone(), &["https://dash.example.com"]);
+        let client = Client::new();
+
+        let response = client
+            .request(Method::OPTIONS, &format!("{}/api/v2/read", server_url))
+            .header(header::ORIGIN, "https://dash.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://dash.example.com"
+        );
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_METHODS],
+            CORS_ALLOWED_METHODS
+        );
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            CORS_ALLOWED_HEADERS
+        );
+        assert_eq!(headers[header::VARY], "Origin");
+
+        let response = client
+            .get(&format!("{}/ping", server_url))
+            .header(header::ORIGIN, "https://dash.example.com")
+            .send()
+            .await?;
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://dash.example.com"
+        );
+        check_response("ping", Ok(response), StatusCode::OK, "PONG").await;
+
+        // Other origins aren't told they're allowed.
+        let response = client
+            .get(&format!("{}/ping", server_url))
+            .header(header::ORIGIN, "https://evil.example.com")
+            .send()
+            .await?;
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        // CORS is disabled by default.
+        let server_url = test_server(test_storage);
+        let response = client
+            .get(&format!("{}/ping", server_url))
+            .header(header::ORIGIN, "https://dash.example.com")
+            .send()
+            .await?;
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_auth_token() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server_with_auth_token(test_storage.clone(), "secret");
+        let client = Client::new();
+
+        let write = |authorization: Option<&'static str>| {
+            let request = client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                    server_url
+                ))
+                .body("h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160");
+            match authorization {
+                Some(authorization) => request.header(header::AUTHORIZATION, authorization),
+                None => request,
+            }
+            .send()
+        };
+
+        let expected_error = serde_json::json!({
+            "error": ApplicationError::Unauthorized {}.to_string()
+        })
+        .to_string();
+
+        let response = write(None).await;
+        check_response(
+            "missing token",
+            response,
+            StatusCode::UNAUTHORIZED,
+            &expected_error,
+        )
+        .await;
+
+        let response = write(Some("Token wrong")).await;
+        check_response(
+            "invalid token",
+            response,
+            StatusCode::UNAUTHORIZED,
+            &expected_error,
+        )
+        .await;
+
+        let response = write(Some("Bearer secret")).await;
+        check_response(
+            "wrong scheme",
+            response,
+            StatusCode::UNAUTHORIZED,
+            &expected_error,
+        )
+        .await;
+
+        // Nothing was written by the rejected requests.
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        assert!(test_db.partition_keys().await.unwrap().is_empty());
+
+        let response = write(Some("Token secret")).await;
+        check_response("valid token", response, StatusCode::NO_CONTENT, "").await;
+        assert_eq!(test_db.partition_keys().await.unwrap().len(), 1);
+
+        // Probes don't need the token.
+        test_storage.set_ready();
+        let response = client.get(&format!("{}/ping", server_url)).send().await;
+        check_response("ping", response, StatusCode::OK, "PONG").await;
+        let response = client
+            .get(&format!("{}/health/ready", server_url))
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+        let response = client
+            .get(&format!("{}/api/v2/capabilities", server_url))
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let capabilities: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        let aggregates = capabilities["aggregates"].as_array().unwrap();
+        for aggregate in &[
+            "sum",
+            "count",
+            "min",
+            "max",
+            "percentile",
+            "approx_quantile",
+        ] {
+            assert!(aggregates.contains(&serde_json::json!(aggregate)));
+        }
+        assert!(capabilities["operators"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("=")));
+        assert_eq!(
+            capabilities["output_formats"],
+            serde_json::json!(["pretty", "json", "csv", "arrow"])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metrics() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let scrape = || client.get(&format!("{}/metrics", server_url)).send();
+
+        let metrics = scrape().await?.text().await?;
+        assert!(metrics.contains("\niox_http_write_requests_total 0\n"));
+        assert!(metrics.contains("\niox_databases 1\n"));
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let metrics = scrape().await?.text().await?;
+        assert!(metrics.contains("\niox_http_write_requests_total 1\n"));
+        assert!(metrics.contains(&format!("\niox_http_write_bytes_total {}\n", lp_data.len())));
+        assert!(metrics.contains("\niox_http_read_requests_total 0\n"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica,state=CA surface_degrees=65.2,bottom_degrees=50.4 1568756160";
+
+        // send write data
+        let bucket_name = "MyBucket";
+        let org_name = "MyOrg";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket={}&org={}",
+                server_url, bucket_name, org_name
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // Check that the data got into the right bucket
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+
+        let results = test_db
+            .query("select * from h2o_temperature")
+            .await
+            .unwrap();
+        let results_str = arrow::util::pretty::pretty_format_batches(&results).unwrap();
+        let results: Vec<_> = results_str.split('\n').collect();
+
+        let expected = vec![
+            "+----------------+--------------+-------+-----------------+------------+",
+            "| bottom_degrees | location     | state | surface_degrees | time       |",
+            "+----------------+--------------+-------+-----------------+------------+",
+            "| 50.4           | santa_monica | CA    | 65.2            | 1568756160 |",
+            "+----------------+--------------+-------+-----------------+------------+",
+            "",
+        ];
+        assert_eq!(results, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_database_rules() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+        let write = |lp_data: &'static str| {
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                    server_url
+                ))
+                .body(lp_data)
+                .send()
+        };
+        let update_rules = |db_name: &str, body: &'static str| {
+            client
+                .patch(&format!(
+                    "{}/api/v2/databases/{}/rules",
+                    server_url, db_name
+                ))
+                .body(body)
+                .send()
+        };
+
+        let response =
+            write("h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = update_rules("MyOrg_MyBucket", r#"{"store_locally": false}"#).await;
+        check_response("update rules", response, StatusCode::NO_CONTENT, "").await;
+
+        // Writes after the update are no longer stored locally.
+        let response =
+            write("h2o_temperature,location=boston surface_degrees=50.1 1568756170").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let results = test_db
+            .query("select location from h2o_temperature")
+            .await
+            .unwrap();
+        let results = arrow::util::pretty::pretty_format_batches(&results).unwrap();
+        assert!(results.contains("santa_monica"), "{}", results);
+        assert!(!results.contains("boston"), "{}", results);
+
+        // Invalid updates are rejected.
+        for &body in &[
+            r#"{"store_locally": "yes"}"#,
+            r#"{"no_such_rule": true}"#,
+            r#"["store_locally"]"#,
+        ] {
+            let response = update_rules("MyOrg_MyBucket", body).await?;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+        }
+
+        let response = update_rules("NotMyOrg_MyBucket", r#"{"store_locally": true}"#).await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_ready() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let health = || client.get(&format!("{}/health/ready", server_url)).send();
+
+        // Not ready until the server has an id and the WAL has been replayed.
+        let response = health().await?;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "ready": false,
+                "checks": {
+                    "server_id": "server ID not set",
+                    "replay": "WAL replay in progress",
+                    "object_store": "ok",
+                },
+            })
+        );
+
+        // /ping is unaffected.
+        let response = client.get(&format!("{}/ping", server_url)).send().await;
+        check_response("ping", response, StatusCode::OK, "PONG").await;
+
+        test_storage.set_id(1).await;
+
+        let response = health().await?;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "ready": false,
+                "checks": {
+                    "server_id": "ok",
+                    "replay": "WAL replay in progress",
+                    "object_store": "ok",
+                },
+            })
+        );
+
+        test_storage.set_ready();
+
+        let response = health().await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "ready": true,
+                "checks": {"server_id": "ok", "replay": "ok", "object_store": "ok"},
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_rejected_until_ready() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+
+        // Writes are accepted while the server is starting up, e.g. while
+        // replaying the WAL.
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let read = || {
+            client
+                .get(&format!("{}/api/v2/read", server_url))
+                .query(&[
+                    ("org", "MyOrg"),
+                    ("bucket", "MyBucket"),
+                    ("sql_query", "select * from h2o_temperature"),
+                ])
+                .send()
+        };
+
+        // Until the server is ready, reads are rejected rather than returning
+        // incomplete data.
+        let response = read().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = client
+            .get(&format!("{}/health/ready", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // The server is still live.
+        let response = client.get(&format!("{}/ping", server_url)).send().await;
+        check_response("ping", response, StatusCode::OK, "PONG").await;
+
+        test_storage.set_ready();
+
+        let response = read().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.text().await.unwrap();
+        assert!(body.contains("santa_monica"), "unexpected body: {}", body);
+
+        let response = client
+            .get(&format!("{}/health/ready", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_malformed_json_body() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        let server_url = test_server(test_storage.clone());
+
+        // a large body with a JSON syntax error on the second line
+        let body = format!("{{\"org\": \"MyOrg\",\n \"bucket\": {}", "a".repeat(10_000));
+
+        let client = Client::new();
+        let response = client
+            .post(&format!("{}/api/v2/read", server_url))
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response: serde_json::Value =
+            serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        let error = response["error"].as_str().unwrap();
+        assert!(
+            error.contains("at line 2, column "),
+            "unexpected error: {}",
+            error
+        );
+
+        // the echoed body is truncated
+        let echoed_body = format!("{}...", &body[..MAX_ECHOED_BODY_CHARS]);
+        assert!(error.contains(&echoed_body), "unexpected error: {}", error);
+        assert!(error.len() < body.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body("short"), "short");
+
+        let long = "é".repeat(MAX_ECHOED_BODY_CHARS + 1);
+        let truncated = truncate_body(&long);
+        assert_eq!(truncated.chars().count(), MAX_ECHOED_BODY_CHARS + 3);
+        assert!(truncated.ends_with("..."));
+    }
+
+    #[tokio::test]
+    async fn test_write_precision() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let write = |precision: &str, lp_data: &'static str| {
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg&precision={}",
+                    server_url, precision
+                ))
+                .body(lp_data)
+                .send()
+        };
+
+        let response = write("s", "h2o_temperature surface_degrees=65.2 1568756160").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        let response = write("ms", "h2o_temperature surface_degrees=50.4 1568756170000").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let results = test_db
+            .query("select time from h2o_temperature order by time")
+            .await
+            .unwrap();
+        let results = arrow::util::pretty::pretty_format_batches(&results).unwrap();
+        assert!(results.contains("1568756160000000000"), "{}", results);
+        assert!(results.contains("1568756170000000000"), "{}", results);
+
+        let response = write("m", "h2o_temperature surface_degrees=65.2 1568756160").await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"Invalid precision 'm': expected one of ns, us, ms or s"}"#,
+        )
+        .await;
+
+        let response = write(
+            "s",
+            "h2o_temperature surface_degrees=65.2 9223372036854775807",
+        )
+        .await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_org_and_bucket_ids() -> Result<()> {
+        // Maps the ids of a single org & bucket to their names.
+        #[derive(Debug)]
+        struct TestResolver;
+
+        impl OrgBucketResolver for TestResolver {
+            fn database_name(
+                &self,
+                org_id: &str,
+                bucket_id: &str,
+            ) -> Result<Option<DatabaseName<'static>>, OrgBucketMappingError> {
+                if org_id != "0001" || bucket_id != "0002" {
+                    return Ok(None);
+                }
+                org_and_bucket_to_database("MyOrg", "MyBucket").map(Some)
+            }
+        }
+
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let make_svc = router_service(
+            test_storage.clone(),
+            DEFAULT_MAX_REQUEST_SIZE,
+            None,
+            vec![],
+            DEFAULT_QUERY_TIMEOUT,
+            Arc::new(TestResolver),
+        );
+        let (resolving_server_url, _) = spawn_test_server(make_svc, futures::future::pending());
+
+        let client = Client::new();
+        let write = |server_url: &str, params: &str, lp_data: &'static str| {
+            client
+                .post(&format!("{}/api/v2/write?{}", server_url, params))
+                .body(lp_data)
+                .send()
+        };
+
+        // By name.
+        let response = write(&server_url, "org=MyOrg&bucket=MyBucket", "h2o temp=1.0 1").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // By id, which by default are mapped in the same way as names.
+        let response = write(
+            &server_url,
+            "orgID=MyOrg&bucketID=MyBucket",
+            "h2o temp=2.0 2",
+        )
+        .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // By id, mapped by the resolver.
+        let response = write(
+            &resolving_server_url,
+            "orgID=0001&bucketID=0002",
+            "h2o temp=3.0 3",
+        )
+        .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        let response = write(
+            &resolving_server_url,
+            "orgID=0001&bucketID=0003",
+            "h2o temp=4.0 4",
+        )
+        .await;
+        check_response("write", response, StatusCode::NOT_FOUND, "").await;
+
+        // Names and ids can't be mixed.
+        let response = write(&server_url, "org=MyOrg&bucketID=MyBucket", "h2o temp=5.0 5").await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_REQUEST);
+        let response = write(&server_url, "org=MyOrg", "h2o temp=5.0 5").await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_REQUEST);
+
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let results = test_db.query("select time from h2o").await.unwrap();
+        let rows: usize = results.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_invalid_database_name() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let write = |org: &str, bucket: &str| {
+            client
+                .post(&format!("{}/api/v2/write", server_url))
+                .query(&[("org", org), ("bucket", bucket)])
+                .body("h2o temp=1.0 1")
+                .send()
+        };
+
+        let response = write("", "MyBucket").await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"Invalid database name '_MyBucket': the org and bucket names must not be empty"}"#,
+        )
+        .await;
+
+        let bucket = "b".repeat(64);
+        let response = write("MyOrg", &bucket).await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            &format!(
+                r#"{{"error":"Invalid database name 'MyOrg_{}': the org and bucket names, joined by '_', must be at most 64 characters long"}}"#,
+                bucket
+            ),
+        )
+        .await;
+
+        let response = write("MyOrg", "My Bucket!").await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"Invalid database name 'MyOrg_My Bucket!': the org and bucket names may only contain alphanumeric characters and '-'"}"#,
+        )
+        .await;
+
+        let response = write("My_Org", "MyBucket").await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"Invalid database name 'My_Org_MyBucket': the '_' character is reserved in org and bucket names"}"#,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_content_type() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let write = |content_type: &'static str| {
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                    server_url
+                ))
+                .header(header::CONTENT_TYPE, content_type)
+                .body("h2o_temperature surface_degrees=65.2 1568756160")
+                .send()
+        };
+
+        let response = write("text/plain").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        let response = write("text/plain; charset=utf-8").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = write("application/json").await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"Invalid content type 'application/json': expected text/plain line protocol"}"#,
+        )
+        .await;
+
+        // The content encoding is independent of the content type.
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .header(header::CONTENT_TYPE, "text/plain")
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(gzip_str("h2o_temperature surface_degrees=50.4 1568756170"))
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let results = test_db
+            .query("select time from h2o_temperature")
+            .await
+            .unwrap();
+        let rows: usize = results.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_verbose() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = vec![
+            "h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160",
+            "h2o_temperature,location=boston surface_degrees=50.1 1568756170",
+            "h2o_temperature,location=boston surface_degrees=50.4 1568756180",
+        ]
+        .join("\n");
+
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg&verbose=true",
+                server_url
+            ))
+            .body(lp_data.clone())
+            .send()
+            .await;
+        check_response(
+            "write",
+            response,
+            StatusCode::OK,
+            r#"{"future_points_clamped":0,"future_points_rejected":0,"lines_parsed":3,"points_written":3}"#,
+        )
+        .await;
+
+        // Without the flag no content is returned.
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg&verbose=false",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_future_points() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            max_future_skew: Some(std::time::Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let now = chrono::Utc::now().timestamp_nanos();
+        let hour = 3_600_000_000_000;
+        let lp_data = format!(
+            "h2o_temperature,location=boston surface_degrees=50.1 {}\n\
+             h2o_temperature,location=boston surface_degrees=50.4 {}",
+            now,
+            now + 24 * hour
+        );
+
+        // The rejected point is reported even without the verbose flag.
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response(
+            "write",
+            response,
+            StatusCode::OK,
+            r#"{"future_points_clamped":0,"future_points_rejected":1,"lines_parsed":2,"points_written":1}"#,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_partial_write() -> Result<()> {
+        use query::TSDatabase;
+
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let write = |measurement: &str, partial: bool| {
+            let lp_data = vec![
+                format!(
+                    "{},location=santa_monica surface_degrees=65.2 1568756160",
+                    measurement
+                ),
+                format!(
+                    "{},location=boston surface_degrees= 1568756170",
+                    measurement
+                ),
+                format!(
+                    "{},location=boston surface_degrees=50.4 1568756180",
+                    measurement
+                ),
+            ]
+            .join("\n");
+
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg&partial={}",
+                    server_url, partial
+                ))
+                .body(lp_data)
+                .send()
+        };
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+
+        // By default nothing is written if any line can't be parsed.
+        let response = write("h2o_temperature", false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(test_db.partition_keys().await.unwrap().is_empty());
+
+        let response = write("h2o_temperature", true).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(body["error"], "1 lines could not be parsed");
+        assert_eq!(body["points_written"], 2);
+        let line_errors = body["line_errors"].as_array().unwrap();
+        assert_eq!(line_errors.len(), 1);
+        assert_eq!(line_errors[0]["line"], 2);
+        assert!(!line_errors[0]["reason"].as_str().unwrap().is_empty());
+
+        let results = test_db
+            .query("select location from h2o_temperature")
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_request_size() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160";
+        let server_url = test_server_with_max_request_size(test_storage.clone(), lp_data.len());
+        let client = Client::new();
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // A body one byte over the limit is rejected.
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(format!("{}\n", lp_data))
+            .send()
+            .await;
+        check_response(
+            "write",
+            response,
+            StatusCode::BAD_REQUEST,
+            &format!(
+                r#"{{"error":"Body exceeds limit of {} bytes"}}"#,
+                lp_data.len()
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_decompressed_request_size() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+
+        // Compresses to much less than the limit
+        let lp_data = (0..100)
+            .map(|i| {
+                format!(
+                    "h2o_temperature,location=santa_monica surface_degrees=65.2 {}",
+                    1568756160 + i
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let server_url = test_server_with_max_request_size(test_storage.clone(), lp_data.len());
+        let client = Client::new();
+        let url = format!(
+            "{}/api/v2/write?bucket=MyBucket&org=MyOrg&precision=s",
+            server_url
+        );
+        let exceeded = format!(
+            r#"{{"error":"Body exceeds limit of {} bytes"}}"#,
+            lp_data.len()
+        );
+
+        let response = client
+            .post(&url)
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(gzip_str(&lp_data))
+            .send()
+            .await;
+        check_response("gzip write", response, StatusCode::NO_CONTENT, "").await;
+
+        // A body that decompresses to one byte over the limit is rejected,
+        // rather than truncated.
+        let response = client
+            .post(&url)
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(gzip_str(&format!("{}\n", lp_data)))
+            .send()
+            .await;
+        check_response("gzip write", response, StatusCode::BAD_REQUEST, &exceeded).await;
+
+        let response = client
+            .post(&url)
+            .header(header::CONTENT_ENCODING, "zstd")
+            .body(zstd::encode_all(format!("{}\n", lp_data).as_bytes(), 0)?)
+            .send()
+            .await;
+        check_response("zstd write", response, StatusCode::BAD_REQUEST, &exceeded).await;
+
+        Ok(())
+    }
+
+    fn gzip_str(s: &str) -> Vec<u8> {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        write!(encoder, "{}", s).expect("writing into encoder");
+        encoder.finish().expect("successfully encoding gzip data")
+    }
+
+    #[tokio::test]
+    async fn test_gzip_read() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = (0..100)
+            .map(|i| {
+                format!(
+                    "h2o_temperature,location=location{} surface_degrees={} {}",
+                    i,
+                    i,
+                    1568756160 + i
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let read = |sql_query: &'static str, accept_encoding: &'static str| {
+            client
+                .get(&format!("{}/api/v2/read", server_url))
+                .query(&[
+                    ("org", "MyOrg"),
+                    ("bucket", "MyBucket"),
+                    ("sql_query", sql_query),
+                ])
+                .header(header::ACCEPT_ENCODING, accept_encoding)
+                .send()
+        };
+
+        let query = "select * from h2o_temperature";
+        let response = read(query, "identity").await?;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let expected = response.text().await?;
+        assert!(expected.len() > MIN_COMPRESSED_RESPONSE_SIZE);
+
+        let response = read(query, "br, gzip;q=0.8").await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let compressed = response.bytes().await?;
+        assert!(compressed.len() < expected.len());
+
+        use std::io::Read;
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut decoded)?;
+        assert_eq!(decoded, expected);
+
+        // Small results aren't compressed.
+        let response = read("select count(*) from h2o_temperature", "gzip").await?;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert!(response.text().await?.contains("100"));
+
+        // Nor are results when gzip is explicitly refused.
+        let response = read(query, "gzip;q=0").await?;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_output_formats() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=77.5 1568756170";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let read = |accept: &'static str| {
+            client
+                .get(&format!("{}/api/v2/read", server_url))
+                .query(&[
+                    ("org", "MyOrg"),
+                    ("bucket", "MyBucket"),
+                    (
+                        "sql_query",
+                        "select location, surface_degrees, time from h2o_temperature order by time",
+                    ),
+                ])
+                .header(header::ACCEPT, accept)
+                .send()
+        };
+
+        let expected_pretty = vec![
+            "+--------------+-----------------+------------+",
+            "| location     | surface_degrees | time       |",
+            "+--------------+-----------------+------------+",
+            "| santa_monica | 65.2            | 1568756160 |",
+            "| coyote_creek | 77.5            | 1568756170 |",
+            "+--------------+-----------------+------------+",
+            "",
+        ]
+        .join("\n");
+
+        for accept in &["text/plain", "*/*", "text/html, text/plain;q=0.9"] {
+            let response = read(*accept).await?;
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "text/plain; charset=utf-8"
+            );
+            check_response(
+                "read pretty",
+                Ok(response),
+                StatusCode::OK,
+                &expected_pretty,
+            )
+            .await;
+        }
+
+        let response = read("application/json").await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let rows: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(
+            rows,
+            serde_json::json!([
+                {"location": "santa_monica", "surface_degrees": 65.2, "time": 1568756160},
+                {"location": "coyote_creek", "surface_degrees": 77.5, "time": 1568756170},
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_csv() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2,bottom_degrees=50.4 1568756160\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=77.5 1568756170";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                (
+                    "sql_query",
+                    "select location, bottom_degrees, surface_degrees, time \
+                     from h2o_temperature order by time",
+                ),
+            ])
+            .header(header::ACCEPT, "text/csv")
+            .send()
+            .await?;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+
+        // The missing bottom_degrees of the second row is an empty field.
+        let expected = "location,bottom_degrees,surface_degrees,time\n\
+                        santa_monica,50.4,65.2,1568756160\n\
+                        coyote_creek,,77.5,1568756170\n";
+        check_response("read csv", Ok(response), StatusCode::OK, expected).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_table_not_found() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("sql_query", "select * from h2o_pressure"),
+            ])
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(response["error"], "Table h2o_pressure not found");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_structured() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica,state=CA surface_degrees=65.2 100\n\
+                       h2o_temperature,location=coyote_creek,state=CA surface_degrees=77.5 200\n\
+                       h2o_temperature,location=santa_monica,state=CA surface_degrees=66.1 300\n\
+                       h2o_temperature,location=puget_sound,state=WA surface_degrees=55.0 400";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // Each predicate is a separate parameter in the query string.
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("measurement", "h2o_temperature"),
+                ("start", "150"),
+                ("stop", "1970-01-01T00:00:00.000000400Z"),
+                ("predicate", "state=CA"),
+                ("predicate", "location!=coyote_creek"),
+            ])
+            .header(header::ACCEPT, "text/csv")
+            .send()
+            .await;
+        let expected = "location,state,surface_degrees,time\n\
+                        santa_monica,CA,66.1,300\n";
+        check_response("read structured", response, StatusCode::OK, expected).await;
+
+        let response = client
+            .post(&format!("{}/api/v2/read", server_url))
+            .body(
+                r#"{"org":"MyOrg","bucket":"MyBucket","measurement":"h2o_temperature",
+                    "stop":"250","predicate":["location=coyote_creek"]}"#,
+            )
+            .header(header::ACCEPT, "text/csv")
+            .send()
+            .await;
+        let expected = "location,state,surface_degrees,time\n\
+                        coyote_creek,CA,77.5,200\n";
+        check_response("read structured json", response, StatusCode::OK, expected).await;
+
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("measurement", "h2o_temperature"),
+                ("predicate", "location"),
+            ])
+            .send()
+            .await;
+        let expected =
+            r#"{"error":"Invalid predicate 'location': expected <tag>=<value> or <tag>!=<value>"}"#;
+        check_response(
+            "invalid predicate",
+            response,
+            StatusCode::BAD_REQUEST,
+            expected,
+        )
+        .await;
+
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("start", "yesterday"),
+            ])
+            .send()
+            .await;
+        let expected = r#"{"error":"Expected either measurement or sql_query in read request"}"#;
+        check_response(
+            "no measurement",
+            response,
+            StatusCode::BAD_REQUEST,
+            expected,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2 100\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=77.5 200\n\
+                       h2o_temperature,location=santa_monica surface_degrees=66.1 300\n\
+                       h2o_temperature,location=santa_monica surface_degrees=64.5 400";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .post(&format!(
+                "{}/api/v2/delete?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(
+                r#"{"start":"100","stop":"1970-01-01T00:00:00.000000300Z",
+                    "predicate":"_measurement=\"h2o_temperature\" AND location=\"santa_monica\""}"#,
+            )
+            .send()
+            .await;
+        check_response("delete", response, StatusCode::NO_CONTENT, "").await;
+
+        // Only santa_monica points from 100 to 300 inclusive are deleted.
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("measurement", "h2o_temperature"),
+            ])
+            .header(header::ACCEPT, "text/csv")
+            .send()
+            .await;
+        let expected = "location,surface_degrees,time\n\
+                        coyote_creek,77.5,200\n\
+                        santa_monica,64.5,400\n";
+        check_response("read after delete", response, StatusCode::OK, expected).await;
+
+        let response = client
+            .post(&format!(
+                "{}/api/v2/delete?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(r#"{"start":"0","stop":"500","predicate":"location"}"#)
+            .send()
+            .await;
+        let expected = r#"{"error":"Invalid delete predicate 'location': expected <tag>=\"<value>\" conditions joined by AND"}"#;
+        check_response(
+            "invalid predicate",
+            response,
+            StatusCode::BAD_REQUEST,
+            expected,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_delete_predicate_quoted_and() {
+        let range = TimestampRange::new(0, 100);
+        let tombstone = parse_delete_predicate(
+            r#"_measurement="cpu" AND host="a and b" and region="us AND eu""#,
+            Tombstone::new(range),
+        )
+        .unwrap();
+        assert_eq!(
+            tombstone,
+            Tombstone::new(range)
+                .with_table("cpu")
+                .with_tag("host", "a and b")
+                .with_tag("region", "us AND eu")
+        );
+
+        let got = parse_delete_predicate(r#"host="a and b"#, Tombstone::new(range)).unwrap_err();
+        assert!(matches!(got, ApplicationError::InvalidDeletePredicate { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_influxql_select() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2,bottom_degrees=50.4 1568756160\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=77.5 1568756170";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "MyOrg_MyBucket"),
+                (
+                    "q",
+                    "SELECT surface_degrees, bottom_degrees FROM h2o_temperature \
+                     WHERE time > 1568756160",
+                ),
+            ])
+            .send()
+            .await;
+        let expected = r#"{"results":[{"series":[{"columns":["time","surface_degrees","bottom_degrees"],"name":"h2o_temperature","values":[["1970-01-01T00:00:01.568756170Z",77.5,null]]}],"statement_id":0}]}"#;
+        check_response("select", response, StatusCode::OK, expected).await;
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "MyOrg_MyBucket"),
+                (
+                    "q",
+                    "SELECT * FROM h2o_temperature WHERE location = 'nowhere'",
+                ),
+            ])
+            .send()
+            .await;
+        check_response(
+            "select nothing",
+            response,
+            StatusCode::OK,
+            r#"{"results":[{"statement_id":0}]}"#,
+        )
+        .await;
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "MyOrg_MyBucket"),
+                (
+                    "q",
+                    "SELECT mean(surface_degrees) FROM h2o_temperature GROUP BY time(1m)",
+                ),
+            ])
+            .send()
+            .await;
+        let expected = r#"{"error":"Error parsing InfluxQL query 'SELECT mean(surface_degrees) FROM h2o_temperature GROUP BY time(1m)': unsupported InfluxQL: GROUP BY time()"}"#;
+        check_response("unsupported", response, StatusCode::BAD_REQUEST, expected).await;
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "NotMyDatabase"),
+                ("q", "SELECT * FROM h2o_temperature"),
+            ])
+            .send()
+            .await;
+        check_response("unknown database", response, StatusCode::NOT_FOUND, "").await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_influxql_grouped_aggregate() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.0 1568756160\n\
+                       h2o_temperature,location=santa_monica surface_degrees=67.0 1568756165\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=77.5 1568756170";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // The statement can be sent in a form encoded body.
+        let response = client
+            .post(&format!("{}/query?db=MyOrg_MyBucket&epoch=s", server_url))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(
+                "q=SELECT+mean(surface_degrees),+count(surface_degrees)+AS+n+\
+                 FROM+h2o_temperature+GROUP+BY+location",
+            )
+            .send()
+            .await;
+        let expected = r#"{"results":[{"series":[{"columns":["time","mean","n"],"name":"h2o_temperature","tags":{"location":"coyote_creek"},"values":[[0,77.5,1]]},{"columns":["time","mean","n"],"name":"h2o_temperature","tags":{"location":"santa_monica"},"values":[[0,66.0,2]]}],"statement_id":0}]}"#;
+        check_response("grouped aggregate", response, StatusCode::OK, expected).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_window() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=60.5 1568756400\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=70.25 1568756430\n\
+                       h2o_temperature,location=santa_monica surface_degrees=65.5 1568756460\n\
+                       h2o_temperature,location=santa_monica surface_degrees=66.25 1568756519\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=68.5 1568756580";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg&precision=s",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // Each series is windowed separately, each window starts on a whole
+        // minute, and windows without any rows are omitted.
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("measurement", "h2o_temperature"),
+                ("every", "1m"),
+                ("aggregate", "surface_degrees:mean"),
+                ("aggregate", "surface_degrees:count"),
+            ])
+            .header(header::ACCEPT, "text/csv")
+            .send()
+            .await;
+        let expected = "location,time,surface_degrees_mean,surface_degrees_count\n\
+                        coyote_creek,1568756400000000000,70.25,1\n\
+                        coyote_creek,1568756580000000000,68.5,1\n\
+                        santa_monica,1568756400000000000,60.5,1\n\
+                        santa_monica,1568756460000000000,65.875,2\n";
+        check_response("read window", response, StatusCode::OK, expected).await;
+
+        let response = client
+            .post(&format!("{}/api/v2/read", server_url))
+            .body(
+                r#"{"org":"MyOrg","bucket":"MyBucket","measurement":"h2o_temperature",
+                    "predicate":["location=santa_monica"],"every":"60s",
+                    "aggregate":["surface_degrees:max"]}"#,
+            )
+            .header(header::ACCEPT, "text/csv")
+            .send()
+            .await;
+        let expected = "location,time,surface_degrees_max\n\
+                        santa_monica,1568756400000000000,60.5\n\
+                        santa_monica,1568756460000000000,66.25\n";
+        check_response("read window json", response, StatusCode::OK, expected).await;
+
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("measurement", "h2o_temperature"),
+                ("every", "1d"),
+                ("aggregate", "surface_degrees:mean"),
+            ])
+            .send()
+            .await;
+        let expected = r#"{"error":"Invalid window aggregate: invalid duration '1d': expected a positive number of seconds, minutes or hours, such as 30s, 1m or 2h"}"#;
+        check_response("invalid every", response, StatusCode::BAD_REQUEST, expected).await;
+
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("measurement", "h2o_temperature"),
+                ("every", "1m"),
+                ("aggregate", "location:mean"),
+            ])
+            .send()
+            .await;
+        let expected = r#"{"error":"Invalid window aggregate: mean can't be applied to column 'location' of type Utf8"}"#;
+        check_response(
+            "unsupported aggregate",
+            response,
+            StatusCode::BAD_REQUEST,
+            expected,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_arrow() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = "h2o_temperature,location=santa_monica surface_degrees=65.2,bottom_degrees=50.4 1568756160\n\
+                       h2o_temperature,location=coyote_creek surface_degrees=77.5 1568756170";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let query = "select * from h2o_temperature order by time";
+        let response = client
+            .get(&format!("{}/api/v2/read", server_url))
+            .query(&[
+                ("org", "MyOrg"),
+                ("bucket", "MyBucket"),
+                ("sql_query", query),
+            ])
+            .header(header::ACCEPT, "application/vnd.apache.arrow.stream")
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/vnd.apache.arrow.stream"
+        );
+        let body = response.bytes().await?;
+
+        let reader = arrow::ipc::reader::StreamReader::try_new(&body[..])?;
+        let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let expected = test_db.query(query).await.unwrap();
+
+        assert_eq!(batches.len(), expected.len());
+        assert_eq!(batches[0].schema(), expected[0].schema());
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&batches)?,
+            arrow::util::pretty::pretty_format_batches(&expected)?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_timeout() -> Result<()> {
+        let deadline = QueryDeadline::new(Duration::from_millis(10));
+        let err = deadline
+            .run(async {
+                tokio::time::delay_for(Duration::from_secs(10)).await;
+                Ok::<_, ApplicationError>(())
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Query did not complete within 10ms");
+        assert_eq!(err.response()?.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let deadline = QueryDeadline::new(DEFAULT_QUERY_TIMEOUT);
+        let result = deadline
+            .run(async { Ok::<_, ApplicationError>(42) })
+            .await?;
+        assert_eq!(result, 42);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_streamed_read() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        // Each partition is read as a separate batch.
+        let rules = DatabaseRules {
+            store_locally: true,
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Column("location".to_string())],
+            },
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let lp_data = (0..300)
+            .map(|i| {
+                format!(
+                    "h2o_temperature,location=location{} surface_degrees={} {}",
+                    i % 3,
+                    i,
+                    1568756160 + i
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let query = "select * from h2o_temperature";
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        let batches = test_db.query(query).await.unwrap();
+        assert_eq!(batches.len(), 3);
+
+        let read = |accept: &'static str, accept_encoding: &'static str| {
+            client
+                .get(&format!("{}/api/v2/read", server_url))
+                .query(&[
+                    ("org", "MyOrg"),
+                    ("bucket", "MyBucket"),
+                    ("sql_query", query),
+                ])
+                .header(header::ACCEPT, accept)
+                .header(header::ACCEPT_ENCODING, accept_encoding)
+                .send()
+        };
+
+        // The streamed output matches formatting all of the batches at once.
+        let pretty = arrow::util::pretty::pretty_format_batches(&batches)?.into_bytes();
+        let json = serde_json::to_vec(&arrow::json::writer::record_batches_to_json_rows(&batches))?;
+        let mut csv = Vec::new();
+        let mut writer = arrow::csv::Writer::new(&mut csv);
+        for batch in &batches {
+            writer.write(batch)?;
+        }
+        drop(writer);
+        let mut ipc = Vec::new();
+        let mut writer = arrow::ipc::writer::StreamWriter::try_new(&mut ipc, &batches[0].schema())?;
+        for batch in &batches {
+            writer.write(batch)?;
+        }
+        writer.finish()?;
+        drop(writer);
+
+        for (accept, expected) in &[
+            ("text/plain", pretty),
+            ("application/json", json),
+            ("text/csv", csv.clone()),
+            ("application/vnd.apache.arrow.stream", ipc),
+        ] {
+            let response = read(*accept, "identity").await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.bytes().await?;
+            assert_eq!(&body[..], &expected[..], "streamed {} output", accept);
+        }
+
+        let response = read("text/csv", "gzip").await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let compressed = response.bytes().await?;
+
+        use std::io::Read;
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&compressed[..]).read_to_end(&mut decoded)?;
+        assert_eq!(decoded, csv);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parse_body_stream_error() {
+        let chunks: Vec<std::result::Result<Bytes, Error>> = vec![
+            Ok(Bytes::from("h2o_temperature,location=santa_monica ")),
+            Err("connection reset".into()),
+        ];
+        let request = hyper::Request::builder()
+            .method(Method::POST)
+            .uri("/api/v2/write?bucket=MyBucket&org=MyOrg")
+            .body(Body::wrap_stream(futures::stream::iter(chunks)))
+            .unwrap();
+
+        let err = parse_body(request).await.unwrap_err();
+        assert!(matches!(err, ApplicationError::ReadingBody { .. }));
+        assert_eq!(err.response().unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_gzip_write() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+        let lp_data = "h2o_temperature,location=santa_monica,state=CA surface_degrees=65.2,bottom_degrees=50.4 1568756160";
+
+        // send write data encoded with gzip
+        let bucket_name = "MyBucket";
+        let org_name = "MyOrg";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket={}&org={}",
+                server_url, bucket_name, org_name
+            ))
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(gzip_str(lp_data))
+            .send()
+            .await;
+
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // Check that the data got into the right bucket
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+
+        let results = test_db
+            .query("select * from h2o_temperature")
+            .await
+            .unwrap();
+        let results_str = arrow::util::pretty::pretty_format_batches(&results).unwrap();
+        let results: Vec<_> = results_str.split('\n').collect();
+
+        let expected = vec![
+            "+----------------+--------------+-------+-----------------+------------+",
+            "| bottom_degrees | location     | state | surface_degrees | time       |",
+            "+----------------+--------------+-------+-----------------+------------+",
+            "| 50.4           | santa_monica | CA    | 65.2            | 1568756160 |",
+            "+----------------+--------------+-------+-----------------+------------+",
+            "",
+        ];
+        assert_eq!(results, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_zstd_write() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+
+        let client = Client::new();
+        let lp_data = "h2o_temperature,location=santa_monica,state=CA surface_degrees=65.2,bottom_degrees=50.4 1568756160";
+
+        // send write data encoded with zstd
+        let bucket_name = "MyBucket";
+        let org_name = "MyOrg";
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket={}&org={}",
+                server_url, bucket_name, org_name
+            ))
+            .header(header::CONTENT_ENCODING, "zstd")
+            .body(zstd::encode_all(lp_data.as_bytes(), 0)?)
+            .send()
+            .await;
+
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        // Check that the data got into the right bucket
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+
+        let results = test_db
+            .query("select * from h2o_temperature")
+            .await
+            .unwrap();
+        let results_str = arrow::util::pretty::pretty_format_batches(&results).unwrap();
+        let results: Vec<_> = results_str.split('\n').collect();
+
+        let expected = vec![
+            "+----------------+--------------+-------+-----------------+------------+",
+            "| bottom_degrees | location     | state | surface_degrees | time       |",
+            "+----------------+--------------+-------+-----------------+------------+",
+            "| 50.4           | santa_monica | CA    | 65.2            | 1568756160 |",
+            "+----------------+--------------+-------+-----------------+------------+",
+            "",
+        ];
+        assert_eq!(results, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_databases() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let response = client
+            .get(&format!("{}/api/v1/databases", server_url))
+            .send()
+            .await;
+        check_response("databases", response, StatusCode::OK, "[]").await;
+
+        for name in &["MyOrg_MyBucket", "AnotherOrg_Bucket"] {
+            test_storage
+                .create_database(*name, DatabaseRules::default())
+                .await
+                .unwrap();
+        }
+
+        let response = client
+            .get(&format!("{}/api/v1/databases", server_url))
+            .send()
+            .await;
+        check_response(
+            "databases",
+            response,
+            StatusCode::OK,
+            r#"["AnotherOrg_Bucket","MyOrg_MyBucket"]"#,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshots() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
+
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body("h2o,state=CA temp=50.4 100")
+            .send()
+            .await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let list = || {
+            client
+                .get(&format!("{}/api/v1/snapshots", server_url))
+                .query(&[("org", "MyOrg"), ("bucket", "MyBucket")])
+                .send()
+        };
+        let response = list().await;
+        check_response("list snapshots", response, StatusCode::OK, "[]").await;
+
+        let response = client
+            .post(&format!("{}/api/v1/snapshot", server_url))
+            .query(&[("org", "MyOrg"), ("bucket", "MyBucket"), ("chunk", "h2o")])
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let id = response.text().await?;
+
+        let snapshots: serde_json::Value = serde_json::from_str(&list().await?.text().await?)?;
+        let snapshots = snapshots.as_array().unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0]["id"], id.as_str());
+        assert_eq!(snapshots[0]["partition_key"], "h2o");
+
+        // The snapshot runs in the background, so wait for it to complete.
+        let mut state = serde_json::Value::Null;
+        for _ in 0..100 {
+            let response = client
+                .get(&format!("{}/api/v1/snapshot/{}", server_url, id))
+                .send()
+                .await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let snapshot: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+            assert_eq!(snapshot["id"], id.as_str());
+            assert_eq!(snapshot["error"], serde_json::Value::Null);
 
-    let db_name =
-        org_and_bucket_to_database(&snapshot.org, &snapshot.bucket).context(BucketMappingError)?;
+            state = snapshot["state"].clone();
+            if state != "running" {
+                break;
+            }
+            tokio::time::delay_for(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(state, "complete");
 
-    // TODO: refactor the rest of this out of the http route and into the server
-    // crate.
-    let db = server.db(&db_name).await.context(BucketNotFound {
-        org: &snapshot.org,
-        bucket: &snapshot.bucket,
-    })?;
+        let response = client
+            .get(&format!(
+                "{}/api/v1/snapshot/{}",
+                server_url,
+                uuid::Uuid::new_v4()
+            ))
+            .send()
+            .await;
+        check_response("unknown snapshot", response, StatusCode::NOT_FOUND, "").await;
 
-    let metadata_path = format!("{}/meta", &db_name);
-    let data_path = format!("{}/data/{}", &db_name, &snapshot.chunk);
-    let partition = db.rollover_partition(&snapshot.chunk).await.unwrap();
-    let snapshot = server::snapshot::snapshot_chunk(
-        metadata_path,
-        data_path,
-        server.store.clone(),
-        partition,
-        None,
-    )
-    .unwrap();
+        let response = client
+            .get(&format!("{}/api/v1/snapshot/not-a-uuid", server_url))
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-    let ret = format!("{}", snapshot.id);
-    Ok(Response::new(Body::from(ret)))
-}
+        // Snapshotting a partition that doesn't exist is an error, rather than
+        // a panic, and doesn't create the partition.
+        let response = client
+            .post(&format!("{}/api/v1/snapshot", server_url))
+            .query(&[("org", "MyOrg"), ("bucket", "MyBucket"), ("chunk", "cpu")])
+            .send()
+            .await;
+        check_response(
+            "snapshot unknown partition",
+            response,
+            StatusCode::NOT_FOUND,
+            "",
+        )
+        .await;
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
+        assert_eq!(test_db.partition_keys().await.unwrap(), vec!["h2o"]);
 
-pub fn router_service<M: ConnectionManager + Send + Sync + Debug + 'static>(
-    server: Arc<AppServer<M>>,
-) -> RouterService<Body, ApplicationError> {
-    let router = router(server);
-    RouterService::new(router).unwrap()
-}
+        // The server is still up.
+        let response = client.get(&format!("{}/ping", server_url)).send().await;
+        check_response("ping", response, StatusCode::OK, "PONG").await;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+        Ok(())
+    }
 
-    use http::header;
-    use reqwest::{Client, Response};
+    #[tokio::test]
+    async fn test_create_database() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let server_url = test_server(test_storage.clone());
+        let client = Client::new();
 
-    use hyper::Server;
+        // Writes to databases that haven't been created are rejected.
+        let write_url = format!("{}/api/v2/write?bucket=MyBucket&org=MyOrg", server_url);
+        let response = client.post(&write_url).body("cpu bar=1 10").send().await;
+        check_response("write", response, StatusCode::NOT_FOUND, "").await;
 
-    use data_types::database_rules::DatabaseRules;
-    use data_types::DatabaseName;
-    use object_store::{InMemory, ObjectStore};
-    use server::server::ConnectionManagerImpl;
+        let rules = DatabaseRules {
+            store_locally: true,
+            ..Default::default()
+        };
+        let database_url = format!("{}/api/v1/databases/MyOrg_MyBucket", server_url);
+        let response = client
+            .put(&database_url)
+            .body(serde_json::to_string(&rules)?)
+            .send()
+            .await;
+        check_response("create", response, StatusCode::CREATED, "").await;
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
 
-    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
-    type Result<T, E = Error> = std::result::Result<T, E>;
+        let response = client.post(&write_url).body("cpu bar=1 10").send().await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        let results = test_db.query("select * from cpu").await.unwrap();
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        // The database can only be created once.
+        let response = client
+            .put(&database_url)
+            .body(serde_json::to_string(&rules)?)
+            .send()
+            .await;
+        check_response(
+            "create",
+            response,
+            StatusCode::CONFLICT,
+            r#"{"error":"Database MyOrg_MyBucket already exists"}"#,
+        )
+        .await;
+
+        let response = client
+            .put(&format!("{}/api/v1/databases/OtherOrg_Bucket", server_url))
+            .body(r#"{"store_locally": "yes"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(test_storage.db_names().await, vec!["MyOrg_MyBucket"]);
+
+        Ok(())
+    }
 
     #[tokio::test]
-    async fn test_ping() -> Result<()> {
+    async fn test_delete_database() -> Result<()> {
         let test_storage = Arc::new(AppServer::new(
             ConnectionManagerImpl {},
             Arc::new(ObjectStore::new_in_memory(InMemory::new())),
         ));
+        test_storage.set_id(1).await;
+        test_storage.set_ready();
+        for name in &["MyOrg_MyBucket", "MyOrg_OtherBucket"] {
+            let rules = DatabaseRules {
+                store_locally: true,
+                ..Default::default()
+            };
+            test_storage.create_database(*name, rules).await.unwrap();
+        }
         let server_url = test_server(test_storage.clone());
-
         let client = Client::new();
-        let response = client.get(&format!("{}/ping", server_url)).send().await;
 
-        // Print the response so if the test fails, we have a log of what went wrong
-        check_response("ping", response, StatusCode::OK, "PONG").await;
+        for bucket in &["MyBucket", "OtherBucket"] {
+            let response = client
+                .post(&format!(
+                    "{}/api/v2/write?bucket={}&org=MyOrg",
+                    server_url, bucket
+                ))
+                .body("h2o_temperature,location=santa_monica surface_degrees=65.2 1568756160")
+                .send()
+                .await;
+            check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        }
+
+        let read = |bucket: &'static str| {
+            client
+                .get(&format!("{}/api/v2/read", server_url))
+                .query(&[
+                    ("org", "MyOrg"),
+                    ("bucket", bucket),
+                    ("sql_query", "select location from h2o_temperature"),
+                ])
+                .send()
+        };
+
+        let delete_url = format!("{}/api/v1/databases/MyOrg_MyBucket", server_url);
+        let response = client.delete(&delete_url).send().await;
+        check_response("delete", response, StatusCode::NO_CONTENT, "").await;
+
+        // Reads of the deleted database fail, but the other is unaffected.
+        let response = read("MyBucket").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = read("OtherBucket").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.text().await?.contains("santa_monica"));
+
+        let response = client.delete(&delete_url).send().await;
+        check_response("delete", response, StatusCode::NOT_FOUND, "").await;
+        assert_eq!(test_storage.db_names().await, vec!["MyOrg_OtherBucket"]);
+
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_write() -> Result<()> {
+    async fn test_chunk_summaries() -> Result<()> {
         let test_storage = Arc::new(AppServer::new(
             ConnectionManagerImpl {},
             Arc::new(ObjectStore::new_in_memory(InMemory::new())),
@@ -607,6 +5620,9 @@ mod tests {
         test_storage.set_id(1).await;
         let rules = DatabaseRules {
             store_locally: true,
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
             ..Default::default()
         };
         test_storage
@@ -617,58 +5633,76 @@ mod tests {
 
         let client = Client::new();
 
-        let lp_data = "h2o_temperature,location=santa_monica,state=CA surface_degrees=65.2,bottom_degrees=50.4 1568756160";
-
-        // send write data
-        let bucket_name = "MyBucket";
-        let org_name = "MyOrg";
+        let lp_data = "h2o,state=CA temp=50.4 100\nh2o,state=MA temp=40.1 250";
         let response = client
             .post(&format!(
-                "{}/api/v2/write?bucket={}&org={}",
-                server_url, bucket_name, org_name
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
             ))
             .body(lp_data)
             .send()
             .await;
-
         check_response("write", response, StatusCode::NO_CONTENT, "").await;
 
-        // Check that the data got into the right bucket
         let test_db = test_storage
             .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
             .await
             .expect("Database exists");
+        assert_eq!(test_db.partition_keys().await.unwrap(), vec!["h2o"]);
 
-        let results = test_db
-            .query("select * from h2o_temperature")
+        let response = client
+            .get(&format!(
+                "{}/api/v1/chunks/h2o?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .send()
             .await
             .unwrap();
-        let results_str = arrow::util::pretty::pretty_format_batches(&results).unwrap();
-        let results: Vec<_> = results_str.split('\n').collect();
+        assert_eq!(response.status(), StatusCode::OK);
+        let chunks: serde_json::Value = serde_json::from_str(&response.text().await?)?;
 
-        let expected = vec![
-            "+----------------+--------------+-------+-----------------+------------+",
-            "| bottom_degrees | location     | state | surface_degrees | time       |",
-            "+----------------+--------------+-------+-----------------+------------+",
-            "| 50.4           | santa_monica | CA    | 65.2            | 1568756160 |",
-            "+----------------+--------------+-------+-----------------+------------+",
-            "",
-        ];
-        assert_eq!(results, expected);
+        let chunk = &chunks[0];
+        assert_eq!(chunk["key"], "h2o");
+        assert_eq!(chunk["row_count"], 2);
 
-        Ok(())
-    }
+        let table = &chunk["tables"][0];
+        assert_eq!(table["name"], "h2o");
+        assert_eq!(table["row_count"], 2);
+        assert_eq!(
+            table["time_range"],
+            serde_json::json!({"min": 100, "max": 250})
+        );
 
-    fn gzip_str(s: &str) -> Vec<u8> {
-        use flate2::{write::GzEncoder, Compression};
-        use std::io::Write;
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        write!(encoder, "{}", s).expect("writing into encoder");
-        encoder.finish().expect("successfully encoding gzip data")
+        let columns: Vec<_> = table["columns"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["name"].as_str().unwrap(), c["type"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![("state", "tag"), ("temp", "f64"), ("time", "i64")]
+        );
+        assert_eq!(
+            table["columns"][0]["stats"],
+            serde_json::json!({"String": {"min": "CA", "max": "MA", "count": 2}})
+        );
+
+        // unknown partitions are not found
+        let response = client
+            .get(&format!(
+                "{}/api/v1/chunks/not_a_key?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .send()
+            .await;
+        check_response("chunks", response, StatusCode::NOT_FOUND, "").await;
+
+        Ok(())
     }
 
     #[tokio::test]
-    async fn test_gzip_write() -> Result<()> {
+    async fn test_list_chunks() -> Result<()> {
         let test_storage = Arc::new(AppServer::new(
             ConnectionManagerImpl {},
             Arc::new(ObjectStore::new_in_memory(InMemory::new())),
@@ -676,6 +5710,9 @@ mod tests {
         test_storage.set_id(1).await;
         let rules = DatabaseRules {
             store_locally: true,
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
             ..Default::default()
         };
         test_storage
@@ -683,47 +5720,224 @@ mod tests {
             .await
             .unwrap();
         let server_url = test_server(test_storage.clone());
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
 
         let client = Client::new();
-        let lp_data = "h2o_temperature,location=santa_monica,state=CA surface_degrees=65.2,bottom_degrees=50.4 1568756160";
+        let write = |lp_data: &'static str| {
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                    server_url
+                ))
+                .body(lp_data)
+                .send()
+        };
+
+        let response = write("h2o,state=CA temp=50.4 100\nh2o,state=MA temp=40.1 250").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        test_db.rollover_partition("h2o").await.unwrap();
+        let response = write("h2o,state=CA temp=52.0 300").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
 
-        // send write data encoded with gzip
-        let bucket_name = "MyBucket";
-        let org_name = "MyOrg";
         let response = client
-            .post(&format!(
-                "{}/api/v2/write?bucket={}&org={}",
-                server_url, bucket_name, org_name
+            .get(&format!(
+                "{}/api/v1/chunks?bucket=MyBucket&org=MyOrg&partition=h2o",
+                server_url
+            ))
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let chunks: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        let chunks = chunks.as_array().unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        assert_eq!(chunks[0]["id"], 0);
+        assert_eq!(chunks[0]["row_count"], 2);
+        assert_eq!(
+            chunks[0]["time_range"],
+            serde_json::json!({"min": 100, "max": 250})
+        );
+        assert_eq!(chunks[1]["id"], 1);
+        assert_eq!(chunks[1]["row_count"], 1);
+        assert_eq!(
+            chunks[1]["time_range"],
+            serde_json::json!({"min": 300, "max": 300})
+        );
+        for chunk in chunks {
+            assert!(chunk["size"].as_u64().unwrap() > 0, "{}", chunk);
+        }
+
+        let response = client
+            .get(&format!(
+                "{}/api/v1/chunks?bucket=MyBucket&org=MyOrg&partition=not_a_key",
+                server_url
             ))
-            .header(header::CONTENT_ENCODING, "gzip")
-            .body(gzip_str(lp_data))
             .send()
             .await;
+        check_response("chunks", response, StatusCode::NOT_FOUND, "").await;
 
-        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        Ok(())
+    }
 
-        // Check that the data got into the right bucket
+    #[tokio::test]
+    async fn test_time_range() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
+            .await
+            .unwrap();
+        let server_url = test_server(test_storage.clone());
         let test_db = test_storage
             .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
             .await
             .expect("Database exists");
 
-        let results = test_db
-            .query("select * from h2o_temperature")
+        let client = Client::new();
+        let write = |lp_data: &'static str| {
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                    server_url
+                ))
+                .body(lp_data)
+                .send()
+        };
+        let time_range = |measurement: &'static str| {
+            client
+                .get(&format!(
+                    "{}/api/v1/time_range?bucket=MyBucket&org=MyOrg&measurement={}",
+                    server_url, measurement
+                ))
+                .send()
+        };
+
+        // The h2o rows are split across two chunks.
+        let response = write("h2o,state=CA temp=50.4 100\nh2o,state=MA temp=40.1 250").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        test_db.rollover_partition("h2o").await.unwrap();
+        let response = write("h2o,state=CA temp=52.0 300\nh2o,state=MA temp=39.5 50").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        let response = write("o2,state=CA level=1.2 1000").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = time_range("h2o").await;
+        check_response(
+            "time_range",
+            response,
+            StatusCode::OK,
+            r#"{"max":300,"min":50}"#,
+        )
+        .await;
+
+        let response = time_range("o2").await;
+        check_response(
+            "time_range",
+            response,
+            StatusCode::OK,
+            r#"{"max":1000,"min":1000}"#,
+        )
+        .await;
+
+        let response = time_range("cpu").await;
+        check_response("time_range", response, StatusCode::NOT_FOUND, "").await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_explain() -> Result<()> {
+        let test_storage = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
+        ));
+        test_storage.set_id(1).await;
+        let rules = DatabaseRules {
+            store_locally: true,
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            ..Default::default()
+        };
+        test_storage
+            .create_database("MyOrg_MyBucket", rules)
             .await
             .unwrap();
-        let results_str = arrow::util::pretty::pretty_format_batches(&results).unwrap();
-        let results: Vec<_> = results_str.split('\n').collect();
+        let server_url = test_server(test_storage.clone());
+        let test_db = test_storage
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .await
+            .expect("Database exists");
 
-        let expected = vec![
-            "+----------------+--------------+-------+-----------------+------------+",
-            "| bottom_degrees | location     | state | surface_degrees | time       |",
-            "+----------------+--------------+-------+-----------------+------------+",
-            "| 50.4           | santa_monica | CA    | 65.2            | 1568756160 |",
-            "+----------------+--------------+-------+-----------------+------------+",
-            "",
-        ];
-        assert_eq!(results, expected);
+        let client = Client::new();
+        let write = |lp_data: &'static str| {
+            client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                    server_url
+                ))
+                .body(lp_data)
+                .send()
+        };
+        let explain = |params: &'static str| {
+            client
+                .get(&format!(
+                    "{}/api/v1/explain?bucket=MyBucket&org=MyOrg&{}",
+                    server_url, params
+                ))
+                .send()
+        };
+
+        // The h2o rows are split across two chunks.
+        let response = write("h2o,state=CA temp=50.4 100\nh2o,state=MA temp=40.1 250").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        test_db.rollover_partition("h2o").await.unwrap();
+        let response = write("h2o,state=CA temp=52.0,humidity=3i 300").await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = explain("measurement=h2o").await;
+        check_response(
+            "explain",
+            response,
+            StatusCode::OK,
+            r#"{"chunks_scanned":2,"columns":["humidity","state","temp","time"],"estimated_rows":3}"#,
+        )
+        .await;
+
+        // A narrower time range scans fewer chunks.
+        let response = explain("measurement=h2o&start=0&stop=260").await;
+        check_response(
+            "explain",
+            response,
+            StatusCode::OK,
+            r#"{"chunks_scanned":1,"columns":["state","temp","time"],"estimated_rows":2}"#,
+        )
+        .await;
+
+        let response = explain("measurement=h2o&start=1000").await;
+        check_response(
+            "explain",
+            response,
+            StatusCode::OK,
+            r#"{"chunks_scanned":0,"columns":[],"estimated_rows":0}"#,
+        )
+        .await;
+
+        let response = explain("measurement=cpu").await;
+        check_response("explain", response, StatusCode::NOT_FOUND, "").await;
 
         Ok(())
     }
@@ -756,14 +5970,92 @@ mod tests {
     /// creates an instance of the http service backed by a in-memory
     /// testable database.  Returns the url of the server
     fn test_server(server: Arc<AppServer<ConnectionManagerImpl>>) -> String {
-        let make_svc = router_service(server);
+        start_test_server(server, DEFAULT_MAX_REQUEST_SIZE, None, vec![])
+    }
+
+    /// creates an instance of the http service, as `test_server` does, that
+    /// rejects request bodies larger than `max_request_size`
+    fn test_server_with_max_request_size(
+        server: Arc<AppServer<ConnectionManagerImpl>>,
+        max_request_size: usize,
+    ) -> String {
+        start_test_server(server, max_request_size, None, vec![])
+    }
+
+    /// creates an instance of the http service, as `test_server` does, that
+    /// requires requests to provide `auth_token`
+    fn test_server_with_auth_token(
+        server: Arc<AppServer<ConnectionManagerImpl>>,
+        auth_token: &str,
+    ) -> String {
+        start_test_server(
+            server,
+            DEFAULT_MAX_REQUEST_SIZE,
+            Some(auth_token.to_string()),
+            vec![],
+        )
+    }
 
+    /// creates an instance of the http service, as `test_server` does, that
+    /// allows cross-origin requests from `cors_allowed_origins`
+    fn test_server_with_cors(
+        server: Arc<AppServer<ConnectionManagerImpl>>,
+        cors_allowed_origins: &[&str],
+    ) -> String {
+        start_test_server(
+            server,
+            DEFAULT_MAX_REQUEST_SIZE,
+            None,
+            cors_allowed_origins.iter().map(|s| s.to_string()).collect(),
+        )
+    }
+
+    /// creates an instance of the http service, as `test_server` does, that
+    /// shuts down gracefully once `shutdown` completes. Also returns the
+    /// handle of the task running the server, which completes once it has.
+    fn test_server_with_shutdown(
+        server: Arc<AppServer<ConnectionManagerImpl>>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> (String, JoinHandle<Result<(), hyper::Error>>) {
+        let make_svc = router_service(
+            server,
+            DEFAULT_MAX_REQUEST_SIZE,
+            None,
+            vec![],
+            DEFAULT_QUERY_TIMEOUT,
+            Arc::new(IdsAsNames),
+        );
+        spawn_test_server(make_svc, shutdown)
+    }
+
+    fn start_test_server(
+        server: Arc<AppServer<ConnectionManagerImpl>>,
+        max_request_size: usize,
+        auth_token: Option<String>,
+        cors_allowed_origins: Vec<String>,
+    ) -> String {
+        let make_svc = router_service(
+            server,
+            max_request_size,
+            auth_token,
+            cors_allowed_origins,
+            DEFAULT_QUERY_TIMEOUT,
+            Arc::new(IdsAsNames),
+        );
+        let (server_url, _) = spawn_test_server(make_svc, futures::future::pending());
+        server_url
+    }
+
+    fn spawn_test_server(
+        make_svc: RouterService<Body, ApplicationError>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> (String, JoinHandle<Result<(), hyper::Error>>) {
         // NB: specify port 0 to let the OS pick the port.
         let bind_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
-        let server = Server::bind(&bind_addr).serve(make_svc);
-        let server_url = format!("http://{}", server.local_addr());
-        tokio::task::spawn(server);
+        let (local_addr, server) = serve(&bind_addr, make_svc, shutdown).unwrap();
+        let server_url = format!("http://{}", local_addr);
+        let handle = tokio::task::spawn(server);
         println!("Started server at {}", server_url);
-        server_url
+        (server_url, handle)
     }
 }
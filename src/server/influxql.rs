@@ -0,0 +1,865 @@
+//! This module translates a subset of InfluxQL, the query language of
+//! InfluxDB 1.x, into the SQL understood by the query engine, and formats the
+//! results in the InfluxDB 1.x JSON shape.
+//!
+//! The supported subset is a single `SELECT` statement of either fields or
+//! `mean`, `sum`, `count`, `min` and `max` aggregates of fields, `FROM` one
+//! measurement, with an optional `WHERE` clause comparing columns (including
+//! `time`) to literals, `GROUP BY` tags and `LIMIT`. Anything else is reported
+//! as unsupported rather than being silently ignored.
+
+use arrow_deps::arrow::{self, record_batch::RecordBatch};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("found {} at position {}, expected {}", found, position, expected))]
+    Unexpected {
+        found: String,
+        position: usize,
+        expected: String,
+    },
+
+    #[snafu(display("unsupported InfluxQL: {}", description))]
+    Unsupported { description: String },
+
+    #[snafu(display("invalid time '{}': {}", value, source))]
+    InvalidTime {
+        value: String,
+        source: chrono::ParseError,
+    },
+
+    #[snafu(display("invalid duration '{}'", value))]
+    InvalidDuration { value: String },
+
+    #[snafu(display("time at position {} is out of range", position))]
+    TimeOutOfRange { position: usize },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+const TIME_COLUMN: &str = "time";
+
+/// A field in the select list of a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    /// `*`, selecting every column
+    Wildcard,
+    /// A column, selected as is
+    Column { name: String },
+    /// An aggregate of a column, named `alias` in the results
+    Aggregate {
+        function: Aggregate,
+        column: String,
+        alias: String,
+    },
+}
+
+/// The aggregate functions that can be selected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Mean,
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "mean" => Some(Self::Mean),
+            "sum" => Some(Self::Sum),
+            "count" => Some(Self::Count),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Sum => "sum",
+            Self::Count => "count",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+
+    fn sql_name(&self) -> &'static str {
+        match self {
+            Self::Mean => "AVG",
+            Self::Sum => "SUM",
+            Self::Count => "COUNT",
+            Self::Min => "MIN",
+            Self::Max => "MAX",
+        }
+    }
+}
+
+/// A parsed InfluxQL `SELECT` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub fields: Vec<Field>,
+    pub measurement: String,
+    /// The `WHERE` clause, already translated into SQL
+    pub condition: Option<String>,
+    pub group_by: Vec<String>,
+    pub limit: Option<u64>,
+}
+
+/// Parses an InfluxQL statement. `now` is the time, in nanoseconds since the
+/// epoch, that `now()` refers to.
+pub fn parse(query: &str, now: i64) -> Result<Select> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        now,
+    };
+    parser.select()
+}
+
+impl Select {
+    /// Returns true if the statement selects aggregates rather than rows.
+    pub fn is_aggregate(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| matches!(field, Field::Aggregate { .. }))
+    }
+
+    /// Translates the statement into SQL. Rows are ordered by the `GROUP BY`
+    /// tags so that each series is contiguous.
+    pub fn to_sql(&self) -> String {
+        let mut columns: Vec<String> = self.group_by.clone();
+        if !self.is_aggregate() && !self.fields.contains(&Field::Wildcard) {
+            columns.push(TIME_COLUMN.to_string());
+        }
+        for field in &self.fields {
+            columns.push(match field {
+                Field::Wildcard => "*".to_string(),
+                Field::Column { name } => name.clone(),
+                Field::Aggregate {
+                    function,
+                    column,
+                    alias,
+                } => format!("{}({}) AS \"{}\"", function.sql_name(), column, alias),
+            });
+        }
+
+        let mut sql = format!("SELECT {} FROM {}", columns.join(", "), self.measurement);
+        if let Some(condition) = &self.condition {
+            sql.push_str(&format!(" WHERE {}", condition));
+        }
+
+        let mut order_by = self.group_by.clone();
+        if self.is_aggregate() {
+            if !self.group_by.is_empty() {
+                sql.push_str(&format!(" GROUP BY {}", self.group_by.join(", ")));
+            }
+        } else {
+            order_by.push(TIME_COLUMN.to_string());
+        }
+        if !order_by.is_empty() {
+            sql.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+        }
+
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        sql
+    }
+
+    /// Formats the results of the SQL query from `to_sql` in the shape of an
+    /// InfluxDB 1.x response, with a series for each combination of `GROUP BY`
+    /// tag values. Times are RFC3339 strings, or, if `epoch` is provided,
+    /// integers in units of that many nanoseconds.
+    pub fn results_json(&self, batches: &[RecordBatch], epoch: Option<i64>) -> serde_json::Value {
+        // As with InfluxDB, a statement that matches nothing has no series.
+        let schema = match batches.iter().find(|batch| batch.num_rows() > 0) {
+            Some(batch) => batch.schema(),
+            None => return serde_json::json!({ "results": [{ "statement_id": 0 }] }),
+        };
+
+        // time is always the first column of a series, and the tags it was
+        // grouped by aren't columns at all.
+        let mut columns = vec![TIME_COLUMN.to_string()];
+        columns.extend(
+            schema
+                .fields()
+                .iter()
+                .map(|field| field.name().to_string())
+                .filter(|name| name != TIME_COLUMN && !self.group_by.contains(name)),
+        );
+
+        let mut series: BTreeMap<Vec<String>, Vec<serde_json::Value>> = BTreeMap::new();
+        for row in arrow::json::writer::record_batches_to_json_rows(batches) {
+            let tags = self
+                .group_by
+                .iter()
+                .map(|tag| match row.get(tag) {
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    _ => String::new(),
+                })
+                .collect();
+
+            // Aggregates are over all time, so are reported at the epoch.
+            let time = if self.is_aggregate() {
+                0
+            } else {
+                row.get(TIME_COLUMN)
+                    .and_then(serde_json::Value::as_i64)
+                    .unwrap_or_default()
+            };
+
+            let mut values = vec![format_time(time, epoch)];
+            values.extend(
+                columns[1..]
+                    .iter()
+                    .map(|column| row.get(column).cloned().unwrap_or(serde_json::Value::Null)),
+            );
+            series.entry(tags).or_default().push(values.into());
+        }
+
+        let series: Vec<_> = series
+            .into_iter()
+            .map(|(tags, values)| {
+                let mut series = serde_json::json!({
+                    "name": self.measurement,
+                    "columns": columns,
+                    "values": values,
+                });
+                if !self.group_by.is_empty() {
+                    let tags: serde_json::Map<String, serde_json::Value> = self
+                        .group_by
+                        .iter()
+                        .cloned()
+                        .zip(tags.into_iter().map(serde_json::Value::String))
+                        .collect();
+                    series["tags"] = tags.into();
+                }
+                series
+            })
+            .collect();
+
+        serde_json::json!({ "results": [{ "statement_id": 0, "series": series }] })
+    }
+}
+
+fn format_time(time: i64, epoch: Option<i64>) -> serde_json::Value {
+    match epoch {
+        Some(epoch) => (time / epoch).into(),
+        None => Utc
+            .timestamp_nanos(time)
+            .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            .into(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare or double quoted identifier, which may be a keyword if bare
+    Ident {
+        name: String,
+        quoted: bool,
+    },
+    /// An integer or float literal
+    Number(String),
+    /// A single quoted string literal
+    Str(String),
+    /// A regular expression literal, between slashes
+    Regex(String),
+    /// A duration literal such as `1h`, in nanoseconds
+    Duration(i64),
+    /// Punctuation or an operator
+    Symbol(&'static str),
+    End,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident { name, .. } => write!(f, "'{}'", name),
+            Self::Number(n) => write!(f, "'{}'", n),
+            Self::Str(s) => write!(f, "string '{}'", s),
+            Self::Regex(r) => write!(f, "regular expression /{}/", r),
+            Self::Duration(d) => write!(f, "duration {}ns", d),
+            Self::Symbol(s) => write!(f, "'{}'", s),
+            Self::End => write!(f, "end of query"),
+        }
+    }
+}
+
+// Operators and punctuation, longest first so that e.g. `<=` isn't read as `<`.
+const SYMBOLS: &[&str] = &[
+    "!=", "<>", "<=", ">=", "=~", "!~", "=", "<", ">", ",", "(", ")", "*", "+", "-", ";",
+];
+
+fn tokenize(query: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Ident {
+                name: chars[start..i].iter().collect(),
+                quoted: false,
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+
+            let unit_start = i;
+            while i < chars.len() && (chars[i].is_alphabetic()) {
+                i += 1;
+            }
+            if unit_start == i {
+                Token::Number(number)
+            } else {
+                let unit: String = chars[unit_start..i].iter().collect();
+                let value: String = chars[start..i].iter().collect();
+                Token::Duration(parse_duration(&number, &unit).context(InvalidDuration { value })?)
+            }
+        } else if c == '\'' || c == '"' || c == '/' {
+            // Delimiters are escaped with a backslash.
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Unexpected {
+                            found: "end of query",
+                            position: i,
+                            expected: format!("closing {}", c),
+                        }
+                        .fail()
+                    }
+                    Some(&'\\') if chars.get(i + 1).is_some() => {
+                        value.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&next) if next == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&next) => {
+                        value.push(next);
+                        i += 1;
+                    }
+                }
+            }
+            if c == '\'' {
+                Token::Str(value)
+            } else if c == '/' {
+                Token::Regex(value)
+            } else {
+                Token::Ident {
+                    name: value,
+                    quoted: true,
+                }
+            }
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| Error::Unexpected {
+                    found: format!("'{}'", c),
+                    position: i,
+                    expected: "a keyword, identifier, literal or operator".to_string(),
+                })?;
+            i += symbol.chars().count();
+            Token::Symbol(*symbol)
+        };
+
+        tokens.push((token, start));
+    }
+
+    tokens.push((Token::End, chars.len()));
+    Ok(tokens)
+}
+
+// Converts a duration literal, such as `90m`, to nanoseconds.
+fn parse_duration(number: &str, unit: &str) -> Option<i64> {
+    let multiplier: i64 = match unit {
+        "ns" => 1,
+        "u" | "µ" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" => 60 * 1_000_000_000,
+        "h" => 60 * 60 * 1_000_000_000,
+        "d" => 24 * 60 * 60 * 1_000_000_000,
+        "w" => 7 * 24 * 60 * 60 * 1_000_000_000,
+        _ => return None,
+    };
+    number.parse::<i64>().ok()?.checked_mul(multiplier)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    now: i64,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].0.clone();
+        if token != Token::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        let (token, position) = &self.tokens[self.position];
+        Unexpected {
+            found: token.to_string(),
+            position: *position,
+            expected,
+        }
+        .fail()
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident { name, quoted: false } if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        if !self.peek_keyword(keyword) {
+            return self.unexpected(keyword);
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn peek_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(s) if *s == symbol)
+    }
+
+    fn symbol(&mut self, symbol: &str) -> Result<()> {
+        if !self.peek_symbol(symbol) {
+            return self.unexpected(&format!("'{}'", symbol));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn consume_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.peek_symbol(symbol);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    // Identifiers are passed through to the SQL unquoted, so are limited to
+    // those that don't need quoting.
+    fn identifier(&mut self) -> Result<String> {
+        match self.peek().clone() {
+            Token::Ident { name, .. } => {
+                ensure!(
+                    !name.is_empty()
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        && !name.starts_with(|c: char| c.is_ascii_digit()),
+                    Unsupported {
+                        description: format!("identifier '{}' must be alphanumeric", name),
+                    }
+                );
+                self.next();
+                Ok(name)
+            }
+            _ => self.unexpected("an identifier"),
+        }
+    }
+
+    fn select(&mut self) -> Result<Select> {
+        self.keyword("SELECT")?;
+        let fields = self.fields()?;
+
+        self.keyword("FROM")?;
+        let measurement = self.identifier()?;
+        if self.peek_symbol(",") {
+            return Unsupported {
+                description: "selecting from more than one measurement",
+            }
+            .fail();
+        }
+
+        let condition = if self.peek_keyword("WHERE") {
+            self.next();
+            Some(self.condition()?)
+        } else {
+            None
+        };
+
+        let mut group_by = vec![];
+        if self.peek_keyword("GROUP") {
+            self.next();
+            self.keyword("BY")?;
+            loop {
+                if self.peek_keyword("time") {
+                    return Unsupported {
+                        description: "GROUP BY time()",
+                    }
+                    .fail();
+                }
+                if self.peek_symbol("*") {
+                    return Unsupported {
+                        description: "GROUP BY *",
+                    }
+                    .fail();
+                }
+                group_by.push(self.identifier()?);
+                if !self.consume_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let limit = if self.peek_keyword("LIMIT") {
+            self.next();
+            ensure!(
+                group_by.is_empty(),
+                Unsupported {
+                    description: "LIMIT with GROUP BY",
+                }
+            );
+            match self.peek().clone() {
+                Token::Number(n) if n.parse::<u64>().is_ok() => {
+                    self.next();
+                    Some(n.parse().unwrap())
+                }
+                _ => return self.unexpected("an integer"),
+            }
+        } else {
+            None
+        };
+
+        self.consume_symbol(";");
+        if self.peek() != &Token::End {
+            if self.peek_keyword("SELECT") {
+                return Unsupported {
+                    description: "more than one statement",
+                }
+                .fail();
+            }
+            return self.unexpected("WHERE, GROUP BY, LIMIT or the end of the query");
+        }
+
+        let select = Select {
+            fields,
+            measurement,
+            condition,
+            group_by,
+            limit,
+        };
+        if select.is_aggregate() {
+            ensure!(
+                select
+                    .fields
+                    .iter()
+                    .all(|field| matches!(field, Field::Aggregate { .. })),
+                Unsupported {
+                    description: "selecting both aggregates and fields",
+                }
+            );
+        }
+        Ok(select)
+    }
+
+    fn fields(&mut self) -> Result<Vec<Field>> {
+        let mut fields = vec![];
+        loop {
+            fields.push(self.field()?);
+            if !self.consume_symbol(",") {
+                return Ok(fields);
+            }
+        }
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        if self.consume_symbol("*") {
+            return Ok(Field::Wildcard);
+        }
+
+        let name = self.identifier()?;
+        if !self.consume_symbol("(") {
+            ensure!(
+                !self.peek_keyword("AS"),
+                Unsupported {
+                    description: "aliasing fields",
+                }
+            );
+            return Ok(Field::Column { name });
+        }
+
+        let function = Aggregate::from_name(&name).ok_or_else(|| Error::Unsupported {
+            description: format!("function {}()", name),
+        })?;
+        let column = self.identifier()?;
+        self.symbol(")")?;
+
+        let alias = if self.peek_keyword("AS") {
+            self.next();
+            self.identifier()?
+        } else {
+            function.name().to_string()
+        };
+
+        Ok(Field::Aggregate {
+            function,
+            column,
+            alias,
+        })
+    }
+
+    // condition := term (( AND | OR ) term)*
+    fn condition(&mut self) -> Result<String> {
+        let mut condition = self.term()?;
+        loop {
+            let operator = if self.peek_keyword("AND") {
+                "AND"
+            } else if self.peek_keyword("OR") {
+                "OR"
+            } else {
+                return Ok(condition);
+            };
+            self.next();
+            condition = format!("{} {} {}", condition, operator, self.term()?);
+        }
+    }
+
+    // term := '(' condition ')' | identifier operator value
+    fn term(&mut self) -> Result<String> {
+        if self.consume_symbol("(") {
+            let condition = self.condition()?;
+            self.symbol(")")?;
+            return Ok(format!("({})", condition));
+        }
+
+        let column = self.identifier()?;
+        let operator = match self.peek().clone() {
+            Token::Symbol(op @ "=")
+            | Token::Symbol(op @ "!=")
+            | Token::Symbol(op @ "<>")
+            | Token::Symbol(op @ "<")
+            | Token::Symbol(op @ "<=")
+            | Token::Symbol(op @ ">")
+            | Token::Symbol(op @ ">=") => op,
+            Token::Symbol("=~") | Token::Symbol("!~") => {
+                return Unsupported {
+                    description: "regular expressions",
+                }
+                .fail()
+            }
+            _ => return self.unexpected("a comparison operator"),
+        };
+        self.next();
+
+        let value = if column == TIME_COLUMN {
+            self.time()?.to_string()
+        } else {
+            let negative = self.consume_symbol("-");
+            let value = match self.peek().clone() {
+                Token::Number(n) if negative => format!("-{}", n),
+                Token::Number(n) => n,
+                Token::Str(s) if !negative => format!("'{}'", s.replace('\'', "''")),
+                Token::Ident {
+                    name,
+                    quoted: false,
+                } if !negative
+                    && (name.eq_ignore_ascii_case("true")
+                        || name.eq_ignore_ascii_case("false")) =>
+                {
+                    name.to_ascii_lowercase()
+                }
+                _ => return self.unexpected("a number, string or boolean"),
+            };
+            self.next();
+            value
+        };
+
+        Ok(format!("{} {} {}", column, operator, value))
+    }
+
+    // A time in nanoseconds since the epoch: an integer, an RFC3339 string or
+    // now(), each optionally plus or minus a duration.
+    fn time(&mut self) -> Result<i64> {
+        let mut time = match self.peek().clone() {
+            Token::Number(n) if n.parse::<i64>().is_ok() => {
+                self.next();
+                n.parse().unwrap()
+            }
+            Token::Str(s) => {
+                self.next();
+                DateTime::parse_from_rfc3339(&s)
+                    .context(InvalidTime { value: &s })?
+                    .timestamp_nanos()
+            }
+            Token::Ident {
+                name,
+                quoted: false,
+            } if name.eq_ignore_ascii_case("now") => {
+                self.next();
+                self.symbol("(")?;
+                self.symbol(")")?;
+                self.now
+            }
+            _ => return self.unexpected("a time"),
+        };
+
+        loop {
+            let add = if self.consume_symbol("+") {
+                true
+            } else if self.consume_symbol("-") {
+                false
+            } else {
+                return Ok(time);
+            };
+            let duration = match self.peek() {
+                Token::Duration(duration) => *duration,
+                _ => return self.unexpected("a duration"),
+            };
+            let shifted = if add {
+                time.checked_add(duration)
+            } else {
+                time.checked_sub(duration)
+            };
+            time = shifted.context(TimeOutOfRange {
+                position: self.tokens[self.position].1,
+            })?;
+            self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_600_000_000_000_000_000;
+
+    #[test]
+    fn select_fields() {
+        let select = parse(
+            "SELECT usage, \"host\" FROM cpu WHERE host = 'a' AND time > now() - 1h LIMIT 10",
+            NOW,
+        )
+        .unwrap();
+        assert!(!select.is_aggregate());
+        assert_eq!(
+            select.to_sql(),
+            format!(
+                "SELECT time, usage, host FROM cpu WHERE host = 'a' AND time > {} \
+                 ORDER BY time LIMIT 10",
+                NOW - 3_600_000_000_000
+            )
+        );
+
+        let select = parse(
+            "select * from cpu where time >= '2020-09-13T12:26:40Z'",
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(
+            select.to_sql(),
+            format!("SELECT * FROM cpu WHERE time >= {} ORDER BY time", NOW)
+        );
+    }
+
+    #[test]
+    fn select_aggregates() {
+        let select = parse(
+            "SELECT mean(usage), max(usage) AS peak FROM cpu WHERE (region = 'west' OR region = 'east') GROUP BY host, region",
+            NOW,
+        )
+        .unwrap();
+        assert!(select.is_aggregate());
+        assert_eq!(
+            select.to_sql(),
+            "SELECT host, region, AVG(usage) AS \"mean\", MAX(usage) AS \"peak\" FROM cpu \
+             WHERE (region = 'west' OR region = 'east') GROUP BY host, region ORDER BY host, region"
+        );
+    }
+
+    #[test]
+    fn time_out_of_range() {
+        let cases = &[
+            ("SELECT usage FROM cpu WHERE time > now() + 2500000h", 43),
+            (
+                "SELECT usage FROM cpu WHERE time > now() - 2500000h - 2500000h",
+                54,
+            ),
+        ];
+        for (query, position) in cases {
+            let err = parse(query, NOW).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("time at position {} is out of range", position),
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported() {
+        let cases = &[
+            (
+                "SELECT mean(usage) FROM cpu GROUP BY time(1m)",
+                "GROUP BY time()",
+            ),
+            (
+                "SELECT usage FROM cpu WHERE host =~ /a.*/",
+                "regular expressions",
+            ),
+            (
+                "SELECT mean(usage), usage FROM cpu",
+                "selecting both aggregates and fields",
+            ),
+            ("SELECT derivative(usage) FROM cpu", "function derivative()"),
+            (
+                "SELECT usage FROM cpu, mem",
+                "selecting from more than one measurement",
+            ),
+            (
+                "SELECT usage FROM cpu; SELECT usage FROM mem",
+                "more than one statement",
+            ),
+        ];
+        for (query, description) in cases {
+            let err = parse(query, NOW).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("unsupported InfluxQL: {}", description),
+                "{}",
+                query
+            );
+        }
+
+        let err = parse("SELECT usage cpu", NOW).unwrap_err();
+        assert_eq!(err.to_string(), "found 'cpu' at position 13, expected FROM");
+    }
+}
@@ -0,0 +1,113 @@
+//! Prometheus metrics describing the requests handled by the HTTP API, which
+//! are exposed by the `/metrics` route.
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, Result, TextEncoder,
+};
+
+use std::fmt;
+
+pub struct Metrics {
+    registry: Registry,
+
+    /// The number of requests to /api/v2/write
+    pub write_requests: IntCounter,
+
+    /// The number of requests to /api/v2/read
+    pub read_requests: IntCounter,
+
+    /// The number of (decompressed) line protocol bytes received by
+    /// /api/v2/write
+    pub bytes_written: IntCounter,
+
+    /// How long queries made with /api/v2/read take to run, including
+    /// formatting their results
+    pub query_duration: Histogram,
+
+    // Set from the server when the metrics are scraped.
+    databases: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let write_requests = IntCounter::new(
+            "iox_http_write_requests_total",
+            "Number of requests to /api/v2/write",
+        )
+        .expect("valid metric");
+        let read_requests = IntCounter::new(
+            "iox_http_read_requests_total",
+            "Number of requests to /api/v2/read",
+        )
+        .expect("valid metric");
+        let bytes_written = IntCounter::new(
+            "iox_http_write_bytes_total",
+            "Number of line protocol bytes written to /api/v2/write",
+        )
+        .expect("valid metric");
+        let query_duration = Histogram::with_opts(HistogramOpts::new(
+            "iox_query_duration_seconds",
+            "Time taken to run queries from /api/v2/read",
+        ))
+        .expect("valid metric");
+        let databases =
+            IntGauge::new("iox_databases", "Number of databases").expect("valid metric");
+
+        let registry = Registry::new();
+        registry
+            .register(Box::new(write_requests.clone()))
+            .expect("metric registered once");
+        registry
+            .register(Box::new(read_requests.clone()))
+            .expect("metric registered once");
+        registry
+            .register(Box::new(bytes_written.clone()))
+            .expect("metric registered once");
+        registry
+            .register(Box::new(query_duration.clone()))
+            .expect("metric registered once");
+        registry
+            .register(Box::new(databases.clone()))
+            .expect("metric registered once");
+
+        Self {
+            registry,
+            write_requests,
+            read_requests,
+            bytes_written,
+            query_duration,
+            databases,
+        }
+    }
+
+    /// The content type of the output of `render`
+    pub fn content_type(&self) -> String {
+        TextEncoder::new().format_type().to_string()
+    }
+
+    /// Renders the current value of each metric in the Prometheus text
+    /// format, given the number of databases the server has.
+    pub fn render(&self, databases: usize) -> Result<Vec<u8>> {
+        self.databases.set(databases as i64);
+
+        let mut output = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut output)?;
+        Ok(output)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("write_requests", &self.write_requests.get())
+            .field("read_requests", &self.read_requests.get())
+            .field("bytes_written", &self.bytes_written.get())
+            .finish()
+    }
+}
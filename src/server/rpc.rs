@@ -10,5 +10,6 @@ pub(crate) const TAG_KEY_FIELD: &[u8] = &[255];
 
 pub mod data;
 pub mod expr;
+pub mod flight;
 pub mod input;
 pub mod service;
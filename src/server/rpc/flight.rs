@@ -0,0 +1,1053 @@
+//! This module contains an implementation of the Arrow Flight gRPC service,
+//! answering the Flight SQL commands below in terms of the
+//! `query::DatabaseStore`:
+//!
+//! * `CommandStatementQuery` runs a SQL query, through the same query path as
+//!   the HTTP API, on the database named by the `database` header of the
+//!   request
+//! * `CommandGetTables` lists the tables of each database. Each database is a
+//!   catalog with a single schema, `iox`.
+//! * `CommandGetSqlInfo` describes the server
+//!
+//! A client calls `GetFlightInfo` with a command to get its schema and a
+//! ticket, then `DoGet` with the ticket to get the results.
+//!
+//! Unlike the Flight SQL specification, where the values of
+//! `CommandGetSqlInfo` are a dense union, they are all strings here, as the
+//! version of Arrow in use can't write unions in IPC streams.
+
+use std::{
+    collections::BTreeSet,
+    convert::{TryFrom, TryInto},
+    sync::Arc,
+};
+
+use arrow_deps::{
+    arrow::{
+        array::{ArrayRef, BinaryArray, StringArray, UInt32Array},
+        datatypes::{DataType, Field, Schema, SchemaRef},
+        error::ArrowError,
+        ipc::{self, writer::StreamWriter},
+        record_batch::RecordBatch,
+    },
+    datafusion::physical_plan::{
+        common::SizedRecordBatchStream, RecordBatchStream, SendableRecordBatchStream,
+    },
+};
+use futures::StreamExt;
+use generated_types::arrow_flight::{
+    self,
+    flight_service_server::{FlightService, FlightServiceServer},
+    sql::{CommandGetSqlInfo, CommandGetTables, CommandStatementQuery, TicketStatementQuery},
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
+};
+use prost::Message;
+use query::{DatabaseStore, SQLDatabase};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{error, info};
+
+/// The prefix of the type URLs of the Flight SQL commands, which are packed
+/// in `google.protobuf.Any` messages
+const TYPE_URL_PREFIX: &str = "type.googleapis.com/arrow.flight.protocol.sql.";
+
+/// The request header naming the database of a statement query
+const DATABASE_HEADER: &str = "database";
+
+/// The single schema of each catalog, i.e. database
+const DB_SCHEMA_NAME: &str = "iox";
+
+/// The type of every table
+const TABLE_TYPE: &str = "TABLE";
+
+/// Marks the start of each message of an Arrow IPC stream
+const CONTINUATION_MARKER: [u8; 4] = [0xff; 4];
+
+/// The `CommandGetSqlInfo` ids answered by the server
+const SQL_INFO_SERVER_NAME: u32 = 0;
+const SQL_INFO_SERVER_VERSION: u32 = 1;
+const SQL_INFO_SERVER_READ_ONLY: u32 = 3;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error decoding Flight SQL command: {}", source))]
+    DecodingCommand { source: prost::DecodeError },
+
+    #[snafu(display("Unsupported Flight SQL command: {}", type_url))]
+    UnsupportedCommand { type_url: String },
+
+    #[snafu(display("Only command descriptors are supported"))]
+    UnsupportedDescriptor,
+
+    #[snafu(display("Invalid statement handle: {}", source))]
+    InvalidStatementHandle { source: serde_json::Error },
+
+    #[snafu(display("Statement queries require a '{}' header", DATABASE_HEADER))]
+    MissingDatabase,
+
+    #[snafu(display("Invalid '{}' header: {}", DATABASE_HEADER, source))]
+    InvalidDatabaseHeader {
+        source: tonic::metadata::errors::ToStrError,
+    },
+
+    #[snafu(display("Database not found: {}", db_name))]
+    DatabaseNotFound { db_name: String },
+
+    #[snafu(display(
+        "Error running query '{}' in database '{}': {}",
+        query,
+        db_name,
+        source
+    ))]
+    Query {
+        db_name: String,
+        query: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Error listing tables in database '{}': {}", db_name, source))]
+    ListingTables {
+        db_name: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Error reading results: {}", source))]
+    ReadingResults { source: ArrowError },
+
+    #[snafu(display("Error encoding results as Arrow IPC: {}", source))]
+    EncodingResults { source: ArrowError },
+
+    #[snafu(display("Invalid Arrow IPC stream"))]
+    InvalidIpcStream,
+
+    #[snafu(display("Operation not yet implemented:  {}", operation))]
+    NotYetImplemented { operation: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl From<Error> for tonic::Status {
+    /// Converts a result from the business logic into the appropriate tonic
+    /// status
+    fn from(err: Error) -> Self {
+        error!("Error handling Flight request: {}", err);
+        err.to_status()
+    }
+}
+
+impl Error {
+    /// Converts a result from the business logic into the appropriate tonic
+    /// status
+    fn to_status(&self) -> tonic::Status {
+        match &self {
+            Self::DecodingCommand { .. } => Status::invalid_argument(self.to_string()),
+            Self::UnsupportedCommand { .. } => Status::unimplemented(self.to_string()),
+            Self::UnsupportedDescriptor => Status::unimplemented(self.to_string()),
+            Self::InvalidStatementHandle { .. } => Status::invalid_argument(self.to_string()),
+            Self::MissingDatabase => Status::invalid_argument(self.to_string()),
+            Self::InvalidDatabaseHeader { .. } => Status::invalid_argument(self.to_string()),
+            Self::DatabaseNotFound { .. } => Status::not_found(self.to_string()),
+            Self::Query { .. } => {
+                // TODO: distinguish between input errors and internal errors
+                Status::invalid_argument(self.to_string())
+            }
+            Self::ListingTables { .. } => Status::internal(self.to_string()),
+            Self::ReadingResults { .. } => Status::internal(self.to_string()),
+            Self::EncodingResults { .. } => Status::internal(self.to_string()),
+            Self::InvalidIpcStream => Status::internal(self.to_string()),
+            Self::NotYetImplemented { .. } => Status::unimplemented(self.to_string()),
+        }
+    }
+}
+
+/// Identifies the results of a statement query, as the handle of its ticket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StatementHandle {
+    database: String,
+    query: String,
+}
+
+/// The Flight SQL commands answered by the server
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    StatementQuery(StatementHandle),
+    GetTables(CommandGetTables),
+    GetSqlInfo(CommandGetSqlInfo),
+}
+
+impl Command {
+    /// Decodes the command of a descriptor or ticket. `database` is the value
+    /// of the `database` header, used by statement queries.
+    fn try_decode(bytes: &[u8], database: Option<&str>) -> Result<Self> {
+        let any = prost_types::Any::decode(bytes).context(DecodingCommand)?;
+        let value = &any.value[..];
+
+        match any.type_url.strip_prefix(TYPE_URL_PREFIX) {
+            Some("CommandStatementQuery") => {
+                let command = CommandStatementQuery::decode(value).context(DecodingCommand)?;
+                let database = database.context(MissingDatabase)?;
+                Ok(Self::StatementQuery(StatementHandle {
+                    database: database.to_string(),
+                    query: command.query,
+                }))
+            }
+            Some("TicketStatementQuery") => {
+                let ticket = TicketStatementQuery::decode(value).context(DecodingCommand)?;
+                let handle = serde_json::from_slice(&ticket.statement_handle)
+                    .context(InvalidStatementHandle)?;
+                Ok(Self::StatementQuery(handle))
+            }
+            Some("CommandGetTables") => Ok(Self::GetTables(
+                CommandGetTables::decode(value).context(DecodingCommand)?,
+            )),
+            Some("CommandGetSqlInfo") => Ok(Self::GetSqlInfo(
+                CommandGetSqlInfo::decode(value).context(DecodingCommand)?,
+            )),
+            _ => UnsupportedCommand {
+                type_url: any.type_url.clone(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Encodes the ticket for the results of this command. The ticket of a
+    /// statement query holds its database, so that it can be redeemed
+    /// without the header.
+    fn to_ticket(&self) -> Ticket {
+        let (name, value) = match self {
+            Self::StatementQuery(handle) => {
+                let statement_handle =
+                    serde_json::to_vec(handle).expect("serializing statement handle");
+                let ticket = TicketStatementQuery { statement_handle };
+                ("TicketStatementQuery", encode_message(&ticket))
+            }
+            Self::GetTables(command) => ("CommandGetTables", encode_message(command)),
+            Self::GetSqlInfo(command) => ("CommandGetSqlInfo", encode_message(command)),
+        };
+
+        let any = prost_types::Any {
+            type_url: format!("{}{}", TYPE_URL_PREFIX, name),
+            value,
+        };
+        Ticket {
+            ticket: encode_message(&any),
+        }
+    }
+}
+
+fn encode_message(message: &impl Message) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(message.encoded_len());
+    message
+        .encode(&mut bytes)
+        .expect("encoding to a Vec can't fail");
+    bytes
+}
+
+#[derive(Debug)]
+pub struct FlightSqlService<T: DatabaseStore> {
+    db_store: Arc<T>,
+}
+
+impl<T> FlightSqlService<T>
+where
+    T: DatabaseStore + 'static,
+{
+    /// Create a new FlightSqlService connected to `db_store`
+    pub fn new(db_store: Arc<T>) -> Self {
+        Self { db_store }
+    }
+
+    /// Returns a `FlightServiceServer` answering requests with this service
+    pub fn into_server(self) -> FlightServiceServer<Self> {
+        FlightServiceServer::new(self)
+    }
+
+    /// Returns the stream of the results of `command`
+    async fn execute(&self, command: &Command) -> Result<SendableRecordBatchStream> {
+        let batch = match command {
+            Command::StatementQuery(StatementHandle { database, query }) => {
+                let db = self.db_store.db(database).await.context(DatabaseNotFound {
+                    db_name: database.as_str(),
+                })?;
+
+                info!(
+                    "Flight SQL statement query in database {}: {}",
+                    database, query
+                );
+
+                return db
+                    .query_stream(query)
+                    .await
+                    .map_err(|e| Box::new(e) as _)
+                    .context(Query {
+                        db_name: database.as_str(),
+                        query: query.as_str(),
+                    });
+            }
+            Command::GetTables(command) => self.get_tables(command).await?,
+            Command::GetSqlInfo(command) => get_sql_info(command)?,
+        };
+
+        Ok(Box::pin(SizedRecordBatchStream::new(
+            batch.schema(),
+            vec![Arc::new(batch)],
+        )))
+    }
+
+    /// Returns the schema of the results of `command`, without running it
+    async fn schema(&self, command: &Command) -> Result<SchemaRef> {
+        match command {
+            Command::StatementQuery(StatementHandle { database, query }) => {
+                let db = self.db_store.db(database).await.context(DatabaseNotFound {
+                    db_name: database.as_str(),
+                })?;
+
+                db.query_schema(query)
+                    .await
+                    .map_err(|e| Box::new(e) as _)
+                    .context(Query {
+                        db_name: database.as_str(),
+                        query: query.as_str(),
+                    })
+            }
+            Command::GetTables(command) => Ok(get_tables_schema(command.include_schema)),
+            Command::GetSqlInfo(_) => Ok(get_sql_info_schema()),
+        }
+    }
+
+    /// Lists the tables matching the filters of `command`, ordered by
+    /// catalog and table name
+    async fn get_tables(&self, command: &CommandGetTables) -> Result<RecordBatch> {
+        let db_names = if command.catalog.is_empty() {
+            self.db_store.db_names_sorted().await
+        } else {
+            vec![command.catalog.clone()]
+        };
+
+        let mut tables = vec![];
+        if matches_pattern(&command.db_schema_filter_pattern, DB_SCHEMA_NAME)
+            && (command.table_types.is_empty()
+                || command.table_types.iter().any(|t| t == TABLE_TYPE))
+        {
+            for db_name in db_names {
+                let db = match self.db_store.db(&db_name).await {
+                    Some(db) => db,
+                    None => continue,
+                };
+
+                for table_name in table_names(db.as_ref(), &db_name).await? {
+                    if !matches_pattern(&command.table_name_filter_pattern, &table_name) {
+                        continue;
+                    }
+
+                    let table_schema = if command.include_schema {
+                        let schema = db
+                            .table_schema(&table_name)
+                            .await
+                            .map_err(|e| Box::new(e) as _)
+                            .context(ListingTables { db_name: &db_name })?;
+                        match schema {
+                            Some(schema) => encode_schema(&schema)?,
+                            None => encode_schema(&Schema::empty())?,
+                        }
+                    } else {
+                        vec![]
+                    };
+
+                    tables.push((db_name.clone(), table_name, table_schema));
+                }
+            }
+        }
+
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(
+                tables
+                    .iter()
+                    .map(|(db, _, _)| db.as_str())
+                    .collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from(vec![DB_SCHEMA_NAME; tables.len()])),
+            Arc::new(StringArray::from(
+                tables
+                    .iter()
+                    .map(|(_, table, _)| table.as_str())
+                    .collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from(vec![TABLE_TYPE; tables.len()])),
+        ];
+        if command.include_schema {
+            columns.push(Arc::new(BinaryArray::from(
+                tables
+                    .iter()
+                    .map(|(_, _, schema)| schema.as_slice())
+                    .collect::<Vec<_>>(),
+            )));
+        }
+
+        RecordBatch::try_new(get_tables_schema(command.include_schema), columns)
+            .context(EncodingResults)
+    }
+}
+
+/// The schema of the results of `CommandGetTables`, which has a
+/// `table_schema` column if `include_schema` is set
+fn get_tables_schema(include_schema: bool) -> SchemaRef {
+    let mut fields = vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, true),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("table_type", DataType::Utf8, false),
+    ];
+    if include_schema {
+        fields.push(Field::new("table_schema", DataType::Binary, false));
+    }
+    Arc::new(Schema::new(fields))
+}
+
+/// Returns the names of the tables of `db`, in sorted order
+async fn table_names<D: SQLDatabase>(db: &D, db_name: &str) -> Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+
+    let partition_keys = db
+        .partition_keys()
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(ListingTables { db_name })?;
+    for partition_key in partition_keys {
+        let partition_names = db
+            .table_names_for_partition(&partition_key)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(ListingTables { db_name })?;
+        names.extend(partition_names);
+    }
+
+    Ok(names)
+}
+
+/// Describes the server with the info ids requested by `command`, or with all
+/// of them if none are requested
+fn get_sql_info(command: &CommandGetSqlInfo) -> Result<RecordBatch> {
+    let info = vec![
+        (SQL_INFO_SERVER_NAME, "InfluxDB IOx"),
+        (SQL_INFO_SERVER_VERSION, env!("CARGO_PKG_VERSION")),
+        (SQL_INFO_SERVER_READ_ONLY, "true"),
+    ];
+    let info: Vec<_> = info
+        .into_iter()
+        .filter(|(id, _)| command.info.is_empty() || command.info.contains(id))
+        .collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt32Array::from(
+            info.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            info.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+        )),
+    ];
+
+    RecordBatch::try_new(get_sql_info_schema(), columns).context(EncodingResults)
+}
+
+/// The schema of the results of `CommandGetSqlInfo`
+fn get_sql_info_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("info_name", DataType::UInt32, false),
+        Field::new("value", DataType::Utf8, false),
+    ]))
+}
+
+/// Whether `value` matches the SQL LIKE `pattern`, where `%` matches any
+/// sequence of characters and `_` any one. An empty pattern matches every
+/// value, as it means that no filter was requested.
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    if pattern.is_empty() {
+        return true;
+    }
+
+    // `matched[i]` is whether the pattern characters consumed so far match
+    // the first `i` characters of the value, so each pattern character is
+    // handled with a single pass over the value.
+    let value: Vec<_> = value.chars().collect();
+    let mut matched = vec![false; value.len() + 1];
+    matched[0] = true;
+    for p in pattern.chars() {
+        let mut next = vec![false; value.len() + 1];
+        if p == '%' {
+            let mut any = false;
+            for (i, m) in matched.iter().enumerate() {
+                any |= m;
+                next[i] = any;
+            }
+        } else {
+            for (i, c) in value.iter().enumerate() {
+                next[i + 1] = matched[i] && (p == '_' || p == *c);
+            }
+        }
+        matched = next;
+    }
+    matched[value.len()]
+}
+
+/// A message of an Arrow IPC stream: the flatbuffer `Message`, and its body
+#[derive(Debug)]
+struct IpcMessage {
+    header: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl From<IpcMessage> for FlightData {
+    fn from(message: IpcMessage) -> Self {
+        Self {
+            flight_descriptor: None,
+            data_header: message.header,
+            app_metadata: vec![],
+            data_body: message.body,
+        }
+    }
+}
+
+/// Encodes `batches` as an Arrow IPC stream, and returns its messages: the
+/// schema, then those of the batches
+fn encode_messages(schema: &Schema, batches: &[RecordBatch]) -> Result<Vec<IpcMessage>> {
+    let mut stream = vec![];
+    {
+        let mut writer = StreamWriter::try_new(&mut stream, schema).context(EncodingResults)?;
+        for batch in batches {
+            writer.write(batch).context(EncodingResults)?;
+        }
+        writer.finish().context(EncodingResults)?;
+    }
+
+    split_messages(&stream)
+}
+
+/// Splits an Arrow IPC stream into its messages. Each message is the length
+/// of its header, preceded by a continuation marker in newer versions of the
+/// format, then the header and the body. A zero length ends the stream.
+fn split_messages(mut stream: &[u8]) -> Result<Vec<IpcMessage>> {
+    let mut messages = vec![];
+
+    loop {
+        if stream.starts_with(&CONTINUATION_MARKER) {
+            stream = &stream[CONTINUATION_MARKER.len()..];
+        }
+
+        ensure!(stream.len() >= 4, InvalidIpcStream);
+        let (header_len, rest) = stream.split_at(4);
+        let header_len = i32::from_le_bytes(header_len.try_into().unwrap());
+        if header_len == 0 {
+            return Ok(messages);
+        }
+
+        let header_len = usize::try_from(header_len)
+            .ok()
+            .filter(|&len| len <= rest.len())
+            .context(InvalidIpcStream)?;
+        let (header, rest) = rest.split_at(header_len);
+
+        let body_len = ipc::get_root_as_message(header).bodyLength();
+        let body_len = usize::try_from(body_len)
+            .ok()
+            .filter(|&len| len <= rest.len())
+            .context(InvalidIpcStream)?;
+        let (body, rest) = rest.split_at(body_len);
+
+        messages.push(IpcMessage {
+            header: header.to_vec(),
+            body: body.to_vec(),
+        });
+        stream = rest;
+    }
+}
+
+/// Encodes `schema` as an encapsulated IPC message, as in `FlightInfo`,
+/// `SchemaResult` and the `table_schema` column of `CommandGetTables`
+fn encode_schema(schema: &Schema) -> Result<Vec<u8>> {
+    let messages = encode_messages(schema, &[])?;
+    let header = &messages.first().context(InvalidIpcStream)?.header;
+
+    let mut bytes = CONTINUATION_MARKER.to_vec();
+    bytes.extend_from_slice(&(header.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(header);
+    Ok(bytes)
+}
+
+/// Sends the results of `stream` to `tx` as `FlightData`: the schema, then
+/// each batch as it is produced
+async fn send_results(
+    tx: &mut mpsc::Sender<Result<FlightData, Status>>,
+    mut stream: SendableRecordBatchStream,
+) -> Result<()> {
+    let schema = stream.schema();
+
+    // Each batch is encoded in a stream of its own, whose first message
+    // repeats the schema
+    let mut skip = 0;
+    let mut batches = vec![];
+    loop {
+        for message in encode_messages(&schema, &batches)?.into_iter().skip(skip) {
+            if tx.send(Ok(message.into())).await.is_err() {
+                // The client has gone away
+                return Ok(());
+            }
+        }
+        skip = 1;
+
+        batches = match stream.next().await {
+            Some(batch) => vec![batch.context(ReadingResults)?],
+            None => return Ok(()),
+        };
+    }
+}
+
+/// Returns the value of the `database` header of `req`, if there is one
+fn database_header<R>(req: &Request<R>) -> Result<Option<&str>> {
+    req.metadata()
+        .get(DATABASE_HEADER)
+        .map(|value| value.to_str().context(InvalidDatabaseHeader))
+        .transpose()
+}
+
+/// Decodes the command of `descriptor`
+fn descriptor_command(descriptor: &FlightDescriptor, database: Option<&str>) -> Result<Command> {
+    ensure!(
+        descriptor.r#type == arrow_flight::flight_descriptor::DescriptorType::Cmd as i32,
+        UnsupportedDescriptor
+    );
+    Command::try_decode(&descriptor.cmd, database)
+}
+
+/// Implements the protobuf defined Flight service for a DatabaseStore
+#[tonic::async_trait]
+impl<T> FlightService for FlightSqlService<T>
+where
+    T: DatabaseStore + 'static,
+{
+    type HandshakeStream = mpsc::Receiver<Result<HandshakeResponse, Status>>;
+
+    async fn handshake(
+        &self,
+        _req: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("handshake"))
+    }
+
+    type ListFlightsStream = mpsc::Receiver<Result<FlightInfo, Status>>;
+
+    async fn list_flights(
+        &self,
+        _req: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("list_flights"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        req: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let command = descriptor_command(req.get_ref(), database_header(&req)?)?;
+        let schema = self.schema(&command).await?;
+
+        Ok(Response::new(FlightInfo {
+            schema: encode_schema(&schema)?,
+            flight_descriptor: Some(req.into_inner()),
+            endpoint: vec![FlightEndpoint {
+                ticket: Some(command.to_ticket()),
+                location: vec![],
+            }],
+            total_records: -1,
+            total_bytes: -1,
+        }))
+    }
+
+    async fn get_schema(
+        &self,
+        req: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        let command = descriptor_command(req.get_ref(), database_header(&req)?)?;
+        let schema = self.schema(&command).await?;
+
+        Ok(Response::new(SchemaResult {
+            schema: encode_schema(&schema)?,
+        }))
+    }
+
+    type DoGetStream = mpsc::Receiver<Result<FlightData, Status>>;
+
+    async fn do_get(&self, req: Request<Ticket>) -> Result<Response<Self::DoGetStream>, Status> {
+        let command = Command::try_decode(&req.get_ref().ticket, database_header(&req)?)?;
+        let stream = self.execute(&command).await?;
+
+        let (mut tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            if let Err(e) = send_results(&mut tx, stream).await {
+                error!("Error sending Flight results: {}", e);
+                // The client may have gone away, in which case there is
+                // nobody to tell
+                tx.send(Err(e.to_status())).await.ok();
+            }
+        });
+
+        Ok(Response::new(rx))
+    }
+
+    type DoPutStream = mpsc::Receiver<Result<PutResult, Status>>;
+
+    async fn do_put(
+        &self,
+        _req: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("do_put"))
+    }
+
+    type DoExchangeStream = mpsc::Receiver<Result<FlightData, Status>>;
+
+    async fn do_exchange(
+        &self,
+        _req: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("do_exchange"))
+    }
+
+    type DoActionStream = mpsc::Receiver<Result<arrow_flight::Result, Status>>;
+
+    async fn do_action(
+        &self,
+        req: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Error::NotYetImplemented {
+            operation: format!("do_action {}", req.get_ref().r#type),
+        }
+        .into())
+    }
+
+    type ListActionsStream = mpsc::Receiver<Result<ActionType, Status>>;
+
+    async fn list_actions(
+        &self,
+        _req: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Err(Status::unimplemented("list_actions"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::arrow::{self, ipc::reader::StreamReader};
+    use influxdb_line_protocol::parse_lines;
+    use mutable_buffer::MutableBufferDatabases;
+    use query::TSDatabase;
+    use std::io::Cursor;
+
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+    type Result<T = (), E = Error> = std::result::Result<T, E>;
+
+    async fn make_service() -> FlightSqlService<MutableBufferDatabases> {
+        let store = Arc::new(MutableBufferDatabases::default());
+        for (name, lp) in &[
+            (
+                "mydb",
+                "cpu,host=a usage=1.5 10\ncpu,host=b usage=2.5 20\ndisk,host=a free=3i 10",
+            ),
+            ("otherdb", "mem,host=a used=4i 10"),
+        ] {
+            let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+            let db = store.db_or_create(name).await.unwrap();
+            db.write_lines(&lines).await.unwrap();
+        }
+        FlightSqlService::new(store)
+    }
+
+    fn command_descriptor(name: &str, command: &impl Message) -> FlightDescriptor {
+        let any = prost_types::Any {
+            type_url: format!("{}{}", TYPE_URL_PREFIX, name),
+            value: encode_message(command),
+        };
+        FlightDescriptor {
+            r#type: arrow_flight::flight_descriptor::DescriptorType::Cmd as i32,
+            cmd: encode_message(&any),
+            path: vec![],
+        }
+    }
+
+    fn with_database<R>(message: R, database: &str) -> Request<R> {
+        let mut req = Request::new(message);
+        req.metadata_mut()
+            .insert(DATABASE_HEADER, database.parse().unwrap());
+        req
+    }
+
+    /// Runs `descriptor` through GetFlightInfo then DoGet, and returns the
+    /// results formatted as a table
+    async fn run(
+        service: &FlightSqlService<MutableBufferDatabases>,
+        descriptor: FlightDescriptor,
+        database: &str,
+    ) -> Result<String> {
+        let info = service
+            .get_flight_info(with_database(descriptor, database))
+            .await?
+            .into_inner();
+        let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+        // The ticket is redeemed without the header
+        let mut stream = service.do_get(Request::new(ticket)).await?.into_inner();
+        let mut data = vec![];
+        while let Some(message) = stream.recv().await {
+            data.push(message?);
+        }
+
+        let batches = decode_flight_data(&data)?;
+        assert_eq!(
+            info.schema,
+            encode_schema(&batches[0].schema())?,
+            "FlightInfo schema"
+        );
+        Ok(arrow::util::pretty::pretty_format_batches(&batches)?)
+    }
+
+    /// Reassembles the IPC stream of `data`, and reads its batches
+    fn decode_flight_data(data: &[FlightData]) -> Result<Vec<RecordBatch>> {
+        let mut stream = vec![];
+        for message in data {
+            stream.extend_from_slice(&CONTINUATION_MARKER);
+            stream.extend_from_slice(&(message.data_header.len() as i32).to_le_bytes());
+            stream.extend_from_slice(&message.data_header);
+            stream.extend_from_slice(&message.data_body);
+        }
+        stream.extend_from_slice(&CONTINUATION_MARKER);
+        stream.extend_from_slice(&0i32.to_le_bytes());
+
+        let reader = StreamReader::try_new(Cursor::new(stream))?;
+        Ok(reader.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[tokio::test]
+    async fn test_statement_query() -> Result {
+        let service = make_service().await;
+
+        let command = CommandStatementQuery {
+            query: "select host, usage from cpu order by host".to_string(),
+            transaction_id: vec![],
+        };
+        let results = run(
+            &service,
+            command_descriptor("CommandStatementQuery", &command),
+            "mydb",
+        )
+        .await?;
+
+        let expected = vec![
+            "+------+-------+",
+            "| host | usage |",
+            "+------+-------+",
+            "| a    | 1.5   |",
+            "| b    | 2.5   |",
+            "+------+-------+",
+        ];
+        assert_eq!(results, expected.join("\n"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_statement_query_errors() -> Result {
+        let service = make_service().await;
+        let command = CommandStatementQuery {
+            query: "select * from cpu".to_string(),
+            transaction_id: vec![],
+        };
+
+        let status = service
+            .get_flight_info(Request::new(command_descriptor(
+                "CommandStatementQuery",
+                &command,
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Statement queries require a 'database' header"
+        );
+
+        let status = service
+            .get_flight_info(with_database(
+                command_descriptor("CommandStatementQuery", &command),
+                "nodb",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let status = service
+            .get_flight_info(with_database(
+                command_descriptor("CommandPreparedStatementQuery", &command),
+                "mydb",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_tables() -> Result {
+        let service = make_service().await;
+
+        let command = CommandGetTables::default();
+        let results = run(
+            &service,
+            command_descriptor("CommandGetTables", &command),
+            "",
+        )
+        .await?;
+        let expected = vec![
+            "+--------------+----------------+------------+------------+",
+            "| catalog_name | db_schema_name | table_name | table_type |",
+            "+--------------+----------------+------------+------------+",
+            "| mydb         | iox            | cpu        | TABLE      |",
+            "| mydb         | iox            | disk       | TABLE      |",
+            "| otherdb      | iox            | mem        | TABLE      |",
+            "+--------------+----------------+------------+------------+",
+        ];
+        assert_eq!(results, expected.join("\n"));
+
+        let command = CommandGetTables {
+            catalog: "mydb".to_string(),
+            table_name_filter_pattern: "c_%".to_string(),
+            ..Default::default()
+        };
+        let results = run(
+            &service,
+            command_descriptor("CommandGetTables", &command),
+            "",
+        )
+        .await?;
+        let expected = vec![
+            "+--------------+----------------+------------+------------+",
+            "| catalog_name | db_schema_name | table_name | table_type |",
+            "+--------------+----------------+------------+------------+",
+            "| mydb         | iox            | cpu        | TABLE      |",
+            "+--------------+----------------+------------+------------+",
+        ];
+        assert_eq!(results, expected.join("\n"));
+
+        let command = CommandGetTables {
+            table_types: vec!["VIEW".to_string()],
+            ..Default::default()
+        };
+        let results = run(
+            &service,
+            command_descriptor("CommandGetTables", &command),
+            "",
+        )
+        .await?;
+        let expected = vec![
+            "+--------------+----------------+------------+------------+",
+            "| catalog_name | db_schema_name | table_name | table_type |",
+            "+--------------+----------------+------------+------------+",
+            "+--------------+----------------+------------+------------+",
+        ];
+        assert_eq!(results, expected.join("\n"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_tables_include_schema() -> Result {
+        let service = make_service().await;
+
+        let command = CommandGetTables {
+            table_name_filter_pattern: "mem".to_string(),
+            include_schema: true,
+            ..Default::default()
+        };
+        let mut stream = service
+            .do_get(Request::new(Command::GetTables(command).to_ticket()))
+            .await?
+            .into_inner();
+        let mut data = vec![];
+        while let Some(message) = stream.recv().await {
+            data.push(message?);
+        }
+        let batches = decode_flight_data(&data)?;
+        assert_eq!(batches[0].num_rows(), 1);
+
+        let table_schema = batches[0]
+            .column(4)
+            .as_any()
+            .downcast_ref::<BinaryArray>()
+            .unwrap()
+            .value(0)
+            .to_vec();
+
+        // The schema of the table is that of the results of querying it
+        let command = CommandStatementQuery {
+            query: "select * from mem".to_string(),
+            transaction_id: vec![],
+        };
+        let info = service
+            .get_flight_info(with_database(
+                command_descriptor("CommandStatementQuery", &command),
+                "otherdb",
+            ))
+            .await?
+            .into_inner();
+        assert_eq!(table_schema, info.schema);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_sql_info() -> Result {
+        let service = make_service().await;
+
+        let command = CommandGetSqlInfo {
+            info: vec![SQL_INFO_SERVER_NAME, SQL_INFO_SERVER_READ_ONLY, 1000],
+        };
+        let results = run(
+            &service,
+            command_descriptor("CommandGetSqlInfo", &command),
+            "",
+        )
+        .await?;
+        let expected = vec![
+            "+-----------+--------------+",
+            "| info_name | value        |",
+            "+-----------+--------------+",
+            "| 0         | InfluxDB IOx |",
+            "| 3         | true         |",
+            "+-----------+--------------+",
+        ];
+        assert_eq!(results, expected.join("\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("", "cpu"));
+        assert!(matches_pattern("cpu", "cpu"));
+        assert!(!matches_pattern("cpu", "cpu2"));
+        assert!(matches_pattern("c%", "cpu"));
+        assert!(matches_pattern("%u", "cpu"));
+        assert!(matches_pattern("%", ""));
+        assert!(matches_pattern("c_u", "cpu"));
+        assert!(!matches_pattern("c_u", "cu"));
+        assert!(matches_pattern("%p%", "cpu"));
+        assert!(!matches_pattern("%x%", "cpu"));
+
+        // Patterns with many wildcards don't backtrack exponentially
+        let value = "a".repeat(100);
+        assert!(!matches_pattern(&format!("{}b", "%a".repeat(50)), &value));
+        assert!(matches_pattern(&"%a".repeat(50), &value));
+    }
+}
@@ -43,6 +43,8 @@ use tokio::{net::TcpListener, sync::mpsc};
 use tonic::Status;
 use tracing::{error, info, warn};
 
+use super::flight::FlightSqlService;
+
 use super::data::{
     fieldlist_to_measurement_fields_response, series_set_item_to_read_response,
     tag_keys_to_byte_vecs,
@@ -1162,7 +1164,7 @@ where
 }
 
 /// Instantiate a server listening on the specified address
-/// implementing the IOx and Storage gRPC interfaces, the
+/// implementing the IOx, Storage and Arrow Flight gRPC interfaces, the
 /// underlying hyper server instance. Resolves when the server has
 /// shutdown.
 pub async fn make_server<T>(
@@ -1182,6 +1184,7 @@ where
             storage.clone(),
             executor.clone(),
         )))
+        .add_service(FlightSqlService::new(storage.clone()).into_server())
         .serve_with_incoming(socket)
         .await
         .context(ServerError {})
@@ -17,10 +17,18 @@
 // - Creating a unique org_id per test
 // - Stopping the server after all relevant tests are run
 
+use arrow_deps::arrow::{ipc::reader::StreamReader, util::pretty::pretty_format_batches};
 use assert_cmd::prelude::*;
+use data_types::database_rules::DatabaseRules;
 use futures::prelude::*;
 use generated_types::{
     aggregate::AggregateType,
+    arrow_flight::{
+        flight_descriptor::DescriptorType,
+        flight_service_client::FlightServiceClient,
+        sql::{CommandGetTables, CommandStatementQuery},
+        FlightData, FlightDescriptor,
+    },
     node::{Comparison, Type as NodeType, Value},
     read_group_request::Group,
     read_response::{frame::Data, *},
@@ -99,6 +107,21 @@ async fn read_and_write_data() -> Result<()> {
     let client = reqwest::Client::new();
     let client2 = influxdb2_client::Client::new(HTTP_BASE, TOKEN);
 
+    // Databases must be created before they're written to.
+    let rules = DatabaseRules {
+        store_locally: true,
+        ..Default::default()
+    };
+    client
+        .put(&format!(
+            "{}/api/v1/databases/{}_{}",
+            HTTP_BASE, org_id_str, bucket_id_str
+        ))
+        .body(serde_json::to_string(&rules)?)
+        .send()
+        .await?
+        .error_for_status()?;
+
     let start_time = SystemTime::now();
     let ns_since_epoch: i64 = start_time
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -404,6 +427,8 @@ async fn read_and_write_data() -> Result<()> {
 
     test_http_error_messages(&client2).await?;
 
+    test_flight_sql(org_id_str, bucket_id_str).await?;
+
     test_read_window_aggregate(
         &mut storage_client,
         &client2,
@@ -431,6 +456,105 @@ async fn test_http_error_messages(client: &influxdb2_client::Client) -> Result<(
     Ok(())
 }
 
+// Runs a statement query and lists the tables through the Flight SQL service
+async fn test_flight_sql(org_id: &str, bucket_id: &str) -> Result<()> {
+    let db_name = format!("{}_{}", org_id, bucket_id);
+    let mut flight_client = FlightServiceClient::connect(GRPC_URL_BASE).await?;
+
+    let command = CommandStatementQuery {
+        query: "select host, region, value from cpu_load_short where host = 'server02'".to_string(),
+        transaction_id: vec![],
+    };
+    let data = do_flight_sql(
+        &mut flight_client,
+        "CommandStatementQuery",
+        &command,
+        &db_name,
+    )
+    .await?;
+    let expected = vec![
+        "+----------+---------+-------+",
+        "| host     | region  | value |",
+        "+----------+---------+-------+",
+        "| server02 | us-west | 3.89  |",
+        "+----------+---------+-------+",
+    ];
+    assert_eq!(decode_flight_data(&data)?, expected.join("\n"));
+
+    let command = CommandGetTables {
+        catalog: db_name.clone(),
+        table_name_filter_pattern: "cpu%".to_string(),
+        ..Default::default()
+    };
+    let data = do_flight_sql(&mut flight_client, "CommandGetTables", &command, &db_name).await?;
+    let expected = vec![
+        "+-----------------------------------+----------------+----------------+------------+",
+        "| catalog_name                      | db_schema_name | table_name     | table_type |",
+        "+-----------------------------------+----------------+----------------+------------+",
+        "| 0000111100001111_1111000011110000 | iox            | cpu_load_short | TABLE      |",
+        "+-----------------------------------+----------------+----------------+------------+",
+    ];
+    assert_eq!(decode_flight_data(&data)?, expected.join("\n"));
+
+    Ok(())
+}
+
+// Sends a Flight SQL command through GetFlightInfo, then redeems the ticket
+// it returns with DoGet
+async fn do_flight_sql(
+    flight_client: &mut FlightServiceClient<tonic::transport::Channel>,
+    command_name: &str,
+    command: &impl Message,
+    db_name: &str,
+) -> Result<Vec<FlightData>> {
+    let mut value = vec![];
+    command.encode(&mut value)?;
+    let any = prost_types::Any {
+        type_url: format!(
+            "type.googleapis.com/arrow.flight.protocol.sql.{}",
+            command_name
+        ),
+        value,
+    };
+    let mut cmd = vec![];
+    any.encode(&mut cmd)?;
+
+    let mut request = tonic::Request::new(FlightDescriptor {
+        r#type: DescriptorType::Cmd as i32,
+        cmd,
+        path: vec![],
+    });
+    request.metadata_mut().insert("database", db_name.parse()?);
+    let info = flight_client.get_flight_info(request).await?.into_inner();
+
+    let ticket = info.endpoint[0].ticket.clone().expect("ticket");
+    let data = flight_client
+        .do_get(ticket)
+        .await?
+        .into_inner()
+        .try_collect()
+        .await?;
+    Ok(data)
+}
+
+// Reassembles the Arrow IPC stream of the messages of `data`, and formats its
+// batches as a table
+fn decode_flight_data(data: &[FlightData]) -> Result<String> {
+    let mut stream = vec![];
+    for message in data {
+        stream.extend_from_slice(&[0xff; 4]);
+        stream.extend_from_slice(&(message.data_header.len() as i32).to_le_bytes());
+        stream.extend_from_slice(&message.data_header);
+        stream.extend_from_slice(&message.data_body);
+    }
+    stream.extend_from_slice(&[0xff; 4]);
+    stream.extend_from_slice(&0i32.to_le_bytes());
+
+    let batches =
+        StreamReader::try_new(std::io::Cursor::new(stream))?.collect::<Result<Vec<_>, _>>()?;
+    Ok(pretty_format_batches(&batches)?)
+}
+
 // Standalone test that all the pipes are hooked up for read window aggregate
 async fn test_read_window_aggregate(
     storage_client: &mut StorageClient<tonic::transport::Channel>,